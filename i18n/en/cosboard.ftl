@@ -1,8 +1,172 @@
 app-title = Cosboard
 show-keyboard = Show Keyboard
 hide-keyboard = Hide Keyboard
+collapse-keyboard = Collapse Keyboard
+expand-keyboard = Expand Keyboard
 toggle-keyboard = Toggle Keyboard
 floating-mode = Floating Mode
 exclusive-mode = Exclusive Mode
+fit-height = Fit Height to Layout
 quit = Quit
+active-layout = Layout: { $name } ({ $source })
+layout-selected = { $name } ✓
+profiles = Profile
+profile-selected = { $name } ✓
+outputs = Show on
+output-active = Active output
+output-selected = { $name } ✓
+loading-layout = Loading…
+gestures = Gestures
+gesture-two-finger-tap = Two-finger tap
+gesture-swipe-down = Swipe down
+gesture-long-press-space = Hold space
+gesture-none = Off
+hide-on-escape = Hide after Esc
+hide-after-enter = Hide after Enter
+gamepad-navigation = Gamepad and remote navigation
+events-dumped = Key events written to { $path }
+palm-rejection = Ignore palm touches
+key-sounds = Key sounds
+haptic-feedback = Vibrate on key press
+idle-inhibit = Keep the screen awake while typing
+emission-indicator = Show when keys are sent
+status-strip = Show modifier and layout status
+drag-suggestions = Drag suggestions into apps
+status-caps-word = Caps word
+auto-show = Show with text fields
+keyboard-focus = Keyboard can take focus
+keyboard-theme = Keyboard theme: { $mode }
+keyboard-theme-system = System
+keyboard-theme-dark = Dark
+keyboard-theme-light = Light
+keyboard-theme-schedule = By time of day
+keyboard-theme-ambient-light = By ambient light
+learn-words = Learn new words
+private-mode = Private mode
+clear-learned-words = Clear Learned Words
+key-stats = Count key presses
+heat-map = Show key heat map
+clear-key-stats = Clear Key Statistics
+dictionaries = Prediction dictionaries
+expand-snippets = Expand text shortcuts
+expand-snippets-in = Expand text shortcuts in { $app }
+double-space-period = Double space types a period
+remove-snippet = Remove { $trigger } → { $expansion }
+dictionary = { $language } ({ $format })
+confirm-external-input = { $peer } wants to { $request }
+accept = Accept
+confirm-type-text = type text
+confirm-send-key = press { $combo }
+confirm-dump-events = read the recent key events
 about = About
+gesture-binding = { $gesture }: { $action }
+palette-no-matches = No matches
+dead-zones-docked = Dead zones when docked
+dead-zones-floating = Dead zones when floating
+dead-zone-top = Top edge
+dead-zone-right = Right edge
+dead-zone-bottom = Bottom edge
+dead-zone-left = Left edge
+dead-zone = { $edge }: { $size ->
+    [0] Off
+   *[other] { $size } px
+}
+
+# Keyboard errors and warnings
+panel-not-found = Panel '{ $panel }' not found
+panel-not-found-fallback = Panel '{ $panel }' not found, showing '{ $fallback }'
+layout-has-no-panels = Layout has no panels
+animation-panel-not-found = Animation error: panel not found
+layout-load-failed = Failed to load keyboard layout
+invalid-keyboard-size = Invalid keyboard size { $width }x{ $height } at scale { $scale }
+uneven-rows = { $count ->
+    [one] Uneven layout row: row { $row } of panel '{ $panel }'
+   *[other] { $count } uneven layout rows, first: row { $row } of panel '{ $panel }'
+}
+keys-too-small = { $count ->
+    [one] A key is smaller than { $min } mm ({ $size } mm)
+   *[other] { $count } keys are smaller than { $min } mm, smallest: { $size } mm
+}
+
+# Command key errors
+action-unknown = Unknown action '{ $name }'
+action-missing-parameter = Action '{ $action }' requires a parameter
+action-unexpected-parameter = Action '{ $action }' does not take a parameter
+action-reserved-name = '{ $name }' is a built-in action name
+action-invalid-parameter = Invalid parameter '{ $value }' for action '{ $action }'
+
+# Action titles (command palette and gesture menu)
+action-title-show = Show keyboard
+action-title-hide = Hide keyboard
+action-title-toggle = Toggle keyboard
+action-title-quit = Quit
+action-title-toggle-floating = Toggle floating mode
+action-title-fit-height = Fit height to layout
+action-title-toggle-collapsed = Collapse or expand keyboard
+action-title-palette = Command palette
+action-title-typing-test = Typing test
+action-title-switch-panel = Switch panel
+action-title-switch-layout = Switch layout
+action-title-reload-layout = Reload layout
+action-title-insert-text = Insert text
+action-title-type-text = Type text
+action-title-send-key = Send key combination
+action-title-send-prefix = Send terminal prefix key
+action-title-set-keyboard-interactivity = Set keyboard focus
+action-title-toggle-keyboard-interactivity = Toggle keyboard focus
+action-title-set-layer = Set layer
+action-title-toggle-layer = Toggle always on top
+action-title-commit-prediction = Commit prediction
+action-title-page-candidates = Turn input method candidate page
+action-title-commit-preedit = Type composed text unconverted
+action-title-toggle-prediction = Toggle word prediction
+action-title-toggle-private-mode = Toggle private mode (do not learn words)
+action-title-set-focused-app = Report the focused application
+action-title-scan-code = Scan QR code or barcode
+action-title-scan-image = Type code from image
+action-title-calc-input = Calculator input
+action-title-calc-backspace = Calculator backspace
+action-title-calc-clear = Clear calculator
+action-title-calc-equals = Type calculator result
+action-title-braille-dot = Braille dot
+action-title-set-profile = Switch profile
+action-title-export-config = Export settings
+action-title-import-config = Import settings
+action-title-reload-config = Reload settings
+
+# Scan to type
+scan-code = Scan code
+scan-choose-image = Choose an image with a QR code or barcode
+scan-cancelled = Scan cancelled
+scan-portal-failed = Cannot open the file chooser
+scan-no-code = No QR code or barcode found in the image
+scan-empty = The code holds no text to type
+scan-unavailable = Scanning is not available in this build
+
+# Emoji panel
+emoji-search-placeholder = Search emoji…
+emoji-recents-empty = Recently used emoji appear here
+
+# Settings export and import
+config-exported = Settings exported
+config-export-failed = Cannot export settings: { $error }
+config-imported = Settings imported
+config-reloaded = Settings reloaded
+config-import-failed = Cannot import settings: { $error }
+
+# Date picker widget
+date-picker-year = Year
+date-picker-month = Month
+date-picker-day = Day
+date-picker-hour = Hour
+date-picker-minute = Min
+date-picker-date = Date
+date-picker-time = Time
+date-picker-both = Both
+date-picker-now = Now
+
+# Calculator panel
+calc-empty = Nothing to calculate
+calc-syntax = The calculation is incomplete or malformed
+calc-division-by-zero = Cannot divide by zero
+calc-out-of-range = The result is out of range
@@ -0,0 +1,42 @@
+#!/bin/bash
+# Cosboard uinput setup
+# Grants the logged-in user access to /dev/uinput, which the uinput emission
+# backend (`uinput` cargo feature) needs on compositors without the Wayland
+# virtual keyboard protocol. Re-runs itself through polkit (pkexec) when not
+# started as root.
+#
+# Usage: cosboard-uinput-setup.sh [--remove]
+
+set -e
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+RULES_DST="/etc/udev/rules.d/70-cosboard-uinput.rules"
+MODULES_DST="/etc/modules-load.d/cosboard-uinput.conf"
+
+if [[ $EUID -ne 0 ]]; then
+    if ! command -v pkexec >/dev/null; then
+        echo "pkexec not found; run this script with sudo instead" >&2
+        exit 1
+    fi
+    exec pkexec "$SCRIPT_DIR/$(basename "${BASH_SOURCE[0]}")" "$@"
+fi
+
+if [[ "$1" == "--remove" ]]; then
+    echo "Removing uinput access for Cosboard..."
+    rm -f "$RULES_DST" "$MODULES_DST"
+    udevadm control --reload-rules
+    echo "Done. Access is revoked after the next login."
+    exit 0
+fi
+
+echo "Granting uinput access for Cosboard..."
+
+# Load the module now and at every boot
+modprobe uinput
+echo "uinput" > "$MODULES_DST"
+
+install -Dm0644 "$SCRIPT_DIR/70-cosboard-uinput.rules" "$RULES_DST"
+udevadm control --reload-rules
+udevadm trigger --subsystem-match=misc --sysname-match=uinput
+
+echo "Done. /dev/uinput is now writable by the logged-in user."
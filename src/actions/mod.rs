@@ -0,0 +1,855 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Named keyboard actions.
+//!
+//! Every way of triggering keyboard behavior (command keys, the D-Bus
+//! interface, the command palette and the applet popup menu) dispatches an
+//! [`AppAction`]. Actions have a stable name and an optional parameter, so
+//! they can be written as strings in layouts and configuration:
+//!
+//! | String                          | Action                                   |
+//! |---------------------------------|------------------------------------------|
+//! | `hide`                          | [`AppAction::Hide`]                      |
+//! | `switch_panel:symbols`          | [`AppAction::SwitchPanel`]`("symbols")`  |
+//! | `set_layer:top`                 | [`AppAction::SetLayer`]`(Layer::Top)`    |
+//! | `set_profile:tablet`            | [`AppAction::SetProfile`]`("tablet")`   |
+//!
+//! The [`ActionRegistry`] resolves names to actions and lists the available
+//! actions (for the palette and for validating user-defined bindings).
+//! [`PostActionPolicy`] decides which action, if any, runs after a key is
+//! released.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use cosboard::actions::{ActionRegistry, AppAction};
+//!
+//! let registry = ActionRegistry::new();
+//! let action = registry.parse("switch_panel:numpad").unwrap();
+//! assert_eq!(action, AppAction::SwitchPanel("numpad".to_string()));
+//! assert_eq!(action.to_string(), "switch_panel:numpad");
+//! ```
+
+use std::fmt;
+
+use crate::fl;
+use crate::input::KeyCombo;
+use crate::layer_shell::{Interactivity, Layer};
+use crate::layout::Modifier;
+
+pub mod post_action;
+
+pub use post_action::PostActionPolicy;
+
+/// Separator between an action name and its parameter.
+pub const PARAMETER_SEPARATOR: char = ':';
+
+/// Key combination sent by [`AppAction::SendPrefix`] unless configured
+/// otherwise: tmux's default prefix.
+pub const DEFAULT_PREFIX_KEY: &str = "ctrl+b";
+
+/// An action that can be dispatched to the keyboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppAction {
+    /// Show the keyboard.
+    Show,
+    /// Hide the keyboard.
+    Hide,
+    /// Toggle keyboard visibility.
+    Toggle,
+    /// Quit the applet.
+    Quit,
+    /// Toggle between docked and floating mode.
+    ToggleFloatingMode,
+    /// Let the keyboard height follow the layout again after a resize.
+    FitHeight,
+    /// Shrink the keyboard to a thin bar, or expand it back.
+    ToggleCollapsed,
+    /// Open the command palette, or close it if it is open.
+    TogglePalette,
+    /// Start the typing test, or end it if it is running.
+    ToggleTypingTest,
+    /// Switch to the panel with this ID.
+    SwitchPanel(String),
+    /// Load the layout file at this path.
+    SwitchLayout(String),
+    /// Re-read the current layout from disk, dropping cached layouts.
+    ReloadLayout,
+    /// Emit text to the client application.
+    InsertText(String),
+    /// Type text into the client application on behalf of another program.
+    ///
+    /// Unlike [`AppAction::InsertText`], the text is not added to the
+    /// emoji recents.
+    TypeText(String),
+    /// Press a key with modifiers in the client application.
+    SendKey(KeyCombo),
+    /// Press the configured terminal multiplexer prefix (tmux's `Ctrl+B`,
+    /// screen's `Ctrl+A`, ...) in the client application.
+    SendPrefix,
+    /// Set whether the keyboard surface may take keyboard focus.
+    SetKeyboardInteractivity(Interactivity),
+    /// Toggle whether the keyboard surface may take keyboard focus.
+    ToggleKeyboardInteractivity,
+    /// Set the layer-shell layer of the keyboard surface.
+    SetLayer(Layer),
+    /// Toggle the keyboard surface between the overlay and top layers.
+    ToggleLayer,
+    /// Type the prediction candidate at this index in place of the current word.
+    CommitPrediction(usize),
+    /// Show another page of input method candidates: the next one for a
+    /// positive number, the previous one for a negative one.
+    PageCandidates(i32),
+    /// Type the text the input method is composing as it is.
+    CommitPreedit,
+    /// Toggle the word prediction row.
+    TogglePrediction,
+    /// Toggle private mode, in which typed words are not learned.
+    TogglePrivateMode,
+    /// Report the application ID of the focused window.
+    SetFocusedApp(String),
+    /// Pick an image with a QR code or barcode and type the decoded text.
+    ScanCode,
+    /// Type the text of the QR code or barcode in the image at this path.
+    ScanImage(String),
+    /// Append to the calculator expression.
+    CalcInput(String),
+    /// Remove the last character of the calculator expression.
+    CalcBackspace,
+    /// Clear the calculator expression.
+    CalcClear,
+    /// Evaluate the calculator expression and type the result.
+    CalcEquals,
+    /// Press a dot of the braille panel (1 to 6, 0 for the space bar).
+    BrailleDot(u8),
+    /// Switch to the configuration profile with this name.
+    SetProfile(String),
+    /// Write the configuration, layouts and learned words to a bundle at
+    /// this path.
+    ExportConfig(String),
+    /// Import the configuration bundle at this path, refusing it if it sets
+    /// hooks or the external input policy.
+    ImportConfig(String),
+    /// Read the configuration and learned words again, e.g. after
+    /// `cosboardctl` imported a bundle.
+    ReloadConfig,
+}
+
+impl AppAction {
+    /// Returns the registry name of the action.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppAction::Show => "show",
+            AppAction::Hide => "hide",
+            AppAction::Toggle => "toggle",
+            AppAction::Quit => "quit",
+            AppAction::ToggleFloatingMode => "toggle_floating",
+            AppAction::FitHeight => "fit_height",
+            AppAction::ToggleCollapsed => "toggle_collapsed",
+            AppAction::TogglePalette => "palette",
+            AppAction::ToggleTypingTest => "typing_test",
+            AppAction::SwitchPanel(_) => "switch_panel",
+            AppAction::SwitchLayout(_) => "switch_layout",
+            AppAction::ReloadLayout => "reload_layout",
+            AppAction::InsertText(_) => "insert_text",
+            AppAction::TypeText(_) => "type_text",
+            AppAction::SendKey(_) => "send_key",
+            AppAction::SetKeyboardInteractivity(_) => "set_keyboard_interactivity",
+            AppAction::ToggleKeyboardInteractivity => "toggle_keyboard_interactivity",
+            AppAction::SetLayer(_) => "set_layer",
+            AppAction::ToggleLayer => "toggle_layer",
+            AppAction::CommitPrediction(_) => "commit_prediction",
+            AppAction::PageCandidates(_) => "page_candidates",
+            AppAction::CommitPreedit => "commit_preedit",
+            AppAction::TogglePrediction => "toggle_prediction",
+            AppAction::TogglePrivateMode => "toggle_private_mode",
+            AppAction::SetFocusedApp(_) => "set_focused_app",
+            AppAction::ScanCode => "scan_code",
+            AppAction::ScanImage(_) => "scan_image",
+            AppAction::SendPrefix => "send_prefix",
+            AppAction::CalcInput(_) => "calc_input",
+            AppAction::CalcBackspace => "calc_backspace",
+            AppAction::CalcClear => "calc_clear",
+            AppAction::CalcEquals => "calc_equals",
+            AppAction::BrailleDot(_) => "braille_dot",
+            AppAction::SetProfile(_) => "set_profile",
+            AppAction::ExportConfig(_) => "export_config",
+            AppAction::ImportConfig(_) => "import_config",
+            AppAction::ReloadConfig => "reload_config",
+        }
+    }
+
+    /// Returns the parameter of the action, if it takes one.
+    #[must_use]
+    pub fn parameter(&self) -> Option<String> {
+        match self {
+            AppAction::SwitchPanel(value)
+            | AppAction::SwitchLayout(value)
+            | AppAction::InsertText(value)
+            | AppAction::TypeText(value)
+            | AppAction::SetFocusedApp(value)
+            | AppAction::ScanImage(value)
+            | AppAction::CalcInput(value)
+            | AppAction::SetProfile(value)
+            | AppAction::ExportConfig(value)
+            | AppAction::ImportConfig(value) => Some(value.clone()),
+            AppAction::SendKey(combo) => Some(combo.to_string()),
+            AppAction::SetKeyboardInteractivity(interactivity) => {
+                Some(interactivity.as_str().to_string())
+            }
+            AppAction::SetLayer(layer) => Some(layer.as_str().to_string()),
+            AppAction::CommitPrediction(index) => Some(index.to_string()),
+            AppAction::PageCandidates(delta) => Some(delta.to_string()),
+            AppAction::BrailleDot(dot) => Some(dot.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the action emits text or keys to the client
+    /// application.
+    #[must_use]
+    pub fn types_text(&self) -> bool {
+        matches!(
+            self,
+            AppAction::InsertText(_)
+                | AppAction::TypeText(_)
+                | AppAction::SendKey(_)
+                | AppAction::SendPrefix
+                | AppAction::CommitPrediction(_)
+                | AppAction::CommitPreedit
+                | AppAction::ScanCode
+                | AppAction::ScanImage(_)
+                | AppAction::CalcEquals
+                | AppAction::BrailleDot(_)
+        )
+    }
+}
+
+impl fmt::Display for AppAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.parameter() {
+            Some(parameter) => write!(f, "{}{}{}", self.name(), PARAMETER_SEPARATOR, parameter),
+            None => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+/// Error resolving an action name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionError {
+    /// No action is registered under this name.
+    Unknown(String),
+    /// The action requires a parameter but none was given.
+    MissingParameter(&'static str),
+    /// The action takes no parameter but one was given.
+    UnexpectedParameter(&'static str),
+    /// The name is reserved by a built-in action.
+    ReservedName(String),
+    /// The parameter is not valid for the action.
+    InvalidParameter {
+        /// Action name
+        action: &'static str,
+        /// The rejected parameter
+        value: String,
+    },
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::Unknown(name) => write!(f, "Unknown action '{}'", name),
+            ActionError::MissingParameter(action) => {
+                write!(f, "Action '{}' requires a parameter", action)
+            }
+            ActionError::UnexpectedParameter(action) => {
+                write!(f, "Action '{}' does not take a parameter", action)
+            }
+            ActionError::ReservedName(name) => {
+                write!(f, "'{}' is a built-in action name", name)
+            }
+            ActionError::InvalidParameter { action, value } => {
+                write!(f, "Invalid parameter '{}' for action '{}'", value, action)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+impl ActionError {
+    /// Returns the error message in the user's language, for toasts.
+    #[must_use]
+    pub fn localized(&self) -> String {
+        match self {
+            ActionError::Unknown(name) => fl!("action-unknown", name = name.as_str()),
+            ActionError::MissingParameter(action) => {
+                fl!("action-missing-parameter", action = action.to_string())
+            }
+            ActionError::UnexpectedParameter(action) => {
+                fl!("action-unexpected-parameter", action = action.to_string())
+            }
+            ActionError::ReservedName(name) => fl!("action-reserved-name", name = name.as_str()),
+            ActionError::InvalidParameter { action, value } => {
+                fl!("action-invalid-parameter", action = action.to_string(), value = value.as_str())
+            }
+        }
+    }
+}
+
+/// Description of a registered action.
+#[derive(Debug, Clone, Copy)]
+pub struct ActionSpec {
+    /// Name used in command keys, bindings and D-Bus.
+    pub name: &'static str,
+    /// Returns the localized title (shown in the command palette).
+    pub title: fn() -> String,
+    /// Description of the parameter, if the action takes one.
+    pub parameter: Option<&'static str>,
+}
+
+/// Built-in actions, in palette order.
+pub static BUILTIN_ACTIONS: &[ActionSpec] = &[
+    ActionSpec {
+        name: "show",
+        title: || fl!("action-title-show"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "hide",
+        title: || fl!("action-title-hide"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "toggle",
+        title: || fl!("action-title-toggle"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "quit",
+        title: || fl!("action-title-quit"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "toggle_floating",
+        title: || fl!("action-title-toggle-floating"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "fit_height",
+        title: || fl!("action-title-fit-height"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "toggle_collapsed",
+        title: || fl!("action-title-toggle-collapsed"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "palette",
+        title: || fl!("action-title-palette"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "typing_test",
+        title: || fl!("action-title-typing-test"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "switch_panel",
+        title: || fl!("action-title-switch-panel"),
+        parameter: Some("panel ID"),
+    },
+    ActionSpec {
+        name: "switch_layout",
+        title: || fl!("action-title-switch-layout"),
+        parameter: Some("layout file path"),
+    },
+    ActionSpec {
+        name: "reload_layout",
+        title: || fl!("action-title-reload-layout"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "insert_text",
+        title: || fl!("action-title-insert-text"),
+        parameter: Some("text"),
+    },
+    ActionSpec {
+        name: "type_text",
+        title: || fl!("action-title-type-text"),
+        parameter: Some("text"),
+    },
+    ActionSpec {
+        name: "send_key",
+        title: || fl!("action-title-send-key"),
+        parameter: Some("modifiers and keysym, e.g. ctrl+alt+Delete"),
+    },
+    ActionSpec {
+        name: "send_prefix",
+        title: || fl!("action-title-send-prefix"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "set_keyboard_interactivity",
+        title: || fl!("action-title-set-keyboard-interactivity"),
+        parameter: Some("None or OnDemand"),
+    },
+    ActionSpec {
+        name: "toggle_keyboard_interactivity",
+        title: || fl!("action-title-toggle-keyboard-interactivity"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "set_layer",
+        title: || fl!("action-title-set-layer"),
+        parameter: Some("layer name"),
+    },
+    ActionSpec {
+        name: "toggle_layer",
+        title: || fl!("action-title-toggle-layer"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "commit_prediction",
+        title: || fl!("action-title-commit-prediction"),
+        parameter: Some("candidate index"),
+    },
+    ActionSpec {
+        name: "page_candidates",
+        title: || fl!("action-title-page-candidates"),
+        parameter: Some("pages to turn (1 or -1)"),
+    },
+    ActionSpec {
+        name: "commit_preedit",
+        title: || fl!("action-title-commit-preedit"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "toggle_prediction",
+        title: || fl!("action-title-toggle-prediction"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "toggle_private_mode",
+        title: || fl!("action-title-toggle-private-mode"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "set_focused_app",
+        title: || fl!("action-title-set-focused-app"),
+        parameter: Some("application ID"),
+    },
+    ActionSpec {
+        name: "scan_code",
+        title: || fl!("action-title-scan-code"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "scan_image",
+        title: || fl!("action-title-scan-image"),
+        parameter: Some("image file path"),
+    },
+    ActionSpec {
+        name: "calc_input",
+        title: || fl!("action-title-calc-input"),
+        parameter: Some("digits or operators"),
+    },
+    ActionSpec {
+        name: "calc_backspace",
+        title: || fl!("action-title-calc-backspace"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "calc_clear",
+        title: || fl!("action-title-calc-clear"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "calc_equals",
+        title: || fl!("action-title-calc-equals"),
+        parameter: None,
+    },
+    ActionSpec {
+        name: "braille_dot",
+        title: || fl!("action-title-braille-dot"),
+        parameter: Some("dot number (1–6, 0 for space)"),
+    },
+    ActionSpec {
+        name: "set_profile",
+        title: || fl!("action-title-set-profile"),
+        parameter: Some("profile name"),
+    },
+    ActionSpec {
+        name: "export_config",
+        title: || fl!("action-title-export-config"),
+        parameter: Some("bundle file path"),
+    },
+    ActionSpec {
+        name: "import_config",
+        title: || fl!("action-title-import-config"),
+        parameter: Some("bundle file path"),
+    },
+    ActionSpec {
+        name: "reload_config",
+        title: || fl!("action-title-reload-config"),
+        parameter: None,
+    },
+];
+
+/// Resolves action names to [`AppAction`]s.
+///
+/// The registry always knows the [built-in actions](BUILTIN_ACTIONS). User
+/// configuration can add aliases: names bound to a fully specified action
+/// (e.g. `"work_layout"` -> `switch_layout:/home/me/work.json`), which can
+/// then be used anywhere an action name is accepted.
+#[derive(Debug, Clone, Default)]
+pub struct ActionRegistry {
+    /// User-defined aliases, in registration order.
+    aliases: Vec<(String, AppAction)>,
+}
+
+impl ActionRegistry {
+    /// Creates a registry with the built-in actions.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an alias for an action.
+    ///
+    /// Aliases cannot shadow built-in names; registering an existing alias
+    /// replaces it.
+    pub fn register_alias(
+        &mut self,
+        alias: impl Into<String>,
+        action: AppAction,
+    ) -> Result<(), ActionError> {
+        let alias = alias.into();
+        if Self::spec(&alias).is_some() {
+            return Err(ActionError::ReservedName(alias));
+        }
+
+        self.aliases.retain(|(name, _)| *name != alias);
+        self.aliases.push((alias, action));
+        Ok(())
+    }
+
+    /// Returns the spec of a built-in action.
+    #[must_use]
+    pub fn spec(name: &str) -> Option<&'static ActionSpec> {
+        BUILTIN_ACTIONS.iter().find(|spec| spec.name == name)
+    }
+
+    /// Returns the built-in action specs.
+    pub fn specs(&self) -> impl Iterator<Item = &'static ActionSpec> {
+        BUILTIN_ACTIONS.iter()
+    }
+
+    /// Returns the user-defined aliases.
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &AppAction)> {
+        self.aliases
+            .iter()
+            .map(|(name, action)| (name.as_str(), action))
+    }
+
+    /// Resolves an action name and optional parameter.
+    pub fn resolve(&self, name: &str, parameter: Option<&str>) -> Result<AppAction, ActionError> {
+        if let Some((_, action)) = self.aliases.iter().find(|(alias, _)| alias == name) {
+            return Ok(action.clone());
+        }
+
+        let spec = Self::spec(name).ok_or_else(|| ActionError::Unknown(name.to_string()))?;
+        match (spec.parameter, parameter) {
+            (Some(_), None) => Err(ActionError::MissingParameter(spec.name)),
+            (None, Some(_)) => Err(ActionError::UnexpectedParameter(spec.name)),
+            (None, None) => build_simple(spec.name),
+            (Some(_), Some(value)) => build_with_parameter(spec.name, value),
+        }
+    }
+
+    /// Parses an action string (`"name"` or `"name:parameter"`).
+    ///
+    /// Only the first separator splits, so parameters may contain `:`.
+    pub fn parse(&self, action: &str) -> Result<AppAction, ActionError> {
+        let action = action.trim();
+        match action.split_once(PARAMETER_SEPARATOR) {
+            Some((name, parameter)) => self.resolve(name, Some(parameter)),
+            None => self.resolve(action, None),
+        }
+    }
+}
+
+/// Builds a built-in action that takes no parameter.
+fn build_simple(name: &'static str) -> Result<AppAction, ActionError> {
+    match name {
+        "show" => Ok(AppAction::Show),
+        "hide" => Ok(AppAction::Hide),
+        "toggle" => Ok(AppAction::Toggle),
+        "quit" => Ok(AppAction::Quit),
+        "toggle_floating" => Ok(AppAction::ToggleFloatingMode),
+        "fit_height" => Ok(AppAction::FitHeight),
+        "toggle_collapsed" => Ok(AppAction::ToggleCollapsed),
+        "palette" => Ok(AppAction::TogglePalette),
+        "typing_test" => Ok(AppAction::ToggleTypingTest),
+        "reload_layout" => Ok(AppAction::ReloadLayout),
+        "toggle_keyboard_interactivity" => Ok(AppAction::ToggleKeyboardInteractivity),
+        "toggle_layer" => Ok(AppAction::ToggleLayer),
+        "toggle_prediction" => Ok(AppAction::TogglePrediction),
+        "commit_preedit" => Ok(AppAction::CommitPreedit),
+        "toggle_private_mode" => Ok(AppAction::TogglePrivateMode),
+        "send_prefix" => Ok(AppAction::SendPrefix),
+        "scan_code" => Ok(AppAction::ScanCode),
+        "calc_backspace" => Ok(AppAction::CalcBackspace),
+        "calc_clear" => Ok(AppAction::CalcClear),
+        "calc_equals" => Ok(AppAction::CalcEquals),
+        "reload_config" => Ok(AppAction::ReloadConfig),
+        other => Err(ActionError::Unknown(other.to_string())),
+    }
+}
+
+/// Builds a built-in action from its parameter.
+fn build_with_parameter(name: &'static str, value: &str) -> Result<AppAction, ActionError> {
+    let invalid = || ActionError::InvalidParameter {
+        action: name,
+        value: value.to_string(),
+    };
+
+    match name {
+        "switch_panel" if !value.is_empty() => Ok(AppAction::SwitchPanel(value.to_string())),
+        "switch_layout" if !value.is_empty() => Ok(AppAction::SwitchLayout(value.to_string())),
+        "insert_text" if !value.is_empty() => Ok(AppAction::InsertText(value.to_string())),
+        "type_text" if !value.is_empty() => Ok(AppAction::TypeText(value.to_string())),
+        "send_key" => KeyCombo::parse(value)
+            .map(AppAction::SendKey)
+            .ok_or_else(invalid),
+        "set_keyboard_interactivity" => Interactivity::from_name(value)
+            .map(AppAction::SetKeyboardInteractivity)
+            .ok_or_else(invalid),
+        "set_layer" => Layer::from_name(value)
+            .map(AppAction::SetLayer)
+            .ok_or_else(invalid),
+        "commit_prediction" => value
+            .parse()
+            .map(AppAction::CommitPrediction)
+            .map_err(|_| invalid()),
+        "page_candidates" => value
+            .parse()
+            .map(AppAction::PageCandidates)
+            .map_err(|_| invalid()),
+        "set_focused_app" => Ok(AppAction::SetFocusedApp(value.to_string())),
+        "scan_image" if !value.is_empty() => Ok(AppAction::ScanImage(value.to_string())),
+        "calc_input" if !value.is_empty() => Ok(AppAction::CalcInput(value.to_string())),
+        "braille_dot" => value
+            .parse()
+            .ok()
+            .filter(|dot| *dot <= 6)
+            .map(AppAction::BrailleDot)
+            .ok_or_else(invalid),
+        "set_profile" if !value.trim().is_empty() => {
+            Ok(AppAction::SetProfile(value.trim().to_string()))
+        }
+        "export_config" if !value.is_empty() => Ok(AppAction::ExportConfig(value.to_string())),
+        "import_config" if !value.is_empty() => Ok(AppAction::ImportConfig(value.to_string())),
+        _ => Err(invalid()),
+    }
+}
+
+/// Returns the key combination [`AppAction::SendPrefix`] presses: the
+/// configured `terminal_prefix`, or [`DEFAULT_PREFIX_KEY`] if it is empty
+/// or not a valid combination.
+#[must_use]
+pub fn prefix_combo(configured: &str) -> KeyCombo {
+    let configured = configured.trim();
+    if !configured.is_empty() {
+        match KeyCombo::parse(configured) {
+            Some(combo) => return combo,
+            None => tracing::warn!(
+                "Invalid terminal prefix '{}', using {}",
+                configured,
+                DEFAULT_PREFIX_KEY
+            ),
+        }
+    }
+    KeyCombo::new("b", &[Modifier::Ctrl])
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns one action of every built-in kind.
+    fn all_actions() -> Vec<AppAction> {
+        vec![
+            AppAction::Show,
+            AppAction::Hide,
+            AppAction::Toggle,
+            AppAction::Quit,
+            AppAction::ToggleFloatingMode,
+            AppAction::FitHeight,
+            AppAction::ToggleCollapsed,
+            AppAction::TogglePalette,
+            AppAction::ToggleTypingTest,
+            AppAction::SwitchPanel("numpad".to_string()),
+            AppAction::SwitchLayout("/tmp/a:b.json".to_string()),
+            AppAction::ReloadLayout,
+            AppAction::InsertText("→".to_string()),
+            AppAction::TypeText("hello: world".to_string()),
+            AppAction::SendKey(KeyCombo::parse("ctrl+alt+Delete").unwrap()),
+            AppAction::SendPrefix,
+            AppAction::SetKeyboardInteractivity(Interactivity::OnDemand),
+            AppAction::ToggleKeyboardInteractivity,
+            AppAction::SetLayer(Layer::Top),
+            AppAction::ToggleLayer,
+            AppAction::CommitPrediction(2),
+            AppAction::PageCandidates(-1),
+            AppAction::CommitPreedit,
+            AppAction::TogglePrediction,
+            AppAction::TogglePrivateMode,
+            AppAction::SetFocusedApp("org.gnome.Terminal".to_string()),
+            AppAction::ScanCode,
+            AppAction::ScanImage("/tmp/wifi.png".to_string()),
+            AppAction::CalcInput("×".to_string()),
+            AppAction::CalcBackspace,
+            AppAction::CalcClear,
+            AppAction::CalcEquals,
+            AppAction::BrailleDot(4),
+            AppAction::SetProfile("tablet".to_string()),
+            AppAction::ExportConfig("/tmp/cosboard.json".to_string()),
+            AppAction::ImportConfig("/tmp/cosboard.json".to_string()),
+            AppAction::ReloadConfig,
+        ]
+    }
+
+    /// Test: Every built-in action round-trips through its string form.
+    #[test]
+    fn test_builtin_round_trip() {
+        let registry = ActionRegistry::new();
+        let actions = all_actions();
+
+        assert_eq!(actions.len(), BUILTIN_ACTIONS.len());
+        for action in actions {
+            let spec = ActionRegistry::spec(action.name());
+            assert!(spec.is_some(), "{} not registered", action.name());
+            assert!(spec.is_some_and(|spec| !(spec.title)().is_empty()));
+            assert_eq!(registry.parse(&action.to_string()), Ok(action.clone()));
+        }
+    }
+
+    /// Test: Exactly the actions that emit text or keys type.
+    #[test]
+    fn test_types_text() {
+        let typing = [
+            "insert_text",
+            "type_text",
+            "send_key",
+            "send_prefix",
+            "commit_prediction",
+            "commit_preedit",
+            "scan_code",
+            "scan_image",
+            "calc_equals",
+            "braille_dot",
+        ];
+        for action in all_actions() {
+            assert_eq!(
+                action.types_text(),
+                typing.contains(&action.name()),
+                "{}",
+                action.name()
+            );
+        }
+    }
+
+    /// Test: Malformed action strings are rejected with a specific error.
+    #[test]
+    fn test_parse_errors() {
+        let registry = ActionRegistry::new();
+        assert_eq!(
+            registry.parse("fly"),
+            Err(ActionError::Unknown("fly".to_string()))
+        );
+        assert_eq!(
+            registry.parse("switch_panel"),
+            Err(ActionError::MissingParameter("switch_panel"))
+        );
+        assert_eq!(
+            registry.parse("hide:now"),
+            Err(ActionError::UnexpectedParameter("hide"))
+        );
+        assert!(matches!(
+            registry.parse("set_layer:sideways"),
+            Err(ActionError::InvalidParameter {
+                action: "set_layer",
+                ..
+            })
+        ));
+        assert!(registry.parse("switch_panel:").is_err());
+        assert!(registry.parse("send_key:hyper+a").is_err());
+    }
+
+    /// Test: Aliases resolve to their action and cannot shadow built-ins.
+    #[test]
+    fn test_aliases() {
+        let mut registry = ActionRegistry::new();
+        let work = AppAction::SwitchLayout("/home/me/work.json".to_string());
+        registry
+            .register_alias("work_layout", work.clone())
+            .unwrap();
+        assert_eq!(registry.parse("work_layout"), Ok(work));
+
+        assert!(registry.register_alias("hide", AppAction::Quit).is_err());
+        assert_eq!(registry.parse("hide"), Ok(AppAction::Hide));
+        assert_eq!(registry.aliases().count(), 1);
+    }
+
+    /// Test: The prefix key falls back to tmux's when unset or invalid.
+    #[test]
+    fn test_prefix_combo() {
+        let tmux = KeyCombo::parse(DEFAULT_PREFIX_KEY).unwrap();
+        assert_eq!(prefix_combo(""), tmux);
+        assert_eq!(prefix_combo("hyper+b"), tmux);
+        assert_eq!(prefix_combo(" ctrl+a "), KeyCombo::parse("ctrl+a").unwrap());
+    }
+
+    /// Test: Command keys and chords of the terminal layout run valid actions.
+    #[test]
+    fn test_terminal_layout_commands() {
+        use crate::input::parse_command;
+        use crate::layout::{parse_layout_file, Cell};
+
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/layouts/terminal.json");
+        let layout = parse_layout_file(path.to_str().unwrap())
+            .expect("terminal layout should parse")
+            .layout;
+
+        let keys = layout
+            .panels
+            .values()
+            .flat_map(|panel| &panel.rows)
+            .flat_map(|row| &row.cells)
+            .filter_map(|cell| match cell {
+                Cell::Key(key) => Some(&key.code),
+                _ => None,
+            });
+        let chords = layout.chords.iter().map(|chord| &chord.code);
+        let registry = ActionRegistry::new();
+        let commands: Vec<AppAction> = keys
+            .chain(chords)
+            .filter_map(parse_command)
+            .map(|command| {
+                registry
+                    .parse(command)
+                    .unwrap_or_else(|e| panic!("invalid command key '{}': {}", command, e))
+            })
+            .collect();
+
+        assert!(commands.contains(&AppAction::SendPrefix));
+        assert!(commands.contains(&AppAction::SendKey(KeyCombo::parse("ctrl+c").unwrap())));
+    }
+}
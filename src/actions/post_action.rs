@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Actions run after a key is released.
+//!
+//! A key can carry its own post-action in the layout (`"after": "hide"`),
+//! which always wins. Otherwise [`PostActionPolicy`] from the user
+//! configuration decides: it can hide the keyboard after Esc, or after Enter
+//! for form-filling kiosks where the keyboard should get out of the way once
+//! a field is submitted.
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{Key, KeyCode};
+
+/// Action run by the keyboard policies.
+const HIDE_ACTION: &str = "hide";
+
+/// Configurable post-actions for keys without an `after` of their own.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostActionPolicy {
+    /// Hide the keyboard after the on-screen Esc key is released.
+    #[serde(default)]
+    pub hide_on_escape: bool,
+    /// Hide the keyboard after the on-screen Enter key is released.
+    #[serde(default)]
+    pub hide_after_enter: bool,
+}
+
+impl PostActionPolicy {
+    /// Returns the action to run after `key` is released, if any.
+    ///
+    /// The key's own `after` takes precedence over the policies; an empty
+    /// `after` disables them for that key.
+    #[must_use]
+    pub fn post_action<'a>(&self, key: &'a Key) -> Option<&'a str> {
+        if let Some(after) = key.after.as_deref() {
+            return (!after.trim().is_empty()).then_some(after);
+        }
+
+        let hide = (self.hide_on_escape && is_escape(&key.code))
+            || (self.hide_after_enter && is_enter(&key.code));
+        hide.then_some(HIDE_ACTION)
+    }
+}
+
+/// Returns `true` if the key code is Esc.
+fn is_escape(code: &KeyCode) -> bool {
+    match code {
+        KeyCode::Keysym(name) => name == "Escape",
+        KeyCode::Unicode(c) => *c == '\u{1b}',
+    }
+}
+
+/// Returns `true` if the key code is Enter (main or keypad).
+fn is_enter(code: &KeyCode) -> bool {
+    match code {
+        KeyCode::Keysym(name) => matches!(name.as_str(), "Return" | "KP_Enter" | "ISO_Enter"),
+        KeyCode::Unicode(c) => matches!(c, '\n' | '\r'),
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, after: Option<&str>) -> Key {
+        Key {
+            code,
+            after: after.map(str::to_string),
+            ..Key::default()
+        }
+    }
+
+    /// Test: Policies hide after Esc/Enter only when enabled.
+    #[test]
+    fn test_policies() {
+        let escape = key(KeyCode::Keysym("Escape".to_string()), None);
+        let enter = key(KeyCode::Keysym("Return".to_string()), None);
+        let letter = key(KeyCode::Unicode('a'), None);
+
+        let off = PostActionPolicy::default();
+        assert_eq!(off.post_action(&escape), None);
+        assert_eq!(off.post_action(&enter), None);
+
+        let kiosk = PostActionPolicy {
+            hide_on_escape: true,
+            hide_after_enter: true,
+        };
+        assert_eq!(kiosk.post_action(&escape), Some("hide"));
+        assert_eq!(kiosk.post_action(&enter), Some("hide"));
+        assert_eq!(kiosk.post_action(&letter), None);
+    }
+
+    /// Test: A key's own post-action overrides the policies.
+    #[test]
+    fn test_key_after_wins() {
+        let kiosk = PostActionPolicy {
+            hide_on_escape: true,
+            hide_after_enter: true,
+        };
+
+        let enter = key(KeyCode::Keysym("Return".to_string()), Some("switch_panel:main"));
+        assert_eq!(kiosk.post_action(&enter), Some("switch_panel:main"));
+
+        let opted_out = key(KeyCode::Keysym("Escape".to_string()), Some(""));
+        assert_eq!(kiosk.post_action(&opted_out), None);
+
+        let letter = key(KeyCode::Unicode('a'), Some("hide"));
+        assert_eq!(PostActionPolicy::default().post_action(&letter), Some("hide"));
+    }
+}
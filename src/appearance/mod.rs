@@ -0,0 +1,278 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Light and dark keyboard palettes chosen independently of the system theme.
+//!
+//! On tablets the keyboard is often used at night next to a light desktop,
+//! or outdoors next to a dark one. [`KeyboardAppearance`] in the
+//! configuration lets the keyboard pick its own palette:
+//!
+//! ```ron
+//! (mode: Schedule, schedule: (dark_from: 1200, light_from: 420))
+//! ```
+//!
+//! - [`ThemeMode::System`] follows the COSMIC theme (the default).
+//! - [`ThemeMode::Dark`] and [`ThemeMode::Light`] fix the palette.
+//! - [`ThemeMode::Schedule`] is dark between two times of day, given in
+//!   minutes after midnight local time.
+//! - [`ThemeMode::AmbientLight`] follows the ambient light sensor read
+//!   through iio-sensor-proxy (see [`sensor`]). The two thresholds leave a
+//!   band in which the palette is kept, so a level hovering around one
+//!   threshold does not make the keyboard flicker. Without a sensor the
+//!   keyboard follows the system theme.
+//!
+//! The applet re-evaluates the palette from a minute timer in schedule mode
+//! and on every sensor reading in ambient light mode.
+
+pub mod sensor;
+
+use serde::{Deserialize, Serialize};
+
+/// Minutes in a day.
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Color palette of the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    /// Light keys on a dark background.
+    Dark,
+    /// Dark keys on a light background.
+    Light,
+}
+
+/// How the keyboard chooses its palette.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeMode {
+    /// Follow the COSMIC system theme.
+    #[default]
+    System,
+    /// Always dark.
+    Dark,
+    /// Always light.
+    Light,
+    /// Dark between [`ThemeSchedule::dark_from`] and
+    /// [`ThemeSchedule::light_from`].
+    Schedule,
+    /// Dark in dim surroundings, as measured by the ambient light sensor.
+    AmbientLight,
+}
+
+impl ThemeMode {
+    /// Every mode, in the order the settings menu cycles through them.
+    pub const ALL: [ThemeMode; 5] = [
+        ThemeMode::System,
+        ThemeMode::Dark,
+        ThemeMode::Light,
+        ThemeMode::Schedule,
+        ThemeMode::AmbientLight,
+    ];
+
+    /// Returns the mode after this one in [`ThemeMode::ALL`], wrapping around.
+    #[must_use]
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// Times of day at which the scheduled palette changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeSchedule {
+    /// Minutes after midnight at which the keyboard turns dark.
+    #[serde(default = "default_dark_from")]
+    pub dark_from: u16,
+    /// Minutes after midnight at which the keyboard turns light.
+    #[serde(default = "default_light_from")]
+    pub light_from: u16,
+}
+
+fn default_dark_from() -> u16 {
+    19 * 60
+}
+
+fn default_light_from() -> u16 {
+    7 * 60
+}
+
+impl Default for ThemeSchedule {
+    fn default() -> Self {
+        Self {
+            dark_from: default_dark_from(),
+            light_from: default_light_from(),
+        }
+    }
+}
+
+impl ThemeSchedule {
+    /// Returns the palette at `minute` minutes after midnight.
+    ///
+    /// The dark period may span midnight (`dark_from` after `light_from`)
+    /// or not; equal times mean the keyboard is always light.
+    #[must_use]
+    pub fn palette_at(&self, minute: u16) -> Palette {
+        let minute = minute % MINUTES_PER_DAY;
+        let dark = if self.dark_from <= self.light_from {
+            (self.dark_from..self.light_from).contains(&minute)
+        } else {
+            minute >= self.dark_from || minute < self.light_from
+        };
+        if dark {
+            Palette::Dark
+        } else {
+            Palette::Light
+        }
+    }
+}
+
+/// Ambient light levels at which the palette changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmbientThresholds {
+    /// Level below which the keyboard turns dark.
+    #[serde(default = "default_dark_below")]
+    pub dark_below: u32,
+    /// Level above which the keyboard turns light.
+    #[serde(default = "default_light_above")]
+    pub light_above: u32,
+}
+
+fn default_dark_below() -> u32 {
+    10
+}
+
+fn default_light_above() -> u32 {
+    50
+}
+
+impl Default for AmbientThresholds {
+    fn default() -> Self {
+        Self {
+            dark_below: default_dark_below(),
+            light_above: default_light_above(),
+        }
+    }
+}
+
+impl AmbientThresholds {
+    /// Returns the palette for a light `level`, keeping `current` while the
+    /// level is between the thresholds.
+    ///
+    /// Levels are in the sensor's unit, lux on most devices. Without a
+    /// current palette, a level between the thresholds counts as light.
+    #[must_use]
+    pub fn palette_for(&self, level: f64, current: Option<Palette>) -> Palette {
+        if level < f64::from(self.dark_below) {
+            Palette::Dark
+        } else if level > f64::from(self.light_above) {
+            Palette::Light
+        } else {
+            current.unwrap_or(Palette::Light)
+        }
+    }
+}
+
+/// Keyboard palette settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyboardAppearance {
+    /// How the palette is chosen.
+    #[serde(default)]
+    pub mode: ThemeMode,
+    /// Dark period in [`ThemeMode::Schedule`] mode.
+    #[serde(default)]
+    pub schedule: ThemeSchedule,
+    /// Light levels used in [`ThemeMode::AmbientLight`] mode.
+    #[serde(default)]
+    pub ambient: AmbientThresholds,
+}
+
+impl KeyboardAppearance {
+    /// Returns the palette the keyboard should use, or `None` to follow the
+    /// system theme.
+    ///
+    /// `minute` is the local time in minutes after midnight, `light_level`
+    /// the latest sensor reading (if any), and `current` the palette shown
+    /// now.
+    #[must_use]
+    pub fn palette(
+        &self,
+        minute: u16,
+        light_level: Option<f64>,
+        current: Option<Palette>,
+    ) -> Option<Palette> {
+        match self.mode {
+            ThemeMode::System => None,
+            ThemeMode::Dark => Some(Palette::Dark),
+            ThemeMode::Light => Some(Palette::Light),
+            ThemeMode::Schedule => Some(self.schedule.palette_at(minute)),
+            ThemeMode::AmbientLight => {
+                light_level.map(|level| self.ambient.palette_for(level, current))
+            }
+        }
+    }
+
+    /// Returns `true` if the palette depends on the time of day.
+    #[must_use]
+    pub fn needs_clock(&self) -> bool {
+        self.mode == ThemeMode::Schedule
+    }
+
+    /// Returns `true` if the palette depends on the ambient light sensor.
+    #[must_use]
+    pub fn needs_light_sensor(&self) -> bool {
+        self.mode == ThemeMode::AmbientLight
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The scheduled dark period may span midnight.
+    #[test]
+    fn test_schedule_palette() {
+        let schedule = ThemeSchedule::default();
+        assert_eq!(schedule.palette_at(12 * 60), Palette::Light);
+        assert_eq!(schedule.palette_at(19 * 60), Palette::Dark);
+        assert_eq!(schedule.palette_at(3 * 60), Palette::Dark);
+        assert_eq!(schedule.palette_at(7 * 60), Palette::Light);
+
+        // Dark during the day, e.g. for a night shift
+        let daytime = ThemeSchedule {
+            dark_from: 9 * 60,
+            light_from: 17 * 60,
+        };
+        assert_eq!(daytime.palette_at(12 * 60), Palette::Dark);
+        assert_eq!(daytime.palette_at(20 * 60), Palette::Light);
+    }
+
+    /// Test: Levels between the thresholds keep the current palette.
+    #[test]
+    fn test_ambient_hysteresis() {
+        let appearance = KeyboardAppearance {
+            mode: ThemeMode::AmbientLight,
+            ..KeyboardAppearance::default()
+        };
+        assert_eq!(appearance.palette(0, None, None), None);
+        assert_eq!(appearance.palette(0, Some(2.0), None), Some(Palette::Dark));
+        assert_eq!(
+            appearance.palette(0, Some(30.0), Some(Palette::Dark)),
+            Some(Palette::Dark)
+        );
+        assert_eq!(
+            appearance.palette(0, Some(30.0), Some(Palette::Light)),
+            Some(Palette::Light)
+        );
+        assert_eq!(
+            appearance.palette(0, Some(400.0), Some(Palette::Dark)),
+            Some(Palette::Light)
+        );
+
+        assert_eq!(
+            KeyboardAppearance::default().palette(0, Some(2.0), None),
+            None
+        );
+        assert_eq!(ThemeMode::AmbientLight.next(), ThemeMode::System);
+    }
+}
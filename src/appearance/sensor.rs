@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Ambient light readings from iio-sensor-proxy.
+//!
+//! iio-sensor-proxy exposes the device's light sensor on the system bus
+//! (`net.hadess.SensorProxy`). The sensor is claimed while the
+//! subscription runs, so the proxy only polls the hardware while the
+//! keyboard uses it, and each change of the `LightLevel` property is
+//! reported as a [`LightEvent::Level`].
+
+use cosmic::iced::futures::{SinkExt, StreamExt};
+use cosmic::iced::stream;
+use cosmic::iced_futures::Subscription;
+
+/// iio-sensor-proxy's sensor interface.
+#[zbus::proxy(
+    interface = "net.hadess.SensorProxy",
+    default_service = "net.hadess.SensorProxy",
+    default_path = "/net/hadess/SensorProxy"
+)]
+trait SensorProxy {
+    /// Starts reading the light sensor for this connection.
+    fn claim_light(&self) -> zbus::Result<()>;
+
+    /// Stops reading the light sensor for this connection.
+    fn release_light(&self) -> zbus::Result<()>;
+
+    /// Whether the device has an ambient light sensor.
+    #[zbus(property)]
+    fn has_ambient_light(&self) -> zbus::Result<bool>;
+
+    /// Latest ambient light level.
+    #[zbus(property)]
+    fn light_level(&self) -> zbus::Result<f64>;
+
+    /// Unit of the light level: `lux`, or `vendor` for arbitrary units.
+    #[zbus(property)]
+    fn light_level_unit(&self) -> zbus::Result<String>;
+}
+
+/// Events produced by the light sensor subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LightEvent {
+    /// The ambient light level changed.
+    Level(f64),
+    /// No light sensor can be read (no system bus, no iio-sensor-proxy, or
+    /// no sensor in the device).
+    Unavailable(String),
+}
+
+/// Creates the subscription reading the ambient light sensor.
+///
+/// Emits the current level once the sensor is claimed, then every change.
+pub fn subscription() -> Subscription<LightEvent> {
+    Subscription::run_with_id(
+        std::any::TypeId::of::<SensorProxyProxy<'static>>(),
+        stream::channel(4, |mut output| async move {
+            let proxy = match claim().await {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    tracing::info!("Ambient light sensor unavailable: {}", e);
+                    let _ = output.send(LightEvent::Unavailable(e)).await;
+                    return;
+                }
+            };
+
+            let mut changes = proxy.receive_light_level_changed().await;
+            if let Ok(level) = proxy.light_level().await {
+                let _ = output.send(LightEvent::Level(level)).await;
+            }
+            while let Some(change) = changes.next().await {
+                let Ok(level) = change.get().await else {
+                    continue;
+                };
+                if output.send(LightEvent::Level(level)).await.is_err() {
+                    break;
+                }
+            }
+
+            let _ = proxy.release_light().await;
+        }),
+    )
+}
+
+/// Connects to iio-sensor-proxy and claims the light sensor.
+async fn claim() -> Result<SensorProxyProxy<'static>, String> {
+    let connection = zbus::Connection::system()
+        .await
+        .map_err(|e| e.to_string())?;
+    let proxy = SensorProxyProxy::new(&connection)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !proxy.has_ambient_light().await.map_err(|e| e.to_string())? {
+        return Err("no ambient light sensor".to_string());
+    }
+    proxy.claim_light().await.map_err(|e| e.to_string())?;
+
+    if let Ok(unit) = proxy.light_level_unit().await {
+        tracing::debug!("Ambient light sensor claimed, levels in {}", unit);
+    }
+    Ok(proxy)
+}
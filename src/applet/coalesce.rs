@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Coalescing of messages that arrive faster than frames.
+//!
+//! While the keyboard is dragged or resized, the pointer reports several
+//! positions per displayed frame, and fast typing rebuilds the prediction
+//! row on every key. Neither needs to happen more than once per frame, so
+//! the applet queues them in a [`FrameQueue`] and applies the queue on the
+//! next frame (`Message::FrameFlush`): the pointer motion is handled once,
+//! as the sum of the reported steps ([`CursorMotion`]), and the row is
+//! rebuilt once for all the keys typed since.
+//!
+//! Key events are still emitted as soon as keys are pressed; only their
+//! visual follow-up waits. Any message that is not [deferrable](is_deferrable)
+//! applies the queue before it is handled, so messages keep their order.
+
+use cosmic::iced::{Point, Vector};
+
+use super::Message;
+
+/// Largest pointer step handled, in pixels along each axis.
+///
+/// Layer surfaces have no pointer grab, so the pointer jumps when it leaves
+/// the window during a drag and comes back elsewhere; larger steps are
+/// clamped so the keyboard does not jump with it.
+pub const MAX_CURSOR_STEP: f32 = 30.0;
+
+/// Updates waiting for the next frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameQueue {
+    /// Pointer motion reported during a drag or resize
+    cursor: Option<CursorMotion>,
+    /// Whether the prediction row is out of date
+    predictions: bool,
+}
+
+/// Pointer motion queued during a drag or resize.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorMotion {
+    /// First position reported since the last frame.
+    pub first: Point,
+    /// Last position reported.
+    pub last: Point,
+    /// Movement from `first` to `last`, each step clamped to
+    /// [`MAX_CURSOR_STEP`].
+    pub travel: Vector,
+}
+
+impl CursorMotion {
+    /// Returns the movement since `previous`, the position handled before:
+    /// the step to the first queued position, clamped like the others, and
+    /// the queued movement.
+    #[must_use]
+    pub fn since(&self, previous: Point) -> Vector {
+        clamp_step(self.first - previous) + self.travel
+    }
+}
+
+/// Clamps a pointer step to [`MAX_CURSOR_STEP`] along each axis.
+#[must_use]
+pub fn clamp_step(step: Vector) -> Vector {
+    Vector::new(
+        step.x.clamp(-MAX_CURSOR_STEP, MAX_CURSOR_STEP),
+        step.y.clamp(-MAX_CURSOR_STEP, MAX_CURSOR_STEP),
+    )
+}
+
+/// Updates taken from a [`FrameQueue`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameUpdates {
+    /// Pointer motion to handle, if the pointer moved.
+    pub cursor: Option<CursorMotion>,
+    /// Whether to rebuild the prediction row.
+    pub predictions: bool,
+}
+
+impl FrameQueue {
+    /// Queues a pointer position, adding the step from the position queued
+    /// before.
+    pub fn move_cursor(&mut self, position: Point) {
+        self.cursor = Some(match self.cursor {
+            Some(motion) => CursorMotion {
+                last: position,
+                travel: motion.travel + clamp_step(position - motion.last),
+                ..motion
+            },
+            None => CursorMotion {
+                first: position,
+                last: position,
+                travel: Vector::new(0.0, 0.0),
+            },
+        });
+    }
+
+    /// Queues a rebuild of the prediction row.
+    pub fn refresh_predictions(&mut self) {
+        self.predictions = true;
+    }
+
+    /// Returns `true` if nothing is queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cursor.is_none() && !self.predictions
+    }
+
+    /// Takes the queued updates, leaving the queue empty.
+    pub fn take(&mut self) -> FrameUpdates {
+        let queue = std::mem::take(self);
+        FrameUpdates {
+            cursor: queue.cursor,
+            predictions: queue.predictions,
+        }
+    }
+}
+
+/// Returns `true` if a message can be handled while updates are queued,
+/// without applying them first.
+///
+/// Key presses, releases and pointer motion only add to the queue.
+#[must_use]
+pub fn is_deferrable(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::CursorMoved(_)
+            | Message::KeyPressed(_)
+            | Message::KeyReleased(_)
+            | Message::KeyEntered(_)
+            | Message::FrameFlush
+    )
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Pointer steps are summed, and taking the updates empties the
+    /// queue.
+    #[test]
+    fn test_frame_queue() {
+        let mut queue = FrameQueue::default();
+        assert!(queue.is_empty());
+
+        queue.move_cursor(Point::new(1.0, 2.0));
+        queue.refresh_predictions();
+        queue.move_cursor(Point::new(3.0, 4.0));
+        queue.refresh_predictions();
+        assert!(!queue.is_empty());
+
+        assert_eq!(
+            queue.take(),
+            FrameUpdates {
+                cursor: Some(CursorMotion {
+                    first: Point::new(1.0, 2.0),
+                    last: Point::new(3.0, 4.0),
+                    travel: Vector::new(2.0, 2.0),
+                }),
+                predictions: true,
+            }
+        );
+        assert!(queue.is_empty());
+        assert_eq!(queue.take(), FrameUpdates::default());
+    }
+
+    /// Test: A fast drag coalesced into one frame moves as far as the
+    /// pointer; only single steps larger than the limit are clamped.
+    #[test]
+    fn test_coalesced_fast_drag() {
+        let start = Point::new(500.0, 300.0);
+        let mut queue = FrameQueue::default();
+        for step in 1..=10 {
+            queue.move_cursor(Point::new(500.0 - 25.0 * step as f32, 300.0));
+        }
+        let motion = queue.take().cursor.unwrap();
+        assert_eq!(motion.last, Point::new(250.0, 300.0));
+        assert_eq!(motion.since(start), Vector::new(-250.0, 0.0));
+
+        // The pointer left the window and came back far away
+        queue.move_cursor(Point::new(900.0, 300.0));
+        queue.move_cursor(Point::new(910.0, 290.0));
+        let motion = queue.take().cursor.unwrap();
+        assert_eq!(motion.since(start), Vector::new(40.0, -10.0));
+    }
+
+    /// Test: Typing and pointer motion do not apply the queue, other
+    /// messages do.
+    #[test]
+    fn test_is_deferrable() {
+        assert!(is_deferrable(&Message::CursorMoved(Point::ORIGIN)));
+        assert!(is_deferrable(&Message::KeyPressed("a".to_string())));
+        assert!(is_deferrable(&Message::KeyReleased("a".to_string())));
+        assert!(!is_deferrable(&Message::DragEnd));
+        assert!(!is_deferrable(&Message::SwitchPanel("main".to_string())));
+    }
+}
@@ -22,22 +22,90 @@
 //! cargo run --bin cosboard-applet
 //! ```
 
+mod coalesce;
+mod recording;
+#[cfg(feature = "soak")]
+pub mod soak;
+mod startup;
+
+use self::coalesce::{CursorMotion, FrameQueue};
+use self::startup::{StartupPhase, StartupTimer, STARTUP_BUDGET_MS};
+use crate::config::bundle::{self, BundleSummary};
+use crate::config::{migrate_user_config, Config};
+use crate::controller::KeyboardController;
+use crate::actions::{ActionRegistry, AppAction};
+use crate::appearance::sensor::{self, LightEvent};
+use crate::appearance::{Palette, ThemeMode};
+use crate::braille::{BrailleCell, BrailleChord, BrailleTranslator, Translation};
+use crate::dbus::{self, Confirmation, DbusEvent, DbusState, KeyboardSnapshot, SharedState};
+use crate::double_space::{sentence_end, DoubleSpaceTracker};
 use crate::fl;
-use crate::input::{parse_keycode, keycodes, ResolvedKeycode, VirtualKeyboard};
-use crate::layout::{parse_layout_file, Cell, Key, KeyCode, Modifier};
+use crate::gestures::{Gesture, GestureTracker, PalmFilter, Rejection};
+use crate::hooks::{HookEvent, HookRunner};
+use crate::idle_inhibit::{self, IdleInhibitor, IDLE_INHIBIT_CHECK_SECS};
+use crate::ime::{self, ImeEngine, ImeInput, ImeOutput};
+use crate::feedback::{self, BoundaryTracker, Feedback, HapticPattern, Haptics};
+use crate::kiosk::KioskPolicy;
+use crate::typing_test::{TypingTest, TYPING_TEST_FIELD};
+use crate::input::{
+    create_key_sink, parse_command, parse_keycode, select_backend, Backend, ChordEvent,
+    ChordMatcher, KeyCombo, KeyRoute, MultiTap, MultiTapEvent, ResolvedKeycode, SessionInfo,
+    SlideTracker,
+};
+use crate::input::gamepad::{self, NavCommand, NavDirection};
+use crate::input::ime::{self as input_ime, InputMethodEvent};
+use crate::input::journal::{self as input_journal, EventJournal};
+use crate::cli::SurfaceOverrides;
+use crate::date_picker::{self, DateField, DateInsert};
+use crate::layer_shell::{Interactivity, Outputs};
+use crate::layout::watcher as layout_watcher;
+use crate::layout::{
+    parse_layout_file_with, Action, AlternativeKey, CachedLayout, Cell, Key, KeyCode, Layout,
+    LayoutCache, LayoutSource, LayoutWatcher, Modifier, ParseResult, ProviderChain,
+    DEFAULT_LAYOUT_FILE,
+};
+use crate::palette::{self, PaletteContext, PaletteEntry, PALETTE_FIELD};
+use crate::profiles::{active_profile, profile_names, ProfileSettings};
+use crate::prediction::dictionary::{self, DictionaryInfo, LoadedDictionaries};
+use crate::prediction::learning::{self, LearnedWords, LEARN_THRESHOLD};
+use crate::prediction::{self, BigramModel, PredictionEngine, Predictor};
+use crate::recording::SessionRecorder;
+use crate::snippets::SnippetExpander;
+use crate::stats::KeyStats;
+use crate::renderer::dead_zone::{render_dead_zones, DeadZoneInsets, Edge};
+use crate::renderer::emoji_search;
+use crate::renderer::fonts;
+use crate::renderer::geometry::fitted_panel_height;
+use crate::renderer::touch_target;
+use crate::renderer::key::key_identifier;
+use crate::renderer::navigation::first_target;
 use crate::renderer::{
-    render_animated_panels, render_current_toast, render_keyboard_with_toast, get_scale_factor,
-    KeyboardRenderer, RendererMessage, ToastSeverity,
-    ANIMATION_FRAME_INTERVAL_MS, LONG_PRESS_TIMER_INTERVAL_MS, TOAST_TIMER_INTERVAL_MS,
+    calculate_row_width, debug_overlay_from_env, panic_message, render_animated_panels,
+    render_current_toast,
+    render_error_panel, render_guarded, render_keyboard_with_toast, get_scale_factor,
+    output_scale_factor,
+    is_palette_open, is_typing_test_active, render_keyboard_with_palette, render_palette,
+    render_typing_test, adjacent_panel, move_focus, HeightTransition, KeyboardRenderer, NavTarget,
+    PointerLeavePolicy, RendererMessage,
+    Toast, ToastSeverity, PALETTE_HEIGHT, TYPING_TEST_HEIGHT,
+    is_pulsing, pulse_intensity, render_emission_strip, EMISSION_STRIP_HEIGHT,
+    collapsed_status, is_double_tap, render_collapsed_bar, COLLAPSED_BAR_HEIGHT,
+    render_status_strip, STATUS_STRIP_HEIGHT,
+    adjust_popup_position, calculate_popup_position, has_swipe_alternatives, panel_geometry,
+    popup_alternative, popup_direction_at, render_popup_overlay, ActivePopup, Rectangle,
+    LONG_PRESS_TIMER_INTERVAL_MS, STUCK_KEY_CHECK_INTERVAL_MS, TOAST_TIMER_INTERVAL_MS,
 };
 use crate::state::WindowState;
 use cosmic::app::{Core, Task};
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::iced::event;
+use cosmic::iced::event::wayland::{Event as WaylandEvent, OutputEvent};
+use cosmic::iced::keyboard;
 use cosmic::iced::mouse;
 use cosmic::iced::time;
+use cosmic::iced::touch;
 use cosmic::iced::window::{self, Id};
-use cosmic::iced::{Event, Length, Limits, Point};
+use cosmic::iced::{Alignment, Event, Length, Limits, Point};
 use cosmic::iced_runtime::platform_specific::wayland::layer_surface::{
     IcedMargin, IcedOutput, SctkLayerSurfaceSettings,
 };
@@ -45,18 +113,19 @@ use cosmic::iced_winit::platform_specific::wayland::commands::layer_surface::{
     destroy_layer_surface, get_layer_surface, set_anchor, set_exclusive_zone, set_margin, set_size,
     Anchor, KeyboardInteractivity, Layer,
 };
+use std::sync::{Arc, Mutex};
+use wayland_client::protocol::wl_output::WlOutput;
 use cosmic::surface::action::{app_popup, destroy_popup};
 use cosmic::widget::{self, container, divider, list_column, mouse_area, Space};
 use cosmic::Element;
-use cosmic::Theme;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 /// The applet Application ID (distinct from the main application).
 pub const APPLET_ID: &str = "io.github.cosboard.Cosboard.Applet";
 
-/// Default layout file path (relative to the executable or absolute).
-const DEFAULT_LAYOUT_PATH: &str = "resources/layouts/example_qwerty.json";
-
 /// Minimum keyboard width in floating mode.
 const MIN_WIDTH: f32 = 300.0;
 /// Maximum keyboard width in floating mode.
@@ -65,10 +134,24 @@ const MAX_WIDTH: f32 = 1920.0;
 const MIN_HEIGHT: f32 = 150.0;
 /// Maximum keyboard height.
 const MAX_HEIGHT: f32 = 500.0;
+/// Height of a one-unit key when the keyboard height follows the layout.
+const DEFAULT_KEY_HEIGHT: f32 = 54.0;
 /// Size of resize handle zones in pixels (larger for easier grabbing).
 const RESIZE_ZONE_SIZE: f32 = 16.0;
+/// Height of the strip along the screen edge that shows the keyboard when
+/// running without the panel.
+const EDGE_STRIP_HEIGHT: u32 = 8;
+/// Layer-shell namespace of the edge strip.
+const EDGE_STRIP_NAMESPACE: &str = "cosboard-edge";
+/// Delay before hiding the keyboard when the focused text field loses focus
+/// (`auto_show`), so focus moving to another field does not hide it.
+const AUTO_HIDE_DELAY_MS: u64 = 300;
 /// Minimum interval between preview surface updates (debounce).
 const PREVIEW_UPDATE_INTERVAL_MS: u128 = 100;
+/// Interval for checking whether held chord keys should be typed.
+const CHORD_TIMER_INTERVAL_MS: u64 = 15;
+/// Interval for re-evaluating the scheduled keyboard palette.
+const THEME_SCHEDULE_INTERVAL_SECS: u64 = 60;
 
 /// Which edge or corner is being resized.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -89,6 +172,8 @@ pub struct AppletModel {
     popup: Option<Id>,
     /// The keyboard layer surface ID (if open).
     keyboard_surface: Option<window::Id>,
+    /// Size of the keyboard surface last configured by the compositor.
+    surface_size: Option<(f32, f32)>,
     /// Whether the keyboard is currently visible.
     keyboard_visible: bool,
     /// Window state (size, floating mode) for the keyboard.
@@ -101,6 +186,8 @@ pub struct AppletModel {
     resize_edge: Option<ResizeEdge>,
     /// Last known cursor position (for incremental drag/resize tracking).
     last_cursor_position: Option<Point>,
+    /// Pointer motion and prediction updates waiting for the next frame.
+    frame_queue: FrameQueue,
     /// Pending width during resize (avoids triggering rebuilds until resize ends).
     pending_width: f32,
     /// Pending height during resize.
@@ -111,6 +198,11 @@ pub struct AppletModel {
     pending_margin_bottom: i32,
     /// Preview layer surface ID (shown during drag/resize operations).
     preview_surface: Option<window::Id>,
+    /// Edge strip layer surface ID (shown while the keyboard is hidden when
+    /// running without the panel).
+    edge_strip: Option<window::Id>,
+    /// Whether a text field has focus, from the input method (`auto_show`).
+    text_field_focused: bool,
     /// Last sent preview width (for deduplication - skip if unchanged).
     last_preview_width: u32,
     /// Last sent preview height (for deduplication).
@@ -123,8 +215,177 @@ pub struct AppletModel {
     last_preview_update: Option<Instant>,
     /// Keyboard renderer for rendering the layout (Task 7.1).
     keyboard_renderer: Option<KeyboardRenderer>,
-    /// Virtual keyboard for emitting key events (Task Group 5).
-    virtual_keyboard: VirtualKeyboard,
+    /// Virtual keyboard and held key combos, typing into the client
+    /// application (Task Group 5).
+    controller: KeyboardController,
+    /// User configuration (surface layer, keyboard interactivity).
+    config: Config,
+    /// Config context for persisting user configuration.
+    config_context: Option<cosmic_config::Config>,
+    /// Surface settings given on the command line or in the environment.
+    overrides: SurfaceOverrides,
+    /// The output the keyboard is pinned to, once the compositor announced it.
+    target_output: Option<WlOutput>,
+    /// Density of each announced output, in logical pixels per millimetre.
+    output_densities: Vec<(WlOutput, f32)>,
+    /// Scale factor of each announced output.
+    output_scales: Vec<(WlOutput, f32)>,
+    /// HDPI scale factor the keys are laid out with, from the keyboard's
+    /// output.
+    scale_factor: f32,
+    /// Announced outputs by name, offered in the popup menu.
+    outputs: Outputs<WlOutput>,
+    /// Panel whose undersized keys were reported last (reported once).
+    touch_target_warned: Option<String>,
+    /// State snapshot served to D-Bus property reads.
+    dbus_state: SharedState,
+    /// Session bus connection, once the D-Bus service is registered.
+    dbus_connection: Option<zbus::Connection>,
+    /// D-Bus callers waiting for the user to accept them, by toast action ID.
+    confirmations: Vec<(u64, Confirmation)>,
+    /// Toast action ID of the last confirmation asked.
+    last_confirmation_id: u64,
+    /// Layout file chosen by the user (from the configuration, the popup
+    /// menu or the palette), if any. Either a path or a file name resolved
+    /// on the layout search path.
+    active_layout_path: Option<String>,
+    /// Layout search path (user, system, built-in directories).
+    layout_providers: ProviderChain,
+    /// Where the loaded layout came from (`None` for paths off the chain).
+    layout_source: Option<LayoutSource>,
+    /// Layout files on the search path, offered by the palette.
+    available_layouts: Vec<String>,
+    /// Resolves action names from command keys and D-Bus.
+    actions: ActionRegistry,
+    /// Recognizes keyboard-wide touch gestures.
+    gesture_tracker: GestureTracker,
+    /// Space key whose emission waits to see if it becomes a long press.
+    deferred_space: Option<String>,
+    /// Prediction candidate typed on release rather than on press, unless it
+    /// is dragged out of the keyboard first.
+    deferred_candidate: Option<String>,
+    /// Key with alternatives whose emission waits to see if it becomes a
+    /// long press, which opens its popup instead.
+    deferred_popup_key: Option<String>,
+    /// Key whose popup was closed by a release away from the key, so its
+    /// own release (if it comes) is ignored.
+    popup_closed_key: Option<String>,
+    /// Pressed keys the pointer left, released when it is lifted elsewhere.
+    exited_keys: Vec<String>,
+    /// Touches classified as fingertips or palms (`palm_rejection`).
+    palm_filter: PalmFilter,
+    /// Key held back by palm rejection until its contact lasted long
+    /// enough, with the time it was pressed.
+    palm_held_key: Option<(String, Instant)>,
+    /// Key dropped by palm rejection while still held, so its release is
+    /// ignored.
+    palm_rejected_key: Option<String>,
+    /// Codes typed by keys held down on a modifier layer, by key
+    /// identifier, so each is released as it was pressed.
+    layer_codes: HashMap<String, KeyCode>,
+    /// Kiosk restrictions, read from the configuration once at startup.
+    kiosk: KioskPolicy,
+    /// Running typing test, while its field has focus.
+    typing_test: Option<TypingTest>,
+    /// Detects the layout's chords among key presses.
+    chord_matcher: ChordMatcher,
+    /// Cycles letters through their accents on repeated taps.
+    multi_tap: MultiTap,
+    /// Dots held on the braille panel.
+    braille_chord: BrailleChord,
+    /// Translates the chorded braille cells to text.
+    braille: BrailleTranslator,
+    /// Tracks a finger sliding across a piano mode panel.
+    slide: SlideTracker,
+    /// Plays key sounds chosen by the layout's feedback profiles.
+    feedback: Feedback,
+    /// Vibrates the device on key presses, through feedbackd.
+    haptics: Haptics,
+    /// Tells which key boundaries a finger sliding over the keys crosses.
+    boundaries: BoundaryTracker,
+    /// Runs the user's hooks on keyboard events.
+    hooks: HookRunner,
+    /// Keeps the screen awake while keys are typed.
+    idle_inhibitor: IdleInhibitor,
+    /// Name of the layout hooks were last told about, to tell a layout
+    /// change from the same layout loaded again.
+    hooked_layout: Option<String>,
+    /// Number of the latest layout load request (older results are dropped).
+    layout_request: u64,
+    /// Whether a layout is being read in the background.
+    layout_loading: bool,
+    /// Layouts already parsed, so showing the keyboard skips the disk.
+    layout_cache: LayoutCache,
+    /// Files of the active layout, reloaded when one of them changes.
+    layout_watcher: LayoutWatcher,
+    /// Panel to show again once a changed layout is reloaded.
+    reload_panel: Option<String>,
+    /// Keyboard height sliding to the height of the panel switched to.
+    height_transition: Option<HeightTransition>,
+    /// Whether the keyboard is shrunk to the collapsed bar.
+    collapsed: bool,
+    /// When the collapsed bar was last tapped, for double taps.
+    collapsed_bar_tapped: Option<Instant>,
+    /// Writes handled messages to a trace file, when recording is enabled.
+    recorder: Option<SessionRecorder>,
+    /// Tracks the word being typed and its prediction candidates.
+    predictor: Predictor,
+    /// Input method engine of the layout's language, if it has one.
+    ime: Option<Box<dyn ImeEngine>>,
+    /// Panel the prediction row is currently composed into.
+    prediction_panel: Option<String>,
+    /// Installed dictionaries, listed in the settings menu.
+    available_dictionaries: Vec<DictionaryInfo>,
+    /// Dictionaries the predictor's engine was built from.
+    loaded_dictionaries: Vec<String>,
+    /// Dictionaries being read in the background, if any.
+    loading_dictionaries: Option<Vec<String>>,
+    /// Words the user typed, once read from disk (`None` until then, or if
+    /// they could not be read, so nothing overwrites them).
+    learned_words: Option<LearnedWords>,
+    /// Whether words were learned since they were last saved.
+    learned_words_changed: bool,
+    /// Per-key press counts, once read from disk (`None` until then, or if
+    /// they could not be read, so nothing overwrites them).
+    key_stats: Option<KeyStats>,
+    /// Whether key presses were counted since the counts were last saved.
+    key_stats_changed: bool,
+    /// Whether learning is paused, e.g. while typing a password.
+    private_mode: bool,
+    /// Tracks typed text for snippet triggers.
+    snippet_expander: SnippetExpander,
+    /// Tracks typed spaces for ending sentences with a double space.
+    double_space: DoubleSpaceTracker,
+    /// Application ID of the focused window, as last reported.
+    focused_app: Option<String>,
+    /// Latest ambient light level, while the light sensor is read.
+    light_level: Option<f64>,
+    /// Palette the keyboard picked itself (`None` follows the system theme).
+    keyboard_palette: Option<Palette>,
+    /// Whether the COSMIC theme is dark, once a switch was reported (`None`
+    /// reads the system preference).
+    system_dark: Option<bool>,
+    /// When each startup phase finished.
+    startup: StartupTimer,
+    /// Whether the setup deferred until after the first icon render started.
+    deferred_started: bool,
+}
+
+/// A layout read in the background, ready to be turned into a renderer.
+#[derive(Debug, Clone)]
+pub struct LoadedLayout {
+    /// Load request this result answers.
+    request: u64,
+    /// Path or file name the layout was requested with.
+    requested: String,
+    /// Path of the layout file that was read.
+    path: String,
+    /// Where the layout file was found on the search path.
+    source: Option<LayoutSource>,
+    /// Paths of all layouts on the search path.
+    available: Vec<String>,
+    /// Parsed layout with its warnings, or the error message.
+    result: Result<ParseResult<Layout>, String>,
 }
 
 impl Default for AppletModel {
@@ -134,6 +395,7 @@ impl Default for AppletModel {
             core: Core::default(),
             popup: None,
             keyboard_surface: None,
+            surface_size: None,
             keyboard_visible: false,
             pending_width: window_state.width,
             pending_height: window_state.height,
@@ -144,14 +406,87 @@ impl Default for AppletModel {
             is_dragging: false,
             resize_edge: None,
             last_cursor_position: None,
+            frame_queue: FrameQueue::default(),
             preview_surface: None,
+            edge_strip: None,
+            text_field_focused: false,
             last_preview_width: 0,
             last_preview_height: 0,
             last_preview_margin_right: 0,
             last_preview_margin_bottom: 0,
             last_preview_update: None,
             keyboard_renderer: None,
-            virtual_keyboard: VirtualKeyboard::new(),
+            controller: KeyboardController::new(),
+            config: Config::default(),
+            config_context: None,
+            overrides: SurfaceOverrides::default(),
+            target_output: None,
+            output_densities: Vec::new(),
+            output_scales: Vec::new(),
+            scale_factor: get_scale_factor(),
+            outputs: Outputs::default(),
+            touch_target_warned: None,
+            dbus_state: Arc::new(Mutex::new(DbusState::default())),
+            dbus_connection: None,
+            confirmations: Vec::new(),
+            last_confirmation_id: 0,
+            active_layout_path: None,
+            layout_providers: ProviderChain::standard(),
+            layout_source: None,
+            available_layouts: Vec::new(),
+            actions: ActionRegistry::new(),
+            gesture_tracker: GestureTracker::new(),
+            deferred_space: None,
+            deferred_candidate: None,
+            deferred_popup_key: None,
+            popup_closed_key: None,
+            exited_keys: Vec::new(),
+            palm_filter: PalmFilter::default(),
+            palm_held_key: None,
+            palm_rejected_key: None,
+            layer_codes: HashMap::new(),
+            kiosk: KioskPolicy::default(),
+            typing_test: None,
+            chord_matcher: ChordMatcher::default(),
+            multi_tap: MultiTap::default(),
+            braille_chord: BrailleChord::new(),
+            braille: BrailleTranslator::default(),
+            slide: SlideTracker::new(),
+            feedback: Feedback::new(),
+            haptics: Haptics::new(),
+            boundaries: BoundaryTracker::new(),
+            hooks: HookRunner::new(),
+            idle_inhibitor: IdleInhibitor::new(),
+            hooked_layout: None,
+            layout_request: 0,
+            layout_loading: false,
+            layout_cache: LayoutCache::new(),
+            layout_watcher: LayoutWatcher::default(),
+            reload_panel: None,
+            height_transition: None,
+            collapsed: false,
+            collapsed_bar_tapped: None,
+            recorder: None,
+            predictor: Predictor::new(PredictionEngine::builtin())
+                .with_bigrams(BigramModel::builtin()),
+            ime: None,
+            prediction_panel: None,
+            available_dictionaries: Vec::new(),
+            loaded_dictionaries: Vec::new(),
+            loading_dictionaries: None,
+            learned_words: None,
+            learned_words_changed: false,
+            key_stats: None,
+            key_stats_changed: false,
+            private_mode: false,
+            snippet_expander: SnippetExpander::new(),
+            double_space: DoubleSpaceTracker::new(),
+            focused_app: None,
+            light_level: None,
+            keyboard_palette: None,
+            system_dark: None,
+            startup: StartupTimer::default(),
+            deferred_started: false,
         }
     }
 }
@@ -169,6 +504,10 @@ pub enum Message {
     Hide,
     /// Quit the applet.
     Quit,
+    /// Exit the process, once the surfaces are destroyed.
+    Exit,
+    /// Dispatch a named action (command keys, D-Bus, palette, popup menu).
+    Action(AppAction),
     /// Popup menu closed.
     PopupClosed(Id),
     /// Handle surface actions (for popup management).
@@ -177,6 +516,11 @@ pub enum Message {
     KeyboardSurfaceClosed(window::Id),
     /// Keyboard layer surface was resized.
     KeyboardSurfaceResized(window::Id, f32, f32),
+    /// An output was announced or changed, with its name, its density
+    /// (logical pixels per millimetre) and its scale factor if known.
+    OutputAdded(WlOutput, Option<String>, Option<f32>, Option<f32>),
+    /// An output was removed.
+    OutputRemoved(WlOutput),
     /// Toggle between docked and floating mode.
     ToggleFloatingMode,
     /// Save window state (debounced).
@@ -202,18 +546,191 @@ pub enum Message {
     KeyPressed(String),
     /// A key was released on the rendered keyboard.
     KeyReleased(String),
+    /// The pointer or finger entered a key on a piano mode panel, or on any
+    /// panel while boundary haptics are on.
+    KeyEntered(String),
+    /// The pointer or finger left a key.
+    KeyExited(String),
+    /// The button or a finger was lifted while keys were pressed.
+    PointerLifted,
+    /// The finger or button that started a piano mode slide was lifted.
+    SlideEnd,
     /// Switch to a different panel.
     SwitchPanel(String),
-    /// Animation frame tick for panel transitions.
+    /// Animation frame tick for panel transitions, once per displayed frame.
     AnimationTick,
     /// Long press timer tick for detecting long presses.
     LongPressTimerTick,
+    /// Stuck key watchdog tick for releasing keys held implausibly long.
+    StuckKeyTimerTick,
+    /// The pointer left a surface, or a touch was cancelled, while keys
+    /// were pressed.
+    PointerLeft(window::Id),
+    /// A modifier chip of the status strip was tapped, to clear it.
+    ClearModifier(Modifier),
+    /// The pointer or finger moved while a long press popup is open.
+    PopupPointerMoved(Point),
+    /// The button or finger holding a long press popup open was released.
+    PopupReleased,
+    /// Chord timer tick for typing held chord keys once the window passes.
+    ChordTimerTick,
+    /// Files in the directories of the active layout changed.
+    LayoutFilesChanged,
+    /// A layout finished loading in the background.
+    LayoutLoaded(Box<LoadedLayout>),
+    /// Layouts parsed at startup, ready to be cached.
+    LayoutsPreloaded(Vec<LoadedLayout>),
+    /// Set up the virtual keyboard once the keyboard surface is requested.
+    InitVirtualKeyboard,
     /// Show a toast notification.
     ShowToast(String, ToastSeverity),
     /// Dismiss the current toast notification.
     DismissToast,
     /// Toast timer tick for auto-dismiss.
     ToastTimerTick,
+    /// Frame tick while the emission indicator fades, to redraw it.
+    EmissionTick,
+    /// Frame tick while a modifier key is held, to redraw its pulse.
+    ModifierPulseTick,
+    /// Frame tick while updates are queued, to apply them.
+    FrameFlush,
+    /// The key event journal was written to this file on `SIGUSR1`, or
+    /// could not be.
+    EventsDumped(Result<String, String>),
+    /// The button of a toast was pressed.
+    ToastAction(u64),
+    /// The collapsed keyboard bar was tapped.
+    CollapsedBarTapped,
+    /// Give input focus to an internal text field (e.g. emoji search).
+    FocusTextField(String),
+    /// Release input focus from the focused internal text field.
+    BlurTextField,
+    /// Emit text to the client application.
+    InsertText(String),
+    /// An emoji or symbol was picked on the emoji/symbol panel.
+    InsertSymbol(String),
+    /// A physical key was pressed while an internal text field has focus.
+    PhysicalKeyPressed(ResolvedKeycode),
+    /// A gamepad button or remote key moved the focus or pressed a key.
+    Navigate(NavCommand),
+    /// Set whether the keyboard can be navigated with a gamepad or remote.
+    SetNavigation(bool),
+    /// Enable or disable palm rejection.
+    SetPalmRejection(bool),
+    /// Set whether keys play their sounds.
+    SetKeySounds(bool),
+    /// Set whether key presses vibrate the device.
+    SetHapticFeedback(bool),
+    /// Turn keeping the screen awake while typing on or off.
+    SetIdleInhibit(bool),
+    /// The session answered a request to keep the screen awake.
+    IdleInhibited(Result<u32, String>),
+    /// Check whether typing stopped long enough to let the screen go idle.
+    IdleInhibitTick,
+    /// Pin or unpin an emoji/symbol in the "Recents" strip.
+    ToggleFavoriteEmoji(String),
+    /// Show a category tab in the symbols browser.
+    SelectSymbolCategory(crate::symbols::SymbolCategory),
+    /// A scrolling panel or grid moved (scroll area ID, offset in pixels).
+    Scrolled(String, u32),
+    /// Step a field of the date picker.
+    StepDate(DateField, i32),
+    /// Set the date picker back to the current time.
+    ResetDate,
+    /// Type the date picker's date, time or both.
+    InsertDate(DateInsert),
+    // ========================================================================
+    // Configuration and D-Bus
+    // ========================================================================
+    /// User configuration finished loading in the background.
+    ConfigLoaded(Option<cosmic_config::Config>, Config),
+    /// A configuration bundle was imported (no summary if the configuration
+    /// was only reloaded), with the configuration read back, or failed to.
+    ConfigImported(Result<(Option<BundleSummary>, Option<cosmic_config::Config>, Config), String>),
+    /// Event from the D-Bus service.
+    Dbus(DbusEvent),
+    /// Change whether the keyboard surface may take keyboard focus.
+    SetKeyboardInteractivity(Interactivity),
+    /// Change the layer-shell layer of the keyboard surface.
+    SetLayer(crate::layer_shell::Layer),
+    /// Pin the keyboard to the output with this name, or follow the active
+    /// output (popup menu).
+    SetOutput(Option<String>),
+    // ========================================================================
+    // Gestures
+    // ========================================================================
+    /// Touch event on any surface (filtered to the keyboard surface).
+    Touch(window::Id, touch::Event, event::Status),
+    /// A keyboard-wide gesture was recognized.
+    Gesture(Gesture),
+    /// Bind a gesture to the next action choice (settings menu).
+    CycleGestureBinding(Gesture),
+    /// Set the dead zone along an edge to the next size, in the current
+    /// mode (settings menu).
+    CycleDeadZone(Edge),
+    /// Set whether the on-screen Esc key hides the keyboard.
+    SetHideOnEscape(bool),
+    /// Set whether the on-screen Enter key hides the keyboard.
+    SetHideAfterEnter(bool),
+    /// Enable or disable a prediction dictionary (settings menu).
+    SetDictionaryEnabled(String, bool),
+    /// Prediction dictionaries finished loading in the background.
+    DictionariesLoaded(Vec<String>, Box<LoadedDictionaries>),
+    /// Learned words finished loading in the background.
+    LearnedWordsLoaded(Option<LearnedWords>),
+    /// Set whether typed words are learned for prediction.
+    SetLearning(bool),
+    /// Forget every learned word.
+    ClearLearnedWords,
+    /// Key press statistics finished loading in the background.
+    KeyStatsLoaded(Option<KeyStats>),
+    /// Set whether key presses are counted.
+    SetKeyStats(bool),
+    /// Set whether a dot pulses under the keys for each emitted key event.
+    SetEmissionIndicator(bool),
+    /// Set whether a strip above the keys shows the modifiers and layout.
+    SetStatusStrip(bool),
+    /// Set whether prediction candidates can be dragged into applications.
+    SetDragSuggestions(bool),
+    /// A key's text started being dragged out of the keyboard.
+    TextDragged(String),
+    /// Set whether keys are tinted by how often they were pressed.
+    SetHeatMap(bool),
+    /// Forget every key press count.
+    ClearKeyStats,
+    /// Set whether snippets are expanded.
+    SetSnippetsEnabled(bool),
+    /// Set whether snippets are expanded in the focused application.
+    SetSnippetsInFocusedApp(bool),
+    /// Set whether two quick spaces type a full stop.
+    SetDoubleSpacePeriod(bool),
+    /// Delete the snippet with this trigger.
+    RemoveSnippet(String),
+    // ========================================================================
+    // Keyboard palette
+    // ========================================================================
+    /// Switch the keyboard palette to the next mode (settings menu).
+    CycleKeyboardTheme,
+    /// Minute timer re-evaluating the scheduled palette.
+    ThemeScheduleTick,
+    /// Reading from the ambient light sensor.
+    LightSensor(LightEvent),
+    /// The COSMIC theme switched between light and dark (`true` for dark).
+    SystemThemeChanged(bool),
+    /// Text field focus changed, from the input method.
+    InputMethod(InputMethodEvent),
+    /// The auto-hide delay after a text field lost focus ran out.
+    AutoHide,
+    /// Set whether the keyboard follows text field focus.
+    SetAutoShow(bool),
+    // ========================================================================
+    // Startup
+    // ========================================================================
+    /// The startup budget passed; start the setup deferred until the icon
+    /// is shown.
+    DeferredStartup,
+    /// Translations for the session language finished loading.
+    LocalizationLoaded,
 }
 
 impl AppletModel {
@@ -228,6 +745,309 @@ impl AppletModel {
         }
     }
 
+    /// Save the user configuration to disk.
+    fn save_config(&self) {
+        if let Some(ref context) = self.config_context {
+            if let Err(e) = self.config.write_entry(context) {
+                tracing::warn!("Failed to save config: {:?}", e);
+            }
+        }
+    }
+
+    /// Applies the palette chosen by the appearance settings.
+    ///
+    /// Returns a task switching the applet's theme when the palette changed,
+    /// back to the system theme when the keyboard stops picking its own.
+    fn sync_keyboard_theme(&mut self) -> Task<Message> {
+        let palette = self.config.appearance.palette(
+            minute_of_day(),
+            self.light_level,
+            self.keyboard_palette,
+        );
+        if palette == self.keyboard_palette {
+            return Task::none();
+        }
+        tracing::debug!("Keyboard palette: {:?}", palette);
+        self.keyboard_palette = palette;
+        cosmic::command::set_theme(self.keyboard_theme())
+    }
+
+    /// Returns the theme the keys and toasts are drawn in: the palette the
+    /// keyboard picked itself, or else the system's light or dark theme.
+    fn keyboard_theme(&self) -> cosmic::Theme {
+        match (self.keyboard_palette, self.system_dark) {
+            (Some(Palette::Dark), _) | (None, Some(true)) => cosmic::theme::system_dark(),
+            (Some(Palette::Light), _) | (None, Some(false)) => cosmic::theme::system_light(),
+            (None, None) => cosmic::theme::system_preference(),
+        }
+    }
+
+    /// Update the D-Bus state snapshot and notify subscribed clients.
+    ///
+    /// Returns a task emitting `PropertiesChanged` (and `VisibilityChanged`
+    /// when `visibility_changed` is set), or `Task::none()` if the D-Bus
+    /// service is not registered or nothing changed.
+    fn sync_dbus_state(&self, visibility_changed: bool) -> Task<Message> {
+        let (width, height) = self
+            .surface_size
+            .unwrap_or((self.window_state.width, self.window_state.height));
+        let changed = self.dbus_state.lock().is_ok_and(|mut state| {
+            let previous = state.clone();
+            state.visible = self.keyboard_visible;
+            state.keyboard_interactivity = self.config.keyboard_interactivity;
+            state.layer = self.surface_layer();
+            state.external_input = self.config.external_input.clone();
+            state.floating = self.window_state.is_floating;
+            state.width = width.round() as u32;
+            state.height = height.round() as u32;
+            *state != previous
+        });
+
+        match self.dbus_connection.clone() {
+            Some(connection) if changed || visibility_changed => {
+                Task::future(dbus::notify_state_changed(connection, visibility_changed)).discard()
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// Tells D-Bus clients about the panel shown, if it changed since the
+    /// last call.
+    fn sync_dbus_panel(&self) -> Task<Message> {
+        let Some(ref renderer) = self.keyboard_renderer else {
+            return Task::none();
+        };
+        let panel_id = renderer.shown_panel_id().to_string();
+        let changed = self.dbus_state.lock().is_ok_and(|mut state| {
+            let changed = state.panel_id != panel_id;
+            state.panel_id.clone_from(&panel_id);
+            changed
+        });
+
+        match self.dbus_connection.clone() {
+            Some(connection) if changed => {
+                Task::future(dbus::notify_panel_changed(connection, panel_id)).discard()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Runs the user's hooks for `event` in the background.
+    fn run_hooks(&self, event: HookEvent) -> Task<Message> {
+        let layout = self.hooked_layout.as_deref().unwrap_or_default();
+        let hooks = self.hooks.dispatch(
+            &self.config.hooks,
+            event,
+            layout,
+            self.dbus_connection.clone(),
+        );
+        match hooks {
+            Some(hooks) => Task::future(hooks).discard(),
+            None => Task::none(),
+        }
+    }
+
+    /// Runs the layout change hooks if the loaded layout is not the one
+    /// they were last told about. The first layout loaded is no change.
+    fn hook_layout_change(&mut self) -> Task<Message> {
+        let Some(ref renderer) = self.keyboard_renderer else {
+            return Task::none();
+        };
+        let name = renderer.layout.name.clone();
+        match self.hooked_layout.replace(name.clone()) {
+            Some(previous) if previous != name => self.run_hooks(HookEvent::LayoutChanged),
+            _ => Task::none(),
+        }
+    }
+
+    /// Asks the user to accept a D-Bus caller, with a toast on the keyboard.
+    ///
+    /// The caller is refused right away while the keyboard is hidden, as
+    /// nobody could answer.
+    fn ask_confirmation(&mut self, confirmation: Confirmation) {
+        let renderer = match self.keyboard_renderer {
+            Some(ref mut renderer) if self.keyboard_visible => renderer,
+            _ => {
+                confirmation.answer(false);
+                return;
+            }
+        };
+
+        self.confirmations.retain(|(_, pending)| pending.is_pending());
+        self.last_confirmation_id += 1;
+        let id = self.last_confirmation_id;
+        renderer.queue(
+            Toast::warning(fl!(
+                "confirm-external-input",
+                peer = confirmation.peer.to_string(),
+                request = confirmation.request.clone()
+            ))
+            .with_action(fl!("accept"), id),
+        );
+        self.confirmations.push((id, confirmation));
+    }
+
+    /// Refuses every D-Bus caller still waiting to be accepted.
+    fn refuse_confirmations(&mut self) {
+        for (_, confirmation) in self.confirmations.drain(..) {
+            confirmation.answer(false);
+        }
+    }
+
+    /// Recreate the keyboard surface so new surface settings take effect.
+    ///
+    /// Layer and keyboard interactivity are fixed when a layer surface is
+    /// created, so a visible keyboard is hidden and shown again.
+    fn recreate_keyboard_surface(&self) -> Task<Message> {
+        if self.keyboard_visible {
+            Task::done(cosmic::Action::App(Message::Hide))
+                .chain(Task::done(cosmic::Action::App(Message::Show)))
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Writes the configuration, layouts and learned words to a bundle.
+    fn export_config(&mut self, path: String) -> Task<Message> {
+        // Words learned since the last save go along
+        self.save_learned_words();
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    bundle::export_user_config(Path::new(&path)).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            |result| {
+                cosmic::Action::App(match result {
+                    Ok(summary) => {
+                        tracing::info!("Exported settings ({})", summary);
+                        Message::ShowToast(fl!("config-exported"), ToastSeverity::Info)
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to export settings: {}", e);
+                        Message::ShowToast(
+                            fl!("config-export-failed", error = e),
+                            ToastSeverity::Warning,
+                        )
+                    }
+                })
+            },
+        )
+    }
+
+    /// Imports the configuration bundle at `path`, refusing it if it changes
+    /// protected entries, then reads the configuration and learned words
+    /// back. Without a path, only reads them back, e.g. after `cosboardctl`
+    /// imported a bundle itself.
+    fn import_config(&mut self, path: Option<String>) -> Task<Message> {
+        // Nothing is learned until the imported words are read, so they are
+        // not overwritten by the words in memory
+        match path {
+            Some(_) => self.save_learned_words(),
+            // Already replaced on disk
+            None => self.learned_words_changed = false,
+        }
+        self.learned_words = None;
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let summary = path
+                        .map(|path| bundle::import_user_config(Path::new(&path), false))
+                        .transpose()
+                        .map_err(|e| e.to_string())?;
+                    let (context, config) = read_user_config();
+                    Ok((summary, context, config))
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            |result| cosmic::Action::App(Message::ConfigImported(result)),
+        )
+    }
+
+    /// Switches to a configuration profile.
+    ///
+    /// The profile's settings and window state replace the current ones,
+    /// which are kept under the profile being left. A profile used for the
+    /// first time keeps the current settings.
+    fn switch_profile(&mut self, name: &str) -> Task<Message> {
+        let leaving = active_profile(&self.config.profile).to_string();
+        if name == leaving {
+            return Task::none();
+        }
+        tracing::info!("Switching profile from {} to {}", leaving, name);
+
+        let settings = ProfileSettings::from_config(&self.config);
+        let stored = self.config.profiles.switch(&leaving, settings, name);
+        let window = self.window_state.profile_window();
+        let stored_window = self.window_state.profiles.switch(&leaving, window, name);
+        let previous = self.config.clone();
+        self.config.profile = name.to_string();
+        if let Some(settings) = stored {
+            settings.apply_to(&mut self.config);
+        }
+        if let Some(ref window) = stored_window {
+            self.window_state.apply_profile_window(window);
+        }
+        self.save_config();
+        self.save_state();
+
+        let mut tasks = vec![self.sync_dbus_state(false)];
+        if self.config.layout != previous.layout {
+            self.active_layout_path = self.config.layout.clone();
+            if self.keyboard_visible {
+                tasks.push(self.load_keyboard_layout());
+            }
+        }
+        // Docking, size, layer and focus are set when the surface is created
+        if stored_window.is_some()
+            || self.config.layer != previous.layer
+            || self.config.keyboard_interactivity != previous.keyboard_interactivity
+        {
+            tasks.push(self.recreate_keyboard_surface());
+        } else {
+            tasks.push(self.sync_keyboard_height());
+        }
+        Task::batch(tasks)
+    }
+
+    /// Layer of the keyboard surface: the override, else the configured one.
+    fn surface_layer(&self) -> crate::layer_shell::Layer {
+        self.overrides.layer.unwrap_or(self.config.layer)
+    }
+
+    /// Anchor of the docked keyboard: full width along the bottom edge,
+    /// unless another edge was given on the command line.
+    fn docked_anchor(&self) -> Anchor {
+        self.overrides.anchor.unwrap_or_default().to_sctk()
+    }
+
+    /// Name of the output the keyboard is pinned to: the one named on the
+    /// command line, else the one picked from the popup menu.
+    fn wanted_output(&self) -> Option<&str> {
+        self.overrides
+            .output
+            .as_deref()
+            .or(self.window_state.output.as_deref())
+    }
+
+    /// Output to open the keyboard surface on.
+    ///
+    /// The output the keyboard is pinned to is used once the compositor has
+    /// announced it; until then the keyboard opens on the active output.
+    fn surface_output(&self) -> IcedOutput {
+        match (&self.target_output, self.wanted_output()) {
+            (Some(output), _) => IcedOutput::Output(output.clone()),
+            (None, Some(name)) => {
+                tracing::warn!("Output {} not found, using the active output", name);
+                IcedOutput::Active
+            }
+            (None, None) => IcedOutput::Active,
+        }
+    }
+
     /// Create a preview layer surface for drag/resize operations.
     /// Returns the task to spawn the surface and the new surface ID.
     fn create_preview_surface(&mut self) -> Task<Message> {
@@ -264,15 +1084,259 @@ impl AppletModel {
         get_layer_surface(settings)
     }
 
-    /// Load the keyboard layout and create the renderer (Task 7.2).
+    /// Opens the edge strip while the keyboard is hidden, and closes it while
+    /// the keyboard is shown.
+    ///
+    /// Only used without the panel (`cosboard` binary), where there is no
+    /// tray icon to show the keyboard with: tapping or swiping the strip
+    /// shows it. The strip is anchored to the edge the keyboard is docked
+    /// to and reserves no space.
+    fn sync_edge_strip(&mut self) -> Task<Message> {
+        let wanted = self.overrides.standalone && !self.keyboard_visible;
+        match self.edge_strip {
+            Some(id) if !wanted => {
+                self.edge_strip = None;
+                tracing::debug!("Destroying edge strip: {:?}", id);
+                destroy_layer_surface(id)
+            }
+            None if wanted => {
+                let id = window::Id::unique();
+                let settings = SctkLayerSurfaceSettings {
+                    id,
+                    layer: self.surface_layer().to_sctk(),
+                    keyboard_interactivity: KeyboardInteractivity::None,
+                    input_zone: None,
+                    anchor: self.docked_anchor(),
+                    output: self.surface_output(),
+                    namespace: EDGE_STRIP_NAMESPACE.to_string(),
+                    margin: IcedMargin::default(),
+                    size: Some((None, Some(EDGE_STRIP_HEIGHT))),
+                    exclusive_zone: 0,
+                    size_limits: Limits::NONE.min_height(EDGE_STRIP_HEIGHT as f32),
+                };
+                self.edge_strip = Some(id);
+                tracing::debug!("Creating edge strip: {:?}", id);
+                get_layer_surface(settings)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Starts loading the keyboard layout in the background (Task 7.2).
+    ///
+    /// Loads the layout chosen at runtime, or the default layout, resolving
+    /// file names on the layout search path (user > system > built-in).
+    /// Reading and parsing happen off the UI thread so showing the keyboard
+    /// is not delayed; a "Loading…" placeholder is shown meanwhile and the
+    /// renderer is created by `apply_loaded_layout` when the result arrives.
+    ///
+    /// Layouts in the layout cache (parsed at startup or loaded before) are
+    /// applied right away, without the placeholder.
+    fn load_keyboard_layout(&mut self) -> Task<Message> {
+        self.layout_request += 1;
+
+        let request = self.layout_request;
+        let requested = self
+            .active_layout_path
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LAYOUT_FILE.to_string());
+
+        if let Some(cached) = self.layout_cache.get(&requested) {
+            tracing::debug!("Using cached layout: {}", cached.path);
+            let loaded = LoadedLayout {
+                request,
+                requested,
+                path: cached.path.clone(),
+                source: cached.source,
+                available: self.available_layouts.clone(),
+                result: Ok(cached.result.clone()),
+            };
+            self.apply_loaded_layout(loaded);
+            return self.sync_keyboard_height();
+        }
+
+        self.layout_loading = true;
+        let providers = self.layout_providers.clone();
+
+        Task::perform(
+            async move {
+                let path = requested.clone();
+                let name = requested.clone();
+                tokio::task::spawn_blocking(move || Self::read_layout(&providers, requested, request))
+                    .await
+                    .unwrap_or_else(|e| LoadedLayout {
+                        request,
+                        requested: name,
+                        path,
+                        source: None,
+                        available: Vec::new(),
+                        result: Err(format!("layout loader failed: {}", e)),
+                    })
+            },
+            |loaded| cosmic::Action::App(Message::LayoutLoaded(Box::new(loaded))),
+        )
+    }
+
+    /// Starts the setup deferred until after the first icon render.
+    ///
+    /// Translations, the preloaded layouts, the learned words and the key
+    /// statistics are loaded in background tasks, and the D-Bus interface is registered by the
+    /// next `subscription()`. Only the first call does anything.
+    fn start_deferred_setup(&mut self) -> Task<Message> {
+        if self.deferred_started {
+            return Task::none();
+        }
+        self.deferred_started = true;
+        self.startup.mark(StartupPhase::Deferred);
+
+        // Parse the fluent resources off the UI thread
+        let localize = Task::perform(
+            async {
+                let _ = tokio::task::spawn_blocking(|| {
+                    crate::i18n::init(&crate::i18n::requested_languages());
+                })
+                .await;
+            },
+            |()| cosmic::Action::App(Message::LocalizationLoaded),
+        );
+
+        // Parse the layout ahead of the first Show
+        let preload = self.preload_layouts();
+
+        // Read the learned words; they are small, unlike dictionaries
+        let load_learned_words = Self::load_learned_words();
+
+        // Read the key press counts for the heat map
+        let load_key_stats = Task::perform(
+            async {
+                tokio::task::spawn_blocking(|| {
+                    KeyStats::load(crate::storage::detect().as_ref())
+                        .map_err(|e| tracing::warn!("Failed to load key statistics: {}", e))
+                        .ok()
+                })
+                .await
+                .ok()
+                .flatten()
+            },
+            |stats| cosmic::Action::App(Message::KeyStatsLoaded(stats)),
+        );
+
+        Task::batch([localize, preload, load_learned_words, load_key_stats])
+    }
+
+    /// Parses the layouts likely to be shown first in the background.
+    ///
+    /// Run once at startup: the configured layout (the one chosen at runtime,
+    /// if any) and the default layout it falls back to are read and parsed
+    /// off the UI thread and cached, so the first Show is instant.
+    fn preload_layouts(&self) -> Task<Message> {
+        let providers = self.layout_providers.clone();
+        let mut names = Vec::new();
+        if let Some(path) = &self.active_layout_path {
+            names.push(path.clone());
+        }
+        names.push(DEFAULT_LAYOUT_FILE.to_string());
+        names.dedup();
+
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    names
+                        .into_iter()
+                        .map(|name| Self::read_layout(&providers, name, 0))
+                        .collect::<Vec<_>>()
+                })
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Layout preloading failed: {}", e);
+                    Vec::new()
+                })
+            },
+            |loaded| cosmic::Action::App(Message::LayoutsPreloaded(loaded)),
+        )
+    }
+
+    /// Adds a successfully parsed layout to the layout cache.
+    fn cache_loaded_layout(&mut self, loaded: &LoadedLayout) {
+        if let Ok(result) = &loaded.result {
+            self.layout_cache.insert(
+                loaded.requested.clone(),
+                CachedLayout {
+                    path: loaded.path.clone(),
+                    source: loaded.source,
+                    result: result.clone(),
+                },
+            );
+        }
+    }
+
+    /// Reads and parses a layout (blocking; runs off the UI thread).
     ///
-    /// Attempts to load the layout from the default path. On success,
-    /// creates a KeyboardRenderer. On failure, queues an error toast.
-    fn load_keyboard_layout(&mut self) {
-        // Try to find the layout file
-        let layout_path = Self::find_layout_path();
+    /// `requested` is a path or a file name on the layout search path.
+    fn read_layout(providers: &ProviderChain, requested: String, request: u64) -> LoadedLayout {
+        let available = providers.list();
+        for entry in available.iter().filter(|entry| !entry.shadowed.is_empty()) {
+            tracing::debug!(
+                "Layout {} from {} shadows {:?}",
+                entry.file_name,
+                entry.source,
+                entry.shadowed
+            );
+        }
+
+        let (path, source) = match providers.locate(&requested) {
+            Some((path, source)) => (path.display().to_string(), source),
+            // Not found anywhere: parsing reports the missing file
+            None => (requested.clone(), None),
+        };
+        let result = parse_layout_file_with(providers.storage(), &path)
+            .map(|mut result| {
+                // Reads the installed fonts, so it is done here off the UI thread
+                result.warnings.extend(fonts::missing_glyph_warnings(&result.layout));
+                result
+            })
+            .map_err(|e| e.to_string());
+
+        LoadedLayout {
+            request,
+            requested,
+            path,
+            source,
+            available: available
+                .iter()
+                .map(|entry| entry.path.display().to_string())
+                .collect(),
+            result,
+        }
+    }
 
-        match parse_layout_file(&layout_path) {
+    /// Builds the renderer from a layout loaded in the background.
+    ///
+    /// Results of superseded requests (another layout was requested, or the
+    /// keyboard was hidden meanwhile) are dropped. On failure, the previous
+    /// renderer (if any) is cleared so the error is shown.
+    fn apply_loaded_layout(&mut self, loaded: LoadedLayout) {
+        if loaded.request != self.layout_request {
+            tracing::debug!("Dropping stale layout load: {}", loaded.path);
+            return;
+        }
+        self.layout_loading = false;
+
+        let LoadedLayout {
+            path: layout_path,
+            source,
+            available,
+            result,
+            ..
+        } = loaded;
+        self.available_layouts = available;
+        self.layout_source = source;
+        // Broken layouts are watched too, so fixing them reloads them
+        self.layout_watcher =
+            LayoutWatcher::new(self.layout_providers.storage(), Path::new(&layout_path));
+        let reload_panel = self.reload_panel.take();
+
+        match result {
             Ok(result) => {
                 // Log any warnings from parsing
                 if result.has_warnings() {
@@ -281,9 +1345,58 @@ impl AppletModel {
                     }
                 }
 
-                // Create the renderer with the loaded layout
-                self.keyboard_renderer = Some(KeyboardRenderer::new(result.layout));
-                tracing::info!("Loaded keyboard layout from: {}", layout_path);
+                // Row width problems visibly shrink the keyboard, so surface
+                // them to the user as well as in the log
+                let row_width_issues: Vec<_> = result
+                    .warnings
+                    .iter()
+                    .filter_map(|w| row_of_path(&w.field_path))
+                    .collect();
+
+                // Create the renderer with the loaded layout plus built-in panels
+                let mut layout = result.layout;
+                crate::symbols::add_builtin_panel(&mut layout);
+                crate::calculator::add_builtin_panel(&mut layout);
+                crate::braille::add_builtin_panel(&mut layout);
+                self.config.theme.apply_key_gap(&mut layout);
+                self.load_ime(layout.language.as_deref());
+                self.chord_matcher = ChordMatcher::new(layout.chords.clone());
+                self.multi_tap = MultiTap::new(layout.accent_cycling.as_ref());
+                self.feedback.set_layout_path(Some(Path::new(&layout_path)));
+                let mut renderer = KeyboardRenderer::new(layout);
+                // Stay on the panel being edited, if the layout still has it
+                if let Some(panel_id) = reload_panel
+                    .filter(|panel_id| renderer.layout.panels.contains_key(panel_id))
+                {
+                    renderer.current_panel_id = panel_id;
+                }
+                if let Some(&(panel, row)) = row_width_issues.first() {
+                    renderer.queue_toast(
+                        fl!(
+                            "uneven-rows",
+                            count = row_width_issues.len(),
+                            panel = panel,
+                            row = row
+                        ),
+                        ToastSeverity::Warning,
+                    );
+                }
+                renderer.emoji_recents = self.config.emoji_recents.clone();
+                renderer.repair_invariants();
+                self.keyboard_renderer = Some(renderer);
+                self.prediction_panel = None;
+                self.sync_debug_overlay();
+                self.sync_heat_map();
+                self.sync_key_style();
+                self.sync_drag_suggestions();
+                self.sync_key_crossings();
+                self.sync_prediction_row();
+                tracing::info!(
+                    "Loaded keyboard layout from: {} ({}, {} storage)",
+                    layout_path,
+                    source.map_or("custom path", |source| source.label()),
+                    self.layout_providers.storage().name()
+                );
             }
             Err(e) => {
                 // Log the error and queue a toast notification
@@ -297,752 +1410,3520 @@ impl AppletModel {
         }
     }
 
-    /// Find the layout file path, checking multiple locations.
-    fn find_layout_path() -> String {
-        // Check various locations for the layout file
-        let candidates = [
-            DEFAULT_LAYOUT_PATH.to_string(),
-            format!("/usr/share/cosboard/layouts/example_qwerty.json"),
-            format!(
-                "{}/resources/layouts/example_qwerty.json",
-                std::env::current_dir()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_default()
-            ),
-        ];
+    /// Sets the keyboard height, resizing the surface if it is open.
+    fn set_keyboard_height(&mut self, height: f32) -> Task<Message> {
+        self.height_transition = None;
+        self.window_state.height = height;
+        self.save_state();
+        self.resize_keyboard_surface(height)
+    }
 
-        for path in &candidates {
-            if std::path::Path::new(path).exists() {
-                return path.clone();
-            }
+    /// Resizes the keyboard surface, and its exclusive zone when docked,
+    /// without recording the height. A collapsed keyboard stays a bar.
+    fn resize_keyboard_surface(&self, height: f32) -> Task<Message> {
+        let Some(id) = self.keyboard_surface else {
+            return Task::none();
+        };
+        let height = if self.collapsed {
+            COLLAPSED_BAR_HEIGHT
+        } else {
+            height
+        };
+        if self.window_state.is_floating {
+            set_size(id, Some(self.window_state.width as u32), Some(height as u32))
+        } else {
+            Task::batch(vec![
+                set_size(id, None, Some(height as u32)),
+                set_exclusive_zone(id, height as i32),
+            ])
         }
-
-        // Return default path even if it doesn't exist (will show error)
-        DEFAULT_LAYOUT_PATH.to_string()
     }
 
-    /// Render the keyboard content using the renderer (Task 7.3).
-    fn render_keyboard_content(&self) -> Element<'_, Message> {
-        let surface_width = self.window_state.width;
-        let surface_height = self.window_state.height;
-        let scale = get_scale_factor();
-
-        if let Some(ref renderer) = self.keyboard_renderer {
-            // Render the keyboard panel using the renderer
-            let panel_element = render_animated_panels(renderer, surface_width, surface_height, scale);
+    /// Fits the keyboard height to the rows of the visible panel at the
+    /// preferred key height, within the keyboard's height limits.
+    ///
+    /// A height the user chose by resizing the keyboard is kept, until the
+    /// `fit_height` action hands the height back to the layout. During a
+    /// panel slide, the height follows the slide (see `step_height_transition`).
+    fn fit_height_to_panel(&mut self) -> Task<Message> {
+        if self.window_state.height_set_by_user {
+            return Task::none();
+        }
+        let Some(renderer) = self.keyboard_renderer.as_ref() else {
+            return Task::none();
+        };
+        let Some(panel) = renderer.get_panel(renderer.shown_panel_id()) else {
+            return Task::none();
+        };
+        let sliding = renderer.is_animating();
+        let key_height = self
+            .config
+            .key_height
+            .map_or(DEFAULT_KEY_HEIGHT, |height| height as f32);
+        let height = fitted_panel_height(panel, self.window_state.width, key_height)
+            .round()
+            .clamp(MIN_HEIGHT, MAX_HEIGHT);
+        if (height - self.window_state.height).abs() < 1.0 {
+            return Task::none();
+        }
+        tracing::debug!("Fitting keyboard height to panel '{}': {}", panel.id, height);
+        if !sliding || self.keyboard_surface.is_none() || self.collapsed {
+            return self.set_keyboard_height(height);
+        }
 
-            // Get the current theme for toast rendering
-            let theme = Theme::dark(); // TODO: Get actual theme from COSMIC context
+        // Start from the height shown, which may be mid-way through the
+        // slide to another panel
+        let from = self
+            .height_transition
+            .map_or(self.window_state.height, |transition| transition.current);
+        self.height_transition = Some(HeightTransition::new(from, height));
+        self.window_state.height = height;
+        self.save_state();
+        Task::none()
+    }
 
-            // Render toast if any
-            let toast_element = render_current_toast(renderer, &theme);
+    /// Resizes the keyboard along with the panel slide, at its eased
+    /// `progress`; `None` once the slide is over, which lands on the target.
+    fn step_height_transition(&mut self, progress: Option<f32>) -> Task<Message> {
+        let Some(ref mut transition) = self.height_transition else {
+            return Task::none();
+        };
+        let step = transition.step(progress.unwrap_or(1.0));
+        if progress.is_none() {
+            self.height_transition = None;
+        }
+        step.map_or_else(Task::none, |height| self.resize_keyboard_surface(height))
+    }
 
-            // Combine panel with toast area
-            let keyboard_with_toast = render_keyboard_with_toast(panel_element, toast_element, surface_height);
+    /// Returns `true` if the layout file at `path` is the one chosen, by
+    /// path or by file name (the default layout if none is).
+    fn is_active_layout(&self, path: &str) -> bool {
+        let requested = self.active_layout_path.as_deref().unwrap_or(DEFAULT_LAYOUT_FILE);
+        requested == path || Path::new(path).file_name().is_some_and(|name| name == requested)
+    }
 
-            // Map RendererMessage to applet Message
-            keyboard_with_toast.map(|msg| match msg {
-                RendererMessage::KeyPressed(id) => Message::KeyPressed(id),
-                RendererMessage::KeyReleased(id) => Message::KeyReleased(id),
-                RendererMessage::SwitchPanel(id) => Message::SwitchPanel(id),
-                RendererMessage::AnimationTick => Message::AnimationTick,
-                RendererMessage::AnimationComplete => Message::AnimationTick, // Handled in update
-                RendererMessage::LongPressTimerTick => Message::LongPressTimerTick,
-                RendererMessage::PopupDismiss => Message::KeyReleased(String::new()),
-                RendererMessage::ShowToast(msg, severity) => Message::ShowToast(msg, severity),
-                RendererMessage::DismissToast => Message::DismissToast,
-                RendererMessage::ToastTimerTick => Message::ToastTimerTick,
-                RendererMessage::Noop => Message::Toggle, // Should not happen
-            })
-        } else {
-            // No renderer available - show error message
-            container(widget::text::body("Failed to load keyboard layout"))
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .class(cosmic::style::Container::Background)
-                .into()
+    /// Shrinks the keyboard to the collapsed bar, or expands it back to its
+    /// height.
+    fn set_collapsed(&mut self, collapsed: bool) -> Task<Message> {
+        if self.collapsed == collapsed {
+            return Task::none();
         }
+        tracing::info!("Keyboard {}", if collapsed { "collapsed" } else { "expanded" });
+        self.collapsed = collapsed;
+        self.collapsed_bar_tapped = None;
+        self.height_transition = None;
+        // The key that collapsed the keyboard is never released; latched
+        // modifiers stay, and are shown on the bar
+        if let Some(renderer) = self.keyboard_renderer.as_mut().filter(|_| collapsed) {
+            renderer.pressed_keys.clear();
+            renderer.cancel_long_press();
+        }
+        self.resize_keyboard_surface(self.window_state.height)
     }
 
-    // ========================================================================
-    // Task Group 5: Key Press Event Flow Helpers
-    // ========================================================================
+    /// Sizes the keyboard for the visible panel: fitted to its rows, then
+    /// checked for undersized keys.
+    fn sync_keyboard_height(&mut self) -> Task<Message> {
+        let fit = self.fit_height_to_panel();
+        Task::batch(vec![fit, self.check_touch_targets()])
+    }
 
-    /// Finds a key by its identifier in the current panel.
-    ///
-    /// This searches through the current panel's rows and cells to find
-    /// a key with the matching identifier.
-    ///
-    /// # Arguments
-    ///
-    /// * `identifier` - The key identifier to search for
-    ///
-    /// # Returns
+    /// Returns the density of the keyboard's output, in logical pixels per
+    /// millimetre.
     ///
-    /// * `Some(&Key)` if a key with the identifier was found
-    /// * `None` if no matching key was found
-    fn find_key_by_identifier(&self, identifier: &str) -> Option<&Key> {
-        let renderer = self.keyboard_renderer.as_ref()?;
-        let panel = renderer.current_panel()?;
-
-        for row in &panel.rows {
-            for cell in &row.cells {
-                if let Cell::Key(key) = cell {
-                    if key.identifier.as_deref() == Some(identifier) {
-                        return Some(key);
-                    }
-                }
-            }
+    /// Unless the keyboard is pinned to an output, it opens on the active
+    /// one, which is not known: the densest output is assumed, where keys
+    /// are physically smallest.
+    fn keyboard_pixels_per_mm(&self) -> Option<f32> {
+        match self.target_output {
+            Some(ref target) => self
+                .output_densities
+                .iter()
+                .find(|(output, _)| output == target)
+                .map(|(_, density)| *density),
+            None => self
+                .output_densities
+                .iter()
+                .map(|(_, density)| *density)
+                .reduce(f32::max),
         }
+    }
 
-        None
+    /// Returns `true` while output announcements are needed: the keyboard
+    /// is shown or pinned to an output, or the touch target check still
+    /// waits for the density of the keyboard's output.
+    fn wants_output_events(&self) -> bool {
+        self.keyboard_visible
+            || self.wanted_output().is_some()
+            || (self.config.touch_target.is_enabled() && self.keyboard_pixels_per_mm().is_none())
     }
 
-    /// Determines if a key is a modifier key based on its KeyCode.
-    ///
-    /// # Arguments
-    ///
-    /// * `code` - The KeyCode to check
+    /// Returns the scale factor of the keyboard's output.
     ///
-    /// # Returns
-    ///
-    /// * `Some(Modifier)` if the key is a modifier
-    /// * `None` if the key is not a modifier
-    fn keycode_to_modifier(code: &KeyCode) -> Option<Modifier> {
-        match code {
-            KeyCode::Keysym(s) => {
-                let s_lower = s.to_lowercase();
-                if s_lower.contains("shift") {
-                    Some(Modifier::Shift)
-                } else if s_lower.contains("control") || s_lower.contains("ctrl") {
-                    Some(Modifier::Ctrl)
-                } else if s_lower.contains("alt") {
-                    Some(Modifier::Alt)
-                } else if s_lower.contains("super") || s_lower.contains("meta") {
-                    Some(Modifier::Super)
-                } else {
-                    None
-                }
-            }
-            KeyCode::Unicode(_) => None,
-        }
+    /// Like the density, the scale of the active output is not known when
+    /// the keyboard is not pinned: the largest scale is assumed.
+    fn keyboard_scale_factor(&self) -> f32 {
+        let scale = match self.target_output {
+            Some(ref target) => self
+                .output_scales
+                .iter()
+                .find(|(output, _)| output == target)
+                .map(|(_, scale)| *scale),
+            None => self
+                .output_scales
+                .iter()
+                .map(|(_, scale)| *scale)
+                .reduce(f32::max),
+        };
+        scale.unwrap_or_else(get_scale_factor)
     }
 
-    /// Gets the hardware keycode for a modifier.
-    ///
-    /// # Arguments
-    ///
-    /// * `modifier` - The modifier to get the keycode for
-    ///
-    /// # Returns
+    /// Follows the scale factor of the keyboard's output, which changes
+    /// when the user rescales it or the keyboard moves to another output.
     ///
-    /// The evdev keycode for the left variant of the modifier.
-    fn modifier_to_keycode(modifier: Modifier) -> u32 {
-        match modifier {
-            Modifier::Shift => keycodes::KEY_LEFTSHIFT,
-            Modifier::Ctrl => keycodes::KEY_LEFTCTRL,
-            Modifier::Alt => keycodes::KEY_LEFTALT,
-            Modifier::Super => keycodes::KEY_LEFTMETA,
+    /// Returns `true` if it changed: the keys are laid out at the new scale
+    /// from the next frame, and the key popup, placed at the old scale, is
+    /// closed. The caller resizes the keyboard for the new key sizes.
+    fn sync_scale_factor(&mut self) -> bool {
+        let scale = self.keyboard_scale_factor();
+        if scale == self.scale_factor {
+            return false;
+        }
+        tracing::info!("Scale factor changed from {} to {}", self.scale_factor, scale);
+        self.scale_factor = scale;
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.popup = None;
         }
+        true
     }
 
-    /// Handles a regular (non-modifier) key press.
+    /// Returns the dead zones of the current mode.
+    fn dead_zone_insets(&self) -> DeadZoneInsets {
+        self.config.dead_zones.for_mode(self.window_state.is_floating)
+    }
+
+    /// Returns where the panel is drawn on the keyboard surface, between the
+    /// dead zones, the resize handles of a floating keyboard and the strips
+    /// above and under the panel.
+    fn panel_area(&self) -> Rectangle {
+        let insets = self.dead_zone_insets();
+        let (width, height) = insets.inner_size(self.window_state.width, self.window_state.height);
+        // The command palette or the typing test takes a strip above the
+        // panel while open
+        let top_strip = match self.keyboard_renderer {
+            Some(ref renderer) if is_palette_open(renderer) => PALETTE_HEIGHT,
+            Some(ref renderer) if self.typing_test.is_some() && is_typing_test_active(renderer) => {
+                TYPING_TEST_HEIGHT
+            }
+            _ => 0.0,
+        };
+        // The status strip sits above both
+        let top_strip = if self.config.status_strip {
+            top_strip + STATUS_STRIP_HEIGHT
+        } else {
+            top_strip
+        };
+        // The emission indicator takes a thin strip under the panel
+        let bottom_strip = if self.config.emission_indicator {
+            EMISSION_STRIP_HEIGHT
+        } else {
+            0.0
+        };
+        let handles = if self.window_state.is_floating {
+            RESIZE_ZONE_SIZE
+        } else {
+            0.0
+        };
+        Rectangle::new(
+            insets.left as f32 + handles,
+            insets.top as f32 + handles + top_strip,
+            width,
+            (height - top_strip - bottom_strip).max(0.0),
+        )
+    }
+
+    /// Checks the keys of the visible panel against the configured minimum
+    /// touch target size.
     ///
-    /// This method:
-    /// 1. Gets active modifiers from the renderer
-    /// 2. Emits modifier key presses for active modifiers
-    /// 3. Emits the main key press
-    /// 4. Stores the pressed key for release handling
+    /// Undersized keys are reported with a toast, once per panel. With
+    /// `auto_height`, a keyboard too short for its keys is made taller
+    /// instead, up to the maximum keyboard height; the check runs again once
+    /// the surface is resized, and reports what is still too small.
+    fn check_touch_targets(&mut self) -> Task<Message> {
+        let policy = self.config.touch_target;
+        let Some(pixels_per_mm) = self
+            .keyboard_pixels_per_mm()
+            .filter(|_| policy.is_enabled())
+        else {
+            return Task::none();
+        };
+        let Some(panel) = self
+            .keyboard_renderer
+            .as_ref()
+            .and_then(|renderer| renderer.current_panel())
+        else {
+            return Task::none();
+        };
+        let panel_id = panel.id.clone();
+        // Keys are laid out between the dead zones
+        let insets = self.dead_zone_insets();
+        let (width, height) = insets.inner_size(self.window_state.width, self.window_state.height);
+        let Some(report) = touch_target::check_panel(
+            panel,
+            width,
+            height,
+            self.scale_factor,
+            pixels_per_mm,
+            policy.min_size_mm,
+        ) else {
+            self.touch_target_warned = None;
+            return Task::none();
+        };
+
+        let raised_height = report
+            .required_height
+            .map(|height| (height + (insets.top + insets.bottom) as f32).min(MAX_HEIGHT))
+            .filter(|height| policy.auto_height && *height > self.window_state.height);
+        if let Some(height) = raised_height.filter(|_| self.keyboard_surface.is_some()) {
+            tracing::info!(
+                "Keys shorter than {} mm, raising the keyboard to {} px",
+                policy.min_size_mm,
+                height
+            );
+            return self.set_keyboard_height(height);
+        }
+
+        if self.touch_target_warned.as_deref() == Some(panel_id.as_str()) {
+            return Task::none();
+        }
+        tracing::warn!(
+            "{} keys of panel '{}' are smaller than {} mm, smallest: '{}' ({:.1} mm)",
+            report.undersized,
+            panel_id,
+            policy.min_size_mm,
+            report.smallest_label,
+            report.smallest_mm
+        );
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.queue_toast(
+                fl!(
+                    "keys-too-small",
+                    count = report.undersized,
+                    min = policy.min_size_mm,
+                    size = format!("{:.1}", report.smallest_mm)
+                ),
+                ToastSeverity::Warning,
+            );
+        }
+        self.touch_target_warned = Some(panel_id);
+        Task::none()
+    }
+
+    /// Collects the panels and layouts offered by the command palette.
+    fn palette_context(&self) -> PaletteContext {
+        let mut panels: Vec<String> = self
+            .keyboard_renderer
+            .as_ref()
+            .map(|renderer| renderer.layout.panels.keys().cloned().collect())
+            .unwrap_or_default();
+        panels.sort();
+
+        PaletteContext {
+            panels,
+            layouts: self.available_layouts.clone(),
+        }
+    }
+
+    /// Returns the command palette entries for `query`.
     ///
-    /// # Arguments
+    /// Entries for actions the kiosk policy forbids are left out.
+    fn palette_entries(&self, query: &str) -> Vec<PaletteEntry> {
+        let mut entries = palette::entries(query, &self.palette_context(), &self.actions);
+        entries.retain(|entry| self.kiosk.permits(&entry.action));
+        entries
+    }
+
+    /// Dispatches a named action.
     ///
-    /// * `key` - The key definition
-    fn handle_regular_key_press(&mut self, key: &Key) {
-        if !self.virtual_keyboard.is_initialized() {
-            tracing::warn!("Virtual keyboard not initialized, cannot emit key press");
-            return;
+    /// Command keys, the D-Bus interface, the command palette and the popup
+    /// menu all end up here, so each action behaves the same regardless of
+    /// where it was triggered. An open command palette is closed first
+    /// (except by the action that toggles it). Actions the kiosk policy does
+    /// not permit are dropped.
+    fn dispatch_action(&mut self, action: AppAction) -> Task<Message> {
+        tracing::debug!("Dispatching action: {}", action);
+
+        if !self.kiosk.permits(&action) {
+            tracing::info!("Action '{}' is disabled in kiosk mode", action);
+            return Task::none();
         }
 
-        // Get active modifiers
-        let active_modifiers = if let Some(ref renderer) = self.keyboard_renderer {
-            renderer.get_active_modifiers()
-        } else {
-            Vec::new()
-        };
-
-        // Emit modifier key presses first
-        for modifier in &active_modifiers {
-            let keycode = Self::modifier_to_keycode(*modifier);
-            self.virtual_keyboard.press_key(keycode);
-            tracing::debug!("Emitted modifier press: {:?} (keycode {})", modifier, keycode);
+        if action != AppAction::TogglePalette {
+            if let Some(ref mut renderer) = self.keyboard_renderer {
+                if renderer.focus.is_focused(PALETTE_FIELD) {
+                    renderer.focus.blur();
+                }
+            }
         }
 
-        // Resolve and emit the main key
-        if let Some(resolved) = parse_keycode(&key.code) {
-            match &resolved {
-                ResolvedKeycode::Character(_) | ResolvedKeycode::Keysym(_) => {
-                    if let Some(keycode) = self.virtual_keyboard.resolve_keycode(&resolved) {
-                        self.virtual_keyboard.press_key(keycode);
-                        tracing::debug!("Emitted key press: {:?} (keycode {})", resolved, keycode);
+        match action {
+            AppAction::Show => self.update(Message::Show),
+            AppAction::Hide => self.update(Message::Hide),
+            AppAction::Toggle => self.update(Message::Toggle),
+            AppAction::Quit => self.update(Message::Quit),
+            AppAction::ToggleFloatingMode => self.update(Message::ToggleFloatingMode),
+            AppAction::FitHeight => {
+                self.window_state.height_set_by_user = false;
+                self.save_state();
+                self.sync_keyboard_height()
+            }
+            AppAction::ToggleCollapsed => self.set_collapsed(!self.collapsed),
+            AppAction::TogglePalette => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    if renderer.focus.is_focused(PALETTE_FIELD) {
+                        renderer.focus.blur();
                     } else {
-                        // Fallback for Unicode characters
-                        if let ResolvedKeycode::Character(c) = resolved {
-                            tracing::debug!("Key not found in keymap, using Unicode fallback for '{}'", c);
-                            self.virtual_keyboard.emit_unicode_codepoint(c as u32);
-                        } else {
-                            tracing::warn!("Could not resolve keycode for: {:?}", resolved);
-                        }
+                        renderer.focus.focus(PALETTE_FIELD);
                     }
                 }
-                ResolvedKeycode::UnicodeCodepoint(codepoint) => {
-                    self.virtual_keyboard.emit_unicode_codepoint(*codepoint);
-                    tracing::debug!("Emitted Unicode codepoint: U+{:04X}", codepoint);
+                Task::none()
+            }
+            AppAction::ToggleTypingTest => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    if renderer.focus.is_focused(TYPING_TEST_FIELD) {
+                        renderer.focus.blur();
+                        self.typing_test = None;
+                    } else {
+                        // Keys typed into the focused field are checked
+                        // locally instead of reaching the client
+                        renderer.focus.focus(TYPING_TEST_FIELD);
+                        self.typing_test = Some(TypingTest::new(0));
+                    }
                 }
+                Task::none()
+            }
+            AppAction::SwitchPanel(panel_id) => self.update(Message::SwitchPanel(panel_id)),
+            AppAction::SetProfile(name) => self.switch_profile(&name),
+            AppAction::ExportConfig(path) => self.export_config(path),
+            AppAction::ImportConfig(path) => self.import_config(Some(path)),
+            AppAction::ReloadConfig => self.import_config(None),
+            AppAction::SwitchLayout(path) => {
+                tracing::info!("Switching layout to {}", path);
+                // Remembered, so the layout is loaded again after a restart
+                self.config.layout = Some(path.clone());
+                self.save_config();
+                self.active_layout_path = Some(path);
+                self.load_keyboard_layout()
+            }
+            AppAction::ReloadLayout => {
+                tracing::info!("Reloading layout, dropping {} cached", self.layout_cache.len());
+                self.layout_cache.invalidate();
+                if self.keyboard_visible {
+                    self.load_keyboard_layout()
+                } else {
+                    Task::none()
+                }
+            }
+            AppAction::InsertText(text) => self.update(Message::InsertText(text)),
+            AppAction::TypeText(text) => {
+                self.controller.emit_text(&text);
+                // The cursor may have moved with the typed text
+                self.snippet_expander.reset();
+                self.reset_typing();
+                self.sync_prediction_row();
+                Task::none()
+            }
+            AppAction::SendKey(combo) => {
+                self.controller.send_key(&combo);
+                self.snippet_expander.reset();
+                self.reset_typing();
+                self.sync_prediction_row();
+                Task::none()
+            }
+            AppAction::SendPrefix => {
+                let combo = crate::actions::prefix_combo(&self.config.terminal_prefix);
+                self.controller.send_key(&combo);
+                self.snippet_expander.reset();
+                self.reset_typing();
+                self.sync_prediction_row();
+                Task::none()
+            }
+            AppAction::SetKeyboardInteractivity(interactivity) => {
+                self.update(Message::SetKeyboardInteractivity(interactivity))
+            }
+            AppAction::ToggleKeyboardInteractivity => {
+                let interactivity = match self.config.keyboard_interactivity {
+                    Interactivity::None => Interactivity::OnDemand,
+                    Interactivity::OnDemand => Interactivity::None,
+                };
+                self.update(Message::SetKeyboardInteractivity(interactivity))
+            }
+            AppAction::SetLayer(layer) => self.update(Message::SetLayer(layer)),
+            AppAction::ToggleLayer => {
+                let layer = match self.surface_layer() {
+                    crate::layer_shell::Layer::Overlay => crate::layer_shell::Layer::Top,
+                    _ => crate::layer_shell::Layer::Overlay,
+                };
+                self.update(Message::SetLayer(layer))
+            }
+            AppAction::CommitPrediction(index) => {
+                self.commit_prediction(index);
+                Task::none()
+            }
+            AppAction::PageCandidates(delta) => {
+                if let Some(ref mut engine) = self.ime {
+                    engine.turn_page(delta);
+                }
+                self.sync_prediction_row();
+                Task::none()
+            }
+            AppAction::CommitPreedit => {
+                if let Some(ref mut engine) = self.ime {
+                    let output = engine.commit_preedit();
+                    self.apply_ime_output(output);
+                }
+                Task::none()
+            }
+            AppAction::TogglePrediction => {
+                self.config.prediction = !self.config.prediction;
+                tracing::info!(
+                    "Word prediction {}",
+                    if self.config.prediction { "on" } else { "off" }
+                );
+                self.reset_typing();
+                if !self.config.prediction {
+                    self.unload_dictionaries();
+                }
+                self.sync_prediction_row();
+                self.save_config();
+                self.load_dictionaries()
+            }
+            AppAction::SetFocusedApp(app) => {
+                tracing::debug!("Focused application: {}", app);
+                self.focused_app = Some(app).filter(|app| !app.is_empty());
+                // Typed text belongs to the previous window
+                self.snippet_expander.reset();
+                self.reset_typing();
+                self.sync_prediction_row();
+                Task::none()
+            }
+            AppAction::TogglePrivateMode => {
+                self.private_mode = !self.private_mode;
+                tracing::info!(
+                    "Private mode {}",
+                    if self.private_mode { "on, words are not learned" } else { "off" }
+                );
+                // Key events are not journaled either
+                if let Ok(mut journal) = self.controller.virtual_keyboard.journal().lock() {
+                    journal.set_enabled(!self.private_mode);
+                }
+                // The word typed so far may be part of a secret
+                self.reset_typing();
+                self.sync_prediction_row();
+                Task::none()
+            }
+            AppAction::ScanCode => self.scan_code(None),
+            AppAction::ScanImage(path) => self.scan_code(Some(path)),
+            AppAction::CalcInput(text) => {
+                if let Some(renderer) = self.keyboard_renderer.as_mut() {
+                    renderer.calculator.input(&text);
+                }
+                Task::none()
+            }
+            AppAction::CalcBackspace => {
+                if let Some(renderer) = self.keyboard_renderer.as_mut() {
+                    renderer.calculator.backspace();
+                }
+                Task::none()
+            }
+            AppAction::CalcClear => {
+                if let Some(renderer) = self.keyboard_renderer.as_mut() {
+                    renderer.calculator.clear();
+                }
+                Task::none()
+            }
+            AppAction::CalcEquals => {
+                let Some(renderer) = self.keyboard_renderer.as_mut() else {
+                    return Task::none();
+                };
+                match renderer.calculator.equals() {
+                    Ok(result) => {
+                        self.controller.emit_text(&result);
+                        self.reset_typing();
+                        self.sync_prediction_row();
+                    }
+                    Err(e) => {
+                        tracing::debug!("Calculator: {}", e);
+                        renderer.queue_toast(e.localized(), ToastSeverity::Warning);
+                    }
+                }
+                Task::none()
+            }
+            AppAction::BrailleDot(dot) => {
+                // Without a key to release, the dot is a cell of its own
+                self.braille_chord.reset();
+                self.braille_chord.press(dot);
+                if let Some(cell) = self.braille_chord.release(dot) {
+                    self.type_braille_cell(cell);
+                }
+                Task::none()
             }
-        } else {
-            tracing::warn!("Could not parse keycode: {:?}", key.code);
         }
     }
 
-    /// Handles a regular (non-modifier) key release.
-    ///
-    /// This method:
-    /// 1. Emits the main key release
-    /// 2. Emits modifier key releases for any active modifiers
-    /// 3. Clears one-shot modifiers from the renderer state
-    ///
-    /// # Arguments
-    ///
-    /// * `key` - The key definition
-    fn handle_regular_key_release(&mut self, key: &Key) {
-        if !self.virtual_keyboard.is_initialized() {
-            return;
+    /// Returns the braille dot a key presses, if it is a dot key of the
+    /// braille panel.
+    fn braille_dot(&self, identifier: &str) -> Option<u8> {
+        let key = self.find_key_by_identifier(identifier)?;
+        match self.actions.parse(parse_command(&key.code)?) {
+            Ok(AppAction::BrailleDot(dot)) => Some(dot),
+            _ => None,
         }
+    }
 
-        // Get active modifiers before clearing
-        let active_modifiers = if let Some(ref renderer) = self.keyboard_renderer {
-            renderer.get_active_modifiers()
-        } else {
-            Vec::new()
-        };
+    /// Types a chorded braille cell and plays the sound confirming it.
+    fn type_braille_cell(&mut self, cell: BrailleCell) {
+        let translation = self.braille.translate(cell);
+        tracing::debug!("Braille cell {} -> {:?}", cell.to_char(), translation);
+        self.feedback.play(&translation.feedback());
+        if let Translation::Text(text) = translation {
+            self.controller.emit_text(&text);
+            // Prediction and text shortcuts see the cell as typed
+            for c in text.chars() {
+                self.track_typed_key(&ResolvedKeycode::Character(c), &[]);
+            }
+        }
+    }
 
-        // Emit the main key release
-        if let Some(resolved) = parse_keycode(&key.code) {
-            match &resolved {
-                ResolvedKeycode::Character(_) | ResolvedKeycode::Keysym(_) => {
-                    if let Some(keycode) = self.virtual_keyboard.resolve_keycode(&resolved) {
-                        self.virtual_keyboard.release_key(keycode);
-                        tracing::debug!("Emitted key release: {:?} (keycode {})", resolved, keycode);
-                    }
-                }
-                ResolvedKeycode::UnicodeCodepoint(_) => {
-                    // Unicode codepoint emission handles press+release in emit_unicode_codepoint
+    /// Returns `true` if the key code types a space.
+    fn is_space_key(code: &KeyCode) -> bool {
+        match code {
+            KeyCode::Unicode(c) => *c == ' ',
+            KeyCode::Keysym(s) => s == "space",
+        }
+    }
+
+    /// Returns `true` if touch gestures need raw touch events.
+    fn wants_touch_gestures(&self) -> bool {
+        let bindings = &self.config.gesture_bindings;
+        self.keyboard_visible
+            && (bindings.is_bound(Gesture::TwoFingerTap) || bindings.is_bound(Gesture::SwipeDown))
+    }
+
+    /// Runs a command key (`"cosboard:<action>"` keysym).
+    fn run_command(&mut self, command: &str) -> Task<Message> {
+        match self.actions.parse(command) {
+            Ok(action) => self.dispatch_action(action),
+            Err(e) => {
+                tracing::warn!("Invalid command key '{}': {}", command, e);
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.queue_toast(e.localized(), ToastSeverity::Warning);
                 }
+                Task::none()
             }
         }
+    }
 
-        // Emit modifier key releases
-        for modifier in &active_modifiers {
-            let keycode = Self::modifier_to_keycode(*modifier);
-            self.virtual_keyboard.release_key(keycode);
-            tracing::debug!("Emitted modifier release: {:?} (keycode {})", modifier, keycode);
+    /// Render the keyboard content using the renderer (Task 7.3).
+    fn render_keyboard_content(&self) -> Element<'_, Message> {
+        let surface_width = self.window_state.width;
+        let surface_height = self.window_state.height;
+        let scale = self.scale_factor;
+
+        // Collapsed: only the bar, the panel is not built. The bar is shown
+        // while no layout is loaded too, so the keyboard can be expanded.
+        if self.collapsed {
+            let status = self.keyboard_renderer.as_ref().map_or_else(String::new, |renderer| {
+                collapsed_status(&renderer.layout.name, &renderer.shown_modifiers())
+            });
+            return render_collapsed_bar(status).map(|msg| match msg {
+                RendererMessage::RunAction(action) => Message::Action(action),
+                _ => Message::CollapsedBarTapped,
+            });
         }
 
-        // Clear one-shot modifiers from the renderer
-        if let Some(ref mut renderer) = self.keyboard_renderer {
-            renderer.clear_oneshot_modifiers();
+        // Keys are laid out between the dead zones, which are tinted while
+        // the settings menu is open
+        let insets = self.dead_zone_insets();
+        let (surface_width, _) = insets.inner_size(surface_width, surface_height);
+
+        if let Some(ref renderer) = self.keyboard_renderer {
+            // The command palette or the typing test takes a strip above the
+            // panel while open
+            let palette_open = is_palette_open(renderer);
+            let typing_test = self
+                .typing_test
+                .as_ref()
+                .filter(|_| is_typing_test_active(renderer));
+            let panel_height = self.panel_area().height;
+
+            // Render the keyboard panel using the renderer. Broken state
+            // (repaired in update) draws an error panel instead of panicking,
+            // and so does any panic while the widgets are built
+            let panel_element = if renderer.current_panel().is_none() {
+                render_error_panel(fl!(
+                    "panel-not-found",
+                    panel = renderer.current_panel_id.clone()
+                ))
+            } else if !surface_width.is_finite() || surface_width <= 0.0 || !scale.is_finite() {
+                render_error_panel(fl!(
+                    "invalid-keyboard-size",
+                    width = surface_width.to_string(),
+                    height = panel_height.to_string(),
+                    scale = scale.to_string()
+                ))
+            } else {
+                render_guarded(|| {
+                    render_animated_panels(renderer, surface_width, panel_height, scale)
+                })
+            };
+
+            // The alternatives of a long-pressed key, over the panel
+            let popup = renderer.popup.as_ref().and_then(|popup| {
+                self.find_key_by_identifier(&popup.key).map(|key| (popup, key))
+            });
+            let panel_element = match popup {
+                Some((popup, key)) => render_popup_overlay(panel_element, key, popup, scale),
+                None => panel_element,
+            };
+
+            // The live theme, so toasts follow the light/dark switch
+            let theme = cosmic::theme::active();
+
+            // Render toast if any
+            let toast_element = render_current_toast(renderer, &theme);
+
+            // Combine panel with toast area
+            let keyboard_with_toast = render_keyboard_with_toast(panel_element, toast_element, panel_height);
+
+            // Add the emission indicator under the panel
+            let keyboard_with_toast = if self.config.emission_indicator {
+                let intensity = pulse_intensity(self.controller.virtual_keyboard.last_emission(), Instant::now());
+                widget::column::column()
+                    .push(keyboard_with_toast)
+                    .push(render_emission_strip(intensity))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into()
+            } else {
+                keyboard_with_toast
+            };
+
+            // Add the palette or typing test strip on top
+            let strip_element = if palette_open {
+                let entries = self.palette_entries(renderer.focus.buffer(PALETTE_FIELD));
+                Some(render_palette(renderer, &entries))
+            } else {
+                typing_test.map(|test| render_typing_test(renderer, test, Instant::now()))
+            };
+            let keyboard_with_toast = render_keyboard_with_palette(keyboard_with_toast, strip_element);
+
+            // Add the status strip on top of everything
+            let keyboard_with_toast = if self.config.status_strip {
+                widget::column::column()
+                    .push(render_status_strip(renderer))
+                    .push(container(keyboard_with_toast).width(Length::Fill).height(Length::Fill))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into()
+            } else {
+                keyboard_with_toast
+            };
+
+            let keyboard_with_toast =
+                render_dead_zones(keyboard_with_toast, insets, self.popup.is_some());
+
+            // Map RendererMessage to applet Message
+            keyboard_with_toast.map(|msg| match msg {
+                RendererMessage::KeyPressed(id) => Message::KeyPressed(id),
+                RendererMessage::KeyReleased(id) => Message::KeyReleased(id),
+                RendererMessage::KeyEntered(id) => Message::KeyEntered(id),
+                RendererMessage::KeyExited(id) => Message::KeyExited(id),
+                RendererMessage::TextDragged(id) => Message::TextDragged(id),
+                RendererMessage::SwitchPanel(id) => Message::SwitchPanel(id),
+                RendererMessage::AnimationTick => Message::AnimationTick,
+                RendererMessage::AnimationComplete => Message::AnimationTick, // Handled in update
+                RendererMessage::LongPressTimerTick => Message::LongPressTimerTick,
+                RendererMessage::StuckKeyTimerTick => Message::StuckKeyTimerTick,
+                RendererMessage::PopupDismiss => Message::KeyReleased(String::new()),
+                RendererMessage::ShowToast(msg, severity) => Message::ShowToast(msg, severity),
+                RendererMessage::DismissToast => Message::DismissToast,
+                RendererMessage::ToastTimerTick => Message::ToastTimerTick,
+                RendererMessage::ToastAction(id) => Message::ToastAction(id),
+                RendererMessage::FocusTextField(id) => Message::FocusTextField(id),
+                RendererMessage::BlurTextField => Message::BlurTextField,
+                RendererMessage::InsertText(glyph) => Message::InsertSymbol(glyph),
+                RendererMessage::ToggleFavoriteEmoji(glyph) => Message::ToggleFavoriteEmoji(glyph),
+                RendererMessage::SelectSymbolCategory(category) => {
+                    Message::SelectSymbolCategory(category)
+                }
+                RendererMessage::Scrolled(id, offset) => Message::Scrolled(id, offset),
+                RendererMessage::StepDate(field, delta) => Message::StepDate(field, delta),
+                RendererMessage::ResetDate => Message::ResetDate,
+                RendererMessage::InsertDate(insert) => Message::InsertDate(insert),
+                RendererMessage::RunAction(action) => Message::Action(action),
+                RendererMessage::CollapsedBarTapped => Message::CollapsedBarTapped,
+                RendererMessage::ClearModifier(modifier) => Message::ClearModifier(modifier),
+                RendererMessage::Noop => Message::Toggle, // Should not happen
+            })
+        } else if self.layout_loading {
+            // Layout still loading in the background
+            container(widget::text::body(fl!("loading-layout")))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center)
+                .class(cosmic::style::Container::Background)
+                .into()
+        } else {
+            // No renderer available - show error message
+            container(widget::text::body(fl!("layout-load-failed")))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .class(cosmic::style::Container::Background)
+                .into()
         }
     }
 
-    /// Handles a modifier key press.
+    // ========================================================================
+    // Task Group 5: Key Press Event Flow Helpers
+    // ========================================================================
+
+    /// Finds a key by its identifier in the current panel.
     ///
-    /// This method activates the modifier in the renderer's modifier state
-    /// based on the key's sticky and stickyrelease fields.
+    /// This searches through the current panel's rows and cells to find
+    /// a key with the matching identifier.
     ///
     /// # Arguments
     ///
-    /// * `key` - The key definition
-    /// * `modifier` - The modifier type
-    fn handle_modifier_key_press(&mut self, key: &Key, modifier: Modifier) {
-        if let Some(ref mut renderer) = self.keyboard_renderer {
-            if key.sticky {
-                // Sticky key: toggle behavior for toggle mode, activate for one-shot
-                if key.stickyrelease {
-                    // One-shot: activate and mark as sticky
-                    renderer.activate_modifier(modifier, true);
-                    if let Some(ref id) = key.identifier {
-                        renderer.sync_modifier_visual_state(modifier, id);
-                    }
-                    tracing::debug!("Activated one-shot modifier: {:?}", modifier);
-                } else {
-                    // Toggle mode: toggle the modifier state
-                    if renderer.is_modifier_active(modifier) {
-                        renderer.deactivate_modifier(modifier);
-                        if let Some(ref id) = key.identifier {
-                            renderer.sticky_keys_active.remove(id);
-                        }
-                        tracing::debug!("Deactivated toggle modifier: {:?}", modifier);
-                    } else {
-                        renderer.activate_modifier(modifier, false);
-                        if let Some(ref id) = key.identifier {
-                            renderer.sync_modifier_visual_state(modifier, id);
-                        }
-                        tracing::debug!("Activated toggle modifier: {:?}", modifier);
+    /// * `identifier` - The key identifier to search for
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&Key)` if a key with the identifier was found
+    /// * `None` if no matching key was found
+    fn find_key_by_identifier(&self, identifier: &str) -> Option<&Key> {
+        let renderer = self.keyboard_renderer.as_ref()?;
+        let panel = renderer.current_panel()?;
+
+        for row in &panel.rows {
+            for cell in &row.cells {
+                if let Cell::Key(key) = cell {
+                    if key.identifier.as_deref() == Some(identifier) {
+                        return Some(key);
                     }
                 }
-            } else {
-                // Hold mode: activate while held (will deactivate on release)
-                renderer.activate_modifier(modifier, false);
-                if let Some(ref id) = key.identifier {
-                    renderer.sync_modifier_visual_state(modifier, id);
-                }
-                tracing::debug!("Activated hold modifier: {:?}", modifier);
             }
         }
+
+        None
     }
 
-    /// Handles a modifier key release.
-    ///
-    /// For hold mode modifiers, this deactivates the modifier.
-    /// For sticky modifiers, release is handled in `clear_oneshot_modifiers`.
-    ///
-    /// # Arguments
+    /// Records an inserted emoji/symbol in the recents and persists it.
+    fn record_emoji_use(&mut self, glyph: &str) {
+        self.config.emoji_recents.record(glyph);
+        self.sync_emoji_recents();
+        self.save_config();
+    }
+
+    /// Mirrors the configured emoji recents into the renderer.
+    fn sync_emoji_recents(&mut self) {
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.emoji_recents = self.config.emoji_recents.clone();
+        }
+    }
+
+    /// Releases all input and returns a task destroying the applet's surfaces.
     ///
-    /// * `key` - The key definition
-    /// * `modifier` - The modifier type
-    fn handle_modifier_key_release(&mut self, key: &Key, modifier: Modifier) {
+    /// Keys still held down are released and latched modifiers cleared, so
+    /// nothing (e.g. Ctrl) stays stuck in client applications after the
+    /// applet exits. Calling it again does nothing.
+    fn shutdown(&mut self) -> Task<Message> {
         if let Some(ref mut renderer) = self.keyboard_renderer {
-            if !key.sticky {
-                // Hold mode: deactivate on release
-                renderer.deactivate_modifier(modifier);
-                if let Some(ref id) = key.identifier {
-                    renderer.sticky_keys_active.remove(id);
-                }
-                tracing::debug!("Released hold modifier: {:?}", modifier);
+            // Caps Lock is not left locked in client applications
+            if renderer.is_caps_lock_active() {
+                self.controller.toggle_caps_lock();
             }
-            // For sticky modifiers, the state persists until cleared by clear_oneshot_modifiers
-            // or toggled off by another press
+            renderer.reset_input_state();
         }
+        self.deferred_space = None;
+        self.deferred_candidate = None;
+        self.deferred_popup_key = None;
+        self.palm_held_key = None;
+        self.palm_rejected_key = None;
+        self.layer_codes.clear();
+        self.boundaries.end();
+        self.controller.shutdown();
+        self.keyboard_visible = false;
+
+        let surfaces = [
+            self.keyboard_surface.take(),
+            self.preview_surface.take(),
+            self.edge_strip.take(),
+        ];
+        Task::batch(surfaces.into_iter().flatten().map(|id| {
+            tracing::info!("Destroying layer surface: {:?}", id);
+            destroy_layer_surface(id)
+        }))
     }
-}
 
-impl cosmic::Application for AppletModel {
-    /// The async executor for running application tasks.
-    type Executor = cosmic::SingleThreadExecutor;
+    /// Appends a message to the session trace, if recording is enabled.
+    ///
+    /// Recording stops if the trace file cannot be written.
+    fn record_message(&mut self, message: &Message) {
+        let Some(ref mut recorder) = self.recorder else {
+            return;
+        };
+        let Some(event) = recording::recorded_event(message) else {
+            return;
+        };
+        if let Err(e) = recorder.record(event) {
+            tracing::warn!(
+                "Stopped recording session to {}: {}",
+                recorder.path().display(),
+                e
+            );
+            self.recorder = None;
+        }
+    }
 
-    /// Data that the application receives at initialization.
-    type Flags = ();
+    /// Moves the drag or resize preview to follow the pointer.
+    fn handle_cursor_moved(&mut self, motion: CursorMotion) -> Task<Message> {
+        let pos = motion.last;
+        // Early return if not in any active drag/resize mode
+        // (This is defensive - subscription() should only send these when active)
+        if !self.is_dragging && self.resize_edge.is_none() {
+            self.last_cursor_position = Some(pos);
+            return Task::none();
+        }
 
-    /// Messages which the application and its widgets will emit.
-    type Message = Message;
+        // Use incremental delta from last position to prevent jumps when cursor
+        // leaves and re-enters the window (layer surfaces don't have pointer grab)
+        //
+        // PREVIEW APPROACH: Update the preview surface only (not keyboard surface).
+        // The keyboard stays unchanged during drag/resize for smooth performance.
+        // Final values are applied to keyboard when operation ends.
+
+        // Handle dragging with incremental updates
+        if self.is_dragging {
+            if let Some(last_pos) = self.last_cursor_position {
+                // Incremental delta from last position, each step clamped to
+                // prevent jumps when the cursor re-enters after leaving the
+                // window (inverted for bottom-right anchor)
+                let delta = motion.since(last_pos);
+                let dx = -delta.x as i32;
+                let dy = -delta.y as i32;
+
+                // Apply to PENDING margins
+                self.pending_margin_right = (self.pending_margin_right + dx).max(0);
+                self.pending_margin_bottom = (self.pending_margin_bottom + dy).max(0);
+
+                // DEDUPLICATION: Only send commands if margin values actually changed
+                let margin_changed = self.pending_margin_right != self.last_preview_margin_right
+                    || self.pending_margin_bottom != self.last_preview_margin_bottom;
+
+                // TIME DEBOUNCE: Only update at most once per 100ms
+                let now = Instant::now();
+                let time_ok = self.last_preview_update
+                    .map(|last| now.duration_since(last).as_millis() >= PREVIEW_UPDATE_INTERVAL_MS)
+                    .unwrap_or(true);
+
+                if margin_changed && time_ok {
+                    if let Some(preview_id) = self.preview_surface {
+                        self.last_preview_margin_right = self.pending_margin_right;
+                        self.last_preview_margin_bottom = self.pending_margin_bottom;
+                        self.last_preview_update = Some(now);
+                        self.last_cursor_position = Some(pos);
+                        return set_margin(preview_id, 0, self.pending_margin_right, self.pending_margin_bottom, 0);
+                    }
+                }
+            }
+        }
 
-    /// Unique identifier in RDNN format.
-    const APP_ID: &'static str = APPLET_ID;
+        // Handle resizing with incremental updates
+        if let Some(edge) = self.resize_edge {
+            if let Some(last_pos) = self.last_cursor_position {
+                // Incremental change, each step clamped to prevent jumps
+                let delta = motion.since(last_pos);
+                let (dx, dy) = (delta.x, delta.y);
+
+                // Work with PENDING values
+                let mut new_width = self.pending_width;
+                let mut new_height = self.pending_height;
+                let mut new_right = self.pending_margin_right;
+                let mut new_bottom = self.pending_margin_bottom;
+
+                match edge {
+                    ResizeEdge::Left => {
+                        // Dragging left edge: decrease dx = increase width, increase right margin
+                        new_width = (new_width - dx).clamp(MIN_WIDTH, MAX_WIDTH);
+                        new_right = (new_right + dx as i32).max(0);
+                    }
+                    ResizeEdge::Top => {
+                        // Dragging top edge: decrease dy = increase height, increase bottom margin
+                        new_height = (new_height - dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
+                        new_bottom = (new_bottom + dy as i32).max(0);
+                    }
+                    ResizeEdge::TopLeft => {
+                        // Dragging top-left corner
+                        new_width = (new_width - dx).clamp(MIN_WIDTH, MAX_WIDTH);
+                        new_height = (new_height - dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
+                        new_right = (new_right + dx as i32).max(0);
+                        new_bottom = (new_bottom + dy as i32).max(0);
+                    }
+                    ResizeEdge::TopRight => {
+                        // Dragging top-right corner: width increases with dx
+                        new_width = (new_width + dx).clamp(MIN_WIDTH, MAX_WIDTH);
+                        new_height = (new_height - dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
+                        new_bottom = (new_bottom + dy as i32).max(0);
+                    }
+                    ResizeEdge::BottomLeft => {
+                        // Dragging bottom-left corner
+                        new_width = (new_width - dx).clamp(MIN_WIDTH, MAX_WIDTH);
+                        new_height = (new_height + dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
+                        new_right = (new_right + dx as i32).max(0);
+                    }
+                    ResizeEdge::BottomRight => {
+                        // Dragging bottom-right corner (anchor point)
+                        // Both dimensions increase with positive delta, no margin changes
+                        new_width = (new_width + dx).clamp(MIN_WIDTH, MAX_WIDTH);
+                        new_height = (new_height + dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
+                    }
+                }
 
-    fn core(&self) -> &Core {
-        &self.core
-    }
+                // Update PENDING values
+                self.pending_width = new_width;
+                self.pending_height = new_height;
+                self.pending_margin_right = new_right;
+                self.pending_margin_bottom = new_bottom;
+
+                // Convert to integer values for deduplication check
+                let new_w = new_width as u32;
+                let new_h = new_height as u32;
+
+                // DEDUPLICATION: Only send commands if values actually changed
+                // This eliminates redundant compositor round-trips
+                let changed = new_w != self.last_preview_width
+                    || new_h != self.last_preview_height
+                    || new_right != self.last_preview_margin_right
+                    || new_bottom != self.last_preview_margin_bottom;
+
+                // TIME DEBOUNCE: Only update at most once per 100ms
+                let now = Instant::now();
+                let time_ok = self.last_preview_update
+                    .map(|last| now.duration_since(last).as_millis() >= PREVIEW_UPDATE_INTERVAL_MS)
+                    .unwrap_or(true);
+
+                if changed && time_ok {
+                    if let Some(preview_id) = self.preview_surface {
+                        self.last_preview_width = new_w;
+                        self.last_preview_height = new_h;
+                        self.last_preview_margin_right = new_right;
+                        self.last_preview_margin_bottom = new_bottom;
+                        self.last_preview_update = Some(now);
+                        self.last_cursor_position = Some(pos);
+                        return Task::batch([
+                            set_size(preview_id, Some(new_w), Some(new_h)),
+                            set_margin(preview_id, 0, new_right, new_bottom, 0),
+                        ]);
+                    }
+                }
+            }
+        }
 
-    fn core_mut(&mut self) -> &mut Core {
-        &mut self.core
+        self.last_cursor_position = Some(pos);
+        Task::none()
     }
 
-    /// Initialize the applet and load persisted window state.
-    fn init(core: Core, _flags: Self::Flags) -> (Self, Task<Self::Message>) {
-        // DIAGNOSTIC: Skip config loading to test if it's causing the delay
-        // TODO: Re-enable once we identify the performance issue
-        let window_state = WindowState::default();
+    /// Applies the updates queued for the next frame.
+    fn apply_frame_queue(&mut self) -> Task<Message> {
+        let updates = self.frame_queue.take();
+        if updates.predictions {
+            self.sync_prediction_row();
+        }
+        updates
+            .cursor
+            .map_or_else(Task::none, |motion| self.handle_cursor_moved(motion))
+    }
 
-        let applet = AppletModel {
-            core,
-            popup: None,
-            keyboard_surface: None,
-            keyboard_visible: false,
-            pending_width: window_state.width,
-            pending_height: window_state.height,
-            pending_margin_right: window_state.margin_right,
-            pending_margin_bottom: window_state.margin_bottom,
-            window_state,
-            state_config: None, // No config = no D-Bus operations
-            is_dragging: false,
-            resize_edge: None,
-            last_cursor_position: None,
-            preview_surface: None,
-            last_preview_width: 0,
-            last_preview_height: 0,
-            last_preview_margin_right: 0,
-            last_preview_margin_bottom: 0,
-            last_preview_update: None,
-            keyboard_renderer: None,
-            virtual_keyboard: VirtualKeyboard::new(),
-        };
-        (applet, Task::none())
+    /// Mirrors the debug overlay switch (configuration or environment) into
+    /// the renderer.
+    fn sync_debug_overlay(&mut self) {
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.debug_overlay = self.config.debug_overlay || debug_overlay_from_env();
+        }
     }
 
-    /// Subscribe to events only when actively dragging or resizing (Task 7.5).
+    /// Shows the prediction candidates in a row above the current panel.
     ///
-    /// Performance critical: Return `Subscription::none()` when idle to avoid
-    /// processing all window events in the system. This is the key difference
-    /// between responsive and laggy applets - the libcosmic example applet has
-    /// no subscription at all when idle.
-    fn subscription(&self) -> cosmic::iced_futures::Subscription<Self::Message> {
-        use cosmic::iced_futures::Subscription;
-
-        let mut subscriptions: Vec<Subscription<Message>> = Vec::new();
-
-        // Subscription for drag/resize mouse events
-        if self.is_dragging || self.resize_edge.is_some() {
-            subscriptions.push(event::listen_with(|event, _, _id| match event {
-                Event::Mouse(mouse_event) => match mouse_event {
-                    mouse::Event::CursorMoved { position } => Some(Message::CursorMoved(position)),
-                    mouse::Event::ButtonReleased(mouse::Button::Left) => {
-                        // End drag/resize on mouse release
-                        Some(Message::DragEnd)
-                    }
-                    _ => None,
-                },
-                _ => None,
-            }));
+    /// The row is composed into the renderer's copy of the layout and
+    /// follows the current panel; it is removed when prediction is off.
+    fn sync_prediction_row(&mut self) {
+        let Some(renderer) = self.keyboard_renderer.as_mut() else {
+            return;
+        };
+        let target = (self.config.prediction || self.ime.is_some())
+            .then(|| renderer.current_panel_id.clone());
+
+        // Take the row off a panel that is no longer shown
+        if let Some(panel_id) = self.prediction_panel.take() {
+            if Some(&panel_id) == target.as_ref() {
+                self.prediction_panel = Some(panel_id);
+            } else if renderer
+                .get_panel(&panel_id)
+                .and_then(|panel| panel.rows.first())
+                .is_some_and(prediction::is_candidate_row)
+            {
+                let _ = renderer.remove_row(&panel_id, 0);
+            }
         }
 
-        // Renderer subscriptions (Task 7.5)
-        if let Some(ref renderer) = self.keyboard_renderer {
-            // Animation subscription - emit ticks during panel transitions
-            if renderer.is_animating() {
-                subscriptions.push(
-                    time::every(Duration::from_millis(ANIMATION_FRAME_INTERVAL_MS))
-                        .map(|_| Message::AnimationTick),
-                );
+        let Some(panel_id) = target else {
+            return;
+        };
+        let row_width = renderer.get_panel(&panel_id).map_or(0.0, |panel| {
+            panel
+                .rows
+                .iter()
+                .map(calculate_row_width)
+                .fold(0.0, f32::max)
+        });
+        // The input method's row while it composes text, else the predictions
+        let row = match self.ime {
+            Some(ref engine) if !engine.preedit().is_empty() || !engine.candidates().is_empty() => {
+                ime::candidate_row(engine.as_ref(), row_width)
             }
+            _ => prediction::candidate_row(self.predictor.candidates(), row_width),
+        };
+        let result = if self.prediction_panel.is_some() {
+            renderer.replace_row(&panel_id, 0, row).map(drop)
+        } else {
+            renderer.insert_row(&panel_id, 0, row)
+        };
+        match result {
+            Ok(()) => self.prediction_panel = Some(panel_id),
+            Err(e) => tracing::warn!("Cannot show prediction row: {}", e),
+        }
+    }
 
-            // Long press timer subscription
-            if renderer.has_pending_long_press() {
-                subscriptions.push(
-                    time::every(Duration::from_millis(LONG_PRESS_TIMER_INTERVAL_MS))
-                        .map(|_| Message::LongPressTimerTick),
+    /// Feeds a typed key to the snippet expander, the double space tracker
+    /// and the word predictor.
+    fn track_typed_key(&mut self, resolved: &ResolvedKeycode, modifiers: &[Modifier]) {
+        // Shortcuts do not type text
+        let input = ImeInput::from_key(resolved, modifiers);
+
+        // Two quick spaces end the sentence, with the full stop of the
+        // layout's language
+        let period = match input {
+            ImeInput::Char(c) => {
+                let end = sentence_end(
+                    self.keyboard_renderer
+                        .as_ref()
+                        .and_then(|renderer| renderer.layout.language.as_deref()),
                 );
+                let settings = self.config.double_space;
+                self.double_space
+                    .type_char(c, Instant::now(), &settings, end)
             }
+            _ => {
+                self.double_space.reset();
+                None
+            }
+        };
 
-            // Toast timer subscription
-            if renderer.has_active_toast() {
-                subscriptions.push(
-                    time::every(Duration::from_millis(TOAST_TIMER_INTERVAL_MS))
-                        .map(|_| Message::ToastTimerTick),
-                );
+        // Text shortcuts
+        let expansion = match input {
+            ImeInput::Char(c) => self.snippet_expander.type_char(c, &self.config.snippets),
+            ImeInput::Backspace => {
+                self.snippet_expander.backspace();
+                None
             }
+            _ => {
+                self.snippet_expander.reset();
+                None
+            }
+        };
+        if let Some(expansion) = expansion
+            .filter(|_| self.config.snippets.active_in(self.focused_app.as_deref()))
+        {
+            self.controller.replace_typed(expansion.backspaces, &expansion.text);
+            self.reset_typing();
+            self.sync_prediction_row();
+            return;
+        }
+        if let Some(period) = period {
+            self.controller.replace_typed(period.backspaces, &period.text);
+            self.reset_typing();
+            self.sync_prediction_row();
+            return;
         }
 
-        // Return combined subscriptions or none
-        if subscriptions.is_empty() {
-            Subscription::none()
-        } else {
-            Subscription::batch(subscriptions)
+        if !self.config.prediction {
+            return;
+        }
+        // Other keysyms (Return, Tab, arrows, ...) end the word
+        if let Some(word) = self.predictor.feed(input).finished {
+            self.learn_word(&word);
         }
+        // Refreshed once per frame however many keys are typed in it
+        self.frame_queue.refresh_predictions();
     }
 
-    /// Handle surface close requests from the compositor.
+    /// Feeds a key to the layout's input method engine, if any.
     ///
-    /// This handles both popup closes and keyboard surface closes.
-    /// Since we no longer have an idle subscription listening for window events,
-    /// this is the proper way to detect when surfaces are closed externally.
-    fn on_close_requested(&self, id: window::Id) -> Option<Message> {
-        if Some(id) == self.keyboard_surface {
-            Some(Message::KeyboardSurfaceClosed(id))
-        } else {
-            Some(Message::PopupClosed(id))
+    /// Returns `true` if the engine used the key, which must then not be
+    /// typed; the text it commits is typed instead.
+    fn route_to_ime(&mut self, resolved: &ResolvedKeycode) -> bool {
+        let Some(ref mut engine) = self.ime else {
+            return false;
+        };
+        let modifiers = self
+            .keyboard_renderer
+            .as_ref()
+            .map(|renderer| renderer.get_active_modifiers())
+            .unwrap_or_default();
+        let output = engine.feed(ImeInput::from_key(resolved, &modifiers));
+        let consumed = output.consumed;
+        if consumed {
+            // The key is not typed, so one-shot modifiers end with it
+            if let Some(ref mut renderer) = self.keyboard_renderer {
+                renderer.clear_oneshot_modifiers();
+            }
         }
+        self.apply_ime_output(output);
+        consumed
     }
 
-    /// Handle messages emitted by the applet (Task 7.4, Task Group 5).
-    fn update(&mut self, message: Message) -> Task<Self::Message> {
-        match message {
-            Message::Toggle => {
-                // Close popup if open
-                if let Some(id) = self.popup.take() {
-                    return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
-                        cosmic::app::Action::Surface(destroy_popup(id)),
-                    ));
-                }
+    /// Feeds a key press to the layout's accent cycling.
+    ///
+    /// Returns `true` if the key replaced the previous character with its
+    /// next accent and must not be typed.
+    fn route_to_multi_tap(&mut self, identifier: &str, resolved: &ResolvedKeycode) -> bool {
+        if !self.multi_tap.is_enabled() {
+            return false;
+        }
+        let modifiers = self
+            .keyboard_renderer
+            .as_ref()
+            .map(|renderer| renderer.get_active_modifiers())
+            .unwrap_or_default();
+        let ImeInput::Char(typed) = ImeInput::from_key(resolved, &modifiers) else {
+            self.multi_tap.reset();
+            return false;
+        };
+        let MultiTapEvent::Replace(accent) = self.multi_tap.press(identifier, typed, Instant::now())
+        else {
+            return false;
+        };
 
-                // Toggle keyboard visibility
-                if self.keyboard_visible {
-                    return Task::done(cosmic::Action::App(Message::Hide));
-                } else {
-                    return Task::done(cosmic::Action::App(Message::Show));
-                }
-            }
-            Message::TogglePopup => {
-                // Right-click: toggle popup menu visibility
-                if let Some(id) = self.popup.take() {
-                    // Close popup if already open
-                    return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
-                        cosmic::app::Action::Surface(destroy_popup(id)),
-                    ));
-                }
+        // The key is not typed, so one-shot modifiers end with it
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.clear_oneshot_modifiers();
+        }
+        self.controller.replace_typed(1, &accent.to_string());
+        // Prediction and text shortcuts see the replacement as typed
+        self.track_typed_key(&ResolvedKeycode::Keysym("BackSpace".to_string()), &[]);
+        self.track_typed_key(&ResolvedKeycode::Character(accent), &[]);
+        true
+    }
 
-                // Open popup menu using applet's default positioning
-                return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
-                    cosmic::app::Action::Surface(app_popup::<AppletModel>(
-                        |state: &mut AppletModel| {
-                            let new_id = Id::unique();
-                            state.popup = Some(new_id);
-                            state.core.applet.get_popup_settings(
-                                state.core.main_window_id().unwrap(),
-                                new_id,
-                                None,
-                                None,
-                                None,
-                            )
-                        },
-                        Some(Box::new(|state: &AppletModel| {
-                            // Build the popup menu content
-                            let mode_label = if state.window_state.is_floating {
-                                fl!("exclusive-mode")
-                            } else {
-                                fl!("floating-mode")
-                            };
+    /// Types the text an input method engine committed and shows its
+    /// candidates.
+    fn apply_ime_output(&mut self, output: ImeOutput) {
+        if let Some(commit) = output.commit {
+            self.snippet_expander.reset();
+            self.predictor.reset();
+            self.controller.replace_typed(commit.backspaces, &commit.text);
+        }
+        self.sync_prediction_row();
+    }
 
-                            let content = list_column()
-                                .padding(8)
-                                .spacing(0)
-                                // Show Keyboard menu item
-                                .add(
-                                    cosmic::applet::menu_button(widget::text::body(fl!(
-                                        "show-keyboard"
-                                    )))
-                                    .on_press(Message::Show),
-                                )
-                                // Hide Keyboard menu item
-                                .add(
-                                    cosmic::applet::menu_button(widget::text::body(fl!(
-                                        "hide-keyboard"
-                                    )))
-                                    .on_press(Message::Hide),
-                                )
-                                // Separator
-                                .add(
-                                    cosmic::applet::padded_control(divider::horizontal::default())
-                                        .padding([8, 0]),
-                                )
-                                // Toggle docked / floating mode
-                                .add(
-                                    cosmic::applet::menu_button(widget::text::body(mode_label))
-                                        .on_press(Message::ToggleFloatingMode),
-                                )
-                                // Separator
-                                .add(
-                                    cosmic::applet::padded_control(divider::horizontal::default())
-                                        .padding([8, 0]),
-                                )
-                                // Quit menu item
-                                .add(
-                                    cosmic::applet::menu_button(widget::text::body(fl!("quit")))
-                                        .on_press(Message::Quit),
-                                );
+    /// Loads the input method of a layout language, with the configured
+    /// options.
+    fn load_ime(&mut self, language: Option<&str>) {
+        self.ime = ime::engine_for_language(
+            language,
+            &self.config.ime,
+            self.layout_providers.storage(),
+        );
+        if let Some(ref engine) = self.ime {
+            tracing::info!("Input method: {}", engine.name());
+        }
+    }
 
-                            Element::from(state.core.applet.popup_container(content))
-                                .map(cosmic::Action::App)
-                        })),
-                    )),
-                ));
-            }
-            Message::Show => {
-                // Close popup if open
-                if let Some(popup_id) = self.popup.take() {
-                    // First close popup, then show keyboard
-                    return Task::batch([
-                        cosmic::task::message(cosmic::Action::<Message>::Cosmic(
-                            cosmic::app::Action::Surface(destroy_popup(popup_id)),
-                        )),
-                        Task::done(cosmic::Action::App(Message::Show)),
-                    ]);
-                }
+    /// Forgets the text being typed, e.g. after the cursor moved or text was
+    /// typed another way: the predicted word, the accent being cycled, the
+    /// braille capital and number modes and the input method's composition.
+    fn reset_typing(&mut self) {
+        self.predictor.reset();
+        self.double_space.reset();
+        self.multi_tap.reset();
+        self.braille.reset();
+        if let Some(ref mut engine) = self.ime {
+            engine.reset();
+        }
+    }
 
-                if self.keyboard_visible {
-                    return Task::none();
-                }
+    /// Loads the enabled dictionaries into the predictor in the background.
+    ///
+    /// Dictionaries are large, so they are only read once prediction is on
+    /// and the keyboard is shown, and read again only when the set of
+    /// enabled dictionaries changes.
+    fn load_dictionaries(&mut self) -> Task<Message> {
+        let enabled = self.config.dictionaries.clone();
+        if !self.config.prediction
+            || !self.keyboard_visible
+            || enabled == self.loaded_dictionaries
+            || self.loading_dictionaries.as_ref() == Some(&enabled)
+        {
+            return Task::none();
+        }
 
-                // Load the keyboard layout (Task 7.2)
-                self.load_keyboard_layout();
+        self.loading_dictionaries = Some(enabled.clone());
+        let ids = enabled.clone();
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let storage = crate::storage::detect();
+                    let selected: Vec<DictionaryInfo> = dictionary::discover(storage.as_ref())
+                        .into_iter()
+                        .filter(|info| enabled.contains(&info.id))
+                        .collect();
+                    dictionary::load(storage.as_ref(), &selected)
+                })
+                .await
+                .unwrap_or_else(|e| LoadedDictionaries {
+                    engine: PredictionEngine::builtin(),
+                    bigrams: BigramModel::builtin(),
+                    errors: vec![format!("dictionary loader failed: {}", e)],
+                    ..LoadedDictionaries::default()
+                })
+            },
+            move |loaded| cosmic::Action::App(Message::DictionariesLoaded(ids, Box::new(loaded))),
+        )
+    }
 
-                // Initialize virtual keyboard (Task Group 5)
-                if let Err(e) = self.virtual_keyboard.initialize() {
-                    tracing::error!("Failed to initialize virtual keyboard: {}", e);
-                    // Continue even if VK fails - keyboard will show but not emit events
-                } else {
-                    tracing::info!("Virtual keyboard initialized");
-                }
+    /// Drops the loaded dictionaries, going back to the bundled words.
+    fn unload_dictionaries(&mut self) {
+        let engine = self.with_learned_words(PredictionEngine::builtin());
+        self.predictor.set_engine(engine);
+        self.predictor.set_bigrams(BigramModel::builtin());
+        self.loaded_dictionaries.clear();
+        self.loading_dictionaries = None;
+    }
 
-                // Create layer surface for keyboard
-                let id = window::Id::unique();
-                let height = self.window_state.height as u32;
-                let width = self.window_state.width as u32;
+    /// Adds the learned words to a freshly built engine.
+    fn with_learned_words(&self, mut engine: PredictionEngine) -> PredictionEngine {
+        if let Some(ref learned) = self.learned_words {
+            learned.apply(&mut engine);
+        }
+        engine
+    }
 
-                // Configure based on floating vs docked mode
-                let (anchor, size, margin, exclusive_zone) = if self.window_state.is_floating {
-                    // Floating: corner anchor, explicit size, position via margins
-                    (
-                        Anchor::BOTTOM | Anchor::RIGHT,
-                        Some((Some(width), Some(height))),
-                        IcedMargin {
-                            top: 0,
-                            right: self.window_state.margin_right,
-                            bottom: self.window_state.margin_bottom,
-                            left: 0,
-                        },
-                        0, // No exclusive zone in floating mode
-                    )
-                } else {
-                    // Docked: full-width bottom anchor with exclusive zone
-                    (
-                        Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
-                        Some((None, Some(height))),
-                        IcedMargin::default(),
-                        height as i32,
-                    )
-                };
+    /// Counts a finished word towards learning it, if that is allowed.
+    fn learn_word(&mut self, word: &str) {
+        if self.private_mode
+            || !self.kiosk.can_learn_words()
+            || !self.config.learning.may_learn(word)
+        {
+            return;
+        }
+        let Some(ref mut learned) = self.learned_words else {
+            return;
+        };
 
-                let settings = SctkLayerSurfaceSettings {
-                    id,
-                    layer: Layer::Overlay,
-                    keyboard_interactivity: KeyboardInteractivity::None,
-                    input_zone: None,
-                    anchor,
-                    output: IcedOutput::Active,
-                    namespace: "cosboard-keyboard".to_string(),
-                    margin,
-                    size,
-                    exclusive_zone,
-                    size_limits: Limits::NONE
-                        .min_width(MIN_WIDTH)
-                        .max_width(MAX_WIDTH)
-                        .min_height(MIN_HEIGHT)
-                        .max_height(MAX_HEIGHT),
-                };
+        let count = learned.record(word);
+        self.learned_words_changed = true;
+        if count >= LEARN_THRESHOLD {
+            self.predictor
+                .engine_mut()
+                .add_word(word, learning::learned_frequency(count));
+        }
+    }
 
-                self.keyboard_surface = Some(id);
-                self.keyboard_visible = true;
+    /// Reads the learned words in the background.
+    fn load_learned_words() -> Task<Message> {
+        Task::perform(
+            async {
+                tokio::task::spawn_blocking(|| {
+                    LearnedWords::load(crate::storage::detect().as_ref())
+                        .map_err(|e| tracing::warn!("Failed to load learned words: {}", e))
+                        .ok()
+                })
+                .await
+                .ok()
+                .flatten()
+            },
+            |learned| cosmic::Action::App(Message::LearnedWordsLoaded(learned)),
+        )
+    }
 
-                tracing::info!(
-                    "Opening keyboard layer surface: {:?} floating={} height={} width={} exclusive_zone={}",
-                    id,
-                    self.window_state.is_floating,
-                    height,
-                    width,
-                    exclusive_zone
-                );
+    /// Writes the learned words to disk if they changed.
+    fn save_learned_words(&mut self) {
+        let Some(ref learned) = self.learned_words else {
+            return;
+        };
+        if !self.learned_words_changed {
+            return;
+        }
+        match learned.save(self.layout_providers.storage()) {
+            Ok(()) => self.learned_words_changed = false,
+            Err(e) => tracing::warn!("Failed to save learned words: {}", e),
+        }
+    }
 
-                return get_layer_surface(settings);
-            }
-            Message::Hide => {
-                // Close popup if open
-                if let Some(popup_id) = self.popup.take() {
-                    // First close popup, then hide keyboard
-                    return Task::batch([
-                        cosmic::task::message(cosmic::Action::<Message>::Cosmic(
-                            cosmic::app::Action::Surface(destroy_popup(popup_id)),
-                        )),
-                        Task::done(cosmic::Action::App(Message::Hide)),
-                    ]);
-                }
+    /// Counts a key press for the key statistics, if that is allowed.
+    fn record_key_press(&mut self, identifier: &str) {
+        if !self.config.key_stats.enabled || self.private_mode || !self.kiosk.can_learn_words() {
+            return;
+        }
+        let Some(ref mut stats) = self.key_stats else {
+            return;
+        };
+        stats.record(identifier);
+        self.key_stats_changed = true;
+        self.sync_heat_map();
+    }
 
-                if !self.keyboard_visible {
-                    return Task::none();
-                }
+    /// Writes the key statistics to disk if they changed.
+    fn save_key_stats(&mut self) {
+        let Some(ref stats) = self.key_stats else {
+            return;
+        };
+        if !self.key_stats_changed {
+            return;
+        }
+        match stats.save(self.layout_providers.storage()) {
+            Ok(()) => self.key_stats_changed = false,
+            Err(e) => tracing::warn!("Failed to save key statistics: {}", e),
+        }
+    }
 
-                // Save state before closing
-                self.save_state();
+    /// Mirrors the key statistics into the renderer while the heat map is on.
+    fn sync_heat_map(&mut self) {
+        let heat_map = if self.config.key_stats.heat_map {
+            Some(self.key_stats.as_ref().map(KeyStats::heat_map).unwrap_or_default())
+        } else {
+            None
+        };
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.heat_map = heat_map;
+        }
+    }
 
-                // Cleanup virtual keyboard (Task Group 5)
-                self.virtual_keyboard.cleanup();
+    /// Makes every key report the finger entering it while boundary haptics
+    /// are on.
+    fn sync_key_crossings(&mut self) {
+        let settings = self.config.key_feedback;
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.key_crossings = settings.haptic && settings.boundaries;
+        }
+    }
 
-                // Clear the renderer (Task 7.1 - clear on layout unload)
-                self.keyboard_renderer = None;
+    /// Mirrors whether prediction candidates can be dragged into the
+    /// renderer.
+    fn sync_drag_suggestions(&mut self) {
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.drag_suggestions = self.config.drag_suggestions;
+        }
+    }
 
-                self.keyboard_visible = false;
-                if let Some(id) = self.keyboard_surface.take() {
-                    tracing::info!("Destroying keyboard layer surface: {:?}", id);
-                    return destroy_layer_surface(id);
+    /// Mirrors the user's key colors and corner radius into the renderer.
+    fn sync_key_style(&mut self) {
+        let key_style = self.config.theme.key_style();
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.key_style = key_style;
+        }
+    }
+
+    /// Decodes a QR code or barcode in the background and types its text.
+    ///
+    /// Without `path`, the image is picked through the file chooser portal.
+    /// The text is typed as [`AppAction::TypeText`], so it is not added to
+    /// the emoji recents; failures are shown as a toast.
+    #[cfg(feature = "scan")]
+    fn scan_code(&mut self, path: Option<String>) -> Task<Message> {
+        use crate::scan::{self, ScanError};
+
+        let title = fl!("scan-choose-image");
+        Task::perform(
+            async move {
+                let path = match path {
+                    Some(path) => std::path::PathBuf::from(path),
+                    None => scan::portal::pick_image(&title).await?,
+                };
+                tokio::task::spawn_blocking(move || scan::decode_file(&path))
+                    .await
+                    .map_err(|e| ScanError::Decode(e.to_string()))?
+            },
+            |result| {
+                cosmic::Action::App(match result {
+                    Ok(scanned) => {
+                        tracing::info!(
+                            "Typing scanned {}",
+                            if scanned.wifi_password { "Wi-Fi password" } else { "code" }
+                        );
+                        Message::Action(AppAction::TypeText(scanned.text))
+                    }
+                    Err(e) => {
+                        tracing::info!("Scan failed: {}", e);
+                        let severity = match e {
+                            ScanError::Cancelled => ToastSeverity::Info,
+                            _ => ToastSeverity::Warning,
+                        };
+                        Message::ShowToast(e.localized(), severity)
+                    }
+                })
+            },
+        )
+    }
+
+    /// Scanning needs the `scan` feature; tells the user it is missing.
+    #[cfg(not(feature = "scan"))]
+    fn scan_code(&mut self, _path: Option<String>) -> Task<Message> {
+        tracing::warn!("Built without the scan feature, cannot scan codes");
+        self.update(Message::ShowToast(
+            fl!("scan-unavailable"),
+            ToastSeverity::Warning,
+        ))
+    }
+
+    /// Types the prediction candidate at `index` in place of the current word.
+    fn commit_prediction(&mut self, index: usize) {
+        // The row shows the input method's candidates while it has any
+        if let Some(ref mut engine) = self.ime {
+            if !engine.candidates().is_empty() {
+                let output = engine.select(index);
+                self.apply_ime_output(output);
+                return;
+            }
+        }
+
+        let candidate = self.predictor.candidates().get(index).cloned();
+        let Some(completion) = self.predictor.select(index).commit else {
+            return;
+        };
+        if let Some(word) = candidate {
+            self.learn_word(&word);
+        }
+        self.snippet_expander.reset();
+        self.controller.replace_typed(completion.backspaces, &completion.text);
+        self.sync_prediction_row();
+    }
+
+    /// Routes a key press to the focused internal text field, if any.
+    ///
+    /// Returns the follow-up task if the key was handled internally and must
+    /// not be emitted to the client application, `None` otherwise.
+    fn route_to_text_field(&mut self, resolved: &ResolvedKeycode) -> Option<Task<Message>> {
+        let renderer = self.keyboard_renderer.as_mut()?;
+
+        let field_id = renderer.focus.focused_id().map(str::to_string);
+        let typing_test = field_id.as_deref() == Some(TYPING_TEST_FIELD);
+        match renderer.focus.route(resolved) {
+            KeyRoute::Client => None,
+            KeyRoute::Edited => {
+                if typing_test {
+                    if let Some(ref mut test) = self.typing_test {
+                        test.update(renderer.focus.buffer(TYPING_TEST_FIELD), Instant::now());
+                    }
                 }
+                Some(Task::none())
             }
-            Message::Quit => {
-                // Save state before quitting
-                self.save_state();
-                // Cleanup virtual keyboard
-                self.virtual_keyboard.cleanup();
-                std::process::exit(0);
+            KeyRoute::Ignored => Some(Task::none()),
+            KeyRoute::Cancelled => {
+                if typing_test {
+                    self.typing_test = None;
+                }
+                Some(Task::none())
             }
-            Message::PopupClosed(id) => {
-                if self.popup.as_ref() == Some(&id) {
-                    self.popup = None;
+            KeyRoute::Submitted(query) => match field_id.as_deref() {
+                Some(emoji_search::EMOJI_SEARCH_WIDGET) => {
+                    if let Some(glyph) = emoji_search::best_match(&query) {
+                        renderer.focus.blur();
+                        self.controller.emit_text(glyph);
+                        self.record_emoji_use(glyph);
+                    }
+                    Some(Task::none())
+                }
+                Some(TYPING_TEST_FIELD) => {
+                    // Enter moves on once the phrase is complete
+                    let next = self
+                        .typing_test
+                        .as_ref()
+                        .filter(|test| test.is_complete())
+                        .map(TypingTest::next);
+                    if let Some(next) = next {
+                        self.typing_test = Some(next);
+                        renderer.focus.blur();
+                        renderer.focus.focus(TYPING_TEST_FIELD);
+                    }
+                    Some(Task::none())
+                }
+                Some(PALETTE_FIELD) => {
+                    // Enter runs the best entry
+                    let first = self.palette_entries(&query).into_iter().next();
+                    match first {
+                        Some(entry) => Some(self.dispatch_action(entry.action)),
+                        None => Some(Task::none()),
+                    }
                 }
+                _ => Some(Task::none()),
+            },
+        }
+    }
+
+    /// Converts a physical key event into a keycode for text field routing.
+    fn physical_key_to_keycode(key: &keyboard::Key, text: Option<&str>) -> Option<ResolvedKeycode> {
+        use keyboard::key::Named;
+
+        match key {
+            keyboard::Key::Named(Named::Backspace) => Some(ResolvedKeycode::Keysym("BackSpace".to_string())),
+            keyboard::Key::Named(Named::Enter) => Some(ResolvedKeycode::Keysym("Return".to_string())),
+            keyboard::Key::Named(Named::Escape) => Some(ResolvedKeycode::Keysym("Escape".to_string())),
+            keyboard::Key::Named(Named::Space) => Some(ResolvedKeycode::Keysym("space".to_string())),
+            keyboard::Key::Named(_) => None,
+            _ => text
+                .and_then(|t| t.chars().next())
+                .filter(|c| !c.is_control())
+                .map(ResolvedKeycode::Character),
+        }
+    }
+
+    /// Maps a key forwarded by the compositor to a navigation command, as
+    /// sent by TV remotes.
+    fn physical_key_to_navigation(key: &keyboard::Key) -> Option<NavCommand> {
+        use keyboard::key::Named;
+
+        match key {
+            keyboard::Key::Named(Named::ArrowUp) => Some(NavCommand::Move(NavDirection::Up)),
+            keyboard::Key::Named(Named::ArrowDown) => Some(NavCommand::Move(NavDirection::Down)),
+            keyboard::Key::Named(Named::ArrowLeft) => Some(NavCommand::Move(NavDirection::Left)),
+            keyboard::Key::Named(Named::ArrowRight) => Some(NavCommand::Move(NavDirection::Right)),
+            keyboard::Key::Named(Named::Enter | Named::Select) => Some(NavCommand::Press),
+            keyboard::Key::Named(Named::Escape | Named::BrowserBack | Named::GoBack) => {
+                Some(NavCommand::Back)
             }
-            Message::Surface(action) => {
-                return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
-                    cosmic::app::Action::Surface(action),
-                ));
+            keyboard::Key::Named(Named::PageUp) => Some(NavCommand::PreviousPanel),
+            keyboard::Key::Named(Named::PageDown) => Some(NavCommand::NextPanel),
+            _ => None,
+        }
+    }
+
+    /// Runs a gamepad or remote navigation command.
+    ///
+    /// The first command only shows the focus ring on the first key of the
+    /// panel; pressing a panel button switches to its panel.
+    fn navigate(&mut self, command: NavCommand) -> Task<Message> {
+        let (surface_width, surface_height) = self
+            .dead_zone_insets()
+            .inner_size(self.window_state.width, self.window_state.height);
+        let Some(ref mut renderer) = self.keyboard_renderer else {
+            return Task::none();
+        };
+        let shown = renderer.shown_panel_id().to_string();
+
+        match command {
+            NavCommand::Move(direction) => {
+                if let Some(panel) = renderer.get_panel(&shown) {
+                    renderer.nav_focus = move_focus(
+                        panel,
+                        renderer.nav_focus.as_ref(),
+                        direction,
+                        surface_width,
+                        surface_height,
+                    );
+                }
             }
-            Message::KeyboardSurfaceClosed(id) => {
-                if self.keyboard_surface == Some(id) {
-                    self.keyboard_surface = None;
-                    self.keyboard_visible = false;
-                    self.keyboard_renderer = None; // Clear renderer
-                    self.virtual_keyboard.cleanup(); // Cleanup VK
-                    tracing::info!("Keyboard layer surface closed: {:?}", id);
+            NavCommand::Press => match renderer.nav_focus.clone() {
+                Some(NavTarget::Key(identifier)) => {
+                    let press = self.update(Message::KeyPressed(identifier.clone()));
+                    let release = self.update(Message::KeyReleased(identifier));
+                    return Task::batch([press, release]);
                 }
-                // Also check if this was the preview surface
-                if self.preview_surface == Some(id) {
-                    self.preview_surface = None;
-                    tracing::debug!("Preview surface closed: {:?}", id);
+                Some(NavTarget::Panel(panel_id)) => {
+                    return self.update(Message::SwitchPanel(panel_id));
+                }
+                None => renderer.nav_focus = renderer.get_panel(&shown).and_then(first_target),
+            },
+            NavCommand::Back => {
+                if renderer.switch_panel_back() {
+                    tracing::debug!("Navigated back to panel: {}", renderer.shown_panel_id());
+                    return self.sync_dbus_panel();
                 }
             }
-            Message::KeyboardSurfaceResized(id, _width, height) => {
-                // PERFORMANCE: Ignore resize events for preview surface entirely.
-                // The preview is just visual feedback - we don't need to track its state.
-                if self.preview_surface == Some(id) {
+            NavCommand::NextPanel | NavCommand::PreviousPanel => {
+                let forward = command == NavCommand::NextPanel;
+                if let Some(panel_id) = adjacent_panel(&renderer.layout, &shown, forward) {
+                    if renderer.nav_focus.is_none() {
+                        renderer.nav_focus = renderer.get_panel(&panel_id).and_then(first_target);
+                    }
+                    return self.update(Message::SwitchPanel(panel_id));
+                }
+            }
+        }
+        Task::none()
+    }
+
+    /// Types a pressed key, with its sound and vibration.
+    fn emit_key_press(&mut self, identifier: String) -> Task<Message> {
+        self.record_key_press(&identifier);
+        let haptic = self.play_key_feedback(&identifier);
+        let inhibit = self.inhibit_idle();
+        Task::batch([haptic, inhibit, self.type_key_press(identifier)])
+    }
+
+    /// Keeps the screen awake while typing, if enabled: returns the task
+    /// asking the session not to go idle, on the first key.
+    fn inhibit_idle(&mut self) -> Task<Message> {
+        if !self.config.idle_inhibit.enabled {
+            return Task::none();
+        }
+        let Some(connection) = self.dbus_connection.clone() else {
+            return Task::none();
+        };
+        if !self.idle_inhibitor.note_activity(Instant::now()) {
+            return Task::none();
+        }
+        Task::perform(idle_inhibit::inhibit(connection, APPLET_ID), |result| {
+            cosmic::Action::App(Message::IdleInhibited(result))
+        })
+    }
+
+    /// Returns the task withdrawing an idle inhibition, if one was released.
+    fn release_idle_inhibit(&self, cookie: Option<u32>) -> Task<Message> {
+        match (cookie, self.dbus_connection.clone()) {
+            (Some(cookie), Some(connection)) => {
+                Task::future(idle_inhibit::release(connection, cookie)).discard()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Plays the sound of a pressed key, if sounds are on, and returns the
+    /// task vibrating the device, if vibration is on.
+    fn play_key_feedback(&mut self, identifier: &str) -> Task<Message> {
+        let settings = self.config.key_feedback;
+
+        // Key sound from the key's (or the layout's) feedback profile
+        let profile = self.keyboard_renderer.as_ref().and_then(|renderer| {
+            self.find_key_by_identifier(identifier)
+                .and_then(|key| feedback::key_profile(key, &renderer.layout))
+                .cloned()
+        });
+        if let Some(profile) = profile.filter(|_| settings.sound) {
+            self.feedback.play(&profile);
+        }
+
+        self.play_haptic(feedback::KEY_PRESS)
+    }
+
+    /// Returns the task vibrating the device with `pattern`, if vibration
+    /// is on.
+    fn play_haptic(&self, pattern: HapticPattern) -> Task<Message> {
+        let trigger = self
+            .dbus_connection
+            .clone()
+            .filter(|_| self.config.key_feedback.haptic)
+            .and_then(|connection| self.haptics.trigger(connection, APPLET_ID, pattern));
+        match trigger {
+            Some(trigger) => Task::future(trigger).discard(),
+            None => Task::none(),
+        }
+    }
+
+    /// Runs a pressed key's command, routes it to an internal text field or
+    /// the input method, or emits it.
+    fn type_key_press(&mut self, identifier: String) -> Task<Message> {
+        // Now handle input emission (Task Group 5)
+        // Clone the key data we need to avoid borrow issues
+        let key_info = self.find_key_by_identifier(&identifier).map(|key| {
+            (
+                key.code.clone(),
+                key.sticky,
+                key.stickyrelease,
+                key.identifier.clone(),
+            )
+        });
+
+        if let Some((code, sticky, stickyrelease, id)) = key_info {
+            // Command keys run keyboard-internal actions
+            if let Some(command) = parse_command(&code) {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.focus.consume_key(identifier.clone());
+                }
+                // Braille dots are typed as a cell once all are released
+                if let Ok(AppAction::BrailleDot(dot)) = self.actions.parse(command) {
+                    self.braille_chord.press(dot);
                     return Task::none();
                 }
+                return self.run_command(command);
+            }
 
-                if self.keyboard_surface == Some(id) {
-                    // PERFORMANCE: Skip state update during active drag/resize to prevent
-                    // widget rebuilds. The compositor sends Resized events in response to
-                    // our set_size() calls, but we don't want to update window_state until
-                    // the operation ends (handled in DragEnd).
-                    if self.resize_edge.is_some() || self.is_dragging {
-                        tracing::debug!("Skipping resize event during active drag/resize");
+            // With a long-press-space gesture bound, space is only typed
+            // once it is released before the long press threshold
+            if Self::is_space_key(&code)
+                && self.config.gesture_bindings.is_bound(Gesture::LongPressSpace)
+                && !self.keyboard_renderer.as_ref().is_some_and(|r| r.focus.is_internal())
+            {
+                self.deferred_space = Some(identifier.clone());
+                return Task::none();
+            }
+
+            // Keys with alternatives are typed on release, or replaced
+            // by the alternative picked from their long press popup
+            let has_popup = self
+                .find_key_by_identifier(&identifier)
+                .is_some_and(|key| has_swipe_alternatives(&key.alternatives));
+            if has_popup
+                && KeyboardController::keycode_to_modifier(&code).is_none()
+                && !self.keyboard_renderer.as_ref().is_some_and(|r| r.focus.is_internal())
+            {
+                self.deferred_popup_key = Some(identifier.clone());
+                return Task::none();
+            }
+
+            // Keys typed into an internal text field never reach the client
+            if KeyboardController::keycode_to_modifier(&code).is_none() {
+                if let Some(resolved) = parse_keycode(&code) {
+                    if let Some(task) = self.route_to_text_field(&resolved) {
+                        if let Some(ref mut renderer) = self.keyboard_renderer {
+                            renderer.focus.consume_key(identifier.clone());
+                        }
+                        return task;
+                    }
+
+                    // The layout's input method may compose the key
+                    if self.route_to_ime(&resolved) {
+                        if let Some(ref mut renderer) = self.keyboard_renderer {
+                            renderer.focus.consume_key(identifier.clone());
+                        }
                         return Task::none();
                     }
 
-                    self.window_state.height = height;
-                    tracing::debug!("Keyboard resized to height {}", height);
+                    // Tapping a letter again may replace it with an accent
+                    if self.route_to_multi_tap(&identifier, &resolved) {
+                        if let Some(ref mut renderer) = self.keyboard_renderer {
+                            renderer.focus.consume_key(identifier.clone());
+                        }
+                        return Task::none();
+                    }
+                }
 
-                    // Update exclusive zone if in exclusive mode
-                    let mut tasks = vec![Task::done(cosmic::Action::App(Message::SaveState))];
-                    if !self.window_state.is_floating {
-                        tasks.push(set_exclusive_zone(id, height as i32));
+                // Keys in a chord wait briefly for the rest of the chord
+                match self.chord_matcher.press(&identifier, Instant::now()) {
+                    ChordEvent::Pass => {}
+                    ChordEvent::Pending => return Task::none(),
+                    ChordEvent::Chord(chord_code) => {
+                        tracing::debug!("Chord detected: {}", chord_code);
+                        return self.emit_chord(chord_code);
+                    }
+                    ChordEvent::Flush(held) => {
+                        self.press_held_keys(&held);
+                        if held.contains(&identifier) {
+                            return Task::none();
+                        }
                     }
-                    return Task::batch(tasks);
                 }
             }
-            Message::ToggleFloatingMode => {
-                self.window_state.is_floating = !self.window_state.is_floating;
-                self.save_state();
 
-                // Update layer surface configuration
-                if let Some(id) = self.keyboard_surface {
-                    let height = self.window_state.height as u32;
-                    let width = self.window_state.width as u32;
+            // Create a temporary Key struct with the needed fields
+            let key = Key {
+                code: code.clone(),
+                sticky,
+                stickyrelease,
+                identifier: id,
+                ..Key::default()
+            };
+
+            // Check if this is a modifier key
+            if let Some(modifier) = KeyboardController::keycode_to_modifier(&code) {
+                // Handle modifier key press
+                self.handle_modifier_key_press(&key, modifier);
+            } else {
+                // Handle regular key press
+                self.handle_regular_key_press(&key);
+            }
+        }
+        Task::none()
+    }
 
-                    let tasks = if self.window_state.is_floating {
-                        // Switching TO floating: corner anchor + explicit size
-                        tracing::info!(
-                            "Switching to floating mode: width={} height={} margin_right={} margin_bottom={}",
-                            width,
-                            height,
-                            self.window_state.margin_right,
-                            self.window_state.margin_bottom
-                        );
-                        vec![
+    /// Settles the key held back by palm rejection: types it once its
+    /// contact lasted long enough, or drops it if the contact was rejected.
+    ///
+    /// With `released`, the contact ended, so it is settled either way. A
+    /// dropped key is remembered so its release is ignored. Returns the task
+    /// of the typed key, or `None` if it was not typed.
+    fn settle_palm_hold(&mut self, released: bool) -> Option<Task<Message>> {
+        let (identifier, pressed_at) = self.palm_held_key.clone()?;
+        let settings = self.config.palm_rejection;
+        let rejection = self.palm_filter.latest_rejection().or_else(|| {
+            settings
+                .is_too_brief(pressed_at.elapsed())
+                .then_some(Rejection::TooBrief)
+        });
+        match rejection {
+            // Too brief so far, the contact may still last
+            Some(Rejection::TooBrief) if !released => None,
+            Some(rejection) => {
+                tracing::debug!("Key {} rejected as a palm: {:?}", identifier, rejection);
+                self.palm_held_key = None;
+                self.palm_rejected_key = Some(identifier);
+                None
+            }
+            None => {
+                self.palm_held_key = None;
+                Some(self.emit_key_press(identifier))
+            }
+        }
+    }
+
+    /// Settles a key still pressed when the pointer left the keyboard, or
+    /// left the key and was lifted elsewhere, as the `pointer_leave` policy
+    /// says.
+    ///
+    /// Its release never arrives, since layer surfaces do not grab the
+    /// pointer and buttons only report releases over them. With [`PointerLeavePolicy::Cancel`], a press that typed
+    /// nothing yet (held back by palm rejection, a deferred space or key with
+    /// alternatives, an open popup) is dropped; anything else is released as
+    /// usual.
+    fn release_left_key(&mut self, identifier: String) -> Task<Message> {
+        tracing::debug!("Pointer left {} while it was pressed", identifier);
+        if self.config.pointer_leave == PointerLeavePolicy::Cancel {
+            let mut held_back = false;
+            if self
+                .palm_held_key
+                .as_ref()
+                .is_some_and(|(key, _)| *key == identifier)
+            {
+                self.palm_held_key = None;
+                held_back = true;
+            }
+            if self.deferred_space.as_deref() == Some(identifier.as_str()) {
+                self.deferred_space = None;
+                held_back = true;
+            }
+            if self.deferred_popup_key.as_deref() == Some(identifier.as_str()) {
+                self.deferred_popup_key = None;
+                held_back = true;
+            }
+            if let Some(ref mut renderer) = self.keyboard_renderer {
+                if renderer
+                    .popup
+                    .as_ref()
+                    .is_some_and(|popup| popup.key == identifier)
+                {
+                    renderer.popup = None;
+                    held_back = true;
+                }
+                if held_back {
+                    renderer.release_key(&identifier);
+                    return Task::none();
+                }
+            }
+        }
+        self.update(Message::KeyReleased(identifier))
+    }
+
+    /// Handles a regular (non-modifier) key press.
+    ///
+    /// This method:
+    /// 1. Gets active modifiers from the renderer
+    /// 2. Emits modifier key presses for active modifiers not already pressed
+    /// 3. Emits the main key press
+    /// 4. Stores the pressed key for release handling
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key definition
+    fn handle_regular_key_press(&mut self, key: &Key) {
+        if !self.controller.virtual_keyboard.is_initialized() {
+            tracing::warn!("Virtual keyboard not initialized, cannot emit key press");
+            return;
+        }
+
+        // Caps-word shifts letters and ends at the end of the word
+        if let (Some(renderer), Some(resolved)) =
+            (self.keyboard_renderer.as_mut(), parse_keycode(&key.code))
+        {
+            renderer.apply_caps_word(&resolved);
+        }
+
+        // Get active modifiers
+        let active_modifiers = if let Some(ref renderer) = self.keyboard_renderer {
+            renderer.get_active_modifiers()
+        } else {
+            Vec::new()
+        };
+
+        // On the layer of the active modifiers, keys type their alternative
+        let identifier = key_identifier(key);
+        let code = match AlternativeKey::layer(&active_modifiers)
+            .and_then(|layer| key.layer_code(&layer))
+        {
+            Some(code) => {
+                self.layer_codes.insert(identifier.clone(), code.clone());
+                code
+            }
+            None => key.code.clone(),
+        };
+
+        if let Some(resolved) = self
+            .controller
+            .press_key(&code, identifier, &active_modifiers)
+        {
+            self.track_typed_key(&resolved, &active_modifiers);
+        }
+    }
+
+    /// Handles a regular (non-modifier) key release.
+    ///
+    /// This method:
+    /// 1. Emits the main key release
+    /// 2. Emits releases for the modifiers pressed with the key
+    /// 3. Clears one-shot modifiers from the renderer state
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key definition
+    fn handle_regular_key_release(&mut self, key: &Key) {
+        if !self.controller.virtual_keyboard.is_initialized() {
+            return;
+        }
+        let identifier = key_identifier(key);
+        let code = self
+            .layer_codes
+            .remove(&identifier)
+            .unwrap_or_else(|| key.code.clone());
+        self.controller.release_key(&code, identifier);
+
+        // Clear one-shot modifiers from the renderer
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.clear_oneshot_modifiers();
+        }
+    }
+
+    /// Types a chord's key code (press and release), or runs it if it is
+    /// a command key (e.g. `cosboard:send_key:ctrl+c`).
+    fn emit_chord(&mut self, code: KeyCode) -> Task<Message> {
+        if let Some(command) = parse_command(&code) {
+            return self.run_command(command);
+        }
+        let key = Key {
+            code,
+            ..Key::default()
+        };
+        self.handle_regular_key_press(&key);
+        self.handle_regular_key_release(&key);
+        Task::none()
+    }
+
+    /// Shows the alternatives popup of a long-pressed key, centered on the
+    /// key and kept inside the panel.
+    fn open_popup(&mut self, identifier: &str) {
+        let area = self.panel_area();
+        let scale = self.scale_factor;
+        let Some(renderer) = self.keyboard_renderer.as_mut() else {
+            return;
+        };
+        let position = renderer.current_panel().and_then(|panel| {
+            let geometry = panel_geometry(panel, area.width, area.height, scale);
+            geometry.cells.iter().find_map(|bounds| match bounds.cell {
+                Cell::Key(key) if key.identifier.as_deref() == Some(identifier) => {
+                    let key_bounds =
+                        Rectangle::new(bounds.x, bounds.y, bounds.width, bounds.height);
+                    let position = calculate_popup_position(key_bounds, &key.alternatives);
+                    let width = position.popup_width(scale);
+                    let height = position.popup_height(scale);
+                    Some(adjust_popup_position(
+                        position,
+                        width,
+                        height,
+                        area.width,
+                        area.height,
+                    ))
+                }
+                _ => None,
+            })
+        });
+        let Some(position) = position else {
+            return;
+        };
+        tracing::debug!("Showing the alternatives of {}", identifier);
+        renderer.popup = Some(ActivePopup {
+            key: identifier.to_string(),
+            position,
+            selected: None,
+        });
+    }
+
+    /// Closes the long press popup and types the alternative picked in it,
+    /// or the long-pressed key itself if none is selected.
+    fn finish_popup(&mut self) -> Task<Message> {
+        let Some(popup) = self
+            .keyboard_renderer
+            .as_mut()
+            .and_then(|renderer| renderer.popup.take())
+        else {
+            return Task::none();
+        };
+        self.deferred_popup_key = None;
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            renderer.release_key(&popup.key);
+        }
+        let Some(key) = self.find_key_by_identifier(&popup.key).cloned() else {
+            return Task::none();
+        };
+
+        let alternative = popup
+            .selected
+            .and_then(|direction| popup_alternative(&key, direction))
+            .cloned();
+        tracing::debug!("Picked from the popup of {}: {:?}", popup.key, alternative);
+        match alternative {
+            None => {
+                self.handle_regular_key_press(&key);
+                self.handle_regular_key_release(&key);
+                Task::none()
+            }
+            Some(Action::Character(c)) => self.emit_chord(KeyCode::Unicode(c)),
+            Some(Action::KeyCode(code)) => self.emit_chord(code),
+            Some(Action::Script(script)) => {
+                let command = script.strip_prefix("script:").unwrap_or(&script);
+                self.run_command(command)
+            }
+            Some(Action::PanelSwitch(panel)) => {
+                let panel = panel.trim_start_matches("panel(").trim_end_matches(')');
+                self.update(Message::SwitchPanel(panel.to_string()))
+            }
+        }
+    }
+
+    /// Taps a key's `post_keys` in order, once the key itself is released
+    /// (e.g. `)` and Left after `(`, leaving the cursor inside the pair).
+    ///
+    /// They are journaled under the key's identifier.
+    fn tap_post_keys(&mut self, key: &Key, post_keys: Vec<KeyCode>) {
+        for code in post_keys {
+            let post_key = Key {
+                code,
+                identifier: key.identifier.clone(),
+                ..Key::default()
+            };
+            self.handle_regular_key_press(&post_key);
+            self.handle_regular_key_release(&post_key);
+        }
+    }
+
+    /// Presses keys that were held back waiting for a chord.
+    ///
+    /// Their releases are handled normally when the keys are let go.
+    fn press_held_keys(&mut self, identifiers: &[String]) {
+        for identifier in identifiers {
+            if let Some(key) = self.find_key_by_identifier(identifier).cloned() {
+                self.handle_regular_key_press(&key);
+            }
+        }
+    }
+
+    /// Handles a modifier key press.
+    ///
+    /// This method activates the modifier in the renderer's modifier state
+    /// based on the key's sticky and stickyrelease fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key definition
+    /// * `modifier` - The modifier type
+    ///
+    /// Double-tapping Shift enables caps-word instead of applying the second
+    /// tap's normal behavior. Double-tapping another one-shot modifier locks
+    /// it until it is tapped again.
+    ///
+    /// Caps Lock is tapped in the client application right away and latched
+    /// until its key is tapped again, whatever the key's sticky fields say.
+    fn handle_modifier_key_press(&mut self, key: &Key, modifier: Modifier) {
+        if modifier == Modifier::CapsLock {
+            if let Some(ref mut renderer) = self.keyboard_renderer {
+                let latched = renderer.toggle_caps_lock(&key_identifier(key));
+                tracing::debug!("Caps Lock latched: {}", latched);
+            }
+            self.controller.toggle_caps_lock();
+            return;
+        }
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            if modifier == Modifier::Shift {
+                let id = key.identifier.as_deref().unwrap_or("shift");
+                if renderer.register_shift_tap(id) {
+                    tracing::debug!("Caps-word enabled");
+                    return;
+                }
+            }
+
+            if key.sticky {
+                // Sticky key: toggle behavior for toggle mode, activate for one-shot
+                if key.stickyrelease {
+                    // One-shot: activate and mark as sticky, lock on a double
+                    // tap, release if locked
+                    let mode = renderer.tap_sticky_modifier(modifier);
+                    if let Some(ref id) = key.identifier {
+                        renderer.sync_modifier_visual_state(modifier, id);
+                    }
+                    tracing::debug!("Tapped one-shot modifier {:?}: {:?}", modifier, mode);
+                } else {
+                    // Toggle mode: toggle the modifier state
+                    if renderer.is_modifier_active(modifier) {
+                        renderer.deactivate_modifier(modifier);
+                        if let Some(ref id) = key.identifier {
+                            renderer.sticky_keys_active.remove(id);
+                        }
+                        tracing::debug!("Deactivated toggle modifier: {:?}", modifier);
+                    } else {
+                        renderer.activate_modifier(modifier, false);
+                        if let Some(ref id) = key.identifier {
+                            renderer.sync_modifier_visual_state(modifier, id);
+                        }
+                        tracing::debug!("Activated toggle modifier: {:?}", modifier);
+                    }
+                }
+            } else {
+                // Hold mode: activate while held (will deactivate on release)
+                renderer.hold_modifier(modifier);
+                if let Some(ref id) = key.identifier {
+                    renderer.sync_modifier_visual_state(modifier, id);
+                }
+                tracing::debug!("Activated hold modifier: {:?}", modifier);
+            }
+        }
+    }
+
+    /// Handles a modifier key release.
+    ///
+    /// For hold mode modifiers, this deactivates the modifier.
+    /// For sticky modifiers, release is handled in `clear_oneshot_modifiers`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key definition
+    /// * `modifier` - The modifier type
+    fn handle_modifier_key_release(&mut self, key: &Key, modifier: Modifier) {
+        // Caps Lock was tapped on press and stays latched
+        if modifier == Modifier::CapsLock {
+            return;
+        }
+        if let Some(ref mut renderer) = self.keyboard_renderer {
+            if !key.sticky {
+                // Hold mode: deactivate on release
+                renderer.deactivate_modifier(modifier);
+                if let Some(ref id) = key.identifier {
+                    renderer.sticky_keys_active.remove(id);
+                }
+                tracing::debug!("Released hold modifier: {:?}", modifier);
+            }
+            // For sticky modifiers, the state persists until cleared by clear_oneshot_modifiers
+            // or toggled off by another press
+        }
+    }
+}
+
+impl cosmic::Application for AppletModel {
+    /// The async executor for running application tasks.
+    type Executor = cosmic::SingleThreadExecutor;
+
+    /// Data that the application receives at initialization.
+    type Flags = SurfaceOverrides;
+
+    /// Messages which the application and its widgets will emit.
+    type Message = Message;
+
+    /// Unique identifier in RDNN format.
+    const APP_ID: &'static str = APPLET_ID;
+
+    fn core(&self) -> &Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut Core {
+        &mut self.core
+    }
+
+    /// Initialize the applet and load persisted window state.
+    fn init(core: Core, overrides: Self::Flags) -> (Self, Task<Self::Message>) {
+        // DIAGNOSTIC: Skip config loading to test if it's causing the delay
+        // TODO: Re-enable once we identify the performance issue
+        let mut window_state = WindowState::default();
+        if let Some(height) = overrides.height {
+            window_state.height = height.clamp(MIN_HEIGHT, MAX_HEIGHT);
+        }
+        if !overrides.is_empty() {
+            tracing::info!("Surface overrides: {:?}", overrides);
+        }
+
+        let mut applet = AppletModel {
+            core,
+            popup: None,
+            keyboard_surface: None,
+            surface_size: None,
+            keyboard_visible: false,
+            pending_width: window_state.width,
+            pending_height: window_state.height,
+            pending_margin_right: window_state.margin_right,
+            pending_margin_bottom: window_state.margin_bottom,
+            window_state,
+            state_config: None, // No config = no D-Bus operations
+            is_dragging: false,
+            resize_edge: None,
+            last_cursor_position: None,
+            frame_queue: FrameQueue::default(),
+            preview_surface: None,
+            edge_strip: None,
+            text_field_focused: false,
+            last_preview_width: 0,
+            last_preview_height: 0,
+            last_preview_margin_right: 0,
+            last_preview_margin_bottom: 0,
+            last_preview_update: None,
+            keyboard_renderer: None,
+            controller: KeyboardController::new(),
+            config: Config::default(),
+            config_context: None,
+            overrides,
+            target_output: None,
+            output_densities: Vec::new(),
+            output_scales: Vec::new(),
+            scale_factor: get_scale_factor(),
+            outputs: Outputs::default(),
+            touch_target_warned: None,
+            dbus_state: Arc::new(Mutex::new(DbusState::default())),
+            dbus_connection: None,
+            confirmations: Vec::new(),
+            last_confirmation_id: 0,
+            active_layout_path: None,
+            layout_providers: ProviderChain::standard(),
+            layout_source: None,
+            available_layouts: Vec::new(),
+            actions: ActionRegistry::new(),
+            gesture_tracker: GestureTracker::new(),
+            deferred_space: None,
+            deferred_candidate: None,
+            deferred_popup_key: None,
+            popup_closed_key: None,
+            exited_keys: Vec::new(),
+            palm_filter: PalmFilter::default(),
+            palm_held_key: None,
+            palm_rejected_key: None,
+            layer_codes: HashMap::new(),
+            kiosk: KioskPolicy::default(),
+            typing_test: None,
+            chord_matcher: ChordMatcher::default(),
+            multi_tap: MultiTap::default(),
+            braille_chord: BrailleChord::new(),
+            braille: BrailleTranslator::default(),
+            slide: SlideTracker::new(),
+            feedback: Feedback::new(),
+            haptics: Haptics::new(),
+            boundaries: BoundaryTracker::new(),
+            hooks: HookRunner::new(),
+            idle_inhibitor: IdleInhibitor::new(),
+            hooked_layout: None,
+            layout_request: 0,
+            layout_loading: false,
+            layout_cache: LayoutCache::new(),
+            layout_watcher: LayoutWatcher::default(),
+            reload_panel: None,
+            height_transition: None,
+            collapsed: false,
+            collapsed_bar_tapped: None,
+            recorder: SessionRecorder::from_env(),
+            predictor: Predictor::new(PredictionEngine::builtin())
+                .with_bigrams(BigramModel::builtin()),
+            ime: None,
+            prediction_panel: None,
+            available_dictionaries: Vec::new(),
+            loaded_dictionaries: Vec::new(),
+            loading_dictionaries: None,
+            learned_words: None,
+            learned_words_changed: false,
+            key_stats: None,
+            key_stats_changed: false,
+            private_mode: false,
+            snippet_expander: SnippetExpander::new(),
+            double_space: DoubleSpaceTracker::new(),
+            focused_app: None,
+            light_level: None,
+            keyboard_palette: None,
+            system_dark: None,
+            startup: StartupTimer::from_env(),
+            deferred_started: false,
+        };
+
+        // Load user configuration in the background so it cannot delay startup
+        let load_config = Task::perform(
+            async {
+                match migrate_user_config() {
+                    Ok(Some(from)) => tracing::info!("Migrated configuration from {}", from),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to migrate configuration: {}", e),
+                }
+                read_user_config()
+            },
+            |(context, config)| cosmic::Action::App(Message::ConfigLoaded(context, config)),
+        );
+
+        // Everything else waits until the icon is shown
+        let deferred = Task::perform(
+            tokio::time::sleep(Duration::from_millis(STARTUP_BUDGET_MS)),
+            |()| cosmic::Action::App(Message::DeferredStartup),
+        );
+
+        // Without the panel, the edge strip is the way to show the keyboard
+        let edge_strip = applet.sync_edge_strip();
+
+        applet.startup.mark(StartupPhase::Init);
+        (applet, Task::batch([load_config, deferred, edge_strip]))
+    }
+
+    /// Subscribe to events only when actively dragging or resizing (Task 7.5).
+    ///
+    /// Performance critical: Return `Subscription::none()` when idle to avoid
+    /// processing all window events in the system. This is the key difference
+    /// between responsive and laggy applets - the libcosmic example applet has
+    /// no subscription at all when idle. The only exceptions are the D-Bus
+    /// service stream and, when the keyboard picks its own palette, a minute
+    /// timer or the light sensor stream; none of them listens to window
+    /// events.
+    fn subscription(&self) -> cosmic::iced_futures::Subscription<Self::Message> {
+        use cosmic::iced_futures::Subscription;
+
+        let mut subscriptions: Vec<Subscription<Message>> = Vec::new();
+
+        // Subscription for drag/resize mouse events
+        if self.is_dragging || self.resize_edge.is_some() {
+            subscriptions.push(event::listen_with(|event, _, _id| match event {
+                Event::Mouse(mouse_event) => match mouse_event {
+                    mouse::Event::CursorMoved { position } => Some(Message::CursorMoved(position)),
+                    mouse::Event::ButtonReleased(mouse::Button::Left) => {
+                        // End drag/resize on mouse release
+                        Some(Message::DragEnd)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            }));
+        }
+
+        // Pointer motion and prediction updates queued for the next frame
+        if !self.frame_queue.is_empty() {
+            subscriptions.push(window::frames().map(|_| Message::FrameFlush));
+        }
+
+        // Output announcements: the output the keyboard is pinned to by
+        // name, the density of each output for the touch target check, and
+        // its scale, announced again when the user changes it
+        if self.wants_output_events() {
+            subscriptions.push(event::listen_with(|event, _, _id| match event {
+                Event::PlatformSpecific(event::PlatformSpecific::Wayland(
+                    WaylandEvent::Output(output_event, output),
+                )) => match output_event {
+                    OutputEvent::Created(Some(info)) | OutputEvent::InfoUpdate(info) => {
+                        let density = info
+                            .logical_size
+                            .and_then(|size| touch_target::pixels_per_mm(size, info.physical_size));
+                        let scale = output_scale_factor(info.scale_factor);
+                        Some(Message::OutputAdded(
+                            output,
+                            info.name,
+                            density,
+                            Some(scale),
+                        ))
+                    }
+                    OutputEvent::Created(None) => {
+                        Some(Message::OutputAdded(output, None, None, None))
+                    }
+                    OutputEvent::Removed => Some(Message::OutputRemoved(output)),
+                },
+                _ => None,
+            }));
+        }
+
+        // Reload the layout when its files are saved, while it is shown
+        if self.keyboard_visible && !self.layout_watcher.is_empty() {
+            let paths: Vec<PathBuf> = self.layout_watcher.paths().map(Path::to_path_buf).collect();
+            subscriptions.push(
+                Subscription::run_with_id(
+                    paths.clone(),
+                    cosmic::iced::stream::channel(4, move |output| {
+                        layout_watcher::watch(paths, output)
+                    }),
+                )
+                .map(|()| Message::LayoutFilesChanged),
+            );
+        }
+
+        // Letting the screen go idle once typing stopped
+        if self.idle_inhibitor.is_inhibiting() {
+            subscriptions.push(
+                time::every(Duration::from_secs(IDLE_INHIBIT_CHECK_SECS))
+                    .map(|_| Message::IdleInhibitTick),
+            );
+        }
+
+        // Keyboard palette chosen by the clock or the light sensor
+        if self.config.appearance.needs_clock() {
+            subscriptions.push(
+                time::every(Duration::from_secs(THEME_SCHEDULE_INTERVAL_SECS))
+                    .map(|_| Message::ThemeScheduleTick),
+            );
+        }
+        if self.config.appearance.needs_light_sensor() {
+            subscriptions.push(sensor::subscription().map(Message::LightSensor));
+        }
+
+        // Light/dark switches of the COSMIC theme, for the keyboard palette
+        // following it
+        subscriptions.push(
+            cosmic_config::config_subscription::<_, cosmic::cosmic_theme::ThemeMode>(
+                TypeId::of::<cosmic::cosmic_theme::ThemeMode>(),
+                cosmic::cosmic_theme::THEME_MODE_ID.into(),
+                cosmic::cosmic_theme::ThemeMode::VERSION,
+            )
+            .map(|update| Message::SystemThemeChanged(update.config.is_dark)),
+        );
+
+        // D-Bus control interface. This is one long-lived stream that only
+        // yields when a client calls in, so it does not wake us while idle.
+        // Registering the name waits until the icon is shown.
+        if self.deferred_started {
+            subscriptions.push(
+                dbus::subscription(self.dbus_state.clone(), self.controller.virtual_keyboard.journal())
+                    .map(Message::Dbus),
+            );
+        }
+
+        // Renderer subscriptions (Task 7.5)
+        if let Some(ref renderer) = self.keyboard_renderer {
+            // Animation subscription - tick on every frame during panel
+            // transitions. Frames are paced by the compositor's frame
+            // callbacks, so the slide runs at the display's refresh rate
+            // instead of a fixed timer that drifts against it.
+            if renderer.is_animating() {
+                subscriptions.push(window::frames().map(|_| Message::AnimationTick));
+            }
+
+            // Long press timer subscription
+            if renderer.has_pending_long_press() {
+                subscriptions.push(
+                    time::every(Duration::from_millis(LONG_PRESS_TIMER_INTERVAL_MS))
+                        .map(|_| Message::LongPressTimerTick),
+                );
+            }
+
+            // Stuck key watchdog, while keys are pressed, and the pointer
+            // leaving the keyboard before the keys were released
+            if renderer.has_pressed_keys() {
+                subscriptions.push(
+                    time::every(Duration::from_millis(STUCK_KEY_CHECK_INTERVAL_MS))
+                        .map(|_| Message::StuckKeyTimerTick),
+                );
+                subscriptions.push(event::listen_with(|event, _, id| match event {
+                    Event::Mouse(mouse::Event::CursorLeft)
+                    | Event::Touch(touch::Event::FingerLost { .. }) => {
+                        Some(Message::PointerLeft(id))
+                    }
+                    _ => None,
+                }));
+            }
+
+            // Lifting the pointer away from the pressed keys it left
+            if !self.exited_keys.is_empty() {
+                subscriptions.push(event::listen_with(|event, _, _id| match event {
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                    | Event::Touch(touch::Event::FingerLifted { .. }) => {
+                        Some(Message::PointerLifted)
+                    }
+                    _ => None,
+                }));
+            }
+
+            // Chord timer subscription, while chord keys are held back
+            if self.chord_matcher.is_pending() {
+                subscriptions.push(
+                    time::every(Duration::from_millis(CHORD_TIMER_INTERVAL_MS))
+                        .map(|_| Message::ChordTimerTick),
+                );
+            }
+
+            // Redraw the emission indicator while it fades out
+            if self.config.emission_indicator
+                && is_pulsing(self.controller.virtual_keyboard.last_emission(), Instant::now())
+            {
+                subscriptions.push(window::frames().map(|_| Message::EmissionTick));
+            }
+
+            // Redraw held modifier keys while they pulse
+            if renderer.has_held_modifiers() {
+                subscriptions.push(window::frames().map(|_| Message::ModifierPulseTick));
+            }
+
+            // Toast timer subscription
+            if renderer.has_active_toast() {
+                subscriptions.push(
+                    time::every(Duration::from_millis(TOAST_TIMER_INTERVAL_MS))
+                        .map(|_| Message::ToastTimerTick),
+                );
+            }
+
+            // Physical keyboard input for a focused internal text field. Only
+            // delivered when the surface has OnDemand keyboard interactivity.
+            if renderer.focus.is_internal() {
+                subscriptions.push(event::listen_with(|event, _, _id| match event {
+                    Event::Keyboard(keyboard::Event::KeyPressed { key, text, .. }) => {
+                        Self::physical_key_to_keycode(&key, text.as_deref())
+                            .map(Message::PhysicalKeyPressed)
+                    }
+                    _ => None,
+                }));
+            }
+        }
+
+        // Sliding onto an alternative of the long press popup, and lifting
+        // the finger or button to type it
+        if self
+            .keyboard_renderer
+            .as_ref()
+            .is_some_and(|renderer| renderer.popup.is_some())
+        {
+            subscriptions.push(event::listen_with(|event, _, _id| match event {
+                Event::Mouse(mouse::Event::CursorMoved { position })
+                | Event::Touch(touch::Event::FingerMoved { position, .. }) => {
+                    Some(Message::PopupPointerMoved(position))
+                }
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                | Event::Touch(
+                    touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. },
+                ) => Some(Message::PopupReleased),
+                _ => None,
+            }));
+        }
+
+        // End of a piano mode slide: the finger or button is lifted
+        if self.slide.is_active() {
+            subscriptions.push(event::listen_with(|event, _, _id| match event {
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                | Event::Touch(touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. }) => {
+                    Some(Message::SlideEnd)
+                }
+                _ => None,
+            }));
+        }
+
+        // Raw touch events for keyboard-wide gestures, only while a touch
+        // gesture is bound and the keyboard is shown
+        if self.wants_touch_gestures() {
+            subscriptions.push(event::listen_with(|event, status, id| match event {
+                Event::Touch(touch_event) => Some(Message::Touch(id, touch_event, status)),
+                _ => None,
+            }));
+        }
+
+        // Key event journal dumps on SIGUSR1, once the icon is shown
+        if self.deferred_started {
+            let journal = self.controller.virtual_keyboard.journal();
+            subscriptions.push(
+                Subscription::run_with_id(
+                    std::any::TypeId::of::<EventJournal>(),
+                    cosmic::iced::stream::channel(4, move |output| {
+                        input_journal::dump_on_signal(journal, output)
+                    }),
+                )
+                .map(Message::EventsDumped),
+            );
+        }
+
+        // Text field focus from the input method, once the icon is shown
+        if self.config.auto_show && self.deferred_started {
+            subscriptions.push(
+                Subscription::run_with_id(
+                    std::any::TypeId::of::<InputMethodEvent>(),
+                    cosmic::iced::stream::channel(8, input_ime::run),
+                )
+                .map(Message::InputMethod),
+            );
+        }
+
+        // Gamepad and remote navigation while the keyboard is shown
+        if self.config.navigation.enabled && self.keyboard_visible {
+            if self.config.navigation.reads_gamepads() {
+                subscriptions.push(
+                    Subscription::run_with_id(
+                        std::any::TypeId::of::<NavCommand>(),
+                        cosmic::iced::stream::channel(16, gamepad::run),
+                    )
+                    .map(Message::Navigate),
+                );
+            }
+
+            // Keys forwarded while the surface has keyboard focus, unless
+            // they edit an internal text field
+            let editing = self
+                .keyboard_renderer
+                .as_ref()
+                .is_some_and(|renderer| renderer.focus.is_internal());
+            if !editing {
+                subscriptions.push(event::listen_with(|event, _, _id| match event {
+                    Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) => {
+                        Self::physical_key_to_navigation(&key).map(Message::Navigate)
+                    }
+                    _ => None,
+                }));
+            }
+        }
+
+        // Return combined subscriptions or none
+        if subscriptions.is_empty() {
+            Subscription::none()
+        } else {
+            Subscription::batch(subscriptions)
+        }
+    }
+
+    /// Handle surface close requests from the compositor.
+    ///
+    /// This handles both popup closes and keyboard surface closes.
+    /// Since we no longer have an idle subscription listening for window events,
+    /// this is the proper way to detect when surfaces are closed externally.
+    fn on_close_requested(&self, id: window::Id) -> Option<Message> {
+        if Some(id) == self.keyboard_surface || Some(id) == self.edge_strip {
+            Some(Message::KeyboardSurfaceClosed(id))
+        } else {
+            Some(Message::PopupClosed(id))
+        }
+    }
+
+    /// Handle messages emitted by the applet (Task 7.4, Task Group 5).
+    fn update(&mut self, message: Message) -> Task<Self::Message> {
+        // Apply the queued updates before anything that might depend on them
+        if !self.frame_queue.is_empty() && !coalesce::is_deferrable(&message) {
+            let flushed = self.apply_frame_queue();
+            return Task::batch([flushed, self.update(message)]);
+        }
+        self.record_message(&message);
+
+        match message {
+            Message::Toggle => {
+                // Close popup if open
+                if let Some(id) = self.popup.take() {
+                    return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
+                        cosmic::app::Action::Surface(destroy_popup(id)),
+                    ));
+                }
+
+                // Toggle keyboard visibility
+                if self.keyboard_visible {
+                    return Task::done(cosmic::Action::App(Message::Hide));
+                } else {
+                    return Task::done(cosmic::Action::App(Message::Show));
+                }
+            }
+            Message::TogglePopup => {
+                // Right-click: toggle popup menu visibility
+                if let Some(id) = self.popup.take() {
+                    // Close popup if already open
+                    return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
+                        cosmic::app::Action::Surface(destroy_popup(id)),
+                    ));
+                }
+
+                // Refresh the dictionaries offered in the settings menu
+                self.available_dictionaries = dictionary::discover(self.layout_providers.storage());
+
+                // Open popup menu using applet's default positioning
+                return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
+                    cosmic::app::Action::Surface(app_popup::<AppletModel>(
+                        |state: &mut AppletModel| {
+                            let new_id = Id::unique();
+                            state.popup = Some(new_id);
+                            state.core.applet.get_popup_settings(
+                                state.core.main_window_id().unwrap(),
+                                new_id,
+                                None,
+                                None,
+                                None,
+                            )
+                        },
+                        Some(Box::new(|state: &AppletModel| {
+                            // Build the popup menu content
+                            let mode_label = if state.window_state.is_floating {
+                                fl!("exclusive-mode")
+                            } else {
+                                fl!("floating-mode")
+                            };
+
+                            // One entry per gesture; tapping cycles its action
+                            let gesture_items = Gesture::ALL.map(|gesture| {
+                                let name = match gesture {
+                                    Gesture::TwoFingerTap => fl!("gesture-two-finger-tap"),
+                                    Gesture::SwipeDown => fl!("gesture-swipe-down"),
+                                    Gesture::LongPressSpace => fl!("gesture-long-press-space"),
+                                };
+                                let action = match state.config.gesture_bindings.action(gesture) {
+                                    Some(action) => ActionRegistry::spec(action)
+                                        .map_or_else(|| action.to_string(), |spec| (spec.title)()),
+                                    None => fl!("gesture-none"),
+                                };
+                                cosmic::applet::menu_button(widget::text::body(fl!(
+                                    "gesture-binding",
+                                    gesture = name,
+                                    action = action
+                                )))
+                                .on_press(Message::CycleGestureBinding(gesture))
+                            });
+                            let [two_finger_tap, swipe_down, long_press_space] = gesture_items;
+
+                            // Dead zones of the current mode; tapping cycles the size
+                            let insets = state.dead_zone_insets();
+                            let dead_zone_items = Edge::ALL.map(|edge| {
+                                let name = match edge {
+                                    Edge::Top => fl!("dead-zone-top"),
+                                    Edge::Right => fl!("dead-zone-right"),
+                                    Edge::Bottom => fl!("dead-zone-bottom"),
+                                    Edge::Left => fl!("dead-zone-left"),
+                                };
+                                cosmic::applet::menu_button(widget::text::body(fl!(
+                                    "dead-zone",
+                                    edge = name,
+                                    size = insets.get(edge)
+                                )))
+                                .on_press(Message::CycleDeadZone(edge))
+                            });
+                            let dead_zones_heading = if state.window_state.is_floating {
+                                fl!("dead-zones-floating")
+                            } else {
+                                fl!("dead-zones-docked")
+                            };
+
+                            // Keyboard palette; tapping cycles the mode
+                            let theme_mode = match state.config.appearance.mode {
+                                ThemeMode::System => fl!("keyboard-theme-system"),
+                                ThemeMode::Dark => fl!("keyboard-theme-dark"),
+                                ThemeMode::Light => fl!("keyboard-theme-light"),
+                                ThemeMode::Schedule => fl!("keyboard-theme-schedule"),
+                                ThemeMode::AmbientLight => fl!("keyboard-theme-ambient-light"),
+                            };
+
+                            let kiosk = state.kiosk;
+                            let separator = || {
+                                cosmic::applet::padded_control(divider::horizontal::default())
+                                    .padding([8, 0])
+                            };
+
+                            let mut content = list_column()
+                                .padding(8)
+                                .spacing(0)
+                                // Show Keyboard menu item
+                                .add(
+                                    cosmic::applet::menu_button(widget::text::body(fl!(
+                                        "show-keyboard"
+                                    )))
+                                    .on_press(Message::Action(AppAction::Show)),
+                                )
+                                // Hide Keyboard menu item
+                                .add(
+                                    cosmic::applet::menu_button(widget::text::body(fl!(
+                                        "hide-keyboard"
+                                    )))
+                                    .on_press(Message::Action(AppAction::Hide)),
+                                );
+
+                            // Collapse a shown keyboard to a bar, or expand it
+                            if state.keyboard_visible {
+                                let label = if state.collapsed {
+                                    fl!("expand-keyboard")
+                                } else {
+                                    fl!("collapse-keyboard")
+                                };
+                                content = content.add(
+                                    cosmic::applet::menu_button(widget::text::body(label))
+                                        .on_press(Message::Action(AppAction::ToggleCollapsed)),
+                                );
+                            }
+
+                            // Settings are hidden entirely in a locked-down kiosk
+                            if kiosk.can_change_settings() {
+                                content = content.add(separator());
+
+                                // Configuration profiles, each with its own
+                                // layout, size and rules
+                                let active = active_profile(&state.config.profile);
+                                content = content.add(cosmic::applet::padded_control(
+                                    widget::text::heading(fl!("profiles")),
+                                ));
+                                for name in profile_names(&state.config.profiles, active) {
+                                    let label = if name == active {
+                                        fl!("profile-selected", name = name)
+                                    } else {
+                                        name.to_string()
+                                    };
+                                    content = content.add(
+                                        cosmic::applet::menu_button(widget::text::body(label))
+                                            .on_press(Message::Action(AppAction::SetProfile(
+                                                name.to_string(),
+                                            ))),
+                                    );
+                                }
+                                content = content.add(separator());
+
+                                // Output the keyboard is shown on, once there
+                                // is a choice; a pinned output stays listed
+                                // while it is disconnected
+                                let mut output_names = state.outputs.names();
+                                let pinned = state.wanted_output();
+                                if let Some(name) = pinned {
+                                    if !output_names.contains(&name) {
+                                        output_names.push(name);
+                                    }
+                                }
+                                if output_names.len() > 1 || pinned.is_some() {
+                                    content = content.add(cosmic::applet::padded_control(
+                                        widget::text::heading(fl!("outputs")),
+                                    ));
+                                    let active_label = if pinned.is_none() {
+                                        fl!("output-selected", name = fl!("output-active"))
+                                    } else {
+                                        fl!("output-active")
+                                    };
+                                    content = content.add(
+                                        cosmic::applet::menu_button(widget::text::body(
+                                            active_label,
+                                        ))
+                                        .on_press(Message::SetOutput(None)),
+                                    );
+                                    for name in output_names {
+                                        let label = if pinned == Some(name) {
+                                            fl!("output-selected", name = name)
+                                        } else {
+                                            name.to_string()
+                                        };
+                                        content = content.add(
+                                            cosmic::applet::menu_button(widget::text::body(label))
+                                                .on_press(Message::SetOutput(Some(
+                                                    name.to_string(),
+                                                ))),
+                                        );
+                                    }
+                                    content = content.add(separator());
+                                }
+
+                                // Active layout and where it was loaded from
+                                if let Some(ref renderer) = state.keyboard_renderer {
+                                    let source = state
+                                        .layout_source
+                                        .map_or("custom", |source| source.label());
+                                    content = content.add(cosmic::applet::padded_control(
+                                        widget::text::body(fl!(
+                                            "active-layout",
+                                            name = renderer.layout.name.clone(),
+                                            source = source
+                                        )),
+                                    ));
+                                }
+
+                                // Layouts on the search path; the one picked
+                                // is remembered in the configuration
+                                if kiosk.can_switch_layout() {
+                                    for path in &state.available_layouts {
+                                        let name = palette::layout_name(path);
+                                        let label = if state.is_active_layout(path) {
+                                            fl!("layout-selected", name = name)
+                                        } else {
+                                            name.to_string()
+                                        };
+                                        // By file name, so a copy in a directory
+                                        // earlier on the search path takes over
+                                        let file_name = Path::new(path)
+                                            .file_name()
+                                            .map_or_else(|| path.clone(), |name| {
+                                                name.to_string_lossy().into_owned()
+                                            });
+                                        content = content.add(
+                                            cosmic::applet::menu_button(widget::text::body(label))
+                                                .on_press(Message::Action(AppAction::SwitchLayout(
+                                                    file_name,
+                                                ))),
+                                        );
+                                    }
+                                }
+
+                                content = content
+                                    // Toggle docked / floating mode
+                                    .add(
+                                        cosmic::applet::menu_button(widget::text::body(mode_label))
+                                            .on_press(Message::Action(
+                                                AppAction::ToggleFloatingMode,
+                                            )),
+                                    );
+                                // Only offered once the user resized the keyboard
+                                if state.window_state.height_set_by_user {
+                                    content = content.add(
+                                        cosmic::applet::menu_button(widget::text::body(fl!(
+                                            "fit-height"
+                                        )))
+                                        .on_press(Message::Action(AppAction::FitHeight)),
+                                    );
+                                }
+                                content = content
+                                    .add(
+                                        cosmic::applet::menu_button(widget::text::body(fl!(
+                                            "keyboard-theme",
+                                            mode = theme_mode
+                                        )))
+                                        .on_press(Message::CycleKeyboardTheme),
+                                    )
+                                    .add(separator())
+                                    // Gesture bindings
+                                    .add(cosmic::applet::padded_control(widget::text::heading(
+                                        fl!("gestures"),
+                                    )))
+                                    .add(two_finger_tap)
+                                    .add(swipe_down)
+                                    .add(long_press_space)
+                                    .add(separator())
+                                    // Dead zones, shown on the keyboard while
+                                    // the menu is open
+                                    .add(cosmic::applet::padded_control(widget::text::heading(
+                                        dead_zones_heading,
+                                    )));
+                                for item in dead_zone_items {
+                                    content = content.add(item);
+                                }
+                                content = content
+                                    .add(separator())
+                                    // Post-action policies
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.post_actions.hide_on_escape)
+                                            .label(fl!("hide-on-escape"))
+                                            .on_toggle(Message::SetHideOnEscape),
+                                    ))
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.post_actions.hide_after_enter)
+                                            .label(fl!("hide-after-enter"))
+                                            .on_toggle(Message::SetHideAfterEnter),
+                                    ))
+                                    // Gamepad and remote navigation
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.navigation.enabled)
+                                            .label(fl!("gamepad-navigation"))
+                                            .on_toggle(Message::SetNavigation),
+                                    ))
+                                    // Palm rejection
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.palm_rejection.enabled)
+                                            .label(fl!("palm-rejection"))
+                                            .on_toggle(Message::SetPalmRejection),
+                                    ))
+                                    // Key press feedback
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.key_feedback.sound)
+                                            .label(fl!("key-sounds"))
+                                            .on_toggle(Message::SetKeySounds),
+                                    ))
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.key_feedback.haptic)
+                                            .label(fl!("haptic-feedback"))
+                                            .on_toggle(Message::SetHapticFeedback),
+                                    ))
+                                    // Keeping the screen awake while typing
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.idle_inhibit.enabled)
+                                            .label(fl!("idle-inhibit"))
+                                            .on_toggle(Message::SetIdleInhibit),
+                                    ))
+                                    // Key emission indicator
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.emission_indicator)
+                                            .label(fl!("emission-indicator"))
+                                            .on_toggle(Message::SetEmissionIndicator),
+                                    ))
+                                    // Modifier and layout status strip
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.status_strip)
+                                            .label(fl!("status-strip"))
+                                            .on_toggle(Message::SetStatusStrip),
+                                    ))
+                                    // Dragging suggestions into applications
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.drag_suggestions)
+                                            .label(fl!("drag-suggestions"))
+                                            .on_toggle(Message::SetDragSuggestions),
+                                    ))
+                                    // Input method
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.auto_show)
+                                            .label(fl!("auto-show"))
+                                            .on_toggle(Message::SetAutoShow),
+                                    ))
+                                    // Whether the keyboard surface may take focus
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(
+                                            state.config.keyboard_interactivity
+                                                == Interactivity::OnDemand,
+                                        )
+                                        .label(fl!("keyboard-focus"))
+                                        .on_toggle(|_| {
+                                            Message::Action(AppAction::ToggleKeyboardInteractivity)
+                                        }),
+                                    ));
+
+                                // Learning typed words
+                                if kiosk.can_learn_words() {
+                                    content = content
+                                        .add(separator())
+                                        .add(cosmic::applet::padded_control(
+                                            widget::toggler(state.config.learning.enabled)
+                                                .label(fl!("learn-words"))
+                                                .on_toggle(Message::SetLearning),
+                                        ))
+                                        .add(cosmic::applet::padded_control(
+                                            widget::toggler(state.private_mode)
+                                                .label(fl!("private-mode"))
+                                                .on_toggle(|_| {
+                                                    Message::Action(AppAction::TogglePrivateMode)
+                                                }),
+                                        ))
+                                        .add(
+                                            cosmic::applet::menu_button(widget::text::body(fl!(
+                                                "clear-learned-words"
+                                            )))
+                                            .on_press(Message::ClearLearnedWords),
+                                        );
+
+                                    // Key press statistics and the heat map
+                                    content = content
+                                        .add(separator())
+                                        .add(cosmic::applet::padded_control(
+                                            widget::toggler(state.config.key_stats.enabled)
+                                                .label(fl!("key-stats"))
+                                                .on_toggle(Message::SetKeyStats),
+                                        ))
+                                        .add(cosmic::applet::padded_control(
+                                            widget::toggler(state.config.key_stats.heat_map)
+                                                .label(fl!("heat-map"))
+                                                .on_toggle(Message::SetHeatMap),
+                                        ))
+                                        .add(
+                                            cosmic::applet::menu_button(widget::text::body(fl!(
+                                                "clear-key-stats"
+                                            )))
+                                            .on_press(Message::ClearKeyStats),
+                                        );
+                                }
+
+                                // Text shortcuts
+                                content = content
+                                    .add(separator())
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.snippets.enabled)
+                                            .label(fl!("expand-snippets"))
+                                            .on_toggle(Message::SetSnippetsEnabled),
+                                    ))
+                                    .add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.double_space.enabled)
+                                            .label(fl!("double-space-period"))
+                                            .on_toggle(Message::SetDoubleSpacePeriod),
+                                    ));
+                                if let Some(ref app) = state.focused_app {
+                                    content = content.add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.snippets.active_in(Some(app)))
+                                            .label(fl!("expand-snippets-in", app = app.clone()))
+                                            .on_toggle(Message::SetSnippetsInFocusedApp),
+                                    ));
+                                }
+                                for snippet in &state.config.snippets.snippets {
+                                    content = content.add(
+                                        cosmic::applet::menu_button(widget::text::body(fl!(
+                                            "remove-snippet",
+                                            trigger = snippet.trigger.clone(),
+                                            expansion = snippet.expansion.clone()
+                                        )))
+                                        .on_press(Message::RemoveSnippet(snippet.trigger.clone())),
+                                    );
+                                }
+
+                                // Prediction dictionaries, one toggle each
+                                if !state.available_dictionaries.is_empty() {
+                                    content = content.add(separator()).add(
+                                        cosmic::applet::padded_control(widget::text::heading(
+                                            fl!("dictionaries"),
+                                        )),
+                                    );
+                                }
+                                for info in &state.available_dictionaries {
+                                    let id = info.id.clone();
+                                    content = content.add(cosmic::applet::padded_control(
+                                        widget::toggler(state.config.dictionaries.contains(&info.id))
+                                            .label(fl!(
+                                                "dictionary",
+                                                language = info.language.clone(),
+                                                format = info.format.label()
+                                            ))
+                                            .on_toggle(move |enabled| {
+                                                Message::SetDictionaryEnabled(id.clone(), enabled)
+                                            }),
+                                    ));
+                                }
+                            }
+
+                            // Quit menu item
+                            if kiosk.can_quit() {
+                                content = content.add(separator()).add(
+                                    cosmic::applet::menu_button(widget::text::body(fl!("quit")))
+                                        .on_press(Message::Action(AppAction::Quit)),
+                                );
+                            }
+
+                            Element::from(state.core.applet.popup_container(content))
+                                .map(cosmic::Action::App)
+                        })),
+                    )),
+                ));
+            }
+            Message::Show => {
+                // Close popup if open
+                if let Some(popup_id) = self.popup.take() {
+                    // First close popup, then show keyboard
+                    return Task::batch([
+                        cosmic::task::message(cosmic::Action::<Message>::Cosmic(
+                            cosmic::app::Action::Surface(destroy_popup(popup_id)),
+                        )),
+                        Task::done(cosmic::Action::App(Message::Show)),
+                    ]);
+                }
+
+                if self.keyboard_visible {
+                    return Task::none();
+                }
+
+                // Load the keyboard layout in the background (Task 7.2); the
+                // surface shows a placeholder until it is ready
+                let load_layout = self.load_keyboard_layout();
+
+                // Create layer surface for keyboard
+                let id = window::Id::unique();
+                let height = self.window_state.height as u32;
+                let width = self.window_state.width as u32;
+
+                // Configure based on floating vs docked mode
+                let (anchor, size, margin, exclusive_zone) = if self.window_state.is_floating {
+                    // Floating: corner anchor, explicit size, position via margins
+                    (
+                        Anchor::BOTTOM | Anchor::RIGHT,
+                        Some((Some(width), Some(height))),
+                        IcedMargin {
+                            top: 0,
+                            right: self.window_state.margin_right,
+                            bottom: self.window_state.margin_bottom,
+                            left: 0,
+                        },
+                        0, // No exclusive zone in floating mode
+                    )
+                } else {
+                    // Docked: full-width edge anchor with exclusive zone
+                    (
+                        self.docked_anchor(),
+                        Some((None, Some(height))),
+                        IcedMargin::default(),
+                        height as i32,
+                    )
+                };
+
+                let settings = SctkLayerSurfaceSettings {
+                    id,
+                    layer: self.surface_layer().to_sctk(),
+                    keyboard_interactivity: self.config.keyboard_interactivity.to_sctk(),
+                    input_zone: None,
+                    anchor,
+                    output: self.surface_output(),
+                    namespace: self.overrides.namespace().to_string(),
+                    margin,
+                    size,
+                    exclusive_zone,
+                    // The collapsed bar is shorter than any keyboard height
+                    size_limits: Limits::NONE
+                        .min_width(MIN_WIDTH)
+                        .max_width(MAX_WIDTH)
+                        .min_height(COLLAPSED_BAR_HEIGHT)
+                        .max_height(MAX_HEIGHT),
+                };
+
+                self.keyboard_surface = Some(id);
+                self.keyboard_visible = true;
+                // The new surface opens expanded, at the final height
+                self.height_transition = None;
+                self.collapsed = false;
+
+                tracing::info!(
+                    "Opening keyboard layer surface: {:?} floating={} height={} width={} exclusive_zone={} layer={} interactivity={}",
+                    id,
+                    self.window_state.is_floating,
+                    height,
+                    width,
+                    exclusive_zone,
+                    self.surface_layer().as_str(),
+                    self.config.keyboard_interactivity.as_str()
+                );
+
+                // Initialize the virtual keyboard (Task Group 5) after the
+                // surface request, so keymap setup does not delay it
+                return Task::batch([
+                    get_layer_surface(settings),
+                    load_layout,
+                    Task::done(cosmic::Action::App(Message::InitVirtualKeyboard)),
+                    self.load_dictionaries(),
+                    self.sync_dbus_state(true),
+                    self.sync_edge_strip(),
+                    self.run_hooks(HookEvent::Shown),
+                ]);
+            }
+            Message::LayoutLoaded(loaded) => {
+                self.cache_loaded_layout(&loaded);
+                self.apply_loaded_layout(*loaded);
+                return Task::batch([
+                    self.sync_keyboard_height(),
+                    self.sync_dbus_panel(),
+                    self.hook_layout_change(),
+                ]);
+            }
+            Message::LayoutsPreloaded(layouts) => {
+                self.startup.mark(StartupPhase::LayoutsPreloaded);
+                for loaded in &layouts {
+                    match &loaded.result {
+                        Ok(_) => tracing::debug!("Preloaded layout: {}", loaded.path),
+                        Err(e) => tracing::debug!("Cannot preload {}: {}", loaded.path, e),
+                    }
+                    // A layout loaded since startup is newer than the preload
+                    if self.layout_cache.get(&loaded.requested).is_none() {
+                        self.cache_loaded_layout(loaded);
+                    }
+                }
+                if self.available_layouts.is_empty() {
+                    if let Some(loaded) = layouts.first() {
+                        self.available_layouts = loaded.available.clone();
+                    }
+                }
+            }
+            Message::InitVirtualKeyboard => {
+                if let Err(e) = self.controller.virtual_keyboard.initialize() {
+                    tracing::error!("Failed to initialize virtual keyboard: {}", e);
+                    // Continue even if VK fails - keyboard will show but not emit events
+                } else {
+                    tracing::info!("Virtual keyboard initialized");
+
+                    // Use X11 XTEST or uinput where the Wayland protocol is
+                    // unavailable
+                    let backend = select_backend(&SessionInfo::from_env(), Backend::is_compiled);
+                    match create_key_sink(backend) {
+                        Ok(Some(sink)) => self.controller.virtual_keyboard.set_sink(sink),
+                        Ok(None) => {
+                            tracing::debug!("Using the {} backend", backend.label());
+                            if let Err(e) = self.controller.virtual_keyboard.connect_wayland() {
+                                tracing::error!("Cannot use the {}: {}", backend.label(), e);
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("Cannot use the {} backend: {}", backend.label(), e);
+                        }
+                    }
+                }
+            }
+            Message::Hide => {
+                // Close popup if open
+                if let Some(popup_id) = self.popup.take() {
+                    // First close popup, then hide keyboard
+                    return Task::batch([
+                        cosmic::task::message(cosmic::Action::<Message>::Cosmic(
+                            cosmic::app::Action::Surface(destroy_popup(popup_id)),
+                        )),
+                        Task::done(cosmic::Action::App(Message::Hide)),
+                    ]);
+                }
+
+                if !self.keyboard_visible {
+                    return Task::none();
+                }
+
+                // Save state before closing
+                self.save_state();
+                self.save_learned_words();
+                self.save_key_stats();
+
+                // Cleanup virtual keyboard (Task Group 5)
+                self.controller.virtual_keyboard.cleanup();
+
+                // Clear the renderer (Task 7.1 - clear on layout unload) and
+                // drop any layout still loading
+                self.keyboard_renderer = None;
+                self.layout_request += 1;
+                self.layout_loading = false;
+                self.prediction_panel = None;
+                self.reset_typing();
+                self.snippet_expander.reset();
+                self.refuse_confirmations();
+
+                self.keyboard_visible = false;
+                let idle_cookie = self.idle_inhibitor.stop();
+                let notify = Task::batch([
+                    self.sync_dbus_state(true),
+                    self.sync_edge_strip(),
+                    self.run_hooks(HookEvent::Hidden),
+                    self.release_idle_inhibit(idle_cookie),
+                ]);
+                if let Some(id) = self.keyboard_surface.take() {
+                    tracing::info!("Destroying keyboard layer surface: {:?}", id);
+                    return Task::batch([destroy_layer_surface(id), notify]);
+                }
+                return notify;
+            }
+            Message::Action(action) => {
+                return self.dispatch_action(action);
+            }
+            Message::Quit => {
+                // Save state before quitting
+                self.save_state();
+                self.save_learned_words();
+                self.save_key_stats();
+                // Release input and destroy the surfaces before exiting
+                return self
+                    .shutdown()
+                    .chain(Task::done(cosmic::Action::App(Message::Exit)));
+            }
+            Message::Exit => {
+                std::process::exit(0);
+            }
+            Message::PopupClosed(id) => {
+                if self.popup.as_ref() == Some(&id) {
+                    self.popup = None;
+                }
+            }
+            Message::Surface(action) => {
+                return cosmic::task::message(cosmic::Action::<Message>::Cosmic(
+                    cosmic::app::Action::Surface(action),
+                ));
+            }
+            Message::KeyboardSurfaceClosed(id) => {
+                if self.keyboard_surface == Some(id) {
+                    self.keyboard_surface = None;
+                    self.keyboard_visible = false;
+                    self.keyboard_renderer = None; // Clear renderer
+                    self.controller.virtual_keyboard.cleanup(); // Cleanup VK
+                    tracing::info!("Keyboard layer surface closed: {:?}", id);
+                    let idle_cookie = self.idle_inhibitor.stop();
+                    return Task::batch([
+                        self.sync_dbus_state(true),
+                        self.sync_edge_strip(),
+                        self.run_hooks(HookEvent::Hidden),
+                        self.release_idle_inhibit(idle_cookie),
+                    ]);
+                }
+                // The compositor may close the edge strip (e.g. its output
+                // went away); it is opened again on the next hide
+                if self.edge_strip == Some(id) {
+                    self.edge_strip = None;
+                    tracing::debug!("Edge strip closed: {:?}", id);
+                }
+                // Also check if this was the preview surface
+                if self.preview_surface == Some(id) {
+                    self.preview_surface = None;
+                    tracing::debug!("Preview surface closed: {:?}", id);
+                }
+            }
+            Message::KeyboardSurfaceResized(id, width, height) => {
+                // PERFORMANCE: Ignore resize events for preview surface entirely.
+                // The preview is just visual feedback - we don't need to track its state.
+                if self.preview_surface == Some(id) {
+                    return Task::none();
+                }
+
+                if self.keyboard_surface == Some(id) {
+                    self.surface_size = Some((width, height));
+
+                    // PERFORMANCE: Skip state update during active drag/resize to prevent
+                    // widget rebuilds. The compositor sends Resized events in response to
+                    // our set_size() calls, but we don't want to update window_state until
+                    // the operation ends (handled in DragEnd).
+                    if self.resize_edge.is_some() || self.is_dragging {
+                        tracing::debug!("Skipping resize event during active drag/resize");
+                        return Task::none();
+                    }
+
+                    self.window_state.height = height;
+                    tracing::debug!("Keyboard resized to height {}", height);
+
+                    // Update exclusive zone if in exclusive mode
+                    let mut tasks = vec![Task::done(cosmic::Action::App(Message::SaveState))];
+                    if !self.window_state.is_floating {
+                        tasks.push(set_exclusive_zone(id, height as i32));
+                    }
+                    tasks.push(self.check_touch_targets());
+                    tasks.push(self.sync_dbus_state(false));
+                    return Task::batch(tasks);
+                }
+            }
+            Message::OutputAdded(output, name, density, scale) => {
+                self.output_densities.retain(|(known, _)| *known != output);
+                if let Some(density) = density {
+                    self.output_densities.push((output.clone(), density));
+                }
+                self.output_scales.retain(|(known, _)| *known != output);
+                if let Some(scale) = scale {
+                    self.output_scales.push((output.clone(), scale));
+                }
+                if let Some(ref name) = name {
+                    self.outputs.add(output.clone(), name.clone());
+                }
+                let wanted = name.is_some() && name.as_deref() == self.wanted_output();
+                if wanted && self.target_output.as_ref() != Some(&output) {
+                    tracing::info!("Output {} found", name.unwrap_or_default());
+                    self.target_output = Some(output);
+                    self.sync_scale_factor();
+                    return self.recreate_keyboard_surface();
+                }
+                if self.sync_scale_factor() {
+                    return self.sync_keyboard_height();
+                }
+                return self.check_touch_targets();
+            }
+            Message::OutputRemoved(output) => {
+                self.output_densities.retain(|(known, _)| *known != output);
+                self.output_scales.retain(|(known, _)| *known != output);
+                self.outputs.remove(&output);
+                // The compositor closes the keyboard surface on a removed output
+                if self.target_output.as_ref() == Some(&output) {
+                    tracing::info!("Output removed, keyboard returns to the active output");
+                    self.target_output = None;
+                }
+                if self.sync_scale_factor() {
+                    return self.sync_keyboard_height();
+                }
+            }
+            Message::ToggleFloatingMode => {
+                self.window_state.is_floating = !self.window_state.is_floating;
+                self.save_state();
+
+                // Update layer surface configuration
+                if let Some(id) = self.keyboard_surface {
+                    let height = self.window_state.height as u32;
+                    let width = self.window_state.width as u32;
+
+                    let mut tasks = if self.window_state.is_floating {
+                        // Switching TO floating: corner anchor + explicit size
+                        tracing::info!(
+                            "Switching to floating mode: width={} height={} margin_right={} margin_bottom={}",
+                            width,
+                            height,
+                            self.window_state.margin_right,
+                            self.window_state.margin_bottom
+                        );
+                        vec![
                             set_anchor(id, Anchor::BOTTOM | Anchor::RIGHT),
                             set_size(id, Some(width), Some(height)),
                             set_margin(
@@ -1055,403 +4936,1211 @@ impl cosmic::Application for AppletModel {
                             set_exclusive_zone(id, 0),
                         ]
                     } else {
-                        // Switching TO docked: full-width bottom
+                        // Switching TO docked: full-width edge
                         tracing::info!("Switching to docked mode: height={}", height);
                         vec![
-                            set_anchor(id, Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT),
+                            set_anchor(id, self.docked_anchor()),
                             set_size(id, None, Some(height)),
                             set_margin(id, 0, 0, 0, 0),
                             set_exclusive_zone(id, height as i32),
                         ]
                     };
-                    return Task::batch(tasks);
+                    tasks.push(self.sync_dbus_state(false));
+                    return Task::batch(tasks);
+                }
+                return self.sync_dbus_state(false);
+            }
+            Message::SaveState => {
+                self.save_state();
+            }
+            Message::DragStart => {
+                if self.window_state.is_floating
+                    && self.preview_surface.is_none()
+                    && self.kiosk.can_drag_resize()
+                {
+                    self.is_dragging = true;
+                    // Initialize pending values from current state
+                    self.pending_margin_right = self.window_state.margin_right;
+                    self.pending_margin_bottom = self.window_state.margin_bottom;
+                    tracing::debug!("Drag started - spawning preview surface");
+                    // Spawn preview surface for visual feedback
+                    return self.create_preview_surface();
+                }
+            }
+            Message::DragEnd => {
+                let mut tasks: Vec<Task<Message>> = Vec::new();
+
+                // Destroy preview surface if it exists
+                if let Some(preview_id) = self.preview_surface.take() {
+                    tracing::debug!("Destroying preview surface: {:?}", preview_id);
+                    tasks.push(destroy_layer_surface(preview_id));
+                }
+
+                if self.is_dragging {
+                    self.is_dragging = false;
+                    // Commit pending values to window_state
+                    self.window_state.margin_right = self.pending_margin_right;
+                    self.window_state.margin_bottom = self.pending_margin_bottom;
+                    self.save_state();
+                    tracing::debug!("Drag ended - applying final position to keyboard");
+
+                    // Apply final position to keyboard surface (single update)
+                    if let Some(keyboard_id) = self.keyboard_surface {
+                        tasks.push(set_margin(
+                            keyboard_id,
+                            0,
+                            self.pending_margin_right,
+                            self.pending_margin_bottom,
+                            0,
+                        ));
+                    }
+                }
+
+                if self.resize_edge.is_some() {
+                    self.resize_edge = None;
+                    // Commit pending values to window_state
+                    self.window_state.width = self.pending_width;
+                    self.window_state.height = self.pending_height;
+                    self.window_state.margin_right = self.pending_margin_right;
+                    self.window_state.margin_bottom = self.pending_margin_bottom;
+                    // The layout no longer picks the height
+                    self.window_state.height_set_by_user = true;
+                    self.save_state();
+                    tracing::debug!("Resize ended - applying final size to keyboard");
+
+                    // Apply final size and position to keyboard surface (single update)
+                    if let Some(keyboard_id) = self.keyboard_surface {
+                        tasks.push(set_size(
+                            keyboard_id,
+                            Some(self.pending_width as u32),
+                            Some(self.pending_height as u32),
+                        ));
+                        tasks.push(set_margin(
+                            keyboard_id,
+                            0,
+                            self.pending_margin_right,
+                            self.pending_margin_bottom,
+                            0,
+                        ));
+                    }
+                }
+
+                if !tasks.is_empty() {
+                    // Sizes reported while resizing were not announced
+                    tasks.push(self.sync_dbus_state(false));
+                    return Task::batch(tasks);
+                }
+            }
+            Message::ResizeStart(edge) => {
+                if self.window_state.is_floating
+                    && self.preview_surface.is_none()
+                    && self.kiosk.can_drag_resize()
+                {
+                    self.resize_edge = Some(edge);
+                    // The user takes over from a height still sliding
+                    self.height_transition = None;
+                    // Initialize pending values from current state
+                    self.pending_width = self.window_state.width;
+                    self.pending_height = self.window_state.height;
+                    self.pending_margin_right = self.window_state.margin_right;
+                    self.pending_margin_bottom = self.window_state.margin_bottom;
+                    tracing::debug!("Resize started on {:?} - spawning preview surface", edge);
+                    // Spawn preview surface for visual feedback
+                    return self.create_preview_surface();
+                }
+            }
+            Message::ResizeEnd => {
+                if self.resize_edge.is_some() {
+                    self.resize_edge = None;
+                    // Commit pending values to window_state (triggers single rebuild)
+                    self.window_state.width = self.pending_width;
+                    self.window_state.height = self.pending_height;
+                    self.window_state.margin_right = self.pending_margin_right;
+                    self.window_state.margin_bottom = self.pending_margin_bottom;
+                    self.save_state();
+                    tracing::debug!("Resize ended");
+                }
+            }
+            Message::CursorMoved(pos) => {
+                // Handled once per frame, for all the positions since
+                self.frame_queue.move_cursor(pos);
+            }
+            Message::FrameFlush => {
+                return self.apply_frame_queue();
+            }
+            Message::PreviewSurfaceCreated(_id) => {
+                // Preview surface created - nothing special to do
+                tracing::debug!("Preview surface created");
+            }
+            Message::PreviewSurfaceClosed(id) => {
+                // Preview surface was closed externally
+                if self.preview_surface == Some(id) {
+                    self.preview_surface = None;
+                    tracing::debug!("Preview surface closed externally: {:?}", id);
+                }
+            }
+            // ================================================================
+            // Renderer Message Handlers (Task 7.4, Task Group 5)
+            // ================================================================
+            Message::KeyPressed(identifier) => {
+                self.popup_closed_key = None;
+
+                // First, update visual state in the renderer
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.press_key(&identifier);
+                    tracing::debug!("Key pressed (visual): {}", identifier);
+                    self.boundaries
+                        .press(&identifier, renderer.key_row(&identifier));
+
+                    // A press on a piano mode panel may start a slide
+                    if renderer.is_piano_panel() && !self.slide.is_active() {
+                        self.slide.begin(&identifier, Instant::now());
+                    }
+                }
+
+                // A candidate that can be dragged out is typed on release
+                if self.config.drag_suggestions
+                    && identifier.starts_with(prediction::CANDIDATE_KEY_PREFIX)
+                {
+                    self.deferred_candidate = Some(identifier);
+                    return Task::none();
+                }
+
+                // With palm rejection, the key is typed once its contact
+                // lasted long enough; a key still held back by then is
+                // settled first
+                if self.config.palm_rejection.enabled {
+                    let earlier = self.settle_palm_hold(true);
+                    self.palm_held_key = Some((identifier, Instant::now()));
+                    return earlier.unwrap_or_else(Task::none);
+                }
+
+                return self.emit_key_press(identifier);
+            }
+            Message::KeyReleased(identifier) => {
+                self.exited_keys.retain(|key| *key != identifier);
+
+                // A candidate released without being dragged is typed now
+                if self.deferred_candidate.as_deref() == Some(identifier.as_str()) {
+                    self.deferred_candidate = None;
+                    let press = self.emit_key_press(identifier.clone());
+                    let release = self.update(Message::KeyReleased(identifier));
+                    return Task::batch([press, release]);
+                }
+
+                // A key held back by palm rejection is typed now, unless its
+                // contact was rejected
+                if self
+                    .palm_held_key
+                    .as_ref()
+                    .is_some_and(|(key, _)| *key == identifier)
+                {
+                    if let Some(press) = self.settle_palm_hold(true) {
+                        let release = self.update(Message::KeyReleased(identifier));
+                        return Task::batch([press, release]);
+                    }
+                }
+                if self.palm_rejected_key.as_deref() == Some(identifier.as_str()) {
+                    self.palm_rejected_key = None;
+                    if let Some(ref mut renderer) = self.keyboard_renderer {
+                        renderer.release_key(&identifier);
+                    }
+                    return Task::none();
+                }
+
+                // Releasing a key with its popup open types the pick
+                let popup_open = self
+                    .keyboard_renderer
+                    .as_ref()
+                    .and_then(|renderer| renderer.popup.as_ref())
+                    .is_some_and(|popup| popup.key == identifier);
+                if popup_open {
+                    return self.finish_popup();
+                }
+                if self.popup_closed_key.as_deref() == Some(identifier.as_str()) {
+                    self.popup_closed_key = None;
+                    return Task::none();
+                }
+
+                // Releasing the last held braille dot types the cell
+                if let Some(dot) = self.braille_dot(&identifier) {
+                    if let Some(cell) = self.braille_chord.release(dot) {
+                        self.type_braille_cell(cell);
+                    }
+                }
+
+                // First, update visual state in the renderer
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.release_key(&identifier);
+                    tracing::debug!("Key released (visual): {}", identifier);
+                    if !renderer.has_pressed_keys() {
+                        self.boundaries.end();
+                    }
+
+                    // The press went to an internal text field, so does the release
+                    if renderer.focus.take_consumed_key(&identifier) {
+                        return Task::none();
+                    }
+                }
+
+                // A piano mode slide already released this key
+                if self.slide.take_stale_release(&identifier) {
+                    return Task::none();
+                }
+
+                // Keys that formed a chord emit nothing on release; a held
+                // chord key released early is typed before its release
+                match self.chord_matcher.release(&identifier) {
+                    Some(held) => self.press_held_keys(&held),
+                    None => return Task::none(),
+                }
+
+                // Action to run once the key is released (`after` or policy)
+                let post_action = self
+                    .find_key_by_identifier(&identifier)
+                    .and_then(|key| self.config.post_actions.post_action(key))
+                    .map(str::to_string);
+
+                // A deferred space released before the long press threshold
+                // is a regular tap: type it now
+                let deferred_tap = self.deferred_space.as_deref() == Some(identifier.as_str());
+                if deferred_tap {
+                    self.deferred_space = None;
+                    if let Some(key) = self.find_key_by_identifier(&identifier).cloned() {
+                        self.handle_regular_key_press(&key);
+                    }
+                }
+                // Likewise for a key with alternatives released before its popup opened
+                if self.deferred_popup_key.as_deref() == Some(identifier.as_str()) {
+                    self.deferred_popup_key = None;
+                    if let Some(key) = self.find_key_by_identifier(&identifier).cloned() {
+                        self.handle_regular_key_press(&key);
+                    }
+                }
+
+                // Now handle input emission (Task Group 5)
+                // Clone the key data we need to avoid borrow issues
+                let key_info = self.find_key_by_identifier(&identifier).map(|key| {
+                    (
+                        key.code.clone(),
+                        key.sticky,
+                        key.stickyrelease,
+                        key.identifier.clone(),
+                        key.post_keys.clone(),
+                    )
+                });
+
+                if let Some((code, sticky, stickyrelease, id, post_keys)) = key_info {
+                    // Create a temporary Key struct with the needed fields
+                    let key = Key {
+                        code: code.clone(),
+                        sticky,
+                        stickyrelease,
+                        identifier: id,
+                        ..Key::default()
+                    };
+
+                    // Check if this is a modifier key
+                    if let Some(modifier) = KeyboardController::keycode_to_modifier(&code) {
+                        // Handle modifier key release
+                        self.handle_modifier_key_release(&key, modifier);
+                    } else {
+                        // Handle regular key release
+                        self.handle_regular_key_release(&key);
+                        self.tap_post_keys(&key, post_keys);
+                    }
+                }
+
+                if let Some(action) = post_action {
+                    return self.run_command(&action);
                 }
             }
-            Message::SaveState => {
-                self.save_state();
+            Message::SwitchPanel(panel_id) => {
+                // Dots held while leaving the braille panel are never released
+                self.braille_chord.reset();
+                let mut switched = false;
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    let shown = renderer.shown_panel_id().to_string();
+                    // Use switch_panel_with_toast which handles errors with toasts
+                    let success = renderer.switch_panel_with_toast(&panel_id);
+                    renderer.repair_invariants();
+                    if success {
+                        tracing::info!("Switching to panel: {}", panel_id);
+                        switched = renderer.shown_panel_id() != shown;
+                    } else {
+                        tracing::warn!("Failed to switch to panel: {}", panel_id);
+                    }
+                }
+                // Another panel is felt as well as seen
+                let haptic = if switched && self.config.key_feedback.boundaries {
+                    self.play_haptic(feedback::PANEL_SWITCH)
+                } else {
+                    Task::none()
+                };
+                return Task::batch([self.sync_keyboard_height(), self.sync_dbus_panel(), haptic]);
             }
-            Message::DragStart => {
-                if self.window_state.is_floating && self.preview_surface.is_none() {
-                    self.is_dragging = true;
-                    // Initialize pending values from current state
-                    self.pending_margin_right = self.window_state.margin_right;
-                    self.pending_margin_bottom = self.window_state.margin_bottom;
-                    tracing::debug!("Drag started - spawning preview surface");
-                    // Spawn preview surface for visual feedback
-                    return self.create_preview_surface();
+            Message::AnimationTick => {
+                let mut progress = None;
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    // Update animation progress
+                    renderer.repair_invariants();
+                    let completed = renderer.update_animation();
+                    progress = renderer.eased_animation_progress();
+                    if completed {
+                        tracing::debug!("Panel animation completed");
+                        self.sync_prediction_row();
+                    }
                 }
+                return self.step_height_transition(progress);
             }
-            Message::DragEnd => {
-                let mut tasks: Vec<Task<Message>> = Vec::new();
-
-                // Destroy preview surface if it exists
-                if let Some(preview_id) = self.preview_surface.take() {
-                    tracing::debug!("Destroying preview surface: {:?}", preview_id);
-                    tasks.push(destroy_layer_surface(preview_id));
+            Message::LongPressTimerTick => {
+                // A key held back by palm rejection may have lasted long enough
+                if let Some(press) = self.settle_palm_hold(false) {
+                    return press;
                 }
 
-                if self.is_dragging {
-                    self.is_dragging = false;
-                    // Commit pending values to window_state
-                    self.window_state.margin_right = self.pending_margin_right;
-                    self.window_state.margin_bottom = self.pending_margin_bottom;
-                    self.save_state();
-                    tracing::debug!("Drag ended - applying final position to keyboard");
+                let mut long_pressed = None;
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    // Check if long press threshold has been exceeded
+                    if renderer.check_long_press_threshold() {
+                        tracing::debug!("Long press detected");
 
-                    // Apply final position to keyboard surface (single update)
-                    if let Some(keyboard_id) = self.keyboard_surface {
-                        tasks.push(set_margin(
-                            keyboard_id,
-                            0,
-                            self.pending_margin_right,
-                            self.pending_margin_bottom,
-                            0,
-                        ));
+                        // A held space runs the long-press-space gesture
+                        let key = renderer.long_press_key_identifier().map(str::to_string);
+                        if key.is_some() && key == self.deferred_space {
+                            self.deferred_space = None;
+                            if let Some(identifier) = key {
+                                renderer.focus.consume_key(identifier);
+                            }
+                            return self.update(Message::Gesture(Gesture::LongPressSpace));
+                        }
+
+                        long_pressed = key;
                     }
                 }
 
-                if self.resize_edge.is_some() {
-                    self.resize_edge = None;
-                    // Commit pending values to window_state
-                    self.window_state.width = self.pending_width;
-                    self.window_state.height = self.pending_height;
-                    self.window_state.margin_right = self.pending_margin_right;
-                    self.window_state.margin_bottom = self.pending_margin_bottom;
-                    self.save_state();
-                    tracing::debug!("Resize ended - applying final size to keyboard");
+                // A held key with alternatives shows them in a popup
+                if let Some(identifier) =
+                    long_pressed.filter(|key| self.deferred_popup_key.as_ref() == Some(key))
+                {
+                    self.open_popup(&identifier);
+                }
+            }
+            Message::PopupPointerMoved(position) => {
+                let area = self.panel_area();
+                let scale = self.scale_factor;
+                if let Some(popup) = self
+                    .keyboard_renderer
+                    .as_mut()
+                    .and_then(|renderer| renderer.popup.as_mut())
+                {
+                    let point = (position.x - area.x, position.y - area.y);
+                    popup.selected = popup_direction_at(&popup.position, point, scale);
+                }
+            }
+            Message::PopupReleased => {
+                // Released away from the key: its own release may never come
+                let key = self
+                    .keyboard_renderer
+                    .as_ref()
+                    .and_then(|renderer| renderer.popup.as_ref())
+                    .map(|popup| popup.key.clone());
+                if key.is_some() {
+                    let task = self.finish_popup();
+                    self.popup_closed_key = key;
+                    return task;
+                }
+            }
+            Message::KeyEntered(identifier) => {
+                let boundary = match self.keyboard_renderer {
+                    Some(ref renderer) if renderer.has_pressed_keys() => {
+                        let row = renderer.key_row(&identifier);
+                        self.boundaries.enter(&identifier, row)
+                    }
+                    _ => {
+                        self.boundaries.end();
+                        None
+                    }
+                };
 
-                    // Apply final size and position to keyboard surface (single update)
-                    if let Some(keyboard_id) = self.keyboard_surface {
-                        tasks.push(set_size(
-                            keyboard_id,
-                            Some(self.pending_width as u32),
-                            Some(self.pending_height as u32),
-                        ));
-                        tasks.push(set_margin(
-                            keyboard_id,
-                            0,
-                            self.pending_margin_right,
-                            self.pending_margin_bottom,
-                            0,
-                        ));
+                if let Some(step) = self.slide.enter(&identifier, Instant::now()) {
+                    tracing::debug!("Slid onto key: {}", step.tap);
+                    let mut tasks = Vec::new();
+                    if let Some(origin) = step.release_origin {
+                        tasks.push(self.update(Message::KeyReleased(origin.clone())));
+                        self.slide.origin_released(&origin);
                     }
+                    tasks.push(self.update(Message::KeyPressed(step.tap.clone())));
+                    tasks.push(self.update(Message::KeyReleased(step.tap)));
+                    return Task::batch(tasks);
                 }
 
-                if !tasks.is_empty() {
-                    return Task::batch(tasks);
+                // Sliding onto another key ticks, twice onto another row
+                if let Some(pattern) = boundary.filter(|_| self.config.key_feedback.boundaries) {
+                    return self.play_haptic(pattern);
                 }
             }
-            Message::ResizeStart(edge) => {
-                if self.window_state.is_floating && self.preview_surface.is_none() {
-                    self.resize_edge = Some(edge);
-                    // Initialize pending values from current state
-                    self.pending_width = self.window_state.width;
-                    self.pending_height = self.window_state.height;
-                    self.pending_margin_right = self.window_state.margin_right;
-                    self.pending_margin_bottom = self.window_state.margin_bottom;
-                    tracing::debug!("Resize started on {:?} - spawning preview surface", edge);
-                    // Spawn preview surface for visual feedback
-                    return self.create_preview_surface();
+            Message::KeyExited(identifier) => {
+                // Held modifiers stay pressed: on touch screens, a second
+                // finger moves the pointer off them. A key whose popup is
+                // open is released by the popup.
+                let pressed = self.keyboard_renderer.as_ref().is_some_and(|renderer| {
+                    renderer.is_key_pressed(&identifier)
+                        && !renderer
+                            .popup
+                            .as_ref()
+                            .is_some_and(|popup| popup.key == identifier)
+                });
+                let modifier = self.find_key_by_identifier(&identifier).is_some_and(|key| {
+                    KeyboardController::keycode_to_modifier(&key.code).is_some()
+                });
+                if pressed && !modifier && !self.exited_keys.contains(&identifier) {
+                    self.exited_keys.push(identifier);
                 }
             }
-            Message::ResizeEnd => {
-                if self.resize_edge.is_some() {
-                    self.resize_edge = None;
-                    // Commit pending values to window_state (triggers single rebuild)
-                    self.window_state.width = self.pending_width;
-                    self.window_state.height = self.pending_height;
-                    self.window_state.margin_right = self.pending_margin_right;
-                    self.window_state.margin_bottom = self.pending_margin_bottom;
-                    self.save_state();
-                    tracing::debug!("Resize ended");
+            Message::PointerLifted => {
+                let still_pressed: Vec<String> = std::mem::take(&mut self.exited_keys)
+                    .into_iter()
+                    .filter(|identifier| {
+                        self.keyboard_renderer
+                            .as_ref()
+                            .is_some_and(|renderer| renderer.is_key_pressed(identifier))
+                    })
+                    .collect();
+                let releases: Vec<_> = still_pressed
+                    .into_iter()
+                    .map(|identifier| self.release_left_key(identifier))
+                    .collect();
+                return Task::batch(releases);
+            }
+            Message::SlideEnd => {
+                self.slide.end();
+                self.boundaries.end();
+            }
+            Message::StuckKeyTimerTick => {
+                let stuck = self
+                    .keyboard_renderer
+                    .as_mut()
+                    .map(|renderer| renderer.stuck_keys(Instant::now()))
+                    .unwrap_or_default();
+                // Release them as if the release had arrived, so a held
+                // character stops repeating
+                let releases: Vec<_> = stuck
+                    .into_iter()
+                    .map(|identifier| {
+                        tracing::warn!("Releasing stuck key: {}", identifier);
+                        self.update(Message::KeyReleased(identifier))
+                    })
+                    .collect();
+                return Task::batch(releases);
+            }
+            Message::PointerLeft(id) => {
+                if Some(id) != self.keyboard_surface {
+                    return Task::none();
+                }
+                let pressed = self
+                    .keyboard_renderer
+                    .as_ref()
+                    .map(KeyboardRenderer::pressed_key_ids)
+                    .unwrap_or_default();
+                let releases: Vec<_> = pressed
+                    .into_iter()
+                    .map(|identifier| self.release_left_key(identifier))
+                    .collect();
+                return Task::batch(releases);
+            }
+            Message::ClearModifier(modifier) => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    // Caps Lock is unlocked in the client application too
+                    if modifier == Modifier::CapsLock && renderer.is_caps_lock_active() {
+                        self.controller.toggle_caps_lock();
+                    }
+                    renderer.release_modifier(modifier);
+                    tracing::debug!("Cleared modifier from the status strip: {:?}", modifier);
+                }
+            }
+            Message::ChordTimerTick => {
+                let held = self.chord_matcher.expire(Instant::now());
+                self.press_held_keys(&held);
+            }
+            Message::LayoutFilesChanged => {
+                if self.layout_watcher.poll(self.layout_providers.storage()) {
+                    tracing::info!("Layout files changed, reloading");
+                    self.layout_cache.invalidate();
+                    self.reload_panel = self
+                        .keyboard_renderer
+                        .as_ref()
+                        .map(|renderer| renderer.current_panel_id.clone());
+                    return self.load_keyboard_layout();
+                }
+            }
+            Message::ShowToast(message, severity) => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.queue_toast(message, severity);
+                }
+            }
+            Message::DismissToast => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.dismiss_current_toast();
+                    renderer.show_next_toast();
+                }
+            }
+            Message::ToastAction(id) => {
+                // The only toast buttons accept D-Bus callers
+                let index = self.confirmations.iter().position(|(pending, _)| *pending == id);
+                if let Some(index) = index {
+                    let (_, confirmation) = self.confirmations.remove(index);
+                    confirmation.answer(true);
+                }
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.dismiss_current_toast();
+                    renderer.show_next_toast();
+                }
+            }
+            Message::ToastTimerTick => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    // Check for toast timeout and advance queue
+                    let _dismissed = renderer.handle_toast_timer_tick();
+                }
+            }
+            Message::EmissionTick => {
+                // Nothing to update; the view reads the time of the last emission
+            }
+            Message::ModifierPulseTick => {
+                // Nothing to update; the view reads how long the keys are held
+            }
+            Message::EventsDumped(result) => match result {
+                Ok(path) => {
+                    tracing::info!("Key event journal written to {}", path);
+                    if let Some(ref mut renderer) = self.keyboard_renderer {
+                        renderer.queue_toast(
+                            fl!("events-dumped", path = path.as_str()),
+                            ToastSeverity::Info,
+                        );
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to write the key event journal: {}", e),
+            },
+            Message::CollapsedBarTapped => {
+                let now = Instant::now();
+                if is_double_tap(self.collapsed_bar_tapped, now) {
+                    return self.set_collapsed(false);
+                }
+                self.collapsed_bar_tapped = Some(now);
+            }
+            Message::FocusTextField(id) => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    tracing::debug!("Internal text field focused: {}", id);
+                    renderer.focus.focus(id);
+                }
+            }
+            Message::BlurTextField => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    if renderer.focus.is_focused(TYPING_TEST_FIELD) {
+                        self.typing_test = None;
+                    }
+                    renderer.focus.blur();
+                }
+            }
+            Message::InsertText(text) => {
+                // Choosing a result ends the search
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.focus.blur();
+                }
+                self.controller.emit_text(&text);
+                self.reset_typing();
+                self.sync_prediction_row();
+            }
+            Message::InsertSymbol(glyph) => {
+                self.record_emoji_use(&glyph);
+                return self.update(Message::InsertText(glyph));
+            }
+            Message::SelectSymbolCategory(category) => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.symbol_category = category;
+                }
+            }
+            Message::Scrolled(id, offset) => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.set_scroll_offset(&id, offset as f32);
+                }
+            }
+            Message::StepDate(field, delta) => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.picked_date = date_picker::step(renderer.picked_date, field, delta);
+                }
+            }
+            Message::ResetDate => {
+                if let Some(ref mut renderer) = self.keyboard_renderer {
+                    renderer.picked_date = date_picker::now();
+                }
+            }
+            Message::InsertDate(insert) => {
+                let Some(picked) = self.keyboard_renderer.as_ref().map(|r| r.picked_date) else {
+                    return Task::none();
+                };
+                let text = self.config.date_picker.format(
+                    insert,
+                    picked,
+                    &date_picker::session_time_locale(),
+                );
+                self.controller.emit_text(&text);
+                self.reset_typing();
+                self.sync_prediction_row();
+            }
+            Message::ToggleFavoriteEmoji(glyph) => {
+                self.config.emoji_recents.toggle_favorite(&glyph);
+                self.sync_emoji_recents();
+                self.save_config();
+            }
+            Message::PhysicalKeyPressed(resolved) => {
+                if let Some(task) = self.route_to_text_field(&resolved) {
+                    return task;
+                }
+            }
+            Message::Navigate(command) => {
+                if !self.config.navigation.enabled || !self.keyboard_visible {
+                    return Task::none();
+                }
+                return self.navigate(command);
+            }
+            Message::SetPalmRejection(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.palm_rejection.enabled = enabled;
+                self.palm_filter.set_settings(self.config.palm_rejection);
+                self.save_config();
+            }
+            Message::SetKeySounds(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.key_feedback.sound = enabled;
+                self.save_config();
+            }
+            Message::SetHapticFeedback(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.key_feedback.haptic = enabled;
+                self.save_config();
+                self.sync_key_crossings();
+            }
+            Message::SetIdleInhibit(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.idle_inhibit.enabled = enabled;
+                self.save_config();
+                if !enabled {
+                    let cookie = self.idle_inhibitor.stop();
+                    return self.release_idle_inhibit(cookie);
+                }
+            }
+            Message::IdleInhibited(result) => {
+                let cookie = self.idle_inhibitor.inhibited(result);
+                return self.release_idle_inhibit(cookie);
+            }
+            Message::IdleInhibitTick => {
+                let release_after = self.config.idle_inhibit.release_after();
+                let cookie = self.idle_inhibitor.expire(Instant::now(), release_after);
+                return self.release_idle_inhibit(cookie);
+            }
+            Message::SetNavigation(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.navigation.enabled = enabled;
+                if !enabled {
+                    if let Some(ref mut renderer) = self.keyboard_renderer {
+                        renderer.nav_focus = None;
+                    }
+                }
+                self.save_config();
+            }
+
+            // ================================================================
+            // Configuration and D-Bus
+            // ================================================================
+            Message::ConfigLoaded(context, config) => {
+                self.startup.mark(StartupPhase::ConfigLoaded);
+                let surface_changed = config.layer != self.config.layer
+                    || config.keyboard_interactivity != self.config.keyboard_interactivity;
+                let ime_changed = config.ime != self.config.ime;
+                let layout_changed = config.layout != self.config.layout;
+                let key_gap_changed = config.theme.key_gap != self.config.theme.key_gap;
+                self.config_context = context;
+                self.config = config;
+                if self.braille.table() != self.config.braille_table {
+                    self.braille.set_table(self.config.braille_table);
+                }
+                self.palm_filter.set_settings(self.config.palm_rejection);
+                if ime_changed {
+                    // The layout may have loaded before the configuration
+                    let language = self
+                        .keyboard_renderer
+                        .as_ref()
+                        .and_then(|renderer| renderer.layout.language.clone());
+                    self.load_ime(language.as_deref());
+                }
+                // Config is loaded at startup and only read again by an
+                // import, which kiosk mode refuses, so the kiosk policy is
+                // fixed for the session
+                self.kiosk = self.config.kiosk;
+                if self.kiosk.enabled {
+                    tracing::info!("Kiosk mode enabled: {:?}", self.kiosk);
+                }
+                self.sync_emoji_recents();
+                self.sync_debug_overlay();
+                self.sync_heat_map();
+                self.sync_key_style();
+                self.sync_drag_suggestions();
+                self.sync_key_crossings();
+                self.sync_prediction_row();
+
+                let mut tasks = vec![
+                    self.sync_dbus_state(false),
+                    self.load_dictionaries(),
+                    self.sync_keyboard_theme(),
+                    self.sync_keyboard_height(),
+                ];
+                if surface_changed {
+                    tasks.push(self.recreate_keyboard_surface());
+                }
+                if layout_changed {
+                    tracing::info!("Configured layout: {:?}", self.config.layout);
+                    self.active_layout_path = self.config.layout.clone();
+                }
+                // A layout shown before the configuration arrived is replaced;
+                // the key gap is applied to the panels when a layout loads
+                if (layout_changed || key_gap_changed) && self.keyboard_visible {
+                    tasks.push(self.load_keyboard_layout());
+                }
+                return Task::batch(tasks);
+            }
+            Message::ConfigImported(result) => {
+                let mut tasks = vec![Self::load_learned_words()];
+                match result {
+                    Ok((summary, context, config)) => {
+                        let toast = match summary {
+                            Some(summary) => {
+                                tracing::info!("Imported settings ({})", summary);
+                                fl!("config-imported")
+                            }
+                            None => {
+                                tracing::info!("Reloaded settings");
+                                fl!("config-reloaded")
+                            }
+                        };
+                        // Imported layouts replace files that may be cached;
+                        // ConfigLoaded reloads the layout if its settings changed
+                        self.layout_cache.invalidate();
+                        let reloads = config.layout != self.config.layout
+                            || config.theme.key_gap != self.config.theme.key_gap;
+                        if !reloads && self.keyboard_visible {
+                            tasks.push(self.load_keyboard_layout());
+                        }
+                        tasks.push(self.update(Message::ConfigLoaded(context, config)));
+                        tasks.push(self.update(Message::ShowToast(toast, ToastSeverity::Info)));
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to import settings: {}", e);
+                        tasks.push(self.update(Message::ShowToast(
+                            fl!("config-import-failed", error = e),
+                            ToastSeverity::Warning,
+                        )));
+                    }
+                }
+                return Task::batch(tasks);
+            }
+            Message::Dbus(event) => match event {
+                DbusEvent::Ready(connection) => {
+                    self.dbus_connection = Some(connection);
+                    return self.sync_dbus_state(false);
+                }
+                DbusEvent::Unavailable(reason) => {
+                    tracing::warn!("Continuing without D-Bus control interface: {}", reason);
+                }
+                DbusEvent::Request(action) => {
+                    tracing::debug!("D-Bus request: {}", action);
+                    return self.dispatch_action(action);
+                }
+                DbusEvent::Confirm(confirmation) => self.ask_confirmation(confirmation),
+                DbusEvent::Snapshot(request) => {
+                    let area = self.panel_area();
+                    let snapshot = self
+                        .keyboard_renderer
+                        .as_ref()
+                        .map(|renderer| {
+                            KeyboardSnapshot::capture(renderer, area, self.scale_factor)
+                        })
+                        .unwrap_or_default();
+                    request.answer(snapshot);
+                }
+            },
+            Message::SetKeyboardInteractivity(interactivity) => {
+                if self.config.keyboard_interactivity == interactivity {
+                    return Task::none();
                 }
+                tracing::info!("Keyboard interactivity set to {}", interactivity.as_str());
+                self.config.keyboard_interactivity = interactivity;
+                self.save_config();
+                return Task::batch([self.sync_dbus_state(false), self.recreate_keyboard_surface()]);
             }
-            Message::CursorMoved(pos) => {
-                // Early return if not in any active drag/resize mode
-                // (This is defensive - subscription() should only send these when active)
-                if !self.is_dragging && self.resize_edge.is_none() {
-                    self.last_cursor_position = Some(pos);
+            Message::SetOutput(name) => {
+                if !self.kiosk.can_change_settings() || self.wanted_output() == name.as_deref() {
+                    return Task::none();
+                }
+                tracing::info!("Keyboard output set to {}", name.as_deref().unwrap_or("active"));
+                // Choosing an output at runtime replaces the command-line one
+                self.overrides.output = None;
+                self.window_state.output = name;
+                self.save_state();
+                self.target_output = self
+                    .wanted_output()
+                    .and_then(|name| self.outputs.find(name))
+                    .cloned();
+                self.sync_scale_factor();
+                return self.recreate_keyboard_surface();
+            }
+            Message::SetLayer(layer) => {
+                if self.surface_layer() == layer {
+                    return Task::none();
+                }
+                tracing::info!("Keyboard layer set to {}", layer.as_str());
+                // Choosing a layer at runtime replaces the command-line one
+                self.overrides.layer = None;
+                self.config.layer = layer;
+                self.save_config();
+                return Task::batch([self.sync_dbus_state(false), self.recreate_keyboard_surface()]);
+            }
+            Message::Touch(id, touch_event, status) => {
+                if Some(id) != self.keyboard_surface {
                     return Task::none();
                 }
 
-                // Use incremental delta from last position to prevent jumps when cursor
-                // leaves and re-enters the window (layer surfaces don't have pointer grab)
-                //
-                // PREVIEW APPROACH: Update the preview surface only (not keyboard surface).
-                // The keyboard stays unchanged during drag/resize for smooth performance.
-                // Final values are applied to keyboard when operation ends.
-
-                // Handle dragging with incremental updates
-                if self.is_dragging {
-                    if let Some(last_pos) = self.last_cursor_position {
-                        // Calculate incremental delta from last position (inverted for bottom-right anchor)
-                        let dx = (last_pos.x - pos.x) as i32;
-                        let dy = (last_pos.y - pos.y) as i32;
-
-                        // Clamp to prevent jumps when cursor re-enters after leaving window
-                        let max_delta = 30;
-                        let dx = dx.clamp(-max_delta, max_delta);
-                        let dy = dy.clamp(-max_delta, max_delta);
-
-                        // Apply to PENDING margins
-                        self.pending_margin_right = (self.pending_margin_right + dx).max(0);
-                        self.pending_margin_bottom = (self.pending_margin_bottom + dy).max(0);
-
-                        // DEDUPLICATION: Only send commands if margin values actually changed
-                        let margin_changed = self.pending_margin_right != self.last_preview_margin_right
-                            || self.pending_margin_bottom != self.last_preview_margin_bottom;
-
-                        // TIME DEBOUNCE: Only update at most once per 100ms
-                        let now = Instant::now();
-                        let time_ok = self.last_preview_update
-                            .map(|last| now.duration_since(last).as_millis() >= PREVIEW_UPDATE_INTERVAL_MS)
-                            .unwrap_or(true);
-
-                        if margin_changed && time_ok {
-                            if let Some(preview_id) = self.preview_surface {
-                                self.last_preview_margin_right = self.pending_margin_right;
-                                self.last_preview_margin_bottom = self.pending_margin_bottom;
-                                self.last_preview_update = Some(now);
-                                self.last_cursor_position = Some(pos);
-                                return set_margin(preview_id, 0, self.pending_margin_right, self.pending_margin_bottom, 0);
-                            }
+                let now = Instant::now();
+                let gesture = match touch_event {
+                    touch::Event::FingerPressed { id: touch::Finger(finger), position } => {
+                        // A palm resting in a dead zone is no gesture
+                        if self.dead_zone_insets().contains(
+                            (position.x, position.y),
+                            self.window_state.width,
+                            self.window_state.height,
+                        ) {
+                            return Task::none();
                         }
+                        let point = (position.x, position.y);
+                        self.palm_filter.finger_pressed(finger, point, now);
+                        // Touches no key or widget captured landed on the background
+                        let on_background = status == event::Status::Ignored;
+                        self.gesture_tracker
+                            .finger_pressed(finger, point, now, on_background);
+                        None
                     }
-                }
-
-                // Handle resizing with incremental updates
-                if let Some(edge) = self.resize_edge {
-                    if let Some(last_pos) = self.last_cursor_position {
-                        let dx = pos.x - last_pos.x;
-                        let dy = pos.y - last_pos.y;
-
-                        // Clamp incremental change to prevent jumps
-                        let max_delta = 30.0;
-                        let dx = dx.clamp(-max_delta, max_delta);
-                        let dy = dy.clamp(-max_delta, max_delta);
-
-                        // Work with PENDING values
-                        let mut new_width = self.pending_width;
-                        let mut new_height = self.pending_height;
-                        let mut new_right = self.pending_margin_right;
-                        let mut new_bottom = self.pending_margin_bottom;
-
-                        match edge {
-                            ResizeEdge::Left => {
-                                // Dragging left edge: decrease dx = increase width, increase right margin
-                                new_width = (new_width - dx).clamp(MIN_WIDTH, MAX_WIDTH);
-                                new_right = (new_right + dx as i32).max(0);
-                            }
-                            ResizeEdge::Top => {
-                                // Dragging top edge: decrease dy = increase height, increase bottom margin
-                                new_height = (new_height - dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
-                                new_bottom = (new_bottom + dy as i32).max(0);
-                            }
-                            ResizeEdge::TopLeft => {
-                                // Dragging top-left corner
-                                new_width = (new_width - dx).clamp(MIN_WIDTH, MAX_WIDTH);
-                                new_height = (new_height - dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
-                                new_right = (new_right + dx as i32).max(0);
-                                new_bottom = (new_bottom + dy as i32).max(0);
-                            }
-                            ResizeEdge::TopRight => {
-                                // Dragging top-right corner: width increases with dx
-                                new_width = (new_width + dx).clamp(MIN_WIDTH, MAX_WIDTH);
-                                new_height = (new_height - dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
-                                new_bottom = (new_bottom + dy as i32).max(0);
-                            }
-                            ResizeEdge::BottomLeft => {
-                                // Dragging bottom-left corner
-                                new_width = (new_width - dx).clamp(MIN_WIDTH, MAX_WIDTH);
-                                new_height = (new_height + dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
-                                new_right = (new_right + dx as i32).max(0);
-                            }
-                            ResizeEdge::BottomRight => {
-                                // Dragging bottom-right corner (anchor point)
-                                // Both dimensions increase with positive delta, no margin changes
-                                new_width = (new_width + dx).clamp(MIN_WIDTH, MAX_WIDTH);
-                                new_height = (new_height + dy).clamp(MIN_HEIGHT, MAX_HEIGHT);
-                            }
+                    touch::Event::FingerMoved { id: touch::Finger(finger), position } => {
+                        self.palm_filter.finger_moved(finger, (position.x, position.y));
+                        self.gesture_tracker.finger_moved(finger, (position.x, position.y));
+                        None
+                    }
+                    touch::Event::FingerLifted { id: touch::Finger(finger), position } => {
+                        // A palm is part of no gesture
+                        if self.palm_filter.finger_lifted(finger, now).is_some() {
+                            self.gesture_tracker.finger_lost(finger);
+                            None
+                        } else {
+                            let point = (position.x, position.y);
+                            self.gesture_tracker.finger_lifted(finger, point, now)
                         }
+                    }
+                    touch::Event::FingerLost { id: touch::Finger(finger), .. } => {
+                        self.palm_filter.finger_lost(finger);
+                        self.gesture_tracker.finger_lost(finger);
+                        None
+                    }
+                };
 
-                        // Update PENDING values
-                        self.pending_width = new_width;
-                        self.pending_height = new_height;
-                        self.pending_margin_right = new_right;
-                        self.pending_margin_bottom = new_bottom;
-
-                        // Convert to integer values for deduplication check
-                        let new_w = new_width as u32;
-                        let new_h = new_height as u32;
-
-                        // DEDUPLICATION: Only send commands if values actually changed
-                        // This eliminates redundant compositor round-trips
-                        let changed = new_w != self.last_preview_width
-                            || new_h != self.last_preview_height
-                            || new_right != self.last_preview_margin_right
-                            || new_bottom != self.last_preview_margin_bottom;
-
-                        // TIME DEBOUNCE: Only update at most once per 100ms
-                        let now = Instant::now();
-                        let time_ok = self.last_preview_update
-                            .map(|last| now.duration_since(last).as_millis() >= PREVIEW_UPDATE_INTERVAL_MS)
-                            .unwrap_or(true);
-
-                        if changed && time_ok {
-                            if let Some(preview_id) = self.preview_surface {
-                                self.last_preview_width = new_w;
-                                self.last_preview_height = new_h;
-                                self.last_preview_margin_right = new_right;
-                                self.last_preview_margin_bottom = new_bottom;
-                                self.last_preview_update = Some(now);
-                                self.last_cursor_position = Some(pos);
-                                return Task::batch([
-                                    set_size(preview_id, Some(new_w), Some(new_h)),
-                                    set_margin(preview_id, 0, new_right, new_bottom, 0),
-                                ]);
-                            }
-                        }
+                if let Some(gesture) = gesture {
+                    return self.update(Message::Gesture(gesture));
+                }
+            }
+            Message::Gesture(gesture) => {
+                // The input method may use the gesture itself
+                if let Some(ref mut engine) = self.ime {
+                    let output = engine.feed(ImeInput::Gesture(gesture));
+                    if output.consumed {
+                        self.apply_ime_output(output);
+                        return Task::none();
                     }
                 }
-
-                self.last_cursor_position = Some(pos);
+                let Some(action) = self.config.gesture_bindings.action(gesture) else {
+                    return Task::none();
+                };
+                tracing::debug!("Gesture {:?} -> {}", gesture, action);
+                let action = action.to_string();
+                return self.run_command(&action);
             }
-            Message::PreviewSurfaceCreated(_id) => {
-                // Preview surface created - nothing special to do
-                tracing::debug!("Preview surface created");
+            Message::CycleGestureBinding(gesture) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.gesture_bindings.cycle(gesture);
+                self.save_config();
             }
-            Message::PreviewSurfaceClosed(id) => {
-                // Preview surface was closed externally
-                if self.preview_surface == Some(id) {
-                    self.preview_surface = None;
-                    tracing::debug!("Preview surface closed externally: {:?}", id);
+            Message::SetHideOnEscape(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
                 }
+                self.config.post_actions.hide_on_escape = enabled;
+                self.save_config();
             }
-            // ================================================================
-            // Renderer Message Handlers (Task 7.4, Task Group 5)
-            // ================================================================
-            Message::KeyPressed(identifier) => {
-                // First, update visual state in the renderer
-                if let Some(ref mut renderer) = self.keyboard_renderer {
-                    renderer.press_key(&identifier);
-                    tracing::debug!("Key pressed (visual): {}", identifier);
+            Message::SetHideAfterEnter(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
                 }
-
-                // Now handle input emission (Task Group 5)
-                // Clone the key data we need to avoid borrow issues
-                let key_info = self.find_key_by_identifier(&identifier).map(|key| {
-                    (
-                        key.code.clone(),
-                        key.sticky,
-                        key.stickyrelease,
-                        key.identifier.clone(),
-                    )
-                });
-
-                if let Some((code, sticky, stickyrelease, id)) = key_info {
-                    // Create a temporary Key struct with the needed fields
-                    let key = Key {
-                        code: code.clone(),
-                        sticky,
-                        stickyrelease,
-                        identifier: id,
-                        ..Key::default()
-                    };
-
-                    // Check if this is a modifier key
-                    if let Some(modifier) = Self::keycode_to_modifier(&code) {
-                        // Handle modifier key press
-                        self.handle_modifier_key_press(&key, modifier);
-                    } else {
-                        // Handle regular key press
-                        self.handle_regular_key_press(&key);
-                    }
+                self.config.post_actions.hide_after_enter = enabled;
+                self.save_config();
+            }
+            Message::SetEmissionIndicator(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
                 }
+                self.config.emission_indicator = enabled;
+                self.save_config();
             }
-            Message::KeyReleased(identifier) => {
-                // First, update visual state in the renderer
+            Message::SetStatusStrip(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.status_strip = enabled;
+                self.save_config();
+            }
+            Message::SetDragSuggestions(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.drag_suggestions = enabled;
+                self.save_config();
+                self.sync_drag_suggestions();
+            }
+            Message::TextDragged(identifier) => {
+                // The candidate went to another application, so it is not
+                // typed here
+                if self.deferred_candidate.as_deref() == Some(identifier.as_str()) {
+                    self.deferred_candidate = None;
+                }
                 if let Some(ref mut renderer) = self.keyboard_renderer {
                     renderer.release_key(&identifier);
-                    tracing::debug!("Key released (visual): {}", identifier);
                 }
-
-                // Now handle input emission (Task Group 5)
-                // Clone the key data we need to avoid borrow issues
-                let key_info = self.find_key_by_identifier(&identifier).map(|key| {
-                    (
-                        key.code.clone(),
-                        key.sticky,
-                        key.stickyrelease,
-                        key.identifier.clone(),
-                    )
-                });
-
-                if let Some((code, sticky, stickyrelease, id)) = key_info {
-                    // Create a temporary Key struct with the needed fields
-                    let key = Key {
-                        code: code.clone(),
-                        sticky,
-                        stickyrelease,
-                        identifier: id,
-                        ..Key::default()
-                    };
-
-                    // Check if this is a modifier key
-                    if let Some(modifier) = Self::keycode_to_modifier(&code) {
-                        // Handle modifier key release
-                        self.handle_modifier_key_release(&key, modifier);
-                    } else {
-                        // Handle regular key release
-                        self.handle_regular_key_release(&key);
-                    }
+                tracing::debug!("Key dragged out of the keyboard: {}", identifier);
+            }
+            Message::SetDictionaryEnabled(id, enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
                 }
+                self.config.dictionaries.retain(|enabled_id| *enabled_id != id);
+                if enabled {
+                    self.config.dictionaries.push(id);
+                    self.config.dictionaries.sort();
+                }
+                self.save_config();
+                return self.load_dictionaries();
             }
-            Message::SwitchPanel(panel_id) => {
-                if let Some(ref mut renderer) = self.keyboard_renderer {
-                    // Use switch_panel_with_toast which handles errors with toasts
-                    let success = renderer.switch_panel_with_toast(&panel_id);
-                    if success {
-                        tracing::info!("Switching to panel: {}", panel_id);
-                    } else {
-                        tracing::warn!("Failed to switch to panel: {}", panel_id);
-                    }
+            Message::LearnedWordsLoaded(learned) => {
+                if let Some(ref learned) = learned {
+                    tracing::debug!("Loaded {} learned words", learned.len());
+                    learned.apply(self.predictor.engine_mut());
                 }
+                self.learned_words = learned;
             }
-            Message::AnimationTick => {
-                if let Some(ref mut renderer) = self.keyboard_renderer {
-                    // Update animation progress
-                    let completed = renderer.update_animation();
-                    if completed {
-                        tracing::debug!("Panel animation completed");
-                    }
+            Message::CycleDeadZone(edge) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                let floating = self.window_state.is_floating;
+                self.config.dead_zones.for_mode_mut(floating).cycle(edge);
+                tracing::info!(
+                    "Dead zone along the {} edge set to {} px",
+                    edge.as_str(),
+                    self.config.dead_zones.for_mode(floating).get(edge)
+                );
+                self.save_config();
+                return self.check_touch_targets();
+            }
+            Message::CycleKeyboardTheme => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                let appearance = &mut self.config.appearance;
+                appearance.mode = appearance.mode.next();
+                if !appearance.needs_light_sensor() {
+                    self.light_level = None;
                 }
+                self.save_config();
+                return self.sync_keyboard_theme();
             }
-            Message::LongPressTimerTick => {
-                if let Some(ref mut renderer) = self.keyboard_renderer {
-                    // Check if long press threshold has been exceeded
-                    if renderer.check_long_press_threshold() {
-                        tracing::debug!("Long press detected");
-                        // Long press popup handling would happen here
-                        // For now, we just log it
+            Message::ThemeScheduleTick => return self.sync_keyboard_theme(),
+            Message::LightSensor(event) => {
+                self.light_level = match event {
+                    LightEvent::Level(level) => Some(level),
+                    LightEvent::Unavailable(_) => None,
+                };
+                return self.sync_keyboard_theme();
+            }
+            Message::SystemThemeChanged(is_dark) => {
+                tracing::debug!("System theme changed (dark: {})", is_dark);
+                self.system_dark = Some(is_dark);
+                // A palette the keyboard picked itself stays; otherwise the
+                // keys and toasts are redrawn in the new system theme
+                if self.keyboard_palette.is_none() {
+                    return cosmic::command::set_theme(self.keyboard_theme());
+                }
+            }
+            Message::InputMethod(event) => match event {
+                InputMethodEvent::Activated => {
+                    self.text_field_focused = true;
+                    if !self.keyboard_visible {
+                        return self.update(Message::Show);
                     }
                 }
+                InputMethodEvent::Deactivated => {
+                    self.text_field_focused = false;
+                    return Task::perform(
+                        tokio::time::sleep(Duration::from_millis(AUTO_HIDE_DELAY_MS)),
+                        |()| cosmic::Action::App(Message::AutoHide),
+                    );
+                }
+                InputMethodEvent::Unavailable(reason) => {
+                    tracing::warn!("Cannot follow text field focus: {}", reason);
+                    self.text_field_focused = false;
+                }
+            },
+            Message::AutoHide => {
+                // Another field may have taken focus during the delay
+                if self.config.auto_show && !self.text_field_focused && self.keyboard_visible {
+                    return self.update(Message::Hide);
+                }
             }
-            Message::ShowToast(message, severity) => {
-                if let Some(ref mut renderer) = self.keyboard_renderer {
-                    renderer.queue_toast(message, severity);
+            Message::SetAutoShow(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
                 }
+                self.config.auto_show = enabled;
+                self.text_field_focused = false;
+                self.save_config();
             }
-            Message::DismissToast => {
-                if let Some(ref mut renderer) = self.keyboard_renderer {
-                    renderer.dismiss_current_toast();
-                    renderer.show_next_toast();
+            Message::DeferredStartup => return self.start_deferred_setup(),
+            Message::LocalizationLoaded => {
+                self.startup.mark(StartupPhase::Localized);
+            }
+            Message::SetLearning(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
                 }
+                self.config.learning.enabled = enabled;
+                self.save_config();
             }
-            Message::ToastTimerTick => {
-                if let Some(ref mut renderer) = self.keyboard_renderer {
-                    // Check for toast timeout and advance queue
-                    let _dismissed = renderer.handle_toast_timer_tick();
+            Message::ClearLearnedWords => {
+                if let Some(ref mut learned) = self.learned_words {
+                    learned.clear();
+                    self.learned_words_changed = true;
+                }
+                self.save_learned_words();
+                tracing::info!("Cleared learned words");
+                // Rebuild the engine without them
+                self.unload_dictionaries();
+                return self.load_dictionaries();
+            }
+            Message::KeyStatsLoaded(stats) => {
+                if let Some(ref stats) = stats {
+                    tracing::debug!("Loaded press counts of {} keys", stats.len());
+                }
+                self.key_stats = stats;
+                self.sync_heat_map();
+            }
+            Message::SetKeyStats(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.key_stats.enabled = enabled;
+                self.save_config();
+            }
+            Message::SetHeatMap(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.key_stats.heat_map = enabled;
+                self.save_config();
+                self.sync_heat_map();
+            }
+            Message::ClearKeyStats => {
+                if let Some(ref mut stats) = self.key_stats {
+                    stats.clear();
+                    self.key_stats_changed = true;
+                }
+                self.save_key_stats();
+                self.sync_heat_map();
+                tracing::info!("Cleared key statistics");
+            }
+            Message::SetSnippetsEnabled(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.snippets.enabled = enabled;
+                self.save_config();
+            }
+            Message::SetDoubleSpacePeriod(enabled) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                self.config.double_space.enabled = enabled;
+                self.save_config();
+            }
+            Message::SetSnippetsInFocusedApp(active) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                if let Some(ref app) = self.focused_app {
+                    self.config.snippets.set_active_in(app, active);
+                    self.save_config();
+                }
+            }
+            Message::RemoveSnippet(trigger) => {
+                if !self.kiosk.can_change_settings() {
+                    return Task::none();
+                }
+                if self.config.snippets.remove(&trigger) {
+                    self.save_config();
+                }
+            }
+            Message::DictionariesLoaded(ids, loaded) => {
+                // A newer set was requested meanwhile, or prediction was turned off
+                if self.loading_dictionaries.as_ref() != Some(&ids) {
+                    return Task::none();
+                }
+                self.loading_dictionaries = None;
+
+                for error in &loaded.errors {
+                    tracing::warn!("Failed to load dictionary {}", error);
                 }
+                if loaded.truncated {
+                    tracing::warn!(
+                        "Dictionaries exceed {} words, the rest is not used",
+                        dictionary::MAX_WORDS
+                    );
+                }
+                tracing::info!(
+                    "Word prediction uses {} words ({} from dictionaries)",
+                    loaded.engine.len(),
+                    loaded.words
+                );
+                let LoadedDictionaries { engine, bigrams, .. } = *loaded;
+                let engine = self.with_learned_words(engine);
+                self.predictor.set_engine(engine);
+                self.predictor.set_bigrams(bigrams);
+                self.loaded_dictionaries = ids;
+                self.sync_prediction_row();
             }
         }
         Task::none()
@@ -1471,6 +6160,14 @@ impl cosmic::Application for AppletModel {
             .on_press(Message::Toggle)
             .on_right_press(Message::TogglePopup);
 
+        self.startup.mark(StartupPhase::FirstView);
+
+        // The tooltip is added once the translations are loaded, so the
+        // first render does not wait for them
+        if !self.startup.is_marked(StartupPhase::Localized) {
+            return clickable.into();
+        }
+
         // Wrap with tooltip
         Element::from(self.core.applet.applet_tooltip::<Message>(
             clickable,
@@ -1487,7 +6184,7 @@ impl cosmic::Application for AppletModel {
             // Render the keyboard content using the renderer
             let keyboard_content = self.render_keyboard_content();
 
-            if self.window_state.is_floating {
+            if self.window_state.is_floating && !self.collapsed {
                 // In floating mode: use a grid-like layout for resize handles around content
                 // Layout structure:
                 // [TopLeft ][   Top    ][TopRight  ]
@@ -1565,6 +6262,24 @@ impl cosmic::Application for AppletModel {
                 // Docked mode: no drag/resize handles, just the keyboard content
                 keyboard_content
             }
+        } else if Some(id) == self.edge_strip {
+            // Edge strip: a faint accent line; any tap or swipe shows the keyboard
+            mouse_area(
+                container(Space::new(Length::Fill, Length::Fill))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .class(cosmic::style::Container::custom(|theme| {
+                        let accent = cosmic::iced::Color::from(theme.cosmic().accent_color());
+                        cosmic::widget::container::Style {
+                            background: Some(cosmic::iced::Background::Color(
+                                cosmic::iced::Color { a: 0.4, ..accent },
+                            )),
+                            ..Default::default()
+                        }
+                    })),
+            )
+            .on_press(Message::Show)
+            .into()
         } else if Some(id) == self.preview_surface {
             // Preview surface: semi-transparent outline showing future bounds
             container(Space::new(Length::Fill, Length::Fill))
@@ -1594,6 +6309,43 @@ impl cosmic::Application for AppletModel {
     }
 }
 
+/// Opens and reads the user configuration, falling back to the defaults
+/// for entries that do not parse.
+fn read_user_config() -> (Option<cosmic_config::Config>, Config) {
+    match cosmic_config::Config::new(APPLET_ID, Config::VERSION) {
+        Ok(context) => {
+            let config = Config::get_entry(&context).unwrap_or_else(|(errors, config)| {
+                for error in errors {
+                    tracing::warn!("Failed to load config entry: {:?}", error);
+                }
+                config
+            });
+            (Some(context), config)
+        }
+        Err(e) => {
+            tracing::warn!("Failed to open config: {:?}", e);
+            (None, Config::default())
+        }
+    }
+}
+
+/// Returns the panel and the row number, from 1, of a row's validation
+/// path (`panels[main].rows[2]`).
+fn row_of_path(path: &str) -> Option<(&str, usize)> {
+    let (panel, row) = path.strip_prefix("panels[")?.split_once("].rows[")?;
+    let row: usize = row.strip_suffix(']')?.parse().ok()?;
+    Some((panel, row + 1))
+}
+
+/// Returns the local time in minutes after midnight, for the palette
+/// schedule.
+fn minute_of_day() -> u16 {
+    use chrono::Timelike;
+
+    let now = chrono::Local::now();
+    (now.hour() * 60 + now.minute()) as u16
+}
+
 // ============================================================================
 // Applet Entry Point
 // ============================================================================
@@ -1603,26 +6355,48 @@ impl cosmic::Application for AppletModel {
 /// This function should be called from a separate binary entry point.
 /// It sets up the COSMIC applet runtime and launches the AppletModel.
 pub fn run() -> cosmic::iced::Result {
-    // Initialize localization using LANG environment variable directly
-    // Avoids slow D-Bus call via DesktopLanguageRequester::requested_languages()
-    let lang = std::env::var("LANG")
-        .or_else(|_| std::env::var("LC_ALL"))
-        .or_else(|_| std::env::var("LC_MESSAGES"))
-        .unwrap_or_else(|_| "en-US.UTF-8".to_string());
-
-    // Parse just the language part (e.g., "en_US.UTF-8" -> "en-US")
-    let lang_code = lang
-        .split('.')
-        .next()
-        .unwrap_or("en-US")
-        .replace('_', "-");
+    run_with(SurfaceOverrides::default())
+}
 
-    if let Ok(lang_id) = lang_code.parse::<i18n_embed::unic_langid::LanguageIdentifier>() {
-        crate::i18n::init(&[lang_id]);
-    }
+/// Run the applet with surface settings overriding the configuration
+/// (see [`crate::cli`]).
+pub fn run_with(overrides: SurfaceOverrides) -> cosmic::iced::Result {
+    // Localization is loaded in the background after the icon is shown
+    // (see the `startup` module)
+    startup::mark_process_start();
 
     // Run the applet (cosmic::applet::run handles logging initialization)
-    cosmic::applet::run::<AppletModel>(())
+    run_catching_panics(move || cosmic::applet::run::<AppletModel>(overrides))
+}
+
+/// Run the keyboard without the COSMIC panel.
+///
+/// The same model runs as a plain application with no main window: there
+/// is no tray icon or popup menu, and the keyboard is shown from the edge
+/// strip or through D-Bus.
+pub fn run_standalone(mut overrides: SurfaceOverrides) -> cosmic::iced::Result {
+    startup::mark_process_start();
+    overrides.standalone = true;
+
+    let settings = cosmic::app::Settings::default().no_main_window(true);
+    run_catching_panics(move || cosmic::app::run::<AppletModel>(settings, overrides))
+}
+
+/// Runs the application, exiting with status 101 if it panics.
+///
+/// A panic unwinds through the runtime, dropping the applet model; the
+/// virtual keyboard's Drop then releases any key still held down.
+fn run_catching_panics(
+    run: impl FnOnce() -> cosmic::iced::Result + std::panic::UnwindSafe,
+) -> cosmic::iced::Result {
+    match std::panic::catch_unwind(run) {
+        Ok(result) => result,
+        Err(payload) => {
+            let reason = panic_message(payload.as_ref());
+            tracing::error!("Applet panicked, input released: {}", reason);
+            std::process::exit(101);
+        }
+    }
 }
 
 // ============================================================================
@@ -1632,6 +6406,7 @@ pub fn run() -> cosmic::iced::Result {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::keycodes;
 
     /// Test: Applet initializes with correct icon name
     #[test]
@@ -1716,11 +6491,40 @@ mod tests {
 
         // Virtual keyboard should exist but not be initialized
         assert!(
-            !applet.virtual_keyboard.is_initialized(),
+            !applet.controller.virtual_keyboard.is_initialized(),
             "Virtual keyboard should not be initialized by default"
         );
     }
 
+    /// Test: Output announcements are only listened to while the keyboard
+    /// is shown or pinned, or the touch target check waits for them.
+    #[test]
+    fn test_output_events_only_when_needed() {
+        let mut applet = AppletModel::default();
+        applet.config.touch_target.min_size_mm = 0;
+        assert!(!applet.wants_output_events(), "Idle keyboard listens to outputs");
+
+        applet.config.touch_target.min_size_mm = 9;
+        assert!(applet.wants_output_events(), "No output density known yet");
+        applet.config.touch_target.min_size_mm = 0;
+
+        applet.keyboard_visible = true;
+        assert!(applet.wants_output_events());
+        applet.keyboard_visible = false;
+
+        applet.overrides.output = Some("HDMI-1".to_string());
+        assert!(applet.wants_output_events());
+    }
+
+    /// Test: Row validation paths name the panel and the row shown in the
+    /// uneven rows toast; paths inside a row do not.
+    #[test]
+    fn test_row_of_path() {
+        assert_eq!(row_of_path("panels[main].rows[2]"), Some(("main", 3)));
+        assert_eq!(row_of_path("panels[main].rows[2].cells[0]"), None);
+        assert_eq!(row_of_path("panels[main]"), None);
+    }
+
     /// Test: Window state has sensible defaults
     #[test]
     fn test_window_state_defaults() {
@@ -1762,6 +6566,32 @@ mod tests {
         );
     }
 
+    /// Test: A fast drag coalesced into one frame ends where the pointer
+    /// went, rather than one clamped step away.
+    #[test]
+    fn test_coalesced_drag_follows_pointer() {
+        use cosmic::iced::Point;
+
+        let mut applet = AppletModel::default();
+        applet.is_dragging = true;
+        applet.last_cursor_position = Some(Point::new(600.0, 400.0));
+        applet.pending_margin_right = 100;
+        applet.pending_margin_bottom = 100;
+
+        // Ten 20 px steps up and to the left before the next frame
+        for step in 1..=10 {
+            let offset = 20.0 * step as f32;
+            applet
+                .frame_queue
+                .move_cursor(Point::new(600.0 - offset, 400.0 - offset));
+        }
+        let _ = applet.apply_frame_queue();
+
+        assert_eq!(applet.pending_margin_right, 300);
+        assert_eq!(applet.pending_margin_bottom, 300);
+        assert_eq!(applet.last_cursor_position, Some(Point::new(400.0, 200.0)));
+    }
+
     /// Test: ResizeEdge variants exist
     #[test]
     fn test_resize_edge_variants() {
@@ -1853,7 +6683,7 @@ mod tests {
         assert_eq!(resolved.unwrap(), ResolvedKeycode::Character('a'));
 
         // Verify it's not a modifier
-        let modifier = AppletModel::keycode_to_modifier(&key.code);
+        let modifier = KeyboardController::keycode_to_modifier(&key.code);
         assert!(modifier.is_none(), "'a' should not be a modifier key");
     }
 
@@ -1865,27 +6695,27 @@ mod tests {
     fn test_modifier_key_activates_modifier_state() {
         // Test Shift detection
         let shift_code = KeyCode::Keysym("Shift_L".to_string());
-        let shift_modifier = AppletModel::keycode_to_modifier(&shift_code);
+        let shift_modifier = KeyboardController::keycode_to_modifier(&shift_code);
         assert_eq!(shift_modifier, Some(Modifier::Shift), "Shift_L should be Shift modifier");
 
         // Test Control detection
         let ctrl_code = KeyCode::Keysym("Control_L".to_string());
-        let ctrl_modifier = AppletModel::keycode_to_modifier(&ctrl_code);
+        let ctrl_modifier = KeyboardController::keycode_to_modifier(&ctrl_code);
         assert_eq!(ctrl_modifier, Some(Modifier::Ctrl), "Control_L should be Ctrl modifier");
 
         // Test Alt detection
         let alt_code = KeyCode::Keysym("Alt_L".to_string());
-        let alt_modifier = AppletModel::keycode_to_modifier(&alt_code);
+        let alt_modifier = KeyboardController::keycode_to_modifier(&alt_code);
         assert_eq!(alt_modifier, Some(Modifier::Alt), "Alt_L should be Alt modifier");
 
         // Test Super detection
         let super_code = KeyCode::Keysym("Super_L".to_string());
-        let super_modifier = AppletModel::keycode_to_modifier(&super_code);
+        let super_modifier = KeyboardController::keycode_to_modifier(&super_code);
         assert_eq!(super_modifier, Some(Modifier::Super), "Super_L should be Super modifier");
 
         // Test Meta detection (should map to Super)
         let meta_code = KeyCode::Keysym("Meta_L".to_string());
-        let meta_modifier = AppletModel::keycode_to_modifier(&meta_code);
+        let meta_modifier = KeyboardController::keycode_to_modifier(&meta_code);
         assert_eq!(meta_modifier, Some(Modifier::Super), "Meta_L should be Super modifier");
     }
 
@@ -1947,7 +6777,7 @@ mod tests {
         assert_eq!(active_modifiers[0], Modifier::Shift, "Active modifier should be Shift");
 
         // Verify modifier keycode mapping
-        let shift_keycode = AppletModel::modifier_to_keycode(Modifier::Shift);
+        let shift_keycode = KeyboardController::modifier_to_keycode(Modifier::Shift);
         assert_eq!(shift_keycode, keycodes::KEY_LEFTSHIFT, "Shift should map to LEFT_SHIFT keycode");
     }
 
@@ -2073,22 +6903,22 @@ mod tests {
     #[test]
     fn test_modifier_to_keycode_mapping() {
         assert_eq!(
-            AppletModel::modifier_to_keycode(Modifier::Shift),
+            KeyboardController::modifier_to_keycode(Modifier::Shift),
             keycodes::KEY_LEFTSHIFT,
             "Shift should map to KEY_LEFTSHIFT"
         );
         assert_eq!(
-            AppletModel::modifier_to_keycode(Modifier::Ctrl),
+            KeyboardController::modifier_to_keycode(Modifier::Ctrl),
             keycodes::KEY_LEFTCTRL,
             "Ctrl should map to KEY_LEFTCTRL"
         );
         assert_eq!(
-            AppletModel::modifier_to_keycode(Modifier::Alt),
+            KeyboardController::modifier_to_keycode(Modifier::Alt),
             keycodes::KEY_LEFTALT,
             "Alt should map to KEY_LEFTALT"
         );
         assert_eq!(
-            AppletModel::modifier_to_keycode(Modifier::Super),
+            KeyboardController::modifier_to_keycode(Modifier::Super),
             keycodes::KEY_LEFTMETA,
             "Super should map to KEY_LEFTMETA"
         );
@@ -2108,14 +6938,39 @@ mod tests {
             (KeyCode::Keysym("Super_L".to_string()), Some(Modifier::Super)),
             (KeyCode::Keysym("Super_R".to_string()), Some(Modifier::Super)),
             (KeyCode::Keysym("Meta_L".to_string()), Some(Modifier::Super)),
+            (KeyCode::Keysym("Caps_Lock".to_string()), Some(Modifier::CapsLock)),
             (KeyCode::Unicode('a'), None),
             (KeyCode::Keysym("Return".to_string()), None),
             (KeyCode::Keysym("BackSpace".to_string()), None),
         ];
 
         for (code, expected) in test_cases {
-            let result = AppletModel::keycode_to_modifier(&code);
+            let result = KeyboardController::keycode_to_modifier(&code);
             assert_eq!(result, expected, "Modifier detection failed for {:?}", code);
         }
     }
+
+    /// Test: Keys are drawn in the light or dark theme the system switched
+    /// to, unless the keyboard picked its own palette.
+    #[test]
+    fn test_key_style_follows_system_theme() {
+        let mut applet = AppletModel::default();
+        let style = crate::renderer::KeyStyle::default();
+
+        let _ = cosmic::Application::update(&mut applet, Message::SystemThemeChanged(true));
+        let dark = applet.keyboard_theme();
+        assert!(dark.cosmic().is_dark);
+
+        let _ = cosmic::Application::update(&mut applet, Message::SystemThemeChanged(false));
+        let light = applet.keyboard_theme();
+        assert!(!light.cosmic().is_dark);
+        assert_ne!(
+            style.background_color(&dark, false),
+            style.background_color(&light, false),
+            "Key background should change with the system theme"
+        );
+
+        applet.keyboard_palette = Some(Palette::Dark);
+        assert!(applet.keyboard_theme().cosmic().is_dark);
+    }
 }
@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Session recording for the applet.
+//!
+//! Converts applet messages to the anonymized [`RecordedEvent`]s written by
+//! the session recorder, and recorded events back to messages for replay.
+//! Messages that carry text (inserted text, text field input) or that only
+//! concern the panel icon and surfaces are not recorded.
+
+use crate::actions::{ActionRegistry, AppAction};
+use crate::recording::RecordedEvent;
+
+use super::Message;
+
+/// Returns the event recorded for a message, if the message is recorded.
+pub(super) fn recorded_event(message: &Message) -> Option<RecordedEvent> {
+    let event = match message {
+        Message::Show => RecordedEvent::Show,
+        Message::Hide => RecordedEvent::Hide,
+        Message::Toggle => RecordedEvent::Toggle,
+        Message::KeyPressed(key) => RecordedEvent::KeyPressed { key: key.clone() },
+        Message::KeyReleased(key) => RecordedEvent::KeyReleased { key: key.clone() },
+        Message::KeyEntered(key) => RecordedEvent::KeyEntered { key: key.clone() },
+        Message::KeyExited(key) => RecordedEvent::KeyExited { key: key.clone() },
+        Message::PointerLifted => RecordedEvent::PointerLifted,
+        Message::SlideEnd => RecordedEvent::SlideEnd,
+        Message::SwitchPanel(panel) => RecordedEvent::SwitchPanel {
+            panel: panel.clone(),
+        },
+        Message::AnimationTick => RecordedEvent::AnimationTick,
+        Message::LongPressTimerTick => RecordedEvent::LongPressTimerTick,
+        Message::ChordTimerTick => RecordedEvent::ChordTimerTick,
+        Message::Gesture(gesture) => RecordedEvent::Gesture { gesture: *gesture },
+        // Inserted text is content, not interaction; image paths name files
+        Message::Action(
+            AppAction::InsertText(_)
+            | AppAction::TypeText(_)
+            | AppAction::ScanImage(_)
+            | AppAction::CalcInput(_),
+        ) => return None,
+        Message::Action(action) => RecordedEvent::Action {
+            action: action.to_string(),
+        },
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Returns the message that replays a recorded event.
+///
+/// Returns `None` for actions the registry does not know (e.g. custom
+/// actions of the user who recorded the trace).
+pub(super) fn replayed_message(event: &RecordedEvent, actions: &ActionRegistry) -> Option<Message> {
+    let message = match event {
+        RecordedEvent::Show => Message::Show,
+        RecordedEvent::Hide => Message::Hide,
+        RecordedEvent::Toggle => Message::Toggle,
+        RecordedEvent::KeyPressed { key } => Message::KeyPressed(key.clone()),
+        RecordedEvent::KeyReleased { key } => Message::KeyReleased(key.clone()),
+        RecordedEvent::KeyEntered { key } => Message::KeyEntered(key.clone()),
+        RecordedEvent::KeyExited { key } => Message::KeyExited(key.clone()),
+        RecordedEvent::PointerLifted => Message::PointerLifted,
+        RecordedEvent::SlideEnd => Message::SlideEnd,
+        RecordedEvent::SwitchPanel { panel } => Message::SwitchPanel(panel.clone()),
+        RecordedEvent::AnimationTick => Message::AnimationTick,
+        RecordedEvent::LongPressTimerTick => Message::LongPressTimerTick,
+        RecordedEvent::ChordTimerTick => Message::ChordTimerTick,
+        RecordedEvent::Gesture { gesture } => Message::Gesture(*gesture),
+        RecordedEvent::Action { action } => match actions.parse(action) {
+            Ok(action) => Message::Action(action),
+            Err(e) => {
+                tracing::warn!("Skipping recorded action '{}': {}", action, e);
+                return None;
+            }
+        },
+    };
+    Some(message)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use super::*;
+    use crate::applet::AppletModel;
+    use crate::layout::{Cell, Key, KeyCode, Layout, Modifier, Panel, Row};
+    use crate::recording::{parse_trace, RecordedEntry};
+    use crate::renderer::KeyboardRenderer;
+
+    /// Feeds a recorded session through the applet's `update()`.
+    ///
+    /// Follow-up tasks returned by `update()` are not run: every message the
+    /// applet handled during the session, including those produced by
+    /// tasks, is already in the trace.
+    fn replay(applet: &mut AppletModel, entries: &[RecordedEntry]) {
+        let actions = ActionRegistry::new();
+        for entry in entries {
+            if let Some(message) = replayed_message(&entry.event, &actions) {
+                let _ = cosmic::Application::update(applet, message);
+            }
+        }
+    }
+
+    /// Reads a trace file (as written with `COSBOARD_RECORD`) and feeds it
+    /// through the applet's `update()`.
+    fn replay_file(applet: &mut AppletModel, path: impl AsRef<Path>) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+        let text = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Cannot read {}: {}", path.display(), e));
+        let entries = parse_trace(&text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        replay(applet, &entries);
+    }
+
+    /// Creates an applet showing a layout with a one-shot Shift and a key.
+    fn applet_with_layout() -> AppletModel {
+        let panel = Panel {
+            id: "main".to_string(),
+            rows: vec![Row {
+                cells: vec![
+                    Cell::Key(Key {
+                        label: "Shift".to_string(),
+                        code: KeyCode::Keysym("Shift_L".to_string()),
+                        identifier: Some("shift".to_string()),
+                        sticky: true,
+                        stickyrelease: true,
+                        ..Key::default()
+                    }),
+                    Cell::Key(Key {
+                        label: "a".to_string(),
+                        code: KeyCode::Unicode('a'),
+                        identifier: Some("key_a".to_string()),
+                        ..Key::default()
+                    }),
+                ],
+            }],
+            ..Panel::default()
+        };
+        let layout = Layout {
+            name: "Replay".to_string(),
+            default_panel_id: "main".to_string(),
+            panels: HashMap::from([("main".to_string(), panel)]),
+            ..Layout::default()
+        };
+
+        let mut applet = AppletModel::default();
+        applet.keyboard_renderer = Some(KeyboardRenderer::new(layout));
+        applet
+    }
+
+    /// Test: Recorded messages replay as the same messages; text is never recorded.
+    #[test]
+    fn test_message_round_trip() {
+        let actions = ActionRegistry::new();
+        let messages = [
+            Message::KeyPressed("shift".to_string()),
+            Message::SwitchPanel("numpad".to_string()),
+            Message::Action(AppAction::SwitchPanel("numpad".to_string())),
+        ];
+        for message in messages {
+            let event = recorded_event(&message).expect("message should be recorded");
+            let replayed = replayed_message(&event, &actions).expect("event should replay");
+            assert_eq!(format!("{:?}", replayed), format!("{:?}", message));
+        }
+
+        assert!(recorded_event(&Message::InsertText("secret".to_string())).is_none());
+        assert!(recorded_event(&Message::InsertSymbol("🔥".to_string())).is_none());
+        assert!(recorded_event(&Message::Action(AppAction::InsertText("secret".to_string()))).is_none());
+    }
+
+    /// Test: A replayed trace file drives the applet like the recorded session.
+    #[test]
+    fn test_replay_trace() {
+        let mut applet = applet_with_layout();
+        if applet.controller.virtual_keyboard.initialize().is_err() {
+            eprintln!("Skipping test: XKB initialization failed");
+            return;
+        }
+
+        replay_file(&mut applet, "tests/traces/one_shot_shift.jsonl");
+
+        let renderer = applet.keyboard_renderer.as_ref().unwrap();
+        assert!(!renderer.is_key_pressed("shift"));
+        assert!(!renderer.is_key_pressed("key_a"));
+        assert!(
+            !renderer.is_modifier_active(Modifier::Shift),
+            "One-shot Shift should clear after the replayed key"
+        );
+        assert!(!applet.controller.virtual_keyboard.pending_events().is_empty());
+    }
+
+    /// Test: A key the finger slid off is released when the finger is
+    /// lifted elsewhere; a held modifier stays pressed.
+    #[test]
+    fn test_replay_slide_off_key() {
+        let mut applet = applet_with_layout();
+        if applet.controller.virtual_keyboard.initialize().is_err() {
+            eprintln!("Skipping test: XKB initialization failed");
+            return;
+        }
+        if let Some(Cell::Key(shift)) = applet
+            .keyboard_renderer
+            .as_mut()
+            .and_then(|renderer| renderer.layout.panels.get_mut("main"))
+            .map(|panel| &mut panel.rows[0].cells[0])
+        {
+            shift.sticky = false;
+        }
+
+        let trace = r#"
+            {"at_ms":0,"event":"key_pressed","key":"shift"}
+            {"at_ms":100,"event":"key_pressed","key":"key_a"}
+            {"at_ms":120,"event":"key_exited","key":"shift"}
+            {"at_ms":180,"event":"key_exited","key":"key_a"}
+            {"at_ms":250,"event":"pointer_lifted"}
+        "#;
+        let entries = parse_trace(trace).unwrap();
+        replay(&mut applet, &entries);
+
+        let renderer = applet.keyboard_renderer.as_ref().unwrap();
+        assert!(
+            !renderer.is_key_pressed("key_a"),
+            "Key slid off stays pressed"
+        );
+        assert!(
+            renderer.is_key_pressed("shift"),
+            "Held modifier was released"
+        );
+        assert!(applet.exited_keys.is_empty());
+    }
+}
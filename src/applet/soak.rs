@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Soak sessions driving the applet model through long random sessions.
+//!
+//! Random show/hide, key press, panel switch, resize and layout reload steps
+//! are fed through the applet's `update()`, the way the runtime would, with
+//! the tasks it returns dropped. Nothing talks to a compositor: surfaces are
+//! never created, and the key events the virtual keyboard queues are taken
+//! and discarded after every step, standing in for the protocol.
+//!
+//! Along the way the session checks that no key, long press, drag or panel
+//! state is left behind, and that the memory held by the model stops growing
+//! once it is warmed up. Memory is measured by the caller: the soak test in
+//! `tests/soak.rs` installs a global allocator counting the bytes each
+//! thread holds, which only its own test binary should run under. The
+//! module is built with the `soak` feature:
+//!
+//! ```bash
+//! cargo test --features soak --test soak
+//! cargo test --features soak --test soak -- --ignored
+//! ```
+
+use std::path::Path;
+
+use cosmic::iced::Point;
+use cosmic::Application;
+
+use super::{AppletModel, LoadedLayout, Message, ResizeEdge};
+use crate::layout::{parse_layout_file, Cell, Modifier, DEFAULT_LAYOUT_FILE};
+use crate::renderer::key_identifier;
+
+/// Steps run before the memory baseline is taken, so caches and collection
+/// capacities reach their working size first.
+const WARMUP_STEPS: usize = 500;
+
+/// Memory the model may gain over a session without counting as a leak.
+const MAX_GROWTH_BYTES: isize = 64 * 1024;
+
+// ============================================================================
+// Session
+// ============================================================================
+
+/// Deterministic random numbers (xorshift64*), so a failing session replays
+/// from its seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number below `n` (which must not be 0).
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Returns a random element of `items`, if any.
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+}
+
+/// An applet driven by random steps.
+struct Session {
+    applet: AppletModel,
+    /// The default layout, as a background load would deliver it.
+    loaded: LoadedLayout,
+    /// Every panel of the layout, including the built-in ones.
+    panels: Vec<String>,
+    rng: Rng,
+    seed: u64,
+    step: usize,
+    /// Returns the bytes this thread holds.
+    live_bytes: fn() -> isize,
+}
+
+impl Session {
+    /// Creates a session, or `None` if the keymap cannot be compiled here.
+    fn new(seed: u64, live_bytes: fn() -> isize) -> Option<Self> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/layouts")
+            .join(DEFAULT_LAYOUT_FILE);
+        let path = path.display().to_string();
+        let result = parse_layout_file(&path).expect("default layout should parse");
+        let loaded = LoadedLayout {
+            request: 0,
+            requested: DEFAULT_LAYOUT_FILE.to_string(),
+            path: path.clone(),
+            source: None,
+            available: vec![path],
+            result: Ok(result),
+        };
+
+        let mut applet = AppletModel::default();
+        if applet.controller.virtual_keyboard.initialize().is_err() {
+            return None;
+        }
+        applet.controller.virtual_keyboard.cleanup();
+
+        // A preloaded layout is applied by Show without a background task
+        let _ = applet.update(Message::LayoutsPreloaded(vec![loaded.clone()]));
+
+        let mut session = Self {
+            applet,
+            loaded,
+            panels: Vec::new(),
+            rng: Rng(seed),
+            seed,
+            step: 0,
+            live_bytes,
+        };
+        session.show();
+        let renderer = session.applet.keyboard_renderer.as_ref()?;
+        session.panels = renderer.layout.panels.keys().cloned().collect();
+        session.panels.sort();
+        Some(session)
+    }
+
+    /// Handles a message, dropping the returned tasks.
+    fn send(&mut self, message: Message) {
+        let _ = self.applet.update(message);
+        // The compositor consumes the queued key events
+        let _ = self.applet.controller.virtual_keyboard.take_pending_events();
+    }
+
+    /// Asserts `condition`, naming the seed and step of the session.
+    #[track_caller]
+    fn check(&self, condition: bool, what: &str) {
+        assert!(
+            condition,
+            "{} (seed {}, step {})",
+            what, self.seed, self.step
+        );
+    }
+
+    /// Shows the keyboard, as the Show tasks would.
+    fn show(&mut self) {
+        self.send(Message::Show);
+        self.send(Message::InitVirtualKeyboard);
+
+        let renderer = self.applet.keyboard_renderer.as_ref();
+        self.check(renderer.is_some(), "shown keyboard has a layout");
+        if let Some(renderer) = renderer {
+            self.check(renderer.pressed_keys.is_empty(), "no key pressed on show");
+            for modifier in [
+                Modifier::Shift,
+                Modifier::Ctrl,
+                Modifier::Alt,
+                Modifier::Super,
+            ] {
+                self.check(
+                    !renderer.is_modifier_active(modifier),
+                    "no modifier active on show",
+                );
+            }
+        }
+    }
+
+    /// Hides the keyboard.
+    fn hide(&mut self) {
+        self.send(Message::Hide);
+        self.check(
+            self.applet.keyboard_renderer.is_none(),
+            "hidden keyboard has no layout",
+        );
+        self.check(
+            self.applet.controller.virtual_keyboard.held_keys().is_empty(),
+            "no key held after hide",
+        );
+    }
+
+    /// Identifiers of the keys on the current panel.
+    fn current_keys(&self) -> Vec<String> {
+        let Some(panel) = self
+            .applet
+            .keyboard_renderer
+            .as_ref()
+            .and_then(|renderer| renderer.current_panel())
+        else {
+            return Vec::new();
+        };
+        panel
+            .rows
+            .iter()
+            .flat_map(|row| &row.cells)
+            .filter_map(|cell| match cell {
+                Cell::Key(key) => Some(key_identifier(key)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Taps a key, sometimes holding it long enough for a long press.
+    fn tap_key(&mut self) {
+        let keys = self.current_keys();
+        let Some(key) = self.rng.pick(&keys).cloned() else {
+            return;
+        };
+        self.send(Message::KeyPressed(key.clone()));
+        if self.rng.below(20) == 0 {
+            self.send(Message::LongPressTimerTick);
+        }
+        self.send(Message::KeyReleased(key.clone()));
+
+        if let Some(renderer) = &self.applet.keyboard_renderer {
+            self.check(
+                !renderer.is_key_pressed(&key),
+                "released key is not pressed",
+            );
+            self.check(
+                !renderer.has_pending_long_press(),
+                "no long press after release",
+            );
+        }
+    }
+
+    /// Presses two keys together and releases them in reverse order.
+    fn roll_keys(&mut self) {
+        let keys = self.current_keys();
+        let (Some(first), Some(second)) =
+            (self.rng.pick(&keys).cloned(), self.rng.pick(&keys).cloned())
+        else {
+            return;
+        };
+        self.send(Message::KeyPressed(first.clone()));
+        self.send(Message::KeyPressed(second.clone()));
+        self.send(Message::KeyReleased(second));
+        self.send(Message::KeyReleased(first));
+
+        if let Some(renderer) = &self.applet.keyboard_renderer {
+            self.check(
+                renderer.pressed_keys.is_empty(),
+                "no key pressed after a roll",
+            );
+        }
+    }
+
+    /// Switches to a random panel and ticks the animation.
+    fn switch_panel(&mut self) {
+        let Some(panel) = self.rng.pick(&self.panels).cloned() else {
+            return;
+        };
+        self.send(Message::SwitchPanel(panel));
+        self.send(Message::AnimationTick);
+        self.send(Message::ToastTimerTick);
+
+        if let Some(renderer) = &self.applet.keyboard_renderer {
+            self.check(renderer.current_panel().is_some(), "current panel exists");
+        }
+    }
+
+    /// Resizes the keyboard, by the compositor or by dragging an edge.
+    fn resize(&mut self) {
+        let width = 300.0 + self.rng.below(1600) as f32;
+        let height = 150.0 + self.rng.below(350) as f32;
+        match self.rng.below(4) {
+            0 => self.send(Message::ToggleFloatingMode),
+            1 => {
+                if let Some(id) = self.applet.keyboard_surface {
+                    self.send(Message::KeyboardSurfaceResized(id, width, height));
+                }
+            }
+            _ => {
+                let edges = [ResizeEdge::Top, ResizeEdge::Left, ResizeEdge::TopLeft];
+                let edge = *self.rng.pick(&edges).unwrap_or(&ResizeEdge::Top);
+                if self.rng.below(2) == 0 {
+                    self.send(Message::ResizeStart(edge));
+                } else {
+                    self.send(Message::DragStart);
+                }
+                for _ in 0..self.rng.below(8) {
+                    let x = self.rng.below(width as usize) as f32;
+                    let y = self.rng.below(height as usize) as f32;
+                    self.send(Message::CursorMoved(Point::new(x, y)));
+                    // Some moves arrive within the same frame
+                    if self.rng.below(2) == 0 {
+                        self.send(Message::FrameFlush);
+                    }
+                }
+                self.send(Message::DragEnd);
+            }
+        }
+
+        self.check(
+            self.applet.resize_edge.is_none() && !self.applet.is_dragging,
+            "no drag or resize left running",
+        );
+        self.check(
+            self.applet.preview_surface.is_none(),
+            "no preview surface left",
+        );
+    }
+
+    /// Delivers the layout again, as a reload would.
+    fn reload_layout(&mut self) {
+        if !self.applet.keyboard_visible {
+            return;
+        }
+        let loaded = LoadedLayout {
+            request: self.applet.layout_request,
+            ..self.loaded.clone()
+        };
+        self.send(Message::LayoutLoaded(Box::new(loaded)));
+    }
+
+    /// Runs one random step.
+    fn run_step(&mut self) {
+        self.step += 1;
+        match self.rng.below(100) {
+            0..=4 => {
+                if self.applet.keyboard_visible {
+                    self.hide();
+                } else {
+                    self.show();
+                }
+            }
+            5..=59 => self.tap_key(),
+            60..=69 => self.roll_keys(),
+            70..=84 => self.switch_panel(),
+            85..=94 => self.resize(),
+            _ => self.reload_layout(),
+        }
+    }
+
+    /// Runs `steps` random steps, checking the memory held by the model
+    /// after the warmup.
+    fn run(&mut self, steps: usize) {
+        for _ in 0..WARMUP_STEPS {
+            self.run_step();
+        }
+        // Measure with the keyboard hidden, so the layout is not counted
+        self.hide();
+        let baseline = (self.live_bytes)();
+
+        for _ in 0..steps {
+            self.run_step();
+        }
+        self.hide();
+        let growth = (self.live_bytes)() - baseline;
+        self.check(
+            growth <= MAX_GROWTH_BYTES,
+            &format!("memory grew by {} bytes", growth),
+        );
+    }
+}
+
+/// Runs a session of `steps` random steps from `seed`, panicking with the
+/// seed and step when a check fails.
+///
+/// `live_bytes` returns the bytes the current thread holds. Returns `false`
+/// if the keymap cannot be compiled here, so nothing ran.
+pub fn run(seed: u64, steps: usize, live_bytes: fn() -> isize) -> bool {
+    let Some(mut session) = Session::new(seed, live_bytes) else {
+        return false;
+    };
+    session.run(steps);
+    true
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Startup ordering and timing for the applet.
+//!
+//! The panel shows the applet's icon once its first view is drawn, so
+//! everything `init()` does before returning delays the icon. `init()` only
+//! builds the model and starts reading the configuration; the rest of the
+//! setup (loading translations, preloading layouts, reading learned words
+//! and registering the D-Bus interface) starts in background tasks once
+//! [`STARTUP_BUDGET_MS`] have passed, after the icon is drawn. Until the
+//! translations are loaded, the icon is shown without its tooltip.
+//!
+//! Setting the [`STARTUP_TIMING_ENV`] environment variable logs when each
+//! [`StartupPhase`] finished, measured from the start of the process:
+//!
+//! ```bash
+//! COSBOARD_STARTUP_TIMING=1 cosboard-applet
+//! ```
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Environment variable that enables the startup timing log.
+pub const STARTUP_TIMING_ENV: &str = "COSBOARD_STARTUP_TIMING";
+
+/// Time from the start of the process to the first icon render, in
+/// milliseconds. Deferred setup starts once it has passed.
+pub const STARTUP_BUDGET_MS: u64 = 50;
+
+/// When the process started, as recorded by [`mark_process_start`].
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+
+/// Records the start of the process. Call it before anything else runs.
+pub fn mark_process_start() {
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+/// Steps of the applet startup, in the order they usually finish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupPhase {
+    /// `init()` returned the model.
+    Init,
+    /// The panel icon was drawn for the first time.
+    FirstView,
+    /// The user configuration was read.
+    ConfigLoaded,
+    /// The deferred setup was started.
+    Deferred,
+    /// The translations for the session language were loaded.
+    Localized,
+    /// The default layout was parsed ahead of the first Show.
+    LayoutsPreloaded,
+}
+
+impl StartupPhase {
+    /// Returns the name used in the timing log.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StartupPhase::Init => "init",
+            StartupPhase::FirstView => "first view",
+            StartupPhase::ConfigLoaded => "config loaded",
+            StartupPhase::Deferred => "deferred setup",
+            StartupPhase::Localized => "localized",
+            StartupPhase::LayoutsPreloaded => "layouts preloaded",
+        }
+    }
+}
+
+/// Times at which the startup phases finished.
+///
+/// Phases are recorded from `view()` as well as `update()`, hence the
+/// interior mutability.
+#[derive(Debug)]
+pub struct StartupTimer {
+    /// Start of the process.
+    start: Instant,
+    /// Whether finished phases are logged.
+    log: bool,
+    /// Finished phases and when they finished, after `start`.
+    phases: Mutex<Vec<(StartupPhase, Duration)>>,
+}
+
+impl Default for StartupTimer {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl StartupTimer {
+    /// Creates a timer that logs finished phases if `log` is set.
+    #[must_use]
+    pub fn new(log: bool) -> Self {
+        Self {
+            start: *PROCESS_START.get_or_init(Instant::now),
+            log,
+            phases: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Creates a timer that logs finished phases if [`STARTUP_TIMING_ENV`]
+    /// is set (to anything but `0`).
+    #[must_use]
+    pub fn from_env() -> Self {
+        let log = std::env::var_os(STARTUP_TIMING_ENV)
+            .is_some_and(|value| !value.is_empty() && value != "0");
+        Self::new(log)
+    }
+
+    /// Records that `phase` finished now.
+    ///
+    /// Only the first time a phase finishes is recorded. Returns the time
+    /// since the start of the process, or `None` if the phase was already
+    /// recorded.
+    pub fn mark(&self, phase: StartupPhase) -> Option<Duration> {
+        self.mark_at(phase, Instant::now())
+    }
+
+    /// Records that `phase` finished at `now`.
+    fn mark_at(&self, phase: StartupPhase, now: Instant) -> Option<Duration> {
+        let mut phases = self.phases.lock().unwrap_or_else(|e| e.into_inner());
+        if phases.iter().any(|(marked, _)| *marked == phase) {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.start);
+        phases.push((phase, elapsed));
+
+        if self.log {
+            tracing::info!(
+                "Startup: {} after {:.1} ms",
+                phase.as_str(),
+                elapsed.as_secs_f64() * 1000.0
+            );
+            if phase == StartupPhase::FirstView
+                && elapsed > Duration::from_millis(STARTUP_BUDGET_MS)
+            {
+                tracing::warn!(
+                    "Startup: first icon render exceeded the {} ms budget",
+                    STARTUP_BUDGET_MS
+                );
+            }
+        }
+        Some(elapsed)
+    }
+
+    /// Returns `true` if `phase` has finished.
+    #[must_use]
+    pub fn is_marked(&self, phase: StartupPhase) -> bool {
+        self.elapsed(phase).is_some()
+    }
+
+    /// Returns when `phase` finished, after the start of the process.
+    #[must_use]
+    pub fn elapsed(&self, phase: StartupPhase) -> Option<Duration> {
+        let phases = self.phases.lock().unwrap_or_else(|e| e.into_inner());
+        phases
+            .iter()
+            .find(|(marked, _)| *marked == phase)
+            .map(|(_, elapsed)| *elapsed)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Each phase is recorded once, relative to the process start.
+    #[test]
+    fn test_startup_phases_recorded_once() {
+        let timer = StartupTimer::new(false);
+        assert!(!timer.is_marked(StartupPhase::FirstView));
+
+        let first = timer.mark_at(
+            StartupPhase::FirstView,
+            timer.start + Duration::from_millis(20),
+        );
+        assert_eq!(first, Some(Duration::from_millis(20)));
+        assert_eq!(
+            timer.mark_at(
+                StartupPhase::FirstView,
+                timer.start + Duration::from_millis(90)
+            ),
+            None
+        );
+        assert_eq!(
+            timer.elapsed(StartupPhase::FirstView),
+            Some(Duration::from_millis(20))
+        );
+        assert!(!timer.is_marked(StartupPhase::Localized));
+    }
+}
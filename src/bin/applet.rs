@@ -14,6 +14,15 @@
 //! cargo run --bin cosboard-applet
 //! ```
 //!
+//! Flags (or `COSBOARD_*` environment variables) override where the
+//! keyboard surface is placed, for scripted and kiosk deployments:
+//!
+//! ```bash
+//! cosboard-applet --anchor top --layer top --output HDMI-1 --height 380
+//! ```
+//!
+//! Run `cosboard-applet --help` for all options.
+//!
 //! # Features
 //!
 //! - Shows a keyboard icon in the system tray
@@ -22,9 +31,21 @@
 //! - Communicates with main Cosboard application via D-Bus
 
 // Re-export the main cosboard crate's modules
-use cosboard::applet;
+use cosboard::cli::{self, Command};
 
 fn main() -> cosmic::iced::Result {
+    let overrides = match cli::from_env() {
+        Ok(Command::Run(overrides)) => overrides,
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     // Initialize logging for the applet
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -36,5 +57,5 @@ fn main() -> cosmic::iced::Result {
     tracing::info!("Starting Cosboard applet");
 
     // Run the applet
-    applet::run()
+    cosboard::run_with(overrides)
 }
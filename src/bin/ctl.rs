@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Cosboard Control Tool
+//!
+//! Command-line companion to the keyboard applet.
+//!
+//! # Usage
+//!
+//! ```bash
+//! cosboardctl doctor
+//! cosboardctl dump-events [FILE]
+//! cosboardctl config export FILE
+//! cosboardctl config import [--allow-hooks] FILE
+//! ```
+//!
+//! `doctor` probes the Wayland protocols, key emission backends, D-Bus
+//! service, layouts and configuration, and prints a report to help debug
+//! keys that do not type. The exit status is 0 when no check failed, and 1
+//! otherwise.
+//!
+//! `dump-events` asks the running keyboard for its journal of the last key
+//! events and prints it, or writes it to `FILE`. The keyboard asks the user
+//! to accept the request first.
+//!
+//! `config export` writes the configuration, user layouts and learned words
+//! to a single bundle file, for moving them to another machine. `config
+//! import` checks a bundle and imports it, then has a running keyboard
+//! reload its settings. A bundle setting hooks, which run commands, or the
+//! external input policy, which lets programs type, is refused unless
+//! `--allow-hooks` is given; this is the only way to import them.
+
+use cosboard::config::bundle;
+use cosboard::dbus::{DBUS_NAME, DBUS_PATH};
+use cosboard::{run_diagnostics, DiagnosticStatus};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: cosboardctl doctor
+       cosboardctl dump-events [FILE]
+       cosboardctl config export FILE
+       cosboardctl config import [--allow-hooks] FILE";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("doctor") if args.len() == 1 => {
+            let report = run_diagnostics().await;
+            println!("{}", report);
+            if report.status() == DiagnosticStatus::Error {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Some("dump-events") if args.len() <= 2 => {
+            match dump_events(args.get(1).map(Path::new)).await {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("cosboardctl: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some("config") if args.len() == 3 || args.len() == 4 => {
+            let allow_hooks = args.len() == 4 && args[2] == "--allow-hooks";
+            let path = Path::new(&args[args.len() - 1]);
+            let result = match args[1].as_str() {
+                "export" if args.len() == 3 => bundle::export_user_config(path)
+                    .map(|summary| println!("Exported {}", summary))
+                    .map_err(|e| e.to_string()),
+                "import" if args.len() == 3 || allow_hooks => {
+                    import_config(path, allow_hooks).await
+                }
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            };
+            match result {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("cosboardctl: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Fetches the key event journal from the running keyboard and prints it,
+/// or writes it to `file`.
+async fn dump_events(file: Option<&Path>) -> Result<(), String> {
+    let connection = zbus::Connection::session()
+        .await
+        .map_err(|e| format!("cannot connect to the session bus: {}", e))?;
+    let proxy = zbus::Proxy::new(&connection, DBUS_NAME, DBUS_PATH, DBUS_NAME)
+        .await
+        .map_err(|e| format!("cannot reach the keyboard: {}", e))?;
+    let journal: String = proxy
+        .call("DumpEvents", &())
+        .await
+        .map_err(|e| format!("cannot read the key events: {}", e))?;
+
+    match file {
+        // The journal holds what was typed; keep the file private
+        Some(path) => std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .and_then(|mut file| file.write_all(journal.as_bytes()))
+            .map_err(|e| format!("cannot write {}: {}", path.display(), e)),
+        None => {
+            print!("{}", journal);
+            Ok(())
+        }
+    }
+}
+
+/// Checks the bundle at `path` and imports it, with its hooks and external
+/// input policy only if `allow_hooks` is set, then has a running keyboard
+/// read the new settings.
+async fn import_config(path: &Path, allow_hooks: bool) -> Result<(), String> {
+    let summary = bundle::import_user_config(path, allow_hooks).map_err(|e| e.to_string())?;
+    println!("Imported {}", summary);
+
+    if let Some(proxy) = keyboard_proxy().await {
+        let (): () = proxy
+            .call("RunAction", &("reload_config",))
+            .await
+            .map_err(|e| format!("cannot reload the running keyboard: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Returns a proxy for the running keyboard, if it owns its D-Bus name.
+async fn keyboard_proxy() -> Option<zbus::Proxy<'static>> {
+    let connection = zbus::Connection::session().await.ok()?;
+    let proxy = zbus::Proxy::new(&connection, DBUS_NAME, DBUS_PATH, DBUS_NAME)
+        .await
+        .ok()?;
+    let dbus = zbus::fdo::DBusProxy::new(&connection).await.ok()?;
+    let name = zbus::names::BusName::try_from(DBUS_NAME).ok()?;
+    dbus.name_has_owner(name).await.ok()?.then_some(proxy)
+}
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Cosboard Standalone Keyboard
+//!
+//! Runs the keyboard without the COSMIC panel, for sessions with another
+//! panel or none at all. There is no tray icon: the keyboard is shown by
+//! tapping the thin strip along the screen edge, or through the D-Bus
+//! interface (`cosboardctl`, scripts, or D-Bus activation of
+//! `io.github.cosboard.Cosboard`).
+//!
+//! # Usage
+//!
+//! ```bash
+//! cosboard --anchor bottom --height 300
+//! ```
+//!
+//! Takes the same flags and `COSBOARD_*` environment variables as
+//! `cosboard-applet`; run `cosboard --help` for all options.
+
+use cosboard::cli::{self, Command};
+
+fn main() -> cosmic::iced::Result {
+    let usage = cli::USAGE.replacen("cosboard-applet", "cosboard", 1);
+    let overrides = match cli::from_env() {
+        Ok(Command::Run(overrides)) => overrides,
+        Ok(Command::Help) => {
+            println!("{}", usage);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, usage);
+            std::process::exit(2);
+        }
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("cosboard=info".parse().unwrap()),
+        )
+        .init();
+
+    tracing::info!("Starting Cosboard without the panel");
+
+    cosboard::run_standalone(overrides)
+}
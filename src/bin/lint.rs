@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Cosboard Layout Linter
+//!
+//! This binary parses and validates keyboard layout files, printing every
+//! warning the parser collects (missing metadata, invalid sizing, uneven row
+//! widths, dangling panel references, ...) without launching the keyboard.
+//!
+//! # Usage
+//!
+//! ```bash
+//! cargo run --bin cosboard-lint -- resources/layouts/example_qwerty.json
+//! ```
+//!
+//! The exit status is 0 when every layout parsed, even with warnings, and 1
+//! when any layout failed to parse.
+
+use cosboard::layout::parse_layout_file;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+
+    if paths.is_empty() {
+        eprintln!("Usage: cosboard-lint <layout.json>...");
+        return ExitCode::from(2);
+    }
+
+    let mut failed = false;
+
+    for path in &paths {
+        match parse_layout_file(path) {
+            Ok(result) => {
+                if result.has_warnings() {
+                    println!("{}: {} warning(s)", path, result.warning_count());
+                    for warning in &result.warnings {
+                        println!("  {}", warning);
+                    }
+                } else {
+                    println!("{}: ok", path);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
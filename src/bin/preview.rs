@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Cosboard Layout Preview
+//!
+//! This binary draws one panel of a layout into a PNG image without a
+//! compositor or GPU, for documentation images and visual-diff tests of the
+//! renderer. Cells are placed by the renderer's offline geometry and
+//! colored with the COSMIC dark or light theme (see
+//! `cosboard::renderer::offscreen`).
+//!
+//! # Usage
+//!
+//! ```bash
+//! cargo run --features preview --bin cosboard-preview -- \
+//!     resources/layouts/example_qwerty.json qwerty.png --width 1000 --height 320
+//! ```
+//!
+//! Key labels are only drawn when a font is given with `--font`, so images
+//! compared in CI do not depend on the fonts installed. Icon labels
+//! (`backspace`, `shift`, ...) are never drawn. `--repeat <n>` renders the
+//! panel `n` times and prints the average time, as a rendering benchmark.
+//!
+//! The exit status is 0 when the image was written, 1 when the layout or the
+//! image could not be read or written, and 2 on invalid arguments.
+
+use cosboard::app_settings;
+use cosboard::layout::{parse_layout_file, Layout};
+use cosboard::renderer::{render_panel_image, ImageStyle};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use tiny_skia::Pixmap;
+
+const USAGE: &str = "\
+Usage: cosboard-preview <layout.json> <output.png> [OPTIONS]
+
+Options:
+  --width <px>          Surface width (default 800)
+  --height <px>         Surface height (default 300)
+  --scale <factor>      HDPI factor applied to pixel sizes (default 1)
+  --panel <id>          Panel to draw (default: the layout's default panel)
+  --theme <dark|light>  COSMIC theme colors (default dark)
+  --font <file.ttf>     Font for key labels (default: no labels)
+  --repeat <n>          Render n times and print the average time";
+
+/// Preview settings from the command line.
+struct Options {
+    layout: String,
+    output: String,
+    width: f32,
+    height: f32,
+    scale: f32,
+    panel: Option<String>,
+    light: bool,
+    font: Option<String>,
+    repeat: u32,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match preview(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        layout: String::new(),
+        output: String::new(),
+        width: app_settings::DEFAULT_WIDTH,
+        height: app_settings::DEFAULT_HEIGHT,
+        scale: 1.0,
+        panel: None,
+        light: false,
+        font: None,
+        repeat: 1,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg.clone());
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Option '{}' requires a value", arg))?;
+        let number = || {
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|number| number.is_finite() && *number > 0.0)
+                .ok_or_else(|| format!("Invalid value '{}' for '{}'", value, arg))
+        };
+        match arg.as_str() {
+            "--width" => options.width = number()?,
+            "--height" => options.height = number()?,
+            "--scale" => options.scale = number()?,
+            "--panel" => options.panel = Some(value.clone()),
+            "--theme" => {
+                options.light = match value.as_str() {
+                    "dark" => false,
+                    "light" => true,
+                    _ => return Err(format!("Invalid value '{}' for '{}'", value, arg)),
+                }
+            }
+            "--font" => options.font = Some(value.clone()),
+            "--repeat" => {
+                options.repeat = value
+                    .parse()
+                    .ok()
+                    .filter(|repeat| *repeat > 0)
+                    .ok_or_else(|| format!("Invalid value '{}' for '{}'", value, arg))?
+            }
+            _ => return Err(format!("Unknown option '{}'", arg)),
+        }
+    }
+
+    match <[String; 2]>::try_from(positional) {
+        Ok([layout, output]) => {
+            options.layout = layout;
+            options.output = output;
+            Ok(options)
+        }
+        Err(_) => Err("Expected a layout file and an output file".to_string()),
+    }
+}
+
+fn preview(options: &Options) -> Result<(), String> {
+    let layout = parse_layout_file(&options.layout)
+        .map_err(|e| format!("{}: {}", options.layout, e))?
+        .layout;
+    let font = match options.font {
+        Some(ref path) => {
+            let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+            let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
+                .map_err(|e| format!("{}: {}", path, e))?;
+            Some(font)
+        }
+        None => None,
+    };
+    let theme = if options.light {
+        cosmic::Theme::light()
+    } else {
+        cosmic::Theme::dark()
+    };
+
+    let mut elapsed = Duration::ZERO;
+    let mut pixmap = None;
+    for _ in 0..options.repeat {
+        let start = Instant::now();
+        pixmap = Some(render(&layout, options, &theme, font.as_ref())?);
+        elapsed += start.elapsed();
+    }
+    if options.repeat > 1 {
+        println!(
+            "Rendered {} times, {:.2} ms on average",
+            options.repeat,
+            elapsed.as_secs_f64() * 1000.0 / f64::from(options.repeat)
+        );
+    }
+
+    let Some(pixmap) = pixmap else {
+        return Ok(());
+    };
+    pixmap
+        .save_png(&options.output)
+        .map_err(|e| format!("{}: {}", options.output, e))
+}
+
+/// Draws the chosen panel of `layout` into a new image.
+fn render(
+    layout: &Layout,
+    options: &Options,
+    theme: &cosmic::Theme,
+    font: Option<&fontdue::Font>,
+) -> Result<Pixmap, String> {
+    let panel_id = options.panel.as_ref().unwrap_or(&layout.default_panel_id);
+    let panel = layout
+        .panels
+        .get(panel_id)
+        .ok_or_else(|| format!("{}: no panel '{}'", options.layout, panel_id))?;
+    render_panel_image(
+        panel,
+        options.width,
+        options.height,
+        options.scale,
+        &ImageStyle::from_theme(theme),
+        font,
+    )
+    .ok_or_else(|| "Image size must not be zero".to_string())
+}
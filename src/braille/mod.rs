@@ -0,0 +1,505 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Six-dot braille entry.
+//!
+//! The built-in `braille` panel ([`add_builtin_panel`]) turns the keyboard
+//! into a Perkins-style brailler: six large keys, one per dot, are pressed
+//! together like the keys of a braille writer, and the cell they form is
+//! typed once all of them are released. Dots 3, 2 and 1 sit under the left
+//! hand, 4, 5 and 6 under the right, with a wide space bar below.
+//!
+//! | Key code                   | Effect                                     |
+//! |----------------------------|--------------------------------------------|
+//! | `cosboard:braille_dot:1`…`6` | Add the dot to the cell being chorded    |
+//! | `cosboard:braille_dot:0`   | Space bar: the empty cell, typed as a space |
+//!
+//! Pressing dots one after another while holding the first still forms one
+//! cell ([`BrailleChord`]), so the fingers do not have to land at the same
+//! instant. Cells are translated by a [`BrailleTable`], chosen with the
+//! `braille_table` configuration entry:
+//!
+//! - **English** (the default): uncontracted (grade 1) Unified English
+//!   Braille. Dot 6 capitalizes the next letter (twice, the whole word), and
+//!   the number sign (dots 3-4-5-6) types the letters `a` to `j` as digits
+//!   until the next space or letter sign (dots 5-6).
+//! - **Unicode**: the braille pattern itself (`⠓⠊`), for writing braille.
+//!
+//! Every cell plays a sound ([`Translation::feedback`]), so entry can be
+//! followed by ear: a click for text, a pop for signs that only change the
+//! next letters, and the sound theme's error for cells with no meaning.
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{Cell, FeedbackProfile, Key, KeyCode, Layout, Panel, PanelRef, Row, Sizing};
+
+/// ID of the built-in braille panel.
+pub const BRAILLE_PANEL_ID: &str = "braille";
+
+/// Sound theme event played for cells with no meaning in the table.
+pub const UNKNOWN_CELL_SOUND: &str = "dialog-error";
+
+/// Height of the dot keys, in key units.
+const DOT_KEY_HEIGHT: f32 = 3.0;
+
+/// Letters of uncontracted English braille, by their dots.
+const ENGLISH_LETTERS: &[(&str, char)] = &[
+    ("1", 'a'),
+    ("12", 'b'),
+    ("14", 'c'),
+    ("145", 'd'),
+    ("15", 'e'),
+    ("124", 'f'),
+    ("1245", 'g'),
+    ("125", 'h'),
+    ("24", 'i'),
+    ("245", 'j'),
+    ("13", 'k'),
+    ("123", 'l'),
+    ("134", 'm'),
+    ("1345", 'n'),
+    ("135", 'o'),
+    ("1234", 'p'),
+    ("12345", 'q'),
+    ("1235", 'r'),
+    ("234", 's'),
+    ("2345", 't'),
+    ("136", 'u'),
+    ("1236", 'v'),
+    ("2456", 'w'),
+    ("1346", 'x'),
+    ("13456", 'y'),
+    ("1356", 'z'),
+];
+
+/// Punctuation of uncontracted English braille, by its dots.
+const ENGLISH_PUNCTUATION: &[(&str, char)] = &[
+    ("2", ','),
+    ("23", ';'),
+    ("25", ':'),
+    ("256", '.'),
+    ("235", '!'),
+    ("236", '?'),
+    ("3", '\''),
+    ("36", '-'),
+];
+
+/// Dots of the capital sign.
+const CAPITAL_SIGN: &str = "6";
+
+/// Dots of the number sign.
+const NUMBER_SIGN: &str = "3456";
+
+/// Dots of the letter sign, which ends numbers.
+const LETTER_SIGN: &str = "56";
+
+/// A braille cell: bit `n` is set when dot `n + 1` is raised.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BrailleCell(pub u8);
+
+impl BrailleCell {
+    /// The cell with no dots raised.
+    pub const EMPTY: BrailleCell = BrailleCell(0);
+
+    /// Returns the cell with the dots listed in `dots` (`"145"`) raised.
+    /// Characters that are not dots 1 to 6 are ignored.
+    #[must_use]
+    pub fn from_dots(dots: &str) -> Self {
+        let bits = dots
+            .chars()
+            .filter_map(|dot| dot.to_digit(10))
+            .filter(|dot| (1..=6).contains(dot))
+            .fold(0, |bits, dot| bits | 1 << (dot - 1));
+        BrailleCell(bits)
+    }
+
+    /// Returns the Unicode braille pattern of the cell (`⠙` for dots 1-4-5).
+    #[must_use]
+    pub fn to_char(self) -> char {
+        char::from_u32(0x2800 + u32::from(self.0 & 0x3f)).unwrap_or(' ')
+    }
+}
+
+/// Dots pressed together, forming a cell.
+///
+/// Dot 0 is the space bar: it takes part in the chord without raising a
+/// dot, so pressing it alone forms the empty cell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BrailleChord {
+    /// Dots held down (bit `n` for dot `n`)
+    held: u8,
+    /// Dots pressed since the chord started
+    cell: BrailleCell,
+}
+
+impl BrailleChord {
+    /// Creates a chord with no dots pressed.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` while any dot is held down.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.held != 0
+    }
+
+    /// Adds a pressed dot (0 to 6) to the chord.
+    pub fn press(&mut self, dot: u8) {
+        if dot > 6 {
+            return;
+        }
+        self.held |= 1 << dot;
+        if dot > 0 {
+            self.cell.0 |= 1 << (dot - 1);
+        }
+    }
+
+    /// Handles the release of a dot.
+    ///
+    /// Returns the cell once the last held dot is released. Releases of dots
+    /// that are not held are ignored.
+    pub fn release(&mut self, dot: u8) -> Option<BrailleCell> {
+        if dot > 6 || self.held & (1 << dot) == 0 {
+            return None;
+        }
+        self.held &= !(1 << dot);
+        if self.held == 0 {
+            Some(std::mem::take(&mut self.cell))
+        } else {
+            None
+        }
+    }
+
+    /// Drops the chord, e.g. when the panel is left with dots held.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Braille table translating cells to text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BrailleTable {
+    /// Uncontracted (grade 1) Unified English Braille.
+    #[default]
+    English,
+    /// Unicode braille patterns, typed as they are.
+    Unicode,
+}
+
+/// What a cell translated to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Translation {
+    /// Text to type.
+    Text(String),
+    /// A sign changing how the next cells read (capital or number sign).
+    Indicator,
+    /// A cell with no meaning in the table.
+    Unknown,
+}
+
+impl Translation {
+    /// Returns the sound confirming the cell.
+    #[must_use]
+    pub fn feedback(&self) -> FeedbackProfile {
+        match self {
+            Translation::Text(_) => FeedbackProfile::Click,
+            Translation::Indicator => FeedbackProfile::Pop,
+            Translation::Unknown => FeedbackProfile::Theme(UNKNOWN_CELL_SOUND.to_string()),
+        }
+    }
+}
+
+/// How the next letters are capitalized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Capitals {
+    #[default]
+    None,
+    /// The next letter
+    Letter,
+    /// Every letter until the end of the word
+    Word,
+}
+
+/// Translates cells with a table, keeping the capital and number modes
+/// between cells.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BrailleTranslator {
+    table: BrailleTable,
+    capitals: Capitals,
+    numeric: bool,
+}
+
+impl BrailleTranslator {
+    /// Creates a translator for `table`.
+    #[must_use]
+    pub fn new(table: BrailleTable) -> Self {
+        Self {
+            table,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the table cells are translated with.
+    #[must_use]
+    pub fn table(&self) -> BrailleTable {
+        self.table
+    }
+
+    /// Switches to another table, forgetting the modes.
+    pub fn set_table(&mut self, table: BrailleTable) {
+        *self = Self::new(table);
+    }
+
+    /// Forgets the capital and number modes, e.g. after the cursor moved.
+    pub fn reset(&mut self) {
+        self.set_table(self.table);
+    }
+
+    /// Translates a cell.
+    pub fn translate(&mut self, cell: BrailleCell) -> Translation {
+        if cell == BrailleCell::EMPTY {
+            self.reset();
+            return Translation::Text(" ".to_string());
+        }
+        match self.table {
+            BrailleTable::English => self.english(cell),
+            BrailleTable::Unicode => Translation::Text(cell.to_char().to_string()),
+        }
+    }
+
+    /// Translates a cell of uncontracted English braille.
+    fn english(&mut self, cell: BrailleCell) -> Translation {
+        let find = |table: &[(&str, char)]| {
+            table
+                .iter()
+                .find(|(dots, _)| BrailleCell::from_dots(dots) == cell)
+                .map(|(_, c)| *c)
+        };
+
+        if cell == BrailleCell::from_dots(CAPITAL_SIGN) {
+            self.capitals = match self.capitals {
+                Capitals::None => Capitals::Letter,
+                _ => Capitals::Word,
+            };
+            return Translation::Indicator;
+        }
+        if cell == BrailleCell::from_dots(NUMBER_SIGN) {
+            self.numeric = true;
+            return Translation::Indicator;
+        }
+        if cell == BrailleCell::from_dots(LETTER_SIGN) {
+            self.numeric = false;
+            return Translation::Indicator;
+        }
+
+        if let Some(letter) = find(ENGLISH_LETTERS) {
+            if self.numeric {
+                // a to j are the digits 1 to 9 and 0
+                if let Some(digit) = ('a'..='j').position(|c| c == letter) {
+                    let digit = (digit as u32 + 1) % 10;
+                    return Translation::Text(digit.to_string());
+                }
+                self.numeric = false;
+            }
+            let text = match self.capitals {
+                Capitals::None => letter.to_string(),
+                _ => letter.to_uppercase().to_string(),
+            };
+            if self.capitals == Capitals::Letter {
+                self.capitals = Capitals::None;
+            }
+            return Translation::Text(text);
+        }
+
+        match find(ENGLISH_PUNCTUATION) {
+            Some(c) => {
+                // Decimal points and thousands separators continue a number
+                if !matches!(c, '.' | ',') {
+                    self.numeric = false;
+                }
+                self.capitals = Capitals::None;
+                Translation::Text(c.to_string())
+            }
+            None => Translation::Unknown,
+        }
+    }
+}
+
+/// Returns the key of a dot (0 for the space bar).
+///
+/// Dot keys are silent: the typed cell plays the sound instead.
+fn dot_key(dot: u8, label: &str, width: f32, height: f32) -> Cell {
+    Cell::Key(Key {
+        label: label.to_string(),
+        code: KeyCode::Keysym(format!("cosboard:braille_dot:{}", dot)),
+        identifier: Some(format!("braille_dot_{}", dot)),
+        width: Sizing::Relative(width),
+        height: Sizing::Relative(height),
+        feedback: Some(FeedbackProfile::Silent),
+        ..Key::default()
+    })
+}
+
+/// Returns the built-in braille panel.
+///
+/// The six dot keys span the panel in brailler order (3 2 1 4 5 6); the
+/// bottom row holds a panel reference back to `return_panel_id`,
+/// BackSpace, the space bar and Return.
+#[must_use]
+pub fn builtin_panel(return_panel_id: &str) -> Panel {
+    let dot_width = 10.0 / 6.0;
+    let dots = [3u8, 2, 1, 4, 5, 6]
+        .into_iter()
+        .map(|dot| dot_key(dot, &dot.to_string(), dot_width, DOT_KEY_HEIGHT))
+        .collect();
+
+    let regular = |label: &str, code: &str, width: f32| {
+        Cell::Key(Key {
+            label: label.to_string(),
+            code: KeyCode::Keysym(code.to_string()),
+            width: Sizing::Relative(width),
+            ..Key::default()
+        })
+    };
+    let bottom = vec![
+        Cell::PanelRef(PanelRef {
+            panel_id: return_panel_id.to_string(),
+            width: Sizing::Relative(1.5),
+            height: Sizing::Relative(1.0),
+        }),
+        regular("⌫", "BackSpace", 1.5),
+        dot_key(0, "Space", 5.0, 1.0),
+        regular("⏎", "Return", 2.0),
+    ];
+
+    Panel {
+        id: BRAILLE_PANEL_ID.to_string(),
+        padding: Some(8.0),
+        margin: Some(4.0),
+        rows: vec![Row { cells: dots }, Row { cells: bottom }],
+        ..Panel::default()
+    }
+}
+
+/// Adds the built-in braille panel to a layout.
+///
+/// Layouts that define their own `braille` panel keep it.
+pub fn add_builtin_panel(layout: &mut Layout) {
+    if !layout.panels.contains_key(BRAILLE_PANEL_ID) {
+        let panel = builtin_panel(&layout.default_panel_id);
+        layout.panels.insert(BRAILLE_PANEL_ID.to_string(), panel);
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_cells(translator: &mut BrailleTranslator, cells: &[&str]) -> String {
+        cells
+            .iter()
+            .filter_map(
+                |dots| match translator.translate(BrailleCell::from_dots(dots)) {
+                    Translation::Text(text) => Some(text),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Test: A cell is typed once every dot of the chord is released, even
+    /// if the dots were not pressed at the same instant.
+    #[test]
+    fn test_chord() {
+        let mut chord = BrailleChord::new();
+        chord.press(1);
+        chord.press(5);
+        assert_eq!(chord.release(1), None);
+        chord.press(4);
+        assert!(chord.is_active());
+        assert_eq!(chord.release(5), None);
+        assert_eq!(chord.release(4), Some(BrailleCell::from_dots("145")));
+        assert!(!chord.is_active());
+
+        // Stray releases and out of range dots are ignored
+        assert_eq!(chord.release(2), None);
+        chord.press(7);
+        assert!(!chord.is_active());
+
+        chord.press(0);
+        assert_eq!(chord.release(0), Some(BrailleCell::EMPTY));
+        assert_eq!(BrailleCell::from_dots("145").to_char(), '⠙');
+    }
+
+    /// Test: English braille types letters, capitals, numbers and
+    /// punctuation.
+    #[test]
+    fn test_english() {
+        let mut translator = BrailleTranslator::new(BrailleTable::English);
+        assert_eq!(
+            type_cells(
+                &mut translator,
+                &["6", "125", "24", "", "6", "6", "125", "24", "256"]
+            ),
+            "Hi HI."
+        );
+        assert_eq!(
+            type_cells(&mut translator, &["3456", "1", "245", "256", "12", "", "1"]),
+            "10.2 a"
+        );
+        assert_eq!(
+            type_cells(
+                &mut translator,
+                &["3456", "12", "56", "1", "3456", "1", "1234"]
+            ),
+            "2a1p"
+        );
+        assert_eq!(
+            translator.translate(BrailleCell::from_dots("346")),
+            Translation::Unknown
+        );
+        assert_eq!(
+            translator.translate(BrailleCell::from_dots("6")).feedback(),
+            FeedbackProfile::Pop
+        );
+
+        let mut unicode = BrailleTranslator::new(BrailleTable::Unicode);
+        assert_eq!(type_cells(&mut unicode, &["125", "24", "", "6"]), "⠓⠊ ⠠");
+    }
+
+    /// Test: The built-in panel is added once, with six dot keys and a
+    /// space bar.
+    #[test]
+    fn test_add_builtin_panel() {
+        let mut layout = Layout::default();
+        add_builtin_panel(&mut layout);
+
+        let panel = &layout.panels[BRAILLE_PANEL_ID];
+        let commands: Vec<_> = panel
+            .rows
+            .iter()
+            .flat_map(|row| &row.cells)
+            .filter_map(|cell| match cell {
+                Cell::Key(key) => crate::input::parse_command(&key.code),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(commands.len(), 7);
+        assert_eq!(commands[0], "braille_dot:3");
+        assert!(commands.contains(&"braille_dot:0"));
+
+        layout
+            .panels
+            .get_mut(BRAILLE_PANEL_ID)
+            .unwrap()
+            .rows
+            .clear();
+        add_builtin_panel(&mut layout);
+        assert!(layout.panels[BRAILLE_PANEL_ID].rows.is_empty());
+    }
+}
@@ -0,0 +1,488 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Calculator panel that types its results.
+//!
+//! The built-in `calculator` panel ([`add_builtin_panel`]) has digit,
+//! operator and parenthesis keys that build an expression inside the
+//! keyboard instead of typing it. The expression is shown in the panel's
+//! `calculator_display` widget (see [`CALCULATOR_DISPLAY_WIDGET`]), and `=`
+//! evaluates it and types only the result into the focused field.
+//!
+//! The keys are command keys dispatching calculator actions, so layouts can
+//! build calculator panels of their own:
+//!
+//! | Key code                        | Action                                    |
+//! |---------------------------------|-------------------------------------------|
+//! | `cosboard:calc_input:7`         | [`AppAction::CalcInput`]: append to the expression |
+//! | `cosboard:calc_backspace`       | [`AppAction::CalcBackspace`]              |
+//! | `cosboard:calc_clear`           | [`AppAction::CalcClear`]                  |
+//! | `cosboard:calc_equals`          | [`AppAction::CalcEquals`]: type the result |
+//!
+//! Expressions use `+`, `-`, `*`/`×`, `/`/`÷`, `^` (power), `%` (percent),
+//! parentheses and decimal numbers; see [`evaluate`]. After `=` the
+//! expression is replaced by the result, so calculations can be continued.
+//!
+//! [`AppAction::CalcInput`]: crate::actions::AppAction::CalcInput
+//! [`AppAction::CalcBackspace`]: crate::actions::AppAction::CalcBackspace
+//! [`AppAction::CalcClear`]: crate::actions::AppAction::CalcClear
+//! [`AppAction::CalcEquals`]: crate::actions::AppAction::CalcEquals
+
+use std::fmt;
+
+use crate::fl;
+use crate::layout::{Cell, Key, KeyCode, Layout, Panel, PanelRef, Row, Sizing, Widget};
+
+/// ID of the built-in calculator panel.
+pub const CALCULATOR_PANEL_ID: &str = "calculator";
+
+/// Widget type name for the calculator display in layout files.
+pub const CALCULATOR_DISPLAY_WIDGET: &str = "calculator_display";
+
+/// Longest expression the calculator accepts, in characters.
+pub const MAX_EXPRESSION_LEN: usize = 256;
+
+/// Decimal places kept in typed results.
+const RESULT_DECIMALS: usize = 10;
+
+/// Error evaluating a calculator expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalcError {
+    /// There is nothing to evaluate.
+    Empty,
+    /// The expression is malformed at this character offset.
+    Syntax(usize),
+    /// The expression divides by zero.
+    DivisionByZero,
+    /// The result is too large, or not a number (e.g. `(-8)^0.5`).
+    OutOfRange,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Empty => write!(f, "Nothing to calculate"),
+            CalcError::Syntax(position) => write!(f, "Syntax error at character {}", position + 1),
+            CalcError::DivisionByZero => write!(f, "Division by zero"),
+            CalcError::OutOfRange => write!(f, "Result out of range"),
+        }
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+impl CalcError {
+    /// Returns the error message in the user's language, for toasts.
+    #[must_use]
+    pub fn localized(&self) -> String {
+        match self {
+            CalcError::Empty => fl!("calc-empty"),
+            CalcError::Syntax(_) => fl!("calc-syntax"),
+            CalcError::DivisionByZero => fl!("calc-division-by-zero"),
+            CalcError::OutOfRange => fl!("calc-out-of-range"),
+        }
+    }
+}
+
+/// Evaluates an arithmetic expression.
+///
+/// Supports `+`, `-`, `*` or `×`, `/` or `÷`, `^` (power, right
+/// associative), postfix `%` (divides by 100), unary signs, parentheses and
+/// decimal numbers with `.` or `,`. Spaces are ignored; missing closing
+/// parentheses at the end are added.
+///
+/// # Errors
+///
+/// Returns a [`CalcError`] for empty or malformed expressions, division by
+/// zero and results that are not finite numbers.
+pub fn evaluate(expression: &str) -> Result<f64, CalcError> {
+    let tokens: Vec<(usize, char)> = expression
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| !c.is_whitespace())
+        .collect();
+    if tokens.is_empty() {
+        return Err(CalcError::Empty);
+    }
+
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+    };
+    let value = parser.sum()?;
+    // Anything left over (e.g. a stray `)`) is not part of the expression
+    if let Some(&(offset, _)) = tokens.get(parser.position) {
+        return Err(CalcError::Syntax(offset));
+    }
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CalcError::OutOfRange)
+    }
+}
+
+/// Formats a result for typing: integers without decimals, other numbers
+/// with at most [`RESULT_DECIMALS`] decimals and no trailing zeros.
+#[must_use]
+pub fn format_result(value: f64) -> String {
+    let text = format!("{:.*}", RESULT_DECIMALS, value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+/// Recursive descent parser over the expression's non-space characters.
+struct Parser<'a> {
+    /// Characters with their offsets in the expression
+    tokens: &'a [(usize, char)],
+    /// Index of the next token
+    position: usize,
+}
+
+impl Parser<'_> {
+    /// Returns the next character without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.tokens.get(self.position).map(|&(_, c)| c)
+    }
+
+    /// Returns the syntax error at the next token (or the end).
+    fn error(&self) -> CalcError {
+        let offset = self
+            .tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(0, |&(offset, _)| offset);
+        CalcError::Syntax(offset)
+    }
+
+    /// sum = product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.product()?;
+        while let Some(op @ ('+' | '-' | '−')) = self.peek() {
+            self.position += 1;
+            let rhs = self.product()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    /// product = unary (('*' | '×' | '/' | '÷') unary)*
+    fn product(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.unary()?;
+        while let Some(op @ ('*' | '×' | '/' | '÷')) = self.peek() {
+            self.position += 1;
+            let rhs = self.unary()?;
+            value = if matches!(op, '*' | '×') {
+                value * rhs
+            } else if rhs == 0.0 {
+                return Err(CalcError::DivisionByZero);
+            } else {
+                value / rhs
+            };
+        }
+        Ok(value)
+    }
+
+    /// unary = ('+' | '-') unary | power
+    ///
+    /// Signs bind looser than powers: `-2^2` is -4.
+    fn unary(&mut self) -> Result<f64, CalcError> {
+        match self.peek() {
+            Some('-' | '−') => {
+                self.position += 1;
+                Ok(-self.unary()?)
+            }
+            Some('+') => {
+                self.position += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// power = postfix ('^' unary)?
+    fn power(&mut self) -> Result<f64, CalcError> {
+        let base = self.postfix()?;
+        if self.peek() == Some('^') {
+            self.position += 1;
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    /// postfix = atom '%'*
+    fn postfix(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.atom()?;
+        while self.peek() == Some('%') {
+            self.position += 1;
+            value /= 100.0;
+        }
+        Ok(value)
+    }
+
+    /// atom = number | '(' sum ')'?
+    fn atom(&mut self) -> Result<f64, CalcError> {
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let value = self.sum()?;
+                match self.peek() {
+                    Some(')') => self.position += 1,
+                    None => {}
+                    Some(_) => return Err(self.error()),
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' || c == ',' => self.number(),
+            _ => Err(self.error()),
+        }
+    }
+
+    /// number = digits with at most one decimal separator
+    fn number(&mut self) -> Result<f64, CalcError> {
+        let start = self.position;
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' => text.push(c),
+                '.' | ',' if !text.contains('.') => text.push('.'),
+                _ => break,
+            }
+            self.position += 1;
+        }
+        text.parse().map_err(|_| {
+            self.position = start;
+            self.error()
+        })
+    }
+}
+
+/// The expression being built on the calculator panel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Calculator {
+    expression: String,
+}
+
+impl Calculator {
+    /// Creates an empty calculator.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the expression as shown on the display.
+    #[must_use]
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Appends key input to the expression, up to
+    /// [`MAX_EXPRESSION_LEN`] characters.
+    pub fn input(&mut self, text: &str) {
+        let room = MAX_EXPRESSION_LEN.saturating_sub(self.expression.chars().count());
+        self.expression.extend(text.chars().take(room));
+    }
+
+    /// Removes the last character of the expression.
+    pub fn backspace(&mut self) {
+        self.expression.pop();
+    }
+
+    /// Clears the expression.
+    pub fn clear(&mut self) {
+        self.expression.clear();
+    }
+
+    /// Evaluates the expression and replaces it with the result.
+    ///
+    /// Returns the result formatted for typing. On error, the expression is
+    /// kept so it can be corrected.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`CalcError`] of [`evaluate`].
+    pub fn equals(&mut self) -> Result<String, CalcError> {
+        let result = format_result(evaluate(&self.expression)?);
+        self.expression.clone_from(&result);
+        Ok(result)
+    }
+}
+
+/// Returns a calculator key dispatching `action`.
+fn calc_key(label: &str, action: &str, identifier: &str, width: f32) -> Cell {
+    Cell::Key(Key {
+        label: label.to_string(),
+        code: KeyCode::Keysym(format!("cosboard:{}", action)),
+        identifier: Some(format!("calc_{}", identifier)),
+        width: Sizing::Relative(width),
+        ..Key::default()
+    })
+}
+
+/// Returns a key appending `input` to the expression.
+fn input_key(label: &str, input: &str, identifier: &str) -> Cell {
+    calc_key(label, &format!("calc_input:{}", input), identifier, 1.0)
+}
+
+/// Returns the built-in calculator panel.
+///
+/// The display sits above a keypad of digits and operators; the bottom row
+/// holds a panel reference back to `return_panel_id`, `0`, the decimal
+/// point, `+` and `=`.
+#[must_use]
+pub fn builtin_panel(return_panel_id: &str) -> Panel {
+    let display = Cell::Widget(Widget {
+        widget_type: CALCULATOR_DISPLAY_WIDGET.to_string(),
+        width: Sizing::Relative(5.0),
+        height: Sizing::Relative(1.0),
+    });
+
+    let digit = |d: char| input_key(&d.to_string(), &d.to_string(), &d.to_string());
+    let rows = vec![
+        vec![display],
+        vec![
+            calc_key("C", "calc_clear", "clear", 2.0),
+            input_key("%", "%", "percent"),
+            calc_key("⌫", "calc_backspace", "backspace", 2.0),
+        ],
+        vec![
+            digit('7'),
+            digit('8'),
+            digit('9'),
+            input_key("÷", "÷", "divide"),
+            input_key("^", "^", "power"),
+        ],
+        vec![
+            digit('4'),
+            digit('5'),
+            digit('6'),
+            input_key("×", "×", "multiply"),
+            input_key("(", "(", "open"),
+        ],
+        vec![
+            digit('1'),
+            digit('2'),
+            digit('3'),
+            input_key("−", "-", "minus"),
+            input_key(")", ")", "close"),
+        ],
+        vec![
+            Cell::PanelRef(PanelRef {
+                panel_id: return_panel_id.to_string(),
+                width: Sizing::Relative(1.0),
+                height: Sizing::Relative(1.0),
+            }),
+            digit('0'),
+            input_key(".", ".", "point"),
+            input_key("+", "+", "plus"),
+            calc_key("=", "calc_equals", "equals", 1.0),
+        ],
+    ];
+
+    Panel {
+        id: CALCULATOR_PANEL_ID.to_string(),
+        padding: Some(8.0),
+        margin: Some(4.0),
+        rows: rows.into_iter().map(|cells| Row { cells }).collect(),
+        ..Panel::default()
+    }
+}
+
+/// Adds the built-in calculator panel to a layout.
+///
+/// Layouts that define their own `calculator` panel keep it.
+pub fn add_builtin_panel(layout: &mut Layout) {
+    if !layout.panels.contains_key(CALCULATOR_PANEL_ID) {
+        let panel = builtin_panel(&layout.default_panel_id);
+        layout.panels.insert(CALCULATOR_PANEL_ID.to_string(), panel);
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Precedence, associativity, signs, percent and parentheses.
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(evaluate("1 + 2 × 3"), Ok(7.0));
+        assert_eq!(evaluate("(1+2)*3"), Ok(9.0));
+        assert_eq!(evaluate("2^3^2"), Ok(512.0));
+        assert_eq!(evaluate("-2^2"), Ok(-4.0));
+        assert_eq!(evaluate("10 - -3"), Ok(13.0));
+        assert_eq!(evaluate("50%×80"), Ok(40.0));
+        assert_eq!(evaluate("7÷2"), Ok(3.5));
+        assert_eq!(evaluate("1,5+1"), Ok(2.5));
+        assert_eq!(evaluate("2×(3+4"), Ok(14.0));
+
+        assert_eq!(evaluate(" "), Err(CalcError::Empty));
+        assert_eq!(evaluate("1/0"), Err(CalcError::DivisionByZero));
+        assert_eq!(evaluate("1+"), Err(CalcError::Syntax(1)));
+        assert_eq!(evaluate("2)"), Err(CalcError::Syntax(1)));
+        assert_eq!(evaluate("1.2.3"), Err(CalcError::Syntax(3)));
+        assert_eq!(evaluate("(-8)^0.5"), Err(CalcError::OutOfRange));
+    }
+
+    /// Test: Results are typed without needless decimals, and replace the
+    /// expression.
+    #[test]
+    fn test_calculator() {
+        assert_eq!(format_result(14.0), "14");
+        assert_eq!(format_result(0.1 + 0.2), "0.3");
+        assert_eq!(format_result(-1e-12), "0");
+        assert_eq!(format_result(1.0 / 3.0), "0.3333333333");
+
+        let mut calculator = Calculator::new();
+        calculator.input("12");
+        calculator.input("÷");
+        calculator.input("5");
+        calculator.backspace();
+        assert_eq!(calculator.equals(), Err(CalcError::Syntax(2)));
+        assert_eq!(calculator.expression(), "12÷");
+
+        calculator.input("4");
+        assert_eq!(calculator.equals().as_deref(), Ok("3"));
+        calculator.input("+0.5");
+        assert_eq!(calculator.equals().as_deref(), Ok("3.5"));
+
+        calculator.clear();
+        calculator.input(&"9".repeat(MAX_EXPRESSION_LEN + 1));
+        assert_eq!(calculator.expression().len(), MAX_EXPRESSION_LEN);
+    }
+
+    /// Test: The built-in panel is added once and its keys are calculator commands.
+    #[test]
+    fn test_add_builtin_panel() {
+        let mut layout = Layout::default();
+        add_builtin_panel(&mut layout);
+
+        let panel = &layout.panels[CALCULATOR_PANEL_ID];
+        let commands: Vec<_> = panel
+            .rows
+            .iter()
+            .flat_map(|row| &row.cells)
+            .filter_map(|cell| match cell {
+                Cell::Key(key) => crate::input::parse_command(&key.code),
+                _ => None,
+            })
+            .collect();
+        assert!(commands.contains(&"calc_equals"));
+        assert!(commands.contains(&"calc_input:7"));
+        assert!(commands.iter().all(|command| command.starts_with("calc_")));
+
+        layout
+            .panels
+            .get_mut(CALCULATOR_PANEL_ID)
+            .unwrap()
+            .rows
+            .clear();
+        add_builtin_panel(&mut layout);
+        assert!(layout.panels[CALCULATOR_PANEL_ID].rows.is_empty());
+    }
+}
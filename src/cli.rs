@@ -0,0 +1,294 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Command-line flags and environment overrides for the applet.
+//!
+//! Scripted and kiosk deployments place the keyboard surface without
+//! editing the user configuration:
+//!
+//! ```bash
+//! cosboard-applet --anchor top --layer top --output HDMI-1 --height 380
+//! ```
+//!
+//! Every flag can also be set in the environment (`COSBOARD_ANCHOR`,
+//! `COSBOARD_LAYER`, `COSBOARD_OUTPUT`, `COSBOARD_HEIGHT`,
+//! `COSBOARD_NAMESPACE`); flags take precedence over the environment, and
+//! both over the configuration. Overrides last for the session and are
+//! never saved.
+
+use std::fmt;
+
+use crate::layer_shell::{Edge, Layer};
+
+/// Usage text printed for `--help` and on invalid arguments.
+pub const USAGE: &str = "\
+Usage: cosboard-applet [OPTIONS]
+
+Options:
+  --anchor <top|bottom>  Screen edge of the docked keyboard    [COSBOARD_ANCHOR]
+  --layer <name>         background, bottom, top or overlay    [COSBOARD_LAYER]
+  --output <name>        Output to show the keyboard on        [COSBOARD_OUTPUT]
+  --height <px>          Keyboard height in pixels             [COSBOARD_HEIGHT]
+  --namespace <name>     Layer-shell namespace of the surface  [COSBOARD_NAMESPACE]
+  -h, --help             Print this help";
+
+/// Layer surface namespace used when none is given.
+pub const DEFAULT_NAMESPACE: &str = "cosboard-keyboard";
+
+/// Surface settings that take precedence over the configuration.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SurfaceOverrides {
+    /// Edge the docked keyboard is anchored to.
+    pub anchor: Option<Edge>,
+    /// Layer-shell layer of the keyboard surface.
+    pub layer: Option<Layer>,
+    /// Name of the output (e.g. `HDMI-1`) the keyboard is shown on.
+    pub output: Option<String>,
+    /// Keyboard height in pixels.
+    pub height: Option<f32>,
+    /// Layer-shell namespace, for compositor rules matching the surface.
+    pub namespace: Option<String>,
+    /// Run without the COSMIC panel: no tray icon, and a strip along the
+    /// screen edge shows the keyboard. Set by the `cosboard` binary rather
+    /// than by a flag.
+    pub standalone: bool,
+}
+
+impl SurfaceOverrides {
+    /// Returns `true` if nothing is overridden.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the layer-shell namespace of the keyboard surface.
+    #[must_use]
+    pub fn namespace(&self) -> &str {
+        self.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE)
+    }
+
+    /// Sets the option `name` (without leading dashes) from its value.
+    fn set(&mut self, name: &str, value: &str) -> Result<(), CliError> {
+        let invalid = || CliError::InvalidValue {
+            option: format!("--{}", name),
+            value: value.to_string(),
+        };
+        match name {
+            "anchor" => self.anchor = Some(Edge::from_name(value).ok_or_else(invalid)?),
+            "layer" => self.layer = Some(Layer::from_name(value).ok_or_else(invalid)?),
+            "output" if !value.is_empty() => self.output = Some(value.to_string()),
+            "height" => {
+                let height = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|height| height.is_finite() && *height > 0.0)
+                    .ok_or_else(invalid)?;
+                self.height = Some(height);
+            }
+            "namespace" if !value.is_empty() => self.namespace = Some(value.to_string()),
+            "output" | "namespace" => return Err(invalid()),
+            _ => return Err(CliError::UnknownOption(format!("--{}", name))),
+        }
+        Ok(())
+    }
+}
+
+/// What the applet was asked to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Run the applet with these overrides.
+    Run(SurfaceOverrides),
+    /// Print the usage text.
+    Help,
+}
+
+/// Error parsing the command line or environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    /// The option is not known.
+    UnknownOption(String),
+    /// The option was given without a value.
+    MissingValue(String),
+    /// The value is not valid for the option.
+    InvalidValue {
+        /// Option flag or environment variable
+        option: String,
+        /// The rejected value
+        value: String,
+    },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(option) => write!(f, "Unknown option '{}'", option),
+            CliError::MissingValue(option) => write!(f, "Option '{}' requires a value", option),
+            CliError::InvalidValue { option, value } => {
+                write!(f, "Invalid value '{}' for '{}'", value, option)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Options that can be overridden, as named on the command line.
+const OPTIONS: &[&str] = &["anchor", "layer", "output", "height", "namespace"];
+
+/// Parses the applet's arguments (without the program name), reading
+/// unset options from `env`.
+///
+/// Options take their value as the next argument or after `=`
+/// (`--height=380`).
+pub fn parse<I, E>(args: I, env: E) -> Result<Command, CliError>
+where
+    I: IntoIterator<Item = String>,
+    E: Fn(&str) -> Option<String>,
+{
+    let mut overrides = SurfaceOverrides::default();
+
+    for option in OPTIONS {
+        let variable = format!("COSBOARD_{}", option.to_ascii_uppercase());
+        if let Some(value) = env(&variable).filter(|value| !value.is_empty()) {
+            overrides
+                .set(option, &value)
+                .map_err(|_| CliError::InvalidValue {
+                    option: variable,
+                    value,
+                })?;
+        }
+    }
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(Command::Help);
+        }
+        let Some(option) = arg.strip_prefix("--") else {
+            return Err(CliError::UnknownOption(arg));
+        };
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (option, None),
+        };
+        if !OPTIONS.contains(&name) {
+            return Err(CliError::UnknownOption(format!("--{}", name)));
+        }
+        let value = match value {
+            Some(value) => value,
+            None => args
+                .next()
+                .ok_or_else(|| CliError::MissingValue(arg.clone()))?,
+        };
+        overrides.set(name, &value)?;
+    }
+
+    Ok(Command::Run(overrides))
+}
+
+/// Parses the process's own arguments and environment.
+pub fn from_env() -> Result<Command, CliError> {
+    parse(std::env::args().skip(1), |name| std::env::var(name).ok())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    /// Test: Flags override the surface, with values separate or after '='.
+    #[test]
+    fn test_parse_flags() {
+        let command = parse(
+            args(&[
+                "--anchor",
+                "top",
+                "--layer",
+                "top",
+                "--output",
+                "HDMI-1",
+                "--height=380",
+            ]),
+            no_env,
+        );
+        let Ok(Command::Run(overrides)) = command else {
+            panic!("unexpected {:?}", command);
+        };
+        assert_eq!(overrides.anchor, Some(Edge::Top));
+        assert_eq!(overrides.layer, Some(Layer::Top));
+        assert_eq!(overrides.output.as_deref(), Some("HDMI-1"));
+        assert_eq!(overrides.height, Some(380.0));
+        assert_eq!(overrides.namespace(), DEFAULT_NAMESPACE);
+
+        assert_eq!(
+            parse(Vec::new(), no_env),
+            Ok(Command::Run(SurfaceOverrides::default()))
+        );
+        assert_eq!(parse(args(&["--help"]), no_env), Ok(Command::Help));
+    }
+
+    /// Test: The environment fills in options not given as flags.
+    #[test]
+    fn test_parse_env() {
+        let env = |name: &str| match name {
+            "COSBOARD_LAYER" => Some("bottom".to_string()),
+            "COSBOARD_NAMESPACE" => Some("kiosk-keyboard".to_string()),
+            "COSBOARD_OUTPUT" => Some(String::new()),
+            _ => None,
+        };
+        let Ok(Command::Run(overrides)) = parse(args(&["--layer", "overlay"]), env) else {
+            panic!("expected overrides");
+        };
+        assert_eq!(overrides.layer, Some(Layer::Overlay));
+        assert_eq!(overrides.namespace(), "kiosk-keyboard");
+        assert_eq!(overrides.output, None);
+        assert!(!overrides.is_empty());
+    }
+
+    /// Test: Unknown options and invalid values are errors.
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse(args(&["--width", "3"]), no_env),
+            Err(CliError::UnknownOption("--width".to_string()))
+        );
+        assert_eq!(
+            parse(args(&["--width=3"]), no_env),
+            Err(CliError::UnknownOption("--width".to_string()))
+        );
+        assert_eq!(
+            parse(args(&["top"]), no_env),
+            Err(CliError::UnknownOption("top".to_string()))
+        );
+        assert_eq!(
+            parse(args(&["--height"]), no_env),
+            Err(CliError::MissingValue("--height".to_string()))
+        );
+        assert!(matches!(
+            parse(args(&["--anchor=left"]), no_env),
+            Err(CliError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse(args(&["--height", "-5"]), no_env),
+            Err(CliError::InvalidValue { .. })
+        ));
+        let env = |name: &str| (name == "COSBOARD_HEIGHT").then(|| "tall".to_string());
+        assert_eq!(
+            parse(Vec::new(), env),
+            Err(CliError::InvalidValue {
+                option: "COSBOARD_HEIGHT".to_string(),
+                value: "tall".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,615 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Export and import of the whole user configuration.
+//!
+//! Moving to another machine takes more than the configuration entries: the
+//! layouts the user wrote and the words the predictor learned live in the
+//! data directory. A [`ConfigBundle`] gathers them into a single JSON file:
+//!
+//! - the configuration entries, as stored by `cosmic_config` (snippets,
+//!   gesture bindings, actions and profiles are entries too);
+//! - the layouts in `~/.local/share/cosboard/layouts`;
+//! - the learned words.
+//!
+//! The window state stays behind: sizes and positions belong to the screens
+//! they were chosen on.
+//!
+//! The [`Manifest`] records the version of the bundle format and of the
+//! configuration entries. Importing checks the whole bundle before anything
+//! is written: a bundle from a newer Cosboard, an unknown entry, an entry or
+//! layout that does not parse, or a file name that would leave its directory
+//! is refused. Entries of an older configuration version are migrated like
+//! stored ones ([`migration`](super::migration)) before they are checked.
+//! Imported entries, layouts and learned words replace the current ones of
+//! the same name; the others are kept.
+//!
+//! Some entries let programs run commands or type into the user's windows:
+//! the hooks and the external input policy ([`PROTECTED_ENTRIES`]). A
+//! bundle changing them is only imported when the user says so, with
+//! `cosboardctl config import --allow-hooks FILE`; the `import_config`
+//! action, which any program on the session bus can run, refuses it.
+//!
+//! Bundles are written with `cosboardctl config export FILE` and read with
+//! `cosboardctl config import FILE`, or with the `export_config` and
+//! `import_config` actions.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::migration::{self, ConfigVersion, Entries, MigrationError, MIGRATIONS};
+use super::{parse_entry, Config, CONFIG_VERSION};
+use crate::layout::parse_layout_from_string;
+use crate::prediction::learning::LEARNED_WORDS_FILE;
+
+/// Value of [`Manifest::format`] identifying a Cosboard bundle.
+pub const BUNDLE_FORMAT: &str = "cosboard-config";
+
+/// Version of the bundle format written by this build.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Directory of the user's layouts, under the data home.
+const LAYOUTS_DIR: &str = "cosboard/layouts";
+
+/// Configuration entries that let programs run commands or type, which are
+/// only imported when the user allows it.
+pub const PROTECTED_ENTRIES: [&str; 2] = ["hooks", "external_input"];
+
+/// Describes what a bundle holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Always [`BUNDLE_FORMAT`].
+    pub format: String,
+    /// Version of the bundle format.
+    pub version: u32,
+    /// Version of the configuration entries.
+    pub config_version: u64,
+    /// Version of Cosboard that wrote the bundle, for information.
+    #[serde(default)]
+    pub app_version: String,
+    /// When the bundle was written (RFC 3339), for information.
+    #[serde(default)]
+    pub created: String,
+}
+
+impl Manifest {
+    /// Returns the manifest of a bundle written now by this build.
+    #[must_use]
+    pub fn current() -> Self {
+        Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            config_version: CONFIG_VERSION.0,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// The user configuration, layouts and learned words in one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    /// Format and versions of the bundle.
+    pub manifest: Manifest,
+    /// Configuration entries: entry name to RON value.
+    #[serde(default)]
+    pub config: Entries,
+    /// User layouts: file name to JSON text.
+    #[serde(default)]
+    pub layouts: BTreeMap<String, String>,
+    /// The learned words file, if there was one.
+    #[serde(default)]
+    pub learned_words: Option<String>,
+}
+
+/// What a bundle held, for reporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleSummary {
+    /// Number of configuration entries.
+    pub entries: usize,
+    /// Number of layouts.
+    pub layouts: usize,
+    /// Whether the learned words were included.
+    pub learned_words: bool,
+}
+
+impl fmt::Display for BundleSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "entries: {}, layouts: {}, learned words: {}",
+            self.entries,
+            self.layouts,
+            if self.learned_words { "yes" } else { "no" }
+        )
+    }
+}
+
+/// Error exporting or importing a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// Neither `XDG_CONFIG_HOME`/`XDG_DATA_HOME` nor `HOME` is set.
+    NoUserDirectory,
+    /// Reading or writing a file failed.
+    Io(String),
+    /// The file is not a Cosboard bundle.
+    Malformed(String),
+    /// The bundle format is newer than this build understands.
+    NewerFormat(u32),
+    /// The entries cannot be migrated to this build's configuration.
+    Migration(MigrationError),
+    /// An entry or layout name is not a plain file name.
+    InvalidName(String),
+    /// An entry is not one of the configuration's, or does not parse.
+    InvalidEntry {
+        /// Name of the entry
+        name: String,
+        /// Why it is refused
+        error: String,
+    },
+    /// The bundle changes these [`PROTECTED_ENTRIES`], and that was not
+    /// allowed.
+    NotAllowed(Vec<String>),
+    /// A layout does not parse.
+    InvalidLayout {
+        /// File name of the layout
+        name: String,
+        /// Why it does not parse
+        error: String,
+    },
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::NoUserDirectory => write!(f, "no user configuration directory"),
+            BundleError::Io(e) => write!(f, "{}", e),
+            BundleError::Malformed(e) => write!(f, "not a Cosboard configuration bundle: {}", e),
+            BundleError::NewerFormat(version) => write!(
+                f,
+                "bundle format {} is newer than this version of Cosboard",
+                version
+            ),
+            BundleError::Migration(e) => write!(f, "{}", e),
+            BundleError::InvalidName(name) => write!(f, "invalid file name '{}'", name),
+            BundleError::InvalidEntry { name, error } => {
+                write!(f, "entry '{}' is invalid: {}", name, error)
+            }
+            BundleError::NotAllowed(names) => write!(
+                f,
+                "the bundle sets {}, which let programs run commands or type; import it \
+                 with cosboardctl config import --allow-hooks to accept them",
+                names.join(" and ")
+            ),
+            BundleError::InvalidLayout { name, error } => {
+                write!(f, "layout '{}' is invalid: {}", name, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<io::Error> for BundleError {
+    fn from(e: io::Error) -> Self {
+        BundleError::Io(e.to_string())
+    }
+}
+
+/// Returns `true` if `name` names a file directly inside a directory.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', '\0'])
+}
+
+impl ConfigBundle {
+    /// Gathers the configuration of `id` and the user's layouts and learned
+    /// words.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file exists but cannot be read.
+    pub fn collect(config_home: &Path, data_home: &Path, id: &str) -> Result<Self, BundleError> {
+        let config =
+            migration::read_entries(&migration::version_dir(config_home, id, CONFIG_VERSION))?;
+
+        let mut layouts = BTreeMap::new();
+        match std::fs::read_dir(data_home.join(LAYOUTS_DIR)) {
+            Ok(listing) => {
+                for item in listing {
+                    let path = item?.path();
+                    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                        continue;
+                    };
+                    if path.is_file() && is_plain_name(name) && name.ends_with(".json") {
+                        layouts.insert(name.to_string(), std::fs::read_to_string(&path)?);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let learned_words = match std::fs::read_to_string(data_home.join(LEARNED_WORDS_FILE)) {
+            Ok(text) => Some(text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            manifest: Manifest::current(),
+            config,
+            layouts,
+            learned_words,
+        })
+    }
+
+    /// Formats the bundle as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bundle cannot be serialized.
+    pub fn to_json(&self) -> Result<String, BundleError> {
+        serde_json::to_string_pretty(self).map_err(|e| BundleError::Malformed(e.to_string()))
+    }
+
+    /// Parses and validates a bundle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a bundle this build can import.
+    pub fn from_json(text: &str) -> Result<Self, BundleError> {
+        let bundle: Self =
+            serde_json::from_str(text).map_err(|e| BundleError::Malformed(e.to_string()))?;
+        bundle.validate()?;
+        Ok(bundle)
+    }
+
+    /// Checks that the bundle can be imported without writing anything.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first problem found.
+    pub fn validate(&self) -> Result<(), BundleError> {
+        let manifest = &self.manifest;
+        if manifest.format != BUNDLE_FORMAT {
+            return Err(BundleError::Malformed(format!(
+                "unknown format '{}'",
+                manifest.format
+            )));
+        }
+        if manifest.version > BUNDLE_VERSION {
+            return Err(BundleError::NewerFormat(manifest.version));
+        }
+        let config_version = ConfigVersion(manifest.config_version);
+        if config_version > CONFIG_VERSION {
+            return Err(BundleError::Migration(MigrationError::Newer(
+                config_version,
+            )));
+        }
+
+        if let Some(name) = self.config.keys().find(|name| !is_plain_name(name)) {
+            return Err(BundleError::InvalidName(name.clone()));
+        }
+        for (name, value) in &self.migrated_entries()? {
+            parse_entry(name, value).map_err(|error| BundleError::InvalidEntry {
+                name: name.clone(),
+                error,
+            })?;
+        }
+        for (name, json) in &self.layouts {
+            if !is_plain_name(name) || !name.ends_with(".json") {
+                return Err(BundleError::InvalidName(name.clone()));
+            }
+            parse_layout_from_string(json).map_err(|e| BundleError::InvalidLayout {
+                name: name.clone(),
+                error: e.to_string(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the bundle's entries migrated to this build's configuration.
+    fn migrated_entries(&self) -> Result<Entries, BundleError> {
+        migration::migrate(
+            self.config.clone(),
+            ConfigVersion(self.manifest.config_version),
+            CONFIG_VERSION,
+            MIGRATIONS,
+        )
+        .map_err(BundleError::Migration)
+    }
+
+    /// Returns the [`PROTECTED_ENTRIES`] the bundle sets to something else
+    /// than their defaults.
+    #[must_use]
+    pub fn protected_entries(&self) -> Vec<String> {
+        let entries = self.migrated_entries().unwrap_or_default();
+        PROTECTED_ENTRIES
+            .into_iter()
+            .filter(|name| {
+                entries.get(*name).is_some_and(|value| {
+                    parse_entry(name, value).map_or(true, |config| config != Config::default())
+                })
+            })
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Returns what the bundle holds.
+    #[must_use]
+    pub fn summary(&self) -> BundleSummary {
+        BundleSummary {
+            entries: self.config.len(),
+            layouts: self.layouts.len(),
+            learned_words: self.learned_words.is_some(),
+        }
+    }
+
+    /// Writes the bundle's configuration of `id`, layouts and learned words,
+    /// migrating older entries first.
+    ///
+    /// A bundle changing [`PROTECTED_ENTRIES`] is refused unless
+    /// `allow_hooks` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bundle is invalid, changes protected entries
+    /// without being allowed to, or a file cannot be written.
+    pub fn install(
+        &self,
+        config_home: &Path,
+        data_home: &Path,
+        id: &str,
+        allow_hooks: bool,
+    ) -> Result<BundleSummary, BundleError> {
+        self.validate()?;
+        let protected = self.protected_entries();
+        if !allow_hooks && !protected.is_empty() {
+            return Err(BundleError::NotAllowed(protected));
+        }
+        let entries = self.migrated_entries()?;
+
+        migration::write_entries(
+            &migration::version_dir(config_home, id, CONFIG_VERSION),
+            &entries,
+        )?;
+
+        let layouts_dir = data_home.join(LAYOUTS_DIR);
+        if !self.layouts.is_empty() {
+            std::fs::create_dir_all(&layouts_dir)?;
+        }
+        for (name, json) in &self.layouts {
+            std::fs::write(layouts_dir.join(name), json)?;
+        }
+
+        if let Some(ref learned_words) = self.learned_words {
+            write_private(&data_home.join(LEARNED_WORDS_FILE), learned_words)?;
+        }
+        Ok(self.summary())
+    }
+}
+
+/// Writes a file only the user can read, creating parent directories.
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents.as_bytes())
+}
+
+/// Returns the user's configuration and data homes.
+fn user_homes() -> Result<(PathBuf, PathBuf), BundleError> {
+    let config_home = migration::user_config_home().ok_or(BundleError::NoUserDirectory)?;
+    let data_home = crate::storage::detect()
+        .user_data_dir()
+        .ok_or(BundleError::NoUserDirectory)?;
+    Ok((config_home, data_home))
+}
+
+/// Writes the user's configuration, layouts and learned words to `path`.
+///
+/// The file holds the learned words, so only the user can read it.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read or the bundle written.
+pub fn export_user_config(path: &Path) -> Result<BundleSummary, BundleError> {
+    let (config_home, data_home) = user_homes()?;
+    let bundle = ConfigBundle::collect(&config_home, &data_home, crate::applet::APPLET_ID)?;
+    write_private(path, &bundle.to_json()?)
+        .map_err(|e| BundleError::Io(format!("cannot write {}: {}", path.display(), e)))?;
+    Ok(bundle.summary())
+}
+
+/// Imports the bundle at `path` into the user's configuration, with its
+/// [`PROTECTED_ENTRIES`] only if `allow_hooks` is set.
+///
+/// # Errors
+///
+/// Returns an error if the bundle cannot be read, is invalid, changes
+/// protected entries without being allowed to, or cannot be written;
+/// nothing is written for a refused bundle.
+pub fn import_user_config(path: &Path, allow_hooks: bool) -> Result<BundleSummary, BundleError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| BundleError::Io(format!("cannot read {}: {}", path.display(), e)))?;
+    let bundle = ConfigBundle::from_json(&text)?;
+    let (config_home, data_home) = user_homes()?;
+    bundle.install(
+        &config_home,
+        &data_home,
+        crate::applet::APPLET_ID,
+        allow_hooks,
+    )
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "io.github.cosboard.Cosboard.Applet";
+
+    const LAYOUT: &str = r#"{
+        "name": "Mine",
+        "version": "1.0",
+        "default_panel_id": "main",
+        "panels": { "main": { "id": "main", "rows": [] } }
+    }"#;
+
+    /// Test: A bundle carries the entries, layouts and learned words over to
+    /// another home, through JSON.
+    #[test]
+    fn test_bundle_round_trip() {
+        let old = tempfile::tempdir().unwrap();
+        let entries = Entries::from([("layer".to_string(), "Top".to_string())]);
+        let config_dir = migration::version_dir(old.path(), ID, CONFIG_VERSION);
+        migration::write_entries(&config_dir, &entries).unwrap();
+        let layouts_dir = old.path().join(LAYOUTS_DIR);
+        std::fs::create_dir_all(&layouts_dir).unwrap();
+        std::fs::write(layouts_dir.join("mine.json"), LAYOUT).unwrap();
+        std::fs::write(layouts_dir.join("notes.txt"), "not a layout").unwrap();
+        std::fs::write(old.path().join(LEARNED_WORDS_FILE), "cosmic 4\n").unwrap();
+
+        let bundle = ConfigBundle::collect(old.path(), old.path(), ID).unwrap();
+        let json = bundle.to_json().unwrap();
+        let imported = ConfigBundle::from_json(&json).unwrap();
+        assert_eq!(imported, bundle);
+
+        let new = tempfile::tempdir().unwrap();
+        let summary = imported.install(new.path(), new.path(), ID, false).unwrap();
+        assert_eq!(
+            summary.to_string(),
+            "entries: 1, layouts: 1, learned words: yes"
+        );
+        let new_config_dir = migration::version_dir(new.path(), ID, CONFIG_VERSION);
+        assert_eq!(migration::read_entries(&new_config_dir).unwrap(), entries);
+        let layout = std::fs::read_to_string(new.path().join(LAYOUTS_DIR).join("mine.json"));
+        assert_eq!(layout.unwrap(), LAYOUT);
+        let learned = std::fs::read_to_string(new.path().join(LEARNED_WORDS_FILE));
+        assert_eq!(learned.unwrap(), "cosmic 4\n");
+    }
+
+    /// Test: Bundles from newer versions, with names leaving their directory
+    /// or with broken layouts, are refused.
+    #[test]
+    fn test_bundle_validation() {
+        let valid = ConfigBundle {
+            manifest: Manifest::current(),
+            config: Entries::new(),
+            layouts: BTreeMap::from([("mine.json".to_string(), LAYOUT.to_string())]),
+            learned_words: None,
+        };
+        assert_eq!(valid.validate(), Ok(()));
+
+        let mut bundle = valid.clone();
+        bundle.manifest.format = "zip".to_string();
+        assert!(matches!(bundle.validate(), Err(BundleError::Malformed(_))));
+
+        let mut bundle = valid.clone();
+        bundle.manifest.version = BUNDLE_VERSION + 1;
+        assert_eq!(
+            bundle.validate(),
+            Err(BundleError::NewerFormat(BUNDLE_VERSION + 1))
+        );
+
+        let mut bundle = valid.clone();
+        bundle.manifest.config_version = CONFIG_VERSION.0 + 1;
+        assert!(matches!(bundle.validate(), Err(BundleError::Migration(_))));
+
+        let mut bundle = valid.clone();
+        bundle
+            .config
+            .insert("../layer".to_string(), "Top".to_string());
+        assert_eq!(
+            bundle.validate(),
+            Err(BundleError::InvalidName("../layer".to_string()))
+        );
+
+        let mut bundle = valid.clone();
+        bundle
+            .config
+            .insert("colour".to_string(), "Top".to_string());
+        assert!(matches!(
+            bundle.validate(),
+            Err(BundleError::InvalidEntry { name, .. }) if name == "colour"
+        ));
+
+        let mut bundle = valid.clone();
+        bundle
+            .config
+            .insert("layer".to_string(), "Sideways".to_string());
+        assert!(matches!(
+            bundle.validate(),
+            Err(BundleError::InvalidEntry { name, .. }) if name == "layer"
+        ));
+
+        let mut bundle = valid.clone();
+        bundle
+            .layouts
+            .insert("broken.json".to_string(), "{".to_string());
+        assert!(matches!(
+            bundle.validate(),
+            Err(BundleError::InvalidLayout { .. })
+        ));
+
+        assert!(matches!(
+            ConfigBundle::from_json("[]"),
+            Err(BundleError::Malformed(_))
+        ));
+    }
+
+    /// Test: A bundle changing the hooks or the external input policy is
+    /// only installed when allowed; entries left at their defaults need no
+    /// permission.
+    #[test]
+    fn test_bundle_protected_entries() {
+        let mut bundle = ConfigBundle {
+            manifest: Manifest::current(),
+            config: Entries::new(),
+            layouts: BTreeMap::new(),
+            learned_words: None,
+        };
+        bundle.config.insert(
+            "hooks".to_string(),
+            r#"(hooks: [(event: Shown, run: Command(["touch", "/tmp/shown"]))])"#.to_string(),
+        );
+        bundle
+            .config
+            .insert("external_input".to_string(), "(mode: Everyone)".to_string());
+        assert_eq!(bundle.validate(), Ok(()));
+        assert_eq!(bundle.protected_entries(), vec!["hooks", "external_input"]);
+
+        let home = tempfile::tempdir().unwrap();
+        let config_dir = migration::version_dir(home.path(), ID, CONFIG_VERSION);
+        assert_eq!(
+            bundle.install(home.path(), home.path(), ID, false),
+            Err(BundleError::NotAllowed(vec![
+                "hooks".to_string(),
+                "external_input".to_string()
+            ]))
+        );
+        assert!(!config_dir.exists());
+        bundle.install(home.path(), home.path(), ID, true).unwrap();
+        assert_eq!(migration::read_entries(&config_dir).unwrap(), bundle.config);
+
+        // Entries left at their defaults need no consent.
+        bundle.config.insert("hooks".to_string(), "()".to_string());
+        bundle
+            .config
+            .insert("external_input".to_string(), "()".to_string());
+        assert!(bundle.protected_entries().is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Migration of the stored configuration between versions.
+//!
+//! `cosmic_config` keeps each version of the configuration in its own
+//! directory, one file per entry holding its RON value:
+//!
+//! ```text
+//! ~/.config/cosmic/io.github.cosboard.Cosboard.Applet/v2/layer
+//! ```
+//!
+//! Bumping the version of [`Config`](super::Config) therefore starts from an
+//! empty directory and would reset every user's settings. Instead, when the
+//! current version has no entries yet, [`migrate_stored`] reads the newest
+//! older version, upgrades its entries one version at a time with the
+//! registered [`Migration`] steps and writes the result to the current
+//! directory. Older directories are left untouched, so a downgrade still
+//! finds its settings.
+//!
+//! To rename or restructure a setting:
+//!
+//! 1. bump `#[version]` on `Config` and [`CONFIG_VERSION`](super::CONFIG_VERSION),
+//! 2. add a [`Migration`] from the previous version to [`MIGRATIONS`] that
+//!    rewrites the affected entries (see [`rename_entry`]),
+//! 3. add a unit test for the step below.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Raw configuration entries of one version: entry name to RON value.
+pub type Entries = BTreeMap<String, String>;
+
+/// Version of the stored configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConfigVersion(pub u64);
+
+impl ConfigVersion {
+    /// Returns the next version.
+    #[must_use]
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    /// Returns the directory name `cosmic_config` uses for this version.
+    #[must_use]
+    pub fn dir_name(self) -> String {
+        format!("v{}", self.0)
+    }
+
+    /// Parses a version directory name (`v2`).
+    #[must_use]
+    pub fn from_dir_name(name: &str) -> Option<Self> {
+        name.strip_prefix('v')?.parse().ok().map(Self)
+    }
+}
+
+impl fmt::Display for ConfigVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.dir_name())
+    }
+}
+
+/// One migration step, from a version to the next.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Version the step upgrades from (to `from.next()`).
+    pub from: ConfigVersion,
+    /// What the step changes, for the log.
+    pub description: &'static str,
+    /// Rewrites the entries in place.
+    pub apply: fn(&mut Entries),
+}
+
+/// Registered migration steps, oldest first.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: ConfigVersion(1),
+    description: "carry entries over; entries that no longer parse fall back to their defaults",
+    apply: migrate_v1_to_v2,
+}];
+
+/// v1 to v2: the entries kept their names.
+fn migrate_v1_to_v2(_entries: &mut Entries) {}
+
+/// Renames an entry, keeping its value. Does nothing if it does not exist.
+pub fn rename_entry(entries: &mut Entries, old: &str, new: &str) {
+    if let Some(value) = entries.remove(old) {
+        entries.insert(new.to_string(), value);
+    }
+}
+
+/// Error migrating the stored configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    /// No step upgrades from this version.
+    MissingStep(ConfigVersion),
+    /// The stored configuration is newer than this build understands.
+    Newer(ConfigVersion),
+    /// Reading or writing the entries failed.
+    Io(String),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::MissingStep(version) => {
+                write!(f, "no migration from configuration {}", version)
+            }
+            MigrationError::Newer(version) => {
+                write!(f, "configuration {} is newer than this version of Cosboard", version)
+            }
+            MigrationError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// Upgrades entries from version `from` to `to` with `migrations`.
+///
+/// # Errors
+///
+/// Returns an error if `from` is newer than `to` or a step is missing.
+pub fn migrate(
+    mut entries: Entries,
+    from: ConfigVersion,
+    to: ConfigVersion,
+    migrations: &[Migration],
+) -> Result<Entries, MigrationError> {
+    if from > to {
+        return Err(MigrationError::Newer(from));
+    }
+
+    let mut version = from;
+    while version < to {
+        let step = migrations
+            .iter()
+            .find(|step| step.from == version)
+            .ok_or(MigrationError::MissingStep(version))?;
+        (step.apply)(&mut entries);
+        tracing::info!(
+            "Migrated configuration {} to {}: {}",
+            version,
+            version.next(),
+            step.description
+        );
+        version = version.next();
+    }
+    Ok(entries)
+}
+
+// ============================================================================
+// Stored entries
+// ============================================================================
+
+/// Returns the user's configuration home (`$XDG_CONFIG_HOME`, `~/.config`).
+#[must_use]
+pub fn user_config_home() -> Option<PathBuf> {
+    let env_dir = |var: &str| {
+        std::env::var_os(var)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    };
+    env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+}
+
+/// Returns the directory holding the entries of `id` at `version`.
+#[must_use]
+pub fn version_dir(config_home: &Path, id: &str, version: ConfigVersion) -> PathBuf {
+    config_home.join("cosmic").join(id).join(version.dir_name())
+}
+
+/// Reads all entries in a version directory (empty if it does not exist).
+///
+/// # Errors
+///
+/// Returns an error if the directory or an entry cannot be read.
+pub fn read_entries(dir: &Path) -> io::Result<Entries> {
+    let mut entries = Entries::new();
+    let listing = match std::fs::read_dir(dir) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => return Err(e),
+    };
+
+    for item in listing {
+        let path = item?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // Skip editor backups and cosmic_config's temporary files
+        if !path.is_file() || name.starts_with('.') {
+            continue;
+        }
+        entries.insert(name.to_string(), std::fs::read_to_string(&path)?);
+    }
+    Ok(entries)
+}
+
+/// Writes entries to a version directory, creating it.
+///
+/// # Errors
+///
+/// Returns an error if the directory or an entry cannot be written.
+pub fn write_entries(dir: &Path, entries: &Entries) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for (name, value) in entries {
+        std::fs::write(dir.join(name), value)?;
+    }
+    Ok(())
+}
+
+/// Migrates the stored configuration of `id` to version `current`.
+///
+/// Does nothing if `current` already has entries or no older version does.
+/// Returns the version that was migrated from, if any.
+///
+/// # Errors
+///
+/// Returns an error if the entries cannot be read, migrated or written.
+pub fn migrate_stored(
+    config_home: &Path,
+    id: &str,
+    current: ConfigVersion,
+    migrations: &[Migration],
+) -> Result<Option<ConfigVersion>, MigrationError> {
+    let io_error = |e: io::Error| MigrationError::Io(e.to_string());
+
+    let current_dir = version_dir(config_home, id, current);
+    if !read_entries(&current_dir).map_err(io_error)?.is_empty() {
+        return Ok(None);
+    }
+
+    let mut versions: Vec<ConfigVersion> = std::fs::read_dir(config_home.join("cosmic").join(id))
+        .map(|listing| {
+            listing
+                .filter_map(Result::ok)
+                .filter_map(|item| {
+                    ConfigVersion::from_dir_name(&item.file_name().to_string_lossy())
+                })
+                .filter(|version| *version < current)
+                .collect()
+        })
+        .unwrap_or_default();
+    versions.sort();
+
+    for from in versions.into_iter().rev() {
+        let entries = read_entries(&version_dir(config_home, id, from)).map_err(io_error)?;
+        if entries.is_empty() {
+            continue;
+        }
+        let entries = migrate(entries, from, current, migrations)?;
+        write_entries(&current_dir, &entries).map_err(io_error)?;
+        return Ok(Some(from));
+    }
+    Ok(None)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "io.github.cosboard.Cosboard.Applet";
+
+    fn entries(pairs: &[(&str, &str)]) -> Entries {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Test: v1 to v2 keeps every entry.
+    #[test]
+    fn test_migrate_v1_to_v2() {
+        let v1 = entries(&[("layer", "Top"), ("keyboard_interactivity", "None")]);
+        let v2 = migrate(v1.clone(), ConfigVersion(1), ConfigVersion(2), MIGRATIONS).unwrap();
+        assert_eq!(v2, v1);
+    }
+
+    /// Test: Steps run in order, and gaps or newer versions are errors.
+    #[test]
+    fn test_migrate_chain() {
+        fn rename_layer(entries: &mut Entries) {
+            rename_entry(entries, "layer", "surface_layer");
+        }
+        fn quote_layer(entries: &mut Entries) {
+            if let Some(value) = entries.get_mut("surface_layer") {
+                *value = format!("Some({})", value);
+            }
+        }
+        let steps = [
+            Migration {
+                from: ConfigVersion(1),
+                description: "rename layer",
+                apply: rename_layer,
+            },
+            Migration {
+                from: ConfigVersion(2),
+                description: "make layer optional",
+                apply: quote_layer,
+            },
+        ];
+
+        let migrated = migrate(
+            entries(&[("layer", "Top")]),
+            ConfigVersion(1),
+            ConfigVersion(3),
+            &steps,
+        );
+        assert_eq!(migrated, Ok(entries(&[("surface_layer", "Some(Top)")])));
+
+        assert_eq!(
+            migrate(Entries::new(), ConfigVersion(1), ConfigVersion(4), &steps),
+            Err(MigrationError::MissingStep(ConfigVersion(3)))
+        );
+        assert_eq!(
+            migrate(Entries::new(), ConfigVersion(5), ConfigVersion(3), &steps),
+            Err(MigrationError::Newer(ConfigVersion(5)))
+        );
+    }
+
+    /// Test: Stored entries of the newest older version are migrated once.
+    #[test]
+    fn test_migrate_stored() {
+        let home = tempfile::tempdir().unwrap();
+        let v1 = entries(&[("layer", "Top")]);
+        write_entries(&version_dir(home.path(), ID, ConfigVersion(1)), &v1).unwrap();
+
+        let migrated = migrate_stored(home.path(), ID, ConfigVersion(2), MIGRATIONS);
+        assert_eq!(migrated, Ok(Some(ConfigVersion(1))));
+        let v2_dir = version_dir(home.path(), ID, ConfigVersion(2));
+        assert_eq!(read_entries(&v2_dir).unwrap(), v1);
+
+        // Settings changed since are not overwritten by a second run
+        write_entries(&v2_dir, &entries(&[("layer", "Overlay")])).unwrap();
+        assert_eq!(migrate_stored(home.path(), ID, ConfigVersion(2), MIGRATIONS), Ok(None));
+        assert_eq!(read_entries(&v2_dir).unwrap(), entries(&[("layer", "Overlay")]));
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! User configuration, its migration between versions, and its export and
+//! import as a bundle.
+
+pub mod bundle;
+pub mod migration;
+
+pub use migration::{ConfigVersion, Migration, MigrationError, MIGRATIONS};
+
+use crate::actions::PostActionPolicy;
+use crate::appearance::KeyboardAppearance;
+use crate::braille::BrailleTable;
+use crate::date_picker::DatePickerSettings;
+use crate::dbus::ExternalInputPolicy;
+use crate::double_space::DoubleSpaceSettings;
+use crate::emoji::EmojiRecents;
+use crate::feedback::FeedbackSettings;
+use crate::gestures::{GestureBindings, PalmRejection};
+use crate::hooks::HookSettings;
+use crate::idle_inhibit::IdleInhibitSettings;
+use crate::ime::ImeSettings;
+use crate::input::NavigationSettings;
+use crate::kiosk::KioskPolicy;
+use crate::layer_shell::{Interactivity, Layer};
+use crate::prediction::learning::LearningPolicy;
+use crate::profiles::{ProfileSettings, ProfileStore};
+use crate::renderer::dead_zone::DeadZones;
+use crate::renderer::state::PointerLeavePolicy;
+use crate::renderer::theme::ThemeOverrides;
+use crate::renderer::touch_target::TouchTargetPolicy;
+use crate::snippets::SnippetSettings;
+use crate::stats::KeyStatsPolicy;
+use cosmic::cosmic_config;
+use cosmic::cosmic_config::{cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use serde::Deserialize;
+
+/// Current version of the stored configuration.
+///
+/// Must match `#[version]` on [`Config`]; bumping it requires a migration
+/// step from the previous version in [`MIGRATIONS`].
+pub const CONFIG_VERSION: ConfigVersion = ConfigVersion(2);
+
+/// User configuration that persists between application runs.
+///
+/// Entries are stored one per field by `cosmic_config`; deserializing the
+/// whole struct checks entries read from elsewhere, such as an imported
+/// bundle ([`parse_entry`]).
+#[derive(Debug, Default, Clone, CosmicConfigEntry, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+#[version = 2]
+pub struct Config {
+    /// Layer-shell layer for the keyboard surface.
+    /// `Overlay` keeps the keyboard above fullscreen windows; `Top` lets
+    /// overlays such as lock screens and notifications cover it.
+    pub layer: Layer,
+    /// Whether the keyboard surface may take keyboard focus.
+    /// Defaults to `None` so key presses never steal focus from the client.
+    pub keyboard_interactivity: Interactivity,
+    /// Recently used and pinned emoji/symbols shown in the "Recents" strip.
+    pub emoji_recents: EmojiRecents,
+    /// Actions run by keyboard-wide gestures (swipe down, long-press space, ...).
+    pub gesture_bindings: GestureBindings,
+    /// Whether Esc or Enter hide the keyboard after they are typed.
+    pub post_actions: PostActionPolicy,
+    /// Kiosk mode restrictions for public terminals, applied at startup.
+    pub kiosk: KioskPolicy,
+    /// Whether the renderer draws cell bounding boxes, identifiers and sizes
+    /// over the keyboard (also enabled by `COSBOARD_DEBUG_OVERLAY=1`).
+    pub debug_overlay: bool,
+    /// Whether word prediction candidates are shown in a row above the keys.
+    pub prediction: bool,
+    /// File names of the installed dictionaries used for word prediction,
+    /// on top of the bundled word list.
+    pub dictionaries: Vec<String>,
+    /// Whether and which typed words are learned for word prediction.
+    pub learning: LearningPolicy,
+    /// Text shortcuts expanded on word boundaries, and where they apply.
+    pub snippets: SnippetSettings,
+    /// Whether two quick spaces after a word type a full stop and a space,
+    /// and how quick they must be.
+    pub double_space: DoubleSpaceSettings,
+    /// Which D-Bus clients may type text and keys through the keyboard.
+    pub external_input: ExternalInputPolicy,
+    /// Commands and D-Bus calls run when the keyboard is shown, hidden or
+    /// switches layouts.
+    pub hooks: HookSettings,
+    /// Whether the keyboard follows the system theme or picks its own
+    /// light/dark palette by time of day or ambient light.
+    pub appearance: KeyboardAppearance,
+    /// Key colors, corner radius and key gap replacing the theme's.
+    pub theme: ThemeOverrides,
+    /// Whether key presses are counted, and whether keys are tinted by how
+    /// often they were pressed (heat map).
+    pub key_stats: KeyStatsPolicy,
+    /// Whether the keyboard can be navigated with a gamepad or remote.
+    pub navigation: NavigationSettings,
+    /// Whether a dot under the keys pulses each time a key event is emitted,
+    /// to tell lost keys from keys that were never sent.
+    pub emission_indicator: bool,
+    /// Whether a strip above the keys shows the latched modifiers and the
+    /// layout and panel, as chips that clear them or go back to the default
+    /// panel.
+    pub status_strip: bool,
+    /// Whether prediction candidates can be dragged out of the keyboard and
+    /// dropped into applications as text, typed on release instead of on
+    /// press.
+    pub drag_suggestions: bool,
+    /// Formats of the dates and times typed by the date picker, instead of
+    /// the locale's.
+    pub date_picker: DatePickerSettings,
+    /// Key combination typed by the `send_prefix` action, e.g. `ctrl+a` for
+    /// GNU screen; empty for tmux's `ctrl+b`.
+    pub terminal_prefix: String,
+    /// Options of the input methods loaded for the layout's language, such
+    /// as the Vietnamese typing method.
+    pub ime: ImeSettings,
+    /// Braille table translating the cells chorded on the braille panel.
+    pub braille_table: BrailleTable,
+    /// Smallest physical key size, and whether the keyboard grows taller to
+    /// keep keys above it.
+    pub touch_target: TouchTargetPolicy,
+    /// Strips along the keyboard edges where presses are ignored, in the
+    /// docked and the floating mode, e.g. for a palm resting on a tablet.
+    pub dead_zones: DeadZones,
+    /// Whether touches that look like a palm resting on or brushing the
+    /// screen are ignored, and the thresholds telling them from fingertips.
+    pub palm_rejection: PalmRejection,
+    /// Whether keys still pressed when the pointer leaves the keyboard are
+    /// typed or dropped.
+    pub pointer_leave: PointerLeavePolicy,
+    /// Whether key presses play sounds and vibrate the device.
+    pub key_feedback: FeedbackSettings,
+    /// Whether typing keeps the screen from dimming or locking, and for how
+    /// long after the last key.
+    pub idle_inhibit: IdleInhibitSettings,
+    /// Height of a one-unit key in pixels when the keyboard height follows
+    /// the layout; unset for the default.
+    pub key_height: Option<u32>,
+    /// Layout picked by the user, as a file name on the layout search path
+    /// or a path to a layout file; unset for the default layout.
+    pub layout: Option<String>,
+    /// Whether the keyboard shows itself when a text field gains focus and
+    /// hides when it loses focus, as the Wayland input method. Off by
+    /// default: it displaces IBus or Fcitx, which cannot run alongside.
+    pub auto_show: bool,
+    /// Name of the active profile; empty for the default profile.
+    pub profile: String,
+    /// Settings of the profiles not in use, restored when they are picked.
+    pub profiles: ProfileStore<ProfileSettings>,
+}
+
+/// Reads the configuration entry `name`, with `value` in RON, into a
+/// configuration that is otherwise the default one.
+///
+/// # Errors
+///
+/// Returns why the value does not parse as the entry's type, or that there
+/// is no entry named `name`.
+pub fn parse_entry(name: &str, value: &str) -> Result<Config, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+        return Err(format!("unknown entry '{}'", name));
+    }
+    ron::from_str(&format!("({}: {})", name, value)).map_err(|e| e.to_string())
+}
+
+/// Migrates the user's stored configuration to [`CONFIG_VERSION`].
+///
+/// Must run before the configuration is opened, so settings stored by an
+/// older version are found. Returns the version that was migrated from, if
+/// a migration was needed.
+///
+/// # Errors
+///
+/// Returns an error if the stored entries cannot be migrated; the
+/// configuration then starts from its defaults.
+pub fn migrate_user_config() -> Result<Option<ConfigVersion>, MigrationError> {
+    let Some(config_home) = migration::user_config_home() else {
+        return Ok(None);
+    };
+    migration::migrate_stored(
+        &config_home,
+        crate::applet::APPLET_ID,
+        CONFIG_VERSION,
+        MIGRATIONS,
+    )
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The migration target matches the version `cosmic_config` opens.
+    #[test]
+    fn test_config_version_matches() {
+        assert_eq!(Config::VERSION, CONFIG_VERSION.0);
+        assert!(
+            MIGRATIONS.iter().all(|step| step.from < CONFIG_VERSION),
+            "Migration steps must not upgrade past the current version"
+        );
+    }
+
+    /// Test: Entries are read as the type of their field, and unknown
+    /// entries are refused.
+    #[test]
+    fn test_parse_entry() {
+        assert_eq!(parse_entry("layer", "Top").unwrap().layer, Layer::Top);
+        let config = parse_entry("key_height", "Some(48)").unwrap();
+        assert_eq!(config.key_height, Some(48));
+        assert_eq!(config.layer, Config::default().layer);
+
+        assert!(parse_entry("layer", "Sideways").is_err());
+        assert!(parse_entry("key_height", "\"tall\"").is_err());
+        assert!(parse_entry("colour", "Top").is_err());
+        assert!(parse_entry("layer: Top, hooks", "()").is_err());
+    }
+}
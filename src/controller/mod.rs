@@ -0,0 +1,371 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! UI-agnostic key emission.
+//!
+//! [`KeyboardController`] owns what the keyboard needs to type into client
+//! applications, independently of libcosmic and of the surface the keys are
+//! drawn on:
+//!
+//! - The [`VirtualKeyboard`] and the backend it sends key events through
+//! - The modifier keys pressed along with held keys ([`ComboTracker`]), so
+//!   modifiers are pressed and released around keys in a consistent order
+//! - Typing text, key combinations, and replacing typed text
+//!
+//! Only emission lives here. The applet (with or without the panel) keeps
+//! the surfaces, the renderer, the configuration and its persistence, and
+//! hands the controller the keys to type along with the modifiers active on
+//! the renderer.
+
+use crate::input::{
+    keycodes, parse_keycode, ComboTracker, KeyCombo, ResolvedKeycode, VirtualKeyboard,
+};
+use crate::layout::{KeyCode, Modifier};
+
+/// Types keys and text into the focused client application.
+#[derive(Debug, Default)]
+pub struct KeyboardController {
+    /// Virtual keyboard emitting key events to the client application.
+    pub virtual_keyboard: VirtualKeyboard,
+    /// Modifiers pressed with each held key
+    combos: ComboTracker,
+}
+
+impl KeyboardController {
+    /// Creates a controller with an uninitialized virtual keyboard.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Determines if a key is a modifier key based on its KeyCode.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Modifier)` if the key is a modifier
+    /// * `None` if the key is not a modifier
+    #[must_use]
+    pub fn keycode_to_modifier(code: &KeyCode) -> Option<Modifier> {
+        match code {
+            KeyCode::Keysym(s) => {
+                let s_lower = s.to_lowercase();
+                if s_lower == "caps_lock" {
+                    Some(Modifier::CapsLock)
+                } else if s_lower.contains("shift") {
+                    Some(Modifier::Shift)
+                } else if s_lower.contains("control") || s_lower.contains("ctrl") {
+                    Some(Modifier::Ctrl)
+                } else if s_lower.contains("alt") {
+                    Some(Modifier::Alt)
+                } else if s_lower.contains("super") || s_lower.contains("meta") {
+                    Some(Modifier::Super)
+                } else {
+                    None
+                }
+            }
+            KeyCode::Unicode(_) => None,
+        }
+    }
+
+    /// Returns the evdev keycode of the left variant of a modifier.
+    #[must_use]
+    pub fn modifier_to_keycode(modifier: Modifier) -> u32 {
+        match modifier {
+            Modifier::Shift => keycodes::KEY_LEFTSHIFT,
+            Modifier::Ctrl => keycodes::KEY_LEFTCTRL,
+            Modifier::Alt => keycodes::KEY_LEFTALT,
+            Modifier::Super => keycodes::KEY_LEFTMETA,
+            Modifier::CapsLock => keycodes::KEY_CAPSLOCK,
+        }
+    }
+
+    /// Taps Caps Lock in the client application, locking or unlocking it
+    /// in the client's keymap state.
+    ///
+    /// Caps Lock is never pressed around other keys: once tapped, the
+    /// client capitalizes letters on its own until it is tapped again.
+    pub fn toggle_caps_lock(&mut self) {
+        if !self.virtual_keyboard.is_initialized() {
+            tracing::warn!("Virtual keyboard not initialized, cannot toggle Caps Lock");
+            return;
+        }
+        self.virtual_keyboard
+            .set_journal_source(Some("caps_lock".to_string()));
+        self.virtual_keyboard.press_key(keycodes::KEY_CAPSLOCK);
+        self.virtual_keyboard.release_key(keycodes::KEY_CAPSLOCK);
+        self.virtual_keyboard.set_journal_source(None);
+    }
+
+    /// Presses a regular (non-modifier) key.
+    ///
+    /// The active modifiers not already held for another key are pressed
+    /// first; they are released with the key. Key events are journaled
+    /// under `source` (the key identifier).
+    ///
+    /// Returns the key's resolved keycode, or `None` if nothing was typed
+    /// (uninitialized virtual keyboard, unparsable key code).
+    pub fn press_key(
+        &mut self,
+        code: &KeyCode,
+        source: String,
+        active_modifiers: &[Modifier],
+    ) -> Option<ResolvedKeycode> {
+        if !self.virtual_keyboard.is_initialized() {
+            tracing::warn!("Virtual keyboard not initialized, cannot emit key press");
+            return None;
+        }
+        self.virtual_keyboard.set_journal_source(Some(source));
+
+        // Emit modifier key presses first (those not already held for another key)
+        for modifier in &self.combos.press(code.clone(), active_modifiers) {
+            let keycode = Self::modifier_to_keycode(*modifier);
+            self.virtual_keyboard.press_key(keycode);
+            tracing::debug!("Emitted modifier press: {:?} (keycode {})", modifier, keycode);
+        }
+
+        // Resolve and emit the main key
+        let resolved = parse_keycode(code);
+        match &resolved {
+            Some(resolved @ (ResolvedKeycode::Character(_) | ResolvedKeycode::Keysym(_))) => {
+                if let Some(keycode) = self.virtual_keyboard.resolve_keycode(resolved) {
+                    self.virtual_keyboard.press_key(keycode);
+                    tracing::debug!("Emitted key press: {:?} (keycode {})", resolved, keycode);
+                } else if let ResolvedKeycode::Character(c) = resolved {
+                    // Fallback for Unicode characters
+                    tracing::debug!("Key not found in keymap, using Unicode fallback for '{}'", c);
+                    self.virtual_keyboard.emit_unicode_codepoint(*c as u32);
+                } else {
+                    tracing::warn!("Could not resolve keycode for: {:?}", resolved);
+                }
+            }
+            Some(ResolvedKeycode::UnicodeCodepoint(codepoint)) => {
+                self.virtual_keyboard.emit_unicode_codepoint(*codepoint);
+                tracing::debug!("Emitted Unicode codepoint: U+{:04X}", codepoint);
+            }
+            None => tracing::warn!("Could not parse keycode: {:?}", code),
+        }
+        self.virtual_keyboard.set_journal_source(None);
+        resolved
+    }
+
+    /// Releases a regular key, then the modifiers pressed with it (and no
+    /// other held key), even if the active modifiers changed meanwhile.
+    pub fn release_key(&mut self, code: &KeyCode, source: String) {
+        if !self.virtual_keyboard.is_initialized() {
+            return;
+        }
+        self.virtual_keyboard.set_journal_source(Some(source));
+
+        // Unicode codepoints are pressed and released at once on press;
+        // resolve_keycode() does not map them
+        let resolved = parse_keycode(code);
+        if let Some(keycode) = resolved
+            .as_ref()
+            .and_then(|resolved| self.virtual_keyboard.resolve_keycode(resolved))
+        {
+            self.virtual_keyboard.release_key(keycode);
+            tracing::debug!("Emitted key release: {:?} (keycode {})", resolved, keycode);
+        }
+
+        for modifier in &self.combos.release(code) {
+            let keycode = Self::modifier_to_keycode(*modifier);
+            self.virtual_keyboard.release_key(keycode);
+            tracing::debug!("Emitted modifier release: {:?} (keycode {})", modifier, keycode);
+        }
+        self.virtual_keyboard.set_journal_source(None);
+    }
+
+    /// Emits a string to the client application.
+    ///
+    /// Characters present in the keymap are typed as key presses; all others
+    /// (emoji, symbols) go through the Unicode codepoint fallback.
+    pub fn emit_text(&mut self, text: &str) {
+        if !self.virtual_keyboard.is_initialized() {
+            tracing::warn!("Virtual keyboard not initialized, cannot emit text");
+            return;
+        }
+
+        self.virtual_keyboard.set_journal_source(Some("text".to_string()));
+        for c in text.chars() {
+            if let Some(keycode) = self.virtual_keyboard.char_to_keycode(c) {
+                self.virtual_keyboard.press_key(keycode);
+                self.virtual_keyboard.release_key(keycode);
+            } else {
+                self.virtual_keyboard.emit_unicode_codepoint(c as u32);
+            }
+        }
+        self.virtual_keyboard.set_journal_source(None);
+    }
+
+    /// Presses a key with modifiers in the client application.
+    ///
+    /// The modifier keys are pressed before the key and released after it,
+    /// in reverse order.
+    pub fn send_key(&mut self, combo: &KeyCombo) {
+        if !self.virtual_keyboard.is_initialized() {
+            tracing::warn!("Virtual keyboard not initialized, cannot send {}", combo);
+            return;
+        }
+
+        let mut chars = combo.keysym.chars();
+        let keycode = match (chars.next(), chars.next()) {
+            (Some(c), None) => self.virtual_keyboard.char_to_keycode(c),
+            _ => None,
+        }
+        .or_else(|| self.virtual_keyboard.keysym_to_keycode(&combo.keysym));
+        let Some(keycode) = keycode else {
+            tracing::warn!("Key '{}' is not in the keymap, cannot send {}", combo.keysym, combo);
+            return;
+        };
+
+        self.virtual_keyboard.set_journal_source(Some(combo.to_string()));
+        for modifier in &combo.modifiers {
+            self.virtual_keyboard.press_key(Self::modifier_to_keycode(*modifier));
+        }
+        self.virtual_keyboard.press_key(keycode);
+        self.virtual_keyboard.release_key(keycode);
+        for modifier in combo.modifiers.iter().rev() {
+            self.virtual_keyboard.release_key(Self::modifier_to_keycode(*modifier));
+        }
+        self.virtual_keyboard.set_journal_source(None);
+    }
+
+    /// Deletes `backspaces` typed characters and types `text` instead.
+    pub fn replace_typed(&mut self, backspaces: usize, text: &str) {
+        if !self.virtual_keyboard.is_initialized() {
+            tracing::warn!("Virtual keyboard not initialized, cannot replace typed text");
+            return;
+        }
+
+        self.virtual_keyboard.set_journal_source(Some("replace".to_string()));
+        for _ in 0..backspaces {
+            self.virtual_keyboard.press_key(keycodes::KEY_BACKSPACE);
+            self.virtual_keyboard.release_key(keycodes::KEY_BACKSPACE);
+        }
+        self.emit_text(text);
+    }
+
+    /// Releases every key still held down and forgets the held combos.
+    ///
+    /// Used when the keyboard stops abruptly (quit, panic) so nothing, in
+    /// particular no modifier, stays stuck in client applications.
+    pub fn shutdown(&mut self) {
+        self.combos = ComboTracker::new();
+        self.virtual_keyboard.cleanup();
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{KeySink, KeyState};
+    use std::sync::{Arc, Mutex};
+
+    /// Key sink that records what it is sent.
+    struct RecordingSink(Arc<Mutex<Vec<(u32, KeyState)>>>);
+
+    impl KeySink for RecordingSink {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn send_key(&mut self, keycode: u32, state: KeyState) -> Result<(), String> {
+            self.0.lock().unwrap().push((keycode, state));
+            Ok(())
+        }
+    }
+
+    /// Test: Modifier keys map to their left keycodes, and other keys are
+    /// not modifiers.
+    #[test]
+    fn test_modifier_mapping() {
+        let shift = KeyCode::Keysym("Shift_R".to_string());
+        assert_eq!(KeyboardController::keycode_to_modifier(&shift), Some(Modifier::Shift));
+        let caps_lock = KeyCode::Keysym("Caps_Lock".to_string());
+        assert_eq!(
+            KeyboardController::keycode_to_modifier(&caps_lock),
+            Some(Modifier::CapsLock)
+        );
+        assert_eq!(KeyboardController::keycode_to_modifier(&KeyCode::Unicode('a')), None);
+        assert_eq!(
+            KeyboardController::modifier_to_keycode(Modifier::Super),
+            keycodes::KEY_LEFTMETA
+        );
+    }
+
+    /// Test: Nothing is typed before the virtual keyboard is initialized.
+    #[test]
+    fn test_uninitialized() {
+        let mut controller = KeyboardController::new();
+        let code = KeyCode::Unicode('a');
+        assert_eq!(controller.press_key(&code, "a".to_string(), &[]), None);
+        controller.release_key(&code, "a".to_string());
+        controller.emit_text("abc");
+        controller.send_key(&KeyCombo::new("c", &[Modifier::Ctrl]));
+        controller.toggle_caps_lock();
+        assert!(controller.virtual_keyboard.pending_events().is_empty());
+    }
+
+    /// Test: Caps Lock is tapped, never left held down.
+    #[test]
+    fn test_toggle_caps_lock() {
+        let mut controller = KeyboardController::new();
+        if controller.virtual_keyboard.initialize().is_err() {
+            eprintln!("Skipping test: XKB initialization failed");
+            return;
+        }
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        controller
+            .virtual_keyboard
+            .set_sink(Box::new(RecordingSink(sent.clone())));
+
+        controller.toggle_caps_lock();
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![
+                (keycodes::KEY_CAPSLOCK, KeyState::Pressed),
+                (keycodes::KEY_CAPSLOCK, KeyState::Released),
+            ]
+        );
+        assert!(controller.virtual_keyboard.held_keys().is_empty());
+    }
+
+    /// Test: A held key keeps its modifiers pressed until it is released,
+    /// and shutdown releases whatever is still held.
+    #[test]
+    fn test_key_with_modifiers() {
+        let mut controller = KeyboardController::new();
+        if controller.virtual_keyboard.initialize().is_err() {
+            eprintln!("Skipping test: XKB initialization failed");
+            return;
+        }
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        controller
+            .virtual_keyboard
+            .set_sink(Box::new(RecordingSink(sent.clone())));
+
+        let return_key = KeyCode::Keysym("Return".to_string());
+        let resolved = controller.press_key(&return_key, "enter".to_string(), &[Modifier::Ctrl]);
+        assert_eq!(resolved, Some(ResolvedKeycode::Keysym("Return".to_string())));
+        controller.release_key(&return_key, "enter".to_string());
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![
+                (keycodes::KEY_LEFTCTRL, KeyState::Pressed),
+                (keycodes::KEY_ENTER, KeyState::Pressed),
+                (keycodes::KEY_ENTER, KeyState::Released),
+                (keycodes::KEY_LEFTCTRL, KeyState::Released),
+            ]
+        );
+
+        sent.lock().unwrap().clear();
+        controller.press_key(&return_key, "enter".to_string(), &[Modifier::Alt]);
+        controller.shutdown();
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 4);
+        assert_eq!(sent[3], (keycodes::KEY_LEFTALT, KeyState::Released));
+    }
+}
@@ -0,0 +1,314 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Date and time picker that types the chosen date.
+//!
+//! Typing a date digit by digit on a touch screen is slow, and the order of
+//! day, month and year differs by country. The `date_picker` layout widget
+//! (see [`DATE_PICKER_WIDGET`]) shows the year, month, day, hour and minute
+//! with buttons to step each of them, and buttons that type the date, the
+//! time or both.
+//!
+//! The picker starts at the current time, and a "now" button goes back to
+//! it. Stepping a field wraps around within it (December steps to January of
+//! the same year) so the other fields stay put; days are clamped to the
+//! length of the month.
+//!
+//! The typed text follows the session's `LC_TIME` locale (see
+//! [`locale_date_format`] and [`locale_time_format`]), unless the
+//! `date_picker` configuration entry sets `strftime` formats of its own:
+//!
+//! ```ron
+//! (date_format: Some("%A %-d %B %Y"), time_format: Some("%H:%M"))
+//! ```
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+/// Widget type name for the date picker in layout files.
+pub const DATE_PICKER_WIDGET: &str = "date_picker";
+
+/// Date format used for locales without a known format (ISO 8601).
+pub const ISO_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Time format used for locales without a known format.
+pub const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+/// A field of the picker that can be stepped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateField {
+    /// The year, from 1 to 9999.
+    Year,
+    /// The month of the year.
+    Month,
+    /// The day of the month.
+    Day,
+    /// The hour, from 0 to 23.
+    Hour,
+    /// The minute.
+    Minute,
+}
+
+impl DateField {
+    /// All fields, in the order they are shown.
+    pub const ALL: [DateField; 5] = [
+        DateField::Year,
+        DateField::Month,
+        DateField::Day,
+        DateField::Hour,
+        DateField::Minute,
+    ];
+
+    /// Returns the field's value in `value`, formatted for the picker.
+    #[must_use]
+    pub fn display(&self, value: NaiveDateTime) -> String {
+        match self {
+            DateField::Year => format!("{:04}", value.year()),
+            DateField::Month => format!("{:02}", value.month()),
+            DateField::Day => format!("{:02}", value.day()),
+            DateField::Hour => format!("{:02}", value.hour()),
+            DateField::Minute => format!("{:02}", value.minute()),
+        }
+    }
+
+    /// Returns the field's name in the user's language.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self {
+            DateField::Year => fl!("date-picker-year"),
+            DateField::Month => fl!("date-picker-month"),
+            DateField::Day => fl!("date-picker-day"),
+            DateField::Hour => fl!("date-picker-hour"),
+            DateField::Minute => fl!("date-picker-minute"),
+        }
+    }
+}
+
+/// What a picker button types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateInsert {
+    /// The date only.
+    Date,
+    /// The time only.
+    Time,
+    /// The date followed by the time.
+    DateTime,
+}
+
+/// Formats the date picker types with, from the user configuration.
+///
+/// Formats are `strftime` strings as understood by chrono; `None` (or an
+/// invalid format) uses the format of the session locale.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatePickerSettings {
+    /// Format of typed dates.
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// Format of typed times.
+    #[serde(default)]
+    pub time_format: Option<String>,
+}
+
+impl DatePickerSettings {
+    /// Formats `value` for typing.
+    ///
+    /// `locale` is a POSIX locale name (e.g. `de_DE.UTF-8`), used for the
+    /// formats the configuration does not set.
+    #[must_use]
+    pub fn format(&self, insert: DateInsert, value: NaiveDateTime, locale: &str) -> String {
+        let date = || {
+            let format = valid_format(self.date_format.as_deref())
+                .unwrap_or_else(|| locale_date_format(locale));
+            value.format(format).to_string()
+        };
+        let time = || {
+            let format = valid_format(self.time_format.as_deref())
+                .unwrap_or_else(|| locale_time_format(locale));
+            value.format(format).to_string()
+        };
+        match insert {
+            DateInsert::Date => date(),
+            DateInsert::Time => time(),
+            DateInsert::DateTime => format!("{} {}", date(), time()),
+        }
+    }
+}
+
+/// Returns `format` if chrono can format with it.
+///
+/// Formatting with an invalid format panics, so configured formats are
+/// checked first.
+fn valid_format(format: Option<&str>) -> Option<&str> {
+    let format = format.filter(|format| !format.is_empty())?;
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        tracing::warn!("Ignoring invalid date picker format '{}'", format);
+        return None;
+    }
+    Some(format)
+}
+
+/// Returns the language and territory of a POSIX locale name:
+/// `de_AT.UTF-8@euro` gives `("de", "AT")`.
+fn split_locale(locale: &str) -> (&str, &str) {
+    let name = locale.split(['.', '@']).next().unwrap_or_default();
+    name.split_once('_').unwrap_or((name, ""))
+}
+
+/// Returns the date format of a POSIX locale (e.g. `en_US.UTF-8`).
+///
+/// Covers the common day-month-year, month-day-year and year-month-day
+/// orders; locales that are not known use [`ISO_DATE_FORMAT`].
+#[must_use]
+pub fn locale_date_format(locale: &str) -> &'static str {
+    match split_locale(locale) {
+        ("en", "US" | "PH") => "%m/%d/%Y",
+        ("en" | "fr", "CA") => ISO_DATE_FORMAT,
+        ("zh" | "ja", _) => "%Y/%m/%d",
+        ("ko" | "hu", _) => "%Y. %m. %d.",
+        ("sv" | "lt", _) => ISO_DATE_FORMAT,
+        ("nl", _) => "%d-%m-%Y",
+        (
+            "de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "nn" | "no" | "da" | "uk" | "tr"
+            | "ro" | "bg" | "hr" | "sl" | "sr" | "et" | "lv",
+            _,
+        ) => "%d.%m.%Y",
+        (
+            "en" | "fr" | "es" | "it" | "pt" | "el" | "ca" | "ga" | "cy" | "he" | "ar" | "id"
+            | "ms" | "vi" | "th" | "hi",
+            _,
+        ) => "%d/%m/%Y",
+        _ => ISO_DATE_FORMAT,
+    }
+}
+
+/// Returns the time format of a POSIX locale (e.g. `en_US.UTF-8`): a
+/// 12-hour clock where it is usual, a 24-hour clock otherwise.
+#[must_use]
+pub fn locale_time_format(locale: &str) -> &'static str {
+    match split_locale(locale) {
+        ("en", "US" | "CA" | "AU" | "NZ" | "PH" | "IN") => "%-I:%M %p",
+        _ => DEFAULT_TIME_FORMAT,
+    }
+}
+
+/// Returns the locale dates and times are formatted in: `LC_ALL`, then
+/// `LC_TIME`, then `LANG`, as for any other program.
+#[must_use]
+pub fn session_time_locale() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|locale| !locale.is_empty())
+        .unwrap_or_default()
+}
+
+/// Returns `value` with one field stepped by `delta`, wrapping around within
+/// the field.
+///
+/// The year is clamped to 1..=9999, and the day to the length of the month
+/// (stepping January 31 to February gives February 28 or 29).
+#[must_use]
+pub fn step(value: NaiveDateTime, field: DateField, delta: i32) -> NaiveDateTime {
+    let wrap = |current: u32, start: u32, count: u32| -> u32 {
+        (current as i32 - start as i32 + delta).rem_euclid(count as i32) as u32 + start
+    };
+
+    let (mut year, mut month, mut day) = (value.year(), value.month(), value.day());
+    let (mut hour, mut minute) = (value.hour(), value.minute());
+    match field {
+        DateField::Year => year = (year + delta).clamp(1, 9999),
+        DateField::Month => month = wrap(month, 1, 12),
+        DateField::Day => day = wrap(day, 1, days_in_month(year, month)),
+        DateField::Hour => hour = wrap(hour, 0, 24),
+        DateField::Minute => minute = wrap(minute, 0, 60),
+    }
+    day = day.min(days_in_month(year, month));
+
+    NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(hour, minute, 0))
+        .unwrap_or(value)
+}
+
+/// Returns the number of days in a month.
+fn days_in_month(year: i32, month: u32) -> u32 {
+    (28..=31)
+        .rev()
+        .find(|&day| NaiveDate::from_ymd_opt(year, month, day).is_some())
+        .unwrap_or(28)
+}
+
+/// Returns the current local time, to the minute, where the picker starts.
+#[must_use]
+pub fn now() -> NaiveDateTime {
+    let now = chrono::Local::now().naive_local();
+    now.with_second(0)
+        .and_then(|now| now.with_nanosecond(0))
+        .unwrap_or(now)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    /// Test: Fields wrap around and days are clamped to the month.
+    #[test]
+    fn test_step() {
+        let value = at(2024, 1, 31, 23, 59);
+        assert_eq!(step(value, DateField::Month, 1), at(2024, 2, 29, 23, 59));
+        assert_eq!(step(value, DateField::Month, -1), at(2024, 12, 31, 23, 59));
+        assert_eq!(step(value, DateField::Day, 1), at(2024, 1, 1, 23, 59));
+        assert_eq!(step(value, DateField::Hour, 1), at(2024, 1, 31, 0, 59));
+        assert_eq!(step(value, DateField::Minute, 1), at(2024, 1, 31, 23, 0));
+        assert_eq!(
+            step(at(2024, 2, 29, 8, 0), DateField::Year, 1),
+            at(2025, 2, 28, 8, 0)
+        );
+        assert_eq!(
+            step(at(9999, 6, 1, 8, 0), DateField::Year, 1),
+            at(9999, 6, 1, 8, 0)
+        );
+        assert_eq!(DateField::Month.display(value), "01");
+    }
+
+    /// Test: Typed text follows the locale unless a valid format is set.
+    #[test]
+    fn test_format() {
+        let value = at(2026, 3, 7, 14, 5);
+        let settings = DatePickerSettings::default();
+        assert_eq!(
+            settings.format(DateInsert::Date, value, "en_US.UTF-8"),
+            "03/07/2026"
+        );
+        assert_eq!(
+            settings.format(DateInsert::DateTime, value, "en_US.UTF-8"),
+            "03/07/2026 2:05 PM"
+        );
+        assert_eq!(
+            settings.format(DateInsert::DateTime, value, "de_DE.UTF-8"),
+            "07.03.2026 14:05"
+        );
+        assert_eq!(settings.format(DateInsert::Date, value, "C"), "2026-03-07");
+
+        let settings = DatePickerSettings {
+            date_format: Some("%d %b %Y".to_string()),
+            time_format: Some("%H:%M %Q".to_string()),
+        };
+        assert_eq!(
+            settings.format(DateInsert::DateTime, value, "en_GB.UTF-8"),
+            "07 Mar 2026 14:05"
+        );
+    }
+}
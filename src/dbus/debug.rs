@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The `io.github.cosboard.Cosboard.Debug` D-Bus interface.
+//!
+//! Integration tests that drive the keyboard from outside (with ydotool,
+//! say) need to know where the keys are and what the keyboard made of the
+//! taps. With the [`DEBUG_DBUS_ENV`] environment variable set (to anything
+//! but `0` or `false`), the keyboard serves this interface next to the
+//! control interface, at the same object path:
+//!
+//! ```bash
+//! COSBOARD_DEBUG_DBUS=1 cosboard-applet
+//! busctl --user call io.github.cosboard.Cosboard /io/github/cosboard/Cosboard \
+//!     io.github.cosboard.Cosboard.Debug KeyGeometry
+//! ```
+//!
+//! - `CurrentPanel() -> s`: ID of the panel shown;
+//! - `PressedKeys() -> as`: identifiers of the keys held down, sorted;
+//! - `ActiveModifiers() -> as`: modifiers active, held or latched;
+//! - `KeyGeometry() -> a(sdddd)`: identifier, x, y, width and height of each
+//!   key of the panel shown, in logical pixels from the top-left corner of
+//!   the keyboard surface. Keys of panels embedded in it are not listed.
+//!
+//! Pressed keys tell what is being typed, so the interface is only served
+//! when asked for. Each call takes a fresh [`KeyboardSnapshot`] from the
+//! applet, requested as [`DbusEvent::Snapshot`](super::DbusEvent::Snapshot).
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cosmic::iced::futures::channel::mpsc::UnboundedSender;
+use cosmic::iced::futures::channel::oneshot;
+use cosmic::iced::Rectangle;
+
+use crate::layout::Cell;
+use crate::renderer::collapsed_bar::modifier_label;
+use crate::renderer::debug_overlay::is_enabled_value;
+use crate::renderer::geometry::panel_geometry;
+use crate::renderer::state::KeyboardRenderer;
+
+/// Environment variable that enables the debug interface.
+pub const DEBUG_DBUS_ENV: &str = "COSBOARD_DEBUG_DBUS";
+
+/// How long a debug call waits for the applet's snapshot.
+pub const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Returns `true` if the environment enables the debug interface.
+#[must_use]
+pub fn debug_interface_from_env() -> bool {
+    is_enabled_value(std::env::var(DEBUG_DBUS_ENV).ok().as_deref())
+}
+
+/// A key of the panel shown and where it is on the keyboard surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBounds {
+    /// Identifier of the key, or its label if it has none.
+    pub identifier: String,
+    /// Distance of the left edge from the left of the surface.
+    pub x: f32,
+    /// Distance of the top edge from the top of the surface.
+    pub y: f32,
+    /// Width of the key.
+    pub width: f32,
+    /// Height of the key.
+    pub height: f32,
+}
+
+/// State of the keyboard served by the debug interface.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyboardSnapshot {
+    /// ID of the panel shown.
+    pub panel_id: String,
+    /// Identifiers of the keys held down, sorted.
+    pub pressed_keys: Vec<String>,
+    /// Names of the active modifiers ("Shift", "Ctrl", ...).
+    pub modifiers: Vec<String>,
+    /// Keys of the panel shown, in row order.
+    pub keys: Vec<KeyBounds>,
+}
+
+impl KeyboardSnapshot {
+    /// Takes a snapshot of `renderer`, whose panel is laid out in `area` of
+    /// the keyboard surface with the HDPI factor `scale`.
+    #[must_use]
+    pub fn capture(renderer: &KeyboardRenderer, area: Rectangle, scale: f32) -> Self {
+        let keys = renderer
+            .current_panel()
+            .map(|panel| {
+                panel_geometry(panel, area.width, area.height, scale)
+                    .cells
+                    .iter()
+                    .filter_map(|bounds| match bounds.cell {
+                        Cell::Key(key) => Some(KeyBounds {
+                            identifier: key.identifier.clone().unwrap_or_else(|| key.label.clone()),
+                            x: area.x + bounds.x,
+                            y: area.y + bounds.y,
+                            width: bounds.width,
+                            height: bounds.height,
+                        }),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            panel_id: renderer.current_panel_id.clone(),
+            pressed_keys: renderer.pressed_key_ids(),
+            modifiers: renderer
+                .shown_modifiers()
+                .into_iter()
+                .map(|modifier| modifier_label(modifier).to_string())
+                .collect(),
+            keys,
+        }
+    }
+}
+
+/// A debug call waiting for a snapshot of the keyboard.
+///
+/// Dropping every copy without answering fails the call.
+#[derive(Debug, Clone)]
+pub struct SnapshotRequest {
+    /// Channel the snapshot is sent on, taken by the first answer
+    reply: Arc<Mutex<Option<oneshot::Sender<KeyboardSnapshot>>>>,
+}
+
+impl SnapshotRequest {
+    /// Creates a request and the receiver of its snapshot.
+    pub fn new() -> (Self, oneshot::Receiver<KeyboardSnapshot>) {
+        let (sender, receiver) = oneshot::channel();
+        let request = Self {
+            reply: Arc::new(Mutex::new(Some(sender))),
+        };
+        (request, receiver)
+    }
+
+    /// Answers the call. Later answers are ignored.
+    pub fn answer(&self, snapshot: KeyboardSnapshot) {
+        let sender = self.reply.lock().ok().and_then(|mut reply| reply.take());
+        if let Some(sender) = sender {
+            let _ = sender.send(snapshot);
+        }
+    }
+}
+
+/// Debug interface object, served next to the
+/// [`CosboardInterface`](super::CosboardInterface).
+#[derive(Debug)]
+pub struct DebugInterface {
+    requests: UnboundedSender<SnapshotRequest>,
+}
+
+impl DebugInterface {
+    /// Creates an interface that asks for snapshots through `requests`.
+    #[must_use]
+    pub fn new(requests: UnboundedSender<SnapshotRequest>) -> Self {
+        Self { requests }
+    }
+
+    /// Asks the applet for a snapshot and waits for it.
+    async fn snapshot(&self) -> zbus::fdo::Result<KeyboardSnapshot> {
+        let (request, reply) = SnapshotRequest::new();
+        self.requests
+            .unbounded_send(request)
+            .map_err(|e| zbus::fdo::Error::Failed(format!("Keyboard is shutting down: {}", e)))?;
+        match tokio::time::timeout(SNAPSHOT_TIMEOUT, reply).await {
+            Ok(Ok(snapshot)) => Ok(snapshot),
+            _ => Err(zbus::fdo::Error::Failed(
+                "The keyboard did not answer".to_string(),
+            )),
+        }
+    }
+}
+
+#[zbus::interface(name = "io.github.cosboard.Cosboard.Debug")]
+impl DebugInterface {
+    /// Returns the ID of the panel shown.
+    async fn current_panel(&self) -> zbus::fdo::Result<String> {
+        Ok(self.snapshot().await?.panel_id)
+    }
+
+    /// Returns the identifiers of the keys held down, sorted.
+    async fn pressed_keys(&self) -> zbus::fdo::Result<Vec<String>> {
+        Ok(self.snapshot().await?.pressed_keys)
+    }
+
+    /// Returns the names of the active modifiers ("Shift", "Ctrl", ...).
+    async fn active_modifiers(&self) -> zbus::fdo::Result<Vec<String>> {
+        Ok(self.snapshot().await?.modifiers)
+    }
+
+    /// Returns the identifier, x, y, width and height of each key of the
+    /// panel shown, in logical pixels on the keyboard surface.
+    async fn key_geometry(&self) -> zbus::fdo::Result<Vec<(String, f64, f64, f64, f64)>> {
+        let snapshot = self.snapshot().await?;
+        Ok(snapshot
+            .keys
+            .into_iter()
+            .map(|key| {
+                let KeyBounds {
+                    identifier,
+                    x,
+                    y,
+                    width,
+                    height,
+                } = key;
+                (identifier, x.into(), y.into(), width.into(), height.into())
+            })
+            .collect())
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::layout::{Key, Layout, Modifier, Panel, Row, Sizing};
+
+    fn create_test_layout() -> Layout {
+        let key = |identifier: &str, width: f32| {
+            Cell::Key(Key {
+                label: identifier.to_uppercase(),
+                identifier: Some(identifier.to_string()),
+                width: Sizing::Relative(width),
+                ..Key::default()
+            })
+        };
+        let panel = Panel {
+            id: "main".to_string(),
+            padding: Some(0.0),
+            margin: Some(0.0),
+            rows: vec![Row {
+                cells: vec![key("q", 1.0), key("space", 3.0)],
+            }],
+            ..Panel::default()
+        };
+        Layout {
+            name: "Test".to_string(),
+            default_panel_id: "main".to_string(),
+            panels: HashMap::from([("main".to_string(), panel)]),
+            ..Layout::default()
+        }
+    }
+
+    /// Test: A snapshot has the panel, pressed keys, modifiers and the keys
+    /// placed within the panel area of the surface.
+    #[test]
+    fn test_capture() {
+        let mut renderer = KeyboardRenderer::new(create_test_layout());
+        renderer.press_key("space");
+        renderer.activate_modifier(Modifier::Ctrl, true);
+
+        let area = Rectangle::new(0.0, 20.0, 400.0, 100.0);
+        let snapshot = KeyboardSnapshot::capture(&renderer, area, 1.0);
+        assert_eq!(snapshot.panel_id, "main");
+        assert_eq!(snapshot.pressed_keys, ["space"]);
+        assert_eq!(snapshot.modifiers, ["Ctrl"]);
+
+        let keys: Vec<(&str, f32, f32, f32)> = snapshot
+            .keys
+            .iter()
+            .map(|key| (key.identifier.as_str(), key.x, key.y, key.width))
+            .collect();
+        assert_eq!(
+            keys,
+            [("q", 0.0, 20.0, 100.0), ("space", 100.0, 20.0, 300.0)]
+        );
+    }
+
+    /// Test: Only the first answer reaches the caller.
+    #[test]
+    fn test_snapshot_request_answer() {
+        let (request, mut reply) = SnapshotRequest::new();
+        let snapshot = KeyboardSnapshot {
+            panel_id: "main".to_string(),
+            ..KeyboardSnapshot::default()
+        };
+        request.clone().answer(snapshot.clone());
+        request.answer(KeyboardSnapshot::default());
+        assert_eq!(reply.try_recv().unwrap(), Some(snapshot));
+    }
+}
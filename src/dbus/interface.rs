@@ -0,0 +1,542 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The `io.github.cosboard.Cosboard` D-Bus interface object.
+
+use super::{
+    CallGate, Confirmation, DebugInterface, Decision, Peer, SharedState, CONFIRMATION_TIMEOUT,
+    DBUS_NAME, DBUS_PATH,
+};
+use crate::actions::{ActionRegistry, AppAction};
+use crate::fl;
+use crate::input::{KeyCombo, SharedJournal};
+use crate::layer_shell::{Interactivity, Layer};
+use cosmic::iced::futures::channel::mpsc::UnboundedSender;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+use zbus::message::Header;
+use zbus::object_server::SignalEmitter;
+
+/// D-Bus interface object served at [`DBUS_PATH`].
+///
+/// Calls are forwarded to the applet as actions through `requests`; property
+/// reads are served from the shared state snapshot.
+#[derive(Debug)]
+pub struct CosboardInterface {
+    requests: UnboundedSender<AppAction>,
+    state: SharedState,
+    actions: ActionRegistry,
+    /// Callers for the user to accept, if the applet can ask
+    confirmations: Option<UnboundedSender<Confirmation>>,
+    /// Rate limits and answers of typing callers
+    gate: Mutex<CallGate>,
+    /// Key event journal served by `DumpEvents`, if any
+    journal: Option<SharedJournal>,
+}
+
+impl CosboardInterface {
+    /// Creates an interface that forwards requests through `requests`.
+    #[must_use]
+    pub fn new(requests: UnboundedSender<AppAction>, state: SharedState) -> Self {
+        Self {
+            requests,
+            state,
+            actions: ActionRegistry::new(),
+            confirmations: None,
+            gate: Mutex::new(CallGate::new()),
+            journal: None,
+        }
+    }
+
+    /// Sends callers the user must accept through `confirmations`.
+    ///
+    /// Without it, such callers are refused.
+    #[must_use]
+    pub fn with_confirmations(mut self, confirmations: UnboundedSender<Confirmation>) -> Self {
+        self.confirmations = Some(confirmations);
+        self
+    }
+
+    /// Serves `journal` through `DumpEvents`.
+    ///
+    /// Without it, `DumpEvents` fails.
+    #[must_use]
+    pub fn with_journal(mut self, journal: SharedJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Forwards a request to the applet.
+    fn forward(&self, request: AppAction) -> zbus::fdo::Result<()> {
+        self.requests
+            .unbounded_send(request)
+            .map_err(|e| zbus::fdo::Error::Failed(format!("Keyboard is shutting down: {}", e)))
+    }
+
+    /// Returns a copy of the shared state.
+    fn snapshot(&self) -> super::DbusState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or_default()
+    }
+
+    /// Locks the per-caller state.
+    fn gate(&self) -> MutexGuard<'_, CallGate> {
+        self.gate.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Checks that the caller of a typing method may type.
+    ///
+    /// `request` describes the call to the user if they are asked.
+    async fn authorize(
+        &self,
+        request: &str,
+        header: &Header<'_>,
+        connection: &zbus::Connection,
+    ) -> zbus::fdo::Result<Peer> {
+        let policy = self.snapshot().external_input;
+        if policy.is_off() {
+            return Err(zbus::fdo::Error::AccessDenied(
+                "Typing over D-Bus is disabled (see the external_input setting)".to_string(),
+            ));
+        }
+        let sender = header
+            .sender()
+            .ok_or_else(|| zbus::fdo::Error::AccessDenied("Unknown caller".to_string()))?;
+        if !self.gate().admit(sender.as_str(), policy.rate_limit, Instant::now()) {
+            return Err(zbus::fdo::Error::LimitsExceeded(format!(
+                "At most {} typing calls per second are allowed",
+                policy.rate_limit
+            )));
+        }
+
+        let peer = Peer::identify(connection, sender).await;
+        let decision = self.gate().resolve(&peer.sender, policy.decide(&peer));
+        match decision {
+            Decision::Allow => Ok(peer),
+            Decision::Ask => self.confirm(peer, request).await,
+            Decision::Deny => {
+                tracing::warn!("Refused typing request from {}", peer);
+                Err(zbus::fdo::Error::AccessDenied(format!(
+                    "{} is not allowed to type (see the external_input setting)",
+                    peer
+                )))
+            }
+        }
+    }
+
+    /// Asks the user to accept a caller and waits for the answer.
+    async fn confirm(&self, peer: Peer, request: &str) -> zbus::fdo::Result<Peer> {
+        let Some(ref confirmations) = self.confirmations else {
+            return Err(zbus::fdo::Error::AccessDenied(format!(
+                "{} cannot be confirmed, no keyboard to ask",
+                peer
+            )));
+        };
+        if !self.gate().begin_prompt(&peer.sender) {
+            return Err(zbus::fdo::Error::AccessDenied(format!(
+                "Already asking the user to accept {}",
+                peer
+            )));
+        }
+
+        tracing::info!("Asking the user to accept {} ({})", peer, request);
+        let (confirmation, reply) = Confirmation::new(peer.clone(), request);
+        let accepted = confirmations.unbounded_send(confirmation).is_ok()
+            && matches!(
+                tokio::time::timeout(CONFIRMATION_TIMEOUT, reply).await,
+                Ok(Ok(true))
+            );
+        self.gate().answer(&peer.sender, accepted);
+
+        if accepted {
+            tracing::info!("User accepted {}", peer);
+            Ok(peer)
+        } else {
+            tracing::warn!("User did not accept {}", peer);
+            Err(zbus::fdo::Error::AccessDenied(format!(
+                "{} was not accepted by the user",
+                peer
+            )))
+        }
+    }
+}
+
+#[zbus::interface(name = "io.github.cosboard.Cosboard")]
+impl CosboardInterface {
+    /// Shows the keyboard.
+    fn show(&self) -> zbus::fdo::Result<()> {
+        self.forward(AppAction::Show)
+    }
+
+    /// Hides the keyboard.
+    fn hide(&self) -> zbus::fdo::Result<()> {
+        self.forward(AppAction::Hide)
+    }
+
+    /// Toggles keyboard visibility.
+    fn toggle(&self) -> zbus::fdo::Result<()> {
+        self.forward(AppAction::Toggle)
+    }
+
+    /// Quits the keyboard.
+    fn quit(&self) -> zbus::fdo::Result<()> {
+        self.forward(AppAction::Quit)
+    }
+
+    /// Runs a named action (e.g. `"switch_panel:numpad"`).
+    ///
+    /// Actions that type are refused; use `TypeText` or `SendKey`.
+    fn run_action(&self, action: &str) -> zbus::fdo::Result<()> {
+        let action = self
+            .actions
+            .parse(action)
+            .map_err(|e| zbus::fdo::Error::InvalidArgs(e.to_string()))?;
+        if action.types_text() {
+            return Err(zbus::fdo::Error::AccessDenied(format!(
+                "'{}' types text; use TypeText or SendKey",
+                action.name()
+            )));
+        }
+        self.forward(action)
+    }
+
+    /// Switches to a configuration profile (e.g. `"tablet"`), creating it
+    /// from the current settings if it was never used.
+    fn set_profile(&self, name: &str) -> zbus::fdo::Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs("No profile name".to_string()));
+        }
+        self.forward(AppAction::SetProfile(name.to_string()))
+    }
+
+    /// Switches to a panel of the current layout (e.g. `"numpad"`).
+    fn switch_panel(&self, panel_id: &str) -> zbus::fdo::Result<()> {
+        let panel_id = panel_id.trim();
+        if panel_id.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs("No panel ID".to_string()));
+        }
+        self.forward(AppAction::SwitchPanel(panel_id.to_string()))
+    }
+
+    /// Switches to a layout, by path or by file name on the layout search
+    /// path. The layout is remembered in the configuration.
+    fn set_layout(&self, path: &str) -> zbus::fdo::Result<()> {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs("No layout path".to_string()));
+        }
+        self.forward(AppAction::SwitchLayout(path.to_string()))
+    }
+
+    /// Returns the ID of the panel shown, or an empty string before a
+    /// layout is loaded.
+    fn get_current_panel(&self) -> String {
+        self.snapshot().panel_id
+    }
+
+    /// Types text into the focused application.
+    async fn type_text(
+        &self,
+        text: String,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> zbus::fdo::Result<()> {
+        if text.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs("No text to type".to_string()));
+        }
+        let peer = self
+            .authorize(&fl!("confirm-type-text"), &header, connection)
+            .await?;
+        tracing::debug!("Typing {} characters for {}", text.chars().count(), peer);
+        self.forward(AppAction::TypeText(text))
+    }
+
+    /// Presses a key (XKB keysym name) with modifiers in the focused
+    /// application. `modifiers` is an X11 modifier mask: Shift 1, Control 4,
+    /// Alt 8, Super 64.
+    async fn send_key(
+        &self,
+        keysym: String,
+        modifiers: u32,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> zbus::fdo::Result<()> {
+        let combo = KeyCombo::from_mask(&keysym, modifiers).ok_or_else(|| {
+            zbus::fdo::Error::InvalidArgs(format!(
+                "Invalid key '{}' with modifiers {:#x}",
+                keysym, modifiers
+            ))
+        })?;
+        let peer = self
+            .authorize(
+                &fl!("confirm-send-key", combo = combo.to_string()),
+                &header,
+                connection,
+            )
+            .await?;
+        tracing::debug!("Sending {} for {}", combo, peer);
+        self.forward(AppAction::SendKey(combo))
+    }
+
+    /// Returns the journal of the last key events, one event per line.
+    ///
+    /// The journal holds what was typed, so the user has to accept every
+    /// call, whatever the external_input setting says.
+    async fn dump_events(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> zbus::fdo::Result<String> {
+        let Some(ref journal) = self.journal else {
+            return Err(zbus::fdo::Error::NotSupported(
+                "No key event journal".to_string(),
+            ));
+        };
+        let sender = header
+            .sender()
+            .ok_or_else(|| zbus::fdo::Error::AccessDenied("Unknown caller".to_string()))?;
+        let peer = Peer::identify(connection, sender).await;
+        let peer = self.confirm(peer, &fl!("confirm-dump-events")).await?;
+        tracing::info!("Dumping the key event journal for {}", peer);
+        journal
+            .lock()
+            .map(|journal| journal.dump())
+            .map_err(|_| zbus::fdo::Error::Failed("Key event journal unavailable".to_string()))
+    }
+
+    /// Whether the keyboard is currently visible.
+    #[zbus(property)]
+    fn visible(&self) -> bool {
+        self.snapshot().visible
+    }
+
+    /// Whether the keyboard floats instead of being docked to an edge.
+    #[zbus(property)]
+    fn floating(&self) -> bool {
+        self.snapshot().floating
+    }
+
+    /// Width of the keyboard surface in logical pixels. While the keyboard
+    /// is hidden, the width it last had.
+    #[zbus(property)]
+    fn width(&self) -> u32 {
+        self.snapshot().width
+    }
+
+    /// Height of the keyboard surface in logical pixels. While the keyboard
+    /// is hidden, the height it last had.
+    #[zbus(property)]
+    fn height(&self) -> u32 {
+        self.snapshot().height
+    }
+
+    /// Keyboard interactivity of the keyboard surface ("None" or "OnDemand").
+    #[zbus(property)]
+    fn keyboard_interactivity(&self) -> String {
+        self.snapshot().keyboard_interactivity.as_str().to_string()
+    }
+
+    #[zbus(property)]
+    fn set_keyboard_interactivity(&mut self, value: String) -> zbus::fdo::Result<()> {
+        let interactivity = Interactivity::from_name(&value).ok_or_else(|| {
+            zbus::fdo::Error::InvalidArgs(format!(
+                "Unknown keyboard interactivity '{}' (expected 'None' or 'OnDemand')",
+                value
+            ))
+        })?;
+        self.forward(AppAction::SetKeyboardInteractivity(interactivity))
+    }
+
+    /// Layer-shell layer of the keyboard surface ("Top", "Overlay", ...).
+    #[zbus(property)]
+    fn layer(&self) -> String {
+        self.snapshot().layer.as_str().to_string()
+    }
+
+    #[zbus(property)]
+    fn set_layer(&mut self, value: String) -> zbus::fdo::Result<()> {
+        let layer = Layer::from_name(&value).ok_or_else(|| {
+            zbus::fdo::Error::InvalidArgs(format!(
+                "Unknown layer '{}' (expected 'Background', 'Bottom', 'Top' or 'Overlay')",
+                value
+            ))
+        })?;
+        self.forward(AppAction::SetLayer(layer))
+    }
+
+    /// Emitted whenever the keyboard is shown or hidden.
+    #[zbus(signal)]
+    async fn visibility_changed(emitter: &SignalEmitter<'_>, visible: bool) -> zbus::Result<()>;
+
+    /// Emitted whenever another panel is shown.
+    #[zbus(signal)]
+    async fn panel_changed(emitter: &SignalEmitter<'_>, panel_id: &str) -> zbus::Result<()>;
+}
+
+/// Registers the interface, and the debug interface if given, on the
+/// session bus and claims [`DBUS_NAME`].
+pub(crate) async fn serve(
+    interface: CosboardInterface,
+    debug: Option<DebugInterface>,
+) -> zbus::Result<zbus::Connection> {
+    let mut builder = zbus::connection::Builder::session()?
+        .name(DBUS_NAME)?
+        .serve_at(DBUS_PATH, interface)?;
+    if let Some(debug) = debug {
+        builder = builder.serve_at(DBUS_PATH, debug)?;
+    }
+    builder.build().await
+}
+
+/// Emits property change notifications (and optionally `VisibilityChanged`).
+pub(crate) async fn emit_changes(
+    connection: &zbus::Connection,
+    visibility_changed: bool,
+) -> zbus::Result<()> {
+    let iface_ref = connection
+        .object_server()
+        .interface::<_, CosboardInterface>(DBUS_PATH)
+        .await?;
+    let iface = iface_ref.get().await;
+    let emitter = iface_ref.signal_emitter();
+
+    iface.visible_changed(emitter).await?;
+    iface.floating_changed(emitter).await?;
+    iface.width_changed(emitter).await?;
+    iface.height_changed(emitter).await?;
+    iface.keyboard_interactivity_changed(emitter).await?;
+    iface.layer_changed(emitter).await?;
+
+    if visibility_changed {
+        let visible = iface.snapshot().visible;
+        CosboardInterface::visibility_changed(emitter, visible).await?;
+    }
+
+    Ok(())
+}
+
+/// Emits `PanelChanged` for `panel_id`.
+pub(crate) async fn emit_panel_changed(
+    connection: &zbus::Connection,
+    panel_id: &str,
+) -> zbus::Result<()> {
+    let iface_ref = connection
+        .object_server()
+        .interface::<_, CosboardInterface>(DBUS_PATH)
+        .await?;
+    CosboardInterface::panel_changed(iface_ref.signal_emitter(), panel_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbus::DbusState;
+    use cosmic::iced::futures::channel::mpsc::unbounded;
+    use std::sync::{Arc, Mutex};
+
+    /// Test: Method calls and property writes are forwarded as requests.
+    #[test]
+    fn test_requests_are_forwarded() {
+        let (sender, mut receiver) = unbounded();
+        let mut iface = CosboardInterface::new(sender, Arc::default());
+
+        iface.show().unwrap();
+        iface.toggle().unwrap();
+        iface.set_layer("top".to_string()).unwrap();
+        iface
+            .set_keyboard_interactivity("OnDemand".to_string())
+            .unwrap();
+        iface.run_action("switch_panel:numpad").unwrap();
+        iface.set_profile(" tablet ").unwrap();
+        iface.switch_panel("symbols").unwrap();
+        iface.set_layout("dvorak.json").unwrap();
+
+        assert_eq!(receiver.try_recv().unwrap(), AppAction::Show);
+        assert_eq!(receiver.try_recv().unwrap(), AppAction::Toggle);
+        assert_eq!(receiver.try_recv().unwrap(), AppAction::SetLayer(Layer::Top));
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            AppAction::SetKeyboardInteractivity(Interactivity::OnDemand)
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            AppAction::SwitchPanel("numpad".to_string())
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            AppAction::SetProfile("tablet".to_string())
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            AppAction::SwitchPanel("symbols".to_string())
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            AppAction::SwitchLayout("dvorak.json".to_string())
+        );
+    }
+
+    /// Test: Invalid property values are rejected without forwarding.
+    #[test]
+    fn test_invalid_property_values_rejected() {
+        let (sender, mut receiver) = unbounded();
+        let mut iface = CosboardInterface::new(sender, Arc::default());
+
+        assert!(iface.set_layer("sideways".to_string()).is_err());
+        assert!(iface.set_keyboard_interactivity("always".to_string()).is_err());
+        assert!(iface.run_action("fly").is_err());
+        assert!(iface.set_profile(" ").is_err());
+        assert!(iface.switch_panel("").is_err());
+        assert!(iface.set_layout(" ").is_err());
+        assert!(receiver.try_recv().is_err(), "Nothing should be forwarded");
+    }
+
+    /// Test: RunAction refuses typing actions, which go through the policy.
+    #[test]
+    fn test_run_action_cannot_type() {
+        let (sender, mut receiver) = unbounded();
+        let iface = CosboardInterface::new(sender, Arc::default());
+
+        assert!(matches!(
+            iface.run_action("insert_text:hunter2"),
+            Err(zbus::fdo::Error::AccessDenied(_))
+        ));
+        assert!(iface.run_action("type_text:hunter2").is_err());
+        assert!(iface.run_action("send_key:ctrl+alt+Delete").is_err());
+        assert!(receiver.try_recv().is_err(), "Nothing should be forwarded");
+    }
+
+    /// Test: Property reads reflect the shared state snapshot.
+    #[test]
+    fn test_properties_read_shared_state() {
+        let (sender, _receiver) = unbounded();
+        let state = Arc::new(Mutex::new(DbusState::default()));
+        let iface = CosboardInterface::new(sender, state.clone());
+
+        assert!(!iface.visible());
+        assert!(!iface.floating());
+        assert_eq!(iface.layer(), "Overlay");
+        assert_eq!(iface.keyboard_interactivity(), "None");
+        assert_eq!(iface.get_current_panel(), "");
+
+        {
+            let mut state = state.lock().unwrap();
+            state.visible = true;
+            state.floating = true;
+            state.width = 800;
+            state.height = 240;
+            state.layer = Layer::Top;
+            state.keyboard_interactivity = Interactivity::OnDemand;
+            state.panel_id = "numpad".to_string();
+        }
+
+        assert!(iface.visible());
+        assert!(iface.floating());
+        assert_eq!((iface.width(), iface.height()), (800, 240));
+        assert_eq!(iface.layer(), "Top");
+        assert_eq!(iface.keyboard_interactivity(), "OnDemand");
+        assert_eq!(iface.get_current_panel(), "numpad");
+    }
+}
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! D-Bus control interface for Cosboard.
+//!
+//! This module exposes the keyboard on the session bus so that other
+//! processes (scripts, settings panels, the compositor) can control it:
+//!
+//! - Service: `io.github.cosboard.Cosboard`
+//! - Object path: `/io/github/cosboard/Cosboard`
+//! - Methods: `Show()`, `Hide()`, `Toggle()`, `Quit()`, `RunAction(action: s)`,
+//!   `SetProfile(name: s)`, `SwitchPanel(panel_id: s)`, `SetLayout(path: s)`,
+//!   `GetCurrentPanel() -> s`, `TypeText(text: s)`,
+//!   `SendKey(keysym: s, modifiers: u)`, `DumpEvents() -> s`
+//! - Properties: `Visible`, `Floating`, `Width`, `Height` (read-only),
+//!   `KeyboardInteractivity`, `Layer`
+//! - Signals: `VisibilityChanged(visible: bool)`, `PanelChanged(panel_id: s)`
+//!
+//! With `COSBOARD_DEBUG_DBUS=1`, the object also serves the
+//! [`io.github.cosboard.Cosboard.Debug`](debug) interface, which reports
+//! the panel, pressed keys, modifiers and key geometry to integration tests.
+//!
+//! # Architecture
+//!
+//! The interface object lives on the zbus connection and never touches the
+//! applet model directly. Method calls and property writes are forwarded to
+//! the applet as [`AppAction`]s through a subscription, and property reads
+//! are answered from a [`SharedState`] snapshot that the applet keeps up to
+//! date after handling each request.
+//!
+//! The subscription is a single long-lived stream that only yields when a
+//! D-Bus call arrives, so it does not wake the applet while idle.
+//!
+//! # Typing
+//!
+//! `TypeText` and `SendKey` type into the focused application through the
+//! keyboard's emission backend. They are refused unless the caller is
+//! permitted by the [`ExternalInputPolicy`] in the configuration, and
+//! `RunAction` refuses actions that type, so it cannot bypass the policy.
+//! Callers the user has to accept are announced to the applet as
+//! [`DbusEvent::Confirm`], and the call waits for the answer.
+//!
+//! The methods that only control the keyboard (`RunAction`, `SetProfile`,
+//! `SwitchPanel`, `SetLayout`) are open like `Show` and `Hide`: the session
+//! bus only accepts the user's own processes, and they take no input.
+//!
+//! `DumpEvents` returns the [key event journal](crate::input::journal),
+//! which holds what was typed, so the user is asked every time.
+
+pub mod debug;
+pub mod interface;
+pub mod peer;
+pub mod policy;
+
+pub use debug::{
+    debug_interface_from_env, DebugInterface, KeyBounds, KeyboardSnapshot, SnapshotRequest,
+    DEBUG_DBUS_ENV,
+};
+pub use interface::CosboardInterface;
+pub use peer::Peer;
+pub use policy::{CallGate, Decision, ExternalInputMode, ExternalInputPolicy, CONFIRMATION_TIMEOUT};
+
+use crate::actions::AppAction;
+use crate::input::SharedJournal;
+use crate::layer_shell::{Interactivity, Layer};
+use cosmic::iced::futures::channel::oneshot;
+use cosmic::iced::futures::{SinkExt, StreamExt};
+use cosmic::iced::stream;
+use cosmic::iced_futures::Subscription;
+use std::sync::{Arc, Mutex};
+
+/// Well-known bus name owned by the keyboard.
+pub const DBUS_NAME: &str = "io.github.cosboard.Cosboard";
+
+/// Object path of the control interface.
+pub const DBUS_PATH: &str = "/io/github/cosboard/Cosboard";
+
+/// Events produced by the D-Bus subscription.
+#[derive(Debug, Clone)]
+pub enum DbusEvent {
+    /// The service is registered on the session bus.
+    Ready(zbus::Connection),
+    /// The service could not be registered (no session bus, name taken, ...).
+    Unavailable(String),
+    /// A client called a method or wrote a property.
+    Request(AppAction),
+    /// A client must be accepted by the user before its call is carried out.
+    Confirm(Confirmation),
+    /// A debug client waits for a snapshot of the keyboard.
+    Snapshot(SnapshotRequest),
+}
+
+/// A call waiting for the user to accept its caller.
+///
+/// Dropping every copy without answering refuses the call.
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    /// The caller.
+    pub peer: Peer,
+    /// What the caller asks to do (e.g. "type text").
+    pub request: String,
+    /// Channel the answer is sent on, taken by the first answer
+    reply: Arc<Mutex<Option<oneshot::Sender<bool>>>>,
+}
+
+impl Confirmation {
+    /// Creates a confirmation and the receiver of its answer.
+    #[must_use]
+    pub fn new(peer: Peer, request: impl Into<String>) -> (Self, oneshot::Receiver<bool>) {
+        let (sender, receiver) = oneshot::channel();
+        let confirmation = Self {
+            peer,
+            request: request.into(),
+            reply: Arc::new(Mutex::new(Some(sender))),
+        };
+        (confirmation, receiver)
+    }
+
+    /// Answers the call. Later answers are ignored.
+    pub fn answer(&self, accepted: bool) {
+        let sender = self.reply.lock().ok().and_then(|mut reply| reply.take());
+        if let Some(sender) = sender {
+            let _ = sender.send(accepted);
+        }
+    }
+
+    /// Returns `true` if the caller still waits for an answer.
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.reply
+            .lock()
+            .map(|reply| reply.as_ref().is_some_and(|sender| !sender.is_canceled()))
+            .unwrap_or(false)
+    }
+}
+
+/// Snapshot of applet state used to answer D-Bus property reads.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbusState {
+    /// Whether the keyboard is currently visible.
+    pub visible: bool,
+    /// Current keyboard interactivity of the keyboard surface.
+    pub keyboard_interactivity: Interactivity,
+    /// Current layer of the keyboard surface.
+    pub layer: Layer,
+    /// Whether the keyboard floats instead of being docked.
+    pub floating: bool,
+    /// Width of the keyboard surface, in logical pixels.
+    pub width: u32,
+    /// Height of the keyboard surface, in logical pixels.
+    pub height: u32,
+    /// Who may type through the interface.
+    pub external_input: ExternalInputPolicy,
+    /// ID of the panel shown, empty before a layout is loaded.
+    pub panel_id: String,
+}
+
+/// State shared between the applet and the D-Bus interface object.
+pub type SharedState = Arc<Mutex<DbusState>>;
+
+/// Creates the subscription that owns the D-Bus service.
+///
+/// The service is registered once and kept alive for as long as the
+/// subscription is active. Incoming calls are emitted as
+/// [`DbusEvent::Request`], and callers to accept as [`DbusEvent::Confirm`].
+/// `journal` is served by `DumpEvents`. The debug interface is served too
+/// if the environment enables it, and asks for [`DbusEvent::Snapshot`]s.
+pub fn subscription(state: SharedState, journal: SharedJournal) -> Subscription<DbusEvent> {
+    Subscription::run_with_id(
+        std::any::TypeId::of::<CosboardInterface>(),
+        stream::channel(16, move |mut output| async move {
+            let (sender, requests) = cosmic::iced::futures::channel::mpsc::unbounded();
+            let (confirm_sender, confirmations) = cosmic::iced::futures::channel::mpsc::unbounded();
+            let (snapshot_sender, snapshots) = cosmic::iced::futures::channel::mpsc::unbounded();
+            let interface = CosboardInterface::new(sender, state)
+                .with_confirmations(confirm_sender)
+                .with_journal(journal);
+            let debug = debug_interface_from_env().then(|| DebugInterface::new(snapshot_sender));
+            let mut events = cosmic::iced::futures::stream::select(
+                cosmic::iced::futures::stream::select(
+                    requests.map(DbusEvent::Request),
+                    confirmations.map(DbusEvent::Confirm),
+                ),
+                snapshots.map(DbusEvent::Snapshot),
+            );
+
+            if debug.is_some() {
+                tracing::warn!("Serving the D-Bus debug interface ({})", DEBUG_DBUS_ENV);
+            }
+            let connection = match interface::serve(interface, debug).await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!("D-Bus service unavailable: {}", e);
+                    let _ = output.send(DbusEvent::Unavailable(e.to_string())).await;
+                    return;
+                }
+            };
+
+            tracing::info!("D-Bus service registered as {}", DBUS_NAME);
+            let _ = output.send(DbusEvent::Ready(connection.clone())).await;
+
+            while let Some(event) = events.next().await {
+                if output.send(event).await.is_err() {
+                    break;
+                }
+            }
+
+            drop(connection);
+        }),
+    )
+}
+
+/// Notifies D-Bus clients that the shared state changed.
+///
+/// Emits `PropertiesChanged` for the interface's properties and, when
+/// `visibility_changed` is set, the `VisibilityChanged` signal.
+pub async fn notify_state_changed(connection: zbus::Connection, visibility_changed: bool) {
+    if let Err(e) = interface::emit_changes(&connection, visibility_changed).await {
+        tracing::debug!("Failed to emit D-Bus change notification: {}", e);
+    }
+}
+
+/// Notifies D-Bus clients that `panel_id` is shown, with `PanelChanged`.
+pub async fn notify_panel_changed(connection: zbus::Connection, panel_id: String) {
+    if let Err(e) = interface::emit_panel_changed(&connection, &panel_id).await {
+        tracing::debug!("Failed to emit D-Bus panel change: {}", e);
+    }
+}
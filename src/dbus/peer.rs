@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Identification of D-Bus callers.
+//!
+//! A caller is known on the bus only by its unique connection name
+//! (`:1.42`). The bus daemon vouches for the credentials of the connection
+//! (the process and user that opened it); the process's executable is then
+//! read from `/proc`. Inside a sandbox the process may not be visible, and
+//! the caller stays unidentified.
+
+use std::fmt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// A process calling the D-Bus interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peer {
+    /// Unique bus name of the caller's connection.
+    pub sender: String,
+    /// Process ID of the caller, if the bus daemon reported it.
+    pub pid: Option<u32>,
+    /// User ID of the caller, if the bus daemon reported it.
+    pub uid: Option<u32>,
+    /// Path of the caller's executable, if it could be read.
+    pub executable: Option<PathBuf>,
+}
+
+impl Peer {
+    /// Creates a peer for a connection opened by process `pid` of user
+    /// `uid`, reading its executable.
+    #[must_use]
+    pub fn from_credentials(sender: impl Into<String>, pid: Option<u32>, uid: Option<u32>) -> Self {
+        let executable = pid.and_then(|pid| {
+            std::fs::read_link(Path::new("/proc").join(pid.to_string()).join("exe")).ok()
+        });
+        Self {
+            sender: sender.into(),
+            pid,
+            uid,
+            executable,
+        }
+    }
+
+    /// Identifies the process behind the `sender` connection.
+    pub async fn identify(
+        connection: &zbus::Connection,
+        sender: &zbus::names::UniqueName<'_>,
+    ) -> Self {
+        let credentials = match zbus::fdo::DBusProxy::new(connection).await {
+            Ok(proxy) => proxy
+                .get_connection_credentials(sender.clone().into())
+                .await
+                .map_err(|e| tracing::debug!("No credentials for {}: {}", sender, e))
+                .ok(),
+            Err(e) => {
+                tracing::debug!("Cannot ask the bus about {}: {}", sender, e);
+                None
+            }
+        };
+        Self::from_credentials(
+            sender.as_str(),
+            credentials.as_ref().and_then(|c| c.process_id()),
+            credentials.as_ref().and_then(|c| c.unix_user_id()),
+        )
+    }
+
+    /// Returns `true` if the caller runs as the same user as the keyboard.
+    ///
+    /// Unknown users are not the same user.
+    #[must_use]
+    pub fn is_same_user(&self) -> bool {
+        let own = std::fs::metadata("/proc/self").map(|metadata| metadata.uid());
+        matches!((self.uid, own), (Some(uid), Ok(own)) if uid == own)
+    }
+
+    /// Returns `true` if an allowlist entry names this peer.
+    ///
+    /// Entries containing `/` must equal the executable path; others are
+    /// compared with the executable's file name.
+    #[must_use]
+    pub fn matches(&self, entry: &str) -> bool {
+        let Some(ref executable) = self.executable else {
+            return false;
+        };
+        if entry.contains('/') {
+            executable == Path::new(entry)
+        } else {
+            executable.file_name().is_some_and(|name| name == entry)
+        }
+    }
+}
+
+impl fmt::Display for Peer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.executable, self.pid) {
+            (Some(executable), Some(pid)) => {
+                write!(f, "{} (pid {}, {})", executable.display(), pid, self.sender)
+            }
+            (None, Some(pid)) => write!(f, "pid {} ({})", pid, self.sender),
+            _ => write!(f, "{}", self.sender),
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The executable of a running process is read from /proc, and its user compared.
+    #[test]
+    fn test_from_credentials() {
+        let uid = std::fs::metadata("/proc/self").unwrap().uid();
+        let peer = Peer::from_credentials(":1.7", Some(std::process::id()), Some(uid));
+        assert_eq!(peer.executable, std::env::current_exe().ok());
+        assert!(peer.is_same_user());
+        assert!(peer
+            .to_string()
+            .ends_with(&format!("(pid {}, :1.7)", std::process::id())));
+
+        let unknown = Peer::from_credentials(":1.8", None, None);
+        assert_eq!(unknown.executable, None);
+        assert!(!unknown.is_same_user());
+        assert_eq!(unknown.to_string(), ":1.8");
+        assert!(!unknown.matches("cosboard"));
+    }
+}
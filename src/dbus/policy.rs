@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Which D-Bus clients may type through the keyboard.
+//!
+//! `TypeText` and `SendKey` let other programs (automation tools, screen
+//! readers, switch access software) type into the focused application. Any
+//! process on the session bus can call them, so they are refused unless the
+//! user opts in with an [`ExternalInputPolicy`] in the configuration:
+//!
+//! ```ron
+//! (mode: Prompt, allowed: ["ydotool", "/usr/bin/orca"], rate_limit: 20)
+//! ```
+//!
+//! Allowlist entries name a client process: either its executable name or
+//! the full path of its executable. Callers are identified by the
+//! credentials the bus daemon reports for their connection (see
+//! [`super::peer`]); callers running as another user are always refused.
+//!
+//! In [`ExternalInputMode::Prompt`] mode, a caller that is not on the
+//! allowlist waits while the keyboard shows a toast with an Accept button.
+//! The answer holds for the caller's connection; a prompt left unanswered
+//! for [`CONFIRMATION_TIMEOUT`] counts as refused.
+//!
+//! Every caller may make at most `rate_limit` typing calls per second, in
+//! short bursts; [`CallGate`] keeps the count and the answers per caller.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::peer::Peer;
+
+/// Time the user has to accept a caller before it is refused.
+///
+/// Shorter than the default D-Bus method call timeout (25 seconds), so the
+/// caller gets the refusal rather than a timeout.
+pub const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Default number of typing calls a caller may make per second.
+pub const DEFAULT_RATE_LIMIT: u32 = 20;
+
+/// Who may type through the D-Bus interface.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExternalInputMode {
+    /// Nobody: typing over D-Bus is refused.
+    #[default]
+    Off,
+    /// Only the processes in [`ExternalInputPolicy::allowed`].
+    Allowlist,
+    /// The processes in [`ExternalInputPolicy::allowed`], and others the
+    /// user accepts when they first call.
+    Prompt,
+    /// Every client on the session bus.
+    Everyone,
+}
+
+/// Outcome of checking a caller against the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// The call is carried out.
+    Allow,
+    /// The user must accept the caller first.
+    Ask,
+    /// The call is refused.
+    Deny,
+}
+
+/// Policy for typing text and keys on behalf of D-Bus clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalInputPolicy {
+    /// Who may type.
+    #[serde(default)]
+    pub mode: ExternalInputMode,
+    /// Executable names or paths allowed to type in
+    /// [`ExternalInputMode::Allowlist`] and [`ExternalInputMode::Prompt`]
+    /// modes.
+    #[serde(default)]
+    pub allowed: Vec<String>,
+    /// Typing calls a caller may make per second (0 for no limit).
+    #[serde(default = "default_rate_limit")]
+    pub rate_limit: u32,
+}
+
+fn default_rate_limit() -> u32 {
+    DEFAULT_RATE_LIMIT
+}
+
+impl Default for ExternalInputPolicy {
+    fn default() -> Self {
+        Self {
+            mode: ExternalInputMode::default(),
+            allowed: Vec::new(),
+            rate_limit: default_rate_limit(),
+        }
+    }
+}
+
+impl ExternalInputPolicy {
+    /// Returns `true` if nobody may type over D-Bus.
+    #[must_use]
+    pub fn is_off(&self) -> bool {
+        match self.mode {
+            ExternalInputMode::Off => true,
+            ExternalInputMode::Allowlist => self.allowed.is_empty(),
+            ExternalInputMode::Prompt | ExternalInputMode::Everyone => false,
+        }
+    }
+
+    /// Decides whether `peer` may type.
+    #[must_use]
+    pub fn decide(&self, peer: &Peer) -> Decision {
+        if self.is_off() || !peer.is_same_user() {
+            return Decision::Deny;
+        }
+        if self.allowed.iter().any(|entry| peer.matches(entry)) {
+            return Decision::Allow;
+        }
+        match self.mode {
+            ExternalInputMode::Off | ExternalInputMode::Allowlist => Decision::Deny,
+            ExternalInputMode::Prompt => Decision::Ask,
+            ExternalInputMode::Everyone => Decision::Allow,
+        }
+    }
+}
+
+/// Per-caller state of typing calls: rate limits and the user's answers.
+///
+/// Callers are keyed by their unique bus name, which the bus never reuses,
+/// so an answer ends with the caller's connection.
+#[derive(Debug, Default)]
+pub struct CallGate {
+    /// Calls each caller may still make, and when that was last computed
+    budgets: HashMap<String, (f64, Instant)>,
+    /// Whether the user accepted each caller that was asked about
+    answers: HashMap<String, bool>,
+    /// Callers waiting for the user's answer
+    pending: HashSet<String>,
+}
+
+impl CallGate {
+    /// Creates a gate that has seen no calls.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a call from `sender` at `now`.
+    ///
+    /// Returns `false` if the caller exceeds `rate_limit` calls per second.
+    /// Up to `rate_limit` calls may be made at once after a pause.
+    pub fn admit(&mut self, sender: &str, rate_limit: u32, now: Instant) -> bool {
+        if rate_limit == 0 {
+            return true;
+        }
+        let limit = f64::from(rate_limit);
+
+        // A caller idle for a second has its full budget again
+        self.budgets
+            .retain(|_, (_, last)| now.saturating_duration_since(*last) < Duration::from_secs(1));
+        let (budget, last) = self
+            .budgets
+            .entry(sender.to_string())
+            .or_insert((limit, now));
+        *budget = (*budget + now.saturating_duration_since(*last).as_secs_f64() * limit).min(limit);
+        *last = now;
+
+        if *budget >= 1.0 {
+            *budget -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Applies the user's earlier answer about `sender` to a decision.
+    #[must_use]
+    pub fn resolve(&self, sender: &str, decision: Decision) -> Decision {
+        match (decision, self.answers.get(sender)) {
+            (Decision::Ask, Some(true)) => Decision::Allow,
+            (Decision::Ask, Some(false)) => Decision::Deny,
+            (decision, _) => decision,
+        }
+    }
+
+    /// Records that the user is asked about `sender`.
+    ///
+    /// Returns `false` if a prompt for the caller is already shown.
+    pub fn begin_prompt(&mut self, sender: &str) -> bool {
+        self.pending.insert(sender.to_string())
+    }
+
+    /// Records the user's answer about `sender`.
+    pub fn answer(&mut self, sender: &str, accepted: bool) {
+        self.pending.remove(sender);
+        self.answers.insert(sender.to_string(), accepted);
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::PathBuf;
+
+    fn peer(executable: Option<&str>) -> Peer {
+        Peer {
+            sender: ":1.42".to_string(),
+            pid: executable.map(|_| 4242),
+            uid: std::fs::metadata("/proc/self")
+                .ok()
+                .map(|metadata| metadata.uid()),
+            executable: executable.map(PathBuf::from),
+        }
+    }
+
+    /// Test: The allowlist matches executable names and paths only.
+    #[test]
+    fn test_allowlist() {
+        let policy = ExternalInputPolicy {
+            mode: ExternalInputMode::Allowlist,
+            allowed: vec!["ydotool".to_string(), "/usr/bin/orca".to_string()],
+            ..ExternalInputPolicy::default()
+        };
+        assert!(!policy.is_off());
+        assert_eq!(
+            policy.decide(&peer(Some("/usr/local/bin/ydotool"))),
+            Decision::Allow
+        );
+        assert_eq!(policy.decide(&peer(Some("/usr/bin/orca"))), Decision::Allow);
+        assert_eq!(policy.decide(&peer(Some("/tmp/orca"))), Decision::Deny);
+        assert_eq!(
+            policy.decide(&peer(Some("/usr/bin/python3"))),
+            Decision::Deny
+        );
+        assert_eq!(policy.decide(&peer(None)), Decision::Deny);
+
+        let other_user = Peer {
+            uid: None,
+            ..peer(Some("/usr/bin/orca"))
+        };
+        assert_eq!(policy.decide(&other_user), Decision::Deny);
+    }
+
+    /// Test: Typing is refused by default, and asked or allowed on request.
+    #[test]
+    fn test_modes() {
+        let policy = ExternalInputPolicy::default();
+        assert!(policy.is_off());
+        assert_eq!(policy.decide(&peer(Some("/usr/bin/orca"))), Decision::Deny);
+
+        let prompt = ExternalInputPolicy {
+            mode: ExternalInputMode::Prompt,
+            allowed: vec!["orca".to_string()],
+            ..ExternalInputPolicy::default()
+        };
+        assert_eq!(prompt.decide(&peer(Some("/usr/bin/orca"))), Decision::Allow);
+        assert_eq!(
+            prompt.decide(&peer(Some("/usr/bin/python3"))),
+            Decision::Ask
+        );
+
+        let everyone = ExternalInputPolicy {
+            mode: ExternalInputMode::Everyone,
+            ..policy
+        };
+        assert_eq!(everyone.decide(&peer(None)), Decision::Allow);
+    }
+
+    /// Test: Callers get a burst of calls, refilled over a second, each on their own.
+    #[test]
+    fn test_rate_limit() {
+        let mut gate = CallGate::new();
+        let start = Instant::now();
+        for _ in 0..4 {
+            assert!(gate.admit(":1.1", 4, start));
+        }
+        assert!(!gate.admit(":1.1", 4, start));
+        assert!(gate.admit(":1.2", 4, start));
+        assert!(gate.admit(":1.1", 4, start + Duration::from_millis(250)));
+        assert!(!gate.admit(":1.1", 4, start + Duration::from_millis(250)));
+        assert!(gate.admit(":1.1", 0, start));
+    }
+
+    /// Test: Answers apply to later calls of the same caller only.
+    #[test]
+    fn test_answers() {
+        let mut gate = CallGate::new();
+        assert!(gate.begin_prompt(":1.1"));
+        assert!(!gate.begin_prompt(":1.1"));
+        gate.answer(":1.1", true);
+        gate.answer(":1.2", false);
+
+        assert_eq!(gate.resolve(":1.1", Decision::Ask), Decision::Allow);
+        assert_eq!(gate.resolve(":1.2", Decision::Ask), Decision::Deny);
+        assert_eq!(gate.resolve(":1.3", Decision::Ask), Decision::Ask);
+        assert_eq!(gate.resolve(":1.1", Decision::Deny), Decision::Deny);
+        assert!(gate.begin_prompt(":1.1"));
+    }
+}
@@ -0,0 +1,496 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Environment diagnostics for "keys don't type" reports.
+//!
+//! Most problems users hit are not bugs in the keyboard but a missing piece
+//! of the environment: a compositor without the virtual keyboard protocol, a
+//! build without a fallback backend, no layout files installed, or a broken
+//! configuration entry. This module probes those and collects the results in
+//! a [`Report`], which `cosboardctl doctor` prints.
+//!
+//! The checks are split in two:
+//!
+//! - The `check_*` functions here turn already gathered facts (the Wayland
+//!   globals, the [`SessionInfo`], the loaded configuration) into report
+//!   sections, without touching the system.
+//! - [`probe`] gathers those facts (connecting to the compositor, the session
+//!   bus and the configuration store) and runs every check.
+
+pub mod probe;
+
+pub use probe::run;
+
+use std::fmt;
+
+use crate::actions::ActionRegistry;
+use crate::config::Config;
+use crate::input::{Backend, SessionInfo};
+use crate::layout::{parse_layout_file_with, LayoutSource, ProviderChain, DEFAULT_LAYOUT_FILE};
+
+// ============================================================================
+// Report
+// ============================================================================
+
+/// Outcome of a single check, from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    /// Working as expected.
+    Ok,
+    /// Works, but something is missing or unusual.
+    Warning,
+    /// Broken; likely the cause of keys not typing.
+    Error,
+}
+
+impl Status {
+    /// Returns the marker printed in front of a check.
+    #[must_use]
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Status::Ok => "[ ok ]",
+            Status::Warning => "[warn]",
+            Status::Error => "[FAIL]",
+        }
+    }
+}
+
+/// Result of one check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// How the check went.
+    pub status: Status,
+    /// What was checked.
+    pub name: String,
+    /// What was found, and what to do about problems.
+    pub detail: String,
+}
+
+/// A group of related checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Heading of the section.
+    pub title: &'static str,
+    /// Checks in the section, in the order they ran.
+    pub checks: Vec<Check>,
+}
+
+impl Section {
+    /// Creates an empty section.
+    #[must_use]
+    pub fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Adds a check result.
+    pub fn push(&mut self, status: Status, name: impl Into<String>, detail: impl Into<String>) {
+        self.checks.push(Check {
+            status,
+            name: name.into(),
+            detail: detail.into(),
+        });
+    }
+
+    /// Returns the worst status in the section (`Ok` when empty).
+    #[must_use]
+    pub fn status(&self) -> Status {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(Status::Ok)
+    }
+}
+
+/// A full diagnostic report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Report sections, in the order they are printed.
+    pub sections: Vec<Section>,
+}
+
+impl Report {
+    /// Returns the worst status in the report (`Ok` when empty).
+    #[must_use]
+    pub fn status(&self) -> Status {
+        self.sections
+            .iter()
+            .map(Section::status)
+            .max()
+            .unwrap_or(Status::Ok)
+    }
+
+    /// Returns the number of checks with `status`.
+    #[must_use]
+    pub fn count(&self, status: Status) -> usize {
+        self.sections
+            .iter()
+            .flat_map(|section| &section.checks)
+            .filter(|check| check.status == status)
+            .count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for section in &self.sections {
+            writeln!(f, "{}", section.title)?;
+            for check in &section.checks {
+                writeln!(f, "  {} {}: {}", check.status.marker(), check.name, check.detail)?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "{} problem(s), {} warning(s)",
+            self.count(Status::Error),
+            self.count(Status::Warning)
+        )
+    }
+}
+
+// ============================================================================
+// Checks
+// ============================================================================
+
+/// How much the keyboard depends on a Wayland protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Need {
+    /// The keyboard cannot work without it.
+    Required,
+    /// Keys do not type without it, unless a fallback backend is used.
+    Emission,
+    /// Only some features use it.
+    Optional,
+}
+
+/// A Wayland protocol the doctor looks for.
+struct Protocol {
+    /// Short name shown in the report
+    name: &'static str,
+    /// Globals that provide the protocol (any one is enough)
+    globals: &'static [&'static str],
+    /// What the keyboard uses it for
+    purpose: &'static str,
+    /// How much the keyboard depends on it
+    need: Need,
+}
+
+/// Wayland protocols probed by [`check_protocols`].
+const PROTOCOLS: &[Protocol] = &[
+    Protocol {
+        name: "layer-shell",
+        globals: &["zwlr_layer_shell_v1"],
+        purpose: "showing the keyboard surface",
+        need: Need::Required,
+    },
+    Protocol {
+        name: "virtual-keyboard",
+        globals: &["zwp_virtual_keyboard_manager_v1"],
+        purpose: "typing keys",
+        need: Need::Emission,
+    },
+    Protocol {
+        name: "input-method",
+        globals: &["zwp_input_method_manager_v2"],
+        purpose: "committing text and following text fields",
+        need: Need::Optional,
+    },
+    Protocol {
+        name: "text-input",
+        globals: &["zwp_text_input_manager_v3"],
+        purpose: "applications reporting their text fields",
+        need: Need::Optional,
+    },
+    Protocol {
+        name: "data-control",
+        globals: &["ext_data_control_manager_v1", "zwlr_data_control_manager_v1"],
+        purpose: "clipboard access",
+        need: Need::Optional,
+    },
+];
+
+/// Checks which Wayland protocols the compositor offers.
+///
+/// `globals` are the compositor's global interface names, or `None` if the
+/// compositor could not be reached.
+#[must_use]
+pub fn check_protocols(globals: Option<&[String]>) -> Section {
+    let mut section = Section::new("Wayland protocols");
+
+    let Some(globals) = globals else {
+        section.push(
+            Status::Error,
+            "compositor",
+            "cannot connect (is WAYLAND_DISPLAY set and the compositor running?)",
+        );
+        return section;
+    };
+
+    for protocol in PROTOCOLS {
+        let found = protocol
+            .globals
+            .iter()
+            .find(|name| globals.iter().any(|global| global == *name));
+
+        match (found, protocol.need) {
+            (Some(global), _) => section.push(
+                Status::Ok,
+                protocol.name,
+                format!("available ({})", global),
+            ),
+            (None, Need::Required) => section.push(
+                Status::Error,
+                protocol.name,
+                format!("missing; needed for {}", protocol.purpose),
+            ),
+            // The key emission section reports whether a fallback can type
+            (None, Need::Emission) => section.push(
+                Status::Warning,
+                protocol.name,
+                format!(
+                    "missing; {} needs the x11 or uinput backend instead",
+                    protocol.purpose
+                ),
+            ),
+            (None, Need::Optional) => section.push(
+                Status::Warning,
+                protocol.name,
+                format!("missing; {} is unavailable", protocol.purpose),
+            ),
+        }
+    }
+
+    section
+}
+
+/// Checks which key emission backend will be used and whether it can work.
+///
+/// `compiled` tells which backends this build includes, normally
+/// [`Backend::is_compiled`].
+#[must_use]
+pub fn check_backend(session: &SessionInfo, compiled: impl Fn(Backend) -> bool) -> Section {
+    let mut section = Section::new("Key emission");
+
+    let built: Vec<&str> = Backend::ALL
+        .iter()
+        .filter(|backend| compiled(**backend))
+        .map(Backend::label)
+        .collect();
+    section.push(Status::Ok, "built with", built.join(", "));
+
+    let backend = crate::input::select_backend(session, &compiled);
+    let broken = backend == Backend::Wayland
+        && (!session.wayland_display || session.virtual_keyboard == Some(false));
+    if broken {
+        section.push(
+            Status::Error,
+            "backend",
+            "no backend can type keys here; build with the x11 or uinput feature",
+        );
+    } else {
+        section.push(Status::Ok, "backend", backend.label());
+    }
+
+    if compiled(Backend::X11) {
+        section.push(
+            if session.x11_display { Status::Ok } else { Status::Warning },
+            "X11 display",
+            if session.x11_display { "set" } else { "DISPLAY is not set" },
+        );
+    }
+
+    if compiled(Backend::Uinput) {
+        if session.uinput {
+            section.push(Status::Ok, "uinput", "writable");
+        } else {
+            section.push(
+                Status::Warning,
+                "uinput",
+                "not writable; run `just setup-uinput` to grant access",
+            );
+        }
+    }
+
+    section
+}
+
+/// Checks the layout search path and the default layout.
+#[must_use]
+pub fn check_layouts(providers: &ProviderChain) -> Section {
+    let mut section = Section::new("Layouts");
+    let entries = providers.list();
+
+    for provider in providers.providers() {
+        let count = entries
+            .iter()
+            .filter(|entry| entry.source == provider.source && entry.path.starts_with(&provider.dir))
+            .count();
+        section.push(
+            Status::Ok,
+            format!("{} layouts", provider.source),
+            format!("{} in {}", count, provider.dir.display()),
+        );
+    }
+
+    if entries.is_empty() {
+        section.push(
+            Status::Error,
+            "search path",
+            "no layout files found; install the layouts or run from the source tree",
+        );
+    }
+
+    match providers.locate(DEFAULT_LAYOUT_FILE) {
+        None => section.push(
+            Status::Error,
+            "default layout",
+            format!("{} not found on the search path", DEFAULT_LAYOUT_FILE),
+        ),
+        Some((path, source)) => {
+            let origin = source.map_or("custom path", |source: LayoutSource| source.label());
+            match parse_layout_file_with(providers.storage(), &path.display().to_string()) {
+                Ok(result) if result.has_warnings() => section.push(
+                    Status::Warning,
+                    "default layout",
+                    format!(
+                        "{} ({}) parses with {} warning(s); see cosboard-lint",
+                        path.display(),
+                        origin,
+                        result.warning_count()
+                    ),
+                ),
+                Ok(_) => section.push(
+                    Status::Ok,
+                    "default layout",
+                    format!("{} ({})", path.display(), origin),
+                ),
+                Err(e) => section.push(
+                    Status::Error,
+                    "default layout",
+                    format!("{} ({}) does not parse: {}", path.display(), origin, e),
+                ),
+            }
+        }
+    }
+
+    section
+}
+
+/// Checks the loaded configuration.
+///
+/// `errors` are the entries that failed to load (and fell back to their
+/// defaults).
+#[must_use]
+pub fn check_config(config: &Config, errors: &[String]) -> Section {
+    let mut section = Section::new("Configuration");
+
+    for error in errors {
+        section.push(Status::Warning, "entry reset to default", error.clone());
+    }
+
+    let registry = ActionRegistry::new();
+    for gesture in config.gesture_bindings.0.keys() {
+        let Some(action) = config.gesture_bindings.action(*gesture) else {
+            continue;
+        };
+        if let Err(e) = registry.parse(action) {
+            section.push(
+                Status::Error,
+                format!("gesture {:?}", gesture),
+                format!("runs '{}': {}", action, e),
+            );
+        }
+    }
+
+    if config.kiosk.enabled {
+        section.push(
+            Status::Warning,
+            "kiosk mode",
+            "enabled; quitting, layout switching and settings may be locked",
+        );
+    }
+
+    if section.checks.is_empty() {
+        section.push(Status::Ok, "config", "no problems found");
+    }
+
+    section
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Missing protocols are reported by how much they are needed.
+    #[test]
+    fn test_check_protocols() {
+        let globals = vec![
+            "zwlr_layer_shell_v1".to_string(),
+            "zwlr_data_control_manager_v1".to_string(),
+        ];
+        let section = check_protocols(Some(&globals));
+        let status = |name: &str| {
+            section
+                .checks
+                .iter()
+                .find(|check| check.name == name)
+                .map(|check| check.status)
+        };
+
+        assert_eq!(status("layer-shell"), Some(Status::Ok));
+        assert_eq!(status("virtual-keyboard"), Some(Status::Warning));
+        assert_eq!(status("input-method"), Some(Status::Warning));
+        assert_eq!(status("data-control"), Some(Status::Ok));
+
+        let unreachable = check_protocols(None);
+        assert_eq!(unreachable.status(), Status::Error);
+    }
+
+    /// Test: A session where no backend can type is an error.
+    #[test]
+    fn test_check_backend() {
+        let session = SessionInfo {
+            wayland_display: true,
+            virtual_keyboard: Some(false),
+            ..SessionInfo::default()
+        };
+        let wayland_only = |backend| backend == Backend::Wayland;
+        assert_eq!(check_backend(&session, wayland_only).status(), Status::Error);
+
+        let with_uinput = SessionInfo {
+            uinput: true,
+            ..session
+        };
+        let section = check_backend(&with_uinput, |_| true);
+        assert_eq!(section.status(), Status::Warning);
+        assert!(section
+            .checks
+            .iter()
+            .any(|check| check.name == "backend" && check.detail == "uinput"));
+    }
+
+    /// Test: Reports render every check and a summary line.
+    #[test]
+    fn test_report_render() {
+        let mut section = Section::new("Layouts");
+        section.push(Status::Ok, "default layout", "found");
+        section.push(Status::Error, "search path", "empty");
+        let report = Report {
+            sections: vec![section],
+        };
+
+        assert_eq!(report.status(), Status::Error);
+        assert_eq!(
+            report.to_string(),
+            "Layouts\n  [ ok ] default layout: found\n  [FAIL] search path: empty\n\n\
+             1 problem(s), 0 warning(s)"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Probes that gather the facts the diagnostic checks work on.
+
+use cosmic::cosmic_config::{self, CosmicConfigEntry};
+
+use super::{
+    check_backend, check_config, check_layouts, check_protocols, Report, Section, Status,
+};
+use crate::applet::APPLET_ID;
+use crate::config::{migrate_user_config, Config};
+use crate::dbus::DBUS_NAME;
+use crate::input::{wayland_globals, Backend, SessionInfo};
+use crate::layout::ProviderChain;
+
+/// Probes the environment and runs every check.
+pub async fn run() -> Report {
+    let globals = wayland_globals();
+    let session = SessionInfo::from_env();
+    let (config, errors) = load_config();
+
+    Report {
+        sections: vec![
+            check_protocols(globals.as_deref()),
+            check_backend(&session, Backend::is_compiled),
+            check_dbus().await,
+            check_layouts(&ProviderChain::standard()),
+            check_config(&config, &errors),
+        ],
+    }
+}
+
+/// Loads the user configuration like the applet does.
+///
+/// Returns the configuration and a description of every entry that failed
+/// to load (those fall back to their defaults).
+#[must_use]
+pub fn load_config() -> (Config, Vec<String>) {
+    let mut problems = Vec::new();
+    if let Err(e) = migrate_user_config() {
+        problems.push(format!("cannot migrate the configuration: {}", e));
+    }
+
+    match cosmic_config::Config::new(APPLET_ID, Config::VERSION) {
+        Ok(context) => match Config::get_entry(&context) {
+            Ok(config) => (config, problems),
+            Err((errors, config)) => {
+                problems.extend(errors.iter().map(|error| format!("{:?}", error)));
+                (config, problems)
+            }
+        },
+        Err(e) => {
+            problems.push(format!("cannot open the configuration: {:?}", e));
+            (Config::default(), problems)
+        }
+    }
+}
+
+/// Checks the session bus and whether the keyboard's service is running.
+pub async fn check_dbus() -> Section {
+    let mut section = Section::new("D-Bus");
+
+    let connection = match zbus::Connection::session().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            section.push(Status::Error, "session bus", format!("cannot connect: {}", e));
+            return section;
+        }
+    };
+    section.push(Status::Ok, "session bus", "connected");
+
+    let owned = match zbus::fdo::DBusProxy::new(&connection).await {
+        Ok(proxy) => match zbus::names::BusName::try_from(DBUS_NAME) {
+            Ok(name) => proxy.name_has_owner(name).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        Err(e) => Err(e.to_string()),
+    };
+    match owned {
+        Ok(true) => section.push(Status::Ok, DBUS_NAME, "running"),
+        Ok(false) => section.push(
+            Status::Warning,
+            DBUS_NAME,
+            "not running; add the applet to the panel or start cosboard-applet",
+        ),
+        Err(e) => section.push(Status::Warning, DBUS_NAME, format!("cannot query: {}", e)),
+    }
+
+    section
+}
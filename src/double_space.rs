@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Ending a sentence with two quick spaces.
+//!
+//! As on phone keyboards, tapping space twice in quick succession after a
+//! word replaces the two spaces with a full stop and a space, so a sentence
+//! can be ended without switching to the symbols panel. The full stop
+//! follows the layout's language ([`sentence_end`]): `。` for Chinese and
+//! Japanese, `।` for Hindi, and so on, without the space where the script
+//! does not put one.
+//!
+//! Only a space typed right after a letter or digit counts as the first
+//! tap, so a run of spaces, or a space after punctuation, is typed as is.
+//! The second tap must follow within `interval_ms`
+//! ([`DoubleSpaceSettings`]).
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::snippets::Expansion;
+
+/// Default longest time between the two spaces, in milliseconds.
+pub const DEFAULT_INTERVAL_MS: u64 = 400;
+
+/// Whether two quick spaces type a full stop, from the user configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DoubleSpaceSettings {
+    /// Whether two quick spaces end the sentence.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Longest time between the two spaces, in milliseconds.
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_ms() -> u64 {
+    DEFAULT_INTERVAL_MS
+}
+
+impl Default for DoubleSpaceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_ms: default_interval_ms(),
+        }
+    }
+}
+
+impl DoubleSpaceSettings {
+    /// Returns the longest time between the two spaces.
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+}
+
+/// Returns the text ending a sentence in `language` (a layout language
+/// such as `de` or `zh_CN`), with the space that follows it if the script
+/// uses one.
+#[must_use]
+pub fn sentence_end(language: Option<&str>) -> &'static str {
+    let language = language
+        .and_then(|language| language.split(['_', '-', '.', '@']).next())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match language.as_str() {
+        "zh" | "ja" => "。",
+        "hi" | "mr" | "ne" | "bn" => "। ",
+        "ur" => "۔ ",
+        "hy" => "։ ",
+        "am" | "ti" => "። ",
+        _ => ". ",
+    }
+}
+
+/// Tracks the spaces typed, to tell a double space from a single one.
+#[derive(Debug, Clone, Default)]
+pub struct DoubleSpaceTracker {
+    /// Last character typed, if the cursor is known to follow it
+    previous: Option<char>,
+    /// When a space that may be the first of two was typed
+    first_space: Option<Instant>,
+}
+
+impl DoubleSpaceTracker {
+    /// Creates a tracker with nothing typed.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a character typed at `now`.
+    ///
+    /// Returns the keystrokes ending the sentence with `end` if `c` is the
+    /// second of two quick spaces after a word. Both spaces have already
+    /// been typed, so they are deleted first.
+    pub fn type_char(
+        &mut self,
+        c: char,
+        now: Instant,
+        settings: &DoubleSpaceSettings,
+        end: &str,
+    ) -> Option<Expansion> {
+        let first_space = self.first_space.take();
+        let previous = self.previous.replace(c);
+        if c != ' ' || !settings.enabled {
+            return None;
+        }
+
+        // A space after a word may be the first of two
+        if previous.is_some_and(char::is_alphanumeric) {
+            self.first_space = Some(now);
+            return None;
+        }
+        let first_space = first_space?;
+        if now.saturating_duration_since(first_space) > settings.interval() {
+            return None;
+        }
+        // The full stop ends the run, so a third space is typed as is
+        self.previous = end.chars().last();
+        Some(Expansion {
+            backspaces: 2,
+            text: end.to_string(),
+        })
+    }
+
+    /// Forgets the typed text, e.g. after the cursor moved or a key other
+    /// than a character was typed.
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.first_space = None;
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(
+        tracker: &mut DoubleSpaceTracker,
+        text: &str,
+        start: Instant,
+        step: Duration,
+    ) -> Option<Expansion> {
+        let settings = DoubleSpaceSettings::default();
+        let mut now = start;
+        text.chars()
+            .map(|c| {
+                now += step;
+                tracker.type_char(c, now, &settings, ". ")
+            })
+            .last()
+            .flatten()
+    }
+
+    /// Test: Two quick spaces after a word type a full stop and a space; a
+    /// third space, slow spaces and spaces after punctuation do not.
+    #[test]
+    fn test_double_space() {
+        let now = Instant::now();
+        let quick = Duration::from_millis(100);
+        let mut tracker = DoubleSpaceTracker::new();
+        assert_eq!(
+            type_text(&mut tracker, "end  ", now, quick),
+            Some(Expansion {
+                backspaces: 2,
+                text: ". ".to_string(),
+            })
+        );
+        assert_eq!(type_text(&mut tracker, " ", now, quick), None);
+
+        tracker.reset();
+        let slow = Duration::from_millis(DEFAULT_INTERVAL_MS + 100);
+        assert_eq!(type_text(&mut tracker, "end  ", now, slow), None);
+        assert_eq!(type_text(&mut tracker, "end,  ", now, quick), None);
+
+        tracker.reset();
+        type_text(&mut tracker, "end ", now, quick);
+        tracker.reset();
+        assert_eq!(type_text(&mut tracker, " ", now, quick), None);
+    }
+
+    /// Test: The full stop follows the layout's language, and nothing is
+    /// replaced with double space turned off.
+    #[test]
+    fn test_sentence_end() {
+        assert_eq!(sentence_end(None), ". ");
+        assert_eq!(sentence_end(Some("de_DE")), ". ");
+        assert_eq!(sentence_end(Some("ja")), "。");
+        assert_eq!(sentence_end(Some("hi-IN")), "। ");
+
+        let settings = DoubleSpaceSettings {
+            enabled: false,
+            ..DoubleSpaceSettings::default()
+        };
+        let now = Instant::now();
+        let mut tracker = DoubleSpaceTracker::new();
+        tracker.type_char('a', now, &settings, ". ");
+        tracker.type_char(' ', now, &settings, ". ");
+        assert_eq!(tracker.type_char(' ', now, &settings, ". "), None);
+    }
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Bundled emoji table.
+//!
+//! A compact, hand-picked set of commonly used emoji with CLDR short names
+//! and a few search keywords each. Entries are grouped by category in the
+//! order they are shown on the emoji panel.
+
+use super::{Category, Emoji};
+
+/// Shorthand constructor used to keep the table readable.
+const fn e(
+    glyph: &'static str,
+    name: &'static str,
+    keywords: &'static [&'static str],
+    category: Category,
+) -> Emoji {
+    Emoji {
+        glyph,
+        name,
+        keywords,
+        category,
+    }
+}
+
+use Category::{Activities, Animals, Flags, Food, Objects, People, Smileys, Symbols, Travel};
+
+/// All bundled emoji.
+pub static EMOJI: &[Emoji] = &[
+    // Smileys
+    e("😀", "grinning face", &["smile", "happy"], Smileys),
+    e("😃", "grinning face with big eyes", &["smile", "happy"], Smileys),
+    e("😄", "grinning face with smiling eyes", &["smile", "happy", "laugh"], Smileys),
+    e("😁", "beaming face with smiling eyes", &["grin", "happy"], Smileys),
+    e("😆", "grinning squinting face", &["laugh", "happy"], Smileys),
+    e("😅", "grinning face with sweat", &["relief", "nervous"], Smileys),
+    e("😂", "face with tears of joy", &["laugh", "lol", "cry"], Smileys),
+    e("🤣", "rolling on the floor laughing", &["rofl", "laugh", "lol"], Smileys),
+    e("🙂", "slightly smiling face", &["smile"], Smileys),
+    e("🙃", "upside-down face", &["silly", "sarcasm"], Smileys),
+    e("😉", "winking face", &["wink", "flirt"], Smileys),
+    e("😊", "smiling face with smiling eyes", &["blush", "happy"], Smileys),
+    e("😇", "smiling face with halo", &["angel", "innocent"], Smileys),
+    e("🥰", "smiling face with hearts", &["love", "adore"], Smileys),
+    e("😍", "smiling face with heart-eyes", &["love", "crush"], Smileys),
+    e("😘", "face blowing a kiss", &["kiss", "love"], Smileys),
+    e("😋", "face savoring food", &["yum", "delicious"], Smileys),
+    e("😛", "face with tongue", &["tongue", "silly"], Smileys),
+    e("😜", "winking face with tongue", &["silly", "joke"], Smileys),
+    e("🤪", "zany face", &["crazy", "silly"], Smileys),
+    e("🤔", "thinking face", &["think", "hmm"], Smileys),
+    e("🤨", "face with raised eyebrow", &["skeptical", "doubt"], Smileys),
+    e("😐", "neutral face", &["meh", "blank"], Smileys),
+    e("😑", "expressionless face", &["meh", "blank"], Smileys),
+    e("🙄", "face with rolling eyes", &["eyeroll", "whatever"], Smileys),
+    e("😏", "smirking face", &["smirk", "smug"], Smileys),
+    e("😬", "grimacing face", &["awkward", "grimace"], Smileys),
+    e("😌", "relieved face", &["relief", "calm"], Smileys),
+    e("😔", "pensive face", &["sad", "thoughtful"], Smileys),
+    e("😴", "sleeping face", &["sleep", "tired", "zzz"], Smileys),
+    e("😷", "face with medical mask", &["sick", "mask"], Smileys),
+    e("🤒", "face with thermometer", &["sick", "fever"], Smileys),
+    e("🤯", "exploding head", &["mind blown", "shocked"], Smileys),
+    e("🥳", "partying face", &["party", "celebrate"], Smileys),
+    e("😎", "smiling face with sunglasses", &["cool", "sunglasses"], Smileys),
+    e("🤓", "nerd face", &["nerd", "geek"], Smileys),
+    e("😕", "confused face", &["confused"], Smileys),
+    e("😟", "worried face", &["worried", "concern"], Smileys),
+    e("😮", "face with open mouth", &["surprised", "wow"], Smileys),
+    e("😲", "astonished face", &["shocked", "wow"], Smileys),
+    e("😳", "flushed face", &["embarrassed", "blush"], Smileys),
+    e("🥺", "pleading face", &["please", "puppy eyes"], Smileys),
+    e("😢", "crying face", &["sad", "tear", "cry"], Smileys),
+    e("😭", "loudly crying face", &["sob", "cry", "sad"], Smileys),
+    e("😱", "face screaming in fear", &["scream", "scared"], Smileys),
+    e("😤", "face with steam from nose", &["frustrated", "triumph"], Smileys),
+    e("😡", "pouting face", &["angry", "mad", "rage"], Smileys),
+    e("🤬", "face with symbols on mouth", &["swear", "angry"], Smileys),
+    e("💀", "skull", &["dead", "death"], Smileys),
+    e("💩", "pile of poo", &["poop"], Smileys),
+    e("🤡", "clown face", &["clown"], Smileys),
+    e("👻", "ghost", &["halloween", "boo"], Smileys),
+    e("👽", "alien", &["ufo", "space"], Smileys),
+    e("🤖", "robot", &["bot", "machine"], Smileys),
+    e("❤️", "red heart", &["love", "heart"], Smileys),
+    e("💔", "broken heart", &["heartbreak", "sad"], Smileys),
+    e("💯", "hundred points", &["100", "perfect", "score"], Smileys),
+    e("💥", "collision", &["boom", "explosion"], Smileys),
+    e("💤", "zzz", &["sleep", "tired"], Smileys),
+    // People
+    e("👋", "waving hand", &["wave", "hello", "bye"], People),
+    e("👌", "ok hand", &["ok", "perfect"], People),
+    e("✌️", "victory hand", &["peace", "victory"], People),
+    e("🤞", "crossed fingers", &["luck", "hope"], People),
+    e("👍", "thumbs up", &["yes", "like", "approve", "+1"], People),
+    e("👎", "thumbs down", &["no", "dislike", "-1"], People),
+    e("👏", "clapping hands", &["applause", "clap", "bravo"], People),
+    e("🙌", "raising hands", &["hooray", "celebrate"], People),
+    e("🙏", "folded hands", &["please", "thanks", "pray"], People),
+    e("💪", "flexed biceps", &["strong", "muscle"], People),
+    e("👀", "eyes", &["look", "see", "watch"], People),
+    e("🤷", "person shrugging", &["shrug", "dunno", "whatever"], People),
+    e("🤦", "person facepalming", &["facepalm", "disbelief"], People),
+    // Animals
+    e("🐶", "dog face", &["dog", "puppy", "pet"], Animals),
+    e("🐱", "cat face", &["cat", "kitten", "pet"], Animals),
+    e("🐭", "mouse face", &["mouse"], Animals),
+    e("🦊", "fox", &["fox"], Animals),
+    e("🐻", "bear", &["bear"], Animals),
+    e("🐼", "panda", &["panda"], Animals),
+    e("🐧", "penguin", &["penguin", "linux"], Animals),
+    e("🐸", "frog", &["frog"], Animals),
+    e("🐢", "turtle", &["turtle", "slow"], Animals),
+    e("🐍", "snake", &["snake", "python"], Animals),
+    e("🦀", "crab", &["crab", "rust", "ferris"], Animals),
+    e("🐝", "honeybee", &["bee"], Animals),
+    e("🦋", "butterfly", &["butterfly"], Animals),
+    e("🌸", "cherry blossom", &["flower", "spring"], Animals),
+    e("🌹", "rose", &["flower", "love"], Animals),
+    e("🌻", "sunflower", &["flower", "summer"], Animals),
+    e("🌲", "evergreen tree", &["tree", "forest"], Animals),
+    e("🍀", "four leaf clover", &["luck", "clover"], Animals),
+    // Food
+    e("🍎", "red apple", &["apple", "fruit"], Food),
+    e("🍌", "banana", &["fruit"], Food),
+    e("🍓", "strawberry", &["fruit", "berry"], Food),
+    e("🍕", "pizza", &["food", "slice"], Food),
+    e("🍔", "hamburger", &["burger", "food"], Food),
+    e("🍟", "french fries", &["fries", "chips"], Food),
+    e("🌮", "taco", &["food", "mexican"], Food),
+    e("🍣", "sushi", &["food", "japanese"], Food),
+    e("🍰", "shortcake", &["cake", "dessert"], Food),
+    e("🎂", "birthday cake", &["birthday", "cake"], Food),
+    e("🍪", "cookie", &["cookie", "dessert"], Food),
+    e("☕", "hot beverage", &["coffee", "tea"], Food),
+    e("🍺", "beer mug", &["beer", "drink"], Food),
+    e("🍷", "wine glass", &["wine", "drink"], Food),
+    // Activities
+    e("⚽", "soccer ball", &["football", "sport"], Activities),
+    e("🏀", "basketball", &["sport", "ball"], Activities),
+    e("🎮", "video game", &["game", "controller", "gaming"], Activities),
+    e("🎲", "game die", &["dice", "game"], Activities),
+    e("🎵", "musical note", &["music", "note"], Activities),
+    e("🎸", "guitar", &["music", "instrument"], Activities),
+    e("🎉", "party popper", &["party", "tada", "celebrate"], Activities),
+    e("🎁", "wrapped gift", &["present", "gift", "birthday"], Activities),
+    e("🏆", "trophy", &["win", "award", "prize"], Activities),
+    // Travel
+    e("🚗", "automobile", &["car", "drive"], Travel),
+    e("🚲", "bicycle", &["bike", "cycle"], Travel),
+    e("✈️", "airplane", &["plane", "flight", "travel"], Travel),
+    e("🚀", "rocket", &["launch", "space", "ship it"], Travel),
+    e("🏠", "house", &["home"], Travel),
+    e("🌍", "globe showing europe-africa", &["world", "earth"], Travel),
+    e("🌙", "crescent moon", &["moon", "night"], Travel),
+    e("☀️", "sun", &["sunny", "weather"], Travel),
+    e("⭐", "star", &["star", "favorite"], Travel),
+    e("🌈", "rainbow", &["rainbow", "pride"], Travel),
+    e("🔥", "fire", &["hot", "lit", "flame"], Travel),
+    e("❄️", "snowflake", &["snow", "cold", "winter"], Travel),
+    // Objects
+    e("💻", "laptop", &["computer", "pc"], Objects),
+    e("⌨️", "keyboard", &["typing", "computer"], Objects),
+    e("📱", "mobile phone", &["phone", "smartphone"], Objects),
+    e("💡", "light bulb", &["idea", "light"], Objects),
+    e("📌", "pushpin", &["pin", "location"], Objects),
+    e("📎", "paperclip", &["attachment", "clip"], Objects),
+    e("🔒", "locked", &["lock", "secure"], Objects),
+    e("🔑", "key", &["key", "password"], Objects),
+    e("🔧", "wrench", &["tool", "fix"], Objects),
+    e("📅", "calendar", &["date", "schedule"], Objects),
+    e("📝", "memo", &["note", "write"], Objects),
+    e("📦", "package", &["box", "shipping"], Objects),
+    e("💰", "money bag", &["money", "cash"], Objects),
+    e("⏰", "alarm clock", &["alarm", "time"], Objects),
+    // Symbols
+    e("✅", "check mark button", &["done", "yes", "check"], Symbols),
+    e("❌", "cross mark", &["no", "wrong", "x"], Symbols),
+    e("⚠️", "warning", &["caution", "alert"], Symbols),
+    e("❓", "red question mark", &["question", "what"], Symbols),
+    e("❗", "red exclamation mark", &["exclamation", "important"], Symbols),
+    e("➡️", "right arrow", &["arrow", "next"], Symbols),
+    e("⬅️", "left arrow", &["arrow", "back"], Symbols),
+    e("♻️", "recycling symbol", &["recycle"], Symbols),
+    e("✨", "sparkles", &["sparkle", "shiny", "new"], Symbols),
+    e("🆗", "ok button", &["ok"], Symbols),
+    // Flags
+    e("🏁", "chequered flag", &["finish", "race"], Flags),
+    e("🚩", "triangular flag", &["flag", "red flag"], Flags),
+    e("🏳️‍🌈", "rainbow flag", &["pride", "lgbt"], Flags),
+];
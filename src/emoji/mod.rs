@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Emoji data and search.
+//!
+//! This module provides the bundled emoji table used by the emoji panel and
+//! the emoji search field, along with a simple ranked name/keyword search.
+//!
+//! # Search Ranking
+//!
+//! Matches are ranked so that the most likely emoji comes first:
+//!
+//! 1. Name equals the query (`"fire"` -> 🔥)
+//! 2. A keyword equals the query (`"lol"` -> 😂)
+//! 3. A word of the name starts with the query (`"thu"` -> 👍)
+//! 4. A keyword starts with the query
+//! 5. The name contains the query anywhere
+//!
+//! Ties keep table order, which groups results by category.
+//!
+//! # Recents and Favorites
+//!
+//! [`EmojiRecents`] tracks recently used and user-pinned emoji. It is stored
+//! in the user configuration and drives the "Recents" strip.
+
+mod data;
+pub mod recents;
+
+pub use data::EMOJI;
+pub use recents::{EmojiRecents, RECENTS_CAPACITY};
+
+/// Emoji categories, in panel order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Faces, hearts and emotion symbols.
+    Smileys,
+    /// Hands, gestures and people.
+    People,
+    /// Animals and nature.
+    Animals,
+    /// Food and drink.
+    Food,
+    /// Sports, games and celebrations.
+    Activities,
+    /// Vehicles, places, weather and sky.
+    Travel,
+    /// Everyday objects.
+    Objects,
+    /// Marks, arrows and signs.
+    Symbols,
+    /// Flags.
+    Flags,
+}
+
+/// A single emoji entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Emoji {
+    /// The emoji text (may be several codepoints, e.g. with a variation selector).
+    pub glyph: &'static str,
+    /// CLDR short name, lowercase.
+    pub name: &'static str,
+    /// Additional search keywords, lowercase.
+    pub keywords: &'static [&'static str],
+    /// Category the emoji is shown under.
+    pub category: Category,
+}
+
+/// Searches the bundled emoji table by name and keywords.
+///
+/// The query is matched case-insensitively after trimming. An empty query
+/// returns the first `limit` emoji in table order.
+///
+/// # Arguments
+///
+/// * `query` - The search text typed by the user
+/// * `limit` - Maximum number of results to return
+#[must_use]
+pub fn search(query: &str, limit: usize) -> Vec<&'static Emoji> {
+    let query = query.trim().to_lowercase();
+
+    if query.is_empty() {
+        return EMOJI.iter().take(limit).collect();
+    }
+
+    let mut ranked: Vec<(u8, usize, &'static Emoji)> = EMOJI
+        .iter()
+        .enumerate()
+        .filter_map(|(index, emoji)| match_rank(emoji, &query).map(|rank| (rank, index, emoji)))
+        .collect();
+
+    ranked.sort_by_key(|(rank, index, _)| (*rank, *index));
+    ranked.into_iter().take(limit).map(|(_, _, emoji)| emoji).collect()
+}
+
+/// Finds the emoji with the given glyph.
+#[must_use]
+pub fn find_by_glyph(glyph: &str) -> Option<&'static Emoji> {
+    EMOJI.iter().find(|emoji| emoji.glyph == glyph)
+}
+
+/// Returns the rank of a match (lower is better), or `None` for no match.
+fn match_rank(emoji: &Emoji, query: &str) -> Option<u8> {
+    if emoji.name == query {
+        Some(0)
+    } else if emoji.keywords.iter().any(|k| *k == query) {
+        Some(1)
+    } else if emoji.name.split([' ', '-']).any(|word| word.starts_with(query)) {
+        Some(2)
+    } else if emoji.keywords.iter().any(|k| k.starts_with(query)) {
+        Some(3)
+    } else if emoji.name.contains(query) {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Exact name and keyword matches rank first.
+    #[test]
+    fn test_search_ranking() {
+        let results = search("fire", 5);
+        assert_eq!(results[0].glyph, "🔥");
+
+        let results = search("lol", 5);
+        assert_eq!(results[0].glyph, "😂", "Exact keyword should rank first");
+
+        let results = search("Thumbs", 5);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().any(|e| e.glyph == "👍"));
+    }
+
+    /// Test: Empty query returns the start of the table, limited.
+    #[test]
+    fn test_search_empty_query_and_limit() {
+        let results = search("  ", 4);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].glyph, EMOJI[0].glyph);
+
+        assert!(search("heart", 2).len() <= 2);
+        assert!(search("zzzzqqq", 10).is_empty());
+    }
+
+    /// Test: Table entries are well-formed.
+    #[test]
+    fn test_table_entries_are_lowercase_and_unique() {
+        let mut seen = std::collections::HashSet::new();
+        for emoji in EMOJI {
+            assert!(!emoji.glyph.is_empty());
+            assert_eq!(emoji.name, emoji.name.to_lowercase(), "{}", emoji.name);
+            assert!(seen.insert(emoji.glyph), "Duplicate emoji {}", emoji.glyph);
+        }
+        assert_eq!(find_by_glyph("🦀").map(|e| e.name), Some("crab"));
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Recently used and favorite emoji.
+//!
+//! [`EmojiRecents`] is a small LRU store persisted in the user configuration.
+//! Recents are updated every time an emoji or symbol is inserted; favorites
+//! are pinned by the user and never evicted. The "Recents" strip shows
+//! favorites first, followed by recents that are not already pinned.
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of recently used entries kept.
+pub const RECENTS_CAPACITY: usize = 24;
+
+/// Recently used and pinned emoji/symbols.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmojiRecents {
+    /// Recently used entries, most recent first.
+    pub recent: Vec<String>,
+    /// User-pinned entries, in pin order.
+    pub favorites: Vec<String>,
+}
+
+impl EmojiRecents {
+    /// Records a use of `glyph`, moving it to the front of the recents.
+    ///
+    /// The oldest entry is evicted once [`RECENTS_CAPACITY`] is exceeded.
+    /// Empty strings are ignored.
+    pub fn record(&mut self, glyph: &str) {
+        if glyph.is_empty() {
+            return;
+        }
+
+        self.recent.retain(|entry| entry != glyph);
+        self.recent.insert(0, glyph.to_string());
+        self.recent.truncate(RECENTS_CAPACITY);
+    }
+
+    /// Pins `glyph` if it is not a favorite, unpins it otherwise.
+    ///
+    /// Returns `true` if the glyph is now a favorite.
+    pub fn toggle_favorite(&mut self, glyph: &str) -> bool {
+        if let Some(index) = self.favorites.iter().position(|entry| entry == glyph) {
+            self.favorites.remove(index);
+            false
+        } else {
+            self.favorites.push(glyph.to_string());
+            true
+        }
+    }
+
+    /// Returns whether `glyph` is pinned.
+    #[must_use]
+    pub fn is_favorite(&self, glyph: &str) -> bool {
+        self.favorites.iter().any(|entry| entry == glyph)
+    }
+
+    /// Returns the entries for the "Recents" strip.
+    ///
+    /// Favorites come first, followed by recents not already pinned, up to
+    /// `limit` entries in total.
+    #[must_use]
+    pub fn strip(&self, limit: usize) -> Vec<&str> {
+        self.favorites
+            .iter()
+            .chain(self.recent.iter().filter(|entry| !self.is_favorite(entry)))
+            .map(String::as_str)
+            .take(limit)
+            .collect()
+    }
+
+    /// Returns `true` if there is nothing to show in the strip.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.recent.is_empty() && self.favorites.is_empty()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Recording moves entries to the front and evicts the oldest.
+    #[test]
+    fn test_record_lru() {
+        let mut recents = EmojiRecents::default();
+        recents.record("🔥");
+        recents.record("👍");
+        recents.record("🔥");
+        assert_eq!(recents.recent, vec!["🔥", "👍"]);
+
+        recents.record("");
+        assert_eq!(recents.recent.len(), 2);
+
+        for i in 0..RECENTS_CAPACITY {
+            recents.record(&i.to_string());
+        }
+        assert_eq!(recents.recent.len(), RECENTS_CAPACITY);
+        assert_eq!(recents.recent[0], (RECENTS_CAPACITY - 1).to_string());
+        assert!(!recents.recent.iter().any(|entry| entry == "👍"));
+    }
+
+    /// Test: Favorites lead the strip and are not repeated from recents.
+    #[test]
+    fn test_favorites_strip() {
+        let mut recents = EmojiRecents::default();
+        recents.record("😀");
+        recents.record("🔥");
+        assert!(recents.toggle_favorite("🔥"));
+        assert!(recents.toggle_favorite("❤️"));
+
+        assert_eq!(recents.strip(10), vec!["🔥", "❤️", "😀"]);
+        assert_eq!(recents.strip(2), vec!["🔥", "❤️"]);
+
+        assert!(!recents.toggle_favorite("🔥"));
+        assert!(!recents.is_favorite("🔥"));
+        assert_eq!(recents.strip(10), vec!["❤️", "🔥", "😀"]);
+    }
+}
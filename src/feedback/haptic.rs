@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Haptic key feedback through feedbackd.
+//!
+//! Phones and tablets running Phosh or COSMIC on mobile hardware ship
+//! [feedbackd](https://source.puri.sm/Librem5/feedbackd), which vibrates
+//! the device (and plays sounds or blinks LEDs) for named events according
+//! to the user's feedback profile. With `key_feedback.haptic` enabled, every
+//! key press triggers its [`KEY_PRESS_EVENT`] over the session bus, so the
+//! strength of the vibration, or none at all in silent mode, follows the
+//! system settings.
+//!
+//! Key presses are not the only thing felt: with `key_feedback.boundaries`
+//! also on, the finger sliding from one key onto another gives a light tick
+//! ([`KEY_BOUNDARY`]), a double tick when it moves to another row
+//! ([`ROW_BOUNDARY`]), and switching panels a double pulse
+//! ([`PANEL_SWITCH`]), so the keyboard can be found by touch. feedbackd
+//! only plays named events, so the patterns are built from those: a
+//! [`HapticPattern`] repeats an event a few times and can keep to
+//! feedbackd's `quiet` level, which vibrates without playing sounds.
+//!
+//! Most desktops have no feedbackd. The first failed call turns haptic
+//! feedback off for the session instead of calling again on every key.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use zbus::zvariant::Value;
+
+/// Well-known name of the feedbackd service.
+pub const FEEDBACKD_NAME: &str = "org.sigxcpu.Feedback";
+
+/// Object path of the feedbackd service.
+pub const FEEDBACKD_PATH: &str = "/org/sigxcpu/Feedback";
+
+/// Event triggered for a key press, from the feedback theme specification.
+pub const KEY_PRESS_EVENT: &str = "button-pressed";
+
+/// Event triggered for a key release, lighter than a press in the usual
+/// feedback themes.
+pub const KEY_RELEASE_EVENT: &str = "button-released";
+
+/// Timeout passed to feedbackd: play the event's feedback once.
+const PLAY_ONCE: i32 = -1;
+
+/// feedbackd profile that vibrates (and blinks LEDs) but plays no sound.
+const QUIET_PROFILE: &str = "quiet";
+
+/// A vibration: a feedbackd event played one or more times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HapticPattern {
+    /// Event played for each pulse.
+    pub event: &'static str,
+    /// Number of pulses.
+    pub pulses: u8,
+    /// Time between the starts of two pulses.
+    pub gap: Duration,
+    /// Whether the pulses keep to feedbackd's `quiet` level, without sound,
+    /// whatever the user's profile allows.
+    pub quiet: bool,
+}
+
+/// A key press: the usual key vibration.
+pub const KEY_PRESS: HapticPattern = HapticPattern {
+    event: KEY_PRESS_EVENT,
+    pulses: 1,
+    gap: Duration::ZERO,
+    quiet: false,
+};
+
+/// The finger slid onto another key of the same row: a light tick.
+pub const KEY_BOUNDARY: HapticPattern = HapticPattern {
+    event: KEY_RELEASE_EVENT,
+    pulses: 1,
+    gap: Duration::ZERO,
+    quiet: true,
+};
+
+/// The finger slid onto a key of another row: two light ticks.
+pub const ROW_BOUNDARY: HapticPattern = HapticPattern {
+    event: KEY_RELEASE_EVENT,
+    pulses: 2,
+    gap: Duration::from_millis(40),
+    quiet: true,
+};
+
+/// Another panel is shown: two key vibrations.
+pub const PANEL_SWITCH: HapticPattern = HapticPattern {
+    event: KEY_PRESS_EVENT,
+    pulses: 2,
+    gap: Duration::from_millis(90),
+    quiet: false,
+};
+
+/// Triggers haptic feedback, until feedbackd turns out to be missing.
+#[derive(Debug, Clone, Default)]
+pub struct Haptics {
+    /// Set when a call failed, to stop calling.
+    unavailable: Arc<AtomicBool>,
+}
+
+impl Haptics {
+    /// Creates the haptic feedback trigger.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `false` once feedbackd could not be reached.
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        !self.unavailable.load(Ordering::Relaxed)
+    }
+
+    /// Returns a future playing `pattern` for the application `app_id`, or
+    /// `None` if feedbackd could not be reached before.
+    ///
+    /// The future waits between pulses but not for the last one to end; if
+    /// a call fails, haptic feedback is turned off for the session.
+    pub fn trigger(
+        &self,
+        connection: zbus::Connection,
+        app_id: &'static str,
+        pattern: HapticPattern,
+    ) -> Option<impl Future<Output = ()> + Send + 'static> {
+        if !self.is_available() {
+            return None;
+        }
+        let unavailable = self.unavailable.clone();
+        Some(async move {
+            for pulse in 0..pattern.pulses {
+                if pulse > 0 {
+                    tokio::time::sleep(pattern.gap).await;
+                }
+                if let Err(e) = trigger_feedback(&connection, app_id, pattern).await {
+                    tracing::info!("No haptic feedback (feedbackd unavailable): {}", e);
+                    unavailable.store(true, Ordering::Relaxed);
+                    return;
+                }
+            }
+        })
+    }
+}
+
+/// Tells which boundary a finger sliding over the keys crosses.
+///
+/// Keys are given with the index of their row on the panel shown.
+#[derive(Debug, Clone, Default)]
+pub struct BoundaryTracker {
+    /// Key under the finger and its row, while a finger is down
+    current: Option<(String, Option<usize>)>,
+}
+
+impl BoundaryTracker {
+    /// Creates a tracker with no finger down.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a finger put down on a key.
+    pub fn press(&mut self, identifier: &str, row: Option<usize>) {
+        self.current = Some((identifier.to_string(), row));
+    }
+
+    /// Records the finger entering a key.
+    ///
+    /// Returns the pattern of the boundary crossed, or `None` if no finger
+    /// is down or it is still on the same key.
+    pub fn enter(&mut self, identifier: &str, row: Option<usize>) -> Option<HapticPattern> {
+        let (current, current_row) = self.current.as_mut()?;
+        if current == identifier {
+            return None;
+        }
+        let pattern = if *current_row == row {
+            KEY_BOUNDARY
+        } else {
+            ROW_BOUNDARY
+        };
+        *current = identifier.to_string();
+        *current_row = row;
+        Some(pattern)
+    }
+
+    /// Records the finger lifted.
+    pub fn end(&mut self) {
+        self.current = None;
+    }
+}
+
+/// Calls feedbackd's `TriggerFeedback` for one pulse of `pattern`.
+async fn trigger_feedback(
+    connection: &zbus::Connection,
+    app_id: &str,
+    pattern: HapticPattern,
+) -> zbus::Result<()> {
+    let proxy =
+        zbus::Proxy::new(connection, FEEDBACKD_NAME, FEEDBACKD_PATH, FEEDBACKD_NAME).await?;
+    let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+    if pattern.quiet {
+        hints.insert("profile", Value::from(QUIET_PROFILE));
+    }
+    let _id: u32 = proxy
+        .call(
+            "TriggerFeedback",
+            &(app_id, pattern.event, hints, PLAY_ONCE),
+        )
+        .await?;
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Sliding onto another key ticks once, onto another row twice;
+    /// staying on a key or sliding without a finger down does nothing.
+    #[test]
+    fn test_boundary_tracker() {
+        let mut tracker = BoundaryTracker::new();
+        assert_eq!(tracker.enter("q", Some(0)), None);
+
+        tracker.press("q", Some(0));
+        assert_eq!(tracker.enter("q", Some(0)), None);
+        assert_eq!(tracker.enter("w", Some(0)), Some(KEY_BOUNDARY));
+        assert_eq!(tracker.enter("s", Some(1)), Some(ROW_BOUNDARY));
+        assert_eq!(tracker.enter("d", Some(1)), Some(KEY_BOUNDARY));
+
+        tracker.end();
+        assert_eq!(tracker.enter("f", Some(1)), None);
+    }
+}
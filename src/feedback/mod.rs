@@ -0,0 +1,356 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Key press feedback: sounds and vibration.
+//!
+//! Layouts choose the sound of each key with a [`FeedbackProfile`]: a key's
+//! own `feedback` field wins, then the layout-wide `feedback` default; keys
+//! with neither are silent. Profiles are:
+//!
+//! - `silent`: no sound
+//! - `click`: the built-in click (the usual key sound)
+//! - `pop`: the built-in, softer pop (e.g. for Backspace)
+//! - `{"sample": "path.wav"}`: a custom WAV file, relative to the layout file
+//! - `{"theme": "cosboard-key"}`: an event of the user's sound theme (see
+//!   [`theme`]), so no sample has to be bundled
+//!
+//! [`Feedback`] resolves profiles to a [`Sound`]: decoded [`Sample`]s (custom
+//! files are decoded once and cached) or sound theme files, and plays them
+//! without blocking the UI. Playback pipes raw PCM, or hands the theme file,
+//! to PipeWire's `pw-cat` or, failing that, PulseAudio's `paplay`, so no
+//! audio library is linked into the keyboard.
+//!
+//! Key presses can also vibrate the device through feedbackd ([`haptic`]).
+//! The user turns each kind of feedback on or off with the `key_feedback`
+//! configuration entry ([`FeedbackSettings`]).
+
+pub mod haptic;
+pub mod sample;
+pub mod theme;
+
+pub use haptic::{
+    BoundaryTracker, HapticPattern, Haptics, KEY_BOUNDARY, KEY_PRESS, KEY_PRESS_EVENT, PANEL_SWITCH,
+    ROW_BOUNDARY,
+};
+pub use sample::{decode_wav, Sample, SampleCache, SampleError};
+pub use theme::{SoundTheme, KEY_SOUND_EVENT};
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{FeedbackProfile, Key, Layout};
+
+/// Which kinds of key press feedback are on, from the user configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedbackSettings {
+    /// Whether keys play the sounds their layout chooses.
+    #[serde(default = "default_sound")]
+    pub sound: bool,
+    /// Whether key presses vibrate the device through feedbackd. Off by
+    /// default, as desktops have nothing to vibrate.
+    #[serde(default)]
+    pub haptic: bool,
+    /// Whether, with `haptic` on, sliding onto another key or row and
+    /// switching panels vibrate too, each with its own pattern.
+    #[serde(default = "default_boundaries")]
+    pub boundaries: bool,
+}
+
+fn default_sound() -> bool {
+    true
+}
+
+fn default_boundaries() -> bool {
+    true
+}
+
+impl Default for FeedbackSettings {
+    fn default() -> Self {
+        Self {
+            sound: default_sound(),
+            haptic: false,
+            boundaries: default_boundaries(),
+        }
+    }
+}
+
+/// Returns the profile that applies to a key of a layout, if any.
+#[must_use]
+pub fn key_profile<'a>(key: &'a Key, layout: &'a Layout) -> Option<&'a FeedbackProfile> {
+    key.feedback.as_ref().or(layout.feedback.as_ref())
+}
+
+/// Command-line audio players that accept raw PCM on stdin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerCommand {
+    /// PipeWire's `pw-cat --playback`
+    PwCat,
+    /// PulseAudio's `paplay --raw`
+    Paplay,
+}
+
+impl PlayerCommand {
+    /// Players in the order they are tried.
+    pub const ALL: [PlayerCommand; 2] = [PlayerCommand::PwCat, PlayerCommand::Paplay];
+
+    /// Builds the command that plays a sound: a sample from stdin, or a
+    /// sound theme file, which the player decodes.
+    fn command(&self, sound: &Sound) -> Command {
+        let sample = match sound {
+            Sound::Sample(sample) => sample,
+            Sound::File(path) => return self.file_command(path),
+        };
+        let mut command = match self {
+            PlayerCommand::PwCat => {
+                let mut command = Command::new("pw-cat");
+                command.args(["--playback", "--format", "s16"]);
+                command.arg(format!("--rate={}", sample.rate));
+                command.arg(format!("--channels={}", sample.channels));
+                command.arg("-");
+                command
+            }
+            PlayerCommand::Paplay => {
+                let mut command = Command::new("paplay");
+                command.args(["--raw", "--format=s16le"]);
+                command.arg(format!("--rate={}", sample.rate));
+                command.arg(format!("--channels={}", sample.channels));
+                command
+            }
+        };
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        command
+    }
+
+    /// Builds the command that plays a sound file.
+    fn file_command(&self, path: &Path) -> Command {
+        let mut command = match self {
+            PlayerCommand::PwCat => {
+                let mut command = Command::new("pw-cat");
+                command.arg("--playback");
+                command
+            }
+            PlayerCommand::Paplay => Command::new("paplay"),
+        };
+        command
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        command
+    }
+}
+
+/// A resolved key sound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sound {
+    /// A decoded sample, piped to the player.
+    Sample(Arc<Sample>),
+    /// A sound theme file, decoded by the player.
+    File(PathBuf),
+}
+
+/// Plays key sounds for the applet.
+#[derive(Debug, Clone)]
+pub struct Feedback {
+    /// Decoded custom samples
+    cache: SampleCache,
+    /// Built-in click
+    click: Arc<Sample>,
+    /// Built-in pop
+    pop: Arc<Sample>,
+    /// The user's sound theme, read at the first theme profile
+    theme: Option<SoundTheme>,
+    /// Directory relative sample paths are resolved against (the layout's)
+    base_dir: Option<PathBuf>,
+    /// Player found to work, once one has been tried
+    player: Option<PlayerCommand>,
+    /// Set when no player could be started, to stop retrying
+    unavailable: bool,
+}
+
+impl Default for Feedback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Feedback {
+    /// Creates the feedback player with the built-in sounds.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            cache: SampleCache::new(),
+            click: Arc::new(Sample::click()),
+            pop: Arc::new(Sample::pop()),
+            theme: None,
+            base_dir: None,
+            player: None,
+            unavailable: false,
+        }
+    }
+
+    /// Sets the layout file whose directory relative sample paths use.
+    ///
+    /// Cached samples are dropped, since their paths may now differ.
+    pub fn set_layout_path(&mut self, layout_path: Option<&Path>) {
+        self.base_dir = layout_path.and_then(Path::parent).map(Path::to_path_buf);
+        self.cache.clear();
+    }
+
+    /// Resolves a sample path against the layout directory.
+    #[must_use]
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        match &self.base_dir {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Uses `theme` for theme profiles instead of the user's sound theme.
+    pub fn set_sound_theme(&mut self, theme: SoundTheme) {
+        self.theme = Some(theme);
+    }
+
+    /// Returns the sample for a profile (`None` when silent or unusable).
+    ///
+    /// Theme profiles have no sample; see [`Feedback::sound_for`].
+    pub fn sample_for(&mut self, profile: &FeedbackProfile) -> Option<Arc<Sample>> {
+        match profile {
+            FeedbackProfile::Silent | FeedbackProfile::Theme(_) => None,
+            FeedbackProfile::Click => Some(self.click.clone()),
+            FeedbackProfile::Pop => Some(self.pop.clone()),
+            FeedbackProfile::Sample(path) => {
+                let path = self.resolve_path(path);
+                self.cache.get(&path)
+            }
+        }
+    }
+
+    /// Returns the sound for a profile (`None` when silent or unusable).
+    ///
+    /// Theme events the sound theme lacks play the built-in click.
+    pub fn sound_for(&mut self, profile: &FeedbackProfile) -> Option<Sound> {
+        let FeedbackProfile::Theme(event) = profile else {
+            return self.sample_for(profile).map(Sound::Sample);
+        };
+        let found = self
+            .theme
+            .get_or_insert_with(SoundTheme::from_environment)
+            .lookup(event);
+        Some(found.map_or_else(|| Sound::Sample(self.click.clone()), Sound::File))
+    }
+
+    /// Plays the sound for a profile in the background.
+    pub fn play(&mut self, profile: &FeedbackProfile) {
+        if self.unavailable {
+            return;
+        }
+        let Some(sound) = self.sound_for(profile) else {
+            return;
+        };
+
+        let candidates: Vec<PlayerCommand> = match self.player {
+            Some(player) => vec![player],
+            None => PlayerCommand::ALL.to_vec(),
+        };
+
+        for player in candidates {
+            match player.command(&sound).spawn() {
+                Ok(mut child) => {
+                    self.player = Some(player);
+                    let sound = sound.clone();
+                    std::thread::spawn(move || {
+                        if let (Some(mut stdin), Sound::Sample(sample)) =
+                            (child.stdin.take(), sound)
+                        {
+                            let _ = stdin.write_all(&sample.to_le_bytes());
+                        }
+                        let _ = child.wait();
+                    });
+                    return;
+                }
+                Err(err) => {
+                    tracing::debug!("Cannot start {:?} for key sounds: {}", player, err);
+                }
+            }
+        }
+
+        tracing::warn!("No audio player (pw-cat or paplay) found; key sounds disabled");
+        self.unavailable = true;
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: A key's own profile wins over the layout default.
+    #[test]
+    fn test_key_profile() {
+        let mut layout = Layout::default();
+        let letter = Key::default();
+        let backspace = Key {
+            feedback: Some(FeedbackProfile::Pop),
+            ..Key::default()
+        };
+
+        assert_eq!(key_profile(&letter, &layout), None);
+
+        layout.feedback = Some(FeedbackProfile::Click);
+        assert_eq!(key_profile(&letter, &layout), Some(&FeedbackProfile::Click));
+        assert_eq!(key_profile(&backspace, &layout), Some(&FeedbackProfile::Pop));
+    }
+
+    /// Test: Sounds stay on and vibration off unless configured otherwise.
+    #[test]
+    fn test_feedback_settings_defaults() {
+        let settings: FeedbackSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, FeedbackSettings::default());
+        assert!(settings.sound && !settings.haptic);
+
+        let settings: FeedbackSettings = serde_json::from_str(r#"{"haptic": true}"#).unwrap();
+        assert!(settings.sound && settings.haptic && settings.boundaries);
+    }
+
+    /// Test: Profiles resolve to built-in sounds or layout-relative files.
+    #[test]
+    fn test_sample_for_profile() {
+        let mut feedback = Feedback::new();
+        assert!(feedback.sample_for(&FeedbackProfile::Silent).is_none());
+        assert!(feedback.sample_for(&FeedbackProfile::Click).is_some());
+        assert_ne!(
+            feedback.sample_for(&FeedbackProfile::Click),
+            feedback.sample_for(&FeedbackProfile::Pop)
+        );
+
+        feedback.set_layout_path(Some(Path::new("/layouts/qwerty.json")));
+        assert_eq!(
+            feedback.resolve_path("sounds/key.wav"),
+            PathBuf::from("/layouts/sounds/key.wav")
+        );
+        assert_eq!(
+            feedback.resolve_path("/usr/share/sounds/key.wav"),
+            PathBuf::from("/usr/share/sounds/key.wav")
+        );
+
+        // Theme events the theme lacks fall back to the click
+        feedback.set_sound_theme(SoundTheme::new("missing", Vec::new()));
+        let profile = FeedbackProfile::Theme(KEY_SOUND_EVENT.to_string());
+        assert!(feedback.sample_for(&profile).is_none());
+        assert_eq!(
+            feedback.sound_for(&profile),
+            feedback.sample_for(&FeedbackProfile::Click).map(Sound::Sample)
+        );
+    }
+}
@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Decoded audio samples and their cache.
+//!
+//! Key sounds are short PCM clips. The built-in `click` and `pop` sounds are
+//! synthesized; custom sounds are WAV files (8- or 16-bit PCM) that are
+//! decoded once and kept in a [`SampleCache`], so typing never touches the
+//! disk after the first press of a key.
+
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Sample rate of the built-in sounds.
+pub const BUILTIN_RATE: u32 = 48_000;
+
+/// A decoded PCM clip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    /// Frames per second.
+    pub rate: u32,
+    /// Number of interleaved channels.
+    pub channels: u16,
+    /// Signed 16-bit samples, interleaved by channel.
+    pub data: Vec<i16>,
+}
+
+impl Sample {
+    /// Synthesizes the built-in click: a few milliseconds of a sharply
+    /// decaying high tone.
+    #[must_use]
+    pub fn click() -> Self {
+        Self::tone(2_000.0, 0.006, 0.35)
+    }
+
+    /// Synthesizes the built-in pop: a softer, lower and longer tone.
+    #[must_use]
+    pub fn pop() -> Self {
+        Self::tone(320.0, 0.025, 0.5)
+    }
+
+    /// Synthesizes a mono sine tone with an exponential decay.
+    fn tone(frequency: f32, seconds: f32, volume: f32) -> Self {
+        let frames = (BUILTIN_RATE as f32 * seconds) as usize;
+        let data = (0..frames)
+            .map(|i| {
+                let t = i as f32 / BUILTIN_RATE as f32;
+                let envelope = (-5.0 * i as f32 / frames as f32).exp();
+                let value = (TAU * frequency * t).sin() * envelope * volume;
+                (value * f32::from(i16::MAX)) as i16
+            })
+            .collect();
+
+        Self {
+            rate: BUILTIN_RATE,
+            channels: 1,
+            data,
+        }
+    }
+
+    /// Returns the samples as little-endian signed 16-bit bytes (`s16le`).
+    #[must_use]
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|s| s.to_le_bytes()).collect()
+    }
+}
+
+/// Error decoding a WAV file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleError {
+    /// The file is not a RIFF/WAVE file.
+    NotWave,
+    /// The file ended in the middle of a chunk.
+    Truncated,
+    /// The `fmt ` or `data` chunk is missing.
+    MissingChunk(&'static str),
+    /// The encoding is not 8- or 16-bit integer PCM.
+    Unsupported {
+        /// WAVE format tag (1 is PCM)
+        format: u16,
+        /// Bits per sample
+        bits: u16,
+    },
+}
+
+impl fmt::Display for SampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleError::NotWave => write!(f, "Not a WAV file"),
+            SampleError::Truncated => write!(f, "WAV file is truncated"),
+            SampleError::MissingChunk(chunk) => write!(f, "WAV file has no '{}' chunk", chunk),
+            SampleError::Unsupported { format, bits } => write!(
+                f,
+                "Unsupported WAV encoding (format {}, {} bits); use 8- or 16-bit PCM",
+                format, bits
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SampleError {}
+
+/// Decodes an 8- or 16-bit PCM WAV file.
+///
+/// # Errors
+///
+/// Returns a [`SampleError`] if the data is not a supported WAV file.
+pub fn decode_wav(bytes: &[u8]) -> Result<Sample, SampleError> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(SampleError::NotWave);
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes([
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ]) as usize;
+        let body = bytes
+            .get(offset + 8..offset + 8 + size)
+            .ok_or(SampleError::Truncated)?;
+
+        match id {
+            b"fmt " => {
+                if body.len() < 16 {
+                    return Err(SampleError::Truncated);
+                }
+                let tag = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                format = Some((tag, channels, rate, bits));
+            }
+            b"data" => data = Some(body),
+            _ => {}
+        }
+
+        // Chunks are padded to an even size
+        offset += 8 + size + (size & 1);
+    }
+
+    let (tag, channels, rate, bits) = format.ok_or(SampleError::MissingChunk("fmt "))?;
+    let data = data.ok_or(SampleError::MissingChunk("data"))?;
+
+    let samples = match (tag, bits) {
+        (1, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect(),
+        (1, 8) => data.iter().map(|&b| (i16::from(b) - 128) << 8).collect(),
+        _ => return Err(SampleError::Unsupported { format: tag, bits }),
+    };
+
+    Ok(Sample {
+        rate,
+        channels: channels.max(1),
+        data: samples,
+    })
+}
+
+/// Decoded WAV files, by path.
+///
+/// Files that fail to load are remembered as missing, so a broken path is
+/// reported once instead of on every key press.
+#[derive(Debug, Clone, Default)]
+pub struct SampleCache {
+    samples: HashMap<PathBuf, Option<Arc<Sample>>>,
+}
+
+impl SampleCache {
+    /// Creates an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the decoded sample for `path`, loading it on first use.
+    pub fn get(&mut self, path: &Path) -> Option<Arc<Sample>> {
+        self.samples
+            .entry(path.to_path_buf())
+            .or_insert_with(|| match load(path) {
+                Ok(sample) => Some(Arc::new(sample)),
+                Err(err) => {
+                    tracing::warn!("Cannot use key sound {}: {}", path.display(), err);
+                    None
+                }
+            })
+            .clone()
+    }
+
+    /// Forgets all decoded samples (e.g. when the layout changes).
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Returns the number of cached paths.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/// Reads and decodes a WAV file.
+fn load(path: &Path) -> Result<Sample, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+    Ok(decode_wav(&bytes)?)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a WAV file with the given format and data.
+    fn wav(bits: u16, channels: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&22_050u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(&bits.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    /// Test: 16- and 8-bit PCM decode; other files are rejected.
+    #[test]
+    fn test_decode_wav() {
+        let sample = decode_wav(&wav(16, 2, &[0x00, 0x80, 0xff, 0x7f])).unwrap();
+        assert_eq!(sample.rate, 22_050);
+        assert_eq!(sample.channels, 2);
+        assert_eq!(sample.data, vec![i16::MIN, i16::MAX]);
+
+        let sample = decode_wav(&wav(8, 1, &[0, 128])).unwrap();
+        assert_eq!(sample.data, vec![-32_768, 0]);
+
+        assert_eq!(decode_wav(b"not a wav file"), Err(SampleError::NotWave));
+        assert_eq!(
+            decode_wav(&wav(24, 1, &[0, 0, 0])),
+            Err(SampleError::Unsupported { format: 1, bits: 24 })
+        );
+    }
+
+    /// Test: Files are decoded once; missing files are remembered too.
+    #[test]
+    fn test_sample_cache() {
+        let dir = std::env::temp_dir().join(format!("cosboard-sample-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("key.wav");
+        std::fs::write(&path, wav(16, 1, &[1, 0])).unwrap();
+
+        let mut cache = SampleCache::new();
+        let first = cache.get(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let second = cache.get(&path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        assert!(cache.get(&dir.join("missing.wav")).is_none());
+        assert_eq!(cache.len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
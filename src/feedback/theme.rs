@@ -0,0 +1,348 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Key sounds from the user's freedesktop sound theme.
+//!
+//! A `{"theme": "<event id>"}` feedback profile plays a sound theme event
+//! (`dialog-information`, or the keyboard's own [`KEY_SOUND_EVENT`]) instead
+//! of a bundled sample, so key sounds follow the sound theme the user picked
+//! for the rest of the desktop.
+//!
+//! Events are looked up the way libcanberra does, following the
+//! [Sound Theme Specification]:
+//!
+//! - Themes live in `sounds/<theme>` under `$XDG_DATA_HOME` and
+//!   `$XDG_DATA_DIRS`, so a Flatpak sees the themes of its runtime and of
+//!   the user's data directory. Their `index.theme` lists the theme's
+//!   directories and the themes it inherits; every theme ends up inheriting
+//!   [`FALLBACK_THEME`].
+//! - Directories of the `stereo` output profile are searched for
+//!   `<event>.oga`, `.ogg` and `.wav`.
+//! - An event that no theme has falls back to its parent event:
+//!   `dialog-information` to `dialog`, `cosboard-key` to `cosboard`.
+//!
+//! The theme is read from `gtk-sound-theme-name` in GTK's `settings.ini`,
+//! which COSMIC keeps in sync with its own setting and Flatpak apps usually
+//! see. Found files are played by the same `pw-cat`/`paplay` players as the
+//! other sounds, which decode the compressed formats themselves; no
+//! libcanberra is linked in.
+//!
+//! [Sound Theme Specification]: https://specifications.freedesktop.org/sound-theme-spec/latest/
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Event id of the keyboard's own key sound, for themes that provide one.
+pub const KEY_SOUND_EVENT: &str = "cosboard-key";
+
+/// Theme every theme inherits, and the one used when none is configured.
+pub const FALLBACK_THEME: &str = "freedesktop";
+
+/// Output profile whose directories are searched.
+pub const OUTPUT_PROFILE: &str = "stereo";
+
+/// File extensions of sound theme files, in the order they are tried.
+pub const SOUND_EXTENSIONS: [&str; 3] = ["oga", "ogg", "wav"];
+
+/// Inherited themes followed before giving up, against inheritance loops.
+const MAX_THEME_DEPTH: usize = 8;
+
+/// The parts of a theme's `index.theme` that lookups use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThemeIndex {
+    /// Themes this theme inherits, in order.
+    pub inherits: Vec<String>,
+    /// Directories holding the theme's sounds for [`OUTPUT_PROFILE`].
+    pub directories: Vec<String>,
+}
+
+impl ThemeIndex {
+    /// Parses an `index.theme` file.
+    ///
+    /// Directories without an `OutputProfile` are taken as stereo, as the
+    /// specification says.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut index = Self::default();
+        let mut listed = Vec::new();
+        let mut profiles: HashMap<String, String> = HashMap::new();
+        let mut section = String::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.to_string();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match (section.as_str(), key) {
+                ("Sound Theme", "Inherits") => index.inherits = split_list(value),
+                ("Sound Theme", "Directories") => listed = split_list(value),
+                (_, "OutputProfile") => {
+                    profiles.insert(section.clone(), value.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        index.directories = listed
+            .into_iter()
+            .filter(|dir| {
+                profiles
+                    .get(dir)
+                    .is_none_or(|profile| profile == OUTPUT_PROFILE)
+            })
+            .collect();
+        index
+    }
+}
+
+/// Splits a comma-separated `index.theme` value.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns the event ids tried for `event`: the event itself, then each
+/// parent event with the last `-` part removed.
+#[must_use]
+pub fn event_candidates(event: &str) -> Vec<&str> {
+    let mut candidates = vec![event];
+    let mut rest = event;
+    while let Some((parent, _)) = rest.rsplit_once('-') {
+        if parent.is_empty() {
+            break;
+        }
+        candidates.push(parent);
+        rest = parent;
+    }
+    candidates
+}
+
+/// Returns the sound theme named by `gtk-sound-theme-name` in a GTK
+/// `settings.ini`, if any.
+#[must_use]
+pub fn theme_name_from_settings(text: &str) -> Option<String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "gtk-sound-theme-name")
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Returns the directories sound themes are looked up in: `sounds` under
+/// `$XDG_DATA_HOME` and each of `$XDG_DATA_DIRS`.
+#[must_use]
+pub fn sound_dirs() -> Vec<PathBuf> {
+    let data_home =
+        env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".local/share")));
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from))
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join("sounds"))
+        .collect()
+}
+
+/// Returns the absolute directory an environment variable names, if any.
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+}
+
+/// A sound theme and the directories it is looked up in.
+///
+/// Lookups read the theme's files and are cached, found or not, so each
+/// event touches the disk once.
+#[derive(Debug, Clone)]
+pub struct SoundTheme {
+    /// Name of the theme
+    name: String,
+    /// Directories holding sound themes, searched in order
+    dirs: Vec<PathBuf>,
+    /// Sound file of each event looked up so far
+    cache: HashMap<String, Option<PathBuf>>,
+}
+
+impl SoundTheme {
+    /// Creates a lookup of theme `name` in `dirs`.
+    #[must_use]
+    pub fn new(name: impl Into<String>, dirs: Vec<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            dirs,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Creates a lookup of the user's sound theme in the XDG data
+    /// directories.
+    #[must_use]
+    pub fn from_environment() -> Self {
+        let name = env_dir("XDG_CONFIG_HOME")
+            .or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+            .map(|dir| dir.join("gtk-3.0/settings.ini"))
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| theme_name_from_settings(&text))
+            .unwrap_or_else(|| FALLBACK_THEME.to_string());
+        Self::new(name, sound_dirs())
+    }
+
+    /// Returns the name of the theme.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the sound file of an event, if the theme (or a theme it
+    /// inherits) has one for it or for a parent event.
+    pub fn lookup(&mut self, event: &str) -> Option<PathBuf> {
+        if let Some(found) = self.cache.get(event) {
+            return found.clone();
+        }
+
+        let themes = self.theme_chain();
+        let found = event_candidates(event).into_iter().find_map(|candidate| {
+            themes
+                .iter()
+                .find_map(|(dir, index)| find_in_theme(dir, index, candidate))
+        });
+        if found.is_none() {
+            tracing::debug!("Sound theme {} has no sound for {}", self.name, event);
+        }
+        self.cache.insert(event.to_string(), found.clone());
+        found
+    }
+
+    /// Returns the directories and indexes of the theme and the themes it
+    /// inherits, in lookup order, ending with [`FALLBACK_THEME`].
+    fn theme_chain(&self) -> Vec<(PathBuf, ThemeIndex)> {
+        let mut chain = Vec::new();
+        let mut visited: Vec<String> = Vec::new();
+        let mut pending = vec![self.name.clone()];
+
+        while let Some(name) = pending.pop() {
+            if visited.contains(&name) || visited.len() >= MAX_THEME_DEPTH {
+                continue;
+            }
+            visited.push(name.clone());
+
+            let mut inherits = Vec::new();
+            for dir in self.dirs.iter().map(|dir| dir.join(&name)) {
+                let index = std::fs::read_to_string(dir.join("index.theme"))
+                    .map(|text| ThemeIndex::parse(&text))
+                    .ok();
+                if let Some(index) = index {
+                    inherits.extend(index.inherits.iter().cloned());
+                    chain.push((dir, index));
+                }
+            }
+            if inherits.is_empty() && name != FALLBACK_THEME {
+                inherits.push(FALLBACK_THEME.to_string());
+            }
+            // Inherited themes are searched in their listed order
+            pending.extend(inherits.into_iter().rev());
+        }
+        chain
+    }
+}
+
+/// Returns the file of an event in one theme directory, if any.
+fn find_in_theme(dir: &Path, index: &ThemeIndex, event: &str) -> Option<PathBuf> {
+    index.directories.iter().find_map(|subdir| {
+        SOUND_EXTENSIONS
+            .iter()
+            .map(|extension| dir.join(subdir).join(format!("{}.{}", event, extension)))
+            .find(|path| path.is_file())
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: index.theme lists stereo directories and inherited themes.
+    #[test]
+    fn test_parse_index() {
+        let index = ThemeIndex::parse(
+            "[Sound Theme]\n\
+             Name=Ocean\n\
+             Inherits=freedesktop, ocean-base\n\
+             Directories=stereo,5.1\n\
+             \n\
+             [stereo]\n\
+             OutputProfile=stereo\n\
+             \n\
+             [5.1]\n\
+             OutputProfile=5.1\n",
+        );
+        assert_eq!(index.inherits, vec!["freedesktop", "ocean-base"]);
+        assert_eq!(index.directories, vec!["stereo"]);
+
+        assert_eq!(
+            theme_name_from_settings("[Settings]\ngtk-sound-theme-name = \"ocean\"\n").as_deref(),
+            Some("ocean")
+        );
+        assert_eq!(theme_name_from_settings("[Settings]\n"), None);
+    }
+
+    /// Test: Events fall back to their parents and inherited themes.
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            event_candidates("dialog-information"),
+            vec!["dialog-information", "dialog"]
+        );
+        assert_eq!(event_candidates("-key"), vec!["-key"]);
+
+        let root =
+            std::env::temp_dir().join(format!("cosboard-sound-theme-{}", std::process::id()));
+        let ocean = root.join("ocean");
+        let fallback = root.join(FALLBACK_THEME);
+        std::fs::create_dir_all(ocean.join("stereo")).unwrap();
+        std::fs::create_dir_all(fallback.join("stereo")).unwrap();
+        std::fs::write(
+            ocean.join("index.theme"),
+            "[Sound Theme]\nDirectories=stereo\n",
+        )
+        .unwrap();
+        std::fs::write(
+            fallback.join("index.theme"),
+            "[Sound Theme]\nDirectories=stereo\n",
+        )
+        .unwrap();
+        std::fs::write(ocean.join("stereo/cosboard.oga"), b"").unwrap();
+        std::fs::write(fallback.join("stereo/dialog-information.oga"), b"").unwrap();
+
+        let mut theme = SoundTheme::new("ocean", vec![root.clone()]);
+        assert_eq!(
+            theme.lookup(KEY_SOUND_EVENT),
+            Some(ocean.join("stereo/cosboard.oga"))
+        );
+        assert_eq!(
+            theme.lookup("dialog-information"),
+            Some(fallback.join("stereo/dialog-information.oga"))
+        );
+        assert_eq!(theme.lookup("bell"), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,325 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Keyboard-wide gestures and their bindings.
+//!
+//! Gestures are recognized on the keyboard surface as a whole (unlike the
+//! per-key swipe alternatives defined in layouts) and run a named action
+//! from the [`ActionRegistry`](crate::actions::ActionRegistry):
+//!
+//! | Gesture            | Default action | Recognized as                                  |
+//! |--------------------|----------------|------------------------------------------------|
+//! | `two_finger_tap`   | `hide`         | Two fingers tapped together on the background  |
+//! | `swipe_down`       | `hide`         | A fast, mostly vertical downward swipe         |
+//! | `long_press_space` | (none)         | Holding the space bar past the long press time |
+//!
+//! Bindings are stored in the user configuration as a map from gesture to
+//! action string; an empty string (or a missing entry) disables the gesture.
+//! Holding space is unbound by default: while it is bound, space is only
+//! typed when released, so it cannot be held down.
+//! Contacts rejected as a palm ([`palm`]) are not part of any gesture.
+
+pub mod palm;
+
+pub use palm::{PalmFilter, PalmRejection, Rejection};
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum duration of a two-finger tap.
+pub const TAP_MAX_DURATION: Duration = Duration::from_millis(300);
+
+/// Maximum finger movement during a tap, in logical pixels.
+pub const TAP_SLOP: f32 = 20.0;
+
+/// Minimum downward distance of a swipe, in logical pixels.
+pub const SWIPE_MIN_DISTANCE: f32 = 120.0;
+
+/// Maximum duration of a swipe.
+pub const SWIPE_MAX_DURATION: Duration = Duration::from_millis(500);
+
+/// A keyboard-wide gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Gesture {
+    /// Two fingers tapped together on the keyboard background.
+    TwoFingerTap,
+    /// A downward swipe across the keyboard.
+    SwipeDown,
+    /// A long press on the space bar.
+    LongPressSpace,
+}
+
+impl Gesture {
+    /// All gestures, in settings order.
+    pub const ALL: [Gesture; 3] = [
+        Gesture::TwoFingerTap,
+        Gesture::SwipeDown,
+        Gesture::LongPressSpace,
+    ];
+}
+
+/// Actions offered when cycling a binding in the settings menu.
+///
+/// The empty string disables the gesture.
+pub const BINDING_CHOICES: &[&str] = &["hide", "palette", "toggle_layer", "toggle_floating", ""];
+
+/// Gesture to action bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GestureBindings(pub BTreeMap<Gesture, String>);
+
+impl Default for GestureBindings {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            (Gesture::TwoFingerTap, "hide".to_string()),
+            (Gesture::SwipeDown, "hide".to_string()),
+            (Gesture::LongPressSpace, String::new()),
+        ]))
+    }
+}
+
+impl GestureBindings {
+    /// Returns the action bound to a gesture, if any.
+    #[must_use]
+    pub fn action(&self, gesture: Gesture) -> Option<&str> {
+        self.0
+            .get(&gesture)
+            .map(String::as_str)
+            .filter(|action| !action.is_empty())
+    }
+
+    /// Returns `true` if the gesture has an action bound.
+    #[must_use]
+    pub fn is_bound(&self, gesture: Gesture) -> bool {
+        self.action(gesture).is_some()
+    }
+
+    /// Binds the gesture to the next entry of [`BINDING_CHOICES`].
+    ///
+    /// Custom bindings (not in the list) restart at the first choice.
+    pub fn cycle(&mut self, gesture: Gesture) {
+        let current = self.action(gesture).unwrap_or("");
+        let next = BINDING_CHOICES
+            .iter()
+            .position(|choice| *choice == current)
+            .map_or(0, |index| (index + 1) % BINDING_CHOICES.len());
+        self.0.insert(gesture, BINDING_CHOICES[next].to_string());
+    }
+}
+
+/// A finger currently touching the keyboard surface.
+#[derive(Debug, Clone, Copy)]
+struct Touch {
+    id: u64,
+    start: (f32, f32),
+    started_at: Instant,
+    on_background: bool,
+    moved: bool,
+}
+
+/// Recognizes surface-wide touch gestures from raw touch events.
+///
+/// Feed it every finger press, move and lift on the keyboard surface; it
+/// returns a gesture when one completes. Long presses on the space bar are
+/// detected by the key long-press timer, not here.
+#[derive(Debug, Default)]
+pub struct GestureTracker {
+    touches: Vec<Touch>,
+    /// Set once a second finger joined on the background.
+    two_finger_candidate: bool,
+}
+
+impl GestureTracker {
+    /// Creates an idle tracker.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a finger press.
+    ///
+    /// `on_background` is `true` when no key captured the press.
+    pub fn finger_pressed(
+        &mut self,
+        id: u64,
+        position: (f32, f32),
+        now: Instant,
+        on_background: bool,
+    ) {
+        self.touches.retain(|touch| touch.id != id);
+        self.touches.push(Touch {
+            id,
+            start: position,
+            started_at: now,
+            on_background,
+            moved: false,
+        });
+
+        if self.touches.len() == 1 {
+            self.two_finger_candidate = false;
+        } else if self.touches.len() == 2 {
+            self.two_finger_candidate = self.touches.iter().all(|touch| touch.on_background);
+        } else {
+            self.two_finger_candidate = false;
+        }
+    }
+
+    /// Records finger movement.
+    pub fn finger_moved(&mut self, id: u64, position: (f32, f32)) {
+        if let Some(touch) = self.touches.iter_mut().find(|touch| touch.id == id) {
+            if distance(touch.start, position) > TAP_SLOP {
+                touch.moved = true;
+            }
+        }
+    }
+
+    /// Records a finger lift and returns the completed gesture, if any.
+    pub fn finger_lifted(
+        &mut self,
+        id: u64,
+        position: (f32, f32),
+        now: Instant,
+    ) -> Option<Gesture> {
+        let index = self.touches.iter().position(|touch| touch.id == id)?;
+        let mut touch = self.touches.remove(index);
+        if distance(touch.start, position) > TAP_SLOP {
+            touch.moved = true;
+        }
+        let elapsed = now.saturating_duration_since(touch.started_at);
+
+        if self.two_finger_candidate {
+            if touch.moved || elapsed > TAP_MAX_DURATION {
+                self.two_finger_candidate = false;
+            } else if self.touches.is_empty() {
+                self.two_finger_candidate = false;
+                return Some(Gesture::TwoFingerTap);
+            }
+            return None;
+        }
+
+        // Single-finger swipe: far enough, fast enough, mostly vertical
+        let dx = position.0 - touch.start.0;
+        let dy = position.1 - touch.start.1;
+        if self.touches.is_empty()
+            && dy >= SWIPE_MIN_DISTANCE
+            && dy > 2.0 * dx.abs()
+            && elapsed <= SWIPE_MAX_DURATION
+        {
+            return Some(Gesture::SwipeDown);
+        }
+
+        None
+    }
+
+    /// Forgets a finger whose touch was cancelled.
+    pub fn finger_lost(&mut self, id: u64) {
+        self.touches.retain(|touch| touch.id != id);
+        self.two_finger_candidate = false;
+    }
+}
+
+/// Euclidean distance between two points.
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Two background fingers tapped together form a two-finger tap.
+    #[test]
+    fn test_two_finger_tap() {
+        let mut tracker = GestureTracker::new();
+        let t0 = Instant::now();
+        tracker.finger_pressed(1, (10.0, 10.0), t0, true);
+        tracker.finger_pressed(2, (60.0, 10.0), t0, true);
+        assert_eq!(
+            tracker.finger_lifted(1, (12.0, 11.0), t0 + Duration::from_millis(100)),
+            None
+        );
+        assert_eq!(
+            tracker.finger_lifted(2, (60.0, 10.0), t0 + Duration::from_millis(120)),
+            Some(Gesture::TwoFingerTap)
+        );
+
+        // A finger on a key, or a slow tap, is not a two-finger tap
+        tracker.finger_pressed(1, (10.0, 10.0), t0, false);
+        tracker.finger_pressed(2, (60.0, 10.0), t0, true);
+        tracker.finger_lifted(1, (10.0, 10.0), t0);
+        assert_eq!(tracker.finger_lifted(2, (60.0, 10.0), t0), None);
+
+        tracker.finger_pressed(1, (10.0, 10.0), t0, true);
+        tracker.finger_pressed(2, (60.0, 10.0), t0, true);
+        tracker.finger_lifted(1, (10.0, 10.0), t0 + Duration::from_millis(500));
+        assert_eq!(
+            tracker.finger_lifted(2, (60.0, 10.0), t0 + Duration::from_millis(500)),
+            None
+        );
+    }
+
+    /// Test: Fast vertical downward swipes are recognized, others are not.
+    #[test]
+    fn test_swipe_down() {
+        let mut tracker = GestureTracker::new();
+        let t0 = Instant::now();
+        let later = t0 + Duration::from_millis(200);
+
+        tracker.finger_pressed(1, (100.0, 20.0), t0, false);
+        tracker.finger_moved(1, (105.0, 100.0));
+        assert_eq!(
+            tracker.finger_lifted(1, (110.0, 180.0), later),
+            Some(Gesture::SwipeDown)
+        );
+
+        // Too short
+        tracker.finger_pressed(1, (100.0, 20.0), t0, false);
+        assert_eq!(tracker.finger_lifted(1, (100.0, 80.0), later), None);
+
+        // Too diagonal
+        tracker.finger_pressed(1, (100.0, 20.0), t0, false);
+        assert_eq!(tracker.finger_lifted(1, (250.0, 180.0), later), None);
+
+        // Upward
+        tracker.finger_pressed(1, (100.0, 200.0), t0, false);
+        assert_eq!(tracker.finger_lifted(1, (100.0, 20.0), later), None);
+    }
+
+    /// Test: Default bindings and cycling through the choices.
+    #[test]
+    fn test_bindings() {
+        let mut bindings = GestureBindings::default();
+        assert_eq!(bindings.action(Gesture::SwipeDown), Some("hide"));
+        assert!(bindings.is_bound(Gesture::TwoFingerTap));
+        assert!(
+            !bindings.is_bound(Gesture::LongPressSpace),
+            "Holding space types spaces by default"
+        );
+
+        bindings.cycle(Gesture::SwipeDown);
+        assert_eq!(bindings.action(Gesture::SwipeDown), Some("palette"));
+
+        for _ in 0..3 {
+            bindings.cycle(Gesture::SwipeDown);
+        }
+        assert!(
+            !bindings.is_bound(Gesture::SwipeDown),
+            "Last choice disables the gesture"
+        );
+
+        bindings
+            .0
+            .insert(Gesture::SwipeDown, "switch_panel:numpad".to_string());
+        bindings.cycle(Gesture::SwipeDown);
+        assert_eq!(
+            bindings.action(Gesture::SwipeDown),
+            Some(BINDING_CHOICES[0])
+        );
+    }
+}
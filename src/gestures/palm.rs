@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Palm rejection on the keyboard surface.
+//!
+//! On a convertible, the hand resting on or brushing over the screen lands
+//! on keys. With `palm_rejection` enabled, the [`PalmFilter`] follows every
+//! finger on the keyboard surface and rejects contacts that do not look like
+//! a fingertip:
+//!
+//! - contacts lifted sooner than `min_duration_ms`, such as a sleeve or the
+//!   side of the hand grazing the screen;
+//! - unstable contacts, whose position jumps by more than `max_jump` between
+//!   two reports, as the centre of a palm does while it rolls.
+//!
+//! While palm rejection is enabled, a key is not typed when pressed but
+//! once its contact lasted `min_duration_ms` (or on release, if the contact
+//! was not rejected by then), and a key whose contact was rejected is not
+//! typed at all. Rejected contacts do not count towards keyboard-wide
+//! gestures either.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Default shortest key press, in milliseconds.
+pub const DEFAULT_MIN_DURATION_MS: u64 = 25;
+
+/// Default largest jump between two positions of a contact, in logical
+/// pixels.
+pub const DEFAULT_MAX_JUMP: u32 = 40;
+
+/// Palm rejection thresholds, from the user configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PalmRejection {
+    /// Whether contacts are filtered at all. Off by default, since keys are
+    /// then typed slightly after they are pressed.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Shortest contact still taken for a key press, in milliseconds; 0
+    /// disables the check.
+    #[serde(default = "default_min_duration_ms")]
+    pub min_duration_ms: u64,
+    /// Largest jump between two reported positions of a contact, in logical
+    /// pixels; 0 disables the check.
+    #[serde(default = "default_max_jump")]
+    pub max_jump: u32,
+}
+
+fn default_min_duration_ms() -> u64 {
+    DEFAULT_MIN_DURATION_MS
+}
+
+fn default_max_jump() -> u32 {
+    DEFAULT_MAX_JUMP
+}
+
+impl Default for PalmRejection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_duration_ms: default_min_duration_ms(),
+            max_jump: default_max_jump(),
+        }
+    }
+}
+
+impl PalmRejection {
+    /// Returns the shortest contact still taken for a key press.
+    #[must_use]
+    pub fn min_duration(&self) -> Duration {
+        Duration::from_millis(self.min_duration_ms)
+    }
+
+    /// Returns `true` if a press held for `held` is too brief to be a key
+    /// press.
+    #[must_use]
+    pub fn is_too_brief(&self, held: Duration) -> bool {
+        self.enabled && self.min_duration_ms > 0 && held < self.min_duration()
+    }
+}
+
+/// Why a contact was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The contact ended too soon.
+    TooBrief,
+    /// The position of the contact jumped.
+    Unstable,
+}
+
+/// A finger currently touching the keyboard surface.
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    id: u64,
+    position: (f32, f32),
+    started_at: Instant,
+    rejection: Option<Rejection>,
+}
+
+/// Classifies the contacts on the keyboard surface.
+///
+/// Feed it every finger press, move and lift, like the
+/// [`GestureTracker`](super::GestureTracker).
+#[derive(Debug, Default)]
+pub struct PalmFilter {
+    settings: PalmRejection,
+    /// Fingers down, the most recently pressed last.
+    contacts: Vec<Contact>,
+}
+
+impl PalmFilter {
+    /// Creates a filter with the given thresholds.
+    #[must_use]
+    pub fn new(settings: PalmRejection) -> Self {
+        Self {
+            settings,
+            contacts: Vec::new(),
+        }
+    }
+
+    /// Replaces the thresholds, keeping the fingers down.
+    pub fn set_settings(&mut self, settings: PalmRejection) {
+        self.settings = settings;
+    }
+
+    /// Records a finger press.
+    pub fn finger_pressed(&mut self, id: u64, position: (f32, f32), now: Instant) {
+        self.contacts.retain(|contact| contact.id != id);
+        self.contacts.push(Contact {
+            id,
+            position,
+            started_at: now,
+            rejection: None,
+        });
+    }
+
+    /// Records finger movement.
+    pub fn finger_moved(&mut self, id: u64, position: (f32, f32)) {
+        let max_jump = self.settings.max_jump;
+        let enabled = self.settings.enabled && max_jump > 0;
+        if let Some(contact) = self.contacts.iter_mut().find(|contact| contact.id == id) {
+            if enabled
+                && contact.rejection.is_none()
+                && super::distance(contact.position, position) > max_jump as f32
+            {
+                contact.rejection = Some(Rejection::Unstable);
+            }
+            contact.position = position;
+        }
+    }
+
+    /// Records a finger lift and returns why the contact was rejected, if it
+    /// was.
+    pub fn finger_lifted(&mut self, id: u64, now: Instant) -> Option<Rejection> {
+        let index = self.contacts.iter().position(|contact| contact.id == id)?;
+        let contact = self.contacts.remove(index);
+        let held = now.saturating_duration_since(contact.started_at);
+        contact.rejection.or_else(|| {
+            self.settings
+                .is_too_brief(held)
+                .then_some(Rejection::TooBrief)
+        })
+    }
+
+    /// Forgets a finger whose touch was cancelled.
+    pub fn finger_lost(&mut self, id: u64) {
+        self.contacts.retain(|contact| contact.id != id);
+    }
+
+    /// Returns why the most recently pressed finger still down was rejected
+    /// so far, if it was.
+    ///
+    /// The touch events of a finger arrive after the key presses they
+    /// cause, so when a key is released its finger is still down here.
+    #[must_use]
+    pub fn latest_rejection(&self) -> Option<Rejection> {
+        self.contacts.last().and_then(|contact| contact.rejection)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> PalmRejection {
+        PalmRejection {
+            enabled: true,
+            ..PalmRejection::default()
+        }
+    }
+
+    /// Test: Brief and jumping contacts are rejected, a fingertip held and
+    /// moved steadily is not.
+    #[test]
+    fn test_palm_filter() {
+        let now = Instant::now();
+        let mut filter = PalmFilter::new(enabled());
+
+        filter.finger_pressed(1, (10.0, 10.0), now);
+        filter.finger_moved(1, (30.0, 10.0));
+        filter.finger_moved(1, (60.0, 10.0));
+        assert_eq!(filter.latest_rejection(), None);
+        assert_eq!(
+            filter.finger_lifted(1, now + Duration::from_millis(80)),
+            None
+        );
+
+        filter.finger_pressed(3, (10.0, 10.0), now);
+        assert_eq!(
+            filter.finger_lifted(3, now + Duration::from_millis(5)),
+            Some(Rejection::TooBrief)
+        );
+
+        filter.finger_pressed(4, (10.0, 10.0), now);
+        filter.finger_moved(4, (90.0, 10.0));
+        assert_eq!(filter.latest_rejection(), Some(Rejection::Unstable));
+        filter.finger_lost(4);
+        assert_eq!(filter.latest_rejection(), None);
+    }
+
+    /// Test: A disabled filter, or a threshold set to 0, rejects nothing.
+    #[test]
+    fn test_palm_filter_disabled() {
+        let now = Instant::now();
+        let mut filter = PalmFilter::default();
+        filter.finger_pressed(1, (10.0, 10.0), now);
+        filter.finger_moved(1, (400.0, 10.0));
+        assert_eq!(filter.finger_lifted(1, now), None);
+
+        filter.set_settings(PalmRejection {
+            min_duration_ms: 0,
+            ..enabled()
+        });
+        filter.finger_pressed(1, (10.0, 10.0), now);
+        assert_eq!(filter.finger_lifted(1, now), None);
+        assert!(!PalmRejection::default().is_too_brief(Duration::ZERO));
+    }
+}
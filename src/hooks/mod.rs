@@ -0,0 +1,538 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Automation hooks run on keyboard events.
+//!
+//! A program can be run, or a D-Bus method called, when the keyboard is
+//! shown, hidden or switches layouts: to resize an application out of the
+//! keyboard's way, say, or to toggle a compositor setting while it is up.
+//! Hooks live in the user configuration ([`HookSettings`]):
+//!
+//! ```ron
+//! (
+//!     hooks: [
+//!         (event: Shown, run: Command(["notify-send", "Keyboard", "{layout}"])),
+//!         (
+//!             event: LayoutChanged,
+//!             run: DbusCall(
+//!                 destination: "org.example.Tool",
+//!                 path: "/org/example/Tool",
+//!                 interface: "org.example.Tool",
+//!                 method: "SetLayout",
+//!                 args: ["{layout}"],
+//!             ),
+//!         ),
+//!     ],
+//!     timeout_ms: 5000,
+//! )
+//! ```
+//!
+//! `{event}` and `{layout}` in arguments are replaced with the event
+//! (`shown`, `hidden` or `layout-changed`) and the name of the layout,
+//! which is empty when the keyboard is first shown, before its layout is
+//! loaded.
+//!
+//! The hooks of an event run off the UI thread, one after the other in the
+//! order they are listed, and never hold up the keyboard. Commands run with
+//! the user's rights and are not sandboxed; hooks are trusted like any
+//! program the user starts, which is why importing them needs consent (see
+//! [`bundle`](crate::config::bundle)). What Cosboard does limit:
+//!
+//! - the program is run directly, never through a shell, so a layout name
+//!   cannot smuggle in commands;
+//! - its environment is cleared but for [`PASSED_ENV`], plus
+//!   `COSBOARD_EVENT` and `COSBOARD_LAYOUT`;
+//! - it runs in the home directory, with no input and its output discarded;
+//! - it runs in a process group of its own, which is killed once the hook
+//!   runs longer than `timeout_ms`, so programs it started go too; D-Bus
+//!   calls are given up after as long;
+//! - the hooks of at most [`MAX_RUNNING`] events run at once; hooks of
+//!   further events are skipped rather than piling up processes.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::future::Future;
+use std::os::unix::process::CommandExt;
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use zbus::zvariant::StructureBuilder;
+
+/// Environment variables passed on to hook commands.
+pub const PASSED_ENV: [&str; 9] = [
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "XDG_RUNTIME_DIR",
+    "XDG_CURRENT_DESKTOP",
+    "WAYLAND_DISPLAY",
+    "DISPLAY",
+    "DBUS_SESSION_BUS_ADDRESS",
+];
+
+/// Most events whose hooks may run at once.
+pub const MAX_RUNNING: usize = 4;
+
+/// Time a hook may take, unless configured otherwise.
+pub const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// Longest time a hook may be given.
+pub const MAX_TIMEOUT_MS: u64 = 60_000;
+
+/// Keyboard events hooks run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookEvent {
+    /// The keyboard was shown.
+    Shown,
+    /// The keyboard was hidden.
+    Hidden,
+    /// Another layout was loaded.
+    LayoutChanged,
+}
+
+impl HookEvent {
+    /// Returns the name of the event, as passed to hooks.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::Shown => "shown",
+            HookEvent::Hidden => "hidden",
+            HookEvent::LayoutChanged => "layout-changed",
+        }
+    }
+}
+
+/// What a hook does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookAction {
+    /// Runs a program with arguments, without a shell.
+    Command(Vec<String>),
+    /// Calls a method on the session bus with string arguments.
+    DbusCall {
+        /// Bus name of the service called.
+        destination: String,
+        /// Object path.
+        path: String,
+        /// Interface of the method.
+        interface: String,
+        /// Method name.
+        method: String,
+        /// Arguments, all strings.
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl fmt::Display for HookAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookAction::Command(argv) => {
+                write!(f, "{}", argv.first().map_or("(no program)", String::as_str))
+            }
+            HookAction::DbusCall {
+                destination,
+                interface,
+                method,
+                ..
+            } => write!(f, "{} {}.{}", destination, interface, method),
+        }
+    }
+}
+
+/// A hook: what runs on which event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    /// Event the hook runs on.
+    pub event: HookEvent,
+    /// What the hook does.
+    pub run: HookAction,
+}
+
+/// User hooks and how long they may take.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookSettings {
+    /// The user's hooks.
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// Time in milliseconds a hook may take before it is stopped (at most
+    /// [`MAX_TIMEOUT_MS`]).
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+impl Default for HookSettings {
+    fn default() -> Self {
+        Self {
+            hooks: Vec::new(),
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+}
+
+impl HookSettings {
+    /// Returns the time a hook may take.
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.min(MAX_TIMEOUT_MS))
+    }
+
+    /// Returns the actions of the hooks of `event`, in order.
+    pub fn actions(&self, event: HookEvent) -> impl Iterator<Item = &HookAction> {
+        self.hooks
+            .iter()
+            .filter(move |hook| hook.event == event)
+            .map(|hook| &hook.run)
+    }
+}
+
+/// Error running a hook.
+#[derive(Debug)]
+pub enum HookError {
+    /// The command names no program.
+    EmptyCommand,
+    /// The program could not be started.
+    Spawn(std::io::Error),
+    /// The program exited with an error.
+    Failed(ExitStatus),
+    /// The hook ran longer than it may.
+    TimedOut(Duration),
+    /// The D-Bus call failed.
+    Dbus(zbus::Error),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookError::EmptyCommand => write!(f, "No program to run"),
+            HookError::Spawn(e) => write!(f, "Cannot start the program: {}", e),
+            HookError::Failed(status) => write!(f, "The program failed ({})", status),
+            HookError::TimedOut(timeout) => {
+                write!(f, "Stopped after {} ms", timeout.as_millis())
+            }
+            HookError::Dbus(e) => write!(f, "D-Bus call failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Replaces `{event}` and `{layout}` in a hook argument.
+#[must_use]
+pub fn expand_argument(argument: &str, event: HookEvent, layout: &str) -> String {
+    argument
+        .replace("{event}", event.as_str())
+        .replace("{layout}", layout)
+}
+
+/// Builds the process of a command hook, passing on only the variables of
+/// `env` named in [`PASSED_ENV`].
+///
+/// Returns `None` if `argv` names no program.
+pub fn hook_command(
+    argv: &[String],
+    event: HookEvent,
+    layout: &str,
+    env: impl IntoIterator<Item = (OsString, OsString)>,
+) -> Option<std::process::Command> {
+    let (program, args) = argv.split_first()?;
+    let env: Vec<(OsString, OsString)> = env
+        .into_iter()
+        .filter(|(name, _)| name.to_str().is_some_and(|name| PASSED_ENV.contains(&name)))
+        .collect();
+
+    let mut command = std::process::Command::new(program);
+    command
+        .args(args.iter().map(|arg| expand_argument(arg, event, layout)))
+        .env_clear()
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .env("COSBOARD_EVENT", event.as_str())
+        .env("COSBOARD_LAYOUT", layout)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    if let Some((_, home)) = env.iter().find(|(name, _)| name == "HOME") {
+        command.current_dir(home);
+    }
+    Some(command)
+}
+
+/// Runs a command hook, killing it after `timeout`.
+async fn run_command(
+    argv: &[String],
+    event: HookEvent,
+    layout: &str,
+    timeout: Duration,
+) -> Result<(), HookError> {
+    let command =
+        hook_command(argv, event, layout, std::env::vars_os()).ok_or(HookError::EmptyCommand)?;
+    let mut child = tokio::process::Command::from(command)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(HookError::Spawn)?;
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(HookError::Failed(status)),
+        Ok(Err(e)) => Err(HookError::Spawn(e)),
+        Err(_) => {
+            if let Some(pid) = child.id() {
+                kill_group(pid);
+            }
+            let _ = child.kill().await;
+            Err(HookError::TimedOut(timeout))
+        }
+    }
+}
+
+/// Kills the process group led by the hook process `pid`.
+fn kill_group(pid: u32) {
+    let Ok(group) = libc::pid_t::try_from(pid) else {
+        return;
+    };
+    // SAFETY: killpg only sends a signal; the group was created for the
+    // hook, which has not been reaped yet, so its ID is not reused.
+    if unsafe { libc::killpg(group, libc::SIGKILL) } < 0 {
+        tracing::debug!(
+            "Cannot kill the processes of hook {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Calls the method of a D-Bus hook.
+async fn call_method(
+    connection: Option<zbus::Connection>,
+    action: &HookAction,
+    event: HookEvent,
+    layout: &str,
+) -> zbus::Result<()> {
+    let HookAction::DbusCall {
+        destination,
+        path,
+        interface,
+        method,
+        args,
+    } = action
+    else {
+        return Ok(());
+    };
+    let connection = match connection {
+        Some(connection) => connection,
+        None => zbus::Connection::session().await?,
+    };
+    let proxy = zbus::Proxy::new(
+        &connection,
+        destination.as_str(),
+        path.as_str(),
+        interface.as_str(),
+    )
+    .await?;
+    if args.is_empty() {
+        proxy.call_method(method.as_str(), &()).await?;
+    } else {
+        let body = args
+            .iter()
+            .fold(StructureBuilder::new(), |body, arg| {
+                body.add_field(expand_argument(arg, event, layout))
+            })
+            .build()?;
+        proxy.call_method(method.as_str(), &body).await?;
+    }
+    Ok(())
+}
+
+/// Runs one hook.
+async fn run_hook(
+    action: &HookAction,
+    event: HookEvent,
+    layout: &str,
+    connection: Option<zbus::Connection>,
+    timeout: Duration,
+) -> Result<(), HookError> {
+    match action {
+        HookAction::Command(argv) => run_command(argv, event, layout, timeout).await,
+        HookAction::DbusCall { .. } => {
+            let call = call_method(connection, action, event, layout);
+            match tokio::time::timeout(timeout, call).await {
+                Ok(result) => result.map_err(HookError::Dbus),
+                Err(_) => Err(HookError::TimedOut(timeout)),
+            }
+        }
+    }
+}
+
+/// Counts an event's hooks as running until dropped.
+#[derive(Debug)]
+pub struct Running(Arc<AtomicUsize>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Runs the hooks of keyboard events, a few events at a time.
+#[derive(Debug, Clone, Default)]
+pub struct HookRunner {
+    /// Events whose hooks are running.
+    running: Arc<AtomicUsize>,
+}
+
+impl HookRunner {
+    /// Creates the hook runner.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts an event's hooks as running, unless [`MAX_RUNNING`] events'
+    /// hooks already are.
+    #[must_use]
+    pub fn admit(&self) -> Option<Running> {
+        let running = Running(self.running.clone());
+        (self.running.fetch_add(1, Ordering::AcqRel) < MAX_RUNNING).then_some(running)
+    }
+
+    /// Returns a future running the hooks of `event` in order, or `None` if
+    /// the event has none or too many hooks are running.
+    ///
+    /// D-Bus hooks are called over `connection`, or a new session bus
+    /// connection if the keyboard has none. Failures are logged.
+    pub fn dispatch(
+        &self,
+        settings: &HookSettings,
+        event: HookEvent,
+        layout: &str,
+        connection: Option<zbus::Connection>,
+    ) -> Option<impl Future<Output = ()> + Send + 'static> {
+        let actions: Vec<HookAction> = settings.actions(event).cloned().collect();
+        if actions.is_empty() {
+            return None;
+        }
+        let Some(running) = self.admit() else {
+            tracing::warn!(
+                "Skipping the {} hooks: {} events' hooks are still running",
+                event.as_str(),
+                MAX_RUNNING
+            );
+            return None;
+        };
+        let layout = layout.to_string();
+        let timeout = settings.timeout();
+        Some(async move {
+            let _running = running;
+            for action in &actions {
+                match run_hook(action, event, &layout, connection.clone(), timeout).await {
+                    Ok(()) => tracing::debug!("Ran {} hook: {}", event.as_str(), action),
+                    Err(e) => tracing::warn!("{} hook {} failed: {}", event.as_str(), action, e),
+                }
+            }
+        })
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(argv: &[&str]) -> HookAction {
+        HookAction::Command(argv.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    /// Test: Hooks are picked by event, in order, and the timeout is capped.
+    #[test]
+    fn test_settings_actions() {
+        let settings = HookSettings {
+            hooks: vec![
+                Hook {
+                    event: HookEvent::Shown,
+                    run: command(&["first"]),
+                },
+                Hook {
+                    event: HookEvent::Hidden,
+                    run: command(&["hidden"]),
+                },
+                Hook {
+                    event: HookEvent::Shown,
+                    run: command(&["second"]),
+                },
+            ],
+            timeout_ms: 600_000,
+        };
+        let shown: Vec<String> = settings
+            .actions(HookEvent::Shown)
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(shown, ["first", "second"]);
+        assert_eq!(settings.actions(HookEvent::LayoutChanged).count(), 0);
+        assert_eq!(settings.timeout(), Duration::from_millis(MAX_TIMEOUT_MS));
+    }
+
+    /// Test: Commands get their arguments expanded, the home directory and
+    /// only the passed environment variables.
+    #[test]
+    fn test_hook_command() {
+        let argv: Vec<String> = ["notify-send", "{event}: {layout}"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let env = [("HOME", "/home/me"), ("SSH_AUTH_SOCK", "/run/agent")]
+            .map(|(name, value)| (OsString::from(name), OsString::from(value)));
+        let command = hook_command(&argv, HookEvent::LayoutChanged, "AZERTY", env).unwrap();
+
+        assert_eq!(command.get_program(), "notify-send");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["layout-changed: AZERTY"]);
+        assert_eq!(command.get_current_dir(), Some("/home/me".as_ref()));
+
+        let mut names: Vec<_> = command.get_envs().map(|(name, _)| name).collect();
+        names.sort();
+        assert_eq!(names, ["COSBOARD_EVENT", "COSBOARD_LAYOUT", "HOME"]);
+
+        assert!(hook_command(&[], HookEvent::Shown, "QWERTY", []).is_none());
+    }
+
+    /// Test: A hook running too long is killed along with the programs it
+    /// started.
+    #[tokio::test]
+    async fn test_timeout_kills_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let script = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let argv = ["sh".to_string(), "-c".to_string(), script];
+
+        let result = run_command(&argv, HookEvent::Shown, "", Duration::from_millis(300)).await;
+        assert!(matches!(result, Err(HookError::TimedOut(_))));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        // Gone, or a zombie until it is reaped
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        let stat = stat.unwrap_or_default();
+        assert!(stat.is_empty() || stat.contains(") Z "), "{}", stat);
+    }
+
+    /// Test: At most MAX_RUNNING events' hooks run at once.
+    #[test]
+    fn test_runner_admit() {
+        let runner = HookRunner::new();
+        let running: Vec<Running> = (0..MAX_RUNNING).filter_map(|_| runner.admit()).collect();
+        assert_eq!(running.len(), MAX_RUNNING);
+        assert!(runner.admit().is_none());
+
+        drop(running);
+        assert!(runner.admit().is_some());
+    }
+}
@@ -17,6 +17,27 @@ pub fn init(requested_languages: &[LanguageIdentifier]) {
     }
 }
 
+/// Returns the session language from the locale environment variables.
+///
+/// Reads `LANG`, `LC_ALL` and `LC_MESSAGES` directly, avoiding the slow D-Bus
+/// call made by `DesktopLanguageRequester::requested_languages()`.
+#[must_use]
+pub fn requested_languages() -> Vec<LanguageIdentifier> {
+    let lang = std::env::var("LANG")
+        .or_else(|_| std::env::var("LC_ALL"))
+        .or_else(|_| std::env::var("LC_MESSAGES"))
+        .unwrap_or_else(|_| "en-US.UTF-8".to_string());
+
+    // Parse just the language part (e.g., "en_US.UTF-8" -> "en-US")
+    let lang_code = lang
+        .split('.')
+        .next()
+        .unwrap_or("en-US")
+        .replace('_', "-");
+
+    lang_code.parse::<LanguageIdentifier>().into_iter().collect()
+}
+
 // Get the `Localizer` to be used for localizing this library.
 #[must_use]
 pub fn localizer() -> Box<dyn Localizer> {
@@ -48,3 +69,109 @@ macro_rules! fl {
         i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id, $($args), *)
     }};
 }
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::path::Path;
+
+    /// Collects the message ids passed to `fl!` in the sources under `dir`.
+    fn used_message_ids(dir: &Path, ids: &mut BTreeSet<String>) {
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                used_message_ids(&path, ids);
+                continue;
+            }
+            if path.extension().is_none_or(|extension| extension != "rs") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            for (start, _) in source.match_indices("fl!(") {
+                let Some(rest) = source[start + 4..].trim_start().strip_prefix('"') else {
+                    continue;
+                };
+                let id: String = rest
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+                    .collect();
+                if rest[id.len()..].starts_with('"') && !id.is_empty() {
+                    ids.insert(id);
+                }
+            }
+        }
+    }
+
+    /// Reads the message ids defined in the locale directory `dir`.
+    fn defined_message_ids(dir: &Path) -> BTreeSet<String> {
+        let mut ids = BTreeSet::new();
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let source = std::fs::read_to_string(entry.path()).unwrap();
+            ids.extend(source.lines().filter_map(|line| {
+                let (id, _) = line.split_once('=')?;
+                line.starts_with(|c: char| c.is_ascii_alphabetic())
+                    .then(|| id.trim().to_string())
+            }));
+        }
+        ids
+    }
+
+    /// Test: Every message requested with `fl!` exists in the fallback language.
+    #[test]
+    fn test_fl_messages_defined() {
+        let mut used = BTreeSet::new();
+        used_message_ids(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut used,
+        );
+        assert!(!used.is_empty());
+
+        let missing: Vec<_> = used.iter().filter(|id| !LANGUAGE_LOADER.has(id)).collect();
+        assert!(
+            missing.is_empty(),
+            "Messages missing from en: {:?}",
+            missing
+        );
+    }
+
+    /// Test: Translations only define messages that exist in the fallback language.
+    #[test]
+    fn test_translations_match_fallback() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("i18n");
+        let fallback = defined_message_ids(&root.join("en"));
+        assert!(fallback.contains("uneven-rows"));
+
+        for entry in std::fs::read_dir(&root).unwrap().flatten() {
+            if entry.file_name() == "en" || !entry.path().is_dir() {
+                continue;
+            }
+            let unknown: Vec<_> = defined_message_ids(&entry.path())
+                .difference(&fallback)
+                .cloned()
+                .collect();
+            assert!(
+                unknown.is_empty(),
+                "{:?} defines messages not in en: {:?}",
+                entry.file_name(),
+                unknown
+            );
+        }
+    }
+
+    /// Test: Counted messages pick the singular or plural form.
+    #[test]
+    fn test_plural_messages() {
+        let one = crate::fl!("uneven-rows", count = 1, panel = "main", row = 2);
+        assert!(one.contains("Uneven layout row:"), "{}", one);
+
+        let many = crate::fl!("uneven-rows", count = 3, panel = "main", row = 2);
+        assert!(many.contains("uneven layout rows"), "{}", many);
+        assert!(many.contains('3'), "{}", many);
+        assert!(many.contains("main"), "{}", many);
+    }
+}
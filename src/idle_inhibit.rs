@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Keeping the screen awake while the keyboard is typed on.
+//!
+//! Entering text on a touch keyboard is slow, and the session's idle timer
+//! does not always see it as activity, so the screen can dim or lock in the
+//! middle of a sentence. With `idle_inhibit` enabled, the first key typed
+//! asks the session not to go idle, and the request is withdrawn once no
+//! key was typed for `release_after_secs` ([`IdleInhibitSettings`]) or the
+//! keyboard is hidden.
+//!
+//! The Wayland idle-inhibit protocol ties the request to a surface, and the
+//! keyboard's layer surface lives on libcosmic's Wayland connection, out of
+//! reach of the keyboard's own. The request goes to the session's
+//! `org.freedesktop.ScreenSaver` service instead, which desktop idle daemons
+//! honour. Sessions without one answer the first request with an error,
+//! which turns inhibiting off until the next start.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Well-known name of the screen saver service.
+pub const SCREENSAVER_NAME: &str = "org.freedesktop.ScreenSaver";
+
+/// Object path of the screen saver service.
+pub const SCREENSAVER_PATH: &str = "/org/freedesktop/ScreenSaver";
+
+/// Reason given to the session for keeping the screen awake.
+pub const INHIBIT_REASON: &str = "Typing on the on-screen keyboard";
+
+/// Interval at which an inhibition is checked for expiry, in seconds.
+pub const IDLE_INHIBIT_CHECK_SECS: u64 = 5;
+
+/// Whether the screen is kept awake while typing, from the user
+/// configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleInhibitSettings {
+    /// Whether typing keeps the screen awake.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Seconds without a key typed after which the screen may go idle again.
+    #[serde(default = "default_release_after_secs")]
+    pub release_after_secs: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_release_after_secs() -> u32 {
+    30
+}
+
+impl Default for IdleInhibitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            release_after_secs: default_release_after_secs(),
+        }
+    }
+}
+
+impl IdleInhibitSettings {
+    /// Returns the time without a key typed after which the screen may go
+    /// idle again.
+    #[must_use]
+    pub fn release_after(&self) -> Duration {
+        Duration::from_secs(u64::from(self.release_after_secs.max(1)))
+    }
+}
+
+/// Tracks typing and the inhibition held for it.
+///
+/// The calls to the session are made by the applet; this only decides when.
+#[derive(Debug, Clone, Default)]
+pub struct IdleInhibitor {
+    /// When the last key was typed, while the screen is to be kept awake
+    last_activity: Option<Instant>,
+    /// Cookie of the inhibition held
+    cookie: Option<u32>,
+    /// Whether an inhibition was asked for and not answered yet
+    requesting: bool,
+    /// Set when the session could not inhibit, to stop asking
+    unavailable: bool,
+}
+
+impl IdleInhibitor {
+    /// Creates a tracker holding no inhibition.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a key typed at `now`.
+    ///
+    /// Returns `true` if the applet should ask for an inhibition: none is
+    /// held or asked for yet.
+    pub fn note_activity(&mut self, now: Instant) -> bool {
+        self.last_activity = Some(now);
+        if self.unavailable || self.requesting || self.cookie.is_some() {
+            return false;
+        }
+        self.requesting = true;
+        true
+    }
+
+    /// Records the answer to an inhibition request.
+    ///
+    /// Returns the cookie to release right away if typing stopped while the
+    /// request was under way.
+    pub fn inhibited(&mut self, result: Result<u32, String>) -> Option<u32> {
+        self.requesting = false;
+        match result {
+            Ok(cookie) if self.last_activity.is_some() => {
+                self.cookie = Some(cookie);
+                None
+            }
+            Ok(cookie) => Some(cookie),
+            Err(e) => {
+                tracing::info!("Cannot keep the screen awake while typing: {}", e);
+                self.unavailable = true;
+                None
+            }
+        }
+    }
+
+    /// Returns the cookie to release if no key was typed for
+    /// `release_after` at `now`.
+    pub fn expire(&mut self, now: Instant, release_after: Duration) -> Option<u32> {
+        match self.last_activity {
+            Some(last) if now.saturating_duration_since(last) < release_after => None,
+            _ => self.stop(),
+        }
+    }
+
+    /// Stops keeping the screen awake, returning the cookie to release.
+    pub fn stop(&mut self) -> Option<u32> {
+        self.last_activity = None;
+        self.cookie.take()
+    }
+
+    /// Returns `true` while an inhibition is held.
+    #[must_use]
+    pub fn is_inhibiting(&self) -> bool {
+        self.cookie.is_some()
+    }
+}
+
+/// Asks the session not to go idle, returning the cookie of the inhibition.
+///
+/// # Errors
+///
+/// Returns the error of the call, e.g. when the session has no screen saver
+/// service.
+pub async fn inhibit(connection: zbus::Connection, app_id: &'static str) -> Result<u32, String> {
+    let proxy = screensaver(&connection).await.map_err(|e| e.to_string())?;
+    proxy
+        .call("Inhibit", &(app_id, INHIBIT_REASON))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Withdraws the inhibition `cookie`.
+pub async fn release(connection: zbus::Connection, cookie: u32) {
+    let result = match screensaver(&connection).await {
+        Ok(proxy) => proxy.call::<_, _, ()>("UnInhibit", &(cookie,)).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::debug!("Failed to release the idle inhibition: {}", e);
+    }
+}
+
+/// Returns a proxy of the screen saver service.
+async fn screensaver(connection: &zbus::Connection) -> zbus::Result<zbus::Proxy<'static>> {
+    zbus::Proxy::new(
+        connection,
+        SCREENSAVER_NAME,
+        SCREENSAVER_PATH,
+        SCREENSAVER_NAME,
+    )
+    .await
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The first key asks for an inhibition, later keys do not, and it
+    /// is released once typing stopped for long enough.
+    #[test]
+    fn test_inhibit_and_expire() {
+        let release_after = Duration::from_secs(30);
+        let start = Instant::now();
+        let mut inhibitor = IdleInhibitor::new();
+
+        assert!(inhibitor.note_activity(start));
+        assert!(!inhibitor.note_activity(start), "A request is under way");
+        assert_eq!(inhibitor.inhibited(Ok(7)), None);
+        assert!(inhibitor.is_inhibiting());
+        assert!(!inhibitor.note_activity(start + Duration::from_secs(20)));
+
+        assert_eq!(
+            inhibitor.expire(start + Duration::from_secs(40), release_after),
+            None
+        );
+        assert_eq!(
+            inhibitor.expire(start + Duration::from_secs(50), release_after),
+            Some(7)
+        );
+        assert!(!inhibitor.is_inhibiting());
+        assert!(inhibitor.note_activity(start + Duration::from_secs(60)));
+    }
+
+    /// Test: An inhibition granted after the keyboard was hidden is released
+    /// right away, and a failed request stops further ones.
+    #[test]
+    fn test_late_answer_and_failure() {
+        let now = Instant::now();
+        let mut inhibitor = IdleInhibitor::new();
+
+        assert!(inhibitor.note_activity(now));
+        assert_eq!(inhibitor.stop(), None);
+        assert_eq!(inhibitor.inhibited(Ok(3)), Some(3));
+        assert!(!inhibitor.is_inhibiting());
+
+        assert!(inhibitor.note_activity(now));
+        assert_eq!(inhibitor.inhibited(Err("no service".to_string())), None);
+        assert!(!inhibitor.note_activity(now));
+    }
+}
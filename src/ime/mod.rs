@@ -0,0 +1,349 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Input method engines.
+//!
+//! An [`ImeEngine`] turns typed keys into text: it is fed each key typed on
+//! the keyboard (and the keyboard gestures) as an [`ImeInput`], and answers
+//! with an [`ImeOutput`] saying whether it used the key and what text to
+//! commit. Between keys it may hold text being composed (the preedit) and
+//! offer candidates, shown in the candidate row above the keys (see
+//! [`candidate_row`]) and chosen with the `commit_prediction` action.
+//!
+//! Word prediction ([`Predictor`](crate::prediction::Predictor)) is an
+//! engine that never uses keys: it watches them after they are typed and
+//! only commits when a candidate is chosen. Engines for a language are
+//! chosen by the layout's `language` with [`engine_for_language`] and see
+//! keys before they are typed, so they can compose or replace them. Adding
+//! an engine means implementing the trait and listing it in [`ENGINES`].
+//!
+//! | Language | Engine                                             |
+//! |----------|----------------------------------------------------|
+//! | `vi`     | [`vietnamese`]: Telex or VNI tones and marks       |
+//! | `zh`     | [`pinyin`]: hanzi from pinyin                      |
+//! | Others   | [`transliteration`], if the language has a table   |
+//!
+//! Engines read their options from the `ime` configuration entry
+//! ([`ImeSettings`]). Transliteration tables are data files, so languages
+//! without an engine gain one by installing a table (`bn`, `hi` and `ta`
+//! are bundled).
+
+pub mod pinyin;
+pub mod transliteration;
+pub mod vietnamese;
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::PARAMETER_SEPARATOR;
+use crate::gestures::Gesture;
+use crate::input::{ResolvedKeycode, COMMAND_PREFIX};
+use crate::layout::{Cell, Key, KeyCode, Modifier, Row, Sizing};
+use crate::prediction::{candidate_key_id, Completion, CANDIDATE_KEY_PREFIX};
+use crate::storage::Storage;
+use vietnamese::VietnameseMethod;
+
+/// Number of candidates an engine shows at once in the candidate row.
+pub const PAGE_SIZE: usize = 5;
+
+/// Width of the preedit key in the candidate row, in candidate widths.
+const PREEDIT_WIDTH: f32 = 2.0;
+
+/// Width of the page keys in the candidate row, in candidate widths.
+const PAGE_KEY_WIDTH: f32 = 0.75;
+
+/// Input fed to an engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImeInput {
+    /// A character typed without shortcut modifiers (Shift applied).
+    Char(char),
+    /// BackSpace.
+    Backspace,
+    /// A key that ends the text being typed (Return, Tab, arrows, or any
+    /// key pressed with Ctrl, Alt or Super).
+    Boundary,
+    /// A keyboard gesture, before its bound action runs.
+    Gesture(Gesture),
+}
+
+impl ImeInput {
+    /// Returns the input for a key typed with `modifiers` held.
+    #[must_use]
+    pub fn from_key(resolved: &ResolvedKeycode, modifiers: &[Modifier]) -> Self {
+        let shortcut = modifiers
+            .iter()
+            .any(|modifier| *modifier != Modifier::Shift);
+        let shifted = modifiers.contains(&Modifier::Shift);
+        let typed = match resolved {
+            _ if shortcut => None,
+            ResolvedKeycode::Character(c) if shifted => Some(c.to_uppercase().next().unwrap_or(*c)),
+            ResolvedKeycode::Character(c) => Some(*c),
+            ResolvedKeycode::UnicodeCodepoint(codepoint) => char::from_u32(*codepoint),
+            ResolvedKeycode::Keysym(name) if name == "space" => Some(' '),
+            ResolvedKeycode::Keysym(_) => None,
+        };
+        match (typed, resolved) {
+            (Some(c), _) => ImeInput::Char(c),
+            (None, ResolvedKeycode::Keysym(name)) if !shortcut && name == "BackSpace" => {
+                ImeInput::Backspace
+            }
+            _ => ImeInput::Boundary,
+        }
+    }
+}
+
+/// What an engine did with an input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImeOutput {
+    /// Whether the engine used the key, which must then not be typed.
+    pub consumed: bool,
+    /// Text to commit: typed characters to delete first, then text to type.
+    pub commit: Option<Completion>,
+    /// Word the input finished, for learning.
+    pub finished: Option<String>,
+}
+
+impl ImeOutput {
+    /// Returns an output leaving the key to be typed as usual.
+    #[must_use]
+    pub fn pass() -> Self {
+        Self::default()
+    }
+
+    /// Returns an output for a key the engine used, committing nothing.
+    #[must_use]
+    pub fn consumed() -> Self {
+        Self {
+            consumed: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// An input method engine.
+pub trait ImeEngine: fmt::Debug + Send {
+    /// Returns the engine's name, for logs.
+    fn name(&self) -> &'static str;
+
+    /// Feeds a key or gesture to the engine.
+    fn feed(&mut self, input: ImeInput) -> ImeOutput;
+
+    /// Returns the text being composed, which is not typed yet.
+    fn preedit(&self) -> &str {
+        ""
+    }
+
+    /// Returns the candidates for the current input, most likely first.
+    ///
+    /// Engines with more candidates than fit in the candidate row return the
+    /// shown page of at most [`PAGE_SIZE`].
+    fn candidates(&self) -> &[String];
+
+    /// Returns the index of the shown page of candidates and the number of
+    /// pages.
+    fn page(&self) -> (usize, usize) {
+        (0, 1)
+    }
+
+    /// Shows the next page of candidates for a positive `delta`, the
+    /// previous one for a negative one. Turning past the first or last page
+    /// does nothing.
+    fn turn_page(&mut self, _delta: i32) {}
+
+    /// Commits the candidate at `index` of the shown page.
+    fn select(&mut self, index: usize) -> ImeOutput;
+
+    /// Commits the text being composed as it is, without converting it.
+    fn commit_preedit(&mut self) -> ImeOutput {
+        ImeOutput::pass()
+    }
+
+    /// Forgets the current input, e.g. after the cursor moved. Text being
+    /// composed is dropped.
+    fn reset(&mut self);
+}
+
+/// Options of the input methods, from the user configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImeSettings {
+    /// How Vietnamese tones and vowel marks are typed.
+    #[serde(default)]
+    pub vietnamese: VietnameseMethod,
+}
+
+/// Function creating an engine with the user's options.
+pub type EngineFactory = fn(&ImeSettings) -> Box<dyn ImeEngine>;
+
+/// Languages with an engine, and the function creating it.
+///
+/// Languages are ISO 639 codes, matched against the language part of the
+/// layout's `language` (`zh` for `zh_CN`).
+pub const ENGINES: &[(&str, EngineFactory)] =
+    &[("vi", vietnamese::new_engine), ("zh", pinyin::new_engine)];
+
+/// Returns the engine for a layout language, if the language has one.
+///
+/// Languages without an engine in [`ENGINES`] get a transliteration engine
+/// if a table for them is installed in `storage` or bundled.
+#[must_use]
+pub fn engine_for_language(
+    language: Option<&str>,
+    settings: &ImeSettings,
+    storage: &dyn Storage,
+) -> Option<Box<dyn ImeEngine>> {
+    let language = language?
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    ENGINES
+        .iter()
+        .find(|(code, _)| *code == language)
+        .map(|(_, new)| new(settings))
+        .or_else(|| transliteration::new_engine(storage, &language))
+}
+
+/// Returns the identifier of a key of the candidate row that is not a
+/// candidate (`prediction_preedit`, ...).
+fn row_key_id(name: &str) -> String {
+    format!("{}{}", CANDIDATE_KEY_PREFIX, name)
+}
+
+/// Returns a key of the candidate row running a command.
+fn row_key(label: String, command: &str, identifier: String, width: f32) -> Cell {
+    Cell::Key(Key {
+        label,
+        code: KeyCode::Keysym(format!("{}{}", COMMAND_PREFIX, command)),
+        identifier: Some(identifier),
+        width: Sizing::Relative(width),
+        ..Key::default()
+    })
+}
+
+/// Builds the candidate row of an engine that is composing text.
+///
+/// The row starts with the preedit, which types it unconverted when pressed
+/// (`commit_preedit`), followed by [`PAGE_SIZE`] candidate keys and keys
+/// turning the page (`page_candidates`). Like
+/// [`prediction::candidate_row`](crate::prediction::candidate_row), keys
+/// without a candidate are blank so the row keeps its layout, and the keys
+/// share `row_width` key units.
+#[must_use]
+pub fn candidate_row(engine: &dyn ImeEngine, row_width: f32) -> Row {
+    let total = PREEDIT_WIDTH + PAGE_SIZE as f32 + 2.0 * PAGE_KEY_WIDTH;
+    let unit = (row_width / total).max(0.5);
+    let candidates = engine.candidates();
+    let (page, pages) = engine.page();
+
+    let mut cells = vec![row_key(
+        engine.preedit().to_string(),
+        "commit_preedit",
+        row_key_id("preedit"),
+        PREEDIT_WIDTH * unit,
+    )];
+    cells.extend((0..PAGE_SIZE).map(|index| {
+        row_key(
+            candidates.get(index).cloned().unwrap_or_default(),
+            &format!("commit_prediction{}{}", PARAMETER_SEPARATOR, index),
+            candidate_key_id(index),
+            unit,
+        )
+    }));
+    let page_key = |label: &str, shown: bool, delta: i32, name: &str| {
+        row_key(
+            if shown {
+                label.to_string()
+            } else {
+                String::new()
+            },
+            &format!("page_candidates{}{}", PARAMETER_SEPARATOR, delta),
+            row_key_id(name),
+            PAGE_KEY_WIDTH * unit,
+        )
+    };
+    cells.push(page_key("‹", page > 0, -1, "previous"));
+    cells.push(page_key("›", page + 1 < pages, 1, "next"));
+    Row { cells }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Keys map to characters, BackSpace, or a word boundary.
+    #[test]
+    fn test_input_from_key() {
+        let a = ResolvedKeycode::Character('a');
+        let backspace = ResolvedKeycode::Keysym("BackSpace".to_string());
+        assert_eq!(ImeInput::from_key(&a, &[]), ImeInput::Char('a'));
+        assert_eq!(
+            ImeInput::from_key(&a, &[Modifier::Shift]),
+            ImeInput::Char('A')
+        );
+        assert_eq!(
+            ImeInput::from_key(&a, &[Modifier::Ctrl]),
+            ImeInput::Boundary
+        );
+        assert_eq!(ImeInput::from_key(&backspace, &[]), ImeInput::Backspace);
+        assert_eq!(
+            ImeInput::from_key(&ResolvedKeycode::Keysym("space".to_string()), &[]),
+            ImeInput::Char(' ')
+        );
+        assert_eq!(
+            ImeInput::from_key(&ResolvedKeycode::Keysym("Return".to_string()), &[]),
+            ImeInput::Boundary
+        );
+        let settings = ImeSettings::default();
+        let storage = crate::storage::NativeStorage;
+        let name = |language| {
+            engine_for_language(language, &settings, &storage).map(|engine| engine.name())
+        };
+        assert_eq!(name(Some("en_US")), None);
+        assert_eq!(name(None), None);
+        assert_eq!(name(Some("zh_CN.UTF-8")), Some("pinyin"));
+        assert_eq!(name(Some("vi")), Some("telex"));
+        assert_eq!(name(Some("hi_IN")), Some("transliteration"));
+    }
+
+    /// Test: The candidate row shows the preedit, a page of candidates and
+    /// the page keys that can be used.
+    #[test]
+    fn test_candidate_row() {
+        let mut engine = pinyin::PinyinEngine::new();
+        for c in "shi".chars() {
+            engine.feed(ImeInput::Char(c));
+        }
+
+        let row = candidate_row(&engine, 10.0);
+        assert!(crate::prediction::is_candidate_row(&row));
+        let keys: Vec<_> = row
+            .cells
+            .iter()
+            .filter_map(|cell| match cell {
+                Cell::Key(key) => Some(key),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(keys.len(), PAGE_SIZE + 3);
+        assert_eq!(keys[0].label, "shi");
+        assert_eq!(keys[1].label, "是");
+        assert_eq!(keys[PAGE_SIZE + 1].label, "");
+        assert_eq!(keys[PAGE_SIZE + 2].label, "›");
+        assert_eq!(
+            keys[PAGE_SIZE + 2].code,
+            KeyCode::Keysym("cosboard:page_candidates:1".to_string())
+        );
+        let width: f32 = keys
+            .iter()
+            .map(|key| match key.width {
+                Sizing::Relative(width) => width,
+                _ => 0.0,
+            })
+            .sum();
+        assert!((width - 10.0).abs() < 0.01);
+    }
+}
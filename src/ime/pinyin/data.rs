@@ -0,0 +1,390 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Bundled pinyin lexicon.
+//!
+//! Toneless pinyin (`v` for `ü`) and the simplified hanzi written with it,
+//! most frequent first, separated by spaces. Covers the most common
+//! characters of each syllable and a few hundred everyday words; an
+//! apostrophe separates syllables where the spelling is ambiguous
+//! (`xi'an`).
+
+/// Bundled pinyin and their candidates, most frequent first.
+pub static LEXICON: &[(&str, &str)] = &[
+    // Syllables
+    ("a", "啊 阿"),
+    ("ai", "爱 哎 矮 艾 碍"),
+    ("an", "安 按 暗 岸 案"),
+    ("ba", "把 吧 八 爸 拔 巴"),
+    ("bai", "白 百 败 拜"),
+    ("ban", "办 半 班 般 板"),
+    ("bang", "帮 棒 邦"),
+    ("bao", "报 包 保 宝 抱"),
+    ("bei", "被 北 备 背 杯"),
+    ("ben", "本 奔"),
+    ("bi", "比 必 笔 闭 鼻"),
+    ("bian", "边 变 便 遍"),
+    ("biao", "表 标"),
+    ("bie", "别"),
+    ("bing", "并 病 冰 兵"),
+    ("bu", "不 部 步 布 补"),
+    ("cai", "才 菜 采 财 猜"),
+    ("can", "参 餐 残"),
+    ("cha", "查 茶 差 插"),
+    ("chang", "长 常 场 唱 厂"),
+    ("chao", "超 朝 吵 炒"),
+    ("che", "车 彻"),
+    ("chen", "陈 沉 晨"),
+    ("cheng", "成 城 程 称 乘"),
+    ("chi", "吃 持 迟 尺"),
+    ("chu", "出 处 初 除 楚"),
+    ("chuan", "穿 传 船"),
+    ("chuang", "床 窗 创"),
+    ("chun", "春 纯"),
+    ("ci", "次 此 词 刺"),
+    ("cong", "从 聪"),
+    ("cuo", "错"),
+    ("da", "大 打 达 答"),
+    ("dai", "带 代 待 袋"),
+    ("dan", "但 单 担 蛋"),
+    ("dang", "当 党"),
+    ("dao", "到 道 倒 刀 岛"),
+    ("de", "的 得 德 地"),
+    ("deng", "等 灯 登"),
+    ("di", "地 第 弟 低 底"),
+    ("dian", "点 电 店 典"),
+    ("diao", "掉 调"),
+    ("ding", "定 顶 订"),
+    ("dong", "东 动 懂 冬"),
+    ("dou", "都 斗 豆"),
+    ("du", "读 度 独 肚"),
+    ("duan", "短 段 断"),
+    ("dui", "对 队"),
+    ("duo", "多 朵 躲"),
+    ("e", "饿 额 恶"),
+    ("er", "二 而 儿 耳"),
+    ("fa", "发 法"),
+    ("fan", "饭 反 翻 范"),
+    ("fang", "方 放 房 防"),
+    ("fei", "非 飞 费"),
+    ("fen", "分 份 粉"),
+    ("feng", "风 封 丰"),
+    ("fu", "服 父 复 附 福"),
+    ("gai", "该 改 概"),
+    ("gan", "感 干 敢 赶"),
+    ("gang", "刚 钢"),
+    ("gao", "高 告 搞"),
+    ("ge", "个 哥 各 歌"),
+    ("gei", "给"),
+    ("gen", "跟 根"),
+    ("geng", "更"),
+    ("gong", "工 公 共 功"),
+    ("gou", "够 狗 构"),
+    ("gu", "古 故 顾 姑"),
+    ("gua", "挂 瓜"),
+    ("guan", "关 管 观 馆"),
+    ("guang", "光 广"),
+    ("gui", "贵 规 鬼"),
+    ("guo", "国 过 果 锅"),
+    ("hai", "还 海 孩 害"),
+    ("han", "汉 喊 寒"),
+    ("hang", "行 航"),
+    ("hao", "好 号 毫"),
+    ("he", "和 喝 合 河"),
+    ("hei", "黑"),
+    ("hen", "很 恨"),
+    ("hong", "红 洪"),
+    ("hou", "后 候 厚"),
+    ("hu", "户 湖 护 互 呼"),
+    ("hua", "话 花 化 画 华"),
+    ("huan", "换 欢 环 还"),
+    ("huang", "黄 皇"),
+    ("hui", "会 回 灰"),
+    ("huo", "或 火 活 货"),
+    ("ji", "及 机 几 记 级"),
+    ("jia", "家 加 价 假"),
+    ("jian", "见 间 件 建 简"),
+    ("jiang", "将 讲 江"),
+    ("jiao", "叫 教 交 脚"),
+    ("jie", "姐 接 结 节 界"),
+    ("jin", "进 今 近 金 紧"),
+    ("jing", "经 京 静 精"),
+    ("jiu", "就 九 久 酒"),
+    ("ju", "句 局 据 举"),
+    ("jue", "觉 决"),
+    ("kai", "开"),
+    ("kan", "看"),
+    ("kao", "考 靠"),
+    ("ke", "可 课 客 科"),
+    ("kong", "空 控"),
+    ("kou", "口"),
+    ("kuai", "快 块"),
+    ("lai", "来"),
+    ("lan", "蓝 兰"),
+    ("lao", "老"),
+    ("le", "了 乐"),
+    ("lei", "累 类"),
+    ("leng", "冷"),
+    ("li", "里 理 力 李 离"),
+    ("lian", "连 脸 练"),
+    ("liang", "两 亮 量"),
+    ("liao", "聊 了"),
+    ("lin", "林"),
+    ("ling", "另 领 零"),
+    ("liu", "六 流 留"),
+    ("long", "龙"),
+    ("lu", "路 录 陆"),
+    ("lv", "绿 旅 律"),
+    ("ma", "吗 妈 马 码"),
+    ("mai", "买 卖"),
+    ("man", "慢 满"),
+    ("mang", "忙"),
+    ("mao", "毛 猫 帽"),
+    ("me", "么"),
+    ("mei", "没 美 每 妹"),
+    ("men", "们 门"),
+    ("mi", "米 密"),
+    ("mian", "面"),
+    ("min", "民"),
+    ("ming", "名 明"),
+    ("mu", "目 母 木"),
+    ("na", "那 拿 哪"),
+    ("nai", "奶"),
+    ("nan", "南 男 难"),
+    ("nao", "脑"),
+    ("ne", "呢"),
+    ("nei", "内"),
+    ("neng", "能"),
+    ("ni", "你 泥 尼"),
+    ("nian", "年 念"),
+    ("niu", "牛"),
+    ("nong", "农"),
+    ("nv", "女"),
+    ("pa", "怕 爬"),
+    ("pai", "排"),
+    ("pang", "旁"),
+    ("pao", "跑"),
+    ("peng", "朋"),
+    ("pi", "皮"),
+    ("pian", "片 篇"),
+    ("piao", "票 漂"),
+    ("ping", "平 苹"),
+    ("qi", "起 气 期 七 其"),
+    ("qian", "前 钱 千"),
+    ("qiang", "强"),
+    ("qiao", "桥"),
+    ("qie", "且"),
+    ("qin", "亲"),
+    ("qing", "请 清 情 青"),
+    ("qiu", "秋 求 球"),
+    ("qu", "去 取 区"),
+    ("quan", "全"),
+    ("que", "却 确"),
+    ("ran", "然"),
+    ("rang", "让"),
+    ("re", "热"),
+    ("ren", "人 认"),
+    ("ri", "日"),
+    ("rong", "容"),
+    ("rou", "肉"),
+    ("ru", "如 入"),
+    ("san", "三"),
+    ("se", "色"),
+    ("shan", "山"),
+    ("shang", "上 商"),
+    ("shao", "少"),
+    ("she", "社 设"),
+    ("shei", "谁"),
+    ("shen", "什 身 深 神"),
+    ("sheng", "生 声 省"),
+    ("shi", "是 时 事 十 世 市 使 实"),
+    ("shou", "手 收 首"),
+    ("shu", "书 数 树"),
+    ("shui", "水 睡 谁"),
+    ("shuo", "说"),
+    ("si", "四 死 思 司"),
+    ("song", "送"),
+    ("su", "速"),
+    ("suan", "算"),
+    ("sui", "岁 虽"),
+    ("suo", "所"),
+    ("ta", "他 她 它"),
+    ("tai", "太 台"),
+    ("tan", "谈"),
+    ("tang", "糖"),
+    ("tao", "讨"),
+    ("te", "特"),
+    ("ti", "体 题 提"),
+    ("tian", "天 田"),
+    ("tiao", "条 跳"),
+    ("ting", "听 停"),
+    ("tong", "同 通"),
+    ("tou", "头"),
+    ("tu", "图"),
+    ("tui", "退"),
+    ("wai", "外"),
+    ("wan", "完 晚 玩 万"),
+    ("wang", "往 望 王 忘 网"),
+    ("wei", "为 位 未 喂"),
+    ("wen", "问 文"),
+    ("wo", "我"),
+    ("wu", "五 无 午 物"),
+    ("xi", "西 喜 系 洗"),
+    ("xia", "下 夏"),
+    ("xian", "先 现 线"),
+    ("xiang", "想 向 像 香"),
+    ("xiao", "小 笑 校"),
+    ("xie", "写 谢 些"),
+    ("xin", "新 心 信"),
+    ("xing", "行 姓 星"),
+    ("xiu", "休"),
+    ("xu", "需 许"),
+    ("xue", "学 雪"),
+    ("ya", "呀 压"),
+    ("yan", "眼 颜 言"),
+    ("yang", "样 羊"),
+    ("yao", "要 药"),
+    ("ye", "也 业 夜 页"),
+    ("yi", "一 以 已 意 衣 医"),
+    ("yin", "因 音 银"),
+    ("ying", "应 英 影"),
+    ("yong", "用"),
+    ("you", "有 又 友 由"),
+    ("yu", "与 语 雨 鱼 于"),
+    ("yuan", "元 员 远 园"),
+    ("yue", "月 越"),
+    ("yun", "运 云"),
+    ("zai", "在 再"),
+    ("zao", "早"),
+    ("ze", "则"),
+    ("zen", "怎"),
+    ("zeng", "增"),
+    ("zhan", "站 战"),
+    ("zhang", "张 长"),
+    ("zhao", "找 照"),
+    ("zhe", "这 着"),
+    ("zhen", "真"),
+    ("zheng", "正 整"),
+    ("zhi", "只 知 之 直 指"),
+    ("zhong", "中 种 重"),
+    ("zhou", "周"),
+    ("zhu", "住 主 注"),
+    ("zhuan", "转"),
+    ("zhun", "准"),
+    ("zi", "字 自 子"),
+    ("zong", "总"),
+    ("zou", "走"),
+    ("zui", "最"),
+    ("zuo", "做 坐 作 昨 左"),
+    // Words
+    ("baba", "爸爸"),
+    ("beijing", "北京"),
+    ("bukeqi", "不客气"),
+    ("buyong", "不用"),
+    ("chifan", "吃饭"),
+    ("dajia", "大家"),
+    ("danshi", "但是"),
+    ("dianhua", "电话"),
+    ("diannao", "电脑"),
+    ("dianying", "电影"),
+    ("didi", "弟弟"),
+    ("difang", "地方"),
+    ("dongxi", "东西"),
+    ("duibuqi", "对不起"),
+    ("duoshao", "多少"),
+    ("fangjian", "房间"),
+    ("feichang", "非常"),
+    ("fenzhong", "分钟"),
+    ("gege", "哥哥"),
+    ("gongzuo", "工作"),
+    ("haishi", "还是"),
+    ("haizi", "孩子"),
+    ("hanyu", "汉语"),
+    ("haode", "好的"),
+    ("huanying", "欢迎"),
+    ("huijia", "回家"),
+    ("jidian", "几点"),
+    ("jiejie", "姐姐"),
+    ("jintian", "今天"),
+    ("juede", "觉得"),
+    ("kafei", "咖啡"),
+    ("kaishi", "开始"),
+    ("keneng", "可能"),
+    ("keshi", "可是"),
+    ("keyi", "可以"),
+    ("kuaile", "快乐"),
+    ("laoshi", "老师"),
+    ("lvse", "绿色"),
+    ("mama", "妈妈"),
+    ("meiguanxi", "没关系"),
+    ("meimei", "妹妹"),
+    ("meiyou", "没有"),
+    ("mingtian", "明天"),
+    ("mingzi", "名字"),
+    ("nage", "那个"),
+    ("nihao", "你好"),
+    ("nimen", "你们"),
+    ("nvren", "女人"),
+    ("pengyou", "朋友"),
+    ("qingwen", "请问"),
+    ("ranhou", "然后"),
+    ("renmin", "人民"),
+    ("renshi", "认识"),
+    ("ruguo", "如果"),
+    ("shangban", "上班"),
+    ("shangdian", "商店"),
+    ("shanghai", "上海"),
+    ("shangwu", "上午"),
+    ("shengri", "生日"),
+    ("shenme", "什么"),
+    ("shenti", "身体"),
+    ("shijian", "时间 事件 实践"),
+    ("shijie", "世界"),
+    ("shouji", "手机"),
+    ("shuijiao", "睡觉"),
+    ("suoyi", "所以"),
+    ("tamen", "他们 她们"),
+    ("tongxue", "同学"),
+    ("wanshang", "晚上"),
+    ("weishenme", "为什么"),
+    ("weixin", "微信"),
+    ("wenti", "问题"),
+    ("women", "我们"),
+    ("xiaban", "下班"),
+    ("xianzai", "现在"),
+    ("xiaoshi", "小时"),
+    ("xiawu", "下午"),
+    ("xiexie", "谢谢"),
+    ("xi'an", "西安"),
+    ("xihuan", "喜欢"),
+    ("xingqi", "星期"),
+    ("xinxi", "信息"),
+    ("xuesheng", "学生"),
+    ("xuexiao", "学校"),
+    ("xuyao", "需要"),
+    ("yidian", "一点"),
+    ("yige", "一个"),
+    ("yihou", "以后"),
+    ("yijing", "已经"),
+    ("yinggai", "应该"),
+    ("yinwei", "因为"),
+    ("yinyue", "音乐"),
+    ("yiqi", "一起"),
+    ("yiqian", "以前"),
+    ("yisheng", "医生"),
+    ("yisi", "意思"),
+    ("yiyang", "一样"),
+    ("yiyuan", "医院"),
+    ("zaijian", "再见"),
+    ("zaoshang", "早上"),
+    ("zenme", "怎么"),
+    ("zenmeyang", "怎么样"),
+    ("zhege", "这个"),
+    ("zhende", "真的"),
+    ("zhidao", "知道"),
+    ("zhongguo", "中国"),
+    ("zhongwen", "中文"),
+    ("zhongwu", "中午"),
+    ("zhunbei", "准备"),
+    ("ziji", "自己"),
+    ("zuotian", "昨天"),
+];
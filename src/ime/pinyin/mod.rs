@@ -0,0 +1,351 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Pinyin input method.
+//!
+//! Letters typed on a layout whose `language` is Chinese (`zh`) are not
+//! typed: they are collected as pinyin (the preedit, shown at the start of
+//! the candidate row) and converted to hanzi from the bundled [`LEXICON`].
+//! Candidates are shown [`PAGE_SIZE`] at a time in the candidate row, with
+//! keys turning the page.
+//!
+//! While pinyin is being composed:
+//!
+//! | Key                  | Effect                                          |
+//! |----------------------|-------------------------------------------------|
+//! | `a`–`z`              | Extend the pinyin (`v` types `ü`)               |
+//! | `'`                  | Separate syllables (`xi'an`)                    |
+//! | Space                | Type the first candidate                        |
+//! | `1`–`5`              | Type the candidate with that number on the page |
+//! | `-`, `=`             | Previous and next page                          |
+//! | BackSpace            | Delete the last letter                          |
+//! | Anything else        | Type the pinyin as it is, then the key          |
+//!
+//! Candidates for the whole pinyin come first, followed by candidates for
+//! shorter and shorter beginnings of it. Choosing one of those types it and
+//! leaves the rest of the pinyin to convert: `nihao` offers `你好`, then
+//! `你`, and after `你` the pinyin left is `hao`. Pinyin that is not a
+//! syllable yet (`zh`) offers the words it starts.
+
+mod data;
+
+pub use data::LEXICON;
+
+use crate::ime::{ImeEngine, ImeInput, ImeOutput, ImeSettings, PAGE_SIZE};
+use crate::prediction::Completion;
+
+/// Character separating syllables in the pinyin.
+pub const SYLLABLE_SEPARATOR: char = '\'';
+
+/// Key showing the previous page of candidates while composing.
+pub const PREVIOUS_PAGE_KEY: char = '-';
+
+/// Key showing the next page of candidates while composing.
+pub const NEXT_PAGE_KEY: char = '=';
+
+/// Returns the candidates of a pinyin in the lexicon, most frequent first
+/// and separated by spaces.
+fn lookup(pinyin: &str) -> Option<&'static str> {
+    LEXICON
+        .iter()
+        .find(|(key, _)| *key == pinyin)
+        .map(|(_, words)| *words)
+}
+
+/// Pinyin input method engine.
+#[derive(Debug, Clone, Default)]
+pub struct PinyinEngine {
+    /// Pinyin typed so far
+    input: String,
+    /// Candidates for the pinyin, most likely first
+    words: Vec<String>,
+    /// Number of pinyin characters each candidate converts
+    lengths: Vec<usize>,
+    /// Index of the shown page of candidates
+    page: usize,
+}
+
+impl PinyinEngine {
+    /// Creates an engine with no pinyin typed.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finds the candidates for the pinyin and shows their first page.
+    fn update_candidates(&mut self) {
+        self.words.clear();
+        self.lengths.clear();
+        self.page = 0;
+
+        // The whole pinyin, then shorter and shorter beginnings of it
+        for end in (1..=self.input.len()).rev() {
+            let pinyin = &self.input[..end];
+            if pinyin.ends_with(SYLLABLE_SEPARATOR) {
+                continue;
+            }
+            if let Some(words) = lookup(pinyin) {
+                for word in words.split(' ') {
+                    self.push_candidate(word, end);
+                }
+            }
+        }
+
+        if self.words.is_empty() && !self.input.is_empty() {
+            // An unfinished syllable: offer the words it starts
+            let input = self.input.as_str();
+            let words: Vec<&str> = LEXICON
+                .iter()
+                .filter(|(key, _)| key.starts_with(input))
+                .flat_map(|(_, words)| words.split(' '))
+                .collect();
+            for word in words {
+                self.push_candidate(word, self.input.len());
+            }
+        }
+    }
+
+    /// Adds a candidate converting `length` pinyin characters, unless it is
+    /// already offered.
+    fn push_candidate(&mut self, word: &str, length: usize) {
+        if !self.words.iter().any(|candidate| candidate == word) {
+            self.words.push(word.to_string());
+            self.lengths.push(length);
+        }
+    }
+
+    /// Returns the number of pages of candidates.
+    fn page_count(&self) -> usize {
+        self.words.len().div_ceil(PAGE_SIZE).max(1)
+    }
+}
+
+impl ImeEngine for PinyinEngine {
+    fn name(&self) -> &'static str {
+        "pinyin"
+    }
+
+    fn feed(&mut self, input: ImeInput) -> ImeOutput {
+        match input {
+            ImeInput::Char(c @ 'a'..='z') => {
+                self.input.push(c);
+                self.update_candidates();
+                ImeOutput::consumed()
+            }
+            // Nothing is being composed: type keys as usual
+            _ if self.input.is_empty() => ImeOutput::pass(),
+            ImeInput::Char(SYLLABLE_SEPARATOR) => {
+                if !self.input.ends_with(SYLLABLE_SEPARATOR) {
+                    self.input.push(SYLLABLE_SEPARATOR);
+                }
+                ImeOutput::consumed()
+            }
+            ImeInput::Char(' ') if self.words.is_empty() => ImeOutput {
+                consumed: true,
+                ..self.commit_preedit()
+            },
+            ImeInput::Char(' ') => self.select(0),
+            ImeInput::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index < self.candidates().len() {
+                    self.select(index)
+                } else {
+                    ImeOutput::consumed()
+                }
+            }
+            ImeInput::Char(PREVIOUS_PAGE_KEY) => {
+                self.turn_page(-1);
+                ImeOutput::consumed()
+            }
+            ImeInput::Char(NEXT_PAGE_KEY) => {
+                self.turn_page(1);
+                ImeOutput::consumed()
+            }
+            ImeInput::Backspace => {
+                self.input.pop();
+                self.update_candidates();
+                ImeOutput::consumed()
+            }
+            ImeInput::Char(_) | ImeInput::Boundary => self.commit_preedit(),
+            ImeInput::Gesture(_) => ImeOutput::pass(),
+        }
+    }
+
+    fn preedit(&self) -> &str {
+        &self.input
+    }
+
+    fn candidates(&self) -> &[String] {
+        let start = (self.page * PAGE_SIZE).min(self.words.len());
+        let end = (start + PAGE_SIZE).min(self.words.len());
+        &self.words[start..end]
+    }
+
+    fn page(&self) -> (usize, usize) {
+        (self.page, self.page_count())
+    }
+
+    fn turn_page(&mut self, delta: i32) {
+        let page = self.page as i64 + i64::from(delta);
+        if (0..self.page_count() as i64).contains(&page) {
+            self.page = page as usize;
+        }
+    }
+
+    fn select(&mut self, index: usize) -> ImeOutput {
+        let index = self.page * PAGE_SIZE + index;
+        let (Some(word), Some(&length)) = (self.words.get(index), self.lengths.get(index)) else {
+            return ImeOutput::consumed();
+        };
+        let text = word.clone();
+
+        // The rest of the pinyin stays to be converted
+        self.input = self.input[length..]
+            .trim_start_matches(SYLLABLE_SEPARATOR)
+            .to_string();
+        self.update_candidates();
+        ImeOutput {
+            consumed: true,
+            commit: Some(Completion {
+                backspaces: 0,
+                text,
+            }),
+            finished: None,
+        }
+    }
+
+    fn commit_preedit(&mut self) -> ImeOutput {
+        if self.input.is_empty() {
+            return ImeOutput::pass();
+        }
+        let text = std::mem::take(&mut self.input);
+        self.update_candidates();
+        ImeOutput {
+            commit: Some(Completion {
+                backspaces: 0,
+                text,
+            }),
+            ..ImeOutput::pass()
+        }
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+        self.update_candidates();
+    }
+}
+
+/// Creates a pinyin engine, for [`ENGINES`](crate::ime::ENGINES).
+#[must_use]
+pub fn new_engine(_settings: &ImeSettings) -> Box<dyn ImeEngine> {
+    Box::new(PinyinEngine::new())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_str(engine: &mut PinyinEngine, text: &str) {
+        for c in text.chars() {
+            assert!(engine.feed(ImeInput::Char(c)).consumed);
+        }
+    }
+
+    fn committed(output: ImeOutput) -> Option<String> {
+        output.commit.map(|commit| commit.text)
+    }
+
+    /// Test: Letters compose pinyin, and candidates convert all of it or
+    /// its beginning.
+    #[test]
+    fn test_compose() {
+        let mut engine = PinyinEngine::new();
+        assert_eq!(engine.feed(ImeInput::Char('A')), ImeOutput::pass());
+
+        type_str(&mut engine, "nihao");
+        assert_eq!(engine.preedit(), "nihao");
+        assert_eq!(engine.candidates(), ["你好", "你", "泥", "尼"]);
+
+        // A syllable converted on its own leaves the rest to convert
+        assert_eq!(committed(engine.select(1)).as_deref(), Some("你"));
+        assert_eq!(engine.preedit(), "hao");
+        assert_eq!(
+            committed(engine.feed(ImeInput::Char(' '))).as_deref(),
+            Some("好")
+        );
+        assert_eq!(engine.preedit(), "");
+
+        type_str(&mut engine, "xi'an");
+        assert_eq!(engine.candidates()[0], "西安");
+        type_str(&mut engine, "'");
+        assert_eq!(engine.preedit(), "xi'an'");
+
+        // Unfinished syllables offer the words they start
+        engine.reset();
+        type_str(&mut engine, "zh");
+        assert_eq!(engine.candidates()[0], "站");
+        assert!(engine.feed(ImeInput::Backspace).consumed);
+        assert_eq!(engine.preedit(), "z");
+    }
+
+    /// Test: Candidates are paged, and digits pick from the shown page.
+    #[test]
+    fn test_pages() {
+        let mut engine = PinyinEngine::new();
+        type_str(&mut engine, "shi");
+        assert_eq!(engine.page(), (0, 2));
+        assert_eq!(engine.candidates().len(), PAGE_SIZE);
+
+        type_str(&mut engine, "=");
+        assert_eq!(engine.page(), (1, 2));
+        assert_eq!(engine.candidates(), ["市", "使", "实"]);
+        engine.turn_page(1);
+        assert_eq!(engine.page(), (1, 2));
+
+        // Digits past the page are ignored
+        type_str(&mut engine, "4");
+        assert_eq!(engine.preedit(), "shi");
+        assert_eq!(
+            committed(engine.feed(ImeInput::Char('2'))).as_deref(),
+            Some("使")
+        );
+        assert_eq!(engine.page(), (0, 1));
+    }
+
+    /// Test: Other keys type the pinyin as it is and are typed after it.
+    #[test]
+    fn test_commit_preedit() {
+        let mut engine = PinyinEngine::new();
+        type_str(&mut engine, "qqq");
+        assert!(engine.candidates().is_empty());
+
+        let output = engine.feed(ImeInput::Char('，'));
+        assert!(!output.consumed);
+        assert_eq!(committed(output).as_deref(), Some("qqq"));
+        assert_eq!(engine.preedit(), "");
+
+        type_str(&mut engine, "qqq ");
+        assert_eq!(engine.preedit(), "");
+        assert_eq!(engine.feed(ImeInput::Boundary), ImeOutput::pass());
+    }
+
+    /// Test: The bundled pinyin layout parses cleanly and loads this engine.
+    #[test]
+    fn test_pinyin_layout() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/layouts/pinyin.json");
+        let result = crate::layout::parse_layout_file(path.to_str().unwrap())
+            .expect("pinyin layout should parse");
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+        let engine = crate::ime::engine_for_language(
+            result.layout.language.as_deref(),
+            &crate::ime::ImeSettings::default(),
+            &crate::storage::NativeStorage,
+        );
+        assert_eq!(engine.map(|engine| engine.name()), Some("pinyin"));
+    }
+}
@@ -0,0 +1,675 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Rule-based transliteration for Indic scripts.
+//!
+//! Letters typed on a layout whose `language` has a transliteration table
+//! are collected as Latin text (the preedit) and converted to the table's
+//! script, ITRANS style: `namaste` becomes `नमस्ते`. Latin letters that
+//! stand for several letters of the script (`n` for `न` or `ण`) make the
+//! conversion ambiguous; every reading is offered in the candidate row,
+//! the table's first choices first.
+//!
+//! While a word is being typed:
+//!
+//! | Key                  | Effect                                          |
+//! |----------------------|-------------------------------------------------|
+//! | Letters              | Extend the word                                 |
+//! | Punctuation of rules | Extend the word (`.n`, `~N`, `\|`)              |
+//! | Space                | Type the first candidate, then the space        |
+//! | `1`–`5`              | Type the candidate with that number on the page |
+//! | BackSpace            | Delete the last letter                          |
+//! | Anything else        | Type the first candidate, then the key          |
+//!
+//! # Rule tables
+//!
+//! Tables are text files named after the language (`hi.translit`), looked
+//! up in:
+//!
+//! 1. `$XDG_DATA_HOME/cosboard/transliteration` (`~/.local/share/cosboard/transliteration`)
+//! 2. `cosboard/transliteration` under each system data directory
+//! 3. The tables bundled with Cosboard ([`BUNDLED`]): Hindi (`hi`),
+//!    Bengali (`bn`) and Tamil (`ta`)
+//!
+//! A table in a higher directory replaces the ones further down, so a
+//! language is added or corrected by dropping a file in place:
+//!
+//! ```text
+//! # Comments start with #
+//! name = Devanagari
+//! virama = ्
+//! final_virama = no
+//!
+//! [vowels]
+//! # key  letter  sign (none for the inherent vowel)
+//! a      अ
+//! aa     आ       ा
+//!
+//! [consonants]
+//! k      क
+//! n      न|ण
+//!
+//! [other]
+//! M      ं
+//! ```
+//!
+//! Keys are the Latin spellings; the longest key matching the input wins.
+//! A vowel after a consonant is written with its sign, elsewhere with its
+//! letter. Consonants following each other are joined with the `virama`,
+//! which also ends a word ending in a consonant if `final_virama` is `yes`.
+//! Alternatives separated by `|` are offered as candidates (a vowel lists
+//! as many signs as letters). Keys are made of letters and punctuation:
+//! digits choose candidates.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::ime::{ImeEngine, ImeInput, ImeOutput, PAGE_SIZE};
+use crate::prediction::Completion;
+use crate::storage::Storage;
+
+/// Directory under the data directories holding transliteration tables.
+pub const TRANSLITERATION_DIR: &str = "cosboard/transliteration";
+
+/// Extension of transliteration table files.
+pub const TABLE_EXTENSION: &str = "translit";
+
+/// Maximum number of candidates offered for a word.
+pub const MAX_CANDIDATES: usize = 20;
+
+/// Maximum number of readings of an ambiguous word that are tried.
+const MAX_READINGS: usize = 256;
+
+/// Tables bundled with Cosboard, by language.
+pub static BUNDLED: &[(&str, &str)] = &[
+    (
+        "bn",
+        include_str!("../../resources/transliteration/bn.translit"),
+    ),
+    (
+        "hi",
+        include_str!("../../resources/transliteration/hi.translit"),
+    ),
+    (
+        "ta",
+        include_str!("../../resources/transliteration/ta.translit"),
+    ),
+];
+
+/// Error in a transliteration table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableError {
+    /// Line of the error, starting at 1.
+    pub line: usize,
+    /// What is wrong with the line.
+    pub message: String,
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TableError {}
+
+/// What a rule writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RuleKind {
+    /// A vowel: its letters, and the signs written after a consonant.
+    Vowel(Vec<String>),
+    /// A consonant, carrying the inherent vowel until another letter follows.
+    Consonant,
+    /// A sign or punctuation written as it is.
+    Other,
+}
+
+/// A Latin spelling and what it is written as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    kind: RuleKind,
+    /// Alternatives, the usual one first (the letters of a vowel)
+    outputs: Vec<String>,
+}
+
+/// A part of the typed word.
+#[derive(Debug, Clone, Copy)]
+enum Segment<'a> {
+    /// Letters matching a rule
+    Rule(&'a Rule),
+    /// A character no rule matches, kept as it is
+    Literal(char),
+}
+
+/// A transliteration table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleTable {
+    /// Name of the script, for logs.
+    pub name: String,
+    /// Sign joining consonants.
+    virama: String,
+    /// Whether a word ending in a consonant ends with the virama.
+    final_virama: bool,
+    /// Rules by Latin spelling
+    rules: HashMap<String, Rule>,
+    /// Length of the longest spelling, in characters
+    longest: usize,
+    /// Characters used in spellings
+    alphabet: HashSet<char>,
+}
+
+impl RuleTable {
+    /// Parses a table (see the module docs).
+    ///
+    /// # Errors
+    ///
+    /// Returns the first malformed line: an unknown setting or section, a
+    /// rule outside a section or without output, or a vowel with a different
+    /// number of letters and signs.
+    pub fn parse(text: &str) -> Result<Self, TableError> {
+        let mut table = RuleTable::default();
+        let mut section: Option<&str> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let error = |message: String| TableError {
+                line: index + 1,
+                message,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                match name.trim() {
+                    name @ ("vowels" | "consonants" | "other") => section = Some(name),
+                    name => return Err(error(format!("unknown section [{}]", name))),
+                }
+                continue;
+            }
+
+            let Some(section) = section else {
+                let Some((setting, value)) = line.split_once('=') else {
+                    return Err(error("expected a setting or a section".to_string()));
+                };
+                let value = value.trim().to_string();
+                match setting.trim() {
+                    "name" => table.name = value,
+                    "virama" => table.virama = value,
+                    "final_virama" => table.final_virama = matches!(value.as_str(), "yes" | "true"),
+                    setting => return Err(error(format!("unknown setting {}", setting))),
+                }
+                continue;
+            };
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [key, output, rest @ ..] = fields.as_slice() else {
+                return Err(error("rule without output".to_string()));
+            };
+            let split =
+                |field: &str| -> Vec<String> { field.split('|').map(str::to_string).collect() };
+            let outputs = split(output);
+            let kind = match section {
+                "vowels" => {
+                    let signs = rest
+                        .first()
+                        .map_or_else(|| vec![String::new(); outputs.len()], |signs| split(signs));
+                    if signs.len() != outputs.len() {
+                        return Err(error(format!(
+                            "vowel {} has {} letters but {} signs",
+                            key,
+                            outputs.len(),
+                            signs.len()
+                        )));
+                    }
+                    RuleKind::Vowel(signs)
+                }
+                "consonants" => RuleKind::Consonant,
+                _ => RuleKind::Other,
+            };
+
+            table.longest = table.longest.max(key.chars().count());
+            table.alphabet.extend(key.chars());
+            table.rules.insert(key.to_string(), Rule { kind, outputs });
+        }
+        Ok(table)
+    }
+
+    /// Returns whether `c` is used in the spellings of the table.
+    #[must_use]
+    pub fn uses(&self, c: char) -> bool {
+        self.alphabet.contains(&c)
+    }
+
+    /// Splits a word into its longest matching spellings.
+    fn segments(&self, word: &str) -> Vec<Segment<'_>> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut segments = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let longest = self.longest.min(chars.len() - start);
+            let found = (1..=longest).rev().find_map(|length| {
+                let key: String = chars[start..start + length].iter().collect();
+                self.rules.get(&key).map(|rule| (rule, length))
+            });
+            match found {
+                Some((rule, length)) => {
+                    segments.push(Segment::Rule(rule));
+                    start += length;
+                }
+                None => {
+                    segments.push(Segment::Literal(chars[start]));
+                    start += 1;
+                }
+            }
+        }
+        segments
+    }
+
+    /// Writes segments with the alternative `choices[i]` for the i-th one.
+    fn write(&self, segments: &[Segment<'_>], choices: &[usize]) -> String {
+        let mut text = String::new();
+        // Whether the last letter is a consonant with its inherent vowel
+        let mut consonant = false;
+        for (segment, &choice) in segments.iter().zip(choices) {
+            let rule = match segment {
+                Segment::Rule(rule) => rule,
+                Segment::Literal(c) => {
+                    text.push(*c);
+                    consonant = false;
+                    continue;
+                }
+            };
+            let output = &rule.outputs[choice];
+            match &rule.kind {
+                RuleKind::Vowel(signs) if consonant => text.push_str(&signs[choice]),
+                RuleKind::Vowel(_) | RuleKind::Other => text.push_str(output),
+                RuleKind::Consonant => {
+                    if consonant {
+                        text.push_str(&self.virama);
+                    }
+                    text.push_str(output);
+                }
+            }
+            consonant = rule.kind == RuleKind::Consonant;
+        }
+        if consonant && self.final_virama {
+            text.push_str(&self.virama);
+        }
+        text
+    }
+
+    /// Returns the readings of a word, the table's first choices first and
+    /// readings using fewer alternatives before others, up to
+    /// [`MAX_CANDIDATES`].
+    #[must_use]
+    pub fn transliterate(&self, word: &str) -> Vec<String> {
+        let segments = self.segments(word);
+        let counts: Vec<usize> = segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Rule(rule) => rule.outputs.len(),
+                Segment::Literal(_) => 1,
+            })
+            .collect();
+
+        // Count through the alternatives of every segment, last one fastest
+        let mut readings: Vec<(usize, String)> = Vec::new();
+        let mut choices = vec![0; segments.len()];
+        loop {
+            let alternatives = choices.iter().filter(|&&choice| choice > 0).count();
+            readings.push((alternatives, self.write(&segments, &choices)));
+            if readings.len() == MAX_READINGS {
+                break;
+            }
+            let Some(index) = (0..choices.len())
+                .rev()
+                .find(|&index| choices[index] + 1 < counts[index])
+            else {
+                break;
+            };
+            choices[index] += 1;
+            choices[index + 1..].fill(0);
+        }
+
+        readings.sort_by_key(|(alternatives, _)| *alternatives);
+        let mut words: Vec<String> = Vec::new();
+        for (_, reading) in readings {
+            if !words.contains(&reading) {
+                words.push(reading);
+            }
+        }
+        words.truncate(MAX_CANDIDATES);
+        words
+    }
+}
+
+/// Returns the directories searched for tables, highest precedence first.
+#[must_use]
+pub fn search_dirs(storage: &dyn Storage) -> Vec<PathBuf> {
+    storage
+        .user_data_dir()
+        .into_iter()
+        .chain(storage.system_data_dirs())
+        .map(|dir| dir.join(TRANSLITERATION_DIR))
+        .collect()
+}
+
+/// Loads the table of a language: the first installed table that parses,
+/// or the bundled one.
+///
+/// Tables that cannot be read or parsed are logged and skipped.
+#[must_use]
+pub fn load_table(storage: &dyn Storage, language: &str) -> Option<RuleTable> {
+    let file = format!("{}.{}", language, TABLE_EXTENSION);
+    for path in search_dirs(storage).into_iter().map(|dir| dir.join(&file)) {
+        if !storage.is_file(&path) {
+            continue;
+        }
+        let table = storage
+            .read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| RuleTable::parse(&text).map_err(|e| e.to_string()));
+        match table {
+            Ok(table) => {
+                tracing::info!("Loaded transliteration table {}", path.display());
+                return Some(table);
+            }
+            Err(e) => tracing::warn!("Skipping transliteration table {}: {}", path.display(), e),
+        }
+    }
+
+    let (_, text) = BUNDLED.iter().find(|(code, _)| *code == language)?;
+    match RuleTable::parse(text) {
+        Ok(table) => Some(table),
+        Err(e) => {
+            tracing::error!(
+                "Bundled transliteration table {} is invalid: {}",
+                language,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Transliteration input method engine.
+#[derive(Debug, Clone)]
+pub struct TransliterationEngine {
+    table: RuleTable,
+    /// Latin text typed so far
+    input: String,
+    /// Readings of the input, most likely first
+    words: Vec<String>,
+    /// Index of the shown page of candidates
+    page: usize,
+}
+
+impl TransliterationEngine {
+    /// Creates an engine converting with `table`.
+    #[must_use]
+    pub fn new(table: RuleTable) -> Self {
+        Self {
+            table,
+            input: String::new(),
+            words: Vec::new(),
+            page: 0,
+        }
+    }
+
+    /// Returns the table the engine converts with.
+    #[must_use]
+    pub fn table(&self) -> &RuleTable {
+        &self.table
+    }
+
+    /// Finds the readings of the input and shows their first page.
+    fn update_candidates(&mut self) {
+        self.words = if self.input.is_empty() {
+            Vec::new()
+        } else {
+            self.table.transliterate(&self.input)
+        };
+        self.page = 0;
+    }
+
+    /// Returns whether a typed character belongs to the word: letters of
+    /// the table, and its punctuation once a word is started.
+    fn composes(&self, c: char) -> bool {
+        self.table.uses(c)
+            && !c.is_ascii_digit()
+            && !c.is_whitespace()
+            && (c.is_alphabetic() || !self.input.is_empty())
+    }
+
+    /// Types the first reading and leaves the key to be typed after it.
+    fn commit_first(&mut self) -> ImeOutput {
+        let text = self.words.first().cloned().unwrap_or_default();
+        self.input.clear();
+        self.update_candidates();
+        ImeOutput {
+            commit: Some(Completion {
+                backspaces: 0,
+                text,
+            }),
+            ..ImeOutput::pass()
+        }
+    }
+
+    /// Returns the number of pages of candidates.
+    fn page_count(&self) -> usize {
+        self.words.len().div_ceil(PAGE_SIZE).max(1)
+    }
+}
+
+impl ImeEngine for TransliterationEngine {
+    fn name(&self) -> &'static str {
+        "transliteration"
+    }
+
+    fn feed(&mut self, input: ImeInput) -> ImeOutput {
+        match input {
+            ImeInput::Char(c) if self.composes(c) => {
+                self.input.push(c);
+                self.update_candidates();
+                ImeOutput::consumed()
+            }
+            // Nothing is being typed: type keys as usual
+            _ if self.input.is_empty() => ImeOutput::pass(),
+            ImeInput::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index < self.candidates().len() {
+                    self.select(index)
+                } else {
+                    ImeOutput::consumed()
+                }
+            }
+            ImeInput::Backspace => {
+                self.input.pop();
+                self.update_candidates();
+                ImeOutput::consumed()
+            }
+            ImeInput::Char(_) | ImeInput::Boundary => self.commit_first(),
+            ImeInput::Gesture(_) => ImeOutput::pass(),
+        }
+    }
+
+    fn preedit(&self) -> &str {
+        &self.input
+    }
+
+    fn candidates(&self) -> &[String] {
+        let start = (self.page * PAGE_SIZE).min(self.words.len());
+        let end = (start + PAGE_SIZE).min(self.words.len());
+        &self.words[start..end]
+    }
+
+    fn page(&self) -> (usize, usize) {
+        (self.page, self.page_count())
+    }
+
+    fn turn_page(&mut self, delta: i32) {
+        let page = self.page as i64 + i64::from(delta);
+        if (0..self.page_count() as i64).contains(&page) {
+            self.page = page as usize;
+        }
+    }
+
+    fn select(&mut self, index: usize) -> ImeOutput {
+        let Some(text) = self.words.get(self.page * PAGE_SIZE + index).cloned() else {
+            return ImeOutput::consumed();
+        };
+        self.input.clear();
+        self.update_candidates();
+        ImeOutput {
+            consumed: true,
+            commit: Some(Completion {
+                backspaces: 0,
+                text,
+            }),
+            finished: None,
+        }
+    }
+
+    fn commit_preedit(&mut self) -> ImeOutput {
+        if self.input.is_empty() {
+            return ImeOutput::pass();
+        }
+        let text = std::mem::take(&mut self.input);
+        self.update_candidates();
+        ImeOutput {
+            commit: Some(Completion {
+                backspaces: 0,
+                text,
+            }),
+            ..ImeOutput::pass()
+        }
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+        self.update_candidates();
+    }
+}
+
+/// Creates the transliteration engine of a language, if it has a table.
+#[must_use]
+pub fn new_engine(storage: &dyn Storage, language: &str) -> Option<Box<dyn ImeEngine>> {
+    let table = load_table(storage, language)?;
+    Some(Box::new(TransliterationEngine::new(table)))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FlatpakStorage;
+
+    fn bundled(language: &str) -> RuleTable {
+        let (_, text) = BUNDLED.iter().find(|(code, _)| *code == language).unwrap();
+        RuleTable::parse(text).unwrap()
+    }
+
+    /// Test: Words are converted with vowel signs and joined consonants, and
+    /// ambiguous letters offer every reading.
+    #[test]
+    fn test_transliterate() {
+        let hindi = bundled("hi");
+        assert_eq!(
+            hindi.transliterate("namaste"),
+            ["नमस्ते", "नमस्टे", "णमस्ते", "णमस्टे"]
+        );
+        assert_eq!(hindi.transliterate("kamal")[0], "कमल");
+        assert_eq!(hindi.transliterate("aapakaa")[0], "आपका");
+        assert_eq!(hindi.transliterate("hiMdii")[0], "हिंदी");
+        assert_eq!(hindi.transliterate("k.h")[0], "क्");
+
+        let bengali = bundled("bn");
+        assert_eq!(bengali.transliterate("aami")[0], "আমি");
+        assert_eq!(bengali.transliterate("bhaalo")[0], "ভালো");
+
+        // Tamil ends words in a consonant with the pulli
+        let tamil = bundled("ta");
+        let words = tamil.transliterate("vanakkam");
+        assert_eq!(words[0], "வநக்கம்");
+        assert!(words.contains(&"வணக்கம்".to_string()));
+        assert_eq!(tamil.transliterate("vaNakkam"), ["வணக்கம்"]);
+    }
+
+    /// Test: Malformed tables are rejected with the line at fault.
+    #[test]
+    fn test_parse_errors() {
+        let table = RuleTable::parse("virama = x\n\n[consonants]\nk K|C\n[vowels]\na\n");
+        assert_eq!(table.unwrap_err().line, 6);
+        let table = RuleTable::parse("[vowels]\nii I|E i\n");
+        assert_eq!(table.unwrap_err().line, 2);
+        assert_eq!(RuleTable::parse("k K\n").unwrap_err().line, 1);
+        assert_eq!(RuleTable::parse("[letters]\n").unwrap_err().line, 1);
+
+        let table = RuleTable::parse("# Comment\nvirama = +\n[consonants]\nk K|C\n").unwrap();
+        assert_eq!(table.transliterate("kk"), ["K+K", "K+C", "C+K", "C+C"]);
+        assert_eq!(table.transliterate("k-"), ["K-", "C-"]);
+    }
+
+    /// Test: Keys compose the word, and space, digits or other keys type a
+    /// reading.
+    #[test]
+    fn test_engine() {
+        let mut engine = TransliterationEngine::new(bundled("hi"));
+        assert_eq!(engine.feed(ImeInput::Char('.')), ImeOutput::pass());
+        for c in "namaste".chars() {
+            assert!(engine.feed(ImeInput::Char(c)).consumed);
+        }
+        assert_eq!(engine.preedit(), "namaste");
+        assert_eq!(engine.candidates().len(), 4);
+
+        let output = engine.feed(ImeInput::Char(' '));
+        assert!(!output.consumed);
+        assert_eq!(output.commit.unwrap().text, "नमस्ते");
+        assert_eq!(engine.preedit(), "");
+
+        for c in "tha".chars() {
+            engine.feed(ImeInput::Char(c));
+        }
+        assert!(engine.feed(ImeInput::Backspace).consumed);
+        assert_eq!(engine.candidates(), ["थ", "ठ"]);
+        assert_eq!(engine.feed(ImeInput::Char('2')).commit.unwrap().text, "ठ");
+
+        engine.feed(ImeInput::Char('k'));
+        assert_eq!(engine.commit_preedit().commit.unwrap().text, "k");
+        engine.feed(ImeInput::Char('k'));
+        let output = engine.feed(ImeInput::Boundary);
+        assert_eq!(output.commit.unwrap().text, "क");
+        assert_eq!(engine.feed(ImeInput::Char('5')), ImeOutput::pass());
+    }
+
+    /// Test: Installed tables replace the bundled ones and add languages.
+    #[test]
+    fn test_load_table() {
+        let root = tempfile::tempdir().unwrap();
+        let storage = FlatpakStorage {
+            data_home: Some(root.path().join("data")),
+            documents_dir: None,
+            app_prefix: root.path().join("app"),
+        };
+        let user = root.path().join("data").join(TRANSLITERATION_DIR);
+        let system = root.path().join("app/share").join(TRANSLITERATION_DIR);
+        std::fs::create_dir_all(&user).unwrap();
+        std::fs::create_dir_all(&system).unwrap();
+        std::fs::write(user.join("hi.translit"), "[letters]\n").unwrap();
+        std::fs::write(system.join("hi.translit"), "name = Mine\n").unwrap();
+        std::fs::write(system.join("mr.translit"), "name = Marathi\n").unwrap();
+
+        // The broken user table is skipped for the system one
+        assert_eq!(load_table(&storage, "hi").unwrap().name, "Mine");
+        assert_eq!(load_table(&storage, "mr").unwrap().name, "Marathi");
+        assert_eq!(load_table(&storage, "ta").unwrap().name, "Tamil");
+        assert!(load_table(&storage, "fr").is_none());
+    }
+}
@@ -0,0 +1,584 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Vietnamese Telex and VNI input.
+//!
+//! Layouts whose `language` is Vietnamese (`vi`) type tones and vowel marks
+//! with the letter (Telex) or digit (VNI) keys typed after a vowel, as most
+//! Vietnamese input methods do:
+//!
+//! | Result         | Telex                 | VNI     |
+//! |----------------|-----------------------|---------|
+//! | á à ả ã ạ      | `s` `f` `r` `x` `j`   | `1`–`5` |
+//! | no tone        | `z`                   | `0`     |
+//! | â ê ô          | `aa` `ee` `oo`        | `6`     |
+//! | ơ ư (ươ)       | `w`                   | `7`     |
+//! | ă              | `w`                   | `8`     |
+//! | đ              | `dd`                  | `9`     |
+//!
+//! Marks may be typed anywhere after the vowel (`tieengs` and `tiengse`
+//! both type `tiếng`), and the tone moves to the right vowel as the word
+//! grows. Typing a mark key twice undoes the mark and types the key (`ass`
+//! types `as`), and the rest of the word is typed as it is.
+//!
+//! The engine works by replacing text: it keeps the keys typed for the
+//! current word and, when a key changes more than its own character,
+//! deletes the changed end of the word and types it again. BackSpace undoes
+//! the last key rather than the last character, so a tone can be taken back
+//! without retyping the vowel. The method is chosen by the `ime`
+//! configuration entry:
+//!
+//! ```ron
+//! (vietnamese: Vni)
+//! ```
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ime::{ImeEngine, ImeInput, ImeOutput, ImeSettings};
+use crate::prediction::Completion;
+
+/// How tones and vowel marks are typed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VietnameseMethod {
+    /// Letters after the vowel: `as` types `á`.
+    #[default]
+    Telex,
+    /// Digits after the vowel: `a1` types `á`.
+    Vni,
+}
+
+/// Vowels with each tone: none, sắc, huyền, hỏi, ngã and nặng.
+const VOWELS: [[char; 6]; 12] = [
+    ['a', 'á', 'à', 'ả', 'ã', 'ạ'],
+    ['ă', 'ắ', 'ằ', 'ẳ', 'ẵ', 'ặ'],
+    ['â', 'ấ', 'ầ', 'ẩ', 'ẫ', 'ậ'],
+    ['e', 'é', 'è', 'ẻ', 'ẽ', 'ẹ'],
+    ['ê', 'ế', 'ề', 'ể', 'ễ', 'ệ'],
+    ['i', 'í', 'ì', 'ỉ', 'ĩ', 'ị'],
+    ['o', 'ó', 'ò', 'ỏ', 'õ', 'ọ'],
+    ['ô', 'ố', 'ồ', 'ổ', 'ỗ', 'ộ'],
+    ['ơ', 'ớ', 'ờ', 'ở', 'ỡ', 'ợ'],
+    ['u', 'ú', 'ù', 'ủ', 'ũ', 'ụ'],
+    ['ư', 'ứ', 'ừ', 'ử', 'ữ', 'ự'],
+    ['y', 'ý', 'ỳ', 'ỷ', 'ỹ', 'ỵ'],
+];
+
+/// A change a key makes to the word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// Put this tone (0 for none) on the word.
+    Tone(usize),
+    /// Turn `a`, `e` or `o` (only this one, if given) into `â`, `ê`, `ô`.
+    Circumflex(Option<char>),
+    /// Turn `o` and `u` into `ơ` and `ư`.
+    Horn,
+    /// Turn `a` into `ă`.
+    Breve,
+    /// Telex `w`: a horn, or a breve on `a`.
+    HornOrBreve,
+    /// Turn `d` into `đ`.
+    Stroke,
+}
+
+/// What an edit did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// The edit changed the word; the key is not typed.
+    Applied,
+    /// The key undid its own edit and is typed.
+    Reverted,
+    /// The key does not edit this word and is typed.
+    Ignored,
+}
+
+impl VietnameseMethod {
+    /// Returns the edit `key` makes, if it is a mark key of the method.
+    fn edit(self, key: char) -> Option<Edit> {
+        match (self, key.to_ascii_lowercase()) {
+            (VietnameseMethod::Telex, 's') => Some(Edit::Tone(1)),
+            (VietnameseMethod::Telex, 'f') => Some(Edit::Tone(2)),
+            (VietnameseMethod::Telex, 'r') => Some(Edit::Tone(3)),
+            (VietnameseMethod::Telex, 'x') => Some(Edit::Tone(4)),
+            (VietnameseMethod::Telex, 'j') => Some(Edit::Tone(5)),
+            (VietnameseMethod::Telex, 'z') => Some(Edit::Tone(0)),
+            (VietnameseMethod::Telex, c @ ('a' | 'e' | 'o')) => Some(Edit::Circumflex(Some(c))),
+            (VietnameseMethod::Telex, 'w') => Some(Edit::HornOrBreve),
+            (VietnameseMethod::Telex, 'd') => Some(Edit::Stroke),
+            (VietnameseMethod::Vni, c @ '0'..='5') => Some(Edit::Tone(c as usize - '0' as usize)),
+            (VietnameseMethod::Vni, '6') => Some(Edit::Circumflex(None)),
+            (VietnameseMethod::Vni, '7') => Some(Edit::Horn),
+            (VietnameseMethod::Vni, '8') => Some(Edit::Breve),
+            (VietnameseMethod::Vni, '9') => Some(Edit::Stroke),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `key` belongs to the word being typed: a letter, or
+    /// a VNI digit after one.
+    fn is_word_key(self, key: char, in_word: bool) -> bool {
+        key.is_alphabetic() || (self == VietnameseMethod::Vni && in_word && key.is_ascii_digit())
+    }
+}
+
+/// Returns the vowel without its tone (but with its mark) and its tone.
+fn split_vowel(c: char) -> Option<(char, usize)> {
+    let lower = c.to_lowercase().next()?;
+    VOWELS.iter().find_map(|forms| {
+        forms
+            .iter()
+            .position(|form| *form == lower)
+            .map(|tone| (forms[0], tone))
+    })
+}
+
+/// Returns `base` with `tone`, in the case of `like`.
+fn join_vowel(base: char, tone: usize, like: char) -> char {
+    let c = VOWELS
+        .iter()
+        .find(|forms| forms[0] == base)
+        .map_or(base, |forms| forms[tone]);
+    if like.is_uppercase() {
+        c.to_uppercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+/// Returns the letter a vowel is written on: `a` for `ă` and `â`, ...
+fn root(base: char) -> char {
+    match base {
+        'ă' | 'â' => 'a',
+        'ê' => 'e',
+        'ô' | 'ơ' => 'o',
+        'ư' => 'u',
+        other => other,
+    }
+}
+
+/// Returns `true` if a vowel has a mark (`ă â ê ô ơ ư`).
+fn has_mark(c: char) -> bool {
+    split_vowel(c).is_some_and(|(base, _)| root(base) != base)
+}
+
+/// Returns the vowels the tone goes on: the last run of vowels, without
+/// the `u` of `qu` and the `i` of `gi` before another vowel.
+fn vowel_group(word: &[char]) -> Option<Range<usize>> {
+    let end = word.iter().rposition(|c| split_vowel(*c).is_some())? + 1;
+    let mut start = end - 1;
+    while start > 0 && split_vowel(word[start - 1]).is_some() {
+        start -= 1;
+    }
+    if end - start > 1 && start > 0 {
+        let consonant = word[start - 1].to_lowercase().next();
+        let vowel = split_vowel(word[start]).map(|(base, _)| root(base));
+        if matches!(
+            (consonant, vowel),
+            (Some('q'), Some('u')) | (Some('g'), Some('i'))
+        ) {
+            start += 1;
+        }
+    }
+    Some(start..end)
+}
+
+/// Returns the index of the vowel of `group` that carries the tone.
+///
+/// A marked vowel takes it (the last one, for `ươ`); otherwise the last
+/// vowel before a final consonant, the middle one of three vowels, or the
+/// first of two (`hòa`, `của`).
+fn tone_position(word: &[char], group: &Range<usize>) -> usize {
+    let vowels = &word[group.clone()];
+    if let Some(index) = vowels.iter().rposition(|c| has_mark(*c)) {
+        return group.start + index;
+    }
+    match vowels.len() {
+        _ if group.end < word.len() => group.end - 1,
+        1 | 2 => group.start,
+        _ => group.start + 1,
+    }
+}
+
+/// Returns the tone on the vowels of `group`, if any.
+fn group_tone(word: &[char], group: &Range<usize>) -> Option<usize> {
+    word[group.clone()]
+        .iter()
+        .filter_map(|c| split_vowel(*c))
+        .map(|(_, tone)| tone)
+        .find(|tone| *tone != 0)
+}
+
+/// Puts `tone` on the word's tone vowel, removing it from the others.
+fn set_tone(word: &mut [char], group: &Range<usize>, tone: usize) {
+    for index in group.clone() {
+        if let Some((base, _)) = split_vowel(word[index]) {
+            word[index] = join_vowel(base, 0, word[index]);
+        }
+    }
+    let index = tone_position(word, group);
+    if let Some((base, _)) = split_vowel(word[index]) {
+        word[index] = join_vowel(base, tone, word[index]);
+    }
+}
+
+/// Moves the tone to the vowel it belongs on after the word changed.
+fn place_tone(word: &mut [char]) {
+    let Some(group) = vowel_group(word) else {
+        return;
+    };
+    if let Some(tone) = group_tone(word, &group) {
+        set_tone(word, &group, tone);
+    }
+}
+
+/// Changes the mark of the vowel at `index` to `marked`, or back to its
+/// root letter if it already has that mark.
+fn toggle_mark(word: &mut [char], index: usize, marked: char) -> Outcome {
+    let Some((base, tone)) = split_vowel(word[index]) else {
+        return Outcome::Ignored;
+    };
+    if base == marked {
+        word[index] = join_vowel(root(base), tone, word[index]);
+        Outcome::Reverted
+    } else {
+        word[index] = join_vowel(marked, tone, word[index]);
+        Outcome::Applied
+    }
+}
+
+/// Returns the marked form of a vowel for a horn or breve edit.
+fn horn_or_breve(edit: Edit, root: char) -> Option<char> {
+    match (edit, root) {
+        (Edit::Horn | Edit::HornOrBreve, 'o') => Some('ơ'),
+        (Edit::Horn | Edit::HornOrBreve, 'u') => Some('ư'),
+        (Edit::Breve | Edit::HornOrBreve, 'a') => Some('ă'),
+        _ => None,
+    }
+}
+
+/// Applies an edit to the word.
+fn apply(edit: Edit, word: &mut [char]) -> Outcome {
+    if edit == Edit::Stroke {
+        return match word.first().copied() {
+            Some(c @ ('d' | 'D')) => {
+                word[0] = if c == 'D' { 'Đ' } else { 'đ' };
+                Outcome::Applied
+            }
+            Some(c @ ('đ' | 'Đ')) => {
+                word[0] = if c == 'Đ' { 'D' } else { 'd' };
+                Outcome::Reverted
+            }
+            _ => Outcome::Ignored,
+        };
+    }
+
+    let Some(group) = vowel_group(word) else {
+        return Outcome::Ignored;
+    };
+    let root_at =
+        |word: &[char], index: usize| split_vowel(word[index]).map(|(base, _)| root(base));
+
+    match edit {
+        Edit::Tone(tone) => match group_tone(word, &group) {
+            None if tone == 0 => Outcome::Ignored,
+            Some(current) if current == tone => {
+                set_tone(word, &group, 0);
+                Outcome::Reverted
+            }
+            _ => {
+                set_tone(word, &group, tone);
+                Outcome::Applied
+            }
+        },
+        Edit::Circumflex(letter) => {
+            let index = group.clone().rev().find(|&index| {
+                root_at(word, index).is_some_and(|root| match letter {
+                    Some(letter) => root == letter,
+                    None => matches!(root, 'a' | 'e' | 'o'),
+                })
+            });
+            let Some(index) = index else {
+                return Outcome::Ignored;
+            };
+            let marked = match root_at(word, index) {
+                Some('a') => 'â',
+                Some('e') => 'ê',
+                _ => 'ô',
+            };
+            toggle_mark(word, index, marked)
+        }
+        _ => {
+            // `uo` takes the horn on both vowels
+            let pair = group.clone().skip(1).find(|&index| {
+                root_at(word, index - 1) == Some('u') && root_at(word, index) == Some('o')
+            });
+            if let (Some(index), Edit::Horn | Edit::HornOrBreve) = (pair, edit) {
+                let horned = [index - 1, index]
+                    .iter()
+                    .all(|&index| matches!(split_vowel(word[index]), Some(('ư' | 'ơ', _))));
+                let (bases, outcome) = if horned {
+                    (['u', 'o'], Outcome::Reverted)
+                } else {
+                    (['ư', 'ơ'], Outcome::Applied)
+                };
+                for (index, base) in [index - 1, index].into_iter().zip(bases) {
+                    if let Some((_, tone)) = split_vowel(word[index]) {
+                        word[index] = join_vowel(base, tone, word[index]);
+                    }
+                }
+                return outcome;
+            }
+
+            let target = group.clone().rev().find_map(|index| {
+                root_at(word, index)
+                    .and_then(|root| horn_or_breve(edit, root))
+                    .map(|marked| (index, marked))
+            });
+            match target {
+                Some((index, marked)) => toggle_mark(word, index, marked),
+                None => Outcome::Ignored,
+            }
+        }
+    }
+}
+
+/// Returns the text typed by a word's keys.
+///
+/// After a key undoes its own mark, the rest of the keys are typed as they
+/// are, so words with doubled letters can still be typed.
+#[must_use]
+pub fn transform(method: VietnameseMethod, keys: &str) -> String {
+    let mut word: Vec<char> = Vec::new();
+    let mut literal = false;
+    for key in keys.chars() {
+        let edit = method.edit(key).filter(|_| !literal);
+        match edit.map(|edit| apply(edit, &mut word)) {
+            Some(Outcome::Applied) => {}
+            Some(Outcome::Reverted) => {
+                word.push(key);
+                literal = true;
+            }
+            Some(Outcome::Ignored) | None => word.push(key),
+        }
+        place_tone(&mut word);
+    }
+    word.into_iter().collect()
+}
+
+/// Vietnamese input method engine.
+#[derive(Debug, Clone, Default)]
+pub struct VietnameseEngine {
+    /// Typing method
+    method: VietnameseMethod,
+    /// Keys typed for the current word
+    keys: String,
+    /// Text typed for the current word
+    word: String,
+}
+
+impl VietnameseEngine {
+    /// Creates an engine typing with `method`.
+    #[must_use]
+    pub fn new(method: VietnameseMethod) -> Self {
+        Self {
+            method,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the typing method.
+    #[must_use]
+    pub fn method(&self) -> VietnameseMethod {
+        self.method
+    }
+
+    /// Updates the word for new keys.
+    ///
+    /// `typed` is what the key types by itself: the key's character, or
+    /// nothing for BackSpace, which deletes a character. If the word
+    /// changes only by that, the key is left to be typed; otherwise the
+    /// changed end of the word is replaced.
+    fn update(&mut self, keys: String, typed: Option<char>) -> ImeOutput {
+        let word = transform(self.method, &keys);
+        let common = self
+            .word
+            .chars()
+            .zip(word.chars())
+            .take_while(|(old, new)| old == new)
+            .count();
+        let backspaces = self.word.chars().count() - common;
+        let text: String = word.chars().skip(common).collect();
+
+        let output = match typed {
+            Some(c) if backspaces == 0 && text.chars().eq([c]) => ImeOutput::pass(),
+            None if backspaces == 1 && text.is_empty() => ImeOutput::pass(),
+            _ => ImeOutput {
+                consumed: true,
+                commit: Some(Completion { backspaces, text }),
+                finished: None,
+            },
+        };
+        self.keys = keys;
+        self.word = word;
+        output
+    }
+}
+
+impl ImeEngine for VietnameseEngine {
+    fn name(&self) -> &'static str {
+        match self.method {
+            VietnameseMethod::Telex => "telex",
+            VietnameseMethod::Vni => "vni",
+        }
+    }
+
+    fn feed(&mut self, input: ImeInput) -> ImeOutput {
+        match input {
+            ImeInput::Char(c) if self.method.is_word_key(c, !self.keys.is_empty()) => {
+                let mut keys = self.keys.clone();
+                keys.push(c);
+                self.update(keys, Some(c))
+            }
+            // BackSpace undoes the last key of the word
+            ImeInput::Backspace if !self.keys.is_empty() => {
+                let mut keys = self.keys.clone();
+                keys.pop();
+                self.update(keys, None)
+            }
+            _ => {
+                self.reset();
+                ImeOutput::pass()
+            }
+        }
+    }
+
+    fn candidates(&self) -> &[String] {
+        &[]
+    }
+
+    fn select(&mut self, _index: usize) -> ImeOutput {
+        ImeOutput::pass()
+    }
+
+    fn reset(&mut self) {
+        self.keys.clear();
+        self.word.clear();
+    }
+}
+
+/// Creates a Vietnamese engine, for [`ENGINES`](crate::ime::ENGINES).
+#[must_use]
+pub fn new_engine(settings: &ImeSettings) -> Box<dyn ImeEngine> {
+    Box::new(VietnameseEngine::new(settings.vietnamese))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Telex and VNI keys type tones and marks, placed on the right
+    /// vowel.
+    #[test]
+    fn test_transform() {
+        let telex = |keys| transform(VietnameseMethod::Telex, keys);
+        assert_eq!(telex("aa"), "â");
+        assert_eq!(telex("as"), "á");
+        assert_eq!(telex("Vieetj"), "Việt");
+        assert_eq!(telex("tieengs"), "tiếng");
+        assert_eq!(telex("tiengse"), "tiếng");
+        assert_eq!(telex("nguoiwf"), "người");
+        assert_eq!(telex("ddaauf"), "đầu");
+        assert_eq!(telex("hoaf"), "hòa");
+        assert_eq!(telex("tosan"), "toán");
+        assert_eq!(telex("quas"), "quá");
+        assert_eq!(telex("gias"), "giá");
+        assert_eq!(telex("awn"), "ăn");
+        assert_eq!(telex("asz"), "a");
+
+        // Doubled mark keys undo the mark, and the rest is typed as it is
+        assert_eq!(telex("ass"), "as");
+        assert_eq!(telex("aaa"), "aa");
+        assert_eq!(telex("asss"), "ass");
+
+        let vni = |keys| transform(VietnameseMethod::Vni, keys);
+        assert_eq!(vni("Vie65t"), "Việt");
+        assert_eq!(vni("d9u7o7ng2"), "đường");
+        assert_eq!(vni("a8n"), "ăn");
+        assert_eq!(vni("a11"), "a1");
+    }
+
+    /// Test: Keys that change more than their own character replace the
+    /// end of the word, and BackSpace undoes the last key.
+    #[test]
+    fn test_engine() {
+        let mut engine = VietnameseEngine::new(VietnameseMethod::Telex);
+        assert_eq!(engine.feed(ImeInput::Char('v')), ImeOutput::pass());
+        assert_eq!(engine.feed(ImeInput::Char('i')), ImeOutput::pass());
+        assert_eq!(engine.feed(ImeInput::Char('e')), ImeOutput::pass());
+
+        let output = engine.feed(ImeInput::Char('e'));
+        assert!(output.consumed);
+        assert_eq!(
+            output.commit,
+            Some(Completion {
+                backspaces: 1,
+                text: "ê".to_string()
+            })
+        );
+        assert_eq!(engine.feed(ImeInput::Char('t')), ImeOutput::pass());
+        let output = engine.feed(ImeInput::Char('j'));
+        assert_eq!(
+            output.commit,
+            Some(Completion {
+                backspaces: 2,
+                text: "ệt".to_string()
+            })
+        );
+
+        // BackSpace takes the tone back, then deletes letters as usual
+        let output = engine.feed(ImeInput::Backspace);
+        assert!(output.consumed);
+        assert_eq!(
+            output.commit,
+            Some(Completion {
+                backspaces: 2,
+                text: "êt".to_string()
+            })
+        );
+        assert_eq!(engine.feed(ImeInput::Backspace), ImeOutput::pass());
+
+        // Other keys end the word
+        assert_eq!(engine.feed(ImeInput::Char(' ')), ImeOutput::pass());
+        assert_eq!(engine.feed(ImeInput::Char('s')), ImeOutput::pass());
+
+        let mut engine = VietnameseEngine::new(VietnameseMethod::Vni);
+        assert_eq!(engine.feed(ImeInput::Char('1')), ImeOutput::pass());
+        assert_eq!(engine.feed(ImeInput::Char('a')), ImeOutput::pass());
+        assert!(engine.feed(ImeInput::Char('1')).consumed);
+    }
+
+    /// Test: The bundled Vietnamese layout parses cleanly and loads the
+    /// configured method.
+    #[test]
+    fn test_vietnamese_layout() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/layouts/vietnamese.json");
+        let result = crate::layout::parse_layout_file(path.to_str().unwrap())
+            .expect("Vietnamese layout should parse");
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+        let settings = ImeSettings {
+            vietnamese: VietnameseMethod::Vni,
+        };
+        let engine = crate::ime::engine_for_language(
+            result.layout.language.as_deref(),
+            &settings,
+            &crate::storage::NativeStorage,
+        );
+        assert_eq!(engine.map(|engine| engine.name()), Some("vni"));
+    }
+}
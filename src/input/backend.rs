@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Selection of the key emission backend.
+//!
+//! Key events are normally delivered through Wayland's
+//! `zwp_virtual_keyboard_v1` protocol. Some sessions cannot use it: plain X11
+//! sessions, and Wayland compositors that do not offer the virtual keyboard
+//! protocol (where clients may still run under XWayland). When Cosboard is
+//! built with the `x11` feature, such sessions send keys through the X11
+//! XTEST extension instead. As a last resort, the `uinput` feature creates a
+//! virtual evdev keyboard through `/dev/uinput`, which needs extra
+//! permissions (see `resources/uinput/`).
+//!
+//! The backend is chosen from the environment when the virtual keyboard is
+//! initialized:
+//!
+//! 1. A Wayland session (`WAYLAND_DISPLAY`) whose compositor offers the
+//!    virtual keyboard protocol uses Wayland.
+//! 2. Otherwise, an X11 display (`DISPLAY`) uses XTEST, if the `x11` feature
+//!    is enabled.
+//! 3. Otherwise, a writable `/dev/uinput` is used, if the `uinput` feature is
+//!    enabled.
+//! 4. Otherwise Wayland is used anyway, and key events are not delivered.
+//!
+//! Backends other than Wayland are [`KeySink`]s handed to the
+//! [`VirtualKeyboard`](super::VirtualKeyboard), which then sends each key
+//! event through the sink instead of queueing it for the protocol.
+
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_registry;
+use wayland_client::{Connection, Dispatch, QueueHandle};
+
+use super::virtual_keyboard::KeyState;
+
+/// Wayland global of the virtual keyboard protocol.
+pub const VIRTUAL_KEYBOARD_MANAGER: &str = "zwp_virtual_keyboard_manager_v1";
+
+/// Device node for the uinput backend.
+pub const UINPUT_PATH: &str = "/dev/uinput";
+
+/// Destination for key events that does not use the Wayland protocol.
+pub trait KeySink {
+    /// Returns a short, human-readable name for logs.
+    fn name(&self) -> &'static str;
+
+    /// Sends a key event. `keycode` is an evdev keycode.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the failure if the event could not be sent.
+    fn send_key(&mut self, keycode: u32, state: KeyState) -> Result<(), String>;
+}
+
+/// Key emission backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Wayland `zwp_virtual_keyboard_v1`.
+    Wayland,
+    /// X11 XTEST extension (`x11` feature).
+    X11,
+    /// Virtual evdev keyboard through `/dev/uinput` (`uinput` feature).
+    Uinput,
+}
+
+impl Backend {
+    /// Every backend, in order of preference.
+    pub const ALL: [Backend; 3] = [Backend::Wayland, Backend::X11, Backend::Uinput];
+
+    /// Returns a short, human-readable name for the backend.
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Backend::Wayland => "Wayland virtual keyboard",
+            Backend::X11 => "X11 XTEST",
+            Backend::Uinput => "uinput",
+        }
+    }
+
+    /// Returns `true` if this build includes the backend.
+    #[must_use]
+    pub fn is_compiled(self) -> bool {
+        match self {
+            Backend::Wayland => true,
+            Backend::X11 => cfg!(feature = "x11"),
+            Backend::Uinput => cfg!(feature = "uinput"),
+        }
+    }
+}
+
+/// What the environment offers for key emission.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionInfo {
+    /// A Wayland display is set (`WAYLAND_DISPLAY`).
+    pub wayland_display: bool,
+    /// An X11 display is set (`DISPLAY`).
+    pub x11_display: bool,
+    /// Whether the Wayland compositor offers the virtual keyboard protocol
+    /// (`None` if it could not be asked).
+    pub virtual_keyboard: Option<bool>,
+    /// `/dev/uinput` can be opened for writing.
+    pub uinput: bool,
+}
+
+impl SessionInfo {
+    /// Inspects the environment, asking the Wayland compositor (if any)
+    /// whether it offers the virtual keyboard protocol.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let is_set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+        let wayland_display = is_set("WAYLAND_DISPLAY");
+
+        Self {
+            wayland_display,
+            x11_display: is_set("DISPLAY"),
+            virtual_keyboard: if wayland_display {
+                wayland_has_virtual_keyboard()
+            } else {
+                None
+            },
+            uinput: uinput_writable(),
+        }
+    }
+}
+
+/// Chooses the backend for a session (see the [module docs](self)).
+///
+/// `compiled` tells which backends are available, normally
+/// [`Backend::is_compiled`].
+#[must_use]
+pub fn select_backend(session: &SessionInfo, compiled: impl Fn(Backend) -> bool) -> Backend {
+    if session.wayland_display && session.virtual_keyboard != Some(false) {
+        Backend::Wayland
+    } else if compiled(Backend::X11) && session.x11_display {
+        Backend::X11
+    } else if compiled(Backend::Uinput) && session.uinput {
+        Backend::Uinput
+    } else {
+        Backend::Wayland
+    }
+}
+
+/// Creates the key sink for a backend.
+///
+/// # Returns
+///
+/// `Ok(None)` for Wayland, whose events go through the virtual keyboard
+/// protocol, or the sink for other backends.
+///
+/// # Errors
+///
+/// Returns a description of the failure if the backend cannot be opened
+/// (e.g. no X server, XTEST missing, or no access to `/dev/uinput`).
+pub fn create_key_sink(backend: Backend) -> Result<Option<Box<dyn KeySink>>, String> {
+    match backend {
+        Backend::Wayland => Ok(None),
+        #[cfg(feature = "x11")]
+        Backend::X11 => Ok(Some(Box::new(super::xtest::XtestSink::connect()?))),
+        #[cfg(not(feature = "x11"))]
+        Backend::X11 => Err("Cosboard was built without the x11 feature".to_string()),
+        #[cfg(feature = "uinput")]
+        Backend::Uinput => Ok(Some(Box::new(super::uinput::UinputSink::create()?))),
+        #[cfg(not(feature = "uinput"))]
+        Backend::Uinput => Err("Cosboard was built without the uinput feature".to_string()),
+    }
+}
+
+/// Returns `true` if `/dev/uinput` can be opened for writing.
+#[must_use]
+pub fn uinput_writable() -> bool {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(UINPUT_PATH)
+        .is_ok()
+}
+
+/// Asks the Wayland compositor whether it offers the virtual keyboard
+/// protocol.
+///
+/// Returns `None` if the compositor cannot be reached.
+#[must_use]
+pub fn wayland_has_virtual_keyboard() -> Option<bool> {
+    let globals = wayland_globals()?;
+    Some(globals.iter().any(|global| global == VIRTUAL_KEYBOARD_MANAGER))
+}
+
+/// Returns the interface names of the globals the Wayland compositor offers.
+///
+/// Returns `None` if the compositor cannot be reached.
+#[must_use]
+pub fn wayland_globals() -> Option<Vec<String>> {
+    let connection = Connection::connect_to_env().ok()?;
+    let (globals, _queue) = registry_queue_init::<RegistryProbe>(&connection).ok()?;
+    Some(globals.contents().with_list(|list| {
+        list.iter()
+            .map(|global| global.interface.clone())
+            .collect()
+    }))
+}
+
+/// Registry listener that only collects the global list.
+struct RegistryProbe;
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for RegistryProbe {
+    fn event(
+        _state: &mut Self,
+        _registry: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(_: Backend) -> bool {
+        true
+    }
+
+    fn wayland_only(backend: Backend) -> bool {
+        backend == Backend::Wayland
+    }
+
+    /// Test: Wayland is preferred; X11 is used when the protocol is missing.
+    #[test]
+    fn test_select_backend() {
+        let wayland = SessionInfo {
+            wayland_display: true,
+            x11_display: true,
+            virtual_keyboard: Some(true),
+            uinput: true,
+        };
+        assert_eq!(select_backend(&wayland, all), Backend::Wayland);
+
+        // Unknown protocol support: keep trying Wayland
+        let unknown = SessionInfo {
+            virtual_keyboard: None,
+            ..wayland.clone()
+        };
+        assert_eq!(select_backend(&unknown, all), Backend::Wayland);
+
+        let no_protocol = SessionInfo {
+            virtual_keyboard: Some(false),
+            ..wayland
+        };
+        assert_eq!(select_backend(&no_protocol, all), Backend::X11);
+        assert_eq!(select_backend(&no_protocol, wayland_only), Backend::Wayland);
+
+        let x11 = SessionInfo {
+            x11_display: true,
+            ..SessionInfo::default()
+        };
+        assert_eq!(select_backend(&x11, all), Backend::X11);
+        assert_eq!(select_backend(&x11, wayland_only), Backend::Wayland);
+    }
+
+    /// Test: uinput is the last resort, used only when it is writable.
+    #[test]
+    fn test_select_uinput_backend() {
+        let session = SessionInfo {
+            wayland_display: true,
+            virtual_keyboard: Some(false),
+            uinput: true,
+            ..SessionInfo::default()
+        };
+        assert_eq!(select_backend(&session, all), Backend::Uinput);
+        assert_eq!(
+            select_backend(&session, |b| b != Backend::Uinput),
+            Backend::Wayland
+        );
+
+        let no_access = SessionInfo {
+            uinput: false,
+            ..session
+        };
+        assert_eq!(select_backend(&no_access, all), Backend::Wayland);
+    }
+}
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Chord matching for keys pressed together.
+//!
+//! Layouts can declare chords (see [`Chord`]): sets of keys that emit a
+//! different key when pressed at the same time, e.g. "a" + "s" for Escape.
+//! Every finger on the touchscreen presses its own key, so a chord arrives
+//! as several key presses in quick succession.
+//!
+//! Keys that take part in a chord cannot be typed on press: the matcher holds
+//! them back for [`CHORD_WINDOW`] to see whether the rest of a chord follows.
+//! A held-back key is released (typed normally) when:
+//!
+//! - the window expires ([`ChordMatcher::expire`]),
+//! - the key is released before a chord completed, or
+//! - a key is pressed that cannot complete any chord with the held keys.
+//!
+//! Keys that are in no chord are never delayed. A chord fires as soon as all
+//! of its keys are down, so a chord that contains another chord's keys can
+//! never complete.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::layout::{Chord, KeyCode};
+
+/// How long a chord key is held back waiting for the rest of the chord.
+pub const CHORD_WINDOW: Duration = Duration::from_millis(60);
+
+/// Outcome of feeding a key press to the [`ChordMatcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordEvent {
+    /// The key is in no chord: handle it normally.
+    Pass,
+    /// The key is held back until a chord completes or the window expires.
+    Pending,
+    /// The pressed keys completed a chord: emit this code instead.
+    Chord(KeyCode),
+    /// No chord can match: type these held keys (in press order) normally.
+    Flush(Vec<String>),
+}
+
+/// Detects chords among key presses.
+#[derive(Debug, Clone, Default)]
+pub struct ChordMatcher {
+    /// Chords declared by the layout
+    chords: Vec<Chord>,
+
+    /// Keys held back, in press order
+    pending: Vec<String>,
+
+    /// When the first held-back key was pressed
+    pending_since: Option<Instant>,
+
+    /// Keys that formed a chord; their releases must not be emitted
+    consumed: HashSet<String>,
+}
+
+impl ChordMatcher {
+    /// Creates a matcher for the given chords.
+    #[must_use]
+    pub fn new(chords: Vec<Chord>) -> Self {
+        Self {
+            chords,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if the layout declares any chords.
+    #[must_use]
+    pub fn has_chords(&self) -> bool {
+        !self.chords.is_empty()
+    }
+
+    /// Returns `true` while keys are held back waiting for a chord.
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Feeds a key press to the matcher.
+    pub fn press(&mut self, identifier: &str, now: Instant) -> ChordEvent {
+        if !self.in_any_chord(identifier) {
+            // An unrelated key ends any chord in progress
+            return if self.is_pending() {
+                ChordEvent::Flush(self.take_pending())
+            } else {
+                ChordEvent::Pass
+            };
+        }
+
+        if !self.pending.iter().any(|id| id == identifier) {
+            self.pending.push(identifier.to_string());
+        }
+        self.pending_since.get_or_insert(now);
+
+        if let Some(code) = self.completed_chord() {
+            let keys = self.take_pending();
+            self.consumed.extend(keys);
+            return ChordEvent::Chord(code);
+        }
+
+        if self.could_complete() {
+            ChordEvent::Pending
+        } else {
+            ChordEvent::Flush(self.take_pending())
+        }
+    }
+
+    /// Handles a key release.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the release belongs to a chord and must be swallowed.
+    /// Otherwise the held keys to type before handling the release normally
+    /// (empty if nothing was held back).
+    pub fn release(&mut self, identifier: &str) -> Option<Vec<String>> {
+        if self.consumed.remove(identifier) {
+            return None;
+        }
+
+        if self.pending.iter().any(|id| id == identifier) {
+            Some(self.take_pending())
+        } else {
+            Some(Vec::new())
+        }
+    }
+
+    /// Flushes held keys once the chord window has passed.
+    ///
+    /// Returns the keys to type normally, in press order.
+    pub fn expire(&mut self, now: Instant) -> Vec<String> {
+        match self.pending_since {
+            Some(since) if now.saturating_duration_since(since) >= CHORD_WINDOW => {
+                self.take_pending()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the key appears in any chord.
+    fn in_any_chord(&self, identifier: &str) -> bool {
+        self.chords
+            .iter()
+            .any(|chord| chord.keys.iter().any(|key| key == identifier))
+    }
+
+    /// Returns the code of the chord whose keys are exactly the held keys.
+    fn completed_chord(&self) -> Option<KeyCode> {
+        self.chords
+            .iter()
+            .find(|chord| {
+                chord.keys.len() == self.pending.len()
+                    && self.pending.iter().all(|id| chord.keys.contains(id))
+            })
+            .map(|chord| chord.code.clone())
+    }
+
+    /// Returns `true` if more presses could still complete a chord.
+    fn could_complete(&self) -> bool {
+        self.chords.iter().any(|chord| {
+            chord.keys.len() > self.pending.len()
+                && self.pending.iter().all(|id| chord.keys.contains(id))
+        })
+    }
+
+    /// Removes and returns the held keys.
+    fn take_pending(&mut self) -> Vec<String> {
+        self.pending_since = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher() -> ChordMatcher {
+        ChordMatcher::new(vec![
+            Chord {
+                keys: vec!["a".to_string(), "s".to_string()],
+                code: KeyCode::Keysym("Escape".to_string()),
+            },
+            Chord {
+                keys: vec!["j".to_string(), "k".to_string(), "l".to_string()],
+                code: KeyCode::Keysym("Tab".to_string()),
+            },
+        ])
+    }
+
+    /// Test: Pressing all keys of a chord emits it and swallows their releases
+    #[test]
+    fn test_chord_completes() {
+        let now = Instant::now();
+        let mut matcher = matcher();
+
+        assert_eq!(matcher.press("x", now), ChordEvent::Pass);
+        assert_eq!(matcher.press("s", now), ChordEvent::Pending);
+        assert_eq!(
+            matcher.press("a", now),
+            ChordEvent::Chord(KeyCode::Keysym("Escape".to_string()))
+        );
+        assert!(!matcher.is_pending());
+
+        assert_eq!(matcher.release("a"), None);
+        assert_eq!(matcher.release("s"), None);
+        assert_eq!(matcher.release("x"), Some(Vec::new()));
+    }
+
+    /// Test: Held keys are typed when the window expires or the key is released
+    #[test]
+    fn test_chord_flushes() {
+        let now = Instant::now();
+        let mut matcher = matcher();
+
+        assert_eq!(matcher.press("a", now), ChordEvent::Pending);
+        assert!(matcher.expire(now + Duration::from_millis(10)).is_empty());
+        assert_eq!(matcher.expire(now + CHORD_WINDOW), vec!["a".to_string()]);
+        assert!(!matcher.is_pending());
+
+        matcher.press("j", now);
+        matcher.press("k", now);
+        assert_eq!(
+            matcher.release("j"),
+            Some(vec!["j".to_string(), "k".to_string()])
+        );
+
+        // A key outside any chord ends the chord in progress
+        matcher.press("s", now);
+        assert_eq!(
+            matcher.press("x", now),
+            ChordEvent::Flush(vec!["s".to_string()])
+        );
+    }
+}
@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Modifier emission for key combos.
+//!
+//! A regular key typed while modifiers are active is emitted as a combo: the
+//! modifier keys are pressed before the key and released after it. With
+//! several fingers on the touchscreen, the active modifiers can change while
+//! a key is held, and two held keys can share a modifier. Releasing whatever
+//! is active when a key is let go would then release modifiers that were
+//! never pressed, or leave pressed ones stuck.
+//!
+//! [`ComboTracker`] remembers which modifiers were pressed with each held key
+//! and how many held keys use each modifier, so every modifier press gets
+//! exactly one release, once the last key using it is released.
+//!
+//! [`KeyCombo`] describes a single combo sent on request (the `send_key`
+//! action and the D-Bus `SendKey` method), written `ctrl+alt+Delete` or
+//! given as a keysym and an X11 modifier mask.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::layout::{KeyCode, Modifier};
+
+/// Tracks the modifier keys pressed for held keys.
+#[derive(Debug, Clone, Default)]
+pub struct ComboTracker {
+    /// Modifiers pressed with each held key
+    held: HashMap<KeyCode, Vec<Modifier>>,
+
+    /// Number of held keys using each pressed modifier
+    pressed: HashMap<Modifier, usize>,
+}
+
+impl ComboTracker {
+    /// Creates a tracker with no held keys.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a key press with the active modifiers.
+    ///
+    /// Returns the modifiers whose keys must be pressed before the key:
+    /// those not already pressed for another held key. A key that is already
+    /// held keeps the modifiers it was first pressed with.
+    pub fn press(&mut self, key: KeyCode, modifiers: &[Modifier]) -> Vec<Modifier> {
+        if self.held.contains_key(&key) {
+            return Vec::new();
+        }
+
+        let mut newly_pressed = Vec::new();
+        for modifier in modifiers {
+            let count = self.pressed.entry(*modifier).or_insert(0);
+            if *count == 0 {
+                newly_pressed.push(*modifier);
+            }
+            *count += 1;
+        }
+        self.held.insert(key, modifiers.to_vec());
+        newly_pressed
+    }
+
+    /// Records a key release.
+    ///
+    /// Returns the modifiers whose keys must be released after the key:
+    /// those pressed with it that no other held key still uses. Releasing a
+    /// key that is not held returns nothing.
+    pub fn release(&mut self, key: &KeyCode) -> Vec<Modifier> {
+        let Some(modifiers) = self.held.remove(key) else {
+            return Vec::new();
+        };
+
+        let mut released = Vec::new();
+        for modifier in modifiers {
+            if let Some(count) = self.pressed.get_mut(&modifier) {
+                *count -= 1;
+                if *count == 0 {
+                    self.pressed.remove(&modifier);
+                    released.push(modifier);
+                }
+            }
+        }
+        released
+    }
+
+    /// Checks if a key is held.
+    #[must_use]
+    pub fn is_held(&self, key: &KeyCode) -> bool {
+        self.held.contains_key(key)
+    }
+
+    /// Returns the modifiers whose keys are currently pressed, sorted.
+    #[must_use]
+    pub fn pressed_modifiers(&self) -> Vec<Modifier> {
+        let mut modifiers: Vec<Modifier> = self.pressed.keys().copied().collect();
+        modifiers.sort();
+        modifiers
+    }
+}
+
+// ============================================================================
+// Requested Combos
+// ============================================================================
+
+/// Shift bit of a modifier mask (X11 `ShiftMask`).
+pub const MASK_SHIFT: u32 = 1 << 0;
+/// Control bit of a modifier mask (X11 `ControlMask`).
+pub const MASK_CTRL: u32 = 1 << 2;
+/// Alt bit of a modifier mask (X11 `Mod1Mask`).
+pub const MASK_ALT: u32 = 1 << 3;
+/// Super bit of a modifier mask (X11 `Mod4Mask`).
+pub const MASK_SUPER: u32 = 1 << 6;
+
+/// Modifier mask bits, in [`Modifier`] order.
+const MASK_BITS: [(Modifier, u32); 4] = [
+    (Modifier::Shift, MASK_SHIFT),
+    (Modifier::Ctrl, MASK_CTRL),
+    (Modifier::Alt, MASK_ALT),
+    (Modifier::Super, MASK_SUPER),
+];
+
+/// Returns the modifier written as `name` in a combo string.
+fn modifier_from_name(name: &str) -> Option<Modifier> {
+    match name.to_ascii_lowercase().as_str() {
+        "shift" => Some(Modifier::Shift),
+        "ctrl" | "control" => Some(Modifier::Ctrl),
+        "alt" => Some(Modifier::Alt),
+        "super" | "meta" | "logo" => Some(Modifier::Super),
+        _ => None,
+    }
+}
+
+/// Returns the name of a modifier in a combo string.
+fn modifier_name(modifier: Modifier) -> &'static str {
+    match modifier {
+        Modifier::Shift => "shift",
+        Modifier::Ctrl => "ctrl",
+        Modifier::Alt => "alt",
+        Modifier::Super => "super",
+        Modifier::CapsLock => "capslock",
+    }
+}
+
+/// A key pressed with modifiers, e.g. `ctrl+alt+Delete`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    /// Modifiers held around the key, sorted and without duplicates
+    pub modifiers: Vec<Modifier>,
+    /// XKB keysym name of the key (`"Delete"`, `"a"`, `"F5"`)
+    pub keysym: String,
+}
+
+impl KeyCombo {
+    /// Creates a combo of a key and modifiers.
+    #[must_use]
+    pub fn new(keysym: impl Into<String>, modifiers: &[Modifier]) -> Self {
+        let mut modifiers = modifiers.to_vec();
+        modifiers.sort();
+        modifiers.dedup();
+        Self {
+            modifiers,
+            keysym: keysym.into(),
+        }
+    }
+
+    /// Parses a combo written as modifiers and a keysym joined by `+`.
+    ///
+    /// Modifier names are case-insensitive (`shift`, `ctrl`/`control`,
+    /// `alt`, `super`/`meta`/`logo`); the keysym comes last. Returns `None`
+    /// for unknown modifiers or a missing keysym.
+    #[must_use]
+    pub fn parse(combo: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = combo.trim().split('+').map(str::trim).collect();
+        let keysym = parts.pop().filter(|keysym| !keysym.is_empty())?;
+        let modifiers = parts
+            .into_iter()
+            .map(modifier_from_name)
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::new(keysym, &modifiers))
+    }
+
+    /// Creates a combo from a keysym and a modifier mask.
+    ///
+    /// Only [`MASK_SHIFT`], [`MASK_CTRL`], [`MASK_ALT`] and [`MASK_SUPER`]
+    /// may be set. Returns `None` for other bits or an empty keysym.
+    #[must_use]
+    pub fn from_mask(keysym: &str, mask: u32) -> Option<Self> {
+        let known = MASK_BITS.iter().fold(0, |known, (_, bit)| known | bit);
+        if keysym.trim().is_empty() || mask & !known != 0 {
+            return None;
+        }
+        let modifiers: Vec<Modifier> = MASK_BITS
+            .iter()
+            .filter(|(_, bit)| mask & bit != 0)
+            .map(|(modifier, _)| *modifier)
+            .collect();
+        Some(Self::new(keysym.trim(), &modifiers))
+    }
+
+    /// Returns the modifier mask of the combo.
+    #[must_use]
+    pub fn mask(&self) -> u32 {
+        MASK_BITS
+            .iter()
+            .filter(|(modifier, _)| self.modifiers.contains(modifier))
+            .fold(0, |mask, (_, bit)| mask | bit)
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier_name(*modifier))?;
+        }
+        write!(f, "{}", self.keysym)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Test: A modifier activated while a key is held is not released with it.
+    #[test]
+    fn test_modifier_changed_while_held() {
+        let mut tracker = ComboTracker::new();
+        let a = KeyCode::Unicode('a');
+
+        assert!(tracker.press(a.clone(), &[]).is_empty());
+        // Shift is activated by a second finger before 'a' is released
+        assert!(tracker.release(&a).is_empty());
+        assert!(tracker.pressed_modifiers().is_empty());
+    }
+
+    /// Test: A shared modifier is released with the last key using it.
+    #[test]
+    fn test_shared_modifier() {
+        let mut tracker = ComboTracker::new();
+        let a = KeyCode::Unicode('a');
+        let b = KeyCode::Unicode('b');
+
+        assert_eq!(tracker.press(a.clone(), &[Modifier::Shift]), vec![Modifier::Shift]);
+        assert_eq!(
+            tracker.press(b.clone(), &[Modifier::Ctrl, Modifier::Shift]),
+            vec![Modifier::Ctrl]
+        );
+        assert!(tracker.release(&a).is_empty());
+        assert_eq!(tracker.release(&b), vec![Modifier::Ctrl, Modifier::Shift]);
+        assert!(!tracker.is_held(&b));
+    }
+
+    /// Test: Combos parse from strings and masks and print in a fixed order.
+    #[test]
+    fn test_key_combo() {
+        let combo = KeyCombo::parse("Alt+ctrl+Delete").unwrap();
+        assert_eq!(combo.modifiers, vec![Modifier::Ctrl, Modifier::Alt]);
+        assert_eq!(combo.keysym, "Delete");
+        assert_eq!(combo.to_string(), "ctrl+alt+Delete");
+        assert_eq!(combo.mask(), MASK_CTRL | MASK_ALT);
+        assert_eq!(KeyCombo::from_mask("Delete", MASK_ALT | MASK_CTRL), Some(combo));
+
+        assert_eq!(KeyCombo::parse("F5"), Some(KeyCombo::new("F5", &[])));
+        assert_eq!(KeyCombo::parse("super+shift+s").unwrap().mask(), 65);
+        assert_eq!(KeyCombo::parse("hyper+a"), None);
+        assert_eq!(KeyCombo::parse("ctrl+"), None);
+        assert_eq!(KeyCombo::from_mask("a", 1 << 1), None);
+        assert_eq!(KeyCombo::from_mask("", 0), None);
+    }
+
+    /// One step of a random key sequence.
+    #[derive(Debug, Clone)]
+    enum Step {
+        Press(char, Vec<Modifier>),
+        Release(char),
+    }
+
+    fn modifier() -> impl Strategy<Value = Modifier> {
+        prop_oneof![
+            Just(Modifier::Shift),
+            Just(Modifier::Ctrl),
+            Just(Modifier::Alt),
+            Just(Modifier::Super),
+        ]
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        let key = prop::char::range('a', 'e');
+        prop_oneof![
+            (key.clone(), prop::collection::btree_set(modifier(), 0..=4))
+                .prop_map(|(key, modifiers)| Step::Press(key, modifiers.into_iter().collect())),
+            key.prop_map(Step::Release),
+        ]
+    }
+
+    proptest! {
+        /// Property: Modifier releases always follow their press, a pressed
+        /// modifier is never pressed again, and nothing is left pressed once
+        /// every key is released.
+        #[test]
+        fn prop_combo_emission_ordering(steps in prop::collection::vec(step(), 0..64)) {
+            let mut tracker = ComboTracker::new();
+            let mut down: HashSet<Modifier> = HashSet::new();
+
+            let mut apply = |tracker: &mut ComboTracker, step: &Step| {
+                match step {
+                    Step::Press(key, modifiers) => {
+                        for modifier in tracker.press(KeyCode::Unicode(*key), modifiers) {
+                            prop_assert!(down.insert(modifier), "{:?} pressed twice", modifier);
+                        }
+                    }
+                    Step::Release(key) => {
+                        for modifier in tracker.release(&KeyCode::Unicode(*key)) {
+                            prop_assert!(down.remove(&modifier), "{:?} released before press", modifier);
+                        }
+                    }
+                }
+                let mut expected: Vec<Modifier> = down.iter().copied().collect();
+                expected.sort();
+                prop_assert_eq!(tracker.pressed_modifiers(), expected);
+                Ok(())
+            };
+
+            for step in &steps {
+                apply(&mut tracker, step)?;
+            }
+            for key in 'a'..='e' {
+                apply(&mut tracker, &Step::Release(key))?;
+            }
+            prop_assert!(down.is_empty(), "stuck modifiers: {:?}", down);
+        }
+    }
+}
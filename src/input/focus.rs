@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Focus arbitration between the keyboard's own text fields and the client.
+//!
+//! Normally every key press is emitted to the focused client application.
+//! When one of the keyboard's internal text fields (such as the emoji search
+//! field) has focus, key input must instead edit that field's buffer and
+//! never reach the client. `InputFocus` sits between the key handlers and
+//! the emission path and decides where each key goes.
+//!
+//! Both on-screen keys and physical keys (delivered when the keyboard surface
+//! has `OnDemand` keyboard interactivity) are routed through [`InputFocus::route`].
+//!
+//! # Key Handling While Focused
+//!
+//! | Key                  | Effect                                   |
+//! |----------------------|------------------------------------------|
+//! | Character / `space`  | Appended to the buffer                   |
+//! | `BackSpace`          | Removes the last character               |
+//! | `Return`, `KP_Enter` | Submits the buffer                       |
+//! | `Escape`             | Cancels editing and releases focus       |
+//! | Other keysyms        | Swallowed so they never reach the client |
+
+use crate::input::ResolvedKeycode;
+use std::collections::HashSet;
+
+/// Where a key press was routed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyRoute {
+    /// No internal field has focus; emit the key to the client application.
+    Client,
+    /// The key edited the focused field's buffer.
+    Edited,
+    /// The key submitted the focused field. Contains the buffer contents.
+    Submitted(String),
+    /// The key cancelled editing; focus was released.
+    Cancelled,
+    /// The key was swallowed without changing the buffer.
+    Ignored,
+}
+
+/// An internal text field that currently holds input focus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusedField {
+    /// Identifier of the field (e.g. `"emoji_search"`).
+    pub id: String,
+    /// Current text in the field.
+    pub buffer: String,
+}
+
+/// Tracks which internal text field, if any, receives key input.
+#[derive(Debug, Clone, Default)]
+pub struct InputFocus {
+    /// The focused internal field, or `None` when input goes to the client.
+    focused: Option<FocusedField>,
+    /// Identifiers of on-screen keys whose press was routed internally, so
+    /// that their release is not emitted to the client either.
+    consumed_keys: HashSet<String>,
+}
+
+impl InputFocus {
+    /// Creates a focus tracker with input going to the client.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives focus to the internal field `id`, starting with an empty buffer.
+    ///
+    /// Focusing the field that already has focus keeps its buffer.
+    pub fn focus(&mut self, id: impl Into<String>) {
+        let id = id.into();
+        if self.focused_id() != Some(id.as_str()) {
+            self.focused = Some(FocusedField {
+                id,
+                buffer: String::new(),
+            });
+        }
+    }
+
+    /// Releases focus back to the client, returning the field that had it.
+    pub fn blur(&mut self) -> Option<FocusedField> {
+        self.focused.take()
+    }
+
+    /// Returns `true` if an internal field has focus.
+    #[must_use]
+    pub fn is_internal(&self) -> bool {
+        self.focused.is_some()
+    }
+
+    /// Returns the identifier of the focused field, if any.
+    #[must_use]
+    pub fn focused_id(&self) -> Option<&str> {
+        self.focused.as_ref().map(|field| field.id.as_str())
+    }
+
+    /// Returns `true` if the field `id` has focus.
+    #[must_use]
+    pub fn is_focused(&self, id: &str) -> bool {
+        self.focused_id() == Some(id)
+    }
+
+    /// Returns the buffer of the field `id`, or an empty string if it does
+    /// not have focus.
+    #[must_use]
+    pub fn buffer(&self, id: &str) -> &str {
+        match &self.focused {
+            Some(field) if field.id == id => &field.buffer,
+            _ => "",
+        }
+    }
+
+    /// Routes a key press to the focused field or to the client.
+    ///
+    /// Returns [`KeyRoute::Client`] when no internal field has focus, in
+    /// which case the caller emits the key as usual.
+    pub fn route(&mut self, key: &ResolvedKeycode) -> KeyRoute {
+        let Some(field) = self.focused.as_mut() else {
+            return KeyRoute::Client;
+        };
+
+        match key {
+            ResolvedKeycode::Character(c) => {
+                field.buffer.push(*c);
+                KeyRoute::Edited
+            }
+            ResolvedKeycode::UnicodeCodepoint(codepoint) => match char::from_u32(*codepoint) {
+                Some(c) => {
+                    field.buffer.push(c);
+                    KeyRoute::Edited
+                }
+                None => KeyRoute::Ignored,
+            },
+            ResolvedKeycode::Keysym(name) => match name.as_str() {
+                "space" => {
+                    field.buffer.push(' ');
+                    KeyRoute::Edited
+                }
+                "BackSpace" => {
+                    if field.buffer.pop().is_some() {
+                        KeyRoute::Edited
+                    } else {
+                        KeyRoute::Ignored
+                    }
+                }
+                "Return" | "KP_Enter" => KeyRoute::Submitted(field.buffer.clone()),
+                "Escape" => {
+                    self.focused = None;
+                    KeyRoute::Cancelled
+                }
+                _ => KeyRoute::Ignored,
+            },
+        }
+    }
+
+    /// Records that the press of on-screen key `identifier` was routed
+    /// internally, so its release must be swallowed too.
+    pub fn consume_key(&mut self, identifier: impl Into<String>) {
+        self.consumed_keys.insert(identifier.into());
+    }
+
+    /// Returns `true` (and forgets the key) if the release of `identifier`
+    /// belongs to a press that was routed internally.
+    pub fn take_consumed_key(&mut self, identifier: &str) -> bool {
+        self.consumed_keys.remove(identifier)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keysym(name: &str) -> ResolvedKeycode {
+        ResolvedKeycode::Keysym(name.to_string())
+    }
+
+    /// Test: Without focus, every key goes to the client.
+    #[test]
+    fn test_unfocused_routes_to_client() {
+        let mut focus = InputFocus::new();
+        assert!(!focus.is_internal());
+        assert_eq!(focus.route(&ResolvedKeycode::Character('a')), KeyRoute::Client);
+        assert_eq!(focus.route(&keysym("Return")), KeyRoute::Client);
+    }
+
+    /// Test: Focused field collects characters and handles editing keys.
+    #[test]
+    fn test_focused_field_editing() {
+        let mut focus = InputFocus::new();
+        focus.focus("emoji_search");
+        assert!(focus.is_focused("emoji_search"));
+
+        assert_eq!(focus.route(&ResolvedKeycode::Character('c')), KeyRoute::Edited);
+        assert_eq!(focus.route(&ResolvedKeycode::Character('a')), KeyRoute::Edited);
+        assert_eq!(focus.route(&keysym("space")), KeyRoute::Edited);
+        assert_eq!(focus.route(&ResolvedKeycode::UnicodeCodepoint(0x03C0)), KeyRoute::Edited);
+        assert_eq!(focus.buffer("emoji_search"), "ca π");
+
+        assert_eq!(focus.route(&keysym("BackSpace")), KeyRoute::Edited);
+        assert_eq!(focus.route(&keysym("BackSpace")), KeyRoute::Edited);
+        assert_eq!(focus.buffer("emoji_search"), "ca");
+
+        // Navigation keys are swallowed rather than leaking to the client
+        assert_eq!(focus.route(&keysym("Left")), KeyRoute::Ignored);
+
+        assert_eq!(
+            focus.route(&keysym("Return")),
+            KeyRoute::Submitted("ca".to_string())
+        );
+        assert!(focus.is_internal(), "Submitting keeps focus");
+
+        assert_eq!(focus.route(&keysym("Escape")), KeyRoute::Cancelled);
+        assert!(!focus.is_internal());
+        assert_eq!(focus.buffer("emoji_search"), "");
+    }
+
+    /// Test: Refocusing keeps the buffer; focusing another field resets it.
+    #[test]
+    fn test_focus_switching() {
+        let mut focus = InputFocus::new();
+        focus.focus("emoji_search");
+        focus.route(&ResolvedKeycode::Character('x'));
+
+        focus.focus("emoji_search");
+        assert_eq!(focus.buffer("emoji_search"), "x");
+
+        focus.focus("other");
+        assert_eq!(focus.buffer("other"), "");
+        assert_eq!(focus.buffer("emoji_search"), "");
+
+        let blurred = focus.blur().unwrap();
+        assert_eq!(blurred.id, "other");
+        assert!(focus.blur().is_none());
+    }
+
+    /// Test: Releases of internally routed presses are tracked.
+    #[test]
+    fn test_consumed_key_tracking() {
+        let mut focus = InputFocus::new();
+        focus.consume_key("key_a");
+
+        assert!(focus.take_consumed_key("key_a"));
+        assert!(!focus.take_consumed_key("key_a"), "Only swallowed once");
+        assert!(!focus.take_consumed_key("key_b"));
+    }
+}
@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Gamepad and remote control navigation commands.
+//!
+//! For couch and TV setups the keyboard can be driven without touching it:
+//! a focus ring is moved from key to key and the focused key is pressed
+//! (see [`crate::renderer::navigation`]). The commands come from two
+//! sources:
+//!
+//! - Gamepads, read directly from their evdev devices
+//!   (`/dev/input/event*`) by [`run`]. Reading them needs access to the
+//!   devices, usually membership of the `input` group.
+//! - Keys forwarded by the compositor while the keyboard surface has
+//!   keyboard focus (arrow keys, Enter, Escape, Page Up/Down), as sent by
+//!   most TV remotes and wireless keyboards.
+//!
+//! | Gamepad                  | Remote / keys   | Command                         |
+//! |--------------------------|-----------------|---------------------------------|
+//! | D-pad                    | Arrow keys      | [`NavCommand::Move`]            |
+//! | A (south button)         | Enter           | [`NavCommand::Press`]           |
+//! | B (east button)          | Escape, Back    | [`NavCommand::Back`]            |
+//! | Left bumper or trigger   | Page Up         | [`NavCommand::PreviousPanel`]   |
+//! | Right bumper or trigger  | Page Down       | [`NavCommand::NextPanel`]       |
+//!
+//! Gamepads are recognised by their joystick handler (`js*`) in
+//! `/proc/bus/input/devices`. They are looked up when navigation starts,
+//! and again every [`RESCAN_INTERVAL`] while none is connected.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::SinkExt;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+/// List of input devices and their handlers.
+pub const INPUT_DEVICES_PATH: &str = "/proc/bus/input/devices";
+
+/// Time between looking for gamepads while none is connected.
+pub const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Size of a `struct input_event`: a `timeval` (two C longs), the type,
+/// the code and the value.
+pub const INPUT_EVENT_SIZE: usize = 2 * std::mem::size_of::<std::ffi::c_long>() + 8;
+
+/// evdev event type of buttons.
+const EV_KEY: u16 = 0x01;
+/// evdev event type of absolute axes (the D-pad hat on most pads).
+const EV_ABS: u16 = 0x03;
+
+const BTN_SOUTH: u16 = 0x130;
+const BTN_EAST: u16 = 0x131;
+const BTN_TL: u16 = 0x136;
+const BTN_TR: u16 = 0x137;
+const BTN_TL2: u16 = 0x138;
+const BTN_TR2: u16 = 0x139;
+const BTN_DPAD_UP: u16 = 0x220;
+const BTN_DPAD_DOWN: u16 = 0x221;
+const BTN_DPAD_LEFT: u16 = 0x222;
+const BTN_DPAD_RIGHT: u16 = 0x223;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+
+/// Whether the keyboard can be navigated with a gamepad or remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NavigationSettings {
+    /// Whether navigation commands are accepted at all. Off by default, so
+    /// arrow keys forwarded to the keyboard surface are left alone.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Whether gamepads are read from their evdev devices.
+    #[serde(default = "default_gamepad")]
+    pub gamepad: bool,
+}
+
+fn default_gamepad() -> bool {
+    true
+}
+
+impl Default for NavigationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gamepad: default_gamepad(),
+        }
+    }
+}
+
+impl NavigationSettings {
+    /// Returns `true` if gamepads should be read.
+    #[must_use]
+    pub fn reads_gamepads(&self) -> bool {
+        self.enabled && self.gamepad
+    }
+}
+
+/// Direction the focus moves in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NavDirection {
+    /// To the key above.
+    Up,
+    /// To the key below.
+    Down,
+    /// To the key on the left.
+    Left,
+    /// To the key on the right.
+    Right,
+}
+
+/// What a gamepad button or remote key asks the keyboard to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NavCommand {
+    /// Move the focus to the nearest key in a direction.
+    Move(NavDirection),
+    /// Press the focused key.
+    Press,
+    /// Go back to the previously shown panel.
+    Back,
+    /// Show the next panel of the layout.
+    NextPanel,
+    /// Show the previous panel of the layout.
+    PreviousPanel,
+}
+
+impl NavCommand {
+    /// Returns the command of a gamepad event, if any.
+    ///
+    /// `kind`, `code` and `value` are the fields of an evdev
+    /// `input_event`. Buttons act when pressed (value 1), not when released
+    /// or auto-repeated; the D-pad hat acts when it leaves the center.
+    #[must_use]
+    pub fn from_evdev(kind: u16, code: u16, value: i32) -> Option<Self> {
+        match (kind, code, value) {
+            (EV_KEY, _, 1) => match code {
+                BTN_SOUTH => Some(Self::Press),
+                BTN_EAST => Some(Self::Back),
+                BTN_TL | BTN_TL2 => Some(Self::PreviousPanel),
+                BTN_TR | BTN_TR2 => Some(Self::NextPanel),
+                BTN_DPAD_UP => Some(Self::Move(NavDirection::Up)),
+                BTN_DPAD_DOWN => Some(Self::Move(NavDirection::Down)),
+                BTN_DPAD_LEFT => Some(Self::Move(NavDirection::Left)),
+                BTN_DPAD_RIGHT => Some(Self::Move(NavDirection::Right)),
+                _ => None,
+            },
+            (EV_ABS, ABS_HAT0X, value) if value < 0 => Some(Self::Move(NavDirection::Left)),
+            (EV_ABS, ABS_HAT0X, value) if value > 0 => Some(Self::Move(NavDirection::Right)),
+            (EV_ABS, ABS_HAT0Y, value) if value < 0 => Some(Self::Move(NavDirection::Up)),
+            (EV_ABS, ABS_HAT0Y, value) if value > 0 => Some(Self::Move(NavDirection::Down)),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a `struct input_event` into its type, code and value.
+///
+/// Returns `None` if `bytes` is not exactly [`INPUT_EVENT_SIZE`] long.
+#[must_use]
+pub fn parse_input_event(bytes: &[u8]) -> Option<(u16, u16, i32)> {
+    if bytes.len() != INPUT_EVENT_SIZE {
+        return None;
+    }
+    let fields = &bytes[INPUT_EVENT_SIZE - 8..];
+    let kind = u16::from_ne_bytes([fields[0], fields[1]]);
+    let code = u16::from_ne_bytes([fields[2], fields[3]]);
+    let value = i32::from_ne_bytes([fields[4], fields[5], fields[6], fields[7]]);
+    Some((kind, code, value))
+}
+
+/// Returns the event devices of the gamepads listed in
+/// `/proc/bus/input/devices`.
+///
+/// A device is a gamepad if it has a joystick handler (`js0`, ...).
+#[must_use]
+pub fn gamepad_devices(devices: &str) -> Vec<PathBuf> {
+    devices
+        .split("\n\n")
+        .filter_map(|device| {
+            let handlers = device
+                .lines()
+                .find_map(|line| line.strip_prefix("H: Handlers="))?;
+            let mut handlers = handlers.split_whitespace();
+            let is_gamepad = handlers.clone().any(|handler| handler.starts_with("js"));
+            let event = handlers.find(|handler| handler.starts_with("event"))?;
+            is_gamepad.then(|| PathBuf::from("/dev/input").join(event))
+        })
+        .collect()
+}
+
+/// Reads navigation commands from every connected gamepad into `output`.
+///
+/// Runs until `output` is closed. While no gamepad is connected (or none
+/// can be read), looks for one every [`RESCAN_INTERVAL`].
+pub async fn run(output: Sender<NavCommand>) {
+    loop {
+        let devices = tokio::fs::read_to_string(INPUT_DEVICES_PATH)
+            .await
+            .map(|text| gamepad_devices(&text))
+            .unwrap_or_default();
+
+        if !devices.is_empty() {
+            tracing::info!("Reading navigation from gamepads: {:?}", devices);
+            let readers = devices
+                .into_iter()
+                .map(|path| read_device(path, output.clone()));
+            futures::future::join_all(readers).await;
+        }
+
+        if output.is_closed() {
+            return;
+        }
+        tokio::time::sleep(RESCAN_INTERVAL).await;
+    }
+}
+
+/// Reads one gamepad until it is unplugged or `output` is closed.
+async fn read_device(path: PathBuf, mut output: Sender<NavCommand>) {
+    let mut file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) => {
+            tracing::debug!("Cannot read gamepad {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    let mut event = [0u8; INPUT_EVENT_SIZE];
+    while file.read_exact(&mut event).await.is_ok() {
+        let Some(command) = parse_input_event(&event)
+            .and_then(|(kind, code, value)| NavCommand::from_evdev(kind, code, value))
+        else {
+            continue;
+        };
+        if output.send(command).await.is_err() {
+            return;
+        }
+    }
+    tracing::debug!("Gamepad {} disconnected", path.display());
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Buttons act on press and the hat acts off center.
+    #[test]
+    fn test_evdev_commands() {
+        assert_eq!(
+            NavCommand::from_evdev(EV_KEY, BTN_SOUTH, 1),
+            Some(NavCommand::Press)
+        );
+        assert_eq!(NavCommand::from_evdev(EV_KEY, BTN_SOUTH, 0), None);
+        assert_eq!(NavCommand::from_evdev(EV_KEY, BTN_SOUTH, 2), None);
+        assert_eq!(
+            NavCommand::from_evdev(EV_KEY, BTN_EAST, 1),
+            Some(NavCommand::Back)
+        );
+        assert_eq!(
+            NavCommand::from_evdev(EV_KEY, BTN_TR2, 1),
+            Some(NavCommand::NextPanel)
+        );
+        assert_eq!(
+            NavCommand::from_evdev(EV_ABS, ABS_HAT0Y, -1),
+            Some(NavCommand::Move(NavDirection::Up))
+        );
+        assert_eq!(
+            NavCommand::from_evdev(EV_ABS, ABS_HAT0X, 1),
+            Some(NavCommand::Move(NavDirection::Right))
+        );
+        assert_eq!(NavCommand::from_evdev(EV_ABS, ABS_HAT0X, 0), None);
+
+        let mut event = vec![0u8; INPUT_EVENT_SIZE];
+        event[INPUT_EVENT_SIZE - 8..INPUT_EVENT_SIZE - 6].copy_from_slice(&EV_KEY.to_ne_bytes());
+        event[INPUT_EVENT_SIZE - 6..INPUT_EVENT_SIZE - 4].copy_from_slice(&BTN_EAST.to_ne_bytes());
+        event[INPUT_EVENT_SIZE - 4..].copy_from_slice(&1i32.to_ne_bytes());
+        assert_eq!(parse_input_event(&event), Some((EV_KEY, BTN_EAST, 1)));
+        assert_eq!(parse_input_event(&event[1..]), None);
+    }
+
+    /// Test: Only devices with a joystick handler are gamepads.
+    #[test]
+    fn test_gamepad_devices() {
+        let devices = "\
+I: Bus=0011 Vendor=0001 Product=0001 Version=ab41
+N: Name=\"AT Translated Set 2 keyboard\"
+H: Handlers=sysrq kbd event3 leds
+
+I: Bus=0003 Vendor=045e Product=028e Version=0114
+N: Name=\"Microsoft X-Box 360 pad\"
+H: Handlers=event17 js0
+B: EV=20000b
+";
+        assert_eq!(
+            gamepad_devices(devices),
+            vec![PathBuf::from("/dev/input/event17")]
+        );
+        assert!(gamepad_devices("").is_empty());
+    }
+}
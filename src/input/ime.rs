@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Text field focus from the Wayland input method protocol.
+//!
+//! With `auto_show` enabled, the applet binds `zwp_input_method_v2` on the
+//! first seat, like squeekboard does: the compositor activates the input
+//! method when a client enables text input on a focused text field, and
+//! deactivates it when the field loses focus. The applet shows the keyboard
+//! on [`InputMethodEvent::Activated`] and hides it shortly after
+//! [`InputMethodEvent::Deactivated`], unless another field takes focus in
+//! between.
+//!
+//! Only one input method can be bound per seat: while IBus or Fcitx holds
+//! it, the compositor reports the input method as unavailable
+//! ([`InputMethodEvent::Unavailable`]) and the keyboard is left alone.
+//! Cosboard never commits text through the protocol; keys are still typed
+//! through the virtual keyboard.
+//!
+//! libcosmic owns the applet's Wayland connection, so the input method
+//! runs on a connection of its own. It is bound on a blocking thread and
+//! then dispatched whenever the connection becomes readable; when the
+//! subscription is dropped, the input method is destroyed.
+
+use std::io::ErrorKind;
+
+use futures::channel::mpsc::Sender;
+use futures::SinkExt;
+use tokio::io::unix::AsyncFd;
+use tokio::io::Interest;
+use wayland_client::backend::WaylandError;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_registry, wl_seat};
+use wayland_client::{Connection, Dispatch, EventQueue, QueueHandle};
+use wayland_protocols_misc::zwp_input_method_v2::client::{
+    zwp_input_method_manager_v2::{self, ZwpInputMethodManagerV2},
+    zwp_input_method_v2::{self, ZwpInputMethodV2},
+};
+
+/// Changes of text field focus reported by the input method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputMethodEvent {
+    /// A text field gained focus.
+    Activated,
+    /// The focused text field lost focus.
+    Deactivated,
+    /// The input method cannot be used (no Wayland connection, no protocol
+    /// support, or another input method holds the seat).
+    Unavailable(String),
+}
+
+/// Activation state of the input method.
+///
+/// Activation and deactivation are double-buffered by the protocol: they
+/// take effect on the next `done` event, so a field losing focus to another
+/// field in the same update is not reported at all.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Activation {
+    /// State received since the last `done`
+    pending: Option<bool>,
+    /// State applied on the last `done`
+    active: bool,
+}
+
+impl Activation {
+    /// Records an `activate` event.
+    pub fn activate(&mut self) {
+        self.pending = Some(true);
+    }
+
+    /// Records a `deactivate` event.
+    pub fn deactivate(&mut self) {
+        self.pending = Some(false);
+    }
+
+    /// Returns `true` if a text field had focus at the last `done`.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Applies the pending state on a `done` event.
+    ///
+    /// Returns the event to report if the focus changed.
+    pub fn done(&mut self) -> Option<InputMethodEvent> {
+        let active = self.pending.take()?;
+        if active == self.active {
+            return None;
+        }
+        self.active = active;
+        Some(if active {
+            InputMethodEvent::Activated
+        } else {
+            InputMethodEvent::Deactivated
+        })
+    }
+}
+
+/// Dispatch state of the input method connection.
+#[derive(Debug, Default)]
+struct InputMethodState {
+    /// Activation state of the input method
+    activation: Activation,
+    /// Events to report after the current dispatch
+    events: Vec<InputMethodEvent>,
+    /// Set once the compositor reported the input method unavailable
+    unavailable: bool,
+}
+
+/// The input method bound on a connection of its own.
+///
+/// Dropping it destroys the input method, so the seat is free for another.
+struct InputMethod {
+    connection: Connection,
+    queue: EventQueue<InputMethodState>,
+    manager: ZwpInputMethodManagerV2,
+    input_method: ZwpInputMethodV2,
+}
+
+impl Drop for InputMethod {
+    fn drop(&mut self) {
+        self.input_method.destroy();
+        self.manager.destroy();
+        let _ = self.connection.flush();
+    }
+}
+
+/// Reports text field focus through `output` until it is closed or the
+/// input method becomes unavailable.
+///
+/// The input method is released when the returned future is dropped.
+pub async fn run(mut output: Sender<InputMethodEvent>) {
+    let result = match tokio::task::spawn_blocking(bind).await {
+        Ok(Ok(input_method)) => dispatch(input_method, &mut output).await,
+        Ok(Err(e)) => Err(e),
+        Err(e) => Err(e.to_string()),
+    };
+
+    if let Err(e) = result {
+        tracing::info!("Input method unavailable: {}", e);
+        let _ = output.send(InputMethodEvent::Unavailable(e)).await;
+    }
+    // Keep the subscription alive without retrying in a loop
+    futures::future::pending::<()>().await;
+}
+
+/// Connects to the compositor and binds the input method on the first seat.
+fn bind() -> Result<InputMethod, String> {
+    let connection = Connection::connect_to_env().map_err(|e| e.to_string())?;
+    let (globals, queue) =
+        registry_queue_init::<InputMethodState>(&connection).map_err(|e| e.to_string())?;
+    let handle = queue.handle();
+
+    let seat: wl_seat::WlSeat = globals
+        .bind(&handle, 1..=1, ())
+        .map_err(|_| "no seat".to_string())?;
+    let manager: ZwpInputMethodManagerV2 = globals
+        .bind(&handle, 1..=1, ())
+        .map_err(|_| "the compositor does not offer zwp_input_method_manager_v2".to_string())?;
+    let input_method = manager.get_input_method(&seat, &handle, ());
+    tracing::info!("Input method bound, following text field focus");
+
+    Ok(InputMethod {
+        connection,
+        queue,
+        manager,
+        input_method,
+    })
+}
+
+/// Dispatches the events of the input method whenever its connection
+/// becomes readable, until `output` is closed.
+async fn dispatch(
+    mut input_method: InputMethod,
+    output: &mut Sender<InputMethodEvent>,
+) -> Result<(), String> {
+    let fd = input_method
+        .connection
+        .backend()
+        .poll_fd()
+        .try_clone_to_owned()
+        .and_then(|fd| AsyncFd::with_interest(fd, Interest::READABLE))
+        .map_err(|e| e.to_string())?;
+
+    let mut state = InputMethodState::default();
+    loop {
+        let queue = &mut input_method.queue;
+        queue
+            .dispatch_pending(&mut state)
+            .map_err(|e| e.to_string())?;
+        if state.unavailable {
+            return Err("another input method is running".to_string());
+        }
+        for event in state.events.drain(..) {
+            // The subscription is gone
+            if output.send(event).await.is_err() {
+                return Ok(());
+            }
+        }
+        queue.flush().map_err(|e| e.to_string())?;
+
+        let mut ready = fd.readable().await.map_err(|e| e.to_string())?;
+        // Events already queued are dispatched first
+        let Some(guard) = queue.prepare_read() else {
+            continue;
+        };
+        match guard.read() {
+            Ok(_) => {}
+            Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => {
+                ready.clear_ready();
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for InputMethodState {
+    fn event(
+        _state: &mut Self,
+        _registry: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for InputMethodState {
+    fn event(
+        _state: &mut Self,
+        _seat: &wl_seat::WlSeat,
+        _event: wl_seat::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpInputMethodManagerV2, ()> for InputMethodState {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwpInputMethodManagerV2,
+        _event: zwp_input_method_manager_v2::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpInputMethodV2, ()> for InputMethodState {
+    fn event(
+        state: &mut Self,
+        _input_method: &ZwpInputMethodV2,
+        event: zwp_input_method_v2::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_input_method_v2::Event::Activate => state.activation.activate(),
+            zwp_input_method_v2::Event::Deactivate => state.activation.deactivate(),
+            zwp_input_method_v2::Event::Done => {
+                if let Some(event) = state.activation.done() {
+                    tracing::debug!("Input method: {:?}", event);
+                    state.events.push(event);
+                }
+            }
+            zwp_input_method_v2::Event::Unavailable => state.unavailable = true,
+            // Surrounding text and content type are not used
+            _ => {}
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Focus changes are reported on `done`, once, and focus moving
+    /// between fields in one update is not reported.
+    #[test]
+    fn test_activation() {
+        let mut activation = Activation::default();
+        activation.activate();
+        assert!(!activation.is_active());
+        assert_eq!(activation.done(), Some(InputMethodEvent::Activated));
+        assert!(activation.is_active());
+        assert_eq!(activation.done(), None);
+
+        // Another field takes focus
+        activation.deactivate();
+        activation.activate();
+        assert_eq!(activation.done(), None);
+
+        activation.deactivate();
+        assert_eq!(activation.done(), Some(InputMethodEvent::Deactivated));
+        activation.deactivate();
+        assert_eq!(activation.done(), None);
+    }
+}
@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! In-memory journal of the last key events sent to the emission backend.
+//!
+//! Reports of missed or doubled keys are hard to reproduce. The
+//! [`VirtualKeyboard`](crate::input::VirtualKeyboard) records every key
+//! event it hands to its backend in an [`EventJournal`]: when, for which key
+//! (the layout key identifier, or `text`/the key combination for typed
+//! text), the evdev keycode and direction, the backend and whether it
+//! accepted the event. Only the last [`JOURNAL_CAPACITY`] events are kept.
+//!
+//! The journal never leaves memory on its own. It is written out on demand:
+//!
+//! - `cosboardctl dump-events [FILE]` reads it through the D-Bus
+//!   `DumpEvents` method, which the user has to accept every time.
+//! - `kill -USR1 <pid>` writes it to [`dump_path`]
+//!   (`$XDG_RUNTIME_DIR/cosboard-events.txt`); without a runtime directory
+//!   nothing is written.
+//!
+//! The journal holds what was typed, so nothing is recorded in private
+//! mode, and a dump should be read before it is attached to a bug report.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::channel::mpsc::Sender;
+use futures::SinkExt;
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::input::KeyState;
+
+/// Number of key events kept in the journal.
+pub const JOURNAL_CAPACITY: usize = 200;
+
+/// File name of the journal dump written on `SIGUSR1`.
+pub const JOURNAL_DUMP_FILE: &str = "cosboard-events.txt";
+
+/// Backend name recorded for events queued for the Wayland protocol.
+pub const WAYLAND_BACKEND: &str = "wayland";
+
+/// One key event handed to the emission backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Wall-clock time of the event, in milliseconds since the Unix epoch.
+    pub time_ms: u64,
+    /// What the event was emitted for: a key identifier, `text`, or a key
+    /// combination. `None` for events without a known source (e.g. keys
+    /// released when the surface closes).
+    pub source: Option<String>,
+    /// The evdev keycode.
+    pub keycode: u32,
+    /// Whether the key was pressed or released.
+    pub state: KeyState,
+    /// The backend the event was handed to.
+    pub backend: &'static str,
+    /// The backend's error, if it refused the event.
+    pub error: Option<String>,
+}
+
+impl JournalEntry {
+    /// Formats the entry as one line of a dump.
+    #[must_use]
+    pub fn to_line(&self) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(self.time_ms as i64)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%H:%M:%S%.3f")
+                    .to_string()
+            })
+            .unwrap_or_else(|| self.time_ms.to_string());
+        let state = match self.state {
+            KeyState::Pressed => "press",
+            KeyState::Released => "release",
+        };
+        format!(
+            "{}  {:<7} {:>4}  {:<16} {:<8} {}",
+            time,
+            state,
+            self.keycode,
+            self.source.as_deref().unwrap_or("-"),
+            self.backend,
+            self.error
+                .as_deref()
+                .map_or("ok".to_string(), |e| format!("error: {}", e)),
+        )
+    }
+}
+
+/// Ring buffer of the last [`JOURNAL_CAPACITY`] key events.
+#[derive(Debug, Clone)]
+pub struct EventJournal {
+    entries: VecDeque<JournalEntry>,
+    /// What the events recorded next are emitted for.
+    source: Option<String>,
+    /// Whether events are recorded (off in private mode).
+    enabled: bool,
+}
+
+impl Default for EventJournal {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(JOURNAL_CAPACITY),
+            source: None,
+            enabled: true,
+        }
+    }
+}
+
+/// Journal shared between the virtual keyboard and the places that dump it.
+pub type SharedJournal = Arc<Mutex<EventJournal>>;
+
+impl EventJournal {
+    /// Creates an empty journal that records events.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets what the events recorded next are emitted for, until changed.
+    pub fn set_source(&mut self, source: Option<String>) {
+        self.source = source;
+    }
+
+    /// Sets whether events are recorded. Turning recording off forgets the
+    /// recorded events.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.entries.clear();
+        }
+    }
+
+    /// Records an event, dropping the oldest one when the journal is full.
+    pub fn record(
+        &mut self,
+        keycode: u32,
+        state: KeyState,
+        backend: &'static str,
+        error: Option<String>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let time_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        if self.entries.len() == JOURNAL_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(JournalEntry {
+            time_ms,
+            source: self.source.clone(),
+            keycode,
+            state,
+            backend,
+            error,
+        });
+    }
+
+    /// Returns the recorded events, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the number of recorded events.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no event is recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Formats the journal as text, one event per line, oldest first.
+    #[must_use]
+    pub fn dump(&self) -> String {
+        let mut text = format!(
+            "# cosboard key event journal, last {} of at most {} events\n\
+             # time          event   code  source           backend  result\n",
+            self.entries.len(),
+            JOURNAL_CAPACITY
+        );
+        for entry in &self.entries {
+            let _ = writeln!(text, "{}", entry.to_line());
+        }
+        text
+    }
+}
+
+/// Returns where `SIGUSR1` writes the journal: [`JOURNAL_DUMP_FILE`] in
+/// `$XDG_RUNTIME_DIR`, which only the user can read.
+///
+/// Returns `None` without a runtime directory; the temporary directory is
+/// shared with the other users, who could plant a file there first.
+#[must_use]
+pub fn dump_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join(JOURNAL_DUMP_FILE))
+}
+
+/// Writes the journal to `path`, readable only by the user.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written.
+pub fn write_dump(journal: &SharedJournal, path: &Path) -> io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let text = journal
+        .lock()
+        .map(|journal| journal.dump())
+        .map_err(|_| io::Error::other("event journal lock poisoned"))?;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(text.as_bytes())
+}
+
+/// Writes the journal to [`dump_path`] each time the process receives
+/// `SIGUSR1`, and reports the written path (or the error) to `output`.
+///
+/// Runs until `output` is closed or the signal cannot be handled.
+pub async fn dump_on_signal(journal: SharedJournal, mut output: Sender<Result<String, String>>) {
+    let mut signals = match signal(SignalKind::user_defined1()) {
+        Ok(signals) => signals,
+        Err(e) => {
+            tracing::warn!("Cannot dump the key event journal on SIGUSR1: {}", e);
+            return;
+        }
+    };
+
+    while signals.recv().await.is_some() {
+        let result = match dump_path() {
+            Some(path) => write_dump(&journal, &path)
+                .map(|()| path.display().to_string())
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            None => Err("XDG_RUNTIME_DIR is not set".to_string()),
+        };
+        if output.send(result).await.is_err() {
+            return;
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The journal keeps the newest events and tags them with their source.
+    #[test]
+    fn test_journal_ring_buffer() {
+        let mut journal = EventJournal::new();
+        journal.set_source(Some("key_a".to_string()));
+        journal.record(30, KeyState::Pressed, WAYLAND_BACKEND, None);
+        journal.set_source(None);
+        for keycode in 0..JOURNAL_CAPACITY as u32 {
+            journal.record(keycode, KeyState::Released, "uinput", None);
+        }
+
+        assert_eq!(journal.len(), JOURNAL_CAPACITY);
+        let first = journal.entries().next().unwrap();
+        assert_eq!(first.keycode, 0);
+        assert_eq!(first.source, None);
+
+        journal.set_source(Some("enter".to_string()));
+        journal.record(28, KeyState::Pressed, "x11", Some("no display".to_string()));
+        let last = journal.entries().last().unwrap();
+        assert_eq!(last.source.as_deref(), Some("enter"));
+
+        let dump = journal.dump();
+        assert_eq!(dump.lines().count(), JOURNAL_CAPACITY + 2);
+        assert!(dump.lines().last().unwrap().contains("error: no display"));
+    }
+
+    /// Test: Nothing is recorded while the journal is disabled.
+    #[test]
+    fn test_journal_disabled() {
+        let mut journal = EventJournal::new();
+        journal.record(30, KeyState::Pressed, WAYLAND_BACKEND, None);
+        journal.set_enabled(false);
+        assert!(journal.is_empty());
+
+        journal.record(30, KeyState::Released, WAYLAND_BACKEND, None);
+        assert!(journal.is_empty());
+    }
+}
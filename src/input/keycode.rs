@@ -20,9 +20,18 @@
 //!
 //! 1. Check if string starts with `"U+"` for Unicode codepoint format
 //! 2. Otherwise, treat as XKB keysym name
+//!
+//! # Command Keys
+//!
+//! Keysym values starting with `"cosboard:"` (e.g. `"cosboard:palette"`) are
+//! command keys. They trigger keyboard-internal actions instead of emitting
+//! input; use [`parse_command`] to detect them before emission.
 
 use crate::layout::KeyCode;
 
+/// Prefix marking a keysym value as a keyboard-internal command.
+pub const COMMAND_PREFIX: &str = "cosboard:";
+
 /// A resolved keycode ready for input emission.
 ///
 /// This enum represents the different types of keycodes that can be
@@ -82,6 +91,24 @@ pub fn parse_keycode(code: &KeyCode) -> Option<ResolvedKeycode> {
     }
 }
 
+/// Returns the command name if `code` is a command key.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use cosboard::input::parse_command;
+/// use cosboard::layout::KeyCode;
+///
+/// let code = KeyCode::Keysym("cosboard:palette".to_string());
+/// assert_eq!(parse_command(&code), Some("palette"));
+/// ```
+pub fn parse_command(code: &KeyCode) -> Option<&str> {
+    match code {
+        KeyCode::Keysym(s) => s.strip_prefix(COMMAND_PREFIX).filter(|name| !name.is_empty()),
+        KeyCode::Unicode(_) => None,
+    }
+}
+
 /// Parses a keysym string, detecting the format automatically.
 ///
 /// Format detection priority:
@@ -195,6 +222,17 @@ mod tests {
         assert_eq!(result, Some(ResolvedKeycode::UnicodeCodepoint(0x10FFFF)));
     }
 
+    /// Test command key detection
+    #[test]
+    fn test_parse_command() {
+        let code = KeyCode::Keysym("cosboard:palette".to_string());
+        assert_eq!(parse_command(&code), Some("palette"));
+
+        assert_eq!(parse_command(&KeyCode::Keysym("cosboard:".to_string())), None);
+        assert_eq!(parse_command(&KeyCode::Keysym("Return".to_string())), None);
+        assert_eq!(parse_command(&KeyCode::Unicode('c')), None);
+    }
+
     /// Test ResolvedKeycode Debug implementation
     #[test]
     fn test_resolved_keycode_debug() {
@@ -9,7 +9,17 @@
 //!
 //! - **Keycode parsing**: Parse keycodes from layout `code` field in multiple formats
 //! - **Modifier state management**: Track active modifiers with one-shot, toggle, and hold modes
+//! - **Combos**: Press and release modifier keys around held keys in a consistent order
 //! - **Virtual keyboard**: Emit key events via Wayland's `zwp_virtual_keyboard_v1` protocol
+//! - **Backend selection**: Fall back to X11 XTEST (`x11` feature) or uinput (`uinput` feature)
+//!   where that protocol is unavailable
+//! - **Focus arbitration**: Route key input to the keyboard's own text fields instead of the client
+//! - **Chords**: Detect layout-defined key combinations pressed together on the touchscreen
+//! - **Multi-tap**: Cycle a letter through its accents by tapping it repeatedly
+//! - **Piano mode**: Type every key a finger slides across on panels that enable it
+//! - **Gamepad navigation**: Move a focus ring and press keys with a gamepad or remote
+//! - **Input method**: Follow text field focus to show and hide the keyboard automatically
+//! - **Event journal**: The last key events sent to the backend, dumped to debug lost keys
 //!
 //! # Keycode Formats
 //!
@@ -77,13 +87,38 @@
 //! ```
 
 // Sub-modules
+pub mod backend;
+pub mod chord;
+pub mod combo;
+pub mod focus;
+pub mod gamepad;
+pub mod ime;
+pub mod journal;
 pub mod keycode;
 pub mod modifier;
+pub mod multi_tap;
+pub mod slide;
+#[cfg(feature = "uinput")]
+pub mod uinput;
 pub mod virtual_keyboard;
+#[cfg(feature = "x11")]
+pub mod xtest;
 
 // Re-export public API
-pub use keycode::{parse_keycode, ResolvedKeycode};
-pub use modifier::ModifierState;
+pub use backend::{
+    create_key_sink, select_backend, uinput_writable, wayland_globals, Backend, KeySink,
+    SessionInfo, UINPUT_PATH,
+};
+pub use chord::{ChordEvent, ChordMatcher, CHORD_WINDOW};
+pub use combo::{ComboTracker, KeyCombo};
+pub use focus::{InputFocus, KeyRoute};
+pub use gamepad::{NavCommand, NavDirection, NavigationSettings};
+pub use ime::{Activation, InputMethodEvent};
+pub use journal::{EventJournal, JournalEntry, SharedJournal, JOURNAL_CAPACITY};
+pub use keycode::{parse_command, parse_keycode, ResolvedKeycode, COMMAND_PREFIX};
+pub use modifier::{ModifierMode, ModifierState};
+pub use multi_tap::{MultiTap, MultiTapEvent};
+pub use slide::{SlideStep, SlideTracker, SLIDE_DEBOUNCE};
 pub use virtual_keyboard::{keycodes, KeyEvent, KeyState, VirtualKeyboard};
 
 // ============================================================================
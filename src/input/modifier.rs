@@ -10,6 +10,21 @@
 //! - **Toggle**: Modifier stays active until explicitly deactivated
 //! - **Hold**: Modifier is active only while the key is held down
 //!
+//! [`ModifierState::mode`] tells the three apart for display, as one-shot,
+//! locked (toggled) or held ([`ModifierMode`]). Double-tapping a one-shot
+//! modifier locks it until it is tapped again ([`ModifierState::tap_sticky`]).
+//!
+//! It also tracks **caps-word**: double-tapping Shift capitalizes letters
+//! until the end of the current word (a space, punctuation or any other key
+//! that does not continue a word), after which it turns itself off.
+//!
+//! **Caps Lock** ([`Modifier::CapsLock`]) is kept apart from the others. Its
+//! key is tapped in the client application, whose keymap then capitalizes
+//! letters, so it is never pressed around other keys: it is latched by one
+//! tap and released by the next ([`ModifierState::toggle_caps_lock`]), is
+//! always [`ModifierMode::Locked`] while on, and is not among
+//! [`ModifierState::get_active_modifiers`].
+//!
 //! # Example
 //!
 //! ```rust,ignore
@@ -27,8 +42,27 @@
 //! state.clear_sticky();
 //! ```
 
+use crate::input::keycode::ResolvedKeycode;
 use crate::layout::Modifier;
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Maximum time between two Shift taps for them to enable caps-word.
+pub const CAPS_WORD_DOUBLE_TAP: Duration = Duration::from_millis(300);
+
+/// Maximum time between two taps of a one-shot modifier for them to lock it.
+pub const MODIFIER_LOCK_DOUBLE_TAP: Duration = Duration::from_millis(300);
+
+/// How an active modifier stays active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModifierMode {
+    /// Cleared after the next key press.
+    OneShot,
+    /// Active until it is tapped again.
+    Locked,
+    /// Active while its key is held down.
+    Held,
+}
 
 /// Tracks the state of modifier keys during keyboard input.
 ///
@@ -41,6 +75,21 @@ pub struct ModifierState {
 
     /// Set of modifiers that should be cleared after the next key (one-shot)
     sticky: HashSet<Modifier>,
+
+    /// Set of modifiers active only while their key is held down
+    held: HashSet<Modifier>,
+
+    /// Last one-shot modifier tapped and when (for double-tap-to-lock)
+    last_sticky_tap: Option<(Modifier, Instant)>,
+
+    /// Whether caps-word is active
+    caps_word: bool,
+
+    /// When Shift was last tapped (for double-tap detection)
+    last_shift_tap: Option<Instant>,
+
+    /// Whether Caps Lock is latched
+    caps_lock: bool,
 }
 
 impl ModifierState {
@@ -50,6 +99,11 @@ impl ModifierState {
         Self {
             active: HashSet::new(),
             sticky: HashSet::new(),
+            held: HashSet::new(),
+            last_sticky_tap: None,
+            caps_word: false,
+            last_shift_tap: None,
+            caps_lock: false,
         }
     }
 
@@ -77,7 +131,13 @@ impl ModifierState {
     /// state.activate(Modifier::Ctrl, false);
     /// ```
     pub fn activate(&mut self, modifier: Modifier, stickyrelease: bool) {
+        // Caps Lock is only ever latched
+        if modifier == Modifier::CapsLock {
+            self.caps_lock = true;
+            return;
+        }
         self.active.insert(modifier);
+        self.held.remove(&modifier);
 
         if stickyrelease {
             self.sticky.insert(modifier);
@@ -87,6 +147,17 @@ impl ModifierState {
         }
     }
 
+    /// Activates a modifier while its key is held down (hold behavior).
+    ///
+    /// The modifier stays active until it is deactivated when the key is
+    /// released; [`mode`](Self::mode) reports it as held meanwhile.
+    pub fn hold(&mut self, modifier: Modifier) {
+        self.activate(modifier, false);
+        if modifier != Modifier::CapsLock {
+            self.held.insert(modifier);
+        }
+    }
+
     /// Deactivates a modifier.
     ///
     /// This removes the modifier from both the active set and the sticky set.
@@ -95,8 +166,66 @@ impl ModifierState {
     ///
     /// * `modifier` - The modifier to deactivate
     pub fn deactivate(&mut self, modifier: Modifier) {
+        if modifier == Modifier::CapsLock {
+            self.caps_lock = false;
+        }
         self.active.remove(&modifier);
         self.sticky.remove(&modifier);
+        self.held.remove(&modifier);
+    }
+
+    /// Records a tap of a one-shot modifier key.
+    ///
+    /// The first tap activates the modifier as one-shot. A second tap within
+    /// [`MODIFIER_LOCK_DOUBLE_TAP`], while it is still pending, locks it, and
+    /// tapping a locked modifier deactivates it.
+    ///
+    /// # Returns
+    ///
+    /// The mode the modifier is in after the tap, `None` if it is inactive.
+    pub fn tap_sticky(&mut self, modifier: Modifier, now: Instant) -> Option<ModifierMode> {
+        let double_tap = self.last_sticky_tap.is_some_and(|(last_modifier, last)| {
+            last_modifier == modifier
+                && now.saturating_duration_since(last) <= MODIFIER_LOCK_DOUBLE_TAP
+        });
+
+        match self.mode(modifier) {
+            Some(ModifierMode::Locked) => {
+                self.deactivate(modifier);
+                self.last_sticky_tap = None;
+            }
+            Some(ModifierMode::OneShot) if double_tap => {
+                self.activate(modifier, false);
+                self.last_sticky_tap = None;
+            }
+            _ => {
+                self.activate(modifier, true);
+                self.last_sticky_tap = Some((modifier, now));
+            }
+        }
+        self.mode(modifier)
+    }
+
+    /// Returns how a modifier is active, or `None` if it is inactive.
+    #[must_use]
+    pub fn mode(&self, modifier: Modifier) -> Option<ModifierMode> {
+        if modifier == Modifier::CapsLock {
+            self.caps_lock.then_some(ModifierMode::Locked)
+        } else if !self.active.contains(&modifier) {
+            None
+        } else if self.held.contains(&modifier) {
+            Some(ModifierMode::Held)
+        } else if self.sticky.contains(&modifier) {
+            Some(ModifierMode::OneShot)
+        } else {
+            Some(ModifierMode::Locked)
+        }
+    }
+
+    /// Checks if any modifier is active only while its key is held down.
+    #[must_use]
+    pub fn has_held_modifiers(&self) -> bool {
+        !self.held.is_empty()
     }
 
     /// Toggles a modifier's state.
@@ -114,7 +243,7 @@ impl ModifierState {
     ///
     /// `true` if the modifier is now active, `false` if it is now inactive
     pub fn toggle(&mut self, modifier: Modifier, stickyrelease: bool) -> bool {
-        if self.active.contains(&modifier) {
+        if self.is_active(modifier) {
             self.deactivate(modifier);
             false
         } else {
@@ -134,12 +263,16 @@ impl ModifierState {
     /// `true` if the modifier is active, `false` otherwise
     #[must_use]
     pub fn is_active(&self, modifier: Modifier) -> bool {
+        if modifier == Modifier::CapsLock {
+            return self.caps_lock;
+        }
         self.active.contains(&modifier)
     }
 
     /// Returns a list of all currently active modifiers.
     ///
     /// The modifiers are returned in a consistent order (sorted by enum value).
+    /// Caps Lock is not included, since it is never pressed with keys.
     ///
     /// # Returns
     ///
@@ -180,10 +313,15 @@ impl ModifierState {
 
     /// Clears all modifiers (both active and sticky).
     ///
-    /// This can be used to reset the modifier state completely.
+    /// This can be used to reset the modifier state completely. Caps Lock is
+    /// released too; the caller unlocks it in the client application.
     pub fn clear_all(&mut self) {
         self.active.clear();
         self.sticky.clear();
+        self.held.clear();
+        self.last_sticky_tap = None;
+        self.cancel_caps_word();
+        self.caps_lock = false;
     }
 
     /// Checks if any modifiers are currently active.
@@ -201,6 +339,109 @@ impl ModifierState {
     pub fn active_count(&self) -> usize {
         self.active.len()
     }
+
+    // ========================================================================
+    // Caps Lock
+    // ========================================================================
+
+    /// Records a tap of the Caps Lock key: latches Caps Lock, or releases
+    /// it if it was latched.
+    ///
+    /// # Returns
+    ///
+    /// `true` if Caps Lock is now latched.
+    pub fn toggle_caps_lock(&mut self) -> bool {
+        self.caps_lock = !self.caps_lock;
+        self.caps_lock
+    }
+
+    /// Checks if Caps Lock is latched.
+    #[must_use]
+    pub fn is_caps_lock(&self) -> bool {
+        self.caps_lock
+    }
+
+    // ========================================================================
+    // Caps-word
+    // ========================================================================
+
+    /// Records a Shift tap and enables caps-word on a double tap.
+    ///
+    /// A second tap within [`CAPS_WORD_DOUBLE_TAP`] of the first enables
+    /// caps-word and deactivates Shift itself, since caps-word applies Shift
+    /// to letters on its own. Tapping Shift while caps-word is active turns
+    /// it off.
+    ///
+    /// # Returns
+    ///
+    /// `true` if this tap enabled caps-word, in which case the caller should
+    /// not apply the tap's normal modifier behavior.
+    pub fn register_shift_tap(&mut self, now: Instant) -> bool {
+        if self.caps_word {
+            self.cancel_caps_word();
+            return false;
+        }
+
+        let double_tap = self
+            .last_shift_tap
+            .is_some_and(|last| now.saturating_duration_since(last) <= CAPS_WORD_DOUBLE_TAP);
+
+        if double_tap {
+            self.caps_word = true;
+            self.last_shift_tap = None;
+            self.deactivate(Modifier::Shift);
+        } else {
+            self.last_shift_tap = Some(now);
+        }
+        double_tap
+    }
+
+    /// Checks if caps-word is active.
+    #[must_use]
+    pub fn is_caps_word(&self) -> bool {
+        self.caps_word
+    }
+
+    /// Turns caps-word off and forgets any pending Shift tap.
+    pub fn cancel_caps_word(&mut self) {
+        self.caps_word = false;
+        self.last_shift_tap = None;
+    }
+
+    /// Applies caps-word to a key that is about to be typed.
+    ///
+    /// Letters are shifted. Digits, `-`, `_`, BackSpace and Delete continue
+    /// the word unshifted. Any other key (space, punctuation, Return, ...)
+    /// ends the word and disables caps-word.
+    ///
+    /// # Returns
+    ///
+    /// `true` if Shift should be applied to this key.
+    pub fn apply_caps_word(&mut self, key: &ResolvedKeycode) -> bool {
+        if !self.caps_word {
+            return false;
+        }
+
+        let c = match key {
+            ResolvedKeycode::Character(c) => Some(*c),
+            ResolvedKeycode::UnicodeCodepoint(cp) => char::from_u32(*cp),
+            ResolvedKeycode::Keysym(name) => {
+                if matches!(name.as_str(), "BackSpace" | "Delete") {
+                    return false;
+                }
+                None
+            }
+        };
+
+        match c {
+            Some(c) if c.is_alphabetic() => true,
+            Some(c) if c.is_numeric() || c == '-' || c == '_' => false,
+            _ => {
+                self.cancel_caps_word();
+                false
+            }
+        }
+    }
 }
 
 // ============================================================================
@@ -209,6 +450,10 @@ impl ModifierState {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use proptest::prelude::*;
+
     use super::*;
 
     /// Test basic activation and deactivation
@@ -278,6 +523,7 @@ mod tests {
                 Modifier::Ctrl => 1,
                 Modifier::Alt => 2,
                 Modifier::Super => 3,
+                Modifier::CapsLock => 4,
             })
             .collect();
 
@@ -306,6 +552,131 @@ mod tests {
         assert_eq!(state.active_count(), 0);
     }
 
+    /// Test: Double-tapping Shift enables caps-word; a slow second tap does not
+    #[test]
+    fn test_caps_word_double_tap() {
+        let start = Instant::now();
+        let mut state = ModifierState::new();
+
+        state.activate(Modifier::Shift, true);
+        assert!(!state.register_shift_tap(start));
+        assert!(!state.register_shift_tap(start + Duration::from_millis(400)));
+        assert!(!state.is_caps_word());
+
+        assert!(state.register_shift_tap(start + Duration::from_millis(600)));
+        assert!(state.is_caps_word());
+        assert!(!state.is_active(Modifier::Shift));
+
+        // Tapping Shift again turns it off
+        assert!(!state.register_shift_tap(start + Duration::from_millis(700)));
+        assert!(!state.is_caps_word());
+    }
+
+    /// Test: Caps-word shifts letters and ends at the end of the word
+    #[test]
+    fn test_caps_word_auto_disable() {
+        let now = Instant::now();
+        let mut state = ModifierState::new();
+        state.register_shift_tap(now);
+        state.register_shift_tap(now);
+
+        assert!(state.apply_caps_word(&ResolvedKeycode::Character('a')));
+        assert!(!state.apply_caps_word(&ResolvedKeycode::Character('1')));
+        assert!(!state.apply_caps_word(&ResolvedKeycode::Character('_')));
+        assert!(!state.apply_caps_word(&ResolvedKeycode::Keysym("BackSpace".to_string())));
+        assert!(state.is_caps_word());
+
+        assert!(!state.apply_caps_word(&ResolvedKeycode::Character(' ')));
+        assert!(!state.is_caps_word());
+        assert!(!state.apply_caps_word(&ResolvedKeycode::Character('b')));
+
+        state.register_shift_tap(now);
+        state.register_shift_tap(now);
+        assert!(!state.apply_caps_word(&ResolvedKeycode::Character('.')));
+        assert!(!state.is_caps_word());
+    }
+
+    /// Test: Modes tell one-shot, locked and held modifiers apart
+    #[test]
+    fn test_modifier_modes() {
+        let mut state = ModifierState::new();
+        assert_eq!(state.mode(Modifier::Shift), None);
+
+        state.activate(Modifier::Shift, true);
+        state.activate(Modifier::Ctrl, false);
+        state.hold(Modifier::Alt);
+        assert_eq!(state.mode(Modifier::Shift), Some(ModifierMode::OneShot));
+        assert_eq!(state.mode(Modifier::Ctrl), Some(ModifierMode::Locked));
+        assert_eq!(state.mode(Modifier::Alt), Some(ModifierMode::Held));
+        assert!(state.has_held_modifiers());
+
+        // Held modifiers are not one-shot and go when their key is released
+        state.clear_sticky();
+        assert_eq!(state.mode(Modifier::Alt), Some(ModifierMode::Held));
+        state.deactivate(Modifier::Alt);
+        assert_eq!(state.mode(Modifier::Alt), None);
+        assert!(!state.has_held_modifiers());
+    }
+
+    /// Test: Double-tapping a one-shot modifier locks it, a slow second tap
+    /// does not, and tapping a locked modifier releases it
+    #[test]
+    fn test_double_tap_to_lock() {
+        let start = Instant::now();
+        let mut state = ModifierState::new();
+
+        assert_eq!(
+            state.tap_sticky(Modifier::Ctrl, start),
+            Some(ModifierMode::OneShot)
+        );
+        let slow = start + Duration::from_millis(400);
+        assert_eq!(
+            state.tap_sticky(Modifier::Ctrl, slow),
+            Some(ModifierMode::OneShot)
+        );
+
+        let quick = slow + Duration::from_millis(200);
+        assert_eq!(
+            state.tap_sticky(Modifier::Ctrl, quick),
+            Some(ModifierMode::Locked)
+        );
+        state.clear_sticky();
+        assert!(state.is_active(Modifier::Ctrl));
+
+        let later = quick + Duration::from_millis(1000);
+        assert_eq!(state.tap_sticky(Modifier::Ctrl, later), None);
+
+        // A tap of another modifier in between is no double tap
+        state.tap_sticky(Modifier::Alt, start);
+        state.tap_sticky(Modifier::Super, start);
+        assert_eq!(
+            state.tap_sticky(Modifier::Alt, start),
+            Some(ModifierMode::OneShot)
+        );
+    }
+
+    /// Test: Caps Lock latches until tapped again, stays locked through
+    /// one-shot clears, and is never pressed with keys
+    #[test]
+    fn test_caps_lock() {
+        let mut state = ModifierState::new();
+        assert_eq!(state.mode(Modifier::CapsLock), None);
+
+        assert!(state.toggle_caps_lock());
+        state.activate(Modifier::Shift, true);
+        state.clear_sticky();
+        assert!(state.is_active(Modifier::CapsLock));
+        assert_eq!(state.mode(Modifier::CapsLock), Some(ModifierMode::Locked));
+        assert!(state.get_active_modifiers().is_empty());
+
+        assert!(!state.toggle_caps_lock());
+        assert!(!state.is_caps_lock());
+
+        state.toggle_caps_lock();
+        state.clear_all();
+        assert!(!state.is_caps_lock());
+    }
+
     /// Test Default trait implementation
     #[test]
     fn test_default() {
@@ -314,4 +685,86 @@ mod tests {
         assert!(!state.has_active_modifiers());
         assert_eq!(state.active_count(), 0);
     }
+
+    // ========================================================================
+    // Property tests
+    // ========================================================================
+
+    const MODIFIERS: [Modifier; 4] = [Modifier::Shift, Modifier::Ctrl, Modifier::Alt, Modifier::Super];
+
+    /// One operation on the modifier state.
+    #[derive(Debug, Clone)]
+    enum Op {
+        Activate(Modifier, bool),
+        Deactivate(Modifier),
+        Toggle(Modifier, bool),
+        ClearSticky,
+        ClearAll,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        let modifier = prop::sample::select(MODIFIERS.to_vec());
+        prop_oneof![
+            (modifier.clone(), any::<bool>()).prop_map(|(m, sticky)| Op::Activate(m, sticky)),
+            modifier.clone().prop_map(Op::Deactivate),
+            (modifier, any::<bool>()).prop_map(|(m, sticky)| Op::Toggle(m, sticky)),
+            Just(Op::ClearSticky),
+            Just(Op::ClearAll),
+        ]
+    }
+
+    proptest! {
+        /// Property: The state matches a simple model (active modifiers and
+        /// whether each is one-shot) after any sequence of operations;
+        /// `clear_sticky` never removes toggles and `clear_all` leaves no
+        /// modifier stuck.
+        #[test]
+        fn prop_modifier_state_model(ops in prop::collection::vec(op(), 0..64)) {
+            let mut state = ModifierState::new();
+            // Active modifiers, mapped to whether they are one-shot
+            let mut model: HashMap<Modifier, bool> = HashMap::new();
+
+            for op in &ops {
+                match *op {
+                    Op::Activate(m, sticky) => {
+                        state.activate(m, sticky);
+                        model.insert(m, sticky);
+                    }
+                    Op::Deactivate(m) => {
+                        state.deactivate(m);
+                        model.remove(&m);
+                    }
+                    Op::Toggle(m, sticky) => {
+                        let now_active = state.toggle(m, sticky);
+                        prop_assert_eq!(now_active, !model.contains_key(&m));
+                        if now_active {
+                            model.insert(m, sticky);
+                        } else {
+                            model.remove(&m);
+                        }
+                    }
+                    Op::ClearSticky => {
+                        let toggles: Vec<Modifier> =
+                            model.iter().filter(|(_, sticky)| !**sticky).map(|(m, _)| *m).collect();
+                        state.clear_sticky();
+                        model.retain(|_, sticky| !*sticky);
+                        for m in toggles {
+                            prop_assert!(state.is_active(m), "clear_sticky removed toggle {:?}", m);
+                        }
+                    }
+                    Op::ClearAll => {
+                        state.clear_all();
+                        model.clear();
+                        prop_assert!(!state.has_active_modifiers());
+                    }
+                }
+
+                for m in MODIFIERS {
+                    prop_assert_eq!(state.is_active(m), model.contains_key(&m), "{:?} after {:?}", m, op);
+                    prop_assert_eq!(state.is_sticky(m), model.get(&m) == Some(&true), "{:?} after {:?}", m, op);
+                }
+                prop_assert_eq!(state.active_count(), model.len());
+            }
+        }
+    }
 }
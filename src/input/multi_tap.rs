@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Multi-tap: characters reached by tapping a key repeatedly.
+//!
+//! A layout with `accent_cycling` (see [`AccentCycling`]) lets letters be
+//! tapped again to turn them into their accented versions, as an
+//! alternative to the long-press popup: `e`, then `é` on the second tap,
+//! `è` on the third, and back to `e` after the last accent.
+//!
+//! The first tap types the letter as usual. Each further tap of the same key
+//! within the timeout is not typed; the previous character is replaced
+//! instead (one BackSpace, then the next character of the cycle). Any other
+//! key, or a pause longer than the timeout, ends the cycle.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::layout::AccentCycling;
+
+/// Accents cycled through when a layout enables accent cycling without
+/// listing its own.
+pub const DEFAULT_ACCENTS: &[(char, &str)] = &[
+    ('a', "àáâäãåæ"),
+    ('c', "ç"),
+    ('e', "éèêë"),
+    ('i', "íìîï"),
+    ('n', "ñ"),
+    ('o', "óòôöõøœ"),
+    ('u', "úùûü"),
+    ('y', "ýÿ"),
+];
+
+/// Outcome of feeding a tap to the [`MultiTap`] tracker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiTapEvent {
+    /// Type the key as usual.
+    Pass,
+    /// Do not type the key: replace the last typed character with this one.
+    Replace(char),
+}
+
+/// The cycle being tapped through.
+#[derive(Debug, Clone)]
+struct Cycle {
+    /// Key tapped
+    identifier: String,
+    /// Characters of the cycle, the typed letter first
+    chars: Vec<char>,
+    /// Index of the character typed last
+    index: usize,
+    /// When the key was last tapped
+    tapped: Instant,
+}
+
+/// Tracks repeated taps of keys with a character cycle.
+#[derive(Debug, Clone, Default)]
+pub struct MultiTap {
+    /// Cycle of each lowercase letter, without the letter itself
+    cycles: HashMap<char, Vec<char>>,
+    /// Longest pause between taps of a cycle
+    timeout: Duration,
+    /// Cycle in progress
+    current: Option<Cycle>,
+}
+
+impl MultiTap {
+    /// Creates a tracker for a layout's accent cycling; `None` disables it.
+    #[must_use]
+    pub fn new(cycling: Option<&AccentCycling>) -> Self {
+        let Some(cycling) = cycling else {
+            return Self::default();
+        };
+        let cycles = if cycling.cycles.is_empty() {
+            DEFAULT_ACCENTS
+                .iter()
+                .map(|(letter, accents)| (*letter, accents.chars().collect()))
+                .collect()
+        } else {
+            // Keys that are not a single character are reported by validation
+            cycling
+                .cycles
+                .iter()
+                .filter_map(|(letter, accents)| {
+                    let mut chars = letter.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(letter), None) => Some((letter, accents.chars().collect())),
+                        _ => None,
+                    }
+                })
+                .collect()
+        };
+        Self {
+            cycles,
+            timeout: Duration::from_millis(cycling.timeout_ms),
+            current: None,
+        }
+    }
+
+    /// Returns `true` if any key cycles.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        !self.cycles.is_empty()
+    }
+
+    /// Feeds a tap of the key `identifier`, which types `typed` (with Shift
+    /// applied).
+    pub fn press(&mut self, identifier: &str, typed: char, now: Instant) -> MultiTapEvent {
+        if let Some(ref mut cycle) = self.current {
+            let repeated = cycle.identifier == identifier
+                && now.saturating_duration_since(cycle.tapped) <= self.timeout;
+            if repeated {
+                cycle.index = (cycle.index + 1) % cycle.chars.len();
+                cycle.tapped = now;
+                return MultiTapEvent::Replace(cycle.chars[cycle.index]);
+            }
+        }
+
+        self.current = self.cycle_of(typed).map(|chars| Cycle {
+            identifier: identifier.to_string(),
+            chars,
+            index: 0,
+            tapped: now,
+        });
+        MultiTapEvent::Pass
+    }
+
+    /// Ends the cycle in progress, e.g. when another key is typed.
+    pub fn reset(&mut self) {
+        self.current = None;
+    }
+
+    /// Returns the cycle starting at `typed`, in its case.
+    fn cycle_of(&self, typed: char) -> Option<Vec<char>> {
+        let lower = typed.to_lowercase().next().unwrap_or(typed);
+        let accents = self
+            .cycles
+            .get(&lower)
+            .filter(|accents| !accents.is_empty())?;
+        let upper = typed.is_uppercase();
+        let chars = std::iter::once(typed)
+            .chain(accents.iter().map(|&accent| {
+                if upper {
+                    accent.to_uppercase().next().unwrap_or(accent)
+                } else {
+                    accent
+                }
+            }))
+            .collect();
+        Some(chars)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycling(cycles: &[(&str, &str)]) -> AccentCycling {
+        AccentCycling {
+            cycles: cycles
+                .iter()
+                .map(|(letter, accents)| (letter.to_string(), accents.to_string()))
+                .collect(),
+            ..AccentCycling::default()
+        }
+    }
+
+    /// Test: Repeated taps within the timeout cycle through the accents and
+    /// wrap around; a pause or another key starts over.
+    #[test]
+    fn test_cycle() {
+        let mut taps = MultiTap::new(Some(&AccentCycling::default()));
+        let start = Instant::now();
+        let later = |ms| start + Duration::from_millis(ms);
+
+        assert_eq!(taps.press("e", 'e', start), MultiTapEvent::Pass);
+        assert_eq!(
+            taps.press("e", 'e', later(200)),
+            MultiTapEvent::Replace('é')
+        );
+        assert_eq!(
+            taps.press("e", 'e', later(400)),
+            MultiTapEvent::Replace('è')
+        );
+        assert_eq!(
+            taps.press("e", 'e', later(600)),
+            MultiTapEvent::Replace('ê')
+        );
+        assert_eq!(
+            taps.press("e", 'e', later(800)),
+            MultiTapEvent::Replace('ë')
+        );
+        assert_eq!(
+            taps.press("e", 'e', later(1000)),
+            MultiTapEvent::Replace('e')
+        );
+
+        // Too slow: a new letter
+        assert_eq!(taps.press("e", 'e', later(5000)), MultiTapEvent::Pass);
+        assert_eq!(taps.press("t", 't', later(5100)), MultiTapEvent::Pass);
+        assert_eq!(taps.press("t", 't', later(5200)), MultiTapEvent::Pass);
+        assert_eq!(taps.press("e", 'e', later(5300)), MultiTapEvent::Pass);
+        taps.reset();
+        assert_eq!(taps.press("e", 'e', later(5400)), MultiTapEvent::Pass);
+    }
+
+    /// Test: Capitals cycle through capital accents, and layouts can list
+    /// their own cycles.
+    #[test]
+    fn test_layout_cycles() {
+        let mut taps = MultiTap::new(Some(&cycling(&[("o", "ôö"), ("ae", "x")])));
+        let now = Instant::now();
+        assert!(taps.is_enabled());
+        assert_eq!(taps.press("o", 'O', now), MultiTapEvent::Pass);
+        assert_eq!(taps.press("o", 'O', now), MultiTapEvent::Replace('Ô'));
+        assert_eq!(taps.press("e", 'e', now), MultiTapEvent::Pass);
+        assert_eq!(taps.press("e", 'e', now), MultiTapEvent::Pass);
+
+        let mut disabled = MultiTap::new(None);
+        assert!(!disabled.is_enabled());
+        assert_eq!(disabled.press("e", 'e', now), MultiTapEvent::Pass);
+        assert_eq!(disabled.press("e", 'e', now), MultiTapEvent::Pass);
+    }
+}
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Slide-to-type tracking for piano mode panels.
+//!
+//! On a panel with `"piano": true`, keys are not only typed when tapped:
+//! after pressing a key, sliding the finger across the panel types every key
+//! the finger enters, like running a finger over piano keys.
+//!
+//! The key that started the slide is held down as usual until the finger
+//! leaves it; it is then released, and every key entered afterwards is tapped
+//! (pressed and released) immediately. A key is not typed again while it is
+//! within [`SLIDE_DEBOUNCE`] of its last activation, so jitter along a key
+//! border does not produce repeats.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Minimum time between two activations of the same key during a slide.
+pub const SLIDE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// What to do when the finger enters a key during a slide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlideStep {
+    /// Key that started the slide and must now be released, if still held
+    pub release_origin: Option<String>,
+    /// Key to tap
+    pub tap: String,
+}
+
+/// Tracks a finger sliding across a piano mode panel.
+#[derive(Debug, Clone, Default)]
+pub struct SlideTracker {
+    /// Whether a slide is in progress (a key is pressed and not yet lifted)
+    active: bool,
+
+    /// Key that started the slide, while it is still held down
+    origin: Option<String>,
+
+    /// Key that started the slide after it was released by the slide
+    released_origin: Option<String>,
+
+    /// When each key was last activated during this slide
+    last_fired: HashMap<String, Instant>,
+}
+
+impl SlideTracker {
+    /// Creates an idle tracker.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` while a slide is in progress.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Starts a slide from a key pressed on a piano mode panel.
+    pub fn begin(&mut self, identifier: &str, now: Instant) {
+        self.active = true;
+        self.origin = Some(identifier.to_string());
+        self.released_origin = None;
+        self.last_fired.clear();
+        self.last_fired.insert(identifier.to_string(), now);
+    }
+
+    /// Handles the finger entering a key.
+    ///
+    /// Returns `None` when no slide is in progress or the key is debounced.
+    pub fn enter(&mut self, identifier: &str, now: Instant) -> Option<SlideStep> {
+        if !self.active {
+            return None;
+        }
+
+        let debounced = self
+            .last_fired
+            .get(identifier)
+            .is_some_and(|last| now.saturating_duration_since(*last) < SLIDE_DEBOUNCE);
+        if debounced {
+            return None;
+        }
+        self.last_fired.insert(identifier.to_string(), now);
+
+        Some(SlideStep {
+            release_origin: self.origin.take(),
+            tap: identifier.to_string(),
+        })
+    }
+
+    /// Ends the slide when the finger is lifted.
+    pub fn end(&mut self) {
+        self.active = false;
+        self.origin = None;
+        self.last_fired.clear();
+    }
+
+    /// Records that the slide released the key that started it.
+    ///
+    /// The key's own release may still arrive when the finger is lifted over
+    /// it; [`take_stale_release`](Self::take_stale_release) then swallows it.
+    pub fn origin_released(&mut self, identifier: &str) {
+        self.released_origin = Some(identifier.to_string());
+    }
+
+    /// Returns `true` (once) for a late release of the key that started the
+    /// slide, which the slide has already released.
+    pub fn take_stale_release(&mut self, identifier: &str) -> bool {
+        if self.released_origin.as_deref() == Some(identifier) {
+            self.released_origin = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Sliding releases the origin key once and taps each entered key
+    #[test]
+    fn test_slide_taps_keys() {
+        let now = Instant::now();
+        let mut tracker = SlideTracker::new();
+
+        // Hovering without a press does nothing
+        assert_eq!(tracker.enter("a", now), None);
+
+        tracker.begin("a", now);
+        assert_eq!(
+            tracker.enter("s", now),
+            Some(SlideStep {
+                release_origin: Some("a".to_string()),
+                tap: "s".to_string(),
+            })
+        );
+        assert_eq!(
+            tracker.enter("d", now),
+            Some(SlideStep {
+                release_origin: None,
+                tap: "d".to_string(),
+            })
+        );
+
+        // The origin's own release arrives late and is swallowed once
+        assert!(!tracker.take_stale_release("a"));
+        tracker.origin_released("a");
+        assert!(tracker.take_stale_release("a"));
+        assert!(!tracker.take_stale_release("a"));
+
+        tracker.end();
+        assert!(!tracker.is_active());
+        assert_eq!(tracker.enter("f", now), None);
+    }
+
+    /// Test: Re-entering a key within the debounce window does not repeat it
+    #[test]
+    fn test_slide_debounce() {
+        let now = Instant::now();
+        let mut tracker = SlideTracker::new();
+        tracker.begin("a", now);
+
+        assert!(tracker.enter("s", now).is_some());
+        assert!(tracker.enter("a", now + Duration::from_millis(50)).is_none());
+        assert!(tracker.enter("s", now + Duration::from_millis(100)).is_none());
+        assert!(tracker.enter("s", now + SLIDE_DEBOUNCE).is_some());
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! uinput key emission (`uinput` feature).
+//!
+//! Last-resort backend for compositors that offer neither the Wayland
+//! virtual keyboard protocol nor an X server: a virtual evdev keyboard is
+//! created through `/dev/uinput`, and its key events reach every client like
+//! those of a hardware keyboard.
+//!
+//! Writing to `/dev/uinput` is privileged. `resources/uinput/` contains a
+//! udev rule granting the logged-in user access and a setup script
+//! (`just setup-uinput`) that installs it through polkit (`pkexec`);
+//! [`uinput_writable`](super::backend::uinput_writable) detects whether the
+//! device can be used.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+
+use super::backend::{KeySink, UINPUT_PATH};
+use super::virtual_keyboard::KeyState;
+
+// ioctl requests from <linux/uinput.h>
+const UI_DEV_CREATE: libc::Ioctl = 0x5501;
+const UI_DEV_DESTROY: libc::Ioctl = 0x5502;
+const UI_DEV_SETUP: libc::Ioctl = 0x405c_5503;
+const UI_SET_EVBIT: libc::Ioctl = 0x4004_5564;
+const UI_SET_KEYBIT: libc::Ioctl = 0x4004_5565;
+
+// Event types and codes from <linux/input-event-codes.h>
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+
+/// Highest keycode the virtual keyboard declares (`KEY_MICMUTE`).
+const LAST_KEYCODE: u32 = 248;
+
+/// Name of the virtual input device.
+const DEVICE_NAME: &[u8] = b"Cosboard virtual keyboard";
+
+/// `struct input_id`
+#[repr(C)]
+struct InputId {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+}
+
+/// `struct uinput_setup`
+#[repr(C)]
+struct UinputSetup {
+    id: InputId,
+    name: [u8; 80],
+    ff_effects_max: u32,
+}
+
+/// Encodes a `struct input_event` (the timestamp is left to the kernel).
+#[must_use]
+pub fn encode_event(kind: u16, code: u16, value: i32) -> Vec<u8> {
+    let mut bytes = vec![0u8; std::mem::size_of::<libc::timeval>()];
+    bytes.extend_from_slice(&kind.to_ne_bytes());
+    bytes.extend_from_slice(&code.to_ne_bytes());
+    bytes.extend_from_slice(&value.to_ne_bytes());
+    bytes
+}
+
+/// Sends key events through a uinput virtual keyboard.
+pub struct UinputSink {
+    /// Open `/dev/uinput`; the device exists while it is open
+    device: File,
+}
+
+impl UinputSink {
+    /// Creates the virtual keyboard device.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the failure, e.g. when `/dev/uinput` is not
+    /// writable by the user.
+    pub fn create() -> Result<Self, String> {
+        let device = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT_PATH)
+            .map_err(|e| {
+                format!(
+                    "Cannot open {}: {} (run `just setup-uinput` to grant access)",
+                    UINPUT_PATH, e
+                )
+            })?;
+
+        let sink = Self { device };
+        sink.ioctl(UI_SET_EVBIT, libc::c_ulong::from(EV_KEY))?;
+        for keycode in 1..=LAST_KEYCODE {
+            sink.ioctl(UI_SET_KEYBIT, libc::c_ulong::from(keycode))?;
+        }
+
+        let mut setup = UinputSetup {
+            id: InputId {
+                bustype: BUS_VIRTUAL,
+                vendor: 0,
+                product: 0,
+                version: 1,
+            },
+            name: [0; 80],
+            ff_effects_max: 0,
+        };
+        setup.name[..DEVICE_NAME.len()].copy_from_slice(DEVICE_NAME);
+        sink.ioctl(UI_DEV_SETUP, &setup as *const UinputSetup as libc::c_ulong)?;
+        sink.ioctl(UI_DEV_CREATE, 0)?;
+
+        tracing::info!("Created uinput virtual keyboard");
+        Ok(sink)
+    }
+
+    /// Issues an ioctl on the uinput device.
+    fn ioctl(&self, request: libc::Ioctl, argument: libc::c_ulong) -> Result<(), String> {
+        // SAFETY: the descriptor is open for the lifetime of `self`, and every
+        // request used here takes an integer or a pointer to a live struct
+        // of the size encoded in the request.
+        let result = unsafe { libc::ioctl(self.device.as_raw_fd(), request, argument) };
+        if result < 0 {
+            Err(format!(
+                "uinput ioctl {:#x} failed: {}",
+                request,
+                std::io::Error::last_os_error()
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl KeySink for UinputSink {
+    fn name(&self) -> &'static str {
+        "uinput"
+    }
+
+    fn send_key(&mut self, keycode: u32, state: KeyState) -> Result<(), String> {
+        let code = u16::try_from(keycode)
+            .ok()
+            .filter(|code| u32::from(*code) <= LAST_KEYCODE)
+            .ok_or_else(|| format!("Keycode {} is not on the uinput keyboard", keycode))?;
+        let value = match state {
+            KeyState::Pressed => 1,
+            KeyState::Released => 0,
+        };
+
+        let mut events = encode_event(EV_KEY, code, value);
+        events.extend(encode_event(EV_SYN, SYN_REPORT, 0));
+        self.device
+            .write_all(&events)
+            .map_err(|e| format!("Cannot write to {}: {}", UINPUT_PATH, e))
+    }
+}
+
+impl Drop for UinputSink {
+    fn drop(&mut self) {
+        if let Err(e) = self.ioctl(UI_DEV_DESTROY, 0) {
+            tracing::debug!("{}", e);
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Events are encoded as the kernel's `struct input_event`.
+    #[test]
+    fn test_encode_event() {
+        let time = std::mem::size_of::<libc::timeval>();
+        let event = encode_event(EV_KEY, 30, 1);
+
+        assert_eq!(event.len(), time + 8);
+        assert!(event[..time].iter().all(|&b| b == 0));
+        assert_eq!(&event[time..time + 2], &EV_KEY.to_ne_bytes());
+        assert_eq!(&event[time + 2..time + 4], &30u16.to_ne_bytes());
+        assert_eq!(&event[time + 4..], &1i32.to_ne_bytes());
+        assert_eq!(std::mem::size_of::<UinputSetup>(), 0x5c);
+    }
+}
@@ -7,6 +7,8 @@
 //!
 //! - Initialization with the system XKB keymap
 //! - Key press and release event emission
+//! - Uploading the keymap to the compositor and sending key and modifier
+//!   events through `zwp_virtual_keyboard_v1`
 //! - XKB keysym to hardware keycode conversion
 //! - Unicode codepoint fallback via Ctrl+Shift+U hex input
 //!
@@ -18,6 +20,14 @@
 //! initialization pattern where the actual protocol binding happens when
 //! the keyboard surface is created.
 //!
+//! The protocol object lives on a Wayland connection of its own
+//! ([`VirtualKeyboard::connect_wayland`]). The keymap used for keysym
+//! lookups is uploaded to the compositor, so keycodes mean the same on both
+//! sides. Events are sent as soon as they are queued, followed by the
+//! modifier state whenever a key changes it, as a physical keyboard's
+//! `wl_keyboard` does. Until the protocol is connected, events wait in the
+//! pending queue.
+//!
 //! # Unicode Fallback
 //!
 //! For characters that cannot be mapped to XKB keycodes (e.g., special
@@ -46,7 +56,21 @@
 //! vk.emit_unicode_codepoint(0x03C0); // pi symbol
 //! ```
 
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::input::backend::KeySink;
+use crate::input::journal::{SharedJournal, WAYLAND_BACKEND};
 use crate::input::ResolvedKeycode;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_keyboard, wl_registry, wl_seat};
+use wayland_client::{Connection, Dispatch, EventQueue, QueueHandle};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::{
+    zwp_virtual_keyboard_manager_v1::{self, ZwpVirtualKeyboardManagerV1},
+    zwp_virtual_keyboard_v1::{self, ZwpVirtualKeyboardV1},
+};
 use xkbcommon::xkb::keysyms::KEY_NoSymbol;
 use xkbcommon::xkb::Keysym;
 
@@ -118,6 +142,10 @@ pub struct VirtualKeyboard {
     /// Pending key events waiting to be flushed (for batching).
     pending_events: Vec<KeyEvent>,
 
+    /// Keys pressed and not yet released, in press order.
+    /// Released by `cleanup()` so no key stays stuck down.
+    held_keys: Vec<u32>,
+
     /// XKB context for keymap operations.
     /// This is only Some after successful initialization.
     xkb_context: Option<xkbcommon::xkb::Context>,
@@ -129,6 +157,25 @@ pub struct VirtualKeyboard {
     /// XKB state for key state tracking.
     /// This is only Some after successful initialization.
     xkb_state: Option<xkbcommon::xkb::State>,
+
+    /// Backend that receives key events directly (e.g. X11 XTEST).
+    /// When None, events go to the Wayland protocol.
+    sink: Option<Box<dyn KeySink>>,
+
+    /// Virtual keyboard protocol object, once connected.
+    /// When None (and no sink is set), events are queued.
+    wayland: Option<WaylandKeyboard>,
+
+    /// Start of the monotonic clock that timestamps key events, so they
+    /// never go back when the system clock does.
+    clock: Instant,
+
+    /// When the last key event was handed to the backend, for the emission
+    /// indicator. Events the sink failed to send do not count.
+    last_emission: Option<Instant>,
+
+    /// The last key events handed to the backend, for debugging.
+    journal: SharedJournal,
 }
 
 impl std::fmt::Debug for VirtualKeyboard {
@@ -136,9 +183,14 @@ impl std::fmt::Debug for VirtualKeyboard {
         f.debug_struct("VirtualKeyboard")
             .field("initialized", &self.initialized)
             .field("pending_events", &self.pending_events)
+            .field("held_keys", &self.held_keys)
             .field("xkb_context", &self.xkb_context.is_some())
             .field("xkb_keymap", &self.xkb_keymap.is_some())
             .field("xkb_state", &self.xkb_state.is_some())
+            .field("sink", &self.sink.as_ref().map(|sink| sink.name()))
+            .field("wayland", &self.wayland.is_some())
+            .field("last_emission", &self.last_emission)
+            .field("journal", &self.journal.lock().map(|journal| journal.len()).ok())
             .finish()
     }
 }
@@ -159,9 +211,15 @@ impl VirtualKeyboard {
         Self {
             initialized: false,
             pending_events: Vec::new(),
+            held_keys: Vec::new(),
             xkb_context: None,
             xkb_keymap: None,
             xkb_state: None,
+            sink: None,
+            wayland: None,
+            clock: Instant::now(),
+            last_emission: None,
+            journal: SharedJournal::default(),
         }
     }
 
@@ -215,6 +273,59 @@ impl VirtualKeyboard {
         Ok(())
     }
 
+    /// Sends key events through `sink` instead of the Wayland protocol.
+    ///
+    /// Used for backends chosen by [`select_backend`](crate::input::select_backend)
+    /// when the virtual keyboard protocol cannot be used. The sink is
+    /// dropped by `cleanup()`.
+    pub fn set_sink(&mut self, sink: Box<dyn KeySink>) {
+        tracing::info!("Sending key events through {}", sink.name());
+        self.sink = Some(sink);
+    }
+
+    /// Binds the compositor's virtual keyboard protocol and uploads the
+    /// keymap.
+    ///
+    /// Must be called after `initialize()`. Events queued before are sent
+    /// right away; later events are sent as they are queued. The protocol is
+    /// released by `cleanup()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compositor cannot be reached, does not offer
+    /// the protocol, or refuses this client.
+    pub fn connect_wayland(&mut self) -> Result<(), String> {
+        if self.wayland.is_some() {
+            return Ok(());
+        }
+        let keymap = self
+            .xkb_keymap
+            .as_ref()
+            .ok_or_else(|| "Virtual keyboard not initialized".to_string())?;
+        let mut wayland = WaylandKeyboard::connect(keymap)?;
+
+        for event in std::mem::take(&mut self.pending_events) {
+            wayland.send(&event)?;
+        }
+        self.wayland = Some(wayland);
+
+        tracing::info!("Virtual keyboard protocol connected");
+        Ok(())
+    }
+
+    /// Returns `true` if key events are sent through the virtual keyboard
+    /// protocol.
+    #[must_use]
+    pub fn is_wayland_connected(&self) -> bool {
+        self.wayland.is_some()
+    }
+
+    /// Returns the name of the key sink in use, if not the Wayland protocol.
+    #[must_use]
+    pub fn sink_name(&self) -> Option<&'static str> {
+        self.sink.as_ref().map(|sink| sink.name())
+    }
+
     /// Returns whether the virtual keyboard has been initialized.
     #[must_use]
     pub fn is_initialized(&self) -> bool {
@@ -241,7 +352,10 @@ impl VirtualKeyboard {
         }
 
         let event = KeyEvent::press(keycode, self.get_timestamp());
-        self.pending_events.push(event);
+        self.queue_event(event);
+        if !self.held_keys.contains(&keycode) {
+            self.held_keys.push(keycode);
+        }
 
         // Update XKB state
         if let Some(ref mut state) = self.xkb_state {
@@ -266,7 +380,8 @@ impl VirtualKeyboard {
         }
 
         let event = KeyEvent::release(keycode, self.get_timestamp());
-        self.pending_events.push(event);
+        self.queue_event(event);
+        self.held_keys.retain(|held| *held != keycode);
 
         // Update XKB state
         if let Some(ref mut state) = self.xkb_state {
@@ -279,6 +394,52 @@ impl VirtualKeyboard {
         tracing::debug!("Queued key release: keycode={}", keycode);
     }
 
+    /// Sends an event through the key sink or the protocol, or queues it
+    /// until the protocol is connected.
+    fn queue_event(&mut self, event: KeyEvent) {
+        let (backend, result) = match (&mut self.sink, &mut self.wayland) {
+            (Some(sink), _) => (sink.name(), sink.send_key(event.keycode, event.state)),
+            (None, Some(wayland)) => (WAYLAND_BACKEND, wayland.send(&event)),
+            (None, None) => {
+                self.pending_events.push(event.clone());
+                (WAYLAND_BACKEND, Ok(()))
+            }
+        };
+
+        if let Ok(mut journal) = self.journal.lock() {
+            let error = result.as_ref().err().map(ToString::to_string);
+            journal.record(event.keycode, event.state, backend, error);
+        }
+        match result {
+            Ok(()) => self.last_emission = Some(Instant::now()),
+            Err(e) => tracing::warn!("{} could not send key {}: {}", backend, event.keycode, e),
+        }
+    }
+
+    /// Returns the journal of the last key events handed to the backend.
+    #[must_use]
+    pub fn journal(&self) -> SharedJournal {
+        Arc::clone(&self.journal)
+    }
+
+    /// Sets what the following key events are emitted for (a key
+    /// identifier, `text`, a key combination), as recorded in the journal.
+    pub fn set_journal_source(&self, source: Option<String>) {
+        if let Ok(mut journal) = self.journal.lock() {
+            journal.set_source(source);
+        }
+    }
+
+    /// Returns when the last key event was handed to the backend (sent by
+    /// the key sink, or queued for the protocol).
+    ///
+    /// Only the time is kept, never the key, so the emission indicator
+    /// cannot reveal what was typed.
+    #[must_use]
+    pub fn last_emission(&self) -> Option<Instant> {
+        self.last_emission
+    }
+
     /// Returns the pending key events and clears the queue.
     ///
     /// This method is used by the applet to retrieve queued events and
@@ -563,25 +724,56 @@ impl VirtualKeyboard {
 
     /// Returns the current timestamp in milliseconds.
     ///
-    /// Uses the system monotonic clock for consistent timing.
+    /// Timestamps follow a monotonic clock, even if the system clock is set
+    /// back, so clients timing key repeat and double presses see events in
+    /// order.
     fn get_timestamp(&self) -> u32 {
-        use std::time::{SystemTime, UNIX_EPOCH};
+        timestamp(self.clock.elapsed())
+    }
 
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| (d.as_millis() % u128::from(u32::MAX)) as u32)
-            .unwrap_or(0)
+    /// Returns the keys pressed and not yet released, in press order.
+    #[must_use]
+    pub fn held_keys(&self) -> &[u32] {
+        &self.held_keys
+    }
+
+    /// Releases every key that is still pressed, most recent first.
+    ///
+    /// Used when input stops abruptly (the surface closes, the applet quits
+    /// or panics) so that no key, in particular no modifier, stays stuck
+    /// down in the client applications.
+    pub fn release_all(&mut self) {
+        while let Some(keycode) = self.held_keys.last().copied() {
+            self.release_key(keycode);
+            // release_key() ignores keys once uninitialized
+            self.held_keys.retain(|held| *held != keycode);
+        }
     }
 
     /// Cleans up virtual keyboard resources.
     ///
     /// This method should be called before the keyboard surface is destroyed.
-    /// It clears pending events and releases XKB resources.
+    /// It releases keys that are still held, clears pending events and
+    /// releases XKB resources. Calling it again is a no-op.
     pub fn cleanup(&mut self) {
+        if !self.initialized
+            && self.sink.is_none()
+            && self.wayland.is_none()
+            && self.pending_events.is_empty()
+        {
+            return;
+        }
+
+        // Held keys are released through the sink or the protocol; queued
+        // releases are dropped with the pending events since nothing was
+        // ever sent for them
+        self.release_all();
         self.pending_events.clear();
+        self.wayland = None;
         self.xkb_state = None;
         self.xkb_keymap = None;
         self.xkb_context = None;
+        self.sink = None;
         self.initialized = false;
 
         tracing::info!("Virtual keyboard cleaned up");
@@ -589,11 +781,234 @@ impl VirtualKeyboard {
 }
 
 impl Drop for VirtualKeyboard {
+    /// Releases held keys and frees resources, also when unwinding from a
+    /// panic.
     fn drop(&mut self) {
-        if self.initialized {
-            self.cleanup();
+        self.cleanup();
+    }
+}
+
+// ============================================================================
+// Wayland Protocol
+// ============================================================================
+
+/// Modifier state reported to the compositor along with key events.
+///
+/// The protocol does not derive modifiers from keys: the client sends the
+/// serialized XKB modifier state itself. The tracker follows the keys sent
+/// on its own XKB state, and reports the state whenever a key changes it.
+struct ModifierTracker {
+    /// XKB state of the keys sent so far
+    state: xkbcommon::xkb::State,
+    /// Depressed, latched and locked modifiers, and the layout group, as
+    /// last sent
+    sent: [u32; 4],
+}
+
+impl ModifierTracker {
+    /// Creates a tracker with no key pressed.
+    fn new(keymap: &xkbcommon::xkb::Keymap) -> Self {
+        Self {
+            state: xkbcommon::xkb::State::new(keymap),
+            sent: [0; 4],
         }
     }
+
+    /// Applies a key event; returns the modifier state to send if the key
+    /// changed it.
+    fn update(&mut self, event: &KeyEvent) -> Option<[u32; 4]> {
+        use xkbcommon::xkb;
+
+        let direction = match event.state {
+            KeyState::Pressed => xkb::KeyDirection::Down,
+            KeyState::Released => xkb::KeyDirection::Up,
+        };
+        self.state
+            .update_key(xkb::Keycode::new(event.keycode + 8), direction);
+
+        let modifiers = [
+            self.state.serialize_mods(xkb::STATE_MODS_DEPRESSED),
+            self.state.serialize_mods(xkb::STATE_MODS_LATCHED),
+            self.state.serialize_mods(xkb::STATE_MODS_LOCKED),
+            self.state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE),
+        ];
+        if modifiers == self.sent {
+            return None;
+        }
+        self.sent = modifiers;
+        Some(modifiers)
+    }
+}
+
+/// Converts the time since the key event clock started to a protocol
+/// timestamp, which wraps around after about 49 days.
+fn timestamp(elapsed: Duration) -> u32 {
+    // Truncating keeps the low bits, so timestamps wrap rather than stop
+    elapsed.as_millis() as u32
+}
+
+/// Returns a keymap in the text format the protocol expects, with the
+/// terminating NUL byte.
+fn keymap_text(keymap: &xkbcommon::xkb::Keymap) -> Vec<u8> {
+    let mut text = keymap
+        .get_as_string(xkbcommon::xkb::KEYMAP_FORMAT_TEXT_V1)
+        .into_bytes();
+    text.push(0);
+    text
+}
+
+/// Writes a keymap to an unlinked file whose descriptor can be handed to
+/// the compositor.
+///
+/// The file is created in the user's runtime directory, readable by the
+/// user only; it must not exist yet, so nobody can plant it beforehand.
+fn keymap_file(text: &[u8]) -> Result<std::fs::File, String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static KEYMAPS: AtomicU32 = AtomicU32::new(0);
+
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .ok_or_else(|| "Cannot write keymap: XDG_RUNTIME_DIR is not set".to_string())?;
+    let path = dir.join(format!(
+        "cosboard-keymap-{}-{}",
+        std::process::id(),
+        KEYMAPS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|e| format!("Cannot write keymap to {}: {}", path.display(), e))?;
+    let _ = std::fs::remove_file(&path);
+    file.write_all(text).map_err(|e| e.to_string())?;
+    Ok(file)
+}
+
+/// Dispatch state of the virtual keyboard connection; the protocol objects
+/// have no events worth handling.
+struct WaylandState;
+
+/// A `zwp_virtual_keyboard_v1` on a connection of its own.
+struct WaylandKeyboard {
+    /// Connection to the compositor
+    connection: Connection,
+    /// Queue of the protocol objects, kept alive with them
+    _queue: EventQueue<WaylandState>,
+    /// Virtual keyboard protocol object
+    keyboard: ZwpVirtualKeyboardV1,
+    /// Modifier state as sent to the compositor
+    modifiers: ModifierTracker,
+}
+
+impl WaylandKeyboard {
+    /// Binds the protocol on the first seat and uploads `keymap`.
+    fn connect(keymap: &xkbcommon::xkb::Keymap) -> Result<Self, String> {
+        let connection = Connection::connect_to_env().map_err(|e| e.to_string())?;
+        let (globals, mut queue) =
+            registry_queue_init::<WaylandState>(&connection).map_err(|e| e.to_string())?;
+        let handle = queue.handle();
+
+        let seat: wl_seat::WlSeat = globals
+            .bind(&handle, 1..=1, ())
+            .map_err(|_| "no seat".to_string())?;
+        let manager: ZwpVirtualKeyboardManagerV1 =
+            globals.bind(&handle, 1..=1, ()).map_err(|_| {
+                "the compositor does not offer zwp_virtual_keyboard_manager_v1".to_string()
+            })?;
+        let keyboard = manager.create_virtual_keyboard(&seat, &handle, ());
+
+        // Key events are a protocol error until a keymap is set
+        let text = keymap_text(keymap);
+        let file = keymap_file(&text)?;
+        keyboard.keymap(
+            wl_keyboard::KeymapFormat::XkbV1 as u32,
+            file.as_fd(),
+            text.len() as u32,
+        );
+        // An unauthorized client is disconnected: find out now
+        queue
+            .roundtrip(&mut WaylandState)
+            .map_err(|e| format!("The compositor refused the virtual keyboard: {}", e))?;
+
+        Ok(Self {
+            connection,
+            _queue: queue,
+            keyboard,
+            modifiers: ModifierTracker::new(keymap),
+        })
+    }
+
+    /// Sends a key event, then the modifier state if the key changed it.
+    fn send(&mut self, event: &KeyEvent) -> Result<(), String> {
+        let state = match event.state {
+            KeyState::Pressed => wl_keyboard::KeyState::Pressed,
+            KeyState::Released => wl_keyboard::KeyState::Released,
+        };
+        self.keyboard.key(event.time, event.keycode, state as u32);
+        if let Some([depressed, latched, locked, group]) = self.modifiers.update(event) {
+            self.keyboard.modifiers(depressed, latched, locked, group);
+        }
+        self.connection.flush().map_err(|e| e.to_string())
+    }
+}
+
+impl Drop for WaylandKeyboard {
+    fn drop(&mut self) {
+        self.keyboard.destroy();
+        let _ = self.connection.flush();
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _registry: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _seat: &wl_seat::WlSeat,
+        _event: wl_seat::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, ()> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwpVirtualKeyboardManagerV1,
+        _event: zwp_virtual_keyboard_manager_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, ()> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _keyboard: &ZwpVirtualKeyboardV1,
+        _event: zwp_virtual_keyboard_v1::Event,
+        _data: &(),
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+    ) {
+    }
 }
 
 // ============================================================================
@@ -900,9 +1315,11 @@ mod tests {
         }
 
         // Queue some events
+        assert!(vk.last_emission().is_none());
         vk.press_key(10);
         vk.press_key(20);
         assert_eq!(vk.pending_events().len(), 2);
+        assert!(vk.last_emission().is_some());
 
         // Take events
         let events = vk.take_pending_events();
@@ -927,6 +1344,7 @@ mod tests {
 
         // Events should not be queued
         assert_eq!(vk.pending_events().len(), 0);
+        assert!(vk.last_emission().is_none());
 
         // Lookups should return None
         assert!(vk.keysym_to_keycode("Return").is_none());
@@ -969,4 +1387,99 @@ mod tests {
         assert!(!vk.is_initialized());
         assert_eq!(vk.pending_events().len(), 0);
     }
+
+    /// Key sink that records what it is sent.
+    struct RecordingSink(std::sync::Arc<std::sync::Mutex<Vec<(u32, KeyState)>>>);
+
+    impl KeySink for RecordingSink {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn send_key(&mut self, keycode: u32, state: KeyState) -> Result<(), String> {
+            self.0.lock().unwrap().push((keycode, state));
+            Ok(())
+        }
+    }
+
+    /// Test: Keys still held are released on drop, and cleanup is idempotent
+    #[test]
+    fn test_drop_releases_held_keys() {
+        let mut vk = VirtualKeyboard::new();
+        if vk.initialize().is_err() {
+            eprintln!("Skipping test: XKB initialization failed");
+            return;
+        }
+        let sent = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        vk.set_sink(Box::new(RecordingSink(sent.clone())));
+
+        vk.press_key(keycodes::KEY_LEFTCTRL);
+        vk.press_key(keycodes::KEY_LEFTSHIFT);
+        vk.press_key(keycodes::KEY_SPACE);
+        vk.release_key(keycodes::KEY_SPACE);
+        assert_eq!(vk.held_keys(), &[keycodes::KEY_LEFTCTRL, keycodes::KEY_LEFTSHIFT]);
+
+        drop(vk);
+        let sent = sent.lock().unwrap();
+        assert_eq!(
+            &sent[4..],
+            &[
+                (keycodes::KEY_LEFTSHIFT, KeyState::Released),
+                (keycodes::KEY_LEFTCTRL, KeyState::Released),
+            ]
+        );
+
+        let mut vk = VirtualKeyboard::new();
+        vk.cleanup();
+        vk.cleanup();
+        assert!(vk.held_keys().is_empty());
+    }
+
+    /// Test: The modifier state is reported only when a key changes it, and
+    /// the keymap is uploaded NUL-terminated.
+    #[test]
+    fn test_protocol_modifiers() {
+        let mut vk = VirtualKeyboard::new();
+        if vk.initialize().is_err() {
+            eprintln!("Skipping test: XKB initialization failed");
+            return;
+        }
+        let keymap = vk.xkb_keymap.as_ref().unwrap();
+        assert_eq!(keymap_text(keymap).last(), Some(&0));
+
+        let mut modifiers = ModifierTracker::new(keymap);
+        let shift = modifiers
+            .update(&KeyEvent::press(keycodes::KEY_LEFTSHIFT, 1))
+            .expect("Shift changes the modifiers");
+        assert_ne!(shift[0], 0, "Shift is depressed");
+        assert_eq!(modifiers.update(&KeyEvent::press(30, 2)), None);
+        assert_eq!(modifiers.update(&KeyEvent::release(30, 3)), None);
+        assert_eq!(
+            modifiers.update(&KeyEvent::release(keycodes::KEY_LEFTSHIFT, 4)),
+            Some([0, 0, 0, 0])
+        );
+    }
+
+    /// Test: Timestamps count milliseconds and keep counting when they wrap
+    /// around.
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(timestamp(Duration::ZERO), 0);
+        assert_eq!(timestamp(Duration::from_millis(1500)), 1500);
+
+        let wrap = Duration::from_millis(u64::from(u32::MAX) + 1);
+        assert_eq!(timestamp(wrap - Duration::from_millis(1)), u32::MAX);
+        assert_eq!(timestamp(wrap), 0);
+        assert_eq!(timestamp(wrap + Duration::from_millis(5)), 5);
+        assert_eq!(
+            timestamp(wrap + Duration::from_millis(5))
+                .wrapping_sub(timestamp(wrap - Duration::from_millis(5))),
+            10
+        );
+
+        let vk = VirtualKeyboard::new();
+        let first = vk.get_timestamp();
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(vk.get_timestamp().wrapping_sub(first) >= 2);
+    }
 }
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! X11 XTEST key emission (`x11` feature).
+//!
+//! Synthesizes key events with the XTEST extension's `FakeInput` request,
+//! for X11 sessions and for compositors without the Wayland virtual keyboard
+//! protocol (see [`backend`](super::backend)). X11 keycodes are evdev
+//! keycodes offset by 8, as in XKB.
+
+use x11rb::connection::Connection as _;
+use x11rb::protocol::xproto::{Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+
+use super::backend::KeySink;
+use super::virtual_keyboard::KeyState;
+
+/// Offset from evdev keycodes to X11 keycodes.
+const X11_KEYCODE_OFFSET: u32 = 8;
+
+/// Sends key events to the X server through XTEST.
+pub struct XtestSink {
+    /// Connection to the X server from `DISPLAY`
+    connection: RustConnection,
+    /// Root window of the default screen
+    root: Window,
+}
+
+impl XtestSink {
+    /// Connects to the X server and checks that it supports XTEST.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the failure if there is no X server or it
+    /// lacks the XTEST extension.
+    pub fn connect() -> Result<Self, String> {
+        let (connection, screen) =
+            x11rb::connect(None).map_err(|e| format!("Cannot connect to the X server: {}", e))?;
+
+        connection
+            .xtest_get_version(2, 2)
+            .map_err(|e| format!("Cannot query XTEST: {}", e))?
+            .reply()
+            .map_err(|e| format!("X server has no XTEST extension: {}", e))?;
+
+        let root = connection.setup().roots[screen].root;
+        Ok(Self { connection, root })
+    }
+}
+
+impl KeySink for XtestSink {
+    fn name(&self) -> &'static str {
+        "X11 XTEST"
+    }
+
+    fn send_key(&mut self, keycode: u32, state: KeyState) -> Result<(), String> {
+        let event = match state {
+            KeyState::Pressed => KEY_PRESS_EVENT,
+            KeyState::Released => KEY_RELEASE_EVENT,
+        };
+        let detail = u8::try_from(keycode + X11_KEYCODE_OFFSET)
+            .map_err(|_| format!("Keycode {} has no X11 equivalent", keycode))?;
+
+        self.connection
+            .xtest_fake_input(event, detail, x11rb::CURRENT_TIME, self.root, 0, 0, 0)
+            .map_err(|e| format!("XTEST request failed: {}", e))?;
+        self.connection
+            .flush()
+            .map_err(|e| format!("Cannot flush X11 connection: {}", e))
+    }
+}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Kiosk mode for point-of-sale and public terminals.
+//!
+//! [`KioskPolicy`] is part of the user configuration and is read once at
+//! startup. When enabled, it locks the keyboard down: the popup menu loses its
+//! quit item and settings, the floating keyboard can no longer be dragged or
+//! resized, and layouts cannot be switched. Each restriction can be lifted
+//! individually with the `allow_*` fields.
+//!
+//! Every gated code path asks the policy (`can_*` or [`KioskPolicy::permits`])
+//! rather than checking `enabled` itself, so a disabled policy allows
+//! everything.
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::AppAction;
+
+/// Restrictions applied to the keyboard in kiosk mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KioskPolicy {
+    /// Whether kiosk mode is active. When `false`, nothing is restricted.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Keep the quit item (and the `quit` action) available.
+    #[serde(default)]
+    pub allow_quit: bool,
+    /// Keep dragging and resizing the floating keyboard available.
+    #[serde(default)]
+    pub allow_drag_resize: bool,
+    /// Keep switching to other layout files available.
+    #[serde(default)]
+    pub allow_layout_switching: bool,
+    /// Keep settings (menu toggles, surface mode, layer, gestures) available.
+    #[serde(default)]
+    pub allow_settings: bool,
+}
+
+impl KioskPolicy {
+    /// A locked-down policy: kiosk mode enabled with nothing allowed.
+    #[must_use]
+    pub fn locked() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if the applet may be quit.
+    #[must_use]
+    pub fn can_quit(&self) -> bool {
+        !self.enabled || self.allow_quit
+    }
+
+    /// Returns `true` if the floating keyboard may be dragged or resized.
+    #[must_use]
+    pub fn can_drag_resize(&self) -> bool {
+        !self.enabled || self.allow_drag_resize
+    }
+
+    /// Returns `true` if another layout file may be loaded.
+    #[must_use]
+    pub fn can_switch_layout(&self) -> bool {
+        !self.enabled || self.allow_layout_switching
+    }
+
+    /// Returns `true` if settings may be changed.
+    #[must_use]
+    pub fn can_change_settings(&self) -> bool {
+        !self.enabled || self.allow_settings
+    }
+
+    /// Returns `true` if typed words may be learned for prediction.
+    ///
+    /// A kiosk is shared by strangers, so it never learns, whatever it allows.
+    #[must_use]
+    pub fn can_learn_words(&self) -> bool {
+        !self.enabled
+    }
+
+    /// Returns `true` if `action` may be dispatched under this policy.
+    ///
+    /// Showing, hiding, typing and switching panels within the layout are
+    /// always allowed; they are what the keyboard is for.
+    #[must_use]
+    pub fn permits(&self, action: &AppAction) -> bool {
+        match action {
+            AppAction::Quit => self.can_quit(),
+            AppAction::SwitchLayout(_) => self.can_switch_layout(),
+            AppAction::ToggleFloatingMode
+            | AppAction::FitHeight
+            | AppAction::SetKeyboardInteractivity(_)
+            | AppAction::ToggleKeyboardInteractivity
+            | AppAction::SetLayer(_)
+            | AppAction::ToggleLayer
+            | AppAction::TogglePrediction
+            | AppAction::SetProfile(_)
+            | AppAction::ExportConfig(_)
+            | AppAction::ImportConfig(_)
+            | AppAction::ReloadConfig => self.can_change_settings(),
+            // The file chooser would let anyone browse the kiosk's files
+            AppAction::ScanCode | AppAction::ScanImage(_) => self.can_change_settings(),
+            AppAction::Show
+            | AppAction::Hide
+            | AppAction::Toggle
+            | AppAction::ToggleCollapsed
+            | AppAction::TogglePalette
+            | AppAction::ToggleTypingTest
+            | AppAction::SwitchPanel(_)
+            | AppAction::ReloadLayout
+            | AppAction::InsertText(_)
+            | AppAction::TypeText(_)
+            | AppAction::SendKey(_)
+            | AppAction::SendPrefix
+            | AppAction::CommitPrediction(_)
+            | AppAction::PageCandidates(_)
+            | AppAction::CommitPreedit
+            | AppAction::TogglePrivateMode
+            | AppAction::SetFocusedApp(_)
+            | AppAction::CalcInput(_)
+            | AppAction::CalcBackspace
+            | AppAction::CalcClear
+            | AppAction::CalcEquals
+            | AppAction::BrailleDot(_) => true,
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer_shell::Layer;
+
+    /// Test: A disabled policy permits everything.
+    #[test]
+    fn test_disabled_permits_all() {
+        let policy = KioskPolicy::default();
+        assert!(policy.can_quit());
+        assert!(policy.can_drag_resize());
+        assert!(policy.permits(&AppAction::SwitchLayout("other.json".to_string())));
+        assert!(policy.permits(&AppAction::SetLayer(Layer::Top)));
+    }
+
+    /// Test: A locked policy blocks quit, layouts and settings but not typing.
+    #[test]
+    fn test_locked_policy() {
+        let policy = KioskPolicy::locked();
+        assert!(!policy.permits(&AppAction::Quit));
+        assert!(!policy.permits(&AppAction::SwitchLayout("other.json".to_string())));
+        assert!(!policy.permits(&AppAction::ToggleFloatingMode));
+        assert!(!policy.permits(&AppAction::ToggleLayer));
+        assert!(!policy.permits(&AppAction::SetProfile("tablet".to_string())));
+        assert!(!policy.permits(&AppAction::ImportConfig("a.json".to_string())));
+        assert!(!policy.can_drag_resize());
+
+        assert!(policy.permits(&AppAction::Hide));
+        assert!(policy.permits(&AppAction::SwitchPanel("numpad".to_string())));
+        assert!(policy.permits(&AppAction::InsertText("€".to_string())));
+        assert!(policy.permits(&AppAction::TogglePrivateMode));
+        assert!(!policy.can_learn_words());
+
+        let policy = KioskPolicy {
+            allow_quit: true,
+            ..KioskPolicy::locked()
+        };
+        assert!(policy.permits(&AppAction::Quit));
+        assert!(!policy.permits(&AppAction::ToggleLayer));
+    }
+}
@@ -9,10 +9,10 @@
 //!
 //! ## Current Implementation
 //!
-//! The implementation uses `window::Level::AlwaysOnTop` for X11 environments
-//! and logs a warning on Wayland where this setting is not supported by the
-//! protocol. Future versions may integrate directly with cctk/sctk for true
-//! layer-shell surface creation.
+//! The applet creates its surfaces through the layer-surface commands of
+//! libcosmic's Wayland platform layer. This module converts the configured
+//! layer, keyboard interactivity and anchor edge to their sctk equivalents,
+//! and [`Outputs`] tracks the outputs a surface can be placed on.
 //!
 //! ## Wayland Layer-Shell Protocol
 //!
@@ -25,10 +25,11 @@
 //! A soft keyboard should ideally be on the Overlay layer to ensure it's always
 //! visible and can receive input regardless of which application has focus.
 
-use cosmic::app::cosmic::WindowingSystem;
+use cosmic::iced_winit::platform_specific::wayland::commands::layer_surface as sctk;
+use serde::{Deserialize, Serialize};
 
 /// Layer types for window positioning (mirrors zwlr_layer_shell_v1 layers).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Layer {
     /// Background layer - below all other windows.
     Background,
@@ -52,179 +53,168 @@ impl Layer {
             Layer::Overlay => "Overlay",
         }
     }
+
+    /// Parses a layer name (case-insensitive), as accepted over D-Bus.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "background" => Some(Layer::Background),
+            "bottom" => Some(Layer::Bottom),
+            "top" => Some(Layer::Top),
+            "overlay" => Some(Layer::Overlay),
+            _ => None,
+        }
+    }
+
+    /// Converts to the layer type used by the layer-surface commands.
+    #[must_use]
+    pub fn to_sctk(self) -> sctk::Layer {
+        match self {
+            Layer::Background => sctk::Layer::Background,
+            Layer::Bottom => sctk::Layer::Bottom,
+            Layer::Top => sctk::Layer::Top,
+            Layer::Overlay => sctk::Layer::Overlay,
+        }
+    }
 }
 
-/// Configuration for layer-shell behavior.
-#[derive(Debug, Clone)]
-pub struct LayerShellConfig {
-    /// The layer to place the window on.
-    pub layer: Layer,
-    /// Whether layer-shell is available on the current system.
-    pub available: bool,
-    /// Whether the window is currently configured as a layer surface.
-    pub is_layer_surface: bool,
+/// Whether the keyboard surface may receive keyboard focus
+/// (mirrors zwlr_layer_surface_v1 keyboard_interactivity).
+///
+/// `None` is the safe default for both docked and floating mode: clicking a
+/// key never steals focus from the application being typed into. `OnDemand`
+/// lets the compositor focus the keyboard surface when it is clicked, which
+/// is only needed for the keyboard's own text fields (e.g. emoji search).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Interactivity {
+    /// The surface never receives keyboard focus.
+    #[default]
+    None,
+    /// The surface receives keyboard focus when the user interacts with it.
+    OnDemand,
 }
 
-impl Default for LayerShellConfig {
-    fn default() -> Self {
-        Self {
-            layer: Layer::Overlay,
-            available: false,
-            is_layer_surface: false,
+impl Interactivity {
+    /// Returns a human-readable name for the interactivity mode.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interactivity::None => "None",
+            Interactivity::OnDemand => "OnDemand",
         }
     }
-}
 
-impl LayerShellConfig {
-    /// Creates a new layer-shell configuration with overlay layer.
+    /// Parses an interactivity name (case-insensitive), as accepted over D-Bus.
     #[must_use]
-    pub fn new() -> Self {
-        Self::default()
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Interactivity::None),
+            "ondemand" | "on_demand" | "on-demand" => Some(Interactivity::OnDemand),
+            _ => None,
+        }
     }
 
-    /// Sets the target layer for the window.
+    /// Converts to the interactivity type used by the layer-surface commands.
     #[must_use]
-    pub fn with_layer(mut self, layer: Layer) -> Self {
-        self.layer = layer;
-        self
-    }
-
-    /// Checks if the current windowing system supports layer-shell.
-    ///
-    /// Returns true only on Wayland compositors that support zwlr_layer_shell_v1.
-    pub fn check_availability(&mut self) -> bool {
-        if let Some(system) = cosmic::app::cosmic::windowing_system() {
-            match system {
-                WindowingSystem::Wayland => {
-                    // On Wayland, layer-shell might be available but the current
-                    // cosmic::Application framework creates XDG toplevels, not layer surfaces.
-                    // True layer-shell would require direct cctk/sctk integration.
-                    tracing::info!(
-                        "Running on Wayland - layer-shell protocol may be available, \
-                         but cosmic::Application creates XDG toplevels. \
-                         Using fallback window hints instead."
-                    );
-                    self.available = true;
-                    self.is_layer_surface = false; // Not a true layer surface yet
-                }
-                WindowingSystem::Xlib | WindowingSystem::Xcb => {
-                    tracing::info!(
-                        "Running on X11 - using EWMH _NET_WM_STATE_ABOVE for always-on-top"
-                    );
-                    self.available = false;
-                }
-                other => {
-                    tracing::warn!(
-                        "Running on unsupported windowing system: {:?} - \
-                         always-on-top behavior may not work",
-                        other
-                    );
-                    self.available = false;
-                }
-            }
-        } else {
-            tracing::warn!(
-                "Windowing system not yet detected - \
-                 layer-shell availability unknown"
-            );
+    pub fn to_sctk(self) -> sctk::KeyboardInteractivity {
+        match self {
+            Interactivity::None => sctk::KeyboardInteractivity::None,
+            Interactivity::OnDemand => sctk::KeyboardInteractivity::OnDemand,
+        }
+    }
+}
+
+/// Screen edge the docked keyboard is anchored to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Edge {
+    /// Top edge of the output.
+    Top,
+    /// Bottom edge of the output.
+    #[default]
+    Bottom,
+}
+
+impl Edge {
+    /// Returns a human-readable name for the edge.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Edge::Top => "Top",
+            Edge::Bottom => "Bottom",
         }
-        self.available
     }
 
-    /// Returns true if true layer-shell overlay behavior is active.
+    /// Parses an edge name (case-insensitive).
     #[must_use]
-    pub fn is_layer_surface(&self) -> bool {
-        self.is_layer_surface
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "top" => Some(Edge::Top),
+            "bottom" => Some(Edge::Bottom),
+            _ => None,
+        }
     }
 
-    /// Returns the configured layer.
+    /// Returns the full-width anchor along this edge.
     #[must_use]
-    pub fn layer(&self) -> Layer {
-        self.layer
+    pub fn to_sctk(self) -> sctk::Anchor {
+        match self {
+            Edge::Top => sctk::Anchor::TOP | sctk::Anchor::LEFT | sctk::Anchor::RIGHT,
+            Edge::Bottom => sctk::Anchor::BOTTOM | sctk::Anchor::LEFT | sctk::Anchor::RIGHT,
+        }
     }
 }
 
-/// Determines the appropriate window level based on platform capabilities.
+/// Outputs announced by the compositor, with their names.
 ///
-/// On X11, this returns `AlwaysOnTop` which is supported via EWMH.
-/// On Wayland, this still returns `AlwaysOnTop` but it may not be effective
-/// since Wayland doesn't support this hint - true overlay behavior requires
-/// layer-shell protocol integration.
-#[must_use]
-pub fn get_window_level() -> cosmic::iced::window::Level {
-    // Always return AlwaysOnTop - it works on X11 and is the best we can do
-    // without true layer-shell integration on Wayland.
-    cosmic::iced::window::Level::AlwaysOnTop
+/// Generic over the output handle so it can be tested without a Wayland
+/// connection; the applet stores `WlOutput`s.
+#[derive(Debug, Clone)]
+pub struct Outputs<T> {
+    /// Named outputs, in the order they were announced.
+    outputs: Vec<(T, String)>,
 }
 
-/// Logs the current layer-shell status for debugging.
-pub fn log_layer_status(config: &LayerShellConfig) {
-    if config.is_layer_surface {
-        tracing::info!(
-            "Window configured as layer surface on {} layer",
-            config.layer.as_str()
-        );
-    } else if config.available {
-        tracing::info!(
-            "Layer-shell available but not using layer surface - \
-             using window hints for always-on-top behavior"
-        );
-    } else {
-        tracing::info!(
-            "Layer-shell not available - using platform window hints"
-        );
+impl<T> Default for Outputs<T> {
+    fn default() -> Self {
+        Self {
+            outputs: Vec::new(),
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    /// Test: Window configured as layer surface (configuration state).
-    #[test]
-    fn test_layer_surface_configuration() {
-        let mut config = LayerShellConfig::new();
-
-        // Initially not a layer surface
-        assert!(!config.is_layer_surface());
-
-        // After simulating layer surface creation
-        config.is_layer_surface = true;
-        assert!(config.is_layer_surface());
+impl<T: Clone + PartialEq> Outputs<T> {
+    /// Records an announced output, or its new name.
+    pub fn add(&mut self, output: T, name: String) {
+        self.remove(&output);
+        self.outputs.push((output, name));
     }
 
-    /// Test: Layer set to overlay layer (highest z-order).
-    #[test]
-    fn test_overlay_layer_default() {
-        let config = LayerShellConfig::new();
-
-        // Default should be Overlay layer
-        assert_eq!(config.layer(), Layer::Overlay);
-        assert_eq!(config.layer().as_str(), "Overlay");
+    /// Forgets a removed output.
+    pub fn remove(&mut self, output: &T) {
+        self.outputs.retain(|(known, _)| known != output);
     }
 
-    /// Test: Window stays above normal windows (via window level).
-    #[test]
-    fn test_always_on_top_level() {
-        let level = get_window_level();
-
-        // Should return AlwaysOnTop
-        assert_eq!(level, cosmic::iced::window::Level::AlwaysOnTop);
+    /// Returns the output with this name, if it was announced.
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&T> {
+        self.outputs
+            .iter()
+            .find(|(_, known)| known == name)
+            .map(|(output, _)| output)
     }
 
-    /// Test: Layer configuration with different layers.
-    #[test]
-    fn test_layer_configuration() {
-        let config = LayerShellConfig::new()
-            .with_layer(Layer::Top);
-
-        assert_eq!(config.layer(), Layer::Top);
-        assert_eq!(config.layer().as_str(), "Top");
-
-        let config = LayerShellConfig::new()
-            .with_layer(Layer::Background);
-        assert_eq!(config.layer(), Layer::Background);
+    /// Returns the names of the outputs, sorted.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.outputs.iter().map(|(_, name)| name.as_str()).collect();
+        names.sort_unstable();
+        names
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     /// Test: All layer variants have correct string representation.
     #[test]
@@ -235,6 +225,43 @@ mod tests {
         assert_eq!(Layer::Overlay.as_str(), "Overlay");
     }
 
+    /// Test: Layer and interactivity names round-trip through parsing.
+    #[test]
+    fn test_layer_and_interactivity_from_name() {
+        for layer in [Layer::Background, Layer::Bottom, Layer::Top, Layer::Overlay] {
+            assert_eq!(Layer::from_name(layer.as_str()), Some(layer));
+        }
+        assert_eq!(Layer::from_name("overlay"), Some(Layer::Overlay));
+        assert_eq!(Layer::from_name("sideways"), None);
+
+        assert_eq!(Interactivity::default(), Interactivity::None);
+        assert_eq!(Interactivity::from_name("none"), Some(Interactivity::None));
+        assert_eq!(Interactivity::from_name("on-demand"), Some(Interactivity::OnDemand));
+        assert_eq!(
+            Interactivity::from_name(Interactivity::OnDemand.as_str()),
+            Some(Interactivity::OnDemand)
+        );
+        assert_eq!(Interactivity::from_name("exclusive"), None);
+    }
+
+    /// Test: Outputs are found by name until they are removed, and a
+    /// renamed output is listed once.
+    #[test]
+    fn test_outputs() {
+        let mut outputs = Outputs::default();
+        outputs.add(1, "HDMI-1".to_string());
+        outputs.add(2, "eDP-1".to_string());
+        outputs.add(1, "HDMI-A-1".to_string());
+
+        assert_eq!(outputs.names(), ["HDMI-A-1", "eDP-1"]);
+        assert_eq!(outputs.find("eDP-1"), Some(&2));
+        assert_eq!(outputs.find("HDMI-1"), None);
+
+        outputs.remove(&2);
+        assert_eq!(outputs.find("eDP-1"), None);
+        assert_eq!(outputs.names(), ["HDMI-A-1"]);
+    }
+
     /// Test: Default layer shell config values.
     #[test]
     fn test_default_config() {
@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Cache of parsed layouts.
+//!
+//! Reading and parsing a layout (with its parents) takes long enough to be
+//! noticeable when the keyboard is shown. The applet parses the layouts it
+//! is likely to show once in the background at startup and keeps the results
+//! here, so showing the keyboard or switching back to a layout reuses the
+//! parsed [`Layout`] instead of going back to disk.
+//!
+//! Entries are keyed by the name the layout was requested with (a path or a
+//! file name on the layout search path). Only layouts that parsed are cached;
+//! failures are retried on the next load. The cache is dropped by the
+//! `reload_layout` action, and single files can be dropped with
+//! [`LayoutCache::invalidate_path`] when they change on disk.
+
+use std::collections::HashMap;
+
+use super::sources::LayoutSource;
+use super::types::{Layout, ParseResult};
+
+/// A parsed layout and where it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedLayout {
+    /// Path of the layout file that was read.
+    pub path: String,
+    /// Where the layout file was found on the search path.
+    pub source: Option<LayoutSource>,
+    /// Parsed layout with its warnings.
+    pub result: ParseResult<Layout>,
+}
+
+/// Parsed layouts, by requested name.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutCache {
+    entries: HashMap<String, CachedLayout>,
+}
+
+impl LayoutCache {
+    /// Creates an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached layout requested under `requested`, if any.
+    #[must_use]
+    pub fn get(&self, requested: &str) -> Option<&CachedLayout> {
+        self.entries.get(requested)
+    }
+
+    /// Caches a layout parsed for `requested`, replacing any older entry.
+    pub fn insert(&mut self, requested: impl Into<String>, layout: CachedLayout) {
+        self.entries.insert(requested.into(), layout);
+    }
+
+    /// Drops every cached layout.
+    pub fn invalidate(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops the layouts read from the file at `path`.
+    ///
+    /// A layout that inherits from the file is not dropped, since its parents
+    /// are not tracked; use [`invalidate`](Self::invalidate) when a parent
+    /// layout changes.
+    ///
+    /// # Returns
+    ///
+    /// `true` if any entry was dropped.
+    pub fn invalidate_path(&mut self, path: &str) -> bool {
+        let before = self.entries.len();
+        self.entries
+            .retain(|requested, cached| requested != path && cached.path != path);
+        self.entries.len() != before
+    }
+
+    /// Returns the number of cached layouts.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(path: &str) -> CachedLayout {
+        CachedLayout {
+            path: path.to_string(),
+            source: Some(LayoutSource::BuiltIn),
+            result: ParseResult::new(Layout::default()),
+        }
+    }
+
+    /// Test: Layouts are found by requested name until invalidated.
+    #[test]
+    fn test_layout_cache() {
+        let mut cache = LayoutCache::new();
+        assert!(cache.get("example_qwerty.json").is_none());
+
+        cache.insert(
+            "example_qwerty.json",
+            cached("/usr/share/cosboard/layouts/example_qwerty.json"),
+        );
+        assert_eq!(
+            cache.get("example_qwerty.json").map(|c| c.path.as_str()),
+            Some("/usr/share/cosboard/layouts/example_qwerty.json")
+        );
+
+        cache.invalidate();
+        assert!(cache.is_empty());
+    }
+
+    /// Test: Invalidating a path drops entries read from it, by either name.
+    #[test]
+    fn test_layout_cache_invalidate_path() {
+        let mut cache = LayoutCache::new();
+        cache.insert(
+            "example_qwerty.json",
+            cached("/layouts/example_qwerty.json"),
+        );
+        cache.insert("/layouts/numpad.json", cached("/layouts/numpad.json"));
+
+        assert!(cache.invalidate_path("/layouts/example_qwerty.json"));
+        assert!(cache.get("example_qwerty.json").is_none());
+        assert!(!cache.invalidate_path("/layouts/example_qwerty.json"));
+
+        assert!(cache.invalidate_path("/layouts/numpad.json"));
+        assert!(cache.is_empty());
+    }
+}
@@ -6,12 +6,12 @@
 //! and child layouts, and detecting circular references.
 
 use crate::layout::types::{Cell, Key, Layout, Panel, ParseError, Row};
+use crate::storage::{NativeStorage, Storage};
 use std::collections::HashSet;
-use std::fs;
 use std::path::{Path, PathBuf};
 
 /// Maximum inheritance depth allowed
-const MAX_INHERITANCE_DEPTH: usize = 5;
+pub(crate) const MAX_INHERITANCE_DEPTH: usize = 5;
 
 /// Resolves inheritance for a layout.
 ///
@@ -48,6 +48,15 @@ const MAX_INHERITANCE_DEPTH: usize = 5;
 pub fn resolve_inheritance(
     layout: Layout,
     layout_path: Option<&str>,
+) -> Result<Layout, ParseError> {
+    resolve_inheritance_with(&NativeStorage, layout, layout_path)
+}
+
+/// Resolves inheritance, reading parent layouts through `storage`.
+pub fn resolve_inheritance_with(
+    storage: &dyn Storage,
+    layout: Layout,
+    layout_path: Option<&str>,
 ) -> Result<Layout, ParseError> {
     let mut visited = HashSet::new();
 
@@ -58,11 +67,12 @@ pub fn resolve_inheritance(
         visited.insert(canonical);
     }
 
-    resolve_inheritance_recursive(layout, layout_path, &mut visited, 0)
+    resolve_inheritance_recursive(storage, layout, layout_path, &mut visited, 0)
 }
 
 /// Internal recursive function for resolving inheritance.
 fn resolve_inheritance_recursive(
+    storage: &dyn Storage,
     layout: Layout,
     layout_path: Option<&str>,
     visited: &mut HashSet<PathBuf>,
@@ -115,10 +125,11 @@ fn resolve_inheritance_recursive(
     visited.insert(canonical_path.clone());
 
     // Load parent layout (without resolving its inheritance yet)
-    let parent_layout = load_parent_layout_raw(resolved_parent_path.to_str().unwrap())?;
+    let parent_layout = load_parent_layout_raw(storage, resolved_parent_path.to_str().unwrap())?;
 
     // Recursively resolve parent's inheritance
     let resolved_parent = resolve_inheritance_recursive(
+        storage,
         parent_layout,
         Some(resolved_parent_path.to_str().unwrap()),
         visited,
@@ -134,9 +145,10 @@ fn resolve_inheritance_recursive(
 /// Loads a parent layout file without resolving inheritance.
 ///
 /// This is a helper to avoid circular dependency with the parser module.
-fn load_parent_layout_raw(parent_path: &str) -> Result<Layout, ParseError> {
-    // Read file from filesystem
-    let json_str = fs::read_to_string(parent_path)
+fn load_parent_layout_raw(storage: &dyn Storage, parent_path: &str) -> Result<Layout, ParseError> {
+    // Read file through the storage backend
+    let json_str = storage
+        .read_to_string(Path::new(parent_path))
         .map_err(|e| ParseError::io_error_with_path(e, parent_path))?;
 
     // Parse JSON using serde_json
@@ -195,6 +207,8 @@ pub fn merge_layouts(child: Layout, parent: Layout) -> Layout {
     merged.author = child.author.or(merged.author);
     merged.language = child.language.or(merged.language);
     merged.locale = child.locale.or(merged.locale);
+    merged.feedback = child.feedback.or(merged.feedback);
+    merged.accent_cycling = child.accent_cycling.or(merged.accent_cycling);
     merged.version = child.version;
     merged.default_panel_id = child.default_panel_id;
 
@@ -204,6 +218,11 @@ pub fn merge_layouts(child: Layout, parent: Layout) -> Layout {
     // Merge panels
     merged.panels = override_panels(child.panels, merged.panels);
 
+    // Chords declared by the child replace the parent's
+    if !child.chords.is_empty() {
+        merged.chords = child.chords;
+    }
+
     merged
 }
 
@@ -673,6 +692,9 @@ mod tests {
             locale: Some("en_US".to_string()),
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels: HashMap::new(),
         };
@@ -685,6 +707,9 @@ mod tests {
             locale: Some("en_GB".to_string()), // Should override parent
             version: "2.0".to_string(),
             default_panel_id: "child_main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: Some("parent.json".to_string()),
             panels: HashMap::new(),
         };
@@ -708,6 +733,9 @@ mod tests {
             name: "Test".to_string(),
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None, // No inheritance
             ..Layout::default()
         };
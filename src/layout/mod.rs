@@ -14,6 +14,8 @@
 //! - **Helpful error messages**: Includes line numbers, field paths, and suggestions
 //! - **Widget support**: Embed widgets like trackpads and prediction bars
 //! - **Panel references**: Nest panels within other panels for modular layouts
+//! - **Search path**: User, system and built-in layout directories with shadowing
+//! - **Hot reload**: Watch a layout and its parents for changes
 //!
 //! # Example Usage
 //!
@@ -115,13 +117,14 @@
 //!
 //! ## Layout Inheritance
 //!
-//! Layouts can inherit from parent layouts using the `inherits` field:
+//! Layouts can inherit from parent layouts using the `inherits` field, a
+//! path relative to the inheriting layout's file:
 //!
 //! ```json
 //! {
 //!     "name": "QWERTY with Numpad",
 //!     "version": "1.0",
-//!     "inherits": "resources/layouts/example_qwerty_base.json",
+//!     "inherits": "example_qwerty_base.json",
 //!     "default_panel_id": "main",
 //!     "panels": {
 //!         "numpad": {
@@ -136,21 +139,35 @@
 //! merging panels and keys by their IDs.
 
 // Sub-modules
+pub mod cache;
 pub mod inheritance;
 pub mod parser;
+pub mod sources;
 pub mod types;
 pub mod validation;
+pub mod watcher;
 
 // Re-export public API - Error handling types
 pub use types::{ParseError, ParseResult, Severity, ValidationIssue};
 
 // Re-export public API - Parser functions
-pub use parser::{parse_layout_file, parse_layout_from_string};
+pub use parser::{parse_layout_file, parse_layout_file_with, parse_layout_from_string};
+
+// Re-export public API - Parsed layout cache
+pub use cache::{CachedLayout, LayoutCache};
+
+// Re-export public API - Layout search path
+pub use sources::{
+    LayoutEntry, LayoutProvider, LayoutSource, ProviderChain, DEFAULT_LAYOUT_FILE,
+};
+
+// Re-export public API - Layout file watching
+pub use watcher::LayoutWatcher;
 
 // Re-export public API - Data structures
 pub use types::{
-    Action, AlternativeKey, Cell, Key, KeyCode, Layout, Modifier, Panel, PanelRef, Row,
-    Sizing, SwipeDirection, Widget,
+    AccentCycling, Action, AlternativeKey, Cell, Chord, FeedbackProfile, Key, KeyCode, Layout,
+    Modifier, Panel, PanelRef, Row, Sizing, SwipeDirection, Widget,
 };
 
 // ============================================================================
@@ -438,6 +455,9 @@ mod tests {
             padding: None,
             margin: None,
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![_row],
         };
 
@@ -453,6 +473,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         };
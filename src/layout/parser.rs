@@ -5,10 +5,11 @@
 //! This module provides functions for parsing keyboard layout definitions from
 //! JSON files and strings, with support for inheritance resolution and validation.
 
-use crate::layout::inheritance::resolve_inheritance;
+use crate::layout::inheritance::resolve_inheritance_with;
 use crate::layout::types::{Layout, ParseError, ParseResult};
 use crate::layout::validation::validate_layout;
-use std::fs;
+use crate::storage::{NativeStorage, Storage};
+use std::path::Path;
 
 /// Parses a keyboard layout from a JSON file.
 ///
@@ -43,8 +44,21 @@ use std::fs;
 /// }
 /// ```
 pub fn parse_layout_file(path: &str) -> Result<ParseResult<Layout>, ParseError> {
-    // Read file from filesystem
-    let json_str = fs::read_to_string(path)
+    parse_layout_file_with(&NativeStorage, path)
+}
+
+/// Parses a keyboard layout from a JSON file read through `storage`.
+///
+/// Same as [`parse_layout_file`], but the layout and any layouts it inherits
+/// from are read through the given [`Storage`], so sandboxed builds get the
+/// sandbox's access rules and error messages.
+pub fn parse_layout_file_with(
+    storage: &dyn Storage,
+    path: &str,
+) -> Result<ParseResult<Layout>, ParseError> {
+    // Read file through the storage backend
+    let json_str = storage
+        .read_to_string(Path::new(path))
         .map_err(|e| ParseError::io_error_with_path(e, path))?;
 
     // Parse JSON using serde_json
@@ -52,7 +66,7 @@ pub fn parse_layout_file(path: &str) -> Result<ParseResult<Layout>, ParseError>
         .map_err(|e| ParseError::json_error_with_path(e, path))?;
 
     // Resolve inheritance if present
-    let resolved_layout = resolve_inheritance(layout, Some(path))?;
+    let resolved_layout = resolve_inheritance_with(storage, layout, Some(path))?;
 
     // Validate the layout and collect warnings
     validate_layout(resolved_layout)
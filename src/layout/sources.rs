@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Layout search path as an ordered chain of providers.
+//!
+//! Layout files are looked up in a fixed order of directories, each tagged
+//! with the [`LayoutSource`] it represents:
+//!
+//! 1. **User**: `$XDG_DATA_HOME/cosboard/layouts` (`~/.local/share/cosboard/layouts`)
+//! 2. **System**: `/usr/local/share/cosboard/layouts`, then `/usr/share/cosboard/layouts`
+//! 3. **Built-in**: `resources/layouts` next to the working directory (development builds)
+//!
+//! The directories come from the [`Storage`] backend, so in a Flatpak the
+//! user directory is the per-app data home and system layouts live under
+//! `/app/share`. All file access also goes through the backend.
+//!
+//! Layouts are identified by file name. A file in a higher-precedence
+//! directory shadows files with the same name further down the chain, so a
+//! user can override a system layout by copying it into their user directory
+//! and editing it. [`ProviderChain::list`] reports, for every layout, where it
+//! was found and which sources it shadows.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::storage::{self, NativeStorage, Storage};
+
+/// File name of the layout loaded when none has been chosen.
+pub const DEFAULT_LAYOUT_FILE: &str = "example_qwerty.json";
+
+/// Where a layout file comes from, in precedence order (highest first).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LayoutSource {
+    /// Per-user layouts directory.
+    User,
+    /// System-wide installation directory.
+    System,
+    /// Layouts shipped in the source tree.
+    BuiltIn,
+}
+
+impl LayoutSource {
+    /// Returns a short, human-readable name for the source.
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            LayoutSource::User => "user",
+            LayoutSource::System => "system",
+            LayoutSource::BuiltIn => "built-in",
+        }
+    }
+}
+
+impl fmt::Display for LayoutSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// A directory searched for layout files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutProvider {
+    /// Source this directory represents.
+    pub source: LayoutSource,
+    /// Directory containing `*.json` layout files.
+    pub dir: PathBuf,
+}
+
+impl LayoutProvider {
+    /// Creates a provider for `dir`.
+    pub fn new(source: LayoutSource, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            source,
+            dir: dir.into(),
+        }
+    }
+
+    /// Lists the layout file names in this provider's directory.
+    ///
+    /// A missing or unreadable directory simply provides no layouts.
+    fn file_names(&self, storage: &dyn Storage) -> Vec<String> {
+        storage
+            .list_dir(&self.dir)
+            .map(|paths| {
+                paths
+                    .into_iter()
+                    .filter(|path| storage.is_file(path))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// A layout file found on the search path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutEntry {
+    /// File name identifying the layout (e.g. `example_qwerty.json`).
+    pub file_name: String,
+    /// Full path of the file that is used.
+    pub path: PathBuf,
+    /// Source of the file that is used.
+    pub source: LayoutSource,
+    /// Lower-precedence sources that also provide this file name.
+    pub shadowed: Vec<LayoutSource>,
+}
+
+/// Ordered list of layout providers, highest precedence first.
+#[derive(Debug, Clone)]
+pub struct ProviderChain {
+    providers: Vec<LayoutProvider>,
+    storage: Arc<dyn Storage>,
+}
+
+impl Default for ProviderChain {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl ProviderChain {
+    /// Creates a chain searching `providers` in order on the native filesystem.
+    pub fn new(providers: Vec<LayoutProvider>) -> Self {
+        Self::with_storage(Arc::new(NativeStorage), providers)
+    }
+
+    /// Creates a chain searching `providers` in order through `storage`.
+    pub fn with_storage(storage: Arc<dyn Storage>, providers: Vec<LayoutProvider>) -> Self {
+        Self { providers, storage }
+    }
+
+    /// Creates the standard chain for the current environment.
+    pub fn standard() -> Self {
+        Self::standard_for(storage::detect())
+    }
+
+    /// Creates the standard chain (user, system, then built-in layouts)
+    /// using the directories of `storage`.
+    pub fn standard_for(storage: Arc<dyn Storage>) -> Self {
+        let mut providers = Vec::new();
+
+        if let Some(data_home) = storage.user_data_dir() {
+            providers.push(LayoutProvider::new(
+                LayoutSource::User,
+                data_home.join("cosboard/layouts"),
+            ));
+        }
+        for dir in storage.system_data_dirs() {
+            providers.push(LayoutProvider::new(
+                LayoutSource::System,
+                dir.join("cosboard/layouts"),
+            ));
+        }
+        if let Ok(cwd) = std::env::current_dir() {
+            providers.push(LayoutProvider::new(
+                LayoutSource::BuiltIn,
+                cwd.join("resources/layouts"),
+            ));
+        }
+
+        Self::with_storage(storage, providers)
+    }
+
+    /// Returns the storage backend used for file access.
+    pub fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+
+    /// Returns the providers, highest precedence first.
+    pub fn providers(&self) -> &[LayoutProvider] {
+        &self.providers
+    }
+
+    /// Lists every available layout, sorted by file name.
+    ///
+    /// Each file name appears once, resolved to the highest-precedence
+    /// provider that has it.
+    #[must_use]
+    pub fn list(&self) -> Vec<LayoutEntry> {
+        let mut entries: BTreeMap<String, LayoutEntry> = BTreeMap::new();
+
+        for provider in &self.providers {
+            for file_name in provider.file_names(self.storage()) {
+                match entries.get_mut(&file_name) {
+                    Some(entry) => {
+                        if entry.source != provider.source
+                            && !entry.shadowed.contains(&provider.source)
+                        {
+                            entry.shadowed.push(provider.source);
+                        }
+                    }
+                    None => {
+                        let path = provider.dir.join(&file_name);
+                        entries.insert(
+                            file_name.clone(),
+                            LayoutEntry {
+                                file_name,
+                                path,
+                                source: provider.source,
+                                shadowed: Vec::new(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        entries.into_values().collect()
+    }
+
+    /// Resolves a layout by file name, honoring precedence.
+    #[must_use]
+    pub fn resolve(&self, file_name: &str) -> Option<LayoutEntry> {
+        self.list()
+            .into_iter()
+            .find(|entry| entry.file_name == file_name)
+    }
+
+    /// Resolves a layout given either a path or a bare file name.
+    ///
+    /// Existing paths are used as-is (their source is the provider whose
+    /// directory contains them, if any); other values are looked up by file
+    /// name on the chain.
+    #[must_use]
+    pub fn locate(&self, path_or_name: &str) -> Option<(PathBuf, Option<LayoutSource>)> {
+        let path = Path::new(path_or_name);
+        if self.storage.is_file(path) {
+            return Some((path.to_path_buf(), self.source_of(path)));
+        }
+
+        let file_name = path.file_name()?.to_str()?;
+        self.resolve(file_name)
+            .map(|entry| (entry.path, Some(entry.source)))
+    }
+
+    /// Returns the source whose directory contains `path`.
+    #[must_use]
+    pub fn source_of(&self, path: &Path) -> Option<LayoutSource> {
+        let dir = path.parent()?;
+        self.providers
+            .iter()
+            .find(|provider| same_dir(&provider.dir, dir))
+            .map(|provider| provider.source)
+    }
+}
+
+/// Compares directories, resolving symlinks and relative paths when possible.
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty scratch directory for a test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cosboard-sources-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn touch(dir: &Path, file_name: &str) {
+        std::fs::write(dir.join(file_name), "{}").unwrap();
+    }
+
+    /// Test: Higher-precedence providers shadow files with the same name.
+    #[test]
+    fn test_shadowing_by_file_name() {
+        let root = scratch_dir("shadow");
+        let user = root.join("user");
+        let system = root.join("system");
+        let builtin = root.join("builtin");
+        for dir in [&user, &system, &builtin] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        touch(&user, "qwerty.json");
+        touch(&system, "qwerty.json");
+        touch(&system, "azerty.json");
+        touch(&builtin, "qwerty.json");
+        touch(&builtin, "notes.txt");
+
+        let chain = ProviderChain::new(vec![
+            LayoutProvider::new(LayoutSource::User, &user),
+            LayoutProvider::new(LayoutSource::System, &system),
+            LayoutProvider::new(LayoutSource::BuiltIn, &builtin),
+        ]);
+
+        let list = chain.list();
+        let names: Vec<&str> = list.iter().map(|e| e.file_name.as_str()).collect();
+        assert_eq!(names, vec!["azerty.json", "qwerty.json"]);
+
+        let qwerty = chain.resolve("qwerty.json").unwrap();
+        assert_eq!(qwerty.source, LayoutSource::User);
+        assert_eq!(qwerty.path, user.join("qwerty.json"));
+        assert_eq!(
+            qwerty.shadowed,
+            vec![LayoutSource::System, LayoutSource::BuiltIn]
+        );
+
+        let azerty = chain.resolve("azerty.json").unwrap();
+        assert_eq!(azerty.source, LayoutSource::System);
+        assert!(azerty.shadowed.is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    /// Test: Paths and bare file names are both located with provenance.
+    #[test]
+    fn test_locate() {
+        let root = scratch_dir("locate");
+        let system = root.join("system");
+        std::fs::create_dir_all(&system).unwrap();
+        touch(&system, "qwerty.json");
+        touch(&root, "custom.json");
+
+        let chain = ProviderChain::new(vec![
+            LayoutProvider::new(LayoutSource::User, root.join("missing")),
+            LayoutProvider::new(LayoutSource::System, &system),
+        ]);
+
+        let (path, source) = chain.locate("qwerty.json").unwrap();
+        assert_eq!(path, system.join("qwerty.json"));
+        assert_eq!(source, Some(LayoutSource::System));
+
+        let custom = root.join("custom.json");
+        let (path, source) = chain.locate(custom.to_str().unwrap()).unwrap();
+        assert_eq!(path, custom);
+        assert_eq!(source, None);
+
+        assert!(chain.locate("nope.json").is_none());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
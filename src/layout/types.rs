@@ -476,6 +476,9 @@ pub enum Modifier {
     Alt,
     /// Super/Windows/Meta modifier
     Super,
+    /// Caps Lock, latched by a tap until tapped again rather than held
+    /// with keys
+    CapsLock,
 }
 
 /// Swipe direction for gesture alternatives.
@@ -512,6 +515,16 @@ impl AlternativeKey {
         modifiers.sort();
         AlternativeKey::ModifierCombo(modifiers)
     }
+
+    /// Returns the layer of the modifiers active together: the modifier
+    /// alone, or their combination. Returns `None` without modifiers.
+    pub fn layer(modifiers: &[Modifier]) -> Option<Self> {
+        match modifiers {
+            [] => None,
+            [modifier] => Some(AlternativeKey::SingleModifier(*modifier)),
+            _ => Some(AlternativeKey::modifier_combo(modifiers.to_vec())),
+        }
+    }
 }
 
 /// Action to perform when a key or alternative is activated.
@@ -528,6 +541,17 @@ pub enum Action {
     PanelSwitch(String),
 }
 
+impl Action {
+    /// Returns the key code the action types, if it types one.
+    pub fn key_code(&self) -> Option<KeyCode> {
+        match self {
+            Action::Character(c) => Some(KeyCode::Unicode(*c)),
+            Action::KeyCode(code) => Some(code.clone()),
+            Action::Script(_) | Action::PanelSwitch(_) => None,
+        }
+    }
+}
+
 /// Default value for `stickyrelease` field.
 ///
 /// Returns `true` because the default behavior for sticky keys is one-shot mode,
@@ -599,6 +623,24 @@ pub struct Key {
     ///   the user taps the modifier key again to deactivate it.
     #[serde(default = "default_stickyrelease")]
     pub stickyrelease: bool,
+
+    /// Action run after the key is released (e.g. `"hide"`).
+    ///
+    /// Uses the same action names as command keys, so `"switch_panel:main"`
+    /// returns to the main panel after typing the key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+
+    /// Sound played when the key is pressed (overrides the layout's `feedback`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<FeedbackProfile>,
+
+    /// Keys tapped in order after the key is released.
+    ///
+    /// A `(` key with `["parenright", "Left"]` types the pair and puts the
+    /// cursor between the brackets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_keys: Vec<KeyCode>,
 }
 
 impl Default for Key {
@@ -614,6 +656,28 @@ impl Default for Key {
             alternatives: HashMap::new(),
             sticky: false,
             stickyrelease: true, // Default to one-shot behavior
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
+        }
+    }
+}
+
+impl Key {
+    /// Returns the key code the key types on a modifier `layer`, if its
+    /// `alternatives` have one for it.
+    ///
+    /// With Shift active, a `1` key with `"Shift": "!"` types `!`.
+    pub fn layer_code(&self, layer: &AlternativeKey) -> Option<KeyCode> {
+        self.alternatives.get(layer).and_then(Action::key_code)
+    }
+
+    /// Returns the label the key shows on a modifier `layer`: the character
+    /// it types there, if its `alternatives` have one for it.
+    pub fn layer_label(&self, layer: &AlternativeKey) -> Option<String> {
+        match self.layer_code(layer)? {
+            KeyCode::Unicode(c) => Some(c.to_string()),
+            KeyCode::Keysym(_) => None,
         }
     }
 }
@@ -699,6 +763,21 @@ pub struct Panel {
     #[serde(default)]
     pub nesting_depth: u8,
 
+    /// Piano mode: sliding a finger across keys types each key it passes
+    #[serde(default)]
+    pub piano: bool,
+
+    /// Number of rows shown at once. Taller panels (e.g. emoji panels with
+    /// thousands of cells) scroll, and only the rows in view are rendered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible_rows: Option<usize>,
+
+    /// Width the rows are designed to fit, in units. Keys are sized for at
+    /// least this many units, and rows wider than it are reported by
+    /// validation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<f32>,
+
     /// Rows of cells in this panel
     #[serde(default)]
     pub rows: Vec<Row>,
@@ -711,6 +790,9 @@ impl Default for Panel {
             padding: None,
             margin: None,
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: Vec::new(),
         }
     }
@@ -753,6 +835,18 @@ pub struct Layout {
     /// Panels indexed by ID
     #[serde(default)]
     pub panels: HashMap<String, Panel>,
+
+    /// Chords: sets of keys pressed together that emit a different key
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chords: Vec<Chord>,
+
+    /// Sound played for keys that do not declare their own `feedback`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<FeedbackProfile>,
+
+    /// Accented letters typed by tapping a letter repeatedly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent_cycling: Option<AccentCycling>,
 }
 
 impl Default for Layout {
@@ -776,10 +870,77 @@ impl Default for Layout {
             default_panel_id: "main".to_string(),
             inherits: None,
             panels,
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
+        }
+    }
+}
+
+/// Sound played when a key is pressed.
+///
+/// In JSON: `"silent"`, `"click"`, `"pop"`, `{"sample": "path/to/sound.wav"}`,
+/// or `{"theme": "cosboard-key"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackProfile {
+    /// No sound
+    Silent,
+    /// Short, sharp click (the usual key sound)
+    Click,
+    /// Softer, lower pop (e.g. for Backspace or Space)
+    Pop,
+    /// A WAV file, relative to the layout file or absolute
+    Sample(String),
+    /// An event of the user's freedesktop sound theme (e.g. `cosboard-key`
+    /// or `dialog-information`), or the click if the theme has none
+    Theme(String),
+}
+
+/// Keys pressed together that emit a different key.
+///
+/// For example, `{"keys": ["a", "s"], "code": "Escape"}` emits Escape when
+/// "a" and "s" are pressed at (nearly) the same time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chord {
+    /// Identifiers of the keys in the chord (two or more, in any order)
+    pub keys: Vec<String>,
+
+    /// Key code emitted instead of the individual keys
+    pub code: KeyCode,
+}
+
+/// Accented letters typed by tapping a letter repeatedly.
+///
+/// Tapping a letter again within `timeout_ms` replaces it with the next
+/// character of its cycle: `e`, `é`, `è`, `ê`, `ë`, then `e` again. In JSON:
+/// `{"timeout_ms": 500, "cycles": {"e": "éèêë", "a": "àâ"}}`. Without
+/// `cycles`, the common accents of the Latin vowels, `c` and `n` are used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccentCycling {
+    /// Longest pause between taps that still cycles, in milliseconds
+    #[serde(default = "default_accent_timeout")]
+    pub timeout_ms: u64,
+
+    /// Accents of each lowercase letter, in the order taps reach them
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub cycles: HashMap<String, String>,
+}
+
+impl Default for AccentCycling {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_accent_timeout(),
+            cycles: HashMap::new(),
         }
     }
 }
 
+/// Default pause allowed between the taps of an accent cycle.
+fn default_accent_timeout() -> u64 {
+    600
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -1000,6 +1161,40 @@ mod tests {
         assert!(key.stickyrelease); // Default should be true
     }
 
+    /// Test: Keys show and type their alternative for the layer of the
+    /// active modifiers, and only modifier layers count.
+    #[test]
+    fn test_key_layers() {
+        let mut key = Key {
+            label: "1".to_string(),
+            code: KeyCode::Unicode('1'),
+            ..Key::default()
+        };
+        key.alternatives.insert(
+            AlternativeKey::SingleModifier(Modifier::Shift),
+            Action::Character('!'),
+        );
+        key.alternatives.insert(
+            AlternativeKey::modifier_combo(vec![Modifier::Shift, Modifier::Alt]),
+            Action::KeyCode(KeyCode::Keysym("onesuperior".to_string())),
+        );
+
+        assert_eq!(AlternativeKey::layer(&[]), None);
+        let shift = AlternativeKey::layer(&[Modifier::Shift]).unwrap();
+        assert_eq!(key.layer_code(&shift), Some(KeyCode::Unicode('!')));
+        assert_eq!(key.layer_label(&shift), Some("!".to_string()));
+
+        let shift_alt = AlternativeKey::layer(&[Modifier::Alt, Modifier::Shift]).unwrap();
+        assert_eq!(
+            key.layer_code(&shift_alt),
+            Some(KeyCode::Keysym("onesuperior".to_string()))
+        );
+        assert_eq!(key.layer_label(&shift_alt), None);
+
+        let ctrl = AlternativeKey::layer(&[Modifier::Ctrl]).unwrap();
+        assert_eq!(key.layer_code(&ctrl), None);
+    }
+
     /// Test 4: Sizing enum variants
     #[test]
     fn test_sizing_variants() {
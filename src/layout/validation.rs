@@ -5,8 +5,10 @@
 //! This module implements permissive validation that collects warnings
 //! and provides sensible defaults for missing or invalid values.
 
+use crate::actions::ActionRegistry;
+use crate::input::{parse_command, parse_keycode};
 use crate::layout::types::{
-    Action, AlternativeKey, Cell, Key, Layout, Panel, ParseError, ParseResult,
+    Action, AlternativeKey, Cell, Key, Layout, Modifier, Panel, ParseError, ParseResult, Row,
     Severity, Sizing, ValidationIssue,
 };
 use std::collections::{HashMap, HashSet};
@@ -14,6 +16,19 @@ use std::collections::{HashMap, HashSet};
 /// Maximum allowed nesting depth for panel references
 const MAX_NESTING_DEPTH: u8 = 5;
 
+/// How many units a row may exceed the typical row width before we warn.
+///
+/// Half a unit leaves room for the usual staggered layouts (e.g. a 10-unit
+/// top row above a 9-unit home row) while catching an extra key or an
+/// oversized modifier that would shrink every other key on the panel.
+const ROW_WIDTH_TOLERANCE: f32 = 0.5;
+
+/// Rows narrower than this fraction of the typical row width are flagged.
+const MIN_ROW_WIDTH_RATIO: f32 = 0.5;
+
+/// Rounding slack when comparing a row with the panel's declared `units`.
+const UNITS_SLACK: f32 = 0.001;
+
 /// Validates a layout and returns it with warnings.
 ///
 /// This function performs comprehensive validation on a layout, collecting
@@ -31,6 +46,15 @@ pub fn validate_layout(mut layout: Layout) -> Result<ParseResult<Layout>, ParseE
     // Validate modifier combinations
     validate_all_modifier_combinations(&layout, &mut warnings);
 
+    // Validate that rows within each panel have consistent widths
+    validate_all_row_widths(&layout, &mut warnings);
+
+    // Validate chord definitions
+    validate_chords(&layout, &mut warnings);
+
+    // Validate accent cycles
+    validate_accent_cycling(&layout, &mut warnings);
+
     // Validate panel references (this can add warnings)
     validate_panel_references(&layout, &mut warnings)?;
 
@@ -141,6 +165,39 @@ fn validate_key_required_fields(key: &Key, key_path: &str, warnings: &mut Vec<Va
     }
 
     // Note: code has a default value (Unicode(' ')), so we don't need to check for empty
+
+    // An empty `after` is allowed: it opts the key out of post-action policies
+    if let Some(after) = key.after.as_deref().filter(|after| !after.trim().is_empty()) {
+        if let Err(e) = ActionRegistry::new().parse(after) {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    format!("Invalid post-action '{}': {}", after, e),
+                    format!("{}.after", key_path),
+                )
+                .with_suggestion("Use an action name such as \"hide\" or \"switch_panel:main\""),
+            );
+        }
+    }
+
+    // Post keys are typed, so command keys among them would be sent as keysyms
+    for (index, code) in key.post_keys.iter().enumerate() {
+        let message = if parse_command(code).is_some() {
+            format!("Command key '{}' cannot be a post key", code)
+        } else if parse_keycode(code).is_none() {
+            format!("Post key '{}' is not a valid key code", code)
+        } else {
+            continue;
+        };
+        warnings.push(
+            ValidationIssue::new(
+                Severity::Warning,
+                message,
+                format!("{}.post_keys[{}]", key_path, index),
+            )
+            .with_suggestion("Use characters or keysyms such as \"parenright\" or \"Left\"; run actions with \"after\""),
+        );
+    }
 }
 
 /// Validates sizing values across all keys and widgets.
@@ -254,6 +311,148 @@ fn validate_all_sizing(layout: &Layout, warnings: &mut Vec<ValidationIssue>) {
     }
 }
 
+/// Calculates the total relative width of a row in units.
+///
+/// Mirrors the renderer's row width calculation: relative sizes contribute
+/// their value and pixel sizes count as a single unit.
+pub fn row_relative_width(row: &Row) -> f32 {
+    row.cells
+        .iter()
+        .map(|cell| {
+            let width = match cell {
+                Cell::Key(key) => &key.width,
+                Cell::Widget(widget) => &widget.width,
+                Cell::PanelRef(panel_ref) => &panel_ref.width,
+            };
+            match width {
+                Sizing::Relative(w) => *w,
+                Sizing::Pixels(_) => 1.0,
+            }
+        })
+        .sum()
+}
+
+/// Formats a unit count without a trailing ".0" for whole numbers.
+fn format_units(units: f32) -> String {
+    if units.fract() == 0.0 {
+        format!("{}", units as i64)
+    } else {
+        format!("{:.1}", units)
+    }
+}
+
+/// Validates that the rows of a panel have consistent widths.
+///
+/// Rows wider than the panel's declared `units` are flagged first: the
+/// renderer sizes keys from the widest row, so they shrink every key the
+/// panel was designed for.
+///
+/// Otherwise the typical row width is the most common width among the
+/// panel's rows (the widest one wins ties). Rows that exceed it by more than
+/// `ROW_WIDTH_TOLERANCE` units are flagged for the same reason. Rows
+/// narrower than half the typical width are flagged as well.
+pub fn validate_row_widths(panel: &Panel, panel_path: &str, warnings: &mut Vec<ValidationIssue>) {
+    let widths: Vec<f32> = panel.rows.iter().map(row_relative_width).collect();
+
+    let units = panel.units;
+    if let Some(units) = units.filter(|units| *units <= 0.0) {
+        warnings.push(ValidationIssue::new(
+            Severity::Warning,
+            format!("Panel units {} is not positive", format_units(units)),
+            format!("{}.units", panel_path),
+        ));
+    }
+    let units = units.filter(|units| *units > 0.0);
+
+    // Find the most common row width, preferring the wider one on ties
+    let mut counts: Vec<(f32, usize)> = Vec::new();
+    for &width in &widths {
+        match counts
+            .iter_mut()
+            .find(|(w, _)| (*w - width).abs() < f32::EPSILON)
+        {
+            Some((_, count)) => *count += 1,
+            None => counts.push((width, 1)),
+        }
+    }
+    let typical = counts
+        .iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(a.0.total_cmp(&b.0)))
+        .map(|(w, _)| *w)
+        .filter(|typical| widths.len() >= 2 && *typical > 0.0);
+
+    for (row_idx, &width) in widths.iter().enumerate() {
+        let row_path = format!("{}.rows[{}]", panel_path, row_idx);
+
+        if let Some(units) = units.filter(|units| width - units > UNITS_SLACK) {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    format!(
+                        "Row is {} units wider than the panel",
+                        format_units(width - units)
+                    ),
+                    row_path,
+                )
+                .with_suggestion(format!(
+                    "row {} is {} units; the panel declares {}; narrow or remove keys so every key is not shrunk to fit",
+                    row_idx + 1,
+                    format_units(width),
+                    format_units(units)
+                )),
+            );
+            continue;
+        }
+
+        let Some(typical) = typical else {
+            continue;
+        };
+        let suggestion = format!(
+            "row {} is {} units; other rows are {}",
+            row_idx + 1,
+            format_units(width),
+            format_units(typical)
+        );
+
+        if width - typical > ROW_WIDTH_TOLERANCE {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    format!(
+                        "Row is {} units wider than the other rows",
+                        format_units(width - typical)
+                    ),
+                    row_path,
+                )
+                .with_suggestion(format!(
+                    "{}; narrow or remove keys so every key is not shrunk to fit",
+                    suggestion
+                )),
+            );
+        } else if width < typical * MIN_ROW_WIDTH_RATIO {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    "Row is much narrower than the other rows",
+                    row_path,
+                )
+                .with_suggestion(format!(
+                    "{}; widen keys (e.g. a wider space bar) to fill the row",
+                    suggestion
+                )),
+            );
+        }
+    }
+}
+
+/// Validates row widths for all panels in the layout.
+fn validate_all_row_widths(layout: &Layout, warnings: &mut Vec<ValidationIssue>) {
+    for (panel_id, panel) in &layout.panels {
+        let panel_path = format!("panels[{}]", panel_id);
+        validate_row_widths(panel, &panel_path, warnings);
+    }
+}
+
 /// Validates modifier combinations in key alternatives.
 pub fn validate_modifier_combinations(
     alternatives: &HashMap<AlternativeKey, Action>,
@@ -261,6 +460,24 @@ pub fn validate_modifier_combinations(
     warnings: &mut Vec<ValidationIssue>,
 ) {
     for (alt_key, _) in alternatives {
+        // Caps Lock is latched rather than held with keys, so keys have no
+        // Caps Lock layer
+        let caps_lock = match alt_key {
+            AlternativeKey::SingleModifier(modifier) => *modifier == Modifier::CapsLock,
+            AlternativeKey::ModifierCombo(modifiers) => modifiers.contains(&Modifier::CapsLock),
+            AlternativeKey::Swipe(_) => false,
+        };
+        if caps_lock {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    "Caps Lock alternatives are never used",
+                    format!("{}.alternatives", key_path),
+                )
+                .with_suggestion("Caps Lock is a lock key, not a layer; use Shift instead"),
+            );
+        }
+
         match alt_key {
             AlternativeKey::ModifierCombo(modifiers) => {
                 // Check for empty combinations
@@ -339,6 +556,88 @@ fn validate_all_modifier_combinations(layout: &Layout, warnings: &mut Vec<Valida
     }
 }
 
+/// Validates chords: each needs two or more distinct keys that exist in the layout.
+///
+/// Keys are matched by identifier, falling back to the label like the renderer does.
+pub fn validate_chords(layout: &Layout, warnings: &mut Vec<ValidationIssue>) {
+    if layout.chords.is_empty() {
+        return;
+    }
+
+    let mut known = HashSet::new();
+    for panel in layout.panels.values() {
+        for row in &panel.rows {
+            for cell in &row.cells {
+                if let Cell::Key(key) = cell {
+                    known.insert(key.identifier.as_deref().unwrap_or(&key.label));
+                }
+            }
+        }
+    }
+
+    for (chord_idx, chord) in layout.chords.iter().enumerate() {
+        let chord_path = format!("chords[{}]", chord_idx);
+
+        let distinct: HashSet<&str> = chord.keys.iter().map(String::as_str).collect();
+        if distinct.len() < 2 || distinct.len() != chord.keys.len() {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    "Chord needs at least two distinct keys",
+                    format!("{}.keys", chord_path),
+                )
+                .with_suggestion("List two or more different key identifiers"),
+            );
+        }
+
+        for key in &chord.keys {
+            if !known.contains(key.as_str()) {
+                warnings.push(
+                    ValidationIssue::new(
+                        Severity::Warning,
+                        format!("Chord key '{}' does not match any key in the layout", key),
+                        format!("{}.keys", chord_path),
+                    )
+                    .with_suggestion("Use the key's identifier (or its label if it has none)"),
+                );
+            }
+        }
+    }
+}
+
+/// Validates accent cycling: each cycle belongs to a single letter, which
+/// its taps return to, so the letter must not be among its accents.
+pub fn validate_accent_cycling(layout: &Layout, warnings: &mut Vec<ValidationIssue>) {
+    let Some(ref cycling) = layout.accent_cycling else {
+        return;
+    };
+
+    let mut letters: Vec<&String> = cycling.cycles.keys().collect();
+    letters.sort();
+    for letter in letters {
+        let path = format!("accent_cycling.cycles.{}", letter);
+        if letter.chars().count() != 1 {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    format!("Accent cycle '{}' is not a single letter", letter),
+                    path,
+                )
+                .with_suggestion("Use the lowercase letter the accents belong to as the key"),
+            );
+        } else if cycling.cycles[letter].contains(letter.as_str()) {
+            warnings.push(
+                ValidationIssue::new(
+                    Severity::Warning,
+                    format!("Accent cycle '{}' lists the letter itself", letter),
+                    path,
+                )
+                .with_suggestion("List only the accents; taps return to the letter after them"),
+            );
+        }
+    }
+}
+
 /// Detects circular references in panel references.
 ///
 /// Uses depth-first search to detect cycles in the panel dependency graph.
@@ -562,8 +861,11 @@ pub fn validate_panel_references(
                         format!("{}.rows[{}].cells[{}].panel_id", panel_path, row_idx, cell_idx);
                     referenced_panels.insert(panel_ref.panel_id.clone());
 
-                    // Check if the referenced panel exists
-                    if !layout.panels.contains_key(&panel_ref.panel_id) {
+                    // Check if the referenced panel exists (built-in panels are
+                    // added after validation)
+                    if !layout.panels.contains_key(&panel_ref.panel_id)
+                        && panel_ref.panel_id != crate::symbols::SYMBOLS_PANEL_ID
+                    {
                         // Provide suggestions for typos
                         let suggestion = if let Some(similar) =
                             find_similar_panel_name(&panel_ref.panel_id, &layout.panels)
@@ -660,7 +962,7 @@ pub fn collect_warnings(layout: Layout, mut warnings: Vec<ValidationIssue>) -> P
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::layout::types::{KeyCode, Modifier, PanelRef, Row};
+    use crate::layout::types::{AccentCycling, Chord, KeyCode, Modifier, PanelRef, Row};
 
     // ========================================================================
     // Task 4.1: Focused tests for validation (2-8 tests)
@@ -949,6 +1251,18 @@ mod tests {
                 .any(|w| w.message.contains("canonical order")),
             "Should NOT warn about sorted modifiers"
         );
+
+        // Test Caps Lock alternatives, which are never used
+        alternatives.clear();
+        alternatives.insert(
+            AlternativeKey::SingleModifier(Modifier::CapsLock),
+            Action::Character('X'),
+        );
+        validate_modifier_combinations(&alternatives, "test_key", &mut warnings);
+        assert!(
+            warnings.iter().any(|w| w.message.contains("Caps Lock")),
+            "Should warn about Caps Lock alternatives"
+        );
     }
 
     /// Test 7: Panel reference validation
@@ -1022,4 +1336,209 @@ mod tests {
             "Should warn about negative sizing"
         );
     }
+
+    /// Test 9: Row width validation
+    #[test]
+    fn test_validate_row_widths() {
+        fn row_of(widths: &[f32]) -> Row {
+            Row {
+                cells: widths
+                    .iter()
+                    .map(|w| {
+                        Cell::Key(Key {
+                            label: "x".to_string(),
+                            width: Sizing::Relative(*w),
+                            ..Key::default()
+                        })
+                    })
+                    .collect(),
+            }
+        }
+
+        // Staggered rows (10, 9, 10, 9) are normal and should not warn
+        let panel = Panel {
+            id: "main".to_string(),
+            rows: vec![
+                row_of(&[1.0; 10]),
+                row_of(&[1.0; 9]),
+                row_of(&[1.0; 10]),
+                row_of(&[1.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.5]),
+            ],
+            ..Panel::default()
+        };
+        let mut warnings = Vec::new();
+        validate_row_widths(&panel, "panels[main]", &mut warnings);
+        assert!(warnings.is_empty(), "Staggered rows should not warn: {:?}", warnings);
+
+        // An oversized third row should be flagged with a concrete suggestion
+        let panel = Panel {
+            id: "main".to_string(),
+            rows: vec![
+                row_of(&[1.0; 10]),
+                row_of(&[1.0; 10]),
+                row_of(&[1.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]),
+                row_of(&[0.5, 1.0, 0.5]),
+            ],
+            ..Panel::default()
+        };
+        let mut warnings = Vec::new();
+        validate_row_widths(&panel, "panels[main]", &mut warnings);
+
+        let oversized = warnings
+            .iter()
+            .find(|w| w.field_path == "panels[main].rows[2]")
+            .expect("Should warn about the oversized row");
+        assert!(oversized.message.contains("wider"));
+        assert!(oversized
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("row 3 is 11.5 units; other rows are 10"));
+
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.field_path == "panels[main].rows[3]" && w.message.contains("narrower")),
+            "Should warn about the undersized row"
+        );
+    }
+
+    /// Test: Rows wider than the panel's declared units are flagged, even
+    /// when every row is equally wide.
+    #[test]
+    fn test_validate_row_widths_declared_units() {
+        let row_of = |count: usize| Row {
+            cells: vec![Cell::Key(Key::default()); count],
+        };
+        let panel = Panel {
+            id: "main".to_string(),
+            units: Some(10.0),
+            rows: vec![row_of(10), row_of(11), row_of(11)],
+            ..Panel::default()
+        };
+        let mut warnings = Vec::new();
+        validate_row_widths(&panel, "panels[main]", &mut warnings);
+
+        let paths: Vec<&str> = warnings.iter().map(|w| w.field_path.as_str()).collect();
+        assert_eq!(paths, ["panels[main].rows[1]", "panels[main].rows[2]"]);
+        assert!(warnings[0].message.contains("wider than the panel"));
+        assert!(warnings[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("row 2 is 11 units; the panel declares 10"));
+
+        // A single row is checked against the declared units too
+        let panel = Panel {
+            units: Some(10.0),
+            rows: vec![row_of(12)],
+            ..Panel::default()
+        };
+        let mut warnings = Vec::new();
+        validate_row_widths(&panel, "panels[main]", &mut warnings);
+        assert_eq!(warnings.len(), 1);
+
+        let panel = Panel {
+            units: Some(0.0),
+            rows: vec![row_of(10)],
+            ..Panel::default()
+        };
+        let mut warnings = Vec::new();
+        validate_row_widths(&panel, "panels[main]", &mut warnings);
+        assert_eq!(warnings[0].field_path, "panels[main].units");
+    }
+
+    /// Test: Unknown post-actions are flagged; valid and empty ones are not.
+    #[test]
+    fn test_validate_key_post_action() {
+        let key_with = |after: &str| Key {
+            label: "Esc".to_string(),
+            after: Some(after.to_string()),
+            ..Key::default()
+        };
+
+        let mut warnings = Vec::new();
+        validate_key_required_fields(&key_with("hide"), "k", &mut warnings);
+        validate_key_required_fields(&key_with("switch_panel:main"), "k", &mut warnings);
+        validate_key_required_fields(&key_with(""), "k", &mut warnings);
+        assert!(warnings.is_empty());
+
+        validate_key_required_fields(&key_with("vanish"), "k", &mut warnings);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field_path, "k.after");
+        assert!(warnings[0].message.contains("vanish"));
+    }
+
+    /// Test: Post keys must be key codes, not command keys.
+    #[test]
+    fn test_validate_post_keys() {
+        let key = Key {
+            label: "(".to_string(),
+            code: KeyCode::Unicode('('),
+            post_keys: vec![
+                KeyCode::Unicode(')'),
+                KeyCode::Keysym("Left".to_string()),
+                KeyCode::Keysym("cosboard:hide".to_string()),
+            ],
+            ..Key::default()
+        };
+
+        let mut warnings = Vec::new();
+        validate_key_required_fields(&key, "k", &mut warnings);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field_path, "k.post_keys[2]");
+    }
+
+    /// Test: Chords must name two or more existing keys.
+    #[test]
+    fn test_validate_chords() {
+        let key = |id: &str| {
+            Cell::Key(Key {
+                label: id.to_uppercase(),
+                identifier: Some(id.to_string()),
+                ..Key::default()
+            })
+        };
+        let mut layout = Layout::default();
+        layout.panels.get_mut("main").unwrap().rows.push(Row {
+            cells: vec![key("a"), key("s")],
+            ..Row::default()
+        });
+
+        let chord = |keys: &[&str]| Chord {
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            code: KeyCode::Keysym("Escape".to_string()),
+        };
+
+        layout.chords = vec![chord(&["a", "s"])];
+        let mut warnings = Vec::new();
+        validate_chords(&layout, &mut warnings);
+        assert!(warnings.is_empty());
+
+        layout.chords = vec![chord(&["a", "a"]), chord(&["a", "q"])];
+        validate_chords(&layout, &mut warnings);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].field_path, "chords[0].keys");
+        assert!(warnings[1].message.contains("'q'"));
+    }
+
+    /// Test: Accent cycles must belong to one letter and not list it.
+    #[test]
+    fn test_validate_accent_cycling() {
+        let mut layout = Layout::default();
+        let mut cycling = AccentCycling::default();
+        cycling.cycles.insert("e".to_string(), "éèêë".to_string());
+        layout.accent_cycling = Some(cycling.clone());
+        let mut warnings = Vec::new();
+        validate_accent_cycling(&layout, &mut warnings);
+        assert!(warnings.is_empty());
+
+        cycling.cycles.insert("ae".to_string(), "æ".to_string());
+        cycling.cycles.insert("o".to_string(), "oô".to_string());
+        layout.accent_cycling = Some(cycling);
+        validate_accent_cycling(&layout, &mut warnings);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].field_path, "accent_cycling.cycles.ae");
+        assert!(warnings[1].message.contains("itself"));
+    }
 }
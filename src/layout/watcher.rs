@@ -0,0 +1,286 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Reloading the active layout when its files change.
+//!
+//! While the keyboard is shown, the applet watches the active layout file
+//! and every layout it inherits from ([`inheritance_chain`]), and loads the
+//! layout again as soon as one of them is saved, so edits show up without
+//! hiding and showing the keyboard. A layout that failed to parse is watched
+//! too: fixing it brings the keyboard back.
+//!
+//! [`watch`] asks the kernel (inotify) to report changes in the
+//! directories of the layout files, so nothing runs until a file is saved.
+//! Directories are watched rather than the files themselves, so editors
+//! that save by renaming a new file over the old one are noticed too. The
+//! [`LayoutWatcher`] then compares modification times to tell whether a
+//! reported change touched one of the layout's files.
+
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use futures::channel::mpsc::{self, Sender};
+use futures::{SinkExt, StreamExt};
+use notify::{RecursiveMode, Watcher};
+
+use super::inheritance::MAX_INHERITANCE_DEPTH;
+use crate::storage::Storage;
+
+/// Returns the path of a layout file and of the layouts it inherits from,
+/// child first.
+///
+/// Parents are found the same way inheritance resolves them: the `inherits`
+/// path, relative to the directory of the layout naming it. The chain stops
+/// at a file that cannot be read or parsed, at a cycle, and after the
+/// maximum inheritance depth.
+#[must_use]
+pub fn inheritance_chain(storage: &dyn Storage, layout_path: &Path) -> Vec<PathBuf> {
+    let mut chain = vec![layout_path.to_path_buf()];
+    while chain.len() <= MAX_INHERITANCE_DEPTH + 1 {
+        let Some(current) = chain.last() else {
+            break;
+        };
+        // Only the `inherits` field matters, the rest may be broken
+        let inherits = storage
+            .read_to_string(current)
+            .ok()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .and_then(|value| value.get("inherits")?.as_str().map(str::to_string));
+        let Some(inherits) = inherits else {
+            break;
+        };
+        let parent = normalize(
+            &current
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(inherits),
+        );
+        if chain.contains(&parent) {
+            break;
+        }
+        chain.push(parent);
+    }
+    chain
+}
+
+/// Removes `.` and `..` components from a path without touching the file
+/// system, so one file is watched once however its parents spell it.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Reports through `output` when files in the directories of `paths` are
+/// created, modified or removed, until `output` is closed.
+///
+/// Changes arriving together are reported once.
+pub async fn watch(paths: Vec<PathBuf>, mut output: Sender<()>) {
+    let (sender, mut changes) = mpsc::unbounded();
+    let watched = paths.clone();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let relevant = event.is_ok_and(|event| {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| watched.contains(&normalize(path)))
+        });
+        if relevant {
+            let _ = sender.unbounded_send(());
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            tracing::warn!("Cannot watch the layout files: {}", e);
+            return;
+        }
+    };
+    for dir in directories(&paths) {
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            tracing::debug!("Cannot watch {}: {}", dir.display(), e);
+        }
+    }
+
+    while changes.next().await.is_some() {
+        while let Ok(Some(())) = changes.try_next() {}
+        // The subscription is gone
+        if output.send(()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Returns the directories holding `paths`, each once.
+fn directories(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut directories: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        if !directories.contains(&dir) {
+            directories.push(dir);
+        }
+    }
+    directories
+}
+
+/// Modification times of the files of a layout.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutWatcher {
+    /// Watched files and their last seen modification time (`None` while
+    /// missing or unreadable)
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl LayoutWatcher {
+    /// Starts watching a layout file and its parents.
+    #[must_use]
+    pub fn new(storage: &dyn Storage, layout_path: &Path) -> Self {
+        let files = inheritance_chain(storage, layout_path)
+            .into_iter()
+            .map(|path| {
+                let modified = storage.modified(&path).ok();
+                (path, modified)
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Returns `true` if no file is watched.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the watched files, child first.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Checks the watched files, after [`watch`] reported a change.
+    ///
+    /// Returns `true` if any of them was modified, created or removed since
+    /// the last check.
+    pub fn poll(&mut self, storage: &dyn Storage) -> bool {
+        let mut changed = false;
+        for (path, modified) in &mut self.files {
+            let now = storage.modified(path).ok();
+            if now != *modified {
+                tracing::debug!("Layout file changed: {}", path.display());
+                *modified = now;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::NativeStorage;
+    use std::time::Duration;
+
+    fn layout_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cosboard-watcher-{}-{}", name, std::process::id()))
+    }
+
+    /// Test: The chain follows `inherits` relative to each layout and stops
+    /// at cycles and unreadable files.
+    #[test]
+    fn test_inheritance_chain() {
+        let dir = layout_dir("chain");
+        let storage = NativeStorage;
+        storage
+            .write(
+                &dir.join("child.json"),
+                r#"{"inherits": "base/parent.json"}"#,
+            )
+            .unwrap();
+        storage
+            .write(
+                &dir.join("base/parent.json"),
+                r#"{"inherits": "../child.json"}"#,
+            )
+            .unwrap();
+
+        let chain = inheritance_chain(&storage, &dir.join("child.json"));
+        assert_eq!(
+            chain,
+            vec![dir.join("child.json"), dir.join("base/parent.json")]
+        );
+        assert_eq!(
+            normalize(Path::new("a/./b/../c.json")),
+            Path::new("a/c.json")
+        );
+
+        let missing = inheritance_chain(&storage, &dir.join("missing.json"));
+        assert_eq!(missing, vec![dir.join("missing.json")]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Test: Saving, removing and recreating a watched file are reported
+    /// once each.
+    #[test]
+    fn test_poll() {
+        let dir = layout_dir("poll");
+        let file = dir.join("layout.json");
+        let storage = NativeStorage;
+        storage.write(&file, "{}").unwrap();
+
+        let mut watcher = LayoutWatcher::new(&storage, &file);
+        assert_eq!(watcher.paths().collect::<Vec<_>>(), vec![file.as_path()]);
+        assert!(!watcher.poll(&storage));
+
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(watcher.poll(&storage));
+        assert!(!watcher.poll(&storage));
+
+        std::fs::remove_file(&file).unwrap();
+        assert!(watcher.poll(&storage));
+        storage.write(&file, "{}").unwrap();
+        assert!(watcher.poll(&storage));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Test: Every directory of a layout chain is watched once.
+    #[test]
+    fn test_directories() {
+        let paths = [
+            PathBuf::from("/layouts/child.json"),
+            PathBuf::from("/layouts/other.json"),
+            PathBuf::from("/base/parent.json"),
+            PathBuf::from("local.json"),
+        ];
+        assert_eq!(
+            directories(&paths),
+            vec![
+                PathBuf::from("/layouts"),
+                PathBuf::from("/base"),
+                PathBuf::from(".")
+            ]
+        );
+    }
+}
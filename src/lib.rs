@@ -7,25 +7,104 @@
 //!
 //! # Modules
 //!
+//! - `actions`: Named actions dispatched by command keys, D-Bus, the palette and menus
 //! - `applet`: System tray applet with integrated keyboard management
+//! - `appearance`: Keyboard light/dark palette by schedule or ambient light
+//! - `braille`: Built-in six-dot braille entry panel and braille tables
+//! - `calculator`: Built-in calculator panel and its expression evaluator
 //! - `app_settings`: Centralized application constants and configuration
+//! - `cli`: Command-line flags and environment overrides for the keyboard surface
 //! - `config`: User configuration with cosmic_config persistence
+//! - `controller`: UI-agnostic key emission into client applications
+//! - `date_picker`: Date and time picker widget typing dates in the locale's format
+//! - `dbus`: D-Bus control interface (`io.github.cosboard.Cosboard`)
+//! - `diagnostics`: Environment probes and the report printed by `cosboardctl doctor`
+//! - `double_space`: Ending a sentence by typing space twice quickly
+//! - `emoji`: Bundled emoji table and search
+//! - `feedback`: Key press sounds chosen per key by layout feedback profiles
+//! - `gestures`: Keyboard-wide touch gestures and their action bindings
+//! - `hooks`: User commands and D-Bus calls run on show, hide and layout changes
 //! - `i18n`: Localization support using fluent translations
+//! - `idle_inhibit`: Keeping the screen awake while the keyboard is typed on
+//! - `ime`: Input method engine interface and the engines chosen by layout language
 //! - `input`: Input handling for keycode parsing, modifier state, and virtual keyboard
+//! - `kiosk`: Kiosk mode policy that locks down quitting, moving and settings
 //! - `layer_shell`: Wayland layer-shell integration for overlay behavior
 //! - `layout`: JSON layout parser for keyboard layout definitions
+//! - `palette`: Command palette entries, built and filtered for the applet
+//! - `prediction`: Word prediction candidates shown above the keyboard
+//! - `prelude`: Semver-stable exports for tools building on the crate
+//! - `profiles`: Named configuration profiles (work, tablet, presentation, ...)
+//! - `recording`: Opt-in, anonymized session traces for reproducing bug reports
 //! - `renderer`: Keyboard layout renderer for visual UI generation
-//! - `state`: Window state persistence (position, size)
+//! - `scan`: QR code and barcode scanning that types the decoded text (`scan` feature)
+//! - `snippets`: User text shortcuts expanded on word boundaries
+//! - `stats`: Opt-in per-key press counts for the heat-map theme
+//! - `state`: Window state persistence (position, size), internal
+//! - `storage`: Native and Flatpak-sandboxed file access for user layouts
+//! - `symbols`: Bundled Unicode symbol table and the built-in symbols panel
+//! - `typing_test`: Typing test phrases and speed/accuracy scoring
+//!
+//! # API stability
+//!
+//! Tools building on Cosboard should import from [`prelude`], which only
+//! changes incompatibly in a new major version. Internal modules
+//! (`applet`, `controller`, `diagnostics`, `idle_inhibit`, `layer_shell`,
+//! `recording`, `state`, `storage`) are `pub(crate)`, and the binaries start
+//! through the entry points re-exported at the crate root; the remaining
+//! public modules may change between minor versions.
+//!
+//! ```compile_fail,E0603
+//! use cosboard::controller::KeyboardController;
+//! ```
 
+pub mod actions;
 pub mod app_settings;
-pub mod applet;
+pub mod appearance;
+pub(crate) mod applet;
+pub mod braille;
+pub mod calculator;
+pub mod cli;
 pub mod config;
+pub(crate) mod controller;
+pub mod date_picker;
+pub mod dbus;
+pub(crate) mod diagnostics;
+pub mod double_space;
+pub mod emoji;
+pub mod feedback;
+pub mod gestures;
+pub mod hooks;
 pub mod i18n;
+pub(crate) mod idle_inhibit;
+pub mod ime;
 pub mod input;
-pub mod layer_shell;
+pub mod kiosk;
+pub(crate) mod layer_shell;
 pub mod layout;
+pub mod palette;
+pub mod prediction;
+pub mod prelude;
+pub mod profiles;
+pub(crate) mod recording;
 pub mod renderer;
-pub mod state;
+#[cfg(feature = "scan")]
+pub mod scan;
+pub mod snippets;
+pub(crate) mod state;
+pub mod stats;
+pub(crate) mod storage;
+pub mod symbols;
+pub mod typing_test;
+
+// Entry points of the binaries, which cannot reach the internal modules
+pub use crate::applet::{run, run_standalone, run_with};
+pub use crate::diagnostics::{run as run_diagnostics, Status as DiagnosticStatus};
+
+// Random applet sessions for the soak test, which is not part of the API
+#[cfg(feature = "soak")]
+#[doc(hidden)]
+pub use crate::applet::soak;
 
 // Re-export the fl! macro for localization
 pub use crate::i18n::LANGUAGE_LOADER;
@@ -42,7 +121,6 @@ pub use crate::input::{keycodes, KeyEvent, KeyState, VirtualKeyboard};
 
 #[cfg(test)]
 mod integration_tests {
-    use crate::layer_shell::{Layer, LayerShellConfig};
     use crate::state::WindowState;
 
     /// Integration Test: State persistence across application restart
@@ -121,6 +199,8 @@ mod integration_tests {
             is_floating: true,
             margin_bottom: 50,
             margin_right: 100,
+            height_set_by_user: true,
+            profiles: Default::default(),
         };
 
         // Clone simulates save/restore cycle
@@ -142,28 +222,6 @@ mod integration_tests {
             "Margin right must be exact"
         );
     }
-
-    /// Integration Test: Layer-shell configuration workflow
-    ///
-    /// This test verifies the layer-shell configuration can be set up correctly
-    /// for overlay behavior.
-    #[test]
-    fn test_layer_shell_configuration_workflow() {
-        // Create default config (should be Overlay)
-        let config = LayerShellConfig::new();
-        assert_eq!(config.layer(), Layer::Overlay);
-
-        // Verify configuration chain works
-        let config = LayerShellConfig::new().with_layer(Layer::Overlay);
-        assert_eq!(config.layer(), Layer::Overlay);
-        assert!(!config.is_layer_surface()); // Not a true layer surface without compositor
-
-        // Verify all layer types work
-        for layer in [Layer::Background, Layer::Bottom, Layer::Top, Layer::Overlay] {
-            let config = LayerShellConfig::new().with_layer(layer);
-            assert_eq!(config.layer(), layer);
-        }
-    }
 }
 
 // ============================================================================
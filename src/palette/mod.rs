@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Keyboard-wide command palette.
+//!
+//! The palette is an overlay opened from a command key (`"code": "cosboard:palette"`).
+//! Typing into it (through the internal text field routing, see
+//! [`InputFocus`]) filters a list of actions: switch panel, switch layout,
+//! insert an emoji by name, or toggle a setting. Selecting an entry runs
+//! its action; Enter runs the first entry.
+//!
+//! This module only builds and filters entries. The applet supplies the
+//! panels and layouts to offer and dispatches the chosen [`AppAction`].
+//!
+//! [`InputFocus`]: crate::input::InputFocus
+
+use crate::actions::{ActionRegistry, AppAction};
+use crate::emoji;
+
+/// Internal text field ID of the palette query.
+pub const PALETTE_FIELD: &str = "command_palette";
+
+/// Maximum number of entries shown at once.
+pub const PALETTE_MAX_ENTRIES: usize = 6;
+
+/// Maximum number of emoji entries added for a query.
+const PALETTE_MAX_EMOJI: usize = 3;
+
+/// A palette entry: a label and the action it runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    /// Text shown for the entry.
+    pub label: String,
+    /// Action run when the entry is selected.
+    pub action: AppAction,
+}
+
+impl PaletteEntry {
+    fn new(label: impl Into<String>, action: AppAction) -> Self {
+        Self {
+            label: label.into(),
+            action,
+        }
+    }
+}
+
+/// What the palette can offer besides the fixed settings entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaletteContext {
+    /// Panel IDs of the current layout.
+    pub panels: Vec<String>,
+    /// Paths of layout files that can be switched to.
+    pub layouts: Vec<String>,
+}
+
+/// Returns the palette entries matching `query`, best first.
+///
+/// Commands are the panels and layouts from `context`, the registry's
+/// user-defined aliases, and its built-in actions that need no parameter
+/// (except opening the palette itself). Entries whose label has a word
+/// starting with the query come before entries that only contain it. Emoji
+/// whose name matches are appended after the commands. An empty query lists
+/// every command.
+#[must_use]
+pub fn entries(query: &str, context: &PaletteContext, registry: &ActionRegistry) -> Vec<PaletteEntry> {
+    let query = query.trim().to_lowercase();
+
+    let builtin = registry
+        .specs()
+        .filter(|spec| spec.parameter.is_none() && spec.name != "palette")
+        .filter_map(|spec| {
+            let action = registry.resolve(spec.name, None).ok()?;
+            Some(PaletteEntry::new((spec.title)(), action))
+        });
+
+    let mut commands: Vec<PaletteEntry> = context
+        .panels
+        .iter()
+        .map(|id| PaletteEntry::new(format!("Panel: {}", id), AppAction::SwitchPanel(id.clone())))
+        .chain(context.layouts.iter().map(|path| {
+            PaletteEntry::new(
+                format!("Layout: {}", layout_name(path)),
+                AppAction::SwitchLayout(path.clone()),
+            )
+        }))
+        .chain(registry.aliases().map(|(name, action)| PaletteEntry::new(name, action.clone())))
+        .chain(builtin)
+        .collect();
+
+    if query.is_empty() {
+        return commands;
+    }
+
+    // Rank: label word starts with the query (0), label contains it (1)
+    let rank = |entry: &PaletteEntry| {
+        let label = entry.label.to_lowercase();
+        if label
+            .split([' ', ':', '_', '-'])
+            .any(|word| word.starts_with(&query))
+        {
+            Some(0)
+        } else if label.contains(&query) {
+            Some(1)
+        } else {
+            None
+        }
+    };
+    commands.retain(|entry| rank(entry).is_some());
+    commands.sort_by_key(|entry| rank(entry));
+
+    commands.extend(emoji::search(&query, PALETTE_MAX_EMOJI).into_iter().map(|emoji| {
+        PaletteEntry::new(
+            format!("{} {}", emoji.glyph, emoji.name),
+            AppAction::InsertText(emoji.glyph.to_string()),
+        )
+    }));
+
+    commands
+}
+
+/// Returns a display name for a layout file path (its file stem).
+#[must_use]
+pub fn layout_name(path: &str) -> &str {
+    std::path::Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(path)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> PaletteContext {
+        PaletteContext {
+            panels: vec!["main".to_string(), "symbols".to_string()],
+            layouts: vec!["/usr/share/cosboard/layouts/dvorak.json".to_string()],
+        }
+    }
+
+    /// Test: An empty query lists every command and no emoji.
+    #[test]
+    fn test_empty_query_lists_commands() {
+        let all = entries("", &context(), &ActionRegistry::new());
+        assert!(!all.iter().any(|e| matches!(e.action, AppAction::InsertText(_))));
+        assert!(!all.iter().any(|e| e.action == AppAction::TogglePalette));
+        assert_eq!(all[2].label, "Layout: dvorak");
+        assert!(all.iter().any(|e| e.action == AppAction::ToggleLayer));
+    }
+
+    /// Test: Queries filter commands, prefer word prefixes, and add emoji.
+    #[test]
+    fn test_query_filters_and_ranks() {
+        let registry = ActionRegistry::new();
+        let results = entries("sym", &context(), &registry);
+        assert_eq!(results[0].action, AppAction::SwitchPanel("symbols".to_string()));
+
+        let results = entries("hide", &context(), &registry);
+        assert_eq!(results[0].action, AppAction::Hide);
+
+        let results = entries("fire", &context(), &registry);
+        assert!(results
+            .iter()
+            .any(|e| e.action == AppAction::InsertText("🔥".to_string())));
+
+        assert!(entries("zzzzqqq", &context(), &registry).is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Next-word prediction from word pairs.
+//!
+//! A [`BigramModel`] knows, for a word, which words most often follow it.
+//! After a word is finished or a candidate committed, the predictor offers
+//! those words before anything of the next word is typed; while it is being
+//! typed, the ones matching the typed prefix are offered first.
+//!
+//! The model starts from a bundled English table. A language can ship its
+//! own table as `<language>.bigrams` next to its dictionary (see
+//! [`super::dictionary`]): one pair per line, optionally followed by its
+//! count (`of the 2301`). Without counts, earlier lines rank higher. Lines
+//! starting with `#` are comments.
+
+use std::collections::HashMap;
+
+use super::data::BIGRAMS;
+
+/// Extension of next-word tables shipped next to dictionaries.
+pub const BIGRAM_EXTENSION: &str = "bigrams";
+
+/// Maximum number of word pairs read from one table.
+pub const MAX_BIGRAMS: usize = 100_000;
+
+/// Sorts next words most frequent first, keeping the highest frequency of
+/// duplicates.
+fn rank_next_words(next: &mut Vec<(String, u32)>) {
+    next.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    next.dedup_by(|word, kept| word.0 == kept.0);
+    next.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+}
+
+/// Words following other words, with frequencies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BigramModel {
+    /// Next words of each lowercase word, most frequent first
+    next: HashMap<String, Vec<(String, u32)>>,
+}
+
+impl BigramModel {
+    /// Creates a model that knows no pairs.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a model with the bundled English table.
+    #[must_use]
+    pub fn builtin() -> Self {
+        let mut model = Self::new();
+        for (previous, next) in BIGRAMS {
+            let count = next.len() as u32;
+            for (rank, word) in next.iter().enumerate() {
+                model.add_pair(previous, word, count - rank as u32);
+            }
+        }
+        model
+    }
+
+    /// Parses a next-word table (see the module docs), up to [`MAX_BIGRAMS`] pairs.
+    #[must_use]
+    pub fn from_text(text: &str) -> Self {
+        let lines: Vec<Vec<&str>> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|fields| fields.len() >= 2)
+            .take(MAX_BIGRAMS)
+            .collect();
+        let count = lines.len() as u32;
+
+        let mut model = Self::new();
+        for (rank, fields) in lines.iter().enumerate() {
+            let frequency = match fields.get(2).and_then(|count| count.parse::<u64>().ok()) {
+                Some(frequency) => frequency.min(u64::from(u32::MAX)) as u32,
+                None => count - rank as u32,
+            };
+            model
+                .next
+                .entry(fields[0].to_lowercase())
+                .or_default()
+                .push((fields[1].to_string(), frequency));
+        }
+        model.next.values_mut().for_each(rank_next_words);
+        model
+    }
+
+    /// Returns the number of known pairs.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.next.values().map(Vec::len).sum()
+    }
+
+    /// Returns `true` if the model knows no pairs.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.next.is_empty()
+    }
+
+    /// Adds a pair, or raises its frequency if it is already known.
+    pub fn add_pair(&mut self, previous: &str, word: &str, frequency: u32) {
+        let next = self.next.entry(previous.to_lowercase()).or_default();
+        next.push((word.to_string(), frequency));
+        rank_next_words(next);
+    }
+
+    /// Adds all pairs of another model, keeping the higher frequencies.
+    pub fn merge(&mut self, other: &BigramModel) {
+        for (previous, next) in &other.next {
+            let known = self.next.entry(previous.clone()).or_default();
+            known.extend(next.iter().cloned());
+            rank_next_words(known);
+        }
+    }
+
+    /// Returns up to `limit` words likely to follow `previous` that start
+    /// with `prefix` (case-insensitively), most likely first.
+    #[must_use]
+    pub fn next_words(&self, previous: &str, prefix: &str, limit: usize) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+        self.next
+            .get(&previous.to_lowercase())
+            .map(|next| {
+                next.iter()
+                    .filter(|(word, _)| word.to_lowercase().starts_with(&prefix))
+                    .take(limit)
+                    .map(|(word, _)| word.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Next words are ranked and filtered by the typed prefix.
+    #[test]
+    fn test_next_words() {
+        let model = BigramModel::builtin();
+        assert_eq!(model.next_words("thank", "", 3), vec!["you"]);
+        assert_eq!(model.next_words("Good", "", 2), vec!["morning", "night"]);
+        assert_eq!(model.next_words("good", "l", 3), vec!["luck"]);
+        assert!(model.next_words("zebra", "", 3).is_empty());
+    }
+
+    /// Test: Tables take counts, or rank by line order, and merge into a model.
+    #[test]
+    fn test_from_text() {
+        let table = BigramModel::from_text(
+            "# de\nguten Tag 90\nguten Morgen 120\nbis bald\nbis morgen\nkaputt\n",
+        );
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.next_words("Guten", "", 3), vec!["Morgen", "Tag"]);
+        assert_eq!(table.next_words("bis", "", 3), vec!["bald", "morgen"]);
+
+        let mut model = BigramModel::builtin();
+        model.merge(&table);
+        assert_eq!(model.next_words("guten", "t", 1), vec!["Tag"]);
+        assert_eq!(model.next_words("thank", "", 1), vec!["you"]);
+    }
+}
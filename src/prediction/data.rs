@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Bundled English word list and next-word table.
+//!
+//! Common English words, most frequent first. The position in the list is
+//! used as the word's frequency rank when no dictionary is installed.
+
+/// Bundled words, most frequent first.
+pub static WORDS: &[&str] = &[
+    "the", "be", "to", "of", "and", "a", "in", "that", "have", "I", "it", "for", "not", "on",
+    "with", "he", "as", "you", "do", "at", "this", "but", "his", "by", "from", "they", "we",
+    "say", "her", "she", "or", "an", "will", "my", "one", "all", "would", "there", "their",
+    "what", "so", "up", "out", "if", "about", "who", "get", "which", "go", "me", "when",
+    "make", "can", "like", "time", "no", "just", "him", "know", "take", "people", "into",
+    "year", "your", "good", "some", "could", "them", "see", "other", "than", "then", "now",
+    "look", "only", "come", "its", "over", "think", "also", "back", "after", "use", "two",
+    "how", "our", "work", "first", "well", "way", "even", "new", "want", "because", "any",
+    "these", "give", "day", "most", "us", "is", "are", "was", "were", "been", "has", "had",
+    "did", "does", "said", "I'm", "don't", "it's", "can't", "didn't", "doesn't", "won't",
+    "I'll", "I've", "that's", "there's", "what's", "let's", "thanks", "thank", "please",
+    "yes", "hello", "hi", "okay", "sorry", "here", "where", "why", "very", "much", "more",
+    "many", "should", "need", "right", "still", "never", "again", "always", "something",
+    "nothing", "everything", "anything", "someone", "everyone", "today", "tomorrow",
+    "yesterday", "tonight", "morning", "evening", "night", "week", "month", "later", "soon",
+    "before", "while", "though", "through", "around", "down", "off", "own", "same", "last",
+    "long", "great", "little", "old", "big", "high", "different", "small", "large", "next",
+    "early", "young", "important", "few", "public", "bad", "able", "sure", "free", "better",
+    "best", "home", "house", "world", "life", "hand", "part", "child", "eye", "woman", "man",
+    "place", "case", "point", "government", "company", "number", "group", "problem", "fact",
+    "question", "thing", "name", "friend", "family", "school", "water", "money", "story",
+    "game", "word", "book", "job", "business", "meeting", "call", "message", "email",
+    "phone", "text", "send", "sent", "read", "write", "find", "tell", "ask", "seem", "feel",
+    "try", "leave", "put", "mean", "keep", "let", "begin", "help", "talk", "turn", "start",
+    "show", "hear", "play", "run", "move", "live", "believe", "bring", "happen", "sit",
+    "stand", "lose", "pay", "meet", "include", "continue", "set", "learn", "change", "lead",
+    "understand", "watch", "follow", "stop", "create", "speak", "allow", "add", "spend",
+    "grow", "open", "walk", "win", "offer", "remember", "love", "consider", "appear", "buy",
+    "wait", "serve", "die", "build", "stay", "fall", "cut", "reach", "kill", "remain",
+    "going", "getting", "doing", "having", "looking", "working", "thinking", "coming",
+    "really", "maybe", "probably", "actually", "already", "almost", "enough", "together",
+    "quite", "rather", "usually", "definitely", "exactly", "awesome", "nice",
+    "happy", "birthday", "weekend", "lunch", "dinner", "breakfast", "coffee",
+    "office", "computer", "keyboard", "screen", "window", "file", "folder", "update",
+];
+
+/// Bundled next-word table: a word and the words that most often follow
+/// it, most frequent first.
+pub static BIGRAMS: &[(&str, &[&str])] = &[
+    ("a", &["lot", "few", "good", "little", "great", "new", "bit"]),
+    ("am", &["not", "going", "sure", "so"]),
+    ("are", &["you", "we", "they", "not", "going", "there"]),
+    ("at", &["the", "home", "least", "work", "all"]),
+    ("be", &["a", "the", "able", "there", "back"]),
+    ("can", &["you", "be", "I", "we", "do"]),
+    ("did", &["you", "not", "it", "I"]),
+    ("do", &["you", "not", "it", "that", "we"]),
+    ("don't", &["know", "think", "have", "want", "worry"]),
+    ("for", &["the", "a", "you", "your", "me", "this"]),
+    ("from", &["the", "a", "my", "home", "work"]),
+    ("going", &["to", "on", "out", "home"]),
+    ("good", &["morning", "night", "luck", "idea", "job", "evening"]),
+    ("have", &["a", "to", "been", "you", "the", "fun"]),
+    ("how", &["are", "to", "about", "much", "many", "is"]),
+    ("I", &["am", "have", "think", "will", "was", "can", "would", "don't"]),
+    ("I'll", &["be", "have", "send", "call", "let"]),
+    ("I'm", &["not", "going", "sure", "so", "on"]),
+    ("in", &["the", "a", "my", "your", "order", "time"]),
+    ("is", &["the", "a", "not", "it", "that", "there"]),
+    ("it", &["is", "was", "will", "would", "to"]),
+    ("it's", &["a", "not", "the", "been", "okay"]),
+    ("let", &["me", "us", "you"]),
+    ("let's", &["go", "do", "see", "meet", "talk"]),
+    ("of", &["the", "a", "course", "my", "your", "them"]),
+    ("on", &["the", "my", "a", "your", "it", "time"]),
+    ("see", &["you", "the", "it", "what"]),
+    ("so", &["much", "I", "that", "the", "far"]),
+    ("talk", &["to", "soon", "about", "later"]),
+    ("thank", &["you"]),
+    ("thanks", &["for", "again", "so"]),
+    ("that", &["is", "was", "the", "you", "I"]),
+    ("the", &["same", "first", "best", "other", "time", "way", "end"]),
+    ("this", &["is", "morning", "week", "weekend", "evening", "one"]),
+    ("to", &["be", "the", "do", "go", "get", "see", "make", "you"]),
+    ("want", &["to", "you", "it"]),
+    ("was", &["a", "the", "not", "going", "so"]),
+    ("we", &["are", "can", "have", "will", "should", "need"]),
+    ("what", &["is", "are", "do", "time", "about"]),
+    ("will", &["be", "you", "not", "have", "do"]),
+    ("with", &["the", "a", "you", "me", "my"]),
+    ("would", &["be", "you", "like", "have"]),
+    ("you", &["are", "can", "have", "know", "for", "want"]),
+];
@@ -0,0 +1,635 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! User-provided dictionaries.
+//!
+//! Besides the bundled word list, the prediction engine can use
+//! dictionaries installed per language. They are looked up in:
+//!
+//! 1. `$XDG_DATA_HOME/cosboard/dictionaries` (`~/.local/share/cosboard/dictionaries`)
+//! 2. `cosboard/dictionaries` under each system data directory
+//! 3. `hunspell` under each system data directory (`/usr/share/hunspell`),
+//!    where distributions install spell checking dictionaries
+//!
+//! Two formats are understood:
+//!
+//! - **Hunspell** (`en_US.dic` with its `en_US.aff` next to it): every stem
+//!   is expanded with its prefix and suffix rules. Hunspell dictionaries
+//!   have no frequencies, so their words rank below the bundled words.
+//! - **Frequency lists** (`en.txt`, `en.freq`): one word per line, optionally
+//!   followed by its count (`the 23135851162`). Without counts, the order of
+//!   the lines is the ranking, most frequent first. Lines starting with `#`
+//!   are comments.
+//!
+//! Dictionaries are identified by file name; a file in a higher directory
+//! shadows files with the same name further down. None is used until it is
+//! enabled in the settings menu, and enabled dictionaries are only read
+//! once prediction is used. All dictionaries together are capped at
+//! [`MAX_WORDS`] words, so a large Hunspell expansion cannot exhaust memory.
+//!
+//! A dictionary can come with a next-word table of the same name
+//! (`fr.bigrams` next to `fr.txt`, see [`super::bigram`]), which is loaded
+//! with it.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::storage::Storage;
+
+use super::bigram::{BigramModel, BIGRAM_EXTENSION};
+use super::PredictionEngine;
+
+/// Directory under the data directories holding Cosboard dictionaries.
+pub const DICTIONARY_DIR: &str = "cosboard/dictionaries";
+
+/// Directory under the system data directories holding Hunspell dictionaries.
+pub const HUNSPELL_DIR: &str = "hunspell";
+
+/// Maximum number of words loaded from all dictionaries together.
+pub const MAX_WORDS: usize = 250_000;
+
+/// Frequency given to words of dictionaries without frequencies.
+pub const UNRANKED_FREQUENCY: u32 = 1;
+
+/// File format of a dictionary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DictionaryFormat {
+    /// Hunspell `.dic` word list with an `.aff` affix file.
+    Hunspell,
+    /// Plain word list, optionally with counts.
+    FrequencyList,
+}
+
+impl DictionaryFormat {
+    /// Returns the format of a dictionary file, judged by its extension.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "dic" => Some(DictionaryFormat::Hunspell),
+            "txt" | "freq" => Some(DictionaryFormat::FrequencyList),
+            _ => None,
+        }
+    }
+
+    /// Returns a short, human-readable name for the format.
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            DictionaryFormat::Hunspell => "hunspell",
+            DictionaryFormat::FrequencyList => "word list",
+        }
+    }
+}
+
+/// An installed dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictionaryInfo {
+    /// File name, used to enable the dictionary in the configuration.
+    pub id: String,
+    /// Language tag taken from the file name (`en_US`).
+    pub language: String,
+    /// Path of the dictionary file.
+    pub path: PathBuf,
+    /// File format.
+    pub format: DictionaryFormat,
+}
+
+/// Returns the directories searched for dictionaries, highest precedence first.
+#[must_use]
+pub fn search_dirs(storage: &dyn Storage) -> Vec<PathBuf> {
+    let system = storage.system_data_dirs();
+    storage
+        .user_data_dir()
+        .map(|dir| dir.join(DICTIONARY_DIR))
+        .into_iter()
+        .chain(system.iter().map(|dir| dir.join(DICTIONARY_DIR)))
+        .chain(system.iter().map(|dir| dir.join(HUNSPELL_DIR)))
+        .collect()
+}
+
+/// Lists the installed dictionaries, sorted by file name.
+///
+/// Missing or unreadable directories are skipped.
+#[must_use]
+pub fn discover(storage: &dyn Storage) -> Vec<DictionaryInfo> {
+    let mut found: Vec<DictionaryInfo> = Vec::new();
+    let mut seen = HashSet::new();
+
+    for dir in search_dirs(storage) {
+        let Ok(paths) = storage.list_dir(&dir) else {
+            continue;
+        };
+        for path in paths {
+            let Some(format) = DictionaryFormat::from_path(&path) else {
+                continue;
+            };
+            let (Some(id), Some(language)) = (
+                path.file_name().and_then(|name| name.to_str()),
+                path.file_stem().and_then(|stem| stem.to_str()),
+            ) else {
+                continue;
+            };
+            if storage.is_file(&path) && seen.insert(id.to_string()) {
+                found.push(DictionaryInfo {
+                    id: id.to_string(),
+                    language: language.to_string(),
+                    path: path.clone(),
+                    format,
+                });
+            }
+        }
+    }
+
+    found.sort_by(|a, b| a.id.cmp(&b.id));
+    found
+}
+
+// ============================================================================
+// Frequency lists
+// ============================================================================
+
+/// Parses a frequency list into words and frequencies, up to `limit` words.
+///
+/// Lines without a count are ranked by their position.
+#[must_use]
+pub fn parse_frequency_list(text: &str, limit: usize) -> Vec<(String, u32)> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .take(limit)
+        .collect();
+    let count = lines.len() as u32;
+
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(rank, line)| {
+            let mut fields = line.split_whitespace();
+            let word = fields.next()?;
+            let frequency = match fields.next().and_then(|count| count.parse::<u64>().ok()) {
+                Some(frequency) => frequency.min(u64::from(u32::MAX)) as u32,
+                None => count - rank as u32,
+            };
+            Some((word.to_string(), frequency))
+        })
+        .collect()
+}
+
+// ============================================================================
+// Hunspell
+// ============================================================================
+
+/// How affix flags are written (the `FLAG` directive of the `.aff` file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagType {
+    /// One character per flag (the default; also `FLAG UTF-8`).
+    Char,
+    /// Two characters per flag (`FLAG long`).
+    Long,
+    /// Comma-separated numbers (`FLAG num`).
+    Numeric,
+}
+
+impl FlagType {
+    /// Splits a flag field into flags.
+    fn split(self, flags: &str) -> Vec<String> {
+        match self {
+            FlagType::Char => flags.chars().map(String::from).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|pair| pair.iter().collect()).collect()
+            }
+            FlagType::Numeric => flags
+                .split(',')
+                .map(str::trim)
+                .filter(|flag| !flag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// One character position of an affix condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ConditionPart {
+    /// `.`: any character
+    Any,
+    /// A literal character
+    Char(char),
+    /// `[abc]` or `[^abc]`
+    Set(Vec<char>, bool),
+}
+
+impl ConditionPart {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionPart::Any => true,
+            ConditionPart::Char(expected) => c == *expected,
+            ConditionPart::Set(chars, negated) => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+/// Parses an affix condition (`.`, `e`, `[^aeiou]y`, ...).
+fn parse_condition(condition: &str) -> Vec<ConditionPart> {
+    if condition == "." {
+        return Vec::new();
+    }
+
+    let mut parts = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(ConditionPart::Any),
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|c| *c != ']').collect();
+                let negated = set.first() == Some(&'^');
+                if negated {
+                    set.remove(0);
+                }
+                parts.push(ConditionPart::Set(set, negated));
+            }
+            c => parts.push(ConditionPart::Char(c)),
+        }
+    }
+    parts
+}
+
+/// A prefix or suffix rule.
+#[derive(Debug, Clone)]
+struct AffixRule {
+    /// Characters removed from the stem (empty for `0`)
+    strip: String,
+    /// Characters added in their place
+    add: String,
+    /// Characters the stem must start (prefix) or end (suffix) with
+    condition: Vec<ConditionPart>,
+}
+
+impl AffixRule {
+    /// Applies the rule to a stem, if its condition holds.
+    fn apply(&self, stem: &str, prefix: bool) -> Option<String> {
+        let chars: Vec<char> = stem.chars().collect();
+        if chars.len() < self.condition.len() {
+            return None;
+        }
+        let checked = if prefix {
+            &chars[..self.condition.len()]
+        } else {
+            &chars[chars.len() - self.condition.len()..]
+        };
+        let holds = checked
+            .iter()
+            .zip(&self.condition)
+            .all(|(c, part)| part.matches(*c));
+        if !holds {
+            return None;
+        }
+
+        if prefix {
+            let rest = stem.strip_prefix(self.strip.as_str())?;
+            Some(format!("{}{}", self.add, rest))
+        } else {
+            let rest = stem.strip_suffix(self.strip.as_str())?;
+            Some(format!("{}{}", rest, self.add))
+        }
+    }
+}
+
+/// All rules of one affix flag.
+#[derive(Debug, Clone)]
+struct AffixClass {
+    /// Whether prefixes and suffixes combine (`Y` in the header)
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+/// Prefix and suffix rules of an `.aff` file.
+#[derive(Debug, Clone)]
+struct AffixFile {
+    flag_type: FlagType,
+    prefixes: HashMap<String, AffixClass>,
+    suffixes: HashMap<String, AffixClass>,
+}
+
+impl AffixFile {
+    /// Parses the directives the expansion needs; others are ignored.
+    fn parse(text: &str) -> Self {
+        let mut affixes = AffixFile {
+            flag_type: FlagType::Char,
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+        };
+
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => affixes.flag_type = FlagType::Long,
+                ["FLAG", "num", ..] => affixes.flag_type = FlagType::Numeric,
+                [kind @ ("PFX" | "SFX"), flag, cross, count] if count.parse::<usize>().is_ok() => {
+                    let classes = if *kind == "PFX" {
+                        &mut affixes.prefixes
+                    } else {
+                        &mut affixes.suffixes
+                    };
+                    classes.insert(
+                        flag.to_string(),
+                        AffixClass {
+                            cross_product: *cross == "Y",
+                            rules: Vec::new(),
+                        },
+                    );
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    let classes = if *kind == "PFX" {
+                        &mut affixes.prefixes
+                    } else {
+                        &mut affixes.suffixes
+                    };
+                    let Some(class) = classes.get_mut(*flag) else {
+                        continue;
+                    };
+                    // Continuation flags after the affix are not expanded further
+                    let add = add.split('/').next().unwrap_or_default();
+                    class.rules.push(AffixRule {
+                        strip: if *strip == "0" { String::new() } else { strip.to_string() },
+                        add: if add == "0" { String::new() } else { add.to_string() },
+                        condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                    });
+                }
+                _ => {}
+            }
+        }
+        affixes
+    }
+
+    /// Returns a stem and all its affixed forms.
+    fn expand(&self, stem: &str, flags: &[String]) -> Vec<String> {
+        let mut words = vec![stem.to_string()];
+
+        let mut suffixed = Vec::new();
+        for class in flags.iter().filter_map(|flag| self.suffixes.get(flag)) {
+            for word in class.rules.iter().filter_map(|rule| rule.apply(stem, false)) {
+                suffixed.push((word, class.cross_product));
+            }
+        }
+
+        for class in flags.iter().filter_map(|flag| self.prefixes.get(flag)) {
+            for rule in &class.rules {
+                words.extend(rule.apply(stem, true));
+                if class.cross_product {
+                    words.extend(
+                        suffixed
+                            .iter()
+                            .filter(|(_, cross)| *cross)
+                            .filter_map(|(word, _)| rule.apply(word, true)),
+                    );
+                }
+            }
+        }
+        words.extend(suffixed.into_iter().map(|(word, _)| word));
+        words
+    }
+}
+
+/// Expands a Hunspell dictionary into words, up to `limit` words.
+///
+/// `aff` is the matching affix file; without it only the stems are used.
+/// Hunspell has no frequencies, so every word gets [`UNRANKED_FREQUENCY`].
+#[must_use]
+pub fn parse_hunspell(dic: &str, aff: Option<&str>, limit: usize) -> Vec<(String, u32)> {
+    let affixes = AffixFile::parse(aff.unwrap_or_default());
+    let mut lines = dic.lines().map(str::trim).filter(|line| !line.is_empty());
+
+    let mut words = Vec::new();
+    // The first line is the approximate number of stems
+    let first = lines.next().filter(|line| line.parse::<usize>().is_err());
+    for line in first.into_iter().chain(lines) {
+        // Morphological fields follow the word after whitespace
+        let entry = line.split_whitespace().next().unwrap_or_default();
+        let (stem, flags) = entry.split_once('/').unwrap_or((entry, ""));
+        if stem.is_empty() {
+            continue;
+        }
+
+        for word in affixes.expand(stem, &affixes.flag_type.split(flags)) {
+            if words.len() == limit {
+                return words;
+            }
+            words.push((word, UNRANKED_FREQUENCY));
+        }
+    }
+    words
+}
+
+// ============================================================================
+// Loading
+// ============================================================================
+
+/// Result of loading dictionaries into a prediction engine.
+#[derive(Debug, Clone, Default)]
+pub struct LoadedDictionaries {
+    /// Bundled words plus the words of all dictionaries that loaded.
+    pub engine: PredictionEngine,
+    /// Bundled next words plus the tables shipped with the dictionaries.
+    pub bigrams: BigramModel,
+    /// Number of dictionary words added (before duplicates were merged).
+    pub words: usize,
+    /// Whether [`MAX_WORDS`] cut a dictionary short.
+    pub truncated: bool,
+    /// Dictionaries that could not be read, with the reason.
+    pub errors: Vec<String>,
+}
+
+/// Reads a dictionary into words, up to `limit` words.
+///
+/// # Errors
+///
+/// Returns an error if the dictionary file cannot be read.
+pub fn read_dictionary(
+    storage: &dyn Storage,
+    dictionary: &DictionaryInfo,
+    limit: usize,
+) -> std::io::Result<Vec<(String, u32)>> {
+    let text = storage.read_to_string(&dictionary.path)?;
+    Ok(match dictionary.format {
+        DictionaryFormat::FrequencyList => parse_frequency_list(&text, limit),
+        DictionaryFormat::Hunspell => {
+            let aff = storage.read_to_string(&dictionary.path.with_extension("aff"));
+            if aff.is_err() {
+                tracing::warn!(
+                    "No affix file for {}, using its stems only",
+                    dictionary.path.display()
+                );
+            }
+            parse_hunspell(&text, aff.ok().as_deref(), limit)
+        }
+    })
+}
+
+/// Builds a prediction engine from the bundled words and `dictionaries`
+/// (blocking; run off the UI thread).
+///
+/// Dictionaries are read in order until [`MAX_WORDS`] is reached.
+#[must_use]
+pub fn load(storage: &dyn Storage, dictionaries: &[DictionaryInfo]) -> LoadedDictionaries {
+    let mut loaded = LoadedDictionaries {
+        engine: PredictionEngine::builtin(),
+        bigrams: BigramModel::builtin(),
+        ..LoadedDictionaries::default()
+    };
+
+    let mut words = Vec::new();
+    for dictionary in dictionaries {
+        let remaining = MAX_WORDS - words.len();
+        if remaining == 0 {
+            loaded.truncated = true;
+            break;
+        }
+        match read_dictionary(storage, dictionary, remaining) {
+            Ok(read) => {
+                tracing::info!(
+                    "Loaded {} words from dictionary {}",
+                    read.len(),
+                    dictionary.path.display()
+                );
+                loaded.truncated |= read.len() == remaining;
+                words.extend(read);
+            }
+            Err(e) => loaded
+                .errors
+                .push(format!("{}: {}", dictionary.path.display(), e)),
+        }
+
+        let table = dictionary.path.with_extension(BIGRAM_EXTENSION);
+        if storage.is_file(&table) {
+            match storage.read_to_string(&table) {
+                Ok(text) => loaded.bigrams.merge(&BigramModel::from_text(&text)),
+                Err(e) => loaded.errors.push(format!("{}: {}", table.display(), e)),
+            }
+        }
+    }
+
+    loaded.words = words.len();
+    loaded.engine.add_words(words);
+    loaded
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FlatpakStorage;
+
+    const AFF: &str = "\
+SET UTF-8
+
+PFX U Y 1
+PFX U   0     un         .
+
+SFX D Y 3
+SFX D   0     d          e
+SFX D   y     ied        [^aeiou]y
+SFX D   0     ed         [^ey]
+";
+
+    fn words(parsed: Vec<(String, u32)>) -> Vec<String> {
+        let mut words: Vec<String> = parsed.into_iter().map(|(word, _)| word).collect();
+        words.sort();
+        words
+    }
+
+    /// Test: Frequency lists take counts, or rank by line order without them.
+    #[test]
+    fn test_parse_frequency_list() {
+        let counted = parse_frequency_list("# comment\nthe 500\nof\t20\n\nzebra 99999999999\n", 10);
+        assert_eq!(
+            counted,
+            vec![
+                ("the".to_string(), 500),
+                ("of".to_string(), 20),
+                ("zebra".to_string(), u32::MAX),
+            ]
+        );
+
+        let ranked = parse_frequency_list("bonjour\nmerci\noui\n", 2);
+        assert_eq!(
+            ranked,
+            vec![("bonjour".to_string(), 2), ("merci".to_string(), 1)]
+        );
+    }
+
+    /// Test: Hunspell stems are expanded with their affix rules.
+    #[test]
+    fn test_parse_hunspell() {
+        let dic = "3\nbake/DU\ncry/D\nwalk/D\tpo:verb\n";
+        assert_eq!(
+            words(parse_hunspell(dic, Some(AFF), 100)),
+            vec!["bake", "baked", "cried", "cry", "unbake", "unbaked", "walk", "walked"]
+        );
+        assert_eq!(
+            words(parse_hunspell(dic, None, 100)),
+            vec!["bake", "cry", "walk"]
+        );
+        assert_eq!(parse_hunspell(dic, Some(AFF), 3).len(), 3);
+    }
+
+    /// Test: Long flags are split in pairs.
+    #[test]
+    fn test_parse_hunspell_long_flags() {
+        let aff = "FLAG long\nSFX Aa N 1\nSFX Aa 0 s .\n";
+        assert_eq!(
+            words(parse_hunspell("1\ncat/AaZz\n", Some(aff), 100)),
+            vec!["cat", "cats"]
+        );
+    }
+
+    /// Test: Dictionaries are found on the search path and loaded with the bundled words.
+    #[test]
+    fn test_discover_and_load() {
+        let root = tempfile::tempdir().unwrap();
+        let storage = FlatpakStorage {
+            data_home: Some(root.path().join("data")),
+            documents_dir: None,
+            app_prefix: root.path().join("app"),
+        };
+        let user = root.path().join("data").join(DICTIONARY_DIR);
+        let hunspell = root.path().join("app/share").join(HUNSPELL_DIR);
+        std::fs::create_dir_all(&user).unwrap();
+        std::fs::create_dir_all(&hunspell).unwrap();
+        std::fs::write(user.join("fr.txt"), "bonjour 50\nbonsoir 10\n").unwrap();
+        std::fs::write(user.join("fr.bigrams"), "bonjour madame\n").unwrap();
+        std::fs::write(user.join("notes.md"), "not a dictionary").unwrap();
+        std::fs::write(hunspell.join("fr.txt"), "shadowed\n").unwrap();
+        std::fs::write(hunspell.join("en_GB.dic"), "1\ncolour/D\n").unwrap();
+        std::fs::write(hunspell.join("en_GB.aff"), AFF).unwrap();
+
+        let found = discover(&storage);
+        let ids: Vec<&str> = found.iter().map(|info| info.id.as_str()).collect();
+        assert_eq!(ids, vec!["en_GB.dic", "fr.txt"]);
+        assert_eq!(found[0].language, "en_GB");
+        assert_eq!(found[0].format, DictionaryFormat::Hunspell);
+        assert_eq!(found[1].path, user.join("fr.txt"));
+
+        let missing = DictionaryInfo {
+            id: "de.txt".to_string(),
+            path: user.join("de.txt"),
+            ..found[1].clone()
+        };
+        let mut selected = found.clone();
+        selected.push(missing);
+        let loaded = load(&storage, &selected);
+
+        assert_eq!(loaded.words, 4);
+        assert!(!loaded.truncated);
+        assert_eq!(loaded.errors.len(), 1);
+        assert_eq!(loaded.engine.candidates("bon", 3), vec!["bonjour", "bonsoir"]);
+        assert_eq!(loaded.engine.candidates("colou", 3), vec!["colour", "coloured"]);
+        assert_eq!(loaded.engine.candidates("th", 1), vec!["the"]);
+        assert_eq!(loaded.bigrams.next_words("bonjour", "", 3), vec!["madame"]);
+        assert_eq!(loaded.bigrams.next_words("thank", "", 3), vec!["you"]);
+    }
+}
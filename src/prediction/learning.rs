@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Learning the words the user types.
+//!
+//! Every finished word is counted in [`LearnedWords`]. Once a word has been
+//! typed [`LEARN_THRESHOLD`] times it is offered as a prediction candidate,
+//! ranked above the bundled words. Counts are stored only on this machine,
+//! in `$XDG_DATA_HOME/cosboard/learned_words.txt` (a frequency list, see
+//! [`super::dictionary`]), and at most [`MAX_LEARNED_WORDS`] are kept: the
+//! least typed word is forgotten first.
+//!
+//! [`LearningPolicy`] in the user configuration controls what is learned:
+//! learning can be turned off, and words on its exclusion list are never
+//! counted. Nothing is learned in private mode (for passwords and other
+//! secrets), in kiosk mode, or from words that contain digits, which are
+//! more likely codes than vocabulary. The settings menu can clear
+//! everything learned so far.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::Storage;
+
+use super::dictionary::parse_frequency_list;
+use super::{PredictionEngine, WORDS};
+
+/// Times a word must be typed before it is offered as a candidate.
+pub const LEARN_THRESHOLD: u32 = 3;
+
+/// Maximum number of words counted.
+pub const MAX_LEARNED_WORDS: usize = 2_000;
+
+/// Shortest word that is learned, in characters.
+pub const MIN_LEARNED_LENGTH: usize = 3;
+
+/// File under the user data directory holding the learned words.
+pub const LEARNED_WORDS_FILE: &str = "cosboard/learned_words.txt";
+
+/// What the predictor may learn from typed words.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LearningPolicy {
+    /// Whether typed words are learned at all.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Words that are never learned (compared case-insensitively).
+    #[serde(default)]
+    pub excluded: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for LearningPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            excluded: Vec::new(),
+        }
+    }
+}
+
+impl LearningPolicy {
+    /// Returns `true` if `word` may be learned under this policy.
+    #[must_use]
+    pub fn may_learn(&self, word: &str) -> bool {
+        self.enabled
+            && word.chars().count() >= MIN_LEARNED_LENGTH
+            && !word.chars().any(|c| c.is_numeric())
+            && !self
+                .excluded
+                .iter()
+                .any(|excluded| excluded.to_lowercase() == word.to_lowercase())
+    }
+}
+
+/// Counts of the words the user typed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LearnedWords {
+    counts: HashMap<String, u32>,
+}
+
+impl LearnedWords {
+    /// Creates an empty set of learned words.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses learned words stored with [`Self::to_text`].
+    #[must_use]
+    pub fn from_text(text: &str) -> Self {
+        Self {
+            counts: parse_frequency_list(text, MAX_LEARNED_WORDS)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Formats the learned words as a frequency list, most typed first.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut words: Vec<(&String, &u32)> = self.counts.iter().collect();
+        words.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        words
+            .into_iter()
+            .map(|(word, count)| format!("{} {}\n", word, count))
+            .collect()
+    }
+
+    /// Returns the path of the learned words file.
+    #[must_use]
+    pub fn path(storage: &dyn Storage) -> Option<PathBuf> {
+        storage
+            .user_data_dir()
+            .map(|dir| dir.join(LEARNED_WORDS_FILE))
+    }
+
+    /// Reads the learned words (empty if none were stored yet).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load(storage: &dyn Storage) -> io::Result<Self> {
+        let Some(path) = Self::path(storage) else {
+            return Ok(Self::new());
+        };
+        match storage.read_to_string(&path) {
+            Ok(text) => Ok(Self::from_text(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the learned words.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no user data directory or the file
+    /// cannot be written.
+    pub fn save(&self, storage: &dyn Storage) -> io::Result<()> {
+        let path = Self::path(storage)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no user data directory"))?;
+        storage.write(&path, &self.to_text())
+    }
+
+    /// Returns the number of words counted.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if no words are counted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns how often a word was typed.
+    #[must_use]
+    pub fn count(&self, word: &str) -> u32 {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// Counts a typed word and returns its new count.
+    ///
+    /// If this exceeds [`MAX_LEARNED_WORDS`], the least typed other word is
+    /// forgotten.
+    pub fn record(&mut self, word: &str) -> u32 {
+        let count = self.counts.entry(word.to_string()).or_insert(0);
+        *count = count.saturating_add(1);
+        let count = *count;
+
+        if self.counts.len() > MAX_LEARNED_WORDS {
+            let least = self
+                .counts
+                .iter()
+                .filter(|(other, _)| other.as_str() != word)
+                .min_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .map(|(other, _)| other.clone());
+            if let Some(least) = least {
+                self.counts.remove(&least);
+            }
+        }
+        count
+    }
+
+    /// Forgets every learned word.
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+
+    /// Returns the words typed often enough to be offered, with their count.
+    pub fn learned(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts
+            .iter()
+            .filter(|(_, count)| **count >= LEARN_THRESHOLD)
+            .map(|(word, count)| (word.as_str(), *count))
+    }
+
+    /// Adds the learned words to `engine`.
+    pub fn apply(&self, engine: &mut PredictionEngine) {
+        engine.add_words(
+            self.learned()
+                .map(|(word, count)| (word.to_string(), learned_frequency(count))),
+        );
+    }
+}
+
+/// Returns the engine frequency of a word typed `count` times.
+///
+/// Learned words rank above all bundled words.
+#[must_use]
+pub fn learned_frequency(count: u32) -> u32 {
+    (WORDS.len() as u32).saturating_add(count)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Words are offered once typed often enough, and outrank bundled words.
+    #[test]
+    fn test_learn_threshold() {
+        let mut learned = LearnedWords::new();
+        for _ in 1..LEARN_THRESHOLD {
+            learned.record("thermoplastic");
+        }
+        assert_eq!(learned.learned().count(), 0);
+        assert_eq!(learned.record("thermoplastic"), LEARN_THRESHOLD);
+
+        let mut engine = PredictionEngine::builtin();
+        learned.apply(&mut engine);
+        assert_eq!(engine.candidates("the", 2), vec!["thermoplastic", "the"]);
+
+        learned.clear();
+        assert!(learned.is_empty());
+    }
+
+    /// Test: The policy skips short, numeric and excluded words, or everything when off.
+    #[test]
+    fn test_policy() {
+        let policy = LearningPolicy {
+            excluded: vec!["Hunter".to_string()],
+            ..LearningPolicy::default()
+        };
+        assert!(policy.may_learn("cosboard"));
+        assert!(!policy.may_learn("ok"));
+        assert!(!policy.may_learn("abc123"));
+        assert!(!policy.may_learn("hunter"));
+
+        let off = LearningPolicy {
+            enabled: false,
+            ..policy
+        };
+        assert!(!off.may_learn("cosboard"));
+    }
+
+    /// Test: The least typed word is forgotten past the cap, and counts round-trip.
+    #[test]
+    fn test_cap_and_text() {
+        let mut learned = LearnedWords::new();
+        for index in 0..MAX_LEARNED_WORDS {
+            learned.record(&format!("word{}", index));
+            learned.record(&format!("word{}", index));
+        }
+        learned.record("rare");
+        learned.record("newest");
+        assert_eq!(learned.len(), MAX_LEARNED_WORDS);
+        assert_eq!(learned.count("rare"), 0);
+        assert_eq!(learned.count("newest"), 1);
+
+        let text = learned.to_text();
+        assert_eq!(LearnedWords::from_text(&text), learned);
+    }
+}
@@ -0,0 +1,633 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Word prediction.
+//!
+//! While the user types, [`Predictor`] tracks the word being typed and
+//! offers the most likely completions from a [`PredictionEngine`]. The
+//! applet shows them as a row of candidate keys above the current panel,
+//! built at runtime with the renderer's composition API (see
+//! [`candidate_row`]). Pressing a candidate runs the `commit_prediction`
+//! action, which types the rest of the word and a space.
+//!
+//! # Ranking
+//!
+//! Candidates start with the typed prefix (case-insensitively) and are
+//! ordered by frequency, most frequent first; ties are alphabetical. The
+//! case of the prefix carries over: `"Th"` offers `"The"`, `"TH"` offers
+//! `"THE"`.
+//!
+//! Without a dictionary, the engine uses a bundled list of common English
+//! words ranked by frequency. Hunspell dictionaries and frequency lists
+//! installed per language can be enabled on top of it (see [`dictionary`]),
+//! and words the user types often are learned (see [`learning`]).
+//!
+//! # Next words
+//!
+//! The predictor remembers the last finished or committed word. Before the
+//! next word is typed, the candidates are the words that most often follow
+//! it (see [`bigram`]); while it is typed, those that match the prefix come
+//! before the completions. Punctuation and moving the cursor forget it.
+
+pub mod bigram;
+mod data;
+pub mod dictionary;
+pub mod learning;
+
+pub use bigram::BigramModel;
+pub use data::{BIGRAMS, WORDS};
+
+use crate::actions::PARAMETER_SEPARATOR;
+use crate::ime::{ImeEngine, ImeInput, ImeOutput};
+use crate::input::COMMAND_PREFIX;
+use crate::layout::{Cell, Key, KeyCode, Row, Sizing};
+
+/// Number of candidates shown in the prediction row.
+pub const CANDIDATE_COUNT: usize = 3;
+
+/// Prefix of the identifiers of the candidate keys (`prediction_0`, ...).
+pub const CANDIDATE_KEY_PREFIX: &str = "prediction_";
+
+/// Returns `true` if `c` can be part of a word.
+#[must_use]
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+/// Applies the case of a typed prefix to a candidate.
+fn match_case(prefix: &str, word: &str) -> String {
+    let mut letters = prefix.chars().filter(|c| c.is_alphabetic());
+    let Some(first) = letters.next() else {
+        return word.to_string();
+    };
+
+    let rest: Vec<char> = letters.collect();
+    if first.is_uppercase() && !rest.is_empty() && rest.iter().all(|c| c.is_uppercase()) {
+        word.to_uppercase()
+    } else if first.is_uppercase() {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        word.to_string()
+    }
+}
+
+// ============================================================================
+// Engine
+// ============================================================================
+
+/// A word known to the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    /// Lowercase form, used for lookup
+    key: String,
+    /// The word as it is typed
+    word: String,
+    /// How common the word is (higher is more common)
+    frequency: u32,
+}
+
+/// Prefix completion over a set of words with frequencies.
+#[derive(Debug, Clone, Default)]
+pub struct PredictionEngine {
+    /// Known words, sorted by their lowercase form
+    entries: Vec<Entry>,
+}
+
+impl PredictionEngine {
+    /// Creates an engine that knows no words.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an engine with the bundled English word list.
+    #[must_use]
+    pub fn builtin() -> Self {
+        let mut engine = Self::new();
+        let count = WORDS.len() as u32;
+        for (rank, word) in WORDS.iter().enumerate() {
+            engine.add_word(word, count - rank as u32);
+        }
+        engine
+    }
+
+    /// Returns the number of known words.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the engine knows no words.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a word, or raises its frequency if it is already known.
+    pub fn add_word(&mut self, word: &str, frequency: u32) {
+        let key = word.to_lowercase();
+        let position = self.entries.binary_search_by(|entry| {
+            entry
+                .key
+                .as_str()
+                .cmp(&key)
+                .then(entry.word.as_str().cmp(word))
+        });
+        match position {
+            Ok(index) => {
+                let entry = &mut self.entries[index];
+                entry.frequency = entry.frequency.max(frequency);
+            }
+            Err(index) => self.entries.insert(
+                index,
+                Entry {
+                    key,
+                    word: word.to_string(),
+                    frequency,
+                },
+            ),
+        }
+    }
+
+    /// Adds many words at once, keeping the highest frequency of duplicates.
+    ///
+    /// Faster than [`Self::add_word`] for whole dictionaries, which would
+    /// shift the entries on every insert.
+    pub fn add_words(&mut self, words: impl IntoIterator<Item = (String, u32)>) {
+        self.entries
+            .extend(words.into_iter().map(|(word, frequency)| Entry {
+                key: word.to_lowercase(),
+                word,
+                frequency,
+            }));
+        // Highest frequency first among equal words, so dedup keeps it
+        self.entries.sort_by(|a, b| {
+            a.key
+                .cmp(&b.key)
+                .then(a.word.cmp(&b.word))
+                .then(b.frequency.cmp(&a.frequency))
+        });
+        self.entries
+            .dedup_by(|entry, kept| entry.key == kept.key && entry.word == kept.word);
+        self.entries.shrink_to_fit();
+    }
+
+    /// Returns the frequency of a word, or `None` if it is unknown.
+    #[must_use]
+    pub fn frequency(&self, word: &str) -> Option<u32> {
+        self.entries
+            .iter()
+            .find(|entry| entry.word == word)
+            .map(|entry| entry.frequency)
+    }
+
+    /// Returns up to `limit` completions of `prefix`, most likely first.
+    ///
+    /// An empty prefix has no completions.
+    #[must_use]
+    pub fn candidates(&self, prefix: &str, limit: usize) -> Vec<String> {
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let key = prefix.to_lowercase();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let mut matches: Vec<&Entry> = self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key.starts_with(&key))
+            .collect();
+        matches.sort_by(|a, b| b.frequency.cmp(&a.frequency).then(a.key.cmp(&b.key)));
+
+        let mut candidates: Vec<String> = Vec::new();
+        for entry in matches {
+            let candidate = match_case(prefix, &entry.word);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+            if candidates.len() == limit {
+                break;
+            }
+        }
+        candidates
+    }
+}
+
+// ============================================================================
+// Typing state
+// ============================================================================
+
+/// Keystrokes that replace the typed prefix with a chosen candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Number of characters to delete with BackSpace first.
+    pub backspaces: usize,
+    /// Text to type afterwards (ends with a space).
+    pub text: String,
+}
+
+impl Completion {
+    /// Returns the keystrokes that turn `typed` into `word` followed by a space.
+    ///
+    /// If `word` extends `typed`, only the missing characters are typed;
+    /// otherwise (e.g. a different case) `typed` is deleted first.
+    #[must_use]
+    pub fn new(typed: &str, word: &str) -> Self {
+        match word.strip_prefix(typed) {
+            Some(rest) => Self {
+                backspaces: 0,
+                text: format!("{} ", rest),
+            },
+            None => Self {
+                backspaces: typed.chars().count(),
+                text: format!("{} ", word),
+            },
+        }
+    }
+}
+
+/// Tracks the word being typed and its prediction candidates.
+#[derive(Debug, Clone, Default)]
+pub struct Predictor {
+    /// Word source
+    engine: PredictionEngine,
+    /// Next-word source
+    bigrams: BigramModel,
+    /// Last finished word, while the next word may follow it
+    previous: Option<String>,
+    /// Characters of the current word typed so far
+    word: String,
+    /// Candidates for the current word, most likely first
+    candidates: Vec<String>,
+}
+
+impl Predictor {
+    /// Creates a predictor using `engine`.
+    #[must_use]
+    pub fn new(engine: PredictionEngine) -> Self {
+        Self {
+            engine,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the word source.
+    #[must_use]
+    pub fn engine(&self) -> &PredictionEngine {
+        &self.engine
+    }
+
+    /// Sets the next-word source.
+    #[must_use]
+    pub fn with_bigrams(mut self, bigrams: BigramModel) -> Self {
+        self.bigrams = bigrams;
+        self
+    }
+
+    /// Replaces the next-word source, e.g. once dictionaries are loaded.
+    pub fn set_bigrams(&mut self, bigrams: BigramModel) {
+        self.bigrams = bigrams;
+        self.refresh();
+    }
+
+    /// Returns the last finished word, if the next word may follow it.
+    #[must_use]
+    pub fn previous(&self) -> Option<&str> {
+        self.previous.as_deref()
+    }
+
+    /// Replaces the word source, e.g. once dictionaries are loaded.
+    pub fn set_engine(&mut self, engine: PredictionEngine) {
+        self.engine = engine;
+        self.refresh();
+    }
+
+    /// Returns the characters of the current word typed so far.
+    #[must_use]
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+
+    /// Returns the current candidates, most likely first.
+    #[must_use]
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    /// Returns the word source for changes, such as learned words.
+    pub fn engine_mut(&mut self) -> &mut PredictionEngine {
+        &mut self.engine
+    }
+
+    /// Records a typed character. Characters that cannot be part of a word
+    /// end the current word.
+    ///
+    /// Returns the word the character ended, if any.
+    pub fn type_char(&mut self, c: char) -> Option<String> {
+        let finished = if is_word_char(c) {
+            self.word.push(c);
+            None
+        } else {
+            Some(std::mem::take(&mut self.word)).filter(|word| !word.is_empty())
+        };
+
+        if c.is_whitespace() {
+            // A second space keeps the context of the first
+            if let Some(ref word) = finished {
+                self.previous = Some(word.clone());
+            }
+        } else if !is_word_char(c) {
+            // Punctuation ends the sentence or clause
+            self.previous = None;
+        }
+        self.refresh();
+        finished
+    }
+
+    /// Records a BackSpace.
+    pub fn backspace(&mut self) {
+        if self.word.pop().is_none() {
+            // Deleting back into the previous word loses track of it
+            self.previous = None;
+        }
+        self.refresh();
+    }
+
+    /// Forgets the current and previous word, e.g. after the cursor moved.
+    pub fn reset(&mut self) {
+        self.word.clear();
+        self.previous = None;
+        self.candidates.clear();
+    }
+
+    /// Commits the candidate at `index`.
+    ///
+    /// Returns the keystrokes to type it, or `None` if there is no such
+    /// candidate. The current word ends, and the candidates become the
+    /// words likely to follow it.
+    pub fn commit(&mut self, index: usize) -> Option<Completion> {
+        let candidate = self.candidates.get(index)?.clone();
+        let completion = Completion::new(&self.word, &candidate);
+        self.word.clear();
+        self.previous = Some(candidate);
+        self.refresh();
+        Some(completion)
+    }
+
+    /// Recomputes the candidates for the current word.
+    ///
+    /// Next words matching the typed prefix come first, then completions.
+    fn refresh(&mut self) {
+        let mut candidates = match self.previous {
+            Some(ref previous) => self
+                .bigrams
+                .next_words(previous, &self.word, CANDIDATE_COUNT)
+                .into_iter()
+                .map(|word| match_case(&self.word, &word))
+                .collect(),
+            None => Vec::new(),
+        };
+        // Ask for enough completions to fill up after dropping duplicates
+        let limit = CANDIDATE_COUNT + candidates.len();
+        for word in self.engine.candidates(&self.word, limit) {
+            if candidates.len() == CANDIDATE_COUNT {
+                break;
+            }
+            if !candidates.contains(&word) {
+                candidates.push(word);
+            }
+        }
+        self.candidates = candidates;
+    }
+}
+
+/// Prediction as an input method: keys are watched after they are typed,
+/// never used, and text is only committed when a candidate is chosen.
+impl ImeEngine for Predictor {
+    fn name(&self) -> &'static str {
+        "prediction"
+    }
+
+    fn feed(&mut self, input: ImeInput) -> ImeOutput {
+        match input {
+            ImeInput::Char(c) => ImeOutput {
+                finished: self.type_char(c),
+                ..ImeOutput::pass()
+            },
+            ImeInput::Backspace => {
+                self.backspace();
+                ImeOutput::pass()
+            }
+            ImeInput::Boundary => {
+                Predictor::reset(self);
+                ImeOutput::pass()
+            }
+            ImeInput::Gesture(_) => ImeOutput::pass(),
+        }
+    }
+
+    fn candidates(&self) -> &[String] {
+        Predictor::candidates(self)
+    }
+
+    fn select(&mut self, index: usize) -> ImeOutput {
+        ImeOutput {
+            commit: self.commit(index),
+            ..ImeOutput::pass()
+        }
+    }
+
+    fn reset(&mut self) {
+        Predictor::reset(self);
+    }
+}
+
+// ============================================================================
+// Candidate row
+// ============================================================================
+
+/// Returns the identifier of the candidate key at `index`.
+#[must_use]
+pub fn candidate_key_id(index: usize) -> String {
+    format!("{}{}", CANDIDATE_KEY_PREFIX, index)
+}
+
+/// Returns `true` if `row` is a candidate row built by [`candidate_row`] or
+/// by an input method's [`crate::ime::candidate_row`].
+#[must_use]
+pub fn is_candidate_row(row: &Row) -> bool {
+    matches!(
+        row.cells.first(),
+        Some(Cell::Key(key)) if key
+            .identifier
+            .as_deref()
+            .is_some_and(|id| id.starts_with(CANDIDATE_KEY_PREFIX))
+    )
+}
+
+/// Builds the row of candidate keys shown above a panel.
+///
+/// The row always has [`CANDIDATE_COUNT`] keys so it keeps its layout while
+/// typing; keys without a candidate are blank. `row_width` is the width of
+/// the panel's widest row in key units, shared evenly by the candidates.
+#[must_use]
+pub fn candidate_row(candidates: &[String], row_width: f32) -> Row {
+    let width = (row_width / CANDIDATE_COUNT as f32).max(1.0);
+    let cells = (0..CANDIDATE_COUNT)
+        .map(|index| {
+            Cell::Key(Key {
+                label: candidates.get(index).cloned().unwrap_or_default(),
+                code: KeyCode::Keysym(format!(
+                    "{}commit_prediction{}{}",
+                    COMMAND_PREFIX, PARAMETER_SEPARATOR, index
+                )),
+                identifier: Some(candidate_key_id(index)),
+                width: Sizing::Relative(width),
+                ..Key::default()
+            })
+        })
+        .collect();
+    Row { cells }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Candidates are ranked by frequency and take the prefix's case.
+    #[test]
+    fn test_candidates() {
+        let engine = PredictionEngine::builtin();
+        assert_eq!(engine.candidates("th", 3), vec!["the", "that", "this"]);
+        assert_eq!(engine.candidates("Th", 1), vec!["The"]);
+        assert_eq!(engine.candidates("TH", 1), vec!["THE"]);
+        assert_eq!(engine.candidates("i'", 2), vec!["I'm", "I'll"]);
+        assert!(engine.candidates("", 3).is_empty());
+        assert!(engine.candidates("zzq", 3).is_empty());
+    }
+
+    /// Test: Typing, BackSpace and word boundaries drive the candidates.
+    #[test]
+    fn test_predictor_typing() {
+        let mut predictor = Predictor::new(PredictionEngine::builtin());
+        for c in "hel".chars() {
+            predictor.type_char(c);
+        }
+        assert_eq!(predictor.word(), "hel");
+        assert_eq!(predictor.candidates(), ["hello", "help"]);
+
+        predictor.backspace();
+        assert_eq!(predictor.word(), "he");
+        assert_eq!(predictor.type_char(' '), Some("he".to_string()));
+        assert_eq!(predictor.word(), "");
+        assert_eq!(predictor.type_char('.'), None);
+        assert!(predictor.candidates().is_empty());
+        assert_eq!(predictor.commit(0), None);
+    }
+
+    /// Test: Committing types the rest of the word, replacing it if needed.
+    #[test]
+    fn test_commit() {
+        let mut predictor = Predictor::new(PredictionEngine::builtin());
+        for c in "hel".chars() {
+            predictor.type_char(c);
+        }
+        assert_eq!(
+            predictor.commit(0),
+            Some(Completion {
+                backspaces: 0,
+                text: "lo ".to_string(),
+            })
+        );
+        assert_eq!(predictor.word(), "");
+
+        assert_eq!(
+            Completion::new("im", "I'm"),
+            Completion {
+                backspaces: 2,
+                text: "I'm ".to_string(),
+            }
+        );
+    }
+
+    /// Test: As an input method, prediction passes keys and commits choices.
+    #[test]
+    fn test_ime_engine() {
+        let mut predictor = Predictor::new(PredictionEngine::builtin());
+        let engine: &mut dyn ImeEngine = &mut predictor;
+        for c in "hel".chars() {
+            assert_eq!(engine.feed(ImeInput::Char(c)), ImeOutput::pass());
+        }
+        assert_eq!(engine.candidates()[0], "hello");
+        let output = engine.select(0);
+        assert!(!output.consumed);
+        assert_eq!(output.commit.unwrap().text, "lo ");
+
+        engine.feed(ImeInput::Char('w'));
+        assert_eq!(
+            engine.feed(ImeInput::Char(' ')).finished.as_deref(),
+            Some("w")
+        );
+        engine.feed(ImeInput::Char('t'));
+        engine.feed(ImeInput::Boundary);
+        assert!(engine.candidates().is_empty());
+    }
+
+    /// Test: Finished and committed words offer their likely next words.
+    #[test]
+    fn test_next_words() {
+        let mut predictor =
+            Predictor::new(PredictionEngine::builtin()).with_bigrams(BigramModel::builtin());
+        for c in "good ".chars() {
+            predictor.type_char(c);
+        }
+        assert_eq!(predictor.previous(), Some("good"));
+        assert_eq!(predictor.candidates(), ["morning", "night", "luck"]);
+
+        // Next words matching the prefix come before other completions
+        predictor.type_char('n');
+        assert_eq!(predictor.candidates(), ["night", "not", "no"]);
+        predictor.backspace();
+        predictor.backspace();
+        assert_eq!(predictor.previous(), None);
+        assert!(predictor.candidates().is_empty());
+
+        for c in "thank".chars() {
+            predictor.type_char(c);
+        }
+        assert_eq!(predictor.candidates(), ["thanks", "thank"]);
+        let completion = predictor.commit(1).unwrap();
+        assert_eq!(completion.text, " ");
+        assert_eq!(predictor.candidates(), ["you"]);
+        predictor.type_char('.');
+        assert!(predictor.candidates().is_empty());
+    }
+
+    /// Test: The candidate row has a fixed number of command keys.
+    #[test]
+    fn test_candidate_row() {
+        let row = candidate_row(&["hello".to_string()], 10.0);
+        assert_eq!(row.cells.len(), CANDIDATE_COUNT);
+        let Cell::Key(first) = &row.cells[0] else {
+            panic!("expected a key");
+        };
+        assert_eq!(first.label, "hello");
+        assert_eq!(first.identifier.as_deref(), Some("prediction_0"));
+        assert_eq!(
+            first.code,
+            KeyCode::Keysym("cosboard:commit_prediction:0".to_string())
+        );
+        let Cell::Key(blank) = &row.cells[2] else {
+            panic!("expected a key");
+        };
+        assert!(blank.label.is_empty());
+        assert!(is_candidate_row(&row));
+        assert!(!is_candidate_row(&Row::default()));
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Stable public API for tools building on Cosboard.
+//!
+//! Everything exported here follows semantic versioning: it is only removed
+//! or changed incompatibly in a new major version. The modules behind it
+//! (`cosboard::layout`, `cosboard::renderer`, ...) stay public for now, but
+//! their other items may change with internal refactors.
+//!
+//! ```rust,ignore
+//! use cosboard::prelude::*;
+//!
+//! let result = parse_layout_file("layouts/qwerty.json")?;
+//! let renderer = KeyboardRenderer::new(result.layout);
+//! ```
+//!
+//! Internal modules such as the applet's window state are not reachable:
+//!
+//! ```compile_fail
+//! use cosboard::state::WindowState;
+//! ```
+//!
+//! The exported set is checked by `tests/public_api.rs`.
+
+// Layout definitions and parsing
+pub use crate::layout::{
+    parse_layout_file, parse_layout_from_string, Cell, Key, KeyCode, Layout, Modifier, Panel,
+    ParseError, ParseResult, Row,
+};
+
+// Layout discovery and loading
+pub use crate::layout::{LayoutCache, LayoutEntry, LayoutSource, ProviderChain};
+
+// Rendering
+pub use crate::renderer::{KeyboardRenderer, RendererMessage};
+
+// Key emission
+pub use crate::input::{KeyEvent, KeySink, KeyState, ModifierState, VirtualKeyboard};
+
+// Actions
+pub use crate::actions::{ActionRegistry, AppAction};
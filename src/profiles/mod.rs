@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Named configuration profiles.
+//!
+//! The same keyboard is used differently at a desk, on a convertible folded
+//! into a tablet, or on a projector. A profile bundles the settings that
+//! change between such uses, so they can be switched together:
+//!
+//! - the layout and the key height ([`ProfileSettings`]);
+//! - docking, size and position ([`ProfileWindow`]);
+//! - the layer and whether the keyboard may take focus;
+//! - the rules hiding and showing the keyboard (post-actions, showing with
+//!   text fields) and the gesture bindings.
+//!
+//! The settings of the active profile are the configuration and window
+//! state as usual. Switching profiles stores them under the profile being
+//! left and restores those stored under the profile switched to, so each
+//! profile keeps its own state across restarts. A profile switched to for
+//! the first time starts from the current settings.
+//!
+//! Profiles are switched from the popup menu, with the `set_profile` action
+//! (`set_profile:tablet`) or over D-Bus (`SetProfile("tablet")`).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::PostActionPolicy;
+use crate::config::Config;
+use crate::gestures::GestureBindings;
+use crate::layer_shell::{Interactivity, Layer};
+
+/// Profile in use until another one is picked.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profiles offered by the popup menu even before they were used.
+pub const SUGGESTED_PROFILES: [&str; 4] = [DEFAULT_PROFILE, "work", "tablet", "presentation"];
+
+/// Configuration entries that belong to a profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileSettings {
+    /// Layout picked in the profile; unset for the default layout.
+    #[serde(default)]
+    pub layout: Option<String>,
+    /// Height of a one-unit key in pixels; unset for the default.
+    #[serde(default)]
+    pub key_height: Option<u32>,
+    /// Layer-shell layer of the keyboard surface.
+    #[serde(default)]
+    pub layer: Layer,
+    /// Whether the keyboard surface may take keyboard focus.
+    #[serde(default)]
+    pub keyboard_interactivity: Interactivity,
+    /// Whether Esc or Enter hide the keyboard after they are typed.
+    #[serde(default)]
+    pub post_actions: PostActionPolicy,
+    /// Whether the keyboard follows text field focus.
+    #[serde(default)]
+    pub auto_show: bool,
+    /// Actions run by keyboard-wide gestures.
+    #[serde(default)]
+    pub gesture_bindings: GestureBindings,
+}
+
+impl ProfileSettings {
+    /// Takes the profile's entries from the configuration.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            layout: config.layout.clone(),
+            key_height: config.key_height,
+            layer: config.layer,
+            keyboard_interactivity: config.keyboard_interactivity,
+            post_actions: config.post_actions,
+            auto_show: config.auto_show,
+            gesture_bindings: config.gesture_bindings.clone(),
+        }
+    }
+
+    /// Writes the profile's entries into the configuration.
+    pub fn apply_to(&self, config: &mut Config) {
+        config.layout = self.layout.clone();
+        config.key_height = self.key_height;
+        config.layer = self.layer;
+        config.keyboard_interactivity = self.keyboard_interactivity;
+        config.post_actions = self.post_actions;
+        config.auto_show = self.auto_show;
+        config.gesture_bindings = self.gesture_bindings.clone();
+    }
+}
+
+/// Docking, size and position of the keyboard in a profile, stored with
+/// the window state.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProfileWindow {
+    /// Keyboard width (floating mode).
+    pub width: f32,
+    /// Keyboard height.
+    pub height: f32,
+    /// Whether the keyboard floats.
+    pub is_floating: bool,
+    /// Margin from the bottom edge (floating mode).
+    pub margin_bottom: i32,
+    /// Margin from the right edge (floating mode).
+    pub margin_right: i32,
+    /// Whether the height was chosen by resizing the keyboard.
+    #[serde(default)]
+    pub height_set_by_user: bool,
+}
+
+/// Settings stored for the profiles not in use, by profile name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProfileStore<T>(pub BTreeMap<String, T>);
+
+impl<T> Default for ProfileStore<T> {
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<T: Clone> ProfileStore<T> {
+    /// Stores the current settings under the profile being left, and
+    /// returns those stored under the profile switched to, if it was used
+    /// before.
+    pub fn switch(&mut self, leaving: &str, current: T, entering: &str) -> Option<T> {
+        self.0.insert(leaving.to_string(), current);
+        self.0.get(entering).cloned()
+    }
+
+    /// Returns the names of the stored profiles.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+/// Returns the name of the active profile from its configuration entry,
+/// which is empty until a profile is picked.
+#[must_use]
+pub fn active_profile(configured: &str) -> &str {
+    match configured.trim() {
+        "" => DEFAULT_PROFILE,
+        name => name,
+    }
+}
+
+/// Returns the profiles offered by the popup menu: the suggested ones, then
+/// the others used before, then the active one if it is neither.
+#[must_use]
+pub fn profile_names<'a>(
+    stored: &'a ProfileStore<ProfileSettings>,
+    active: &'a str,
+) -> Vec<&'a str> {
+    let mut names: Vec<&str> = SUGGESTED_PROFILES.to_vec();
+    for name in stored.names().chain(std::iter::once(active)) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Switching stores the settings left behind and restores the
+    /// ones of a profile used before; a new profile restores nothing.
+    #[test]
+    fn test_profile_store_switch() {
+        let mut store = ProfileStore::default();
+        assert_eq!(store.switch("default", 1, "tablet"), None);
+        assert_eq!(store.switch("tablet", 2, "default"), Some(1));
+        assert_eq!(store.switch("default", 3, "tablet"), Some(2));
+        assert_eq!(store.0.get("default"), Some(&3));
+    }
+
+    /// Test: The menu lists the suggested profiles first, then the others,
+    /// each once.
+    #[test]
+    fn test_profile_names() {
+        let store = ProfileStore(BTreeMap::from([
+            ("kiosk".to_string(), ProfileSettings::default()),
+            ("tablet".to_string(), ProfileSettings::default()),
+        ]));
+
+        assert_eq!(active_profile(" "), DEFAULT_PROFILE);
+        let names = profile_names(&store, active_profile("couch"));
+        assert_eq!(names[..4], SUGGESTED_PROFILES);
+        assert_eq!(names[4..], ["kiosk", "couch"]);
+        assert_eq!(profile_names(&store, "work").len(), 5);
+    }
+}
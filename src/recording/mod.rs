@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Opt-in recording of interaction sessions for bug reports.
+//!
+//! Intermittent state bugs (stuck modifiers, keys left pressed, panels that
+//! do not switch back) are hard to reproduce from a description. When the
+//! [`RECORD_ENV`] environment variable names a file, the applet writes every
+//! interaction message it handles to that file, and the resulting trace can
+//! be attached to a bug report and replayed through the applet's `update()`
+//! in a test.
+//!
+//! Traces are anonymized: they contain key identifiers, panel IDs, gestures,
+//! action names and timings, but never typed or inserted text. Each line is
+//! one JSON [`RecordedEntry`]:
+//!
+//! ```text
+//! {"at_ms":0,"event":"show"}
+//! {"at_ms":812,"event":"key_pressed","key":"shift"}
+//! {"at_ms":903,"event":"key_released","key":"shift"}
+//! ```
+//!
+//! Lines are flushed as they are written, so the trace survives a crash.
+
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gestures::Gesture;
+
+/// Environment variable naming the file a session is recorded to.
+pub const RECORD_ENV: &str = "COSBOARD_RECORD";
+
+/// An interaction, as recorded in a trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// The keyboard was shown.
+    Show,
+    /// The keyboard was hidden.
+    Hide,
+    /// Keyboard visibility was toggled.
+    Toggle,
+    /// A key was pressed.
+    KeyPressed {
+        /// Key identifier
+        key: String,
+    },
+    /// A key was released.
+    KeyReleased {
+        /// Key identifier
+        key: String,
+    },
+    /// A slide entered a key on a piano mode panel.
+    KeyEntered {
+        /// Key identifier
+        key: String,
+    },
+    /// The pointer left a key.
+    KeyExited {
+        /// Key identifier
+        key: String,
+    },
+    /// The pointer was lifted while keys were pressed.
+    PointerLifted,
+    /// A piano mode slide ended.
+    SlideEnd,
+    /// A panel switch was requested.
+    SwitchPanel {
+        /// Panel ID
+        panel: String,
+    },
+    /// Panel animation frame.
+    AnimationTick,
+    /// Long press timer tick.
+    LongPressTimerTick,
+    /// Chord timer tick.
+    ChordTimerTick,
+    /// A keyboard-wide gesture was recognized.
+    Gesture {
+        /// The gesture
+        gesture: Gesture,
+    },
+    /// A named action was run (see [`AppAction`](crate::actions::AppAction)).
+    Action {
+        /// Action name with its parameter, as parsed by the action registry
+        action: String,
+    },
+}
+
+/// A recorded event with its time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    /// Milliseconds since recording started.
+    pub at_ms: u64,
+    /// What happened.
+    #[serde(flatten)]
+    pub event: RecordedEvent,
+}
+
+/// Writes recorded events to a trace file.
+pub struct SessionRecorder {
+    /// Trace file, flushed after every line
+    writer: LineWriter<File>,
+    /// Path of the trace file
+    path: PathBuf,
+    /// When recording started
+    started: Instant,
+}
+
+impl SessionRecorder {
+    /// Creates (or truncates) the trace file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path)?;
+        Ok(Self {
+            writer: LineWriter::new(file),
+            path,
+            started: Instant::now(),
+        })
+    }
+
+    /// Starts recording if [`RECORD_ENV`] names a trace file.
+    ///
+    /// Returns `None` if recording is not requested or the file cannot be
+    /// created (which is logged).
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os(RECORD_ENV).filter(|path| !path.is_empty())?;
+        match Self::create(&path) {
+            Ok(recorder) => {
+                tracing::info!("Recording session to {}", recorder.path.display());
+                Some(recorder)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Cannot record session to {}: {}",
+                    Path::new(&path).display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns the path of the trace file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends an event to the trace.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trace file cannot be written.
+    pub fn record(&mut self, event: RecordedEvent) -> io::Result<()> {
+        let at_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        write_entry(&mut self.writer, &RecordedEntry { at_ms, event })
+    }
+}
+
+/// Writes one entry as a line of JSON.
+///
+/// # Errors
+///
+/// Returns an error if the writer fails.
+pub fn write_entry(writer: &mut impl Write, entry: &RecordedEntry) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, entry)?;
+    writeln!(writer)
+}
+
+/// Parses a trace, one JSON entry per line (blank lines are skipped).
+///
+/// # Errors
+///
+/// Returns the number and error of the first line that does not parse.
+pub fn parse_trace(text: &str) -> Result<Vec<RecordedEntry>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", index + 1, e))
+        })
+        .collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Entries are written as flat JSON lines and parse back.
+    #[test]
+    fn test_entry_round_trip() {
+        let entries = vec![
+            RecordedEntry {
+                at_ms: 0,
+                event: RecordedEvent::Show,
+            },
+            RecordedEntry {
+                at_ms: 812,
+                event: RecordedEvent::KeyPressed {
+                    key: "shift".to_string(),
+                },
+            },
+            RecordedEntry {
+                at_ms: 1500,
+                event: RecordedEvent::Gesture {
+                    gesture: Gesture::SwipeDown,
+                },
+            },
+        ];
+
+        let mut trace = Vec::new();
+        for entry in &entries {
+            write_entry(&mut trace, entry).unwrap();
+        }
+        let trace = String::from_utf8(trace).unwrap();
+
+        assert_eq!(
+            trace.lines().nth(1),
+            Some(r#"{"at_ms":812,"event":"key_pressed","key":"shift"}"#)
+        );
+        assert_eq!(parse_trace(&trace), Ok(entries));
+    }
+
+    /// Test: Parse errors name the offending line.
+    #[test]
+    fn test_parse_trace_errors() {
+        let trace = "{\"at_ms\":0,\"event\":\"show\"}\n\n{\"at_ms\":5,\"event\":\"typed\"}\n";
+        let error = parse_trace(trace).unwrap_err();
+        assert!(error.starts_with("line 3:"), "{}", error);
+    }
+}
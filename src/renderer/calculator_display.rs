@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Calculator display rendering for the keyboard layout renderer.
+//!
+//! This module renders the `calculator_display` widget (see
+//! [`crate::calculator`]): the expression being entered, right-aligned like
+//! on a pocket calculator.
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, container};
+use cosmic::Element;
+
+use crate::layout::Widget;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::sizing::resolve_sizing;
+use crate::renderer::state::KeyboardRenderer;
+
+/// Horizontal padding inside the display in pixels.
+const DISPLAY_PADDING: f32 = 8.0;
+
+/// Renders the calculator display widget.
+///
+/// Shows `0` while the expression is empty. The display does not react to
+/// presses; the calculator keys are ordinary command keys.
+///
+/// # Arguments
+///
+/// * `widget` - The widget definition from the layout
+/// * `state` - The keyboard renderer state (for the expression)
+/// * `base_unit` - The calculated base unit for relative sizing
+/// * `scale` - HDPI scale factor for pixel sizing
+///
+/// # Returns
+///
+/// An Element containing the display.
+pub fn render_calculator_display<'a>(
+    widget: &Widget,
+    state: &KeyboardRenderer,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let width = resolve_sizing(&widget.width, base_unit, scale);
+    let height = resolve_sizing(&widget.height, base_unit, scale);
+
+    let expression = state.calculator.expression();
+    let text = if expression.is_empty() {
+        "0".to_string()
+    } else {
+        expression.to_string()
+    };
+
+    container(widget::text::title3(text))
+        .padding([0.0, DISPLAY_PADDING])
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .align_x(Alignment::End)
+        .align_y(Alignment::Center)
+        .class(cosmic::style::Container::Background)
+        .into()
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Collapsed bar rendering for the keyboard layout renderer.
+//!
+//! Collapsing the keyboard (`toggle_collapsed` action, e.g. from a chevron
+//! key) shrinks it to a thin bar instead of hiding it: the surface and the
+//! space reserved for it when docked drop to [`COLLAPSED_BAR_HEIGHT`], and
+//! the bar shows the layout name and the active modifiers. The chevron
+//! button on the bar, or a double tap anywhere else on it, expands the
+//! keyboard again at its previous height, without re-creating the surface.
+
+use std::time::{Duration, Instant};
+
+use cosmic::iced::{Alignment, Length, Padding};
+use cosmic::widget::{self, button, container, icon, mouse_area, Space};
+use cosmic::Element;
+
+use crate::actions::AppAction;
+use crate::layout::Modifier;
+use crate::renderer::message::RendererMessage;
+
+/// Height of the collapsed keyboard, in pixels.
+pub const COLLAPSED_BAR_HEIGHT: f32 = 36.0;
+
+/// Longest pause between the two taps of a double tap on the bar.
+pub const HANDLE_DOUBLE_TAP: Duration = Duration::from_millis(400);
+
+/// Icon of the expand button.
+const EXPAND_ICON: &str = "go-up-symbolic";
+
+/// Returns the name of a modifier as shown on the bar.
+#[must_use]
+pub fn modifier_label(modifier: Modifier) -> &'static str {
+    match modifier {
+        Modifier::Shift => "Shift",
+        Modifier::Ctrl => "Ctrl",
+        Modifier::Alt => "Alt",
+        Modifier::Super => "Super",
+        Modifier::CapsLock => "Caps Lock",
+    }
+}
+
+/// Returns the status shown on the bar: the layout name, followed by the
+/// active modifiers if any (`QWERTY · Shift+Ctrl`).
+#[must_use]
+pub fn collapsed_status(layout_name: &str, modifiers: &[Modifier]) -> String {
+    if modifiers.is_empty() {
+        return layout_name.to_string();
+    }
+    let modifiers: Vec<_> = modifiers.iter().map(|&m| modifier_label(m)).collect();
+    format!("{} · {}", layout_name, modifiers.join("+"))
+}
+
+/// Returns `true` if a tap on the bar at `now` completes a double tap
+/// started at `last_tap`.
+#[must_use]
+pub fn is_double_tap(last_tap: Option<Instant>, now: Instant) -> bool {
+    last_tap.is_some_and(|last| now.saturating_duration_since(last) <= HANDLE_DOUBLE_TAP)
+}
+
+/// Renders the collapsed keyboard.
+///
+/// # Arguments
+///
+/// * `status` - Text shown on the bar, from [`collapsed_status`]
+///
+/// # Returns
+///
+/// An Element filling the surface, meant to be [`COLLAPSED_BAR_HEIGHT`]
+/// high. Taps on the bar outside the button emit
+/// [`RendererMessage::CollapsedBarTapped`].
+pub fn render_collapsed_bar<'a>(status: String) -> Element<'a, RendererMessage> {
+    let expand = button::custom(icon::from_name(EXPAND_ICON).size(16).symbolic(true))
+        .on_press(RendererMessage::RunAction(AppAction::ToggleCollapsed))
+        .class(cosmic::style::Button::Icon)
+        .height(Length::Fill);
+
+    let bar = container(
+        widget::row::row()
+            .spacing(8)
+            .align_y(Alignment::Center)
+            .push(widget::text::body(status))
+            .push(Space::new(Length::Fill, Length::Shrink))
+            .push(expand),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding(Padding::new(4.0).left(12.0))
+    .align_y(Alignment::Center)
+    .class(cosmic::style::Container::Background);
+
+    mouse_area(bar)
+        .on_press(RendererMessage::CollapsedBarTapped)
+        .into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The status lists the active modifiers after the layout name.
+    #[test]
+    fn test_collapsed_status() {
+        assert_eq!(collapsed_status("QWERTY", &[]), "QWERTY");
+        assert_eq!(
+            collapsed_status("QWERTY", &[Modifier::Shift, Modifier::Ctrl]),
+            "QWERTY · Shift+Ctrl"
+        );
+    }
+
+    /// Test: Only a second tap soon after the first is a double tap.
+    #[test]
+    fn test_is_double_tap() {
+        let now = Instant::now();
+        assert!(!is_double_tap(None, now));
+        assert!(is_double_tap(Some(now), now + HANDLE_DOUBLE_TAP));
+        assert!(!is_double_tap(
+            Some(now),
+            now + HANDLE_DOUBLE_TAP + Duration::from_millis(1)
+        ));
+    }
+}
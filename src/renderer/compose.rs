@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Runtime panel composition.
+//!
+//! Panels are usually defined by the layout file, but some content is only
+//! known at runtime: clipboard history, prediction candidates, recently
+//! used snippets. Rather than drawing each of those as a bespoke widget, the
+//! applet can build them as ordinary panels with the methods here, and they
+//! get key rendering, press feedback, long press and panel switching for
+//! free.
+//!
+//! ```rust,ignore
+//! use cosboard::layout::{Cell, Key, KeyCode, Panel, Row};
+//!
+//! renderer.add_panel(Panel { id: "clipboard".to_string(), ..Panel::default() })?;
+//! renderer.insert_row("clipboard", 0, Row::default())?;
+//! renderer.insert_key("clipboard", 0, 0, Key {
+//!     label: "hello".to_string(),
+//!     code: KeyCode::Unicode('h'),
+//!     identifier: Some("clip_0".to_string()),
+//!     ..Key::default()
+//! })?;
+//! renderer.switch_panel("clipboard")?;
+//! ```
+//!
+//! Changes only affect the renderer's copy of the layout; the layout file
+//! and the layout cache are never modified. Keys are addressed by their
+//! identifier, which must be unique within a panel.
+
+use std::collections::HashSet;
+
+use crate::layout::{Cell, Key, Panel, Row};
+use crate::renderer::state::KeyboardRenderer;
+
+/// Returns the identifiers of the keys in a row.
+fn row_identifiers(row: &Row) -> impl Iterator<Item = &str> {
+    row.cells.iter().filter_map(|cell| match cell {
+        Cell::Key(key) => key.identifier.as_deref(),
+        _ => None,
+    })
+}
+
+/// Checks that no key identifier in `rows` is used twice or already in `panel`.
+fn check_unique_identifiers<'a>(
+    panel: &Panel,
+    rows: impl IntoIterator<Item = &'a Row>,
+) -> Result<(), String> {
+    let mut seen: HashSet<&str> = panel.rows.iter().flat_map(row_identifiers).collect();
+    for row in rows {
+        for identifier in row_identifiers(row) {
+            if !seen.insert(identifier) {
+                return Err(format!(
+                    "Key '{}' already exists in panel '{}'",
+                    identifier, panel.id
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl KeyboardRenderer {
+    /// Returns a mutable reference to a panel by ID.
+    fn panel_mut(&mut self, panel_id: &str) -> Result<&mut Panel, String> {
+        self.layout
+            .panels
+            .get_mut(panel_id)
+            .ok_or_else(|| format!("Panel '{}' not found", panel_id))
+    }
+
+    /// Adds a panel to the layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a panel with the same ID exists or the panel
+    /// uses a key identifier twice.
+    pub fn add_panel(&mut self, panel: Panel) -> Result<(), String> {
+        if self.layout.panels.contains_key(&panel.id) {
+            return Err(format!("Panel '{}' already exists", panel.id));
+        }
+        let empty = Panel {
+            id: panel.id.clone(),
+            ..Panel::default()
+        };
+        check_unique_identifiers(&empty, &panel.rows)?;
+
+        self.layout.panels.insert(panel.id.clone(), panel);
+        Ok(())
+    }
+
+    /// Removes a panel from the layout and returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the panel does not exist, is the default panel,
+    /// or is shown or being animated to or from.
+    pub fn remove_panel(&mut self, panel_id: &str) -> Result<Panel, String> {
+        let in_animation = self.animation().is_some_and(|animation| {
+            animation.from_panel_id == panel_id || animation.to_panel_id == panel_id
+        });
+        if self.current_panel_id == panel_id || in_animation {
+            return Err(format!("Panel '{}' is shown and cannot be removed", panel_id));
+        }
+        if self.layout.default_panel_id == panel_id {
+            return Err(format!("Panel '{}' is the default panel", panel_id));
+        }
+
+        self.layout
+            .panels
+            .remove(panel_id)
+            .ok_or_else(|| format!("Panel '{}' not found", panel_id))
+    }
+
+    /// Inserts a row into a panel at `index` (`index == rows` appends).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the panel does not exist, the index is out of
+    /// bounds, or a key identifier in the row is already used in the panel.
+    pub fn insert_row(&mut self, panel_id: &str, index: usize, row: Row) -> Result<(), String> {
+        let panel = self.panel_mut(panel_id)?;
+        if index > panel.rows.len() {
+            return Err(format!(
+                "Row {} is out of bounds for panel '{}' ({} rows)",
+                index,
+                panel_id,
+                panel.rows.len()
+            ));
+        }
+        check_unique_identifiers(panel, [&row])?;
+
+        panel.rows.insert(index, row);
+        Ok(())
+    }
+
+    /// Replaces a row of a panel, returning the old row.
+    ///
+    /// Useful for content that changes on every key press, such as a row of
+    /// prediction candidates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the panel or row does not exist, or a key
+    /// identifier in the row is used elsewhere in the panel.
+    pub fn replace_row(&mut self, panel_id: &str, index: usize, row: Row) -> Result<Row, String> {
+        let old = self.remove_row(panel_id, index)?;
+        if let Err(e) = self.insert_row(panel_id, index, row) {
+            // Put the old row back so a rejected row leaves the panel unchanged
+            self.panel_mut(panel_id)?.rows.insert(index, old);
+            return Err(e);
+        }
+        Ok(old)
+    }
+
+    /// Removes a row from a panel and returns it.
+    ///
+    /// Keys in the row are released first (see [`Self::remove_key`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the panel or row does not exist.
+    pub fn remove_row(&mut self, panel_id: &str, index: usize) -> Result<Row, String> {
+        let panel = self.panel_mut(panel_id)?;
+        if index >= panel.rows.len() {
+            return Err(format!(
+                "Row {} is out of bounds for panel '{}' ({} rows)",
+                index,
+                panel_id,
+                panel.rows.len()
+            ));
+        }
+
+        let row = panel.rows.remove(index);
+        for identifier in row_identifiers(&row) {
+            self.forget_key(identifier);
+        }
+        Ok(row)
+    }
+
+    /// Inserts a key into a row of a panel at cell `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the panel or row does not exist, the index is out
+    /// of bounds, or the key's identifier is already used in the panel.
+    pub fn insert_key(
+        &mut self,
+        panel_id: &str,
+        row: usize,
+        index: usize,
+        key: Key,
+    ) -> Result<(), String> {
+        let panel = self.panel_mut(panel_id)?;
+        let duplicate = key.identifier.as_deref().filter(|identifier| {
+            panel.rows.iter().flat_map(row_identifiers).any(|id| id == *identifier)
+        });
+        if let Some(identifier) = duplicate {
+            return Err(format!(
+                "Key '{}' already exists in panel '{}'",
+                identifier, panel_id
+            ));
+        }
+
+        let row_count = panel.rows.len();
+        let Some(cells) = panel.rows.get_mut(row).map(|row| &mut row.cells) else {
+            return Err(format!(
+                "Row {} is out of bounds for panel '{}' ({} rows)",
+                row, panel_id, row_count
+            ));
+        };
+        if index > cells.len() {
+            return Err(format!(
+                "Cell {} is out of bounds for row {} of panel '{}' ({} cells)",
+                index,
+                row,
+                panel_id,
+                cells.len()
+            ));
+        }
+
+        cells.insert(index, Cell::Key(key));
+        Ok(())
+    }
+
+    /// Removes every key with the given identifier from all panels.
+    ///
+    /// A removed key that is pressed, sticky or long-pressed is released so
+    /// no state refers to a key that is no longer drawn.
+    ///
+    /// Returns the number of keys removed.
+    pub fn remove_key(&mut self, identifier: &str) -> usize {
+        let mut removed = 0;
+        for panel in self.layout.panels.values_mut() {
+            for row in &mut panel.rows {
+                let before = row.cells.len();
+                row.cells.retain(|cell| match cell {
+                    Cell::Key(key) => key.identifier.as_deref() != Some(identifier),
+                    _ => true,
+                });
+                removed += before - row.cells.len();
+            }
+        }
+
+        if removed > 0 {
+            self.forget_key(identifier);
+        }
+        removed
+    }
+
+    /// Clears the press, sticky and long press state of a key.
+    fn forget_key(&mut self, identifier: &str) {
+        self.pressed_keys.remove(identifier);
+        self.sticky_keys_active.remove(identifier);
+        if self.long_press_key.as_deref() == Some(identifier) {
+            self.cancel_long_press();
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::layout::{KeyCode, Layout};
+
+    fn key(identifier: &str) -> Key {
+        Key {
+            label: identifier.to_string(),
+            code: KeyCode::Unicode('x'),
+            identifier: Some(identifier.to_string()),
+            ..Key::default()
+        }
+    }
+
+    fn row(identifiers: &[&str]) -> Row {
+        Row {
+            cells: identifiers.iter().map(|id| Cell::Key(key(id))).collect(),
+        }
+    }
+
+    fn identifiers(renderer: &KeyboardRenderer, panel_id: &str) -> Vec<Vec<String>> {
+        renderer
+            .get_panel(panel_id)
+            .unwrap()
+            .rows
+            .iter()
+            .map(|row| row_identifiers(row).map(str::to_string).collect())
+            .collect()
+    }
+
+    fn renderer() -> KeyboardRenderer {
+        let main = Panel {
+            id: "main".to_string(),
+            rows: vec![row(&["a", "b"])],
+            ..Panel::default()
+        };
+        KeyboardRenderer::new(Layout {
+            name: "Compose".to_string(),
+            default_panel_id: "main".to_string(),
+            panels: HashMap::from([("main".to_string(), main)]),
+            ..Layout::default()
+        })
+    }
+
+    /// Test: A panel built at runtime can be filled, switched to and removed.
+    #[test]
+    fn test_build_runtime_panel() {
+        let mut renderer = renderer();
+        let clipboard = Panel {
+            id: "clipboard".to_string(),
+            ..Panel::default()
+        };
+        renderer.add_panel(clipboard.clone()).unwrap();
+        assert!(renderer.add_panel(clipboard).is_err());
+
+        renderer.insert_row("clipboard", 0, row(&["clip_1"])).unwrap();
+        renderer.insert_row("clipboard", 0, row(&["clip_0"])).unwrap();
+        renderer.insert_key("clipboard", 1, 0, key("clip_2")).unwrap();
+        assert_eq!(
+            identifiers(&renderer, "clipboard"),
+            vec![vec!["clip_0"], vec!["clip_2", "clip_1"]]
+        );
+        assert!(renderer.insert_row("clipboard", 5, Row::default()).is_err());
+
+        renderer.switch_panel("clipboard").unwrap();
+        renderer.complete_animation();
+        assert!(renderer.remove_panel("clipboard").is_err());
+        renderer.switch_panel("main").unwrap();
+        renderer.complete_animation();
+        assert!(renderer.remove_panel("clipboard").is_ok());
+        assert!(renderer.remove_panel("main").is_err());
+    }
+
+    /// Test: Duplicate identifiers are rejected and leave the panel unchanged.
+    #[test]
+    fn test_duplicate_identifiers_rejected() {
+        let mut renderer = renderer();
+
+        assert!(renderer.insert_key("main", 0, 0, key("a")).is_err());
+        assert!(renderer.insert_row("main", 1, row(&["c", "c"])).is_err());
+        assert!(renderer.replace_row("main", 0, row(&["c", "c"])).is_err());
+        assert_eq!(identifiers(&renderer, "main"), vec![vec!["a", "b"]]);
+
+        let old = renderer.replace_row("main", 0, row(&["a", "c"])).unwrap();
+        assert_eq!(old, row(&["a", "b"]));
+    }
+
+    /// Test: Removing a key releases its pressed and sticky state.
+    #[test]
+    fn test_remove_key_releases_state() {
+        let mut renderer = renderer();
+        renderer.press_key("a");
+        renderer.toggle_sticky("a");
+
+        assert_eq!(renderer.remove_key("a"), 1);
+        assert_eq!(renderer.remove_key("a"), 0);
+        assert!(!renderer.is_key_pressed("a"));
+        assert!(!renderer.is_sticky_active("a"));
+        assert_eq!(identifiers(&renderer, "main"), vec![vec!["b"]]);
+    }
+}
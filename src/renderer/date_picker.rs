@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Date picker rendering for the keyboard layout renderer.
+//!
+//! This module renders the `date_picker` widget (see [`crate::date_picker`]):
+//! a column per field with buttons stepping it up and down, next to buttons
+//! that type the date, the time or both, and one that goes back to now.
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, button, container, icon};
+use cosmic::Element;
+
+use crate::date_picker::{DateField, DateInsert};
+use crate::fl;
+use crate::layout::Widget;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::sizing::resolve_sizing;
+use crate::renderer::state::KeyboardRenderer;
+
+/// Spacing between fields and buttons in pixels.
+const PICKER_SPACING: f32 = 4.0;
+
+/// Share of the widget width taken by the insert buttons.
+const ACTIONS_WIDTH_RATIO: f32 = 0.3;
+
+/// Renders the date picker widget.
+///
+/// Stepping a field emits `StepDate`, the insert buttons emit `InsertDate`
+/// and the now button `ResetDate`.
+///
+/// # Arguments
+///
+/// * `widget` - The widget definition from the layout
+/// * `state` - The keyboard renderer state (for the picked date)
+/// * `base_unit` - The calculated base unit for relative sizing
+/// * `scale` - HDPI scale factor for pixel sizing
+///
+/// # Returns
+///
+/// An Element containing the fields and the insert buttons.
+pub fn render_date_picker<'a>(
+    widget: &Widget,
+    state: &KeyboardRenderer,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let width = resolve_sizing(&widget.width, base_unit, scale);
+    let height = resolve_sizing(&widget.height, base_unit, scale);
+    let step_height = (height / 4.0).min(base_unit);
+
+    let step_button = |field: DateField, delta: i32| {
+        let icon_name = if delta > 0 {
+            "go-up-symbolic"
+        } else {
+            "go-down-symbolic"
+        };
+        button::custom(
+            container(icon::from_name(icon_name).size(16).symbolic(true))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center),
+        )
+        .on_press(RendererMessage::StepDate(field, delta))
+        .class(cosmic::style::Button::Standard)
+        .width(Length::Fill)
+        .height(Length::Fixed(step_height))
+    };
+
+    // One column per field: up, value and name, down
+    let mut fields = widget::row::row()
+        .spacing(PICKER_SPACING)
+        .width(Length::Fill);
+    for field in DateField::ALL {
+        let value = widget::column::column()
+            .align_x(Alignment::Center)
+            .push(widget::text::title4(field.display(state.picked_date)))
+            .push(widget::text::caption(field.label()));
+        let column = widget::column::column()
+            .spacing(PICKER_SPACING)
+            .width(Length::Fill)
+            .push(step_button(field, 1))
+            .push(
+                container(value)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Alignment::Center)
+                    .align_y(Alignment::Center),
+            )
+            .push(step_button(field, -1));
+        fields = fields.push(column);
+    }
+
+    let action_button = |label: String, message: RendererMessage, class: cosmic::style::Button| {
+        button::custom(
+            container(widget::text::body(label))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center),
+        )
+        .on_press(message)
+        .class(class)
+        .width(Length::Fill)
+        .height(Length::Fill)
+    };
+    let actions = widget::column::column()
+        .spacing(PICKER_SPACING)
+        .width(Length::Fixed(width * ACTIONS_WIDTH_RATIO))
+        .push(action_button(
+            fl!("date-picker-date"),
+            RendererMessage::InsertDate(DateInsert::Date),
+            cosmic::style::Button::Suggested,
+        ))
+        .push(action_button(
+            fl!("date-picker-time"),
+            RendererMessage::InsertDate(DateInsert::Time),
+            cosmic::style::Button::Standard,
+        ))
+        .push(action_button(
+            fl!("date-picker-both"),
+            RendererMessage::InsertDate(DateInsert::DateTime),
+            cosmic::style::Button::Standard,
+        ))
+        .push(action_button(
+            fl!("date-picker-now"),
+            RendererMessage::ResetDate,
+            cosmic::style::Button::Text,
+        ));
+
+    let content = widget::row::row()
+        .spacing(PICKER_SPACING)
+        .push(fields)
+        .push(actions);
+
+    container(content)
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .into()
+}
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Dead zones along the edges of the keyboard.
+//!
+//! On a tablet, the palm resting on the bottom edge of the screen presses
+//! whatever key is under it. The `dead_zones` configuration entry reserves
+//! strips along the edges of the keyboard, separately for the docked and
+//! the floating mode: the keys are laid out inside the remaining area
+//! ([`DeadZoneInsets::inner_size`]), nothing in the strips reacts to a
+//! press, and touches starting there do not count towards keyboard-wide
+//! gestures ([`DeadZoneInsets::contains`]).
+//!
+//! While the settings menu is open, the strips are tinted with the accent
+//! color so their size can be judged while adjusting it.
+
+use cosmic::iced::{Color, Length, Padding};
+use cosmic::widget::container;
+use cosmic::Element;
+use serde::{Deserialize, Serialize};
+
+use crate::renderer::theme::keyboard_background_color;
+
+/// Inset sizes offered by the settings menu, in pixels, cycled in order.
+pub const DEAD_ZONE_STEPS: [u32; 5] = [0, 16, 32, 48, 64];
+
+/// Opacity of the accent tint over the dead zones while they are shown.
+const DEAD_ZONE_TINT_ALPHA: f32 = 0.35;
+
+/// An edge of the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// Top edge.
+    Top,
+    /// Right edge.
+    Right,
+    /// Bottom edge, where palms usually rest.
+    Bottom,
+    /// Left edge.
+    Left,
+}
+
+impl Edge {
+    /// All edges, in the order the settings menu lists them.
+    pub const ALL: [Edge; 4] = [Edge::Bottom, Edge::Left, Edge::Right, Edge::Top];
+
+    /// Returns the name of the edge used in the settings menu.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Edge::Top => "top",
+            Edge::Right => "right",
+            Edge::Bottom => "bottom",
+            Edge::Left => "left",
+        }
+    }
+}
+
+/// Width of the dead zone along each edge, in logical pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadZoneInsets {
+    /// Dead zone along the top edge.
+    #[serde(default)]
+    pub top: u32,
+    /// Dead zone along the right edge.
+    #[serde(default)]
+    pub right: u32,
+    /// Dead zone along the bottom edge.
+    #[serde(default)]
+    pub bottom: u32,
+    /// Dead zone along the left edge.
+    #[serde(default)]
+    pub left: u32,
+}
+
+impl DeadZoneInsets {
+    /// Returns `true` if no edge has a dead zone.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the width of the dead zone along an edge.
+    #[must_use]
+    pub fn get(&self, edge: Edge) -> u32 {
+        match edge {
+            Edge::Top => self.top,
+            Edge::Right => self.right,
+            Edge::Bottom => self.bottom,
+            Edge::Left => self.left,
+        }
+    }
+
+    /// Sets the width of the dead zone along an edge.
+    pub fn set(&mut self, edge: Edge, inset: u32) {
+        match edge {
+            Edge::Top => self.top = inset,
+            Edge::Right => self.right = inset,
+            Edge::Bottom => self.bottom = inset,
+            Edge::Left => self.left = inset,
+        }
+    }
+
+    /// Sets the dead zone along an edge to the next of [`DEAD_ZONE_STEPS`],
+    /// back to none after the largest.
+    pub fn cycle(&mut self, edge: Edge) {
+        let current = self.get(edge);
+        let next = DEAD_ZONE_STEPS
+            .iter()
+            .copied()
+            .find(|&step| step > current)
+            .unwrap_or(0);
+        self.set(edge, next);
+    }
+
+    /// Returns the size left for the keys on a surface of the given size.
+    #[must_use]
+    pub fn inner_size(&self, width: f32, height: f32) -> (f32, f32) {
+        (
+            (width - (self.left + self.right) as f32).max(0.0),
+            (height - (self.top + self.bottom) as f32).max(0.0),
+        )
+    }
+
+    /// Returns `true` if a position on a surface of the given size falls in
+    /// a dead zone.
+    #[must_use]
+    pub fn contains(&self, position: (f32, f32), width: f32, height: f32) -> bool {
+        let (x, y) = position;
+        x < self.left as f32
+            || y < self.top as f32
+            || x >= width - self.right as f32
+            || y >= height - self.bottom as f32
+    }
+}
+
+/// Dead zones of the keyboard in each mode, from the user configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadZones {
+    /// Insets while the keyboard is docked to a screen edge.
+    #[serde(default)]
+    pub docked: DeadZoneInsets,
+    /// Insets while the keyboard floats.
+    #[serde(default)]
+    pub floating: DeadZoneInsets,
+}
+
+impl DeadZones {
+    /// Returns the insets of a mode.
+    #[must_use]
+    pub fn for_mode(&self, floating: bool) -> DeadZoneInsets {
+        if floating {
+            self.floating
+        } else {
+            self.docked
+        }
+    }
+
+    /// Returns the insets of a mode for changing them.
+    pub fn for_mode_mut(&mut self, floating: bool) -> &mut DeadZoneInsets {
+        if floating {
+            &mut self.floating
+        } else {
+            &mut self.docked
+        }
+    }
+}
+
+/// Surrounds the keyboard content with its dead zones.
+///
+/// # Arguments
+///
+/// * `content` - The keyboard, sized to [`DeadZoneInsets::inner_size`]
+/// * `insets` - The dead zones of the current mode
+/// * `highlighted` - Whether to tint the dead zones, while they are adjusted
+///
+/// # Returns
+///
+/// An Element filling the surface. The dead zones hold no widget, so
+/// presses there reach no key.
+pub fn render_dead_zones<'a, M: 'a>(
+    content: Element<'a, M>,
+    insets: DeadZoneInsets,
+    highlighted: bool,
+) -> Element<'a, M> {
+    if insets.is_empty() {
+        return content;
+    }
+    let padding = Padding {
+        top: insets.top as f32,
+        right: insets.right as f32,
+        bottom: insets.bottom as f32,
+        left: insets.left as f32,
+    };
+    container(content)
+        .padding(padding)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .class(cosmic::style::Container::custom(move |theme| {
+            let background = if highlighted {
+                Color {
+                    a: DEAD_ZONE_TINT_ALPHA,
+                    ..Color::from(theme.cosmic().accent_color())
+                }
+            } else {
+                keyboard_background_color(theme)
+            };
+            container::Style {
+                background: Some(cosmic::iced::Background::Color(background)),
+                ..Default::default()
+            }
+        }))
+        .into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Positions on the edges fall in the dead zones, the rest of the
+    /// surface is left to the keys.
+    #[test]
+    fn test_contains() {
+        let insets = DeadZoneInsets {
+            bottom: 40,
+            left: 10,
+            ..DeadZoneInsets::default()
+        };
+        assert!(insets.contains((50.0, 170.0), 400.0, 200.0));
+        assert!(insets.contains((5.0, 50.0), 400.0, 200.0));
+        assert!(!insets.contains((50.0, 159.0), 400.0, 200.0));
+        assert!(!insets.contains((399.0, 0.0), 400.0, 200.0));
+        assert_eq!(insets.inner_size(400.0, 200.0), (390.0, 160.0));
+        assert_eq!(insets.inner_size(30.0, 20.0), (20.0, 0.0));
+        assert!(!DeadZoneInsets::default().contains((0.0, 0.0), 400.0, 200.0));
+    }
+
+    /// Test: Cycling an edge steps through the offered sizes and back to
+    /// none, in the mode it was changed in only.
+    #[test]
+    fn test_cycle() {
+        let mut zones = DeadZones::default();
+        zones.for_mode_mut(true).cycle(Edge::Bottom);
+        assert_eq!(zones.floating.bottom, 16);
+        assert!(zones.docked.is_empty());
+
+        zones.floating.bottom = 20;
+        zones.for_mode_mut(true).cycle(Edge::Bottom);
+        assert_eq!(zones.for_mode(true).get(Edge::Bottom), 32);
+        zones.floating.bottom = 64;
+        zones.for_mode_mut(true).cycle(Edge::Bottom);
+        assert!(zones.for_mode(true).is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Debug overlay for diagnosing layout and sizing issues.
+//!
+//! When enabled, every cell is outlined with its bounding box and labelled
+//! with its identifier and computed size in pixels, and the panel shows its
+//! ID, the current base unit and the scale factor in the top-left corner.
+//!
+//! The overlay is enabled by the `debug_overlay` configuration entry or by
+//! setting the [`DEBUG_OVERLAY_ENV`] environment variable (to anything but
+//! `0` or `false`) before starting the applet:
+//!
+//! ```bash
+//! COSBOARD_DEBUG_OVERLAY=1 cosboard-applet
+//! ```
+
+use cosmic::iced::widget::stack;
+use cosmic::iced::{Alignment, Length, Padding};
+use cosmic::widget::{self, container};
+use cosmic::Element;
+
+use crate::layout::Cell;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::sizing::resolve_sizing;
+
+/// Environment variable that enables the debug overlay.
+pub const DEBUG_OVERLAY_ENV: &str = "COSBOARD_DEBUG_OVERLAY";
+
+/// Text size of the overlay labels.
+const LABEL_SIZE: f32 = 9.0;
+
+/// Color of the cell outlines and labels.
+const OVERLAY_COLOR: cosmic::iced::Color = cosmic::iced::Color::from_rgb(1.0, 0.2, 0.4);
+
+/// Returns `true` if the environment enables the debug overlay.
+#[must_use]
+pub fn debug_overlay_from_env() -> bool {
+    is_enabled_value(std::env::var(DEBUG_OVERLAY_ENV).ok().as_deref())
+}
+
+/// Interprets the value of [`DEBUG_OVERLAY_ENV`], or of another debug
+/// switch.
+pub(crate) fn is_enabled_value(value: Option<&str>) -> bool {
+    value.is_some_and(|value| {
+        let value = value.trim();
+        !value.is_empty() && value != "0" && !value.eq_ignore_ascii_case("false")
+    })
+}
+
+/// Returns the computed size of a cell in pixels.
+#[must_use]
+pub fn cell_size(cell: &Cell, base_unit: f32, scale: f32) -> (f32, f32) {
+    let (width, height) = match cell {
+        Cell::Key(key) => (&key.width, &key.height),
+        Cell::Widget(widget) => (&widget.width, &widget.height),
+        Cell::PanelRef(panel_ref) => (&panel_ref.width, &panel_ref.height),
+    };
+    (
+        resolve_sizing(width, base_unit, scale),
+        resolve_sizing(height, base_unit, scale),
+    )
+}
+
+/// Returns the overlay label of a cell: its identifier and computed size.
+#[must_use]
+pub fn cell_label(cell: &Cell, base_unit: f32, scale: f32) -> String {
+    let name = match cell {
+        Cell::Key(key) => key.identifier.clone().unwrap_or_else(|| key.label.clone()),
+        Cell::Widget(widget) => format!("<{}>", widget.widget_type),
+        Cell::PanelRef(panel_ref) => format!("-> {}", panel_ref.panel_id),
+    };
+    let (width, height) = cell_size(cell, base_unit, scale);
+    format!("{}\n{:.0}x{:.0}", name, width, height)
+}
+
+/// Returns the overlay label of a panel: its ID, base unit and scale.
+#[must_use]
+pub fn panel_label(panel_id: &str, base_unit: f32, scale: f32) -> String {
+    format!("{} | base unit {:.1}px | scale {}", panel_id, base_unit, scale)
+}
+
+/// Draws a cell's bounding box and label over the rendered cell.
+pub fn with_cell_overlay<'a>(
+    element: Element<'a, RendererMessage>,
+    cell: &Cell,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let (width, height) = cell_size(cell, base_unit, scale);
+    let label = widget::text(cell_label(cell, base_unit, scale))
+        .size(LABEL_SIZE)
+        .class(cosmic::style::Text::Color(OVERLAY_COLOR));
+
+    let outline = container(label)
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .padding(Padding::from(2.0))
+        .align_x(Alignment::Start)
+        .align_y(Alignment::Start)
+        .class(cosmic::style::Container::custom(|_theme| container::Style {
+            background: None,
+            border: cosmic::iced::Border {
+                color: OVERLAY_COLOR,
+                width: 1.0,
+                radius: 0.0.into(),
+            },
+            icon_color: None,
+            text_color: None,
+            shadow: cosmic::iced::Shadow::default(),
+        }));
+
+    stack![element, outline].into()
+}
+
+/// Draws the panel label in the top-left corner of the rendered panel.
+pub fn with_panel_overlay<'a>(
+    element: Element<'a, RendererMessage>,
+    panel_id: &str,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let label = widget::text(panel_label(panel_id, base_unit, scale))
+        .size(LABEL_SIZE)
+        .class(cosmic::style::Text::Color(OVERLAY_COLOR));
+
+    stack![element, container(label).padding(Padding::from(1.0))].into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Key, PanelRef, Sizing};
+
+    /// Test: Only non-empty values other than 0/false enable the overlay.
+    #[test]
+    fn test_env_value() {
+        assert!(!is_enabled_value(None));
+        assert!(!is_enabled_value(Some("")));
+        assert!(!is_enabled_value(Some("0")));
+        assert!(!is_enabled_value(Some("False")));
+        assert!(is_enabled_value(Some("1")));
+        assert!(is_enabled_value(Some("yes")));
+    }
+
+    /// Test: Labels show the identifier (or label) and the computed size.
+    #[test]
+    fn test_cell_label() {
+        let key = Cell::Key(Key {
+            label: "Shift".to_string(),
+            width: Sizing::Relative(1.5),
+            ..Key::default()
+        });
+        assert_eq!(cell_label(&key, 40.0, 1.0), "Shift\n60x40");
+
+        let panel_ref = Cell::PanelRef(PanelRef {
+            panel_id: "numpad".to_string(),
+            width: Sizing::Pixels("30px".to_string()),
+            height: Sizing::Relative(2.0),
+        });
+        assert_eq!(cell_label(&panel_ref, 40.0, 2.0), "-> numpad\n60x80");
+
+        assert_eq!(panel_label("main", 42.25, 1.0), "main | base unit 42.2px | scale 1");
+    }
+}
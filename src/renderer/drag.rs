@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Dragging text out of the keyboard.
+//!
+//! Typing a long suggestion key by key through the virtual keyboard is
+//! slow, and some applications mangle synthetic input (terminals with
+//! input methods, remote desktops). With `drag_suggestions` enabled, a
+//! prediction candidate can instead be dragged out of the keyboard and
+//! dropped into any application that accepts text drops, through a Wayland
+//! drag-and-drop offer.
+//!
+//! A press only becomes a drag once it moved [`DRAG_THRESHOLD`] pixels, so
+//! tapping a candidate still types it. While dragging is enabled,
+//! candidates are typed when released rather than when pressed, so a drag
+//! never types the suggestion as well.
+
+use std::borrow::Cow;
+
+use cosmic::iced::clipboard::mime::AsMimeTypes;
+use cosmic::widget;
+use cosmic::Element;
+
+use crate::layout::Key;
+use crate::prediction::CANDIDATE_KEY_PREFIX;
+use crate::renderer::message::RendererMessage;
+
+/// Distance in pixels a press moves before it becomes a drag.
+pub const DRAG_THRESHOLD: f32 = 16.0;
+
+/// MIME types the dragged text is offered as, preferred first.
+pub const TEXT_MIME_TYPES: [&str; 4] = [
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+];
+
+/// Text offered to the application it is dropped on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DragText(pub String);
+
+impl AsMimeTypes for DragText {
+    fn available(&self) -> Cow<'static, [String]> {
+        Cow::Owned(TEXT_MIME_TYPES.iter().map(ToString::to_string).collect())
+    }
+
+    fn as_bytes(&self, mime_type: &str) -> Option<Cow<'static, [u8]>> {
+        TEXT_MIME_TYPES
+            .contains(&mime_type)
+            .then(|| Cow::Owned(self.0.clone().into_bytes()))
+    }
+}
+
+/// Returns the text dragged out of `key`, if it can be dragged: prediction
+/// candidates that offer a word.
+#[must_use]
+pub fn drag_text(key: &Key, identifier: &str) -> Option<String> {
+    (identifier.starts_with(CANDIDATE_KEY_PREFIX) && !key.label.is_empty())
+        .then(|| key.label.clone())
+}
+
+/// Lets `element`, the key `identifier`, be dragged out of the keyboard
+/// with `text`.
+///
+/// Starting the drag emits [`RendererMessage::TextDragged`], so the press
+/// is not typed.
+pub fn draggable(
+    element: Element<'static, RendererMessage>,
+    identifier: String,
+    text: String,
+) -> Element<'static, RendererMessage> {
+    widget::dnd_source(element)
+        .drag_threshold(DRAG_THRESHOLD)
+        .drag_content(move || DragText(text.clone()))
+        .on_start(Some(RendererMessage::TextDragged(identifier)))
+        .into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prediction::candidate_key_id;
+
+    /// Test: The text is offered as plain text only.
+    #[test]
+    fn test_drag_text_mime_types() {
+        let text = DragText("héllo".to_string());
+        assert_eq!(text.available().len(), TEXT_MIME_TYPES.len());
+        assert_eq!(
+            text.as_bytes("text/plain;charset=utf-8").as_deref(),
+            Some("héllo".as_bytes())
+        );
+        assert_eq!(text.as_bytes("text/uri-list"), None);
+    }
+
+    /// Test: Only candidates with a word can be dragged.
+    #[test]
+    fn test_drag_text() {
+        let key = |label: &str| Key {
+            label: label.to_string(),
+            ..Key::default()
+        };
+        let candidate = candidate_key_id(0);
+        assert_eq!(
+            drag_text(&key("hello"), &candidate),
+            Some("hello".to_string())
+        );
+        assert_eq!(drag_text(&key(""), &candidate), None);
+        assert_eq!(drag_text(&key("a"), "a"), None);
+    }
+}
@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Key emission indicator for the keyboard layout renderer.
+//!
+//! When keys seem to go nowhere, it helps to know whether the keyboard sent
+//! them at all or the client (or the compositor's focus) lost them. With the
+//! indicator enabled, a thin strip under the keys holds a dot that lights up
+//! each time a key event is handed to the emission backend and fades out
+//! over [`EMISSION_PULSE`].
+//!
+//! The dot is the same for every key and only follows
+//! [`VirtualKeyboard::last_emission`], which keeps no key, so it is safe to
+//! leave on while typing passwords.
+//!
+//! [`VirtualKeyboard::last_emission`]: crate::input::VirtualKeyboard::last_emission
+
+use std::time::{Duration, Instant};
+
+use cosmic::iced::{Alignment, Color, Length, Padding};
+use cosmic::widget::{container, Space};
+use cosmic::Element;
+
+use crate::renderer::message::RendererMessage;
+
+/// Height of the strip holding the emission dot, in pixels.
+pub const EMISSION_STRIP_HEIGHT: f32 = 10.0;
+
+/// How long the dot takes to fade out after a key event.
+pub const EMISSION_PULSE: Duration = Duration::from_millis(250);
+
+/// Diameter of the emission dot in pixels.
+const EMISSION_DOT_SIZE: f32 = 6.0;
+
+/// Lowest opacity of the dot, so the strip shows where to look while idle.
+const EMISSION_DOT_IDLE_ALPHA: f32 = 0.15;
+
+/// Returns how lit the dot is at `now`: 1.0 right after a key event, fading
+/// linearly to 0.0 over [`EMISSION_PULSE`].
+#[must_use]
+pub fn pulse_intensity(last_emission: Option<Instant>, now: Instant) -> f32 {
+    let Some(last_emission) = last_emission else {
+        return 0.0;
+    };
+    let elapsed = now.saturating_duration_since(last_emission);
+    (1.0 - elapsed.as_secs_f32() / EMISSION_PULSE.as_secs_f32()).clamp(0.0, 1.0)
+}
+
+/// Returns `true` while the dot is fading, so the view needs redrawing.
+#[must_use]
+pub fn is_pulsing(last_emission: Option<Instant>, now: Instant) -> bool {
+    pulse_intensity(last_emission, now) > 0.0
+}
+
+/// Renders the emission strip with its dot at the given intensity.
+///
+/// # Arguments
+///
+/// * `intensity` - How lit the dot is, from [`pulse_intensity`]
+///
+/// # Returns
+///
+/// An Element containing the strip, [`EMISSION_STRIP_HEIGHT`] high.
+pub fn render_emission_strip<'a>(intensity: f32) -> Element<'a, RendererMessage> {
+    let alpha = EMISSION_DOT_IDLE_ALPHA + (1.0 - EMISSION_DOT_IDLE_ALPHA) * intensity;
+
+    let dot = container(Space::new(Length::Fill, Length::Fill))
+        .width(Length::Fixed(EMISSION_DOT_SIZE))
+        .height(Length::Fixed(EMISSION_DOT_SIZE))
+        .class(cosmic::style::Container::custom(move |theme| {
+            let accent = Color::from(theme.cosmic().accent_color());
+            container::Style {
+                background: Some(cosmic::iced::Background::Color(Color {
+                    a: alpha,
+                    ..accent
+                })),
+                border: cosmic::iced::Border {
+                    color: Color::TRANSPARENT,
+                    width: 0.0,
+                    radius: (EMISSION_DOT_SIZE / 2.0).into(),
+                },
+                icon_color: None,
+                text_color: None,
+                shadow: cosmic::iced::Shadow::default(),
+            }
+        }));
+
+    container(dot)
+        .width(Length::Fill)
+        .height(Length::Fixed(EMISSION_STRIP_HEIGHT))
+        .padding(Padding::new(0.0).right(EMISSION_STRIP_HEIGHT))
+        .align_x(Alignment::End)
+        .align_y(Alignment::Center)
+        .into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The dot is brightest right after a key event and fades out.
+    #[test]
+    fn test_pulse_intensity() {
+        let now = Instant::now();
+        assert_eq!(pulse_intensity(None, now), 0.0);
+        assert_eq!(pulse_intensity(Some(now), now), 1.0);
+
+        let half = pulse_intensity(Some(now), now + EMISSION_PULSE / 2);
+        assert!(half > 0.4 && half < 0.6);
+        assert!(is_pulsing(Some(now), now + EMISSION_PULSE / 2));
+
+        assert_eq!(pulse_intensity(Some(now), now + EMISSION_PULSE), 0.0);
+        assert!(!is_pulsing(Some(now), now + EMISSION_PULSE * 2));
+    }
+}
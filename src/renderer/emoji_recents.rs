@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Recents strip rendering for the keyboard layout renderer.
+//!
+//! This module renders the `emoji_recents` widget: a row of recently used and
+//! pinned emoji/symbols, intended for the top of an emoji panel. Entries come
+//! from the renderer's [`EmojiRecents`], which the applet keeps in sync with
+//! the user configuration.
+//!
+//! [`EmojiRecents`]: crate::emoji::EmojiRecents
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, button, container};
+use cosmic::Element;
+
+use crate::fl;
+use crate::layout::Widget;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::sizing::resolve_sizing;
+use crate::renderer::state::KeyboardRenderer;
+
+/// Widget type name for the recents strip in layout files.
+pub const EMOJI_RECENTS_WIDGET: &str = "emoji_recents";
+
+/// Maximum number of entries shown in the recents strip.
+pub const EMOJI_RECENTS_MAX_ENTRIES: usize = 10;
+
+/// Renders the recents strip widget.
+///
+/// Tapping an entry emits `InsertText`. The trailing pin button emits
+/// `ToggleFavoriteEmoji` for the most recently used entry, pinning it to the
+/// front of the strip (or unpinning it if it is already a favorite).
+///
+/// # Arguments
+///
+/// * `widget` - The widget definition from the layout
+/// * `state` - The keyboard renderer state (for the recents store)
+/// * `base_unit` - The calculated base unit for relative sizing
+/// * `scale` - HDPI scale factor for pixel sizing
+///
+/// # Returns
+///
+/// An Element containing the recents strip.
+pub fn render_emoji_recents<'a>(
+    widget: &Widget,
+    state: &KeyboardRenderer,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let width = resolve_sizing(&widget.width, base_unit, scale);
+    let height = resolve_sizing(&widget.height, base_unit, scale);
+
+    let recents = &state.emoji_recents;
+    let mut content = widget::row::row().spacing(4).align_y(Alignment::Center);
+
+    if recents.is_empty() {
+        content = content.push(
+            container(widget::text::body(fl!("emoji-recents-empty")))
+                .width(Length::Fill)
+                .align_x(Alignment::Center),
+        );
+    }
+
+    for glyph in recents.strip(EMOJI_RECENTS_MAX_ENTRIES) {
+        let entry = button::custom(
+            container(widget::text::title4(glyph.to_string()))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center),
+        )
+        .on_press(RendererMessage::InsertText(glyph.to_string()))
+        .class(if recents.is_favorite(glyph) {
+            cosmic::style::Button::Suggested
+        } else {
+            cosmic::style::Button::Standard
+        })
+        .width(Length::Fill)
+        .height(Length::Fixed(height));
+
+        content = content.push(entry);
+    }
+
+    // Pin button for the most recent entry
+    if let Some(latest) = recents.recent.first() {
+        let pin_label = if recents.is_favorite(latest) { "★" } else { "☆" };
+        let pin = button::custom(
+            container(widget::text::title4(pin_label))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center),
+        )
+        .on_press(RendererMessage::ToggleFavoriteEmoji(latest.clone()))
+        .class(cosmic::style::Button::Text)
+        .width(Length::Fixed(height))
+        .height(Length::Fixed(height));
+
+        content = content.push(pin);
+    }
+
+    container(content)
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .into()
+}
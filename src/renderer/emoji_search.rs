@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Emoji search field rendering for the keyboard layout renderer.
+//!
+//! This module renders the `emoji_search` widget: an on-keyboard search
+//! field followed by a strip of matching emoji. The field is not an iced
+//! text input; its text lives in the renderer's [`InputFocus`] buffer and is
+//! edited by routing key presses to it, so searching works without the
+//! keyboard surface ever taking keyboard focus from the client.
+//!
+//! [`InputFocus`]: crate::input::InputFocus
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, button, container};
+use cosmic::Element;
+
+use crate::emoji;
+use crate::fl;
+use crate::layout::Widget;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::sizing::resolve_sizing;
+use crate::renderer::state::KeyboardRenderer;
+
+/// Widget type name for the emoji search field in layout files.
+pub const EMOJI_SEARCH_WIDGET: &str = "emoji_search";
+
+/// Maximum number of results shown next to the search field.
+pub const EMOJI_SEARCH_MAX_RESULTS: usize = 8;
+
+/// Share of the widget width taken by the search field itself.
+const FIELD_WIDTH_RATIO: f32 = 0.35;
+
+/// Renders the emoji search widget.
+///
+/// Tapping the field focuses it (emits `FocusTextField`); tapping it again
+/// while focused releases focus (emits `BlurTextField`). Tapping a result
+/// emits `InsertText` with the emoji.
+///
+/// # Arguments
+///
+/// * `widget` - The widget definition from the layout
+/// * `state` - The keyboard renderer state (for focus and buffer)
+/// * `base_unit` - The calculated base unit for relative sizing
+/// * `scale` - HDPI scale factor for pixel sizing
+///
+/// # Returns
+///
+/// An Element containing the search field and result strip.
+pub fn render_emoji_search<'a>(
+    widget: &Widget,
+    state: &KeyboardRenderer,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let width = resolve_sizing(&widget.width, base_unit, scale);
+    let height = resolve_sizing(&widget.height, base_unit, scale);
+
+    let focused = state.focus.is_focused(EMOJI_SEARCH_WIDGET);
+    let query = state.focus.buffer(EMOJI_SEARCH_WIDGET);
+
+    // Field label: the query with a caret while focused, a hint otherwise
+    let label = if focused {
+        format!("{}|", query)
+    } else {
+        fl!("emoji-search-placeholder")
+    };
+
+    let field_message = if focused {
+        RendererMessage::BlurTextField
+    } else {
+        RendererMessage::FocusTextField(EMOJI_SEARCH_WIDGET.to_string())
+    };
+
+    let field = button::custom(
+        container(widget::text::body(label))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_y(Alignment::Center),
+    )
+    .on_press(field_message)
+    .class(if focused {
+        cosmic::style::Button::Suggested
+    } else {
+        cosmic::style::Button::Standard
+    })
+    .width(Length::Fixed(width * FIELD_WIDTH_RATIO))
+    .height(Length::Fixed(height));
+
+    let mut content = widget::row::row().spacing(4).push(field);
+
+    // Before anything is typed, offer recently used emoji first
+    let results: Vec<&str> = if query.trim().is_empty() && !state.emoji_recents.is_empty() {
+        state.emoji_recents.strip(EMOJI_SEARCH_MAX_RESULTS)
+    } else {
+        emoji::search(query, EMOJI_SEARCH_MAX_RESULTS)
+            .into_iter()
+            .map(|result| result.glyph)
+            .collect()
+    };
+
+    for glyph in results {
+        let result_button = button::custom(
+            container(widget::text::title4(glyph.to_string()))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center),
+        )
+        .on_press(RendererMessage::InsertText(glyph.to_string()))
+        .class(cosmic::style::Button::Standard)
+        .width(Length::Fill)
+        .height(Length::Fixed(height));
+
+        content = content.push(result_button);
+    }
+
+    container(content)
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .into()
+}
+
+/// Returns the emoji that submitting `query` inserts (the best match).
+#[must_use]
+pub fn best_match(query: &str) -> Option<&'static str> {
+    emoji::search(query, 1).first().map(|emoji| emoji.glyph)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Submitting a query picks the top-ranked emoji.
+    #[test]
+    fn test_best_match() {
+        assert_eq!(best_match("fire"), Some("🔥"));
+        assert_eq!(best_match("qqqqzzzz"), None);
+    }
+}
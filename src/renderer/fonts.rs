@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Font fallback for key labels.
+//!
+//! Text shaping falls back to some installed font for characters the UI font
+//! lacks, but which one is left to chance, and symbols no font it tries
+//! covers are drawn as boxes. Key labels instead go through an explicit
+//! chain: the system UI font, then [`LABEL_FALLBACK_FONTS`] (Noto Sans
+//! Symbols, Noto Sans Symbols 2, then the emoji font). Each label is drawn
+//! in the first font of the chain that has a glyph for every one of its
+//! characters ([`label_font`]).
+//!
+//! Finding out which font covers a character reads the installed fonts,
+//! which takes a moment the first time. That happens when a layout is
+//! loaded, off the UI thread: [`missing_glyph_warnings`] lists, per panel,
+//! the labels no font of the chain covers, so layout authors know which
+//! font to install or which symbol to replace. Until then, and for plain
+//! ASCII labels, [`label_font`] returns the UI font without looking.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, OnceLock};
+
+use cosmic::iced::advanced::graphics::text::cosmic_text::{self, fontdb};
+use cosmic::iced::font::Family;
+use cosmic::iced::Font;
+
+use super::key::is_icon_name;
+use crate::layout::{Cell, Layout, Severity, ValidationIssue};
+
+/// Fonts tried for key labels after the system UI font, in order.
+pub const LABEL_FALLBACK_FONTS: [&str; 3] = [
+    "Noto Sans Symbols",
+    "Noto Sans Symbols 2",
+    "Noto Color Emoji",
+];
+
+/// Returns the fonts tried for key labels, in order.
+#[must_use]
+pub fn label_font_chain() -> Vec<Font> {
+    std::iter::once(cosmic::font::default())
+        .chain(LABEL_FALLBACK_FONTS.into_iter().map(Font::with_name))
+        .collect()
+}
+
+/// Returns `true` if a character is drawn with a glyph of its own.
+///
+/// Spaces, control characters, joiners and variation selectors only shape
+/// the characters around them, and fonts often have no glyph for them.
+fn needs_glyph(c: char) -> bool {
+    !c.is_whitespace()
+        && !c.is_control()
+        && !matches!(c, '\u{200B}'..='\u{200D}' | '\u{FE00}'..='\u{FE0F}')
+}
+
+/// Returns the index of the first of `fonts` fonts that covers every
+/// character of `label`, according to `covers(font, character)`.
+fn first_covering(
+    label: &str,
+    fonts: usize,
+    mut covers: impl FnMut(usize, char) -> bool,
+) -> Option<usize> {
+    (0..fonts).find(|&font| {
+        label
+            .chars()
+            .filter(|&c| needs_glyph(c))
+            .all(|c| covers(font, c))
+    })
+}
+
+/// Which fonts of the chain have a glyph for which characters.
+struct Coverage {
+    fonts: cosmic_text::FontSystem,
+    /// Face of each font of the chain, if it is installed.
+    faces: Vec<Option<fontdb::ID>>,
+    /// Answers so far, by font index and character.
+    cache: HashMap<(usize, char), bool>,
+}
+
+impl Coverage {
+    /// Reads the installed fonts (slow) and finds the faces of the chain.
+    fn load(chain: &[Font]) -> Self {
+        let fonts = cosmic_text::FontSystem::new();
+        let faces = chain
+            .iter()
+            .map(|font| {
+                let family = match font.family {
+                    Family::Name(name) => fontdb::Family::Name(name),
+                    Family::Serif => fontdb::Family::Serif,
+                    Family::SansSerif => fontdb::Family::SansSerif,
+                    Family::Cursive => fontdb::Family::Cursive,
+                    Family::Fantasy => fontdb::Family::Fantasy,
+                    Family::Monospace => fontdb::Family::Monospace,
+                };
+                fonts.db().query(&fontdb::Query {
+                    families: &[family],
+                    ..fontdb::Query::default()
+                })
+            })
+            .collect();
+        Self {
+            fonts,
+            faces,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if no font of the chain is installed, so nothing can
+    /// be told about the labels.
+    fn is_empty(&self) -> bool {
+        self.faces.iter().all(Option::is_none)
+    }
+
+    /// Returns `true` if the font at `index` of the chain has a glyph for `c`.
+    fn covers(&mut self, index: usize, c: char) -> bool {
+        if let Some(&covered) = self.cache.get(&(index, c)) {
+            return covered;
+        }
+        let covered = self.faces[index]
+            .and_then(|id| self.fonts.get_font(id))
+            .is_some_and(|font| font.rustybuzz().glyph_index(c).is_some());
+        self.cache.insert((index, c), covered);
+        covered
+    }
+
+    /// Returns the index of the first font of the chain covering `label`.
+    fn pick(&mut self, label: &str) -> Option<usize> {
+        let fonts = self.faces.len();
+        first_covering(label, fonts, |index, c| self.covers(index, c))
+    }
+}
+
+/// The chain and its coverage, once the installed fonts were read.
+struct LabelFonts {
+    chain: Vec<Font>,
+    coverage: Mutex<Coverage>,
+}
+
+static LABEL_FONTS: OnceLock<LabelFonts> = OnceLock::new();
+
+/// Returns the label fonts, reading the installed fonts the first time.
+fn label_fonts() -> &'static LabelFonts {
+    LABEL_FONTS.get_or_init(|| {
+        let chain = label_font_chain();
+        let coverage = Mutex::new(Coverage::load(&chain));
+        LabelFonts { chain, coverage }
+    })
+}
+
+/// Returns the font to draw a key label in: the first font of the chain
+/// with glyphs for all its characters, or the UI font.
+///
+/// Never blocks the UI: before the installed fonts were read, for ASCII
+/// labels, or while a layout is being checked, it returns the UI font.
+#[must_use]
+pub fn label_font(label: &str) -> Font {
+    let default = cosmic::font::default();
+    if label.is_ascii() {
+        return default;
+    }
+    let Some(fonts) = LABEL_FONTS.get() else {
+        return default;
+    };
+    let Ok(mut coverage) = fonts.coverage.try_lock() else {
+        return default;
+    };
+    coverage
+        .pick(label)
+        .map_or(default, |index| fonts.chain[index])
+}
+
+/// Returns a warning per panel listing the key labels no font of the chain
+/// has glyphs for.
+///
+/// Reads the installed fonts the first time, so call it off the UI thread.
+#[must_use]
+pub fn missing_glyph_warnings(layout: &Layout) -> Vec<ValidationIssue> {
+    let fonts = label_fonts();
+    let mut coverage = fonts.coverage.lock().unwrap_or_else(|e| e.into_inner());
+    if coverage.is_empty() {
+        return Vec::new();
+    }
+
+    let mut warnings = Vec::new();
+    for (panel_id, panel) in &layout.panels {
+        let labels = panel
+            .rows
+            .iter()
+            .flat_map(|row| &row.cells)
+            .filter_map(|cell| match cell {
+                Cell::Key(key) if !is_icon_name(&key.label.to_lowercase()) => {
+                    Some(key.label.as_str())
+                }
+                _ => None,
+            });
+        let missing = missing_labels(labels, |label| coverage.pick(label).is_some());
+        if missing.is_empty() {
+            continue;
+        }
+        warnings.push(
+            ValidationIssue::new(
+                Severity::Warning,
+                format!("No label font has glyphs for: {}", missing.join(" ")),
+                format!("panels[{}]", panel_id),
+            )
+            .with_suggestion(format!(
+                "Install one of {} or use other labels",
+                LABEL_FALLBACK_FONTS.join(", ")
+            )),
+        );
+    }
+    warnings
+}
+
+/// Returns the distinct labels `covered` rejects, sorted.
+fn missing_labels<'a>(
+    labels: impl IntoIterator<Item = &'a str>,
+    mut covered: impl FnMut(&str) -> bool,
+) -> Vec<&'a str> {
+    let missing: BTreeSet<&str> = labels.into_iter().filter(|label| !covered(label)).collect();
+    missing.into_iter().collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: A label goes to the first font covering all its characters,
+    /// ignoring spaces, joiners and variation selectors.
+    #[test]
+    fn test_first_covering() {
+        // Font 0 has letters, font 1 symbols, font 2 both
+        let covers = |font: usize, c: char| match font {
+            0 => c.is_alphabetic(),
+            1 => !c.is_alphabetic(),
+            _ => true,
+        };
+        assert_eq!(first_covering("é", 3, covers), Some(0));
+        assert_eq!(first_covering("⌘ ⌥", 3, covers), Some(1));
+        assert_eq!(first_covering("☎\u{FE0F}", 3, covers), Some(1));
+        assert_eq!(first_covering("a⌘", 3, covers), Some(2));
+        assert_eq!(first_covering("a⌘", 2, covers), None);
+    }
+
+    /// Test: Missing labels are listed once each, in order.
+    #[test]
+    fn test_missing_labels() {
+        let labels = ["a", "🯅", "⌘", "🯅", "☙"];
+        let missing = missing_labels(labels, |label| label.is_ascii() || label == "⌘");
+        assert_eq!(missing, ["☙", "🯅"]);
+    }
+}
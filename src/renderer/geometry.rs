@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Offline geometry of a rendered panel.
+//!
+//! Computes where each cell of a panel lands on a surface of a given size,
+//! the same way the widget tree built by
+//! [`render_panel`](super::panel::render_panel) lays it out: rows are
+//! stacked from the top with the panel margin between them, each row is as
+//! tall as its tallest cell, cells are top-aligned within their row, and
+//! the block of rows is centered horizontally inside the panel padding.
+//!
+//! Nothing here needs a window or a GPU, so tools can draw a layout without
+//! running the keyboard (see the `cosboard-preview` binary).
+
+use crate::layout::{Cell, Panel};
+use crate::renderer::debug_overlay::cell_size;
+use crate::renderer::row::calculate_row_width;
+use crate::renderer::sizing::{calculate_base_unit, calculate_total_height_units};
+
+/// Default padding in pixels if not specified in the layout.
+pub const DEFAULT_PADDING: f32 = 8.0;
+
+/// Default margin between cells in pixels if not specified in the layout.
+pub const DEFAULT_MARGIN: f32 = 4.0;
+
+/// A cell and the rectangle it occupies on the surface, in pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellBounds<'a> {
+    /// The cell from the layout.
+    pub cell: &'a Cell,
+    /// Distance of the left edge from the left of the surface.
+    pub x: f32,
+    /// Distance of the top edge from the top of the surface.
+    pub y: f32,
+    /// Width of the cell.
+    pub width: f32,
+    /// Height of the cell.
+    pub height: f32,
+}
+
+/// Placement of every cell of a panel on a surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanelGeometry<'a> {
+    /// Base unit the relative sizes were resolved with.
+    pub base_unit: f32,
+    /// Cells in row order.
+    pub cells: Vec<CellBounds<'a>>,
+}
+
+/// Calculates the maximum row width across all rows in a panel.
+///
+/// This is used to determine the base unit for proportional sizing. A panel
+/// that declares its `units` is at least that wide.
+///
+/// # Returns
+///
+/// The maximum width in relative units, or 10 as a fallback minimum.
+pub fn calculate_max_row_width(panel: &Panel) -> f32 {
+    panel
+        .rows
+        .iter()
+        .map(calculate_row_width)
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or(10.0)
+        .max(panel.units.unwrap_or(0.0))
+        .max(1.0) // Ensure at least 1 to avoid division by zero
+}
+
+/// Returns the number of rows of `panel` shown at once.
+///
+/// All rows, unless the panel sets fewer `visible_rows` and scrolls.
+#[must_use]
+pub fn visible_row_count(panel: &Panel) -> usize {
+    match panel.visible_rows {
+        Some(visible) if visible > 0 => visible.min(panel.rows.len()),
+        _ => panel.rows.len(),
+    }
+}
+
+/// Returns `true` if `panel` has more rows than it shows at once.
+#[must_use]
+pub fn is_scrolling(panel: &Panel) -> bool {
+    visible_row_count(panel) < panel.rows.len()
+}
+
+/// Calculates the base unit of a panel shown on a surface of the given size.
+///
+/// The panel padding and the margins between rows are taken off the
+/// surface before the rows are fitted into what remains. A scrolling panel
+/// is sized so that its first `visible_rows` rows fit.
+#[must_use]
+pub fn panel_base_unit(panel: &Panel, surface_width: f32, surface_height: f32) -> f32 {
+    let padding = panel.padding.unwrap_or(DEFAULT_PADDING);
+    let margin = panel.margin.unwrap_or(DEFAULT_MARGIN);
+    let visible_rows = &panel.rows[..visible_row_count(panel)];
+
+    let available_width = surface_width - (padding * 2.0);
+    let available_height = surface_height - (padding * 2.0);
+    let margin_height = margin * (visible_rows.len().saturating_sub(1)) as f32;
+
+    calculate_base_unit(
+        available_width,
+        available_height - margin_height,
+        calculate_max_row_width(panel) as usize,
+        calculate_total_height_units(visible_rows),
+    )
+}
+
+/// Returns the surface height at which `panel` gets a base unit of
+/// `base_unit`, as long as its width allows it.
+///
+/// The reverse of [`panel_base_unit`] for the height: the visible rows at
+/// that unit, plus the panel padding and the margins between rows.
+#[must_use]
+pub fn panel_height_for_unit(panel: &Panel, base_unit: f32) -> f32 {
+    let padding = panel.padding.unwrap_or(DEFAULT_PADDING);
+    let margin = panel.margin.unwrap_or(DEFAULT_MARGIN);
+    let visible_rows = &panel.rows[..visible_row_count(panel)];
+    let margin_height = margin * (visible_rows.len().saturating_sub(1)) as f32;
+
+    base_unit * calculate_total_height_units(visible_rows) + margin_height + padding * 2.0
+}
+
+/// Returns the height of a surface `surface_width` wide on which `panel`
+/// gets a base unit of `key_height`, or as close to it as the width allows.
+///
+/// Keys are never wider than the width allows, so a height beyond this one
+/// would only add space around the rows.
+#[must_use]
+pub fn fitted_panel_height(panel: &Panel, surface_width: f32, key_height: f32) -> f32 {
+    let padding = panel.padding.unwrap_or(DEFAULT_PADDING);
+    let width_unit = (surface_width - padding * 2.0) / calculate_max_row_width(panel);
+    panel_height_for_unit(panel, key_height.min(width_unit).max(1.0))
+}
+
+/// Returns the height in pixels of each row of `panel` (its tallest cell).
+#[must_use]
+pub fn row_heights(panel: &Panel, base_unit: f32, scale: f32) -> Vec<f32> {
+    panel
+        .rows
+        .iter()
+        .map(|row| {
+            row.cells
+                .iter()
+                .map(|cell| cell_size(cell, base_unit, scale).1)
+                .fold(0.0, f32::max)
+        })
+        .collect()
+}
+
+/// Places every cell of `panel` on a surface of the given size.
+///
+/// `scale` is the HDPI factor applied to pixel sizes, as passed to
+/// [`render_panel`](super::panel::render_panel).
+#[must_use]
+pub fn panel_geometry(
+    panel: &Panel,
+    surface_width: f32,
+    surface_height: f32,
+    scale: f32,
+) -> PanelGeometry<'_> {
+    let padding = panel.padding.unwrap_or(DEFAULT_PADDING);
+    let margin = panel.margin.unwrap_or(DEFAULT_MARGIN);
+    let base_unit = panel_base_unit(panel, surface_width, surface_height);
+
+    // Size every row first: the column is as wide as its widest row
+    let rows: Vec<(Vec<(f32, f32)>, f32, f32)> = panel
+        .rows
+        .iter()
+        .map(|row| {
+            let sizes: Vec<(f32, f32)> = row
+                .cells
+                .iter()
+                .map(|cell| cell_size(cell, base_unit, scale))
+                .collect();
+            let width = sizes.iter().map(|(width, _)| width).sum::<f32>()
+                + margin * sizes.len().saturating_sub(1) as f32;
+            let height = sizes.iter().map(|(_, height)| *height).fold(0.0, f32::max);
+            (sizes, width, height)
+        })
+        .collect();
+    let column_width = rows.iter().map(|(_, width, _)| *width).fold(0.0, f32::max);
+    let left = padding + (surface_width - padding * 2.0 - column_width) / 2.0;
+
+    let mut cells = Vec::new();
+    let mut y = padding;
+    for (row, (sizes, _, row_height)) in panel.rows.iter().zip(&rows) {
+        let mut x = left;
+        for (cell, (width, height)) in row.cells.iter().zip(sizes) {
+            cells.push(CellBounds {
+                cell,
+                x,
+                y,
+                width: *width,
+                height: *height,
+            });
+            x += width + margin;
+        }
+        y += row_height + margin;
+    }
+
+    PanelGeometry { base_unit, cells }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Key, Row, Sizing};
+
+    fn key(label: &str, width: f32) -> Cell {
+        Cell::Key(Key {
+            label: label.to_string(),
+            width: Sizing::Relative(width),
+            ..Key::default()
+        })
+    }
+
+    /// Test: Rows stack from the top and are centered as one block.
+    #[test]
+    fn test_panel_geometry() {
+        let panel = Panel {
+            id: "main".to_string(),
+            padding: Some(10.0),
+            margin: Some(5.0),
+            rows: vec![
+                Row {
+                    cells: vec![key("a", 1.0), key("b", 1.0), key("c", 2.0)],
+                },
+                Row {
+                    cells: vec![key("space", 2.0)],
+                },
+            ],
+            ..Panel::default()
+        };
+
+        // Height bound: (130 - 20 - 5) / 2 rows = 52.5
+        let geometry = panel_geometry(&panel, 1000.0, 130.0, 1.0);
+        assert_eq!(geometry.base_unit, 52.5);
+        assert_eq!(geometry.cells.len(), 4);
+
+        // Widest row: 4 units and 2 margins = 220, centered in 980
+        let a = &geometry.cells[0];
+        assert_eq!((a.x, a.y, a.width, a.height), (390.0, 10.0, 52.5, 52.5));
+        let c = &geometry.cells[2];
+        assert_eq!((c.x, c.width), (390.0 + 2.0 * 57.5, 105.0));
+        let space = &geometry.cells[3];
+        assert_eq!((space.x, space.y), (390.0, 67.5));
+    }
+
+    /// Test: A scrolling panel is sized for its visible rows only.
+    #[test]
+    fn test_scrolling_panel_base_unit() {
+        let row = Row {
+            cells: vec![key("a", 1.0)],
+        };
+        let mut panel = Panel {
+            id: "emoji".to_string(),
+            padding: Some(10.0),
+            margin: Some(5.0),
+            rows: vec![row; 100],
+            ..Panel::default()
+        };
+        assert!(!is_scrolling(&panel));
+
+        // (130 - 20 - 5) / 2 visible rows = 52.5
+        panel.visible_rows = Some(2);
+        assert!(is_scrolling(&panel));
+        assert_eq!(panel_base_unit(&panel, 1000.0, 130.0), 52.5);
+        assert_eq!(panel_height_for_unit(&panel, 52.5), 130.0);
+        assert_eq!(fitted_panel_height(&panel, 1000.0, 52.5), 130.0);
+        // 40 px inside the padding: the width limits the unit to 40
+        assert_eq!(fitted_panel_height(&panel, 60.0, 52.5), 105.0);
+        assert_eq!(row_heights(&panel, 52.5, 1.0), vec![52.5; 100]);
+
+        panel.visible_rows = Some(0);
+        assert_eq!(visible_row_count(&panel), 100);
+    }
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Graceful handling of malformed renderer state.
+//!
+//! The view is rebuilt after every update, so a bug that leaves the renderer
+//! in an unexpected state (e.g. a panel that vanished in a layout
+//! hot-reload) would otherwise panic on every frame and take the applet, and
+//! with it the user's only keyboard, down. [`render_guarded`] catches such
+//! panics while the keyboard widgets are built and draws an error panel
+//! instead; [`KeyboardRenderer::repair_invariants`] fixes the state itself
+//! and tells the user with a toast.
+//!
+//! [`KeyboardRenderer::repair_invariants`]: crate::renderer::KeyboardRenderer::repair_invariants
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, container};
+use cosmic::Element;
+
+use crate::renderer::message::RendererMessage;
+
+/// Returns the message a panic was raised with.
+#[must_use]
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| (*message).to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Renders a panel showing an error instead of the keyboard.
+pub fn render_error_panel<'a>(message: impl Into<String>) -> Element<'a, RendererMessage> {
+    container(widget::text::body(message.into()))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Alignment::Center)
+        .align_y(Alignment::Center)
+        .class(cosmic::style::Container::Background)
+        .into()
+}
+
+/// Builds keyboard widgets, rendering an error panel if building panics.
+pub fn render_guarded<'a>(
+    build: impl FnOnce() -> Element<'a, RendererMessage>,
+) -> Element<'a, RendererMessage> {
+    match panic::catch_unwind(AssertUnwindSafe(build)) {
+        Ok(element) => element,
+        Err(payload) => {
+            let reason = panic_message(payload.as_ref());
+            tracing::error!("Rendering the keyboard failed: {}", reason);
+            render_error_panel(format!("The keyboard could not be drawn: {}", reason))
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Panic messages are recovered from both payload types.
+    #[test]
+    fn test_panic_message() {
+        let payload = panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "static");
+
+        let panel = "main";
+        let payload = panic::catch_unwind(|| panic!("panel {} missing", panel)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "panel main missing");
+    }
+}
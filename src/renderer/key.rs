@@ -5,15 +5,37 @@
 //! This module provides functions for rendering individual keyboard keys
 //! using libcosmic/Iced widgets. Keys are rendered as buttons with appropriate
 //! sizing, styling, and label content.
+//!
+//! Active modifier keys are drawn by how their modifier is active: outlined
+//! while one-shot, filled while locked, and pulsing while held down. Caps
+//! Lock keys are filled and framed for as long as Caps Lock is latched, and
+//! letter keys then show their labels in upper case.
+
+use std::time::{Duration, Instant};
 
 use cosmic::iced::{Alignment, Length};
-use cosmic::widget::{self, button, container, icon};
+use cosmic::widget::{self, button, container, icon, mouse_area};
 use cosmic::Element;
 
-use crate::layout::Key;
+use crate::input::ModifierMode;
+use crate::layout::{Key, KeyCode};
+use crate::renderer::drag::{drag_text, draggable};
+use crate::renderer::fonts::label_font;
 use crate::renderer::message::RendererMessage;
 use crate::renderer::sizing::resolve_sizing;
 use crate::renderer::state::KeyboardRenderer;
+use crate::renderer::theme::{heat_color, KeyStyle};
+use crate::stats::heat_level;
+
+/// Time a held modifier key takes to pulse once.
+pub const MODIFIER_PULSE_PERIOD: Duration = Duration::from_millis(1000);
+
+/// Steps a held modifier key's pulse is drawn in, each way, so the key is
+/// only rebuilt when it changes step.
+pub const MODIFIER_PULSE_STEPS: u8 = 8;
+
+/// Fill of a held modifier key at the dimmest step of its pulse.
+const MODIFIER_PULSE_MIN: f32 = 0.3;
 
 /// Icon names that should be rendered with system icons.
 const ICON_NAMES: &[&str] = &[
@@ -41,6 +63,125 @@ const ICON_NAMES: &[&str] = &[
     "input-keyboard-symbolic",
 ];
 
+/// Everything a key's widgets are built from.
+///
+/// Keys are built inside a [`lazy`](cosmic::iced::widget::lazy) widget keyed
+/// on their visual state. When one key is pressed or a sticky modifier
+/// toggles, every other key hashes the same as in the previous view and
+/// reuses its widgets and layout, so only the keys that changed are built
+/// and laid out again. On large layouts this keeps a key press from
+/// rebuilding the whole keyboard in `view()`. It does not limit drawing:
+/// the surface is still redrawn as a whole, every key included.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyVisual {
+    /// Identifier sent with the key's messages.
+    pub identifier: String,
+    /// Label shown on the key (the caps icon while caps-word is active).
+    pub label: String,
+    /// Width in pixels, as `f32` bits so the state can be hashed.
+    pub width_bits: u32,
+    /// Height in pixels, as `f32` bits so the state can be hashed.
+    pub height_bits: u32,
+    /// Whether the key uses the accent style (active sticky modifier,
+    /// caps-word or Caps Lock).
+    pub highlighted: bool,
+    /// How the key's modifier is active, if it is an active modifier key.
+    pub modifier: Option<ModifierMode>,
+    /// Whether the key is a Caps Lock key while Caps Lock is latched.
+    pub caps_lock: bool,
+    /// Step of the pulse of a held modifier key (see
+    /// [`modifier_pulse_step`]), 0 for other keys.
+    pub pulse: u8,
+    /// Whether entering the key while sliding types it (piano mode panels).
+    pub piano: bool,
+    /// Whether entering the key is reported, for boundary haptics.
+    pub crossings: bool,
+    /// Heat level the key is tinted with (see [`heat_level`]), or `None`
+    /// when the heat map is off.
+    pub heat: Option<u8>,
+    /// Colors and corner radius from the user's theme overrides.
+    pub style: KeyStyle,
+    /// Text the key can be dragged out of the keyboard with, if any.
+    pub drag_text: Option<String>,
+}
+
+impl KeyVisual {
+    /// Returns the visual state of `key` in the current renderer state.
+    #[must_use]
+    pub fn new(key: &Key, state: &KeyboardRenderer, base_unit: f32, scale: f32) -> Self {
+        let width = resolve_sizing(&key.width, base_unit, scale);
+        let height = resolve_sizing(&key.height, base_unit, scale);
+
+        // Determine the key identifier for state lookups
+        let identifier = key_identifier(key);
+
+        // Check if this key should show active modifier styling.
+        // Uses the helper function to determine visual state based on:
+        // - For sticky keys (sticky: true): Checks sticky_keys_active HashSet
+        // - For hold keys (sticky: false): Uses native button pressed state (not tracked here)
+        let is_sticky_active = should_show_modifier_active(key, state, &identifier);
+
+        // One-shot, locked and held modifiers are drawn differently
+        let modifier = modifier_key_mode(key, state, &identifier, is_sticky_active);
+        let pulse = match modifier {
+            Some(ModifierMode::Held) => state
+                .held_for(&identifier, Instant::now())
+                .map_or(MODIFIER_PULSE_STEPS, modifier_pulse_step),
+            _ => 0,
+        };
+
+        // While caps-word is active, Shift keys show the caps icon
+        let is_caps_word = should_show_caps_word(key, state);
+
+        // Caps Lock keys stay lit while Caps Lock is latched
+        let caps_lock = should_show_caps_lock(key, state);
+
+        // With Shift (or another modifier) active, keys show what they type;
+        // with Caps Lock, letters show in upper case
+        let layer_label = state
+            .active_layer()
+            .and_then(|layer| key.layer_label(&layer))
+            .or_else(|| {
+                state
+                    .is_caps_lock_active()
+                    .then(|| caps_lock_label(&key.label))
+                    .flatten()
+            });
+
+        // Keys never pressed are tinted with the coldest color
+        let heat = state.heat_map.as_ref().map(|heat_map| {
+            heat_level(heat_map.get(&identifier).copied().unwrap_or(0.0))
+        });
+
+        // Prediction candidates can be dragged out when enabled
+        let drag_text = if state.drag_suggestions {
+            drag_text(key, &identifier)
+        } else {
+            None
+        };
+
+        Self {
+            identifier,
+            label: if is_caps_word {
+                "caps".to_string()
+            } else {
+                layer_label.unwrap_or_else(|| key.label.clone())
+            },
+            width_bits: width.to_bits(),
+            height_bits: height.to_bits(),
+            highlighted: is_sticky_active || is_caps_word || caps_lock,
+            modifier,
+            caps_lock,
+            pulse,
+            piano: state.is_piano_panel(),
+            crossings: state.key_crossings,
+            heat,
+            style: state.key_style,
+            drag_text,
+        }
+    }
+}
+
 /// Renders a single key as an Element.
 ///
 /// The key is rendered as a button with:
@@ -48,6 +189,13 @@ const ICON_NAMES: &[&str] = &[
 /// - Background color based on pressed/sticky state
 /// - Centered label (text or icon)
 ///
+/// Pressing the button emits `KeyPressed` and releasing it emits
+/// `KeyReleased`, so held keys (and long presses) are seen as such. The
+/// button only reports releases over the key, so leaving it emits
+/// `KeyExited`, and the applet releases the key when the finger is lifted
+/// elsewhere.
+/// The button is only rebuilt when the key's [`KeyVisual`] changes.
+///
 /// # Arguments
 ///
 /// * `key` - The key definition from the layout
@@ -64,49 +212,73 @@ pub fn render_key<'a>(
     base_unit: f32,
     scale: f32,
 ) -> Element<'a, RendererMessage> {
-    let width = resolve_sizing(&key.width, base_unit, scale);
-    let height = resolve_sizing(&key.height, base_unit, scale);
-
-    // Determine the key identifier for state lookups
-    let identifier = key
-        .identifier
-        .clone()
-        .unwrap_or_else(|| key.label.clone());
-
-    // Check if this key should show active modifier styling.
-    // Uses the helper function to determine visual state based on:
-    // - For sticky keys (sticky: true): Checks sticky_keys_active HashSet
-    // - For hold keys (sticky: false): Uses native button pressed state (not tracked here)
-    let is_sticky_active = should_show_modifier_active(key, state, &identifier);
+    let visual = KeyVisual::new(key, state, base_unit, scale);
+    cosmic::iced::widget::lazy(visual, build_key).into()
+}
 
+/// Builds the widgets of a key from its visual state.
+fn build_key(visual: &KeyVisual) -> Element<'static, RendererMessage> {
     // Create the label content
-    let label: Element<'a, RendererMessage> = render_label(&key.label);
-
-    // Create styled button
-    let id_for_message = identifier.clone();
+    let label = render_label(&visual.label);
 
     // Choose button style based on state
-    // - Sticky keys that are active use accent/suggested color
+    // - Locked sticky keys and caps-word use accent/suggested color
+    // - Active one-shot modifiers are outlined, held modifiers pulse
+    // - A latched Caps Lock key is filled and framed
     // - All other keys use standard styling (native pressed state handled by Iced button)
-    let button_class = if is_sticky_active {
-        cosmic::style::Button::Suggested // Use accent color for active sticky keys
-    } else {
-        cosmic::style::Button::Standard // Use standard button color for all other states
+    // - Theme overrides replace either with the user's colors
+    let button_class = match visual.modifier {
+        _ if visual.caps_lock => visual.style.caps_lock_class(),
+        Some(mode) => visual
+            .style
+            .modifier_class(mode, modifier_pulse(visual.pulse)),
+        None => visual.style.button_class(visual.highlighted),
     };
 
-    let btn = button::custom(
-        container(label)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .align_x(Alignment::Center)
-            .align_y(Alignment::Center),
-    )
-    .on_press(RendererMessage::KeyPressed(id_for_message))
+    let mut content = container(label)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Alignment::Center)
+        .align_y(Alignment::Center);
+
+    // With the heat map on, tint the key by how often it was pressed
+    if let Some(level) = visual.heat {
+        let tint = heat_color(level);
+        let radius = visual.style.corner_radius.map_or(4.0, |radius| radius as f32);
+        content = content.class(cosmic::style::Container::custom(move |_theme| {
+            container::Style {
+                background: Some(tint.into()),
+                border: cosmic::iced::Border {
+                    radius: radius.into(),
+                    ..cosmic::iced::Border::default()
+                },
+                icon_color: None,
+                text_color: None,
+                shadow: cosmic::iced::Shadow::default(),
+            }
+        }));
+    }
+
+    let btn = button::custom(content)
+    .on_press_down(RendererMessage::KeyPressed(visual.identifier.clone()))
+    .on_press(RendererMessage::KeyReleased(visual.identifier.clone()))
     .class(button_class)
-    .width(Length::Fixed(width))
-    .height(Length::Fixed(height));
+    .width(Length::Fixed(f32::from_bits(visual.width_bits)))
+    .height(Length::Fixed(f32::from_bits(visual.height_bits)));
 
-    btn.into()
+    // On piano mode panels, entering a key while sliding types it; with
+    // boundary haptics, it ticks
+    let mut area = mouse_area(btn).on_exit(RendererMessage::KeyExited(visual.identifier.clone()));
+    if visual.piano || visual.crossings {
+        area = area.on_enter(RendererMessage::KeyEntered(visual.identifier.clone()));
+    }
+    let key: Element<'static, RendererMessage> = area.into();
+
+    // Suggestions can be dropped into other applications
+    match visual.drag_text {
+        Some(ref text) => draggable(key, visual.identifier.clone(), text.clone()),
+        None => key,
+    }
 }
 
 /// Determines if a key should display the active modifier visual state.
@@ -149,11 +321,83 @@ pub fn should_show_modifier_active(key: &Key, state: &KeyboardRenderer, identifi
     false
 }
 
+/// Returns how the modifier of an active modifier key is active.
+///
+/// Sticky keys shown active without a known modifier (keys toggled by
+/// identifier only) count as locked. Hold keys report their mode only while
+/// the renderer tracks their modifier as held.
+#[must_use]
+pub fn modifier_key_mode(
+    key: &Key,
+    state: &KeyboardRenderer,
+    identifier: &str,
+    is_sticky_active: bool,
+) -> Option<ModifierMode> {
+    match state.modifier_key_mode(identifier) {
+        Some(mode) if key.sticky || mode == ModifierMode::Held => Some(mode),
+        None if is_sticky_active => Some(ModifierMode::Locked),
+        _ => None,
+    }
+}
+
+/// Returns the step of the pulse of a modifier key held for `held`, from 0
+/// (dimmest) to [`MODIFIER_PULSE_STEPS`] (brightest).
+///
+/// The pulse starts bright, when the key is pressed, dims over the first
+/// half of [`MODIFIER_PULSE_PERIOD`] and brightens again over the second.
+#[must_use]
+pub fn modifier_pulse_step(held: Duration) -> u8 {
+    let period = MODIFIER_PULSE_PERIOD.as_millis();
+    let phase = (held.as_millis() % period) as f32 / period as f32;
+    let brightness = (2.0 * phase - 1.0).abs();
+    (brightness * f32::from(MODIFIER_PULSE_STEPS)).round() as u8
+}
+
+/// Returns the fill of a held modifier key at a pulse step, from
+/// `MODIFIER_PULSE_MIN` to 1.0.
+fn modifier_pulse(step: u8) -> f32 {
+    let brightness = f32::from(step) / f32::from(MODIFIER_PULSE_STEPS);
+    MODIFIER_PULSE_MIN + (1.0 - MODIFIER_PULSE_MIN) * brightness
+}
+
+/// Determines if a key should display the caps-word visual state.
+///
+/// While caps-word is active, every Shift key is highlighted and shows the
+/// caps icon instead of its label, so it is distinguishable from a plain
+/// one-shot or toggled Shift.
+#[must_use]
+pub fn should_show_caps_word(key: &Key, state: &KeyboardRenderer) -> bool {
+    state.is_caps_word_active()
+        && matches!(&key.code, KeyCode::Keysym(name) if name.to_lowercase().contains("shift"))
+}
+
+/// Determines if a key should display the latched Caps Lock state.
+///
+/// While Caps Lock is latched, every Caps Lock key keeps a style of its own,
+/// whether it is sticky or not, so it is never mistaken for a locked Shift.
+#[must_use]
+pub fn should_show_caps_lock(key: &Key, state: &KeyboardRenderer) -> bool {
+    state.is_caps_lock_active()
+        && matches!(&key.code, KeyCode::Keysym(name) if name.eq_ignore_ascii_case("caps_lock"))
+}
+
+/// Returns the label a key shows while Caps Lock is latched: a single
+/// lower case letter in upper case, `None` for any other label.
+#[must_use]
+pub fn caps_lock_label(label: &str) -> Option<String> {
+    let mut chars = label.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_lowercase() => Some(c.to_uppercase().collect()),
+        _ => None,
+    }
+}
+
 /// Renders a key label as either text or an icon.
 ///
 /// The function detects icon names and renders them using `widget::icon::from_name()`.
 /// Regular text labels are rendered using `widget::text::body()`.
-/// Unicode symbols are rendered directly as text.
+/// Unicode symbols are rendered directly as text, in the first font of the
+/// label font chain that has their glyphs (see [`super::fonts`]).
 ///
 /// # Arguments
 ///
@@ -190,7 +434,9 @@ pub fn render_label<'a>(label: &str) -> Element<'a, RendererMessage> {
     } else {
         // Render as text (includes Unicode symbols)
         // Use to_string() to take ownership of the label
-        widget::text::body(label.to_string()).into()
+        widget::text::body(label.to_string())
+            .font(label_font(label))
+            .into()
     }
 }
 
@@ -255,6 +501,7 @@ pub fn key_identifier(key: &Key) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::ResolvedKeycode;
     use crate::layout::{Cell, KeyCode, Layout, Modifier, Panel, Row, Sizing};
     use std::collections::HashMap;
 
@@ -267,6 +514,9 @@ mod tests {
             padding: Some(5.0),
             margin: Some(2.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![Row {
                 cells: vec![Cell::Key(Key {
                     label: "A".to_string(),
@@ -279,6 +529,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 })],
             }],
         };
@@ -293,6 +546,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         }
@@ -321,6 +577,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: false,
             stickyrelease: true,
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
 
         // This should not panic and should produce a valid Element
@@ -369,6 +628,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: false,
             stickyrelease: true,
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
         assert_eq!(key_identifier(&key_with_id), "key_a");
 
@@ -384,6 +646,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: false,
             stickyrelease: true,
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
         assert_eq!(key_identifier(&key_without_id), "B");
     }
@@ -425,6 +690,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: true, // Sticky mode enabled
             stickyrelease: true, // One-shot behavior
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
 
         // Initially, the modifier should NOT show active styling
@@ -471,6 +739,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: true, // Sticky mode enabled
             stickyrelease: false, // Toggle behavior
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
 
         // Inactive modifier should show normal styling
@@ -491,6 +762,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: false, // Not a sticky key
             stickyrelease: true,
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
 
         // Even if we somehow add "key_a" to sticky_keys_active, it should not show active
@@ -524,6 +798,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: true,
             stickyrelease: false, // Toggle mode
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
 
         // Step 1: Initially inactive
@@ -583,4 +860,196 @@ mod tests {
         );
         assert!(!state.is_sticky_active("shift"));
     }
+
+    /// Test: Double-tapping Shift shows the caps-word state until the word ends
+    #[test]
+    fn test_caps_word_visual_state() {
+        let layout = create_test_layout();
+        let mut state = KeyboardRenderer::new(layout);
+
+        let shift_key = Key {
+            label: "Shift".to_string(),
+            code: KeyCode::Keysym("Shift_L".to_string()),
+            identifier: Some("shift".to_string()),
+            sticky: true,
+            stickyrelease: true,
+            ..Key::default()
+        };
+        let regular_key = Key {
+            label: "A".to_string(),
+            code: KeyCode::Unicode('a'),
+            ..Key::default()
+        };
+
+        assert!(!state.register_shift_tap("shift"));
+        assert!(state.register_shift_tap("shift"));
+        assert!(should_show_caps_word(&shift_key, &state));
+        assert!(!should_show_caps_word(&regular_key, &state));
+        assert!(!should_show_modifier_active(&shift_key, &state, "shift"));
+
+        // Letters get a one-shot Shift
+        state.apply_caps_word(&ResolvedKeycode::Character('a'));
+        assert!(state.is_modifier_active(Modifier::Shift));
+        state.clear_oneshot_modifiers();
+        assert!(!state.is_modifier_active(Modifier::Shift));
+
+        // Space ends the word
+        state.apply_caps_word(&ResolvedKeycode::Character(' '));
+        assert!(!state.is_modifier_active(Modifier::Shift));
+        assert!(!should_show_caps_word(&shift_key, &state));
+    }
+
+    /// Test: Pressing a sticky modifier only changes that key's visual state.
+    #[test]
+    fn test_key_visual_isolates_changes() {
+        let layout = create_test_layout();
+        let mut state = KeyboardRenderer::new(layout);
+
+        let shift_key = Key {
+            label: "Shift".to_string(),
+            code: KeyCode::Keysym("Shift_L".to_string()),
+            identifier: Some("shift".to_string()),
+            sticky: true,
+            stickyrelease: true,
+            ..Key::default()
+        };
+        let regular_key = Key {
+            label: "A".to_string(),
+            code: KeyCode::Unicode('a'),
+            ..Key::default()
+        };
+
+        let shift_before = KeyVisual::new(&shift_key, &state, 40.0, 1.0);
+        let regular_before = KeyVisual::new(&regular_key, &state, 40.0, 1.0);
+
+        state.activate_modifier(Modifier::Shift, true);
+        state.sync_modifier_visual_state(Modifier::Shift, "shift");
+        state.press_key("A".to_string());
+
+        let shift_after = KeyVisual::new(&shift_key, &state, 40.0, 1.0);
+        assert_ne!(shift_after, shift_before);
+        assert!(shift_after.highlighted);
+        // The pressed state is kept by the button widget, not rebuilt
+        assert_eq!(KeyVisual::new(&regular_key, &state, 40.0, 1.0), regular_before);
+
+        // Resizing rebuilds every key
+        assert_ne!(KeyVisual::new(&regular_key, &state, 48.0, 1.0), regular_before);
+
+        // Turning the heat map on tints even keys that were never pressed
+        assert_eq!(regular_before.heat, None);
+        state.heat_map = Some(HashMap::from([("A".to_string(), 1.0)]));
+        assert_eq!(
+            KeyVisual::new(&regular_key, &state, 40.0, 1.0).heat,
+            Some(crate::stats::HEAT_LEVELS)
+        );
+        assert_eq!(KeyVisual::new(&shift_key, &state, 40.0, 1.0).heat, Some(0));
+    }
+
+    /// Test: Keys show their alternative for the layer of the active
+    /// modifiers, and their own label without one.
+    #[test]
+    fn test_key_visual_layer_label() {
+        use crate::layout::{Action, AlternativeKey};
+
+        let mut state = KeyboardRenderer::new(create_test_layout());
+        let mut key = Key {
+            label: "1".to_string(),
+            code: KeyCode::Unicode('1'),
+            ..Key::default()
+        };
+        key.alternatives.insert(
+            AlternativeKey::SingleModifier(Modifier::Shift),
+            Action::Character('!'),
+        );
+        assert_eq!(KeyVisual::new(&key, &state, 40.0, 1.0).label, "1");
+
+        state.activate_modifier(Modifier::Shift, true);
+        assert_eq!(KeyVisual::new(&key, &state, 40.0, 1.0).label, "!");
+
+        state.activate_modifier(Modifier::Ctrl, true);
+        assert_eq!(KeyVisual::new(&key, &state, 40.0, 1.0).label, "1");
+    }
+
+    /// Test: While Caps Lock is latched, Caps Lock keys keep their own style
+    /// and letters show in upper case.
+    #[test]
+    fn test_key_visual_caps_lock() {
+        let mut state = KeyboardRenderer::new(create_test_layout());
+        let caps_key = Key {
+            label: "capslock".to_string(),
+            code: KeyCode::Keysym("Caps_Lock".to_string()),
+            identifier: Some("caps".to_string()),
+            ..Key::default()
+        };
+        let letter_key = Key {
+            label: "a".to_string(),
+            code: KeyCode::Unicode('a'),
+            ..Key::default()
+        };
+        assert!(!KeyVisual::new(&caps_key, &state, 40.0, 1.0).caps_lock);
+
+        state.toggle_caps_lock("caps");
+        let caps = KeyVisual::new(&caps_key, &state, 40.0, 1.0);
+        assert!(caps.caps_lock);
+        assert!(caps.highlighted);
+        assert_eq!(caps.label, "capslock");
+        assert_eq!(KeyVisual::new(&letter_key, &state, 40.0, 1.0).label, "A");
+        assert_eq!(caps_lock_label("1"), None);
+        assert_eq!(caps_lock_label("Tab"), None);
+
+        state.toggle_caps_lock("caps");
+        assert!(!KeyVisual::new(&caps_key, &state, 40.0, 1.0).caps_lock);
+        assert_eq!(KeyVisual::new(&letter_key, &state, 40.0, 1.0).label, "a");
+    }
+
+    /// Test: One-shot, locked and held modifier keys are drawn in their own
+    /// way; only held keys pulse.
+    #[test]
+    fn test_key_visual_modifier_modes() {
+        let layout = create_test_layout();
+        let mut state = KeyboardRenderer::new(layout);
+
+        let modifier_key = |id: &str, keysym: &str, sticky: bool| Key {
+            label: id.to_string(),
+            code: KeyCode::Keysym(keysym.to_string()),
+            identifier: Some(id.to_string()),
+            sticky,
+            stickyrelease: true,
+            ..Key::default()
+        };
+        let ctrl_key = modifier_key("ctrl", "Control_L", true);
+        let alt_key = modifier_key("alt", "Alt_L", false);
+        assert_eq!(KeyVisual::new(&ctrl_key, &state, 40.0, 1.0).modifier, None);
+
+        state.tap_sticky_modifier(Modifier::Ctrl);
+        state.sync_modifier_visual_state(Modifier::Ctrl, "ctrl");
+        let one_shot = KeyVisual::new(&ctrl_key, &state, 40.0, 1.0);
+        assert_eq!(one_shot.modifier, Some(ModifierMode::OneShot));
+        assert_eq!(one_shot.pulse, 0);
+
+        state.tap_sticky_modifier(Modifier::Ctrl);
+        let locked = KeyVisual::new(&ctrl_key, &state, 40.0, 1.0);
+        assert_eq!(locked.modifier, Some(ModifierMode::Locked));
+
+        state.press_key("alt".to_string());
+        state.hold_modifier(Modifier::Alt);
+        state.sync_modifier_visual_state(Modifier::Alt, "alt");
+        let held = KeyVisual::new(&alt_key, &state, 40.0, 1.0);
+        assert_eq!(held.modifier, Some(ModifierMode::Held));
+        assert!(!held.highlighted);
+        assert!(held.pulse > 0);
+    }
+
+    /// Test: A held modifier key pulses from bright to dim and back once
+    /// per period.
+    #[test]
+    fn test_modifier_pulse_step() {
+        let period = MODIFIER_PULSE_PERIOD;
+        assert_eq!(modifier_pulse_step(Duration::ZERO), MODIFIER_PULSE_STEPS);
+        assert_eq!(modifier_pulse_step(period / 2), 0);
+        assert_eq!(modifier_pulse_step(period / 4), MODIFIER_PULSE_STEPS / 2);
+        assert_eq!(modifier_pulse_step(period), MODIFIER_PULSE_STEPS);
+        assert_eq!(modifier_pulse(0), MODIFIER_PULSE_MIN);
+        assert_eq!(modifier_pulse(MODIFIER_PULSE_STEPS), 1.0);
+    }
 }
@@ -6,7 +6,11 @@
 //! for handling key presses, panel switching, toast notifications,
 //! and other interactions.
 
+use crate::actions::AppAction;
+use crate::date_picker::{DateField, DateInsert};
+use crate::layout::Modifier;
 use crate::renderer::state::ToastSeverity;
+use crate::symbols::SymbolCategory;
 
 /// Messages emitted by the keyboard renderer.
 ///
@@ -20,13 +24,25 @@ pub enum RendererMessage {
     /// A key was released. Contains the key identifier.
     KeyReleased(String),
 
+    /// The pointer or finger entered a key on a piano mode panel, or on any
+    /// panel while boundary haptics are on. Contains the key identifier.
+    KeyEntered(String),
+
+    /// The pointer or finger left a key. Contains the key identifier.
+    KeyExited(String),
+
+    /// A key's text started being dragged out of the keyboard, so the press
+    /// is not typed. Contains the key identifier.
+    TextDragged(String),
+
     /// Switch to a different panel. Contains the panel ID.
     SwitchPanel(String),
 
     /// Animation frame tick for panel transitions.
     ///
-    /// This message is emitted at ~60fps (every ~16ms) during panel
-    /// slide animations to update the animation progress.
+    /// This message is emitted once per frame during panel slide
+    /// animations to update the animation progress, at the refresh rate
+    /// of the display the keyboard is shown on.
     AnimationTick,
 
     /// Animation has completed.
@@ -43,6 +59,13 @@ pub enum RendererMessage {
     /// threshold (300ms) has been exceeded.
     LongPressTimerTick,
 
+    /// Periodic timer tick of the stuck key watchdog.
+    ///
+    /// This message is emitted every second while a key is pressed, so keys
+    /// whose release never arrived are released after
+    /// `STUCK_KEY_TIMEOUT_MS`.
+    StuckKeyTimerTick,
+
     /// Dismiss the active popup.
     ///
     /// This message is emitted when the user releases the key or moves
@@ -78,6 +101,54 @@ pub enum RendererMessage {
     /// and auto-dismiss the toast.
     ToastTimerTick,
 
+    /// The button of a toast was pressed. Contains the toast action ID.
+    ToastAction(u64),
+
+    // ========================================================================
+    // Internal Text Field Messages
+    // ========================================================================
+
+    /// Give input focus to an internal text field (e.g. the emoji search).
+    ///
+    /// While an internal field is focused, key presses edit its buffer
+    /// instead of being emitted to the client application.
+    FocusTextField(String),
+
+    /// Release input focus from the focused internal text field.
+    BlurTextField,
+
+    /// Emit text to the client application (e.g. a chosen emoji).
+    InsertText(String),
+
+    /// Pin or unpin an emoji/symbol in the "Recents" strip.
+    ToggleFavoriteEmoji(String),
+
+    /// Show a category tab in the symbols browser.
+    SelectSymbolCategory(SymbolCategory),
+
+    /// Step a field of the date picker up (positive) or down (negative).
+    StepDate(DateField, i32),
+
+    /// Set the date picker back to the current time.
+    ResetDate,
+
+    /// Type the date picker's date, time or both.
+    InsertDate(DateInsert),
+
+    /// A scrolling panel or grid moved. Contains the scroll area ID and the
+    /// new offset from the top in pixels.
+    Scrolled(String, u32),
+
+    /// Dispatch a named action (e.g. a command palette entry).
+    RunAction(AppAction),
+
+    /// The collapsed keyboard bar was tapped outside its expand button.
+    CollapsedBarTapped,
+
+    /// A modifier chip of the status strip was tapped, to clear the
+    /// modifier.
+    ClearModifier(Modifier),
+
     /// No-op message (used for placeholder elements).
     Noop,
 }
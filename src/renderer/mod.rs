@@ -11,17 +11,38 @@
 //! The renderer is organized into several sub-modules:
 //!
 //! - **state**: Core renderer state including `KeyboardRenderer`, `PanelAnimation`,
-//!   and `Toast` types for tracking pressed keys, panel transitions, and notifications.
+//!   `HeightTransition` and `Toast` types for tracking pressed keys, panel transitions,
+//!   and notifications.
 //! - **sizing**: Size calculations for relative and pixel-based sizing with HDPI support.
+//! - **geometry**: Offline placement of a panel's cells, for drawing without widgets.
+//! - **touch_target**: Minimum physical key size, checked against the output's density.
+//! - **dead_zone**: Insets along the keyboard edges where presses are ignored.
+//! - **offscreen**: Panels drawn into images with tiny-skia (`preview` feature).
 //! - **theme**: COSMIC theme integration for consistent keyboard styling.
 //! - **key**: Individual key rendering with label/icon detection.
 //! - **row**: Horizontal row layout for keyboard cells.
 //! - **panel**: Full panel rendering with rows, padding, and animation support.
+//! - **virtualize**: Scrolling panels and grids that only build the rows in view.
 //! - **message**: Renderer message types for interactions.
 //! - **widget_placeholder**: Placeholder rendering for trackpad/autocomplete widgets.
+//! - **emoji_search**: On-keyboard emoji search field with a result strip.
+//! - **emoji_recents**: "Recents" strip of recently used and pinned emoji.
+//! - **symbols_browser**: Category tabs and symbol grid for the built-in symbols panel.
+//! - **date_picker**: Date and time fields with buttons typing the picked date.
+//! - **calculator_display**: Expression display of the built-in calculator panel.
+//! - **scan_button**: Button typing the text of a scanned QR code or barcode (`scan` feature).
+//! - **palette**: Command palette strip drawn above the keyboard.
+//! - **typing_test**: Typing test strip (phrase, typed text, speed) above the keyboard.
 //! - **panel_ref**: Panel reference button rendering for panel switching.
 //! - **popup**: Long press popup rendering for swipe gesture alternatives.
+//! - **drag**: Prediction candidates dragged out of the keyboard as text.
 //! - **toast**: Toast notification rendering for error messages and status updates.
+//! - **emission**: Dot pulsing under the keys whenever a key event is emitted.
+//! - **collapsed_bar**: Thin bar with the layout name and modifiers shown while collapsed.
+//! - **status_strip**: Chips for the latched modifiers and the layout above the keyboard.
+//! - **compose**: Runtime insertion and removal of panels, rows and keys.
+//! - **debug_overlay**: Cell bounding boxes, identifiers, sizes and base unit drawn over the keyboard.
+//! - **guard**: Error panel instead of a crash when building the keyboard widgets panics.
 //!
 //! # Usage
 //!
@@ -67,7 +88,7 @@
 //! Panel transitions are animated with a smooth slide effect:
 //!
 //! ```rust,ignore
-//! use cosboard::renderer::{KeyboardRenderer, render_animated_panels, ANIMATION_FRAME_INTERVAL_MS};
+//! use cosboard::renderer::{KeyboardRenderer, render_animated_panels};
 //!
 //! let mut renderer = KeyboardRenderer::new(layout);
 //!
@@ -80,7 +101,7 @@
 //! // - Using eased progress for smooth visual effect
 //! let element = render_animated_panels(&renderer, 800.0, 300.0, 1.0);
 //!
-//! // Update animation progress (call from AnimationTick subscription)
+//! // Update animation progress (call on every frame, see AnimationTick)
 //! if renderer.update_animation() {
 //!     // Animation completed - new panel is now current
 //! }
@@ -142,6 +163,9 @@
 //! let key_pressed = theme::key_pressed_color(&theme);
 //! ```
 //!
+//! The `theme` configuration entry overrides key colors, the corner radius
+//! and the key gap ([`ThemeOverrides`]).
+//!
 //! # Visual Modifier State Indication
 //!
 //! Modifier keys (Shift, Ctrl, Alt, Super) show visual feedback when active:
@@ -161,6 +185,11 @@
 //! assert!(should_show_modifier_active(&shift_key, &renderer, "shift"));
 //! ```
 //!
+//! How the key is drawn follows [`ModifierMode`](crate::input::ModifierMode):
+//! outlined while one-shot, filled while locked (a double tap on a one-shot
+//! key, see [`KeyboardRenderer::tap_sticky_modifier`]) and pulsing while
+//! held down.
+//!
 //! # Features
 //!
 //! - **Panel Management**: Track current panel, switch between panels with animations
@@ -174,59 +203,151 @@
 //! - **Theme Integration**: Colors adapt to the user's COSMIC theme
 
 // Core modules (Task Groups 1-2)
+pub mod geometry;
 pub mod sizing;
 pub mod state;
 pub mod theme;
+pub mod touch_target;
+pub mod dead_zone;
+
+// Runtime panel composition
+pub mod compose;
 
 // Rendering modules (Task Group 3)
+pub mod fonts;
 pub mod key;
 pub mod message;
 pub mod panel;
 pub mod panel_ref;
 pub mod row;
+pub mod virtualize;
 pub mod widget_placeholder;
 
+// Internal text field widgets
+pub mod emoji_recents;
+pub mod emoji_search;
+pub mod symbols_browser;
+
+// Date and time picker
+pub mod date_picker;
+
+// Calculator display
+pub mod calculator_display;
+
+// "Scan to type" button (`scan` feature)
+#[cfg(feature = "scan")]
+pub mod scan_button;
+
+// Overlay modules
+pub mod palette;
+pub mod typing_test;
+
 // Interactive modules (Task Group 4)
 pub mod popup;
 
+// Text dragged out of the keyboard
+pub mod drag;
+
 // Toast notification module (Task Group 6)
 pub mod toast;
 
+// Key emission indicator
+pub mod emission;
+
+// Collapsed keyboard
+pub mod collapsed_bar;
+
+// Status strip
+pub mod status_strip;
+
+// Layout debugging
+pub mod debug_overlay;
+
+// Gamepad and remote navigation
+pub mod navigation;
+
+// Crash protection
+pub mod guard;
+
+// Software rendering into images
+#[cfg(feature = "preview")]
+pub mod offscreen;
+
 // Re-export public API from state
 pub use state::{
-    KeyboardRenderer, PanelAnimation, Toast, ToastSeverity, ANIMATION_DURATION_MS,
-    ANIMATION_FRAME_INTERVAL_MS, LONG_PRESS_THRESHOLD_MS, LONG_PRESS_TIMER_INTERVAL_MS,
-    TOAST_DURATION_MS, TOAST_TIMER_INTERVAL_MS,
+    HeightTransition, KeyboardRenderer, PanelAnimation, PointerLeavePolicy, Toast, ToastAction,
+    ToastSeverity, ANIMATION_DURATION_MS, LONG_PRESS_THRESHOLD_MS, LONG_PRESS_TIMER_INTERVAL_MS,
+    STUCK_KEY_CHECK_INTERVAL_MS, STUCK_KEY_TIMEOUT_MS, TOAST_ACTION_DURATION_MS, TOAST_DURATION_MS,
+    TOAST_TIMER_INTERVAL_MS,
 };
 
+// Re-export offline geometry
+pub use geometry::{panel_geometry, CellBounds, PanelGeometry};
+#[cfg(feature = "preview")]
+pub use offscreen::{compare_images, render_panel_image, ImageDiff, ImageStyle};
+
 // Re-export sizing functions for convenience
 pub use sizing::{
-    calculate_base_unit, calculate_total_height_units, get_scale_factor, parse_pixels,
-    resolve_sizing,
+    calculate_base_unit, calculate_total_height_units, get_scale_factor, output_scale_factor,
+    parse_pixels, resolve_sizing,
 };
 
 // Re-export theme functions for convenience
 pub use theme::{
-    key_background_color, key_pressed_border_color, key_pressed_color, key_text_color,
-    keyboard_background_color, sticky_active_color, toast_background_color, toast_text_color,
+    focus_ring_color, heat_color, key_background_color, key_pressed_border_color,
+    key_pressed_color, key_text_color, keyboard_background_color, mix_color, sticky_active_color,
+    toast_background_color, toast_text_color, KeyStyle, ThemeOverrides, ONE_SHOT_BORDER_WIDTH,
 };
 
 // Re-export message types
 pub use message::RendererMessage;
 
 // Re-export rendering functions
-pub use key::{is_icon_name, key_identifier, render_key, render_label, should_show_modifier_active};
-pub use panel::{render_animated_panels, render_current_panel, render_panel};
+pub use key::{
+    is_icon_name, key_identifier, modifier_key_mode, modifier_pulse_step, render_key, render_label,
+    should_show_caps_word, should_show_modifier_active, KeyVisual, MODIFIER_PULSE_PERIOD,
+    MODIFIER_PULSE_STEPS,
+};
+pub use panel::{panel_scroll_id, render_animated_panels, render_current_panel, render_panel};
 pub use panel_ref::render_panel_ref_button;
 pub use row::{calculate_row_width, render_cell, render_row};
+pub use virtualize::{render_virtual_rows, row_window, uniform_row_window, RowWindow};
 pub use widget_placeholder::render_widget_placeholder;
+pub use emoji_recents::{render_emoji_recents, EMOJI_RECENTS_WIDGET};
+pub use emoji_search::{render_emoji_search, EMOJI_SEARCH_WIDGET};
+pub use symbols_browser::render_symbols_browser;
+pub use date_picker::render_date_picker;
+pub use calculator_display::render_calculator_display;
+#[cfg(feature = "scan")]
+pub use scan_button::render_scan_button;
+pub use palette::{is_palette_open, render_keyboard_with_palette, render_palette, PALETTE_HEIGHT};
+pub use typing_test::{is_typing_test_active, render_typing_test, TYPING_TEST_HEIGHT};
 
 // Re-export popup functions and constants
 pub use popup::{
-    adjust_popup_position, calculate_popup_position, has_swipe_alternatives, render_popup,
-    PopupPosition, Rectangle, POPUP_CELL_SIZE, POPUP_CELL_SPACING,
+    adjust_popup_position, calculate_popup_position, has_swipe_alternatives, popup_alternative,
+    popup_direction_at, render_popup, render_popup_overlay, ActivePopup, PopupPosition, Rectangle,
+    POPUP_CELL_SIZE, POPUP_CELL_SPACING,
 };
 
+// Re-export render guards
+pub use guard::{panic_message, render_error_panel, render_guarded};
+
+// Re-export debug overlay switches
+pub use debug_overlay::{debug_overlay_from_env, DEBUG_OVERLAY_ENV};
+pub use navigation::{adjacent_panel, move_focus, NavTarget};
+
+// Re-export the emission indicator
+pub use emission::{is_pulsing, pulse_intensity, render_emission_strip, EMISSION_STRIP_HEIGHT};
+
+// Re-export the collapsed bar
+pub use collapsed_bar::{
+    collapsed_status, is_double_tap, render_collapsed_bar, COLLAPSED_BAR_HEIGHT,
+};
+
+// Re-export the status strip
+pub use status_strip::{render_status_strip, status_chips, StatusChip, STATUS_STRIP_HEIGHT};
+
 // Re-export toast functions and constants (Task Group 6)
 pub use toast::{
     render_current_toast, render_keyboard_with_toast, render_toast, TOAST_HEIGHT,
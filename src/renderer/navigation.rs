@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Focus ring for gamepad and remote control navigation.
+//!
+//! With navigation enabled, one key (or panel button) of the shown panel
+//! holds the navigation focus ([`KeyboardRenderer::nav_focus`]) and is
+//! outlined. [`NavCommand::Move`](crate::input::gamepad::NavCommand::Move)
+//! moves the focus to the nearest key in that direction, as laid out on
+//! screen ([`move_focus`]): keys overlapping the focused one across the
+//! direction of travel (the same row for left and right, the keys above or
+//! below for up and down) are preferred, then the closest. At the edge of
+//! the panel the focus stays where it is.
+//!
+//! Panels are cycled in the order of [`panel_order`]: the layout's default
+//! panel first, then the others by ID.
+//!
+//! [`KeyboardRenderer::nav_focus`]: crate::renderer::KeyboardRenderer::nav_focus
+
+use cosmic::iced::widget::stack;
+use cosmic::iced::Length;
+use cosmic::widget::{container, Space};
+use cosmic::Element;
+
+use crate::input::gamepad::NavDirection;
+use crate::layout::{Cell, Layout, Panel};
+use crate::renderer::debug_overlay::cell_size;
+use crate::renderer::geometry::{panel_geometry, CellBounds};
+use crate::renderer::key::key_identifier;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::theme::focus_ring_color;
+
+/// Width of the focus ring in pixels.
+const FOCUS_RING_WIDTH: f32 = 3.0;
+
+/// A cell that can hold the navigation focus.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NavTarget {
+    /// A key, by identifier.
+    Key(String),
+    /// A button switching to a panel, by panel ID.
+    Panel(String),
+}
+
+impl NavTarget {
+    /// Returns the target for `cell`, or `None` if it cannot be focused
+    /// (widgets handle their own input).
+    #[must_use]
+    pub fn of(cell: &Cell) -> Option<Self> {
+        match cell {
+            Cell::Key(key) => Some(Self::Key(key_identifier(key))),
+            Cell::PanelRef(panel_ref) => Some(Self::Panel(panel_ref.panel_id.clone())),
+            Cell::Widget(_) => None,
+        }
+    }
+}
+
+/// Returns the first focusable cell of `panel`, in row order.
+#[must_use]
+pub fn first_target(panel: &Panel) -> Option<NavTarget> {
+    panel
+        .rows
+        .iter()
+        .flat_map(|row| &row.cells)
+        .find_map(NavTarget::of)
+}
+
+/// Returns the target the focus moves to from `from` in `direction`.
+///
+/// The panel is laid out on a surface of the given size. Without a focus,
+/// or if `from` is not on the panel, the first target is focused. At the
+/// edge of the panel, `from` is returned.
+#[must_use]
+pub fn move_focus(
+    panel: &Panel,
+    from: Option<&NavTarget>,
+    direction: NavDirection,
+    surface_width: f32,
+    surface_height: f32,
+) -> Option<NavTarget> {
+    let geometry = panel_geometry(panel, surface_width, surface_height, 1.0);
+    let targets: Vec<(NavTarget, &CellBounds)> = geometry
+        .cells
+        .iter()
+        .filter_map(|bounds| NavTarget::of(bounds.cell).map(|target| (target, bounds)))
+        .collect();
+
+    let Some((_, origin)) = from.and_then(|from| targets.iter().find(|(t, _)| t == from)) else {
+        return first_target(panel);
+    };
+
+    let (ox, oy) = center(origin);
+    let candidates = targets.iter().filter_map(|(target, bounds)| {
+        let (x, y) = center(bounds);
+        // Distance along the direction of travel, and how far apart the
+        // two cells are across it (0 if they overlap)
+        let (along, gap, offset) = match direction {
+            NavDirection::Left => (ox - x, span_gap(origin, bounds, false), (y - oy).abs()),
+            NavDirection::Right => (x - ox, span_gap(origin, bounds, false), (y - oy).abs()),
+            NavDirection::Up => (oy - y, span_gap(origin, bounds, true), (x - ox).abs()),
+            NavDirection::Down => (y - oy, span_gap(origin, bounds, true), (x - ox).abs()),
+        };
+        (along > 0.5).then_some((target, along, gap, offset))
+    });
+
+    // Overlapping cells first, nearest first; then whatever is closest
+    let best = candidates.min_by(|a, b| {
+        let key = |(_, along, gap, offset): &(&NavTarget, f32, f32, f32)| {
+            if *gap <= 0.0 {
+                (0, *along, *offset)
+            } else {
+                (1, along + gap * 2.0, *offset)
+            }
+        };
+        let (a, b) = (key(a), key(b));
+        a.0.cmp(&b.0)
+            .then(a.1.total_cmp(&b.1))
+            .then(a.2.total_cmp(&b.2))
+    });
+
+    best.map(|(target, ..)| target.clone())
+        .or_else(|| from.cloned())
+}
+
+/// Returns the panels of `layout` in the order panel switching cycles
+/// through them: the default panel first, then the others by ID.
+#[must_use]
+pub fn panel_order(layout: &Layout) -> Vec<&str> {
+    let mut others: Vec<&str> = layout
+        .panels
+        .keys()
+        .map(String::as_str)
+        .filter(|id| *id != layout.default_panel_id)
+        .collect();
+    others.sort_unstable();
+
+    let mut order = Vec::with_capacity(others.len() + 1);
+    if layout.panels.contains_key(&layout.default_panel_id) {
+        order.push(layout.default_panel_id.as_str());
+    }
+    order.extend(others);
+    order
+}
+
+/// Returns the panel after (or before) `current` in [`panel_order`],
+/// wrapping around.
+///
+/// Returns `None` if the layout has a single panel.
+#[must_use]
+pub fn adjacent_panel(layout: &Layout, current: &str, forward: bool) -> Option<String> {
+    let order = panel_order(layout);
+    if order.len() < 2 {
+        return None;
+    }
+    let index = order.iter().position(|id| *id == current).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % order.len()
+    } else {
+        (index + order.len() - 1) % order.len()
+    };
+    Some(order[next].to_string())
+}
+
+/// Returns the center of a cell.
+fn center(bounds: &CellBounds) -> (f32, f32) {
+    (
+        bounds.x + bounds.width / 2.0,
+        bounds.y + bounds.height / 2.0,
+    )
+}
+
+/// Returns the gap between two cells across the direction of travel:
+/// horizontally for vertical moves, vertically otherwise. Negative or zero
+/// if they overlap.
+fn span_gap(a: &CellBounds, b: &CellBounds, vertical: bool) -> f32 {
+    let (a_start, a_len, b_start, b_len) = if vertical {
+        (a.x, a.width, b.x, b.width)
+    } else {
+        (a.y, a.height, b.y, b.height)
+    };
+    (b_start - (a_start + a_len)).max(a_start - (b_start + b_len))
+}
+
+/// Outlines `element`, the rendered `cell`, as holding the navigation focus.
+pub fn with_focus_ring<'a>(
+    element: Element<'a, RendererMessage>,
+    cell: &Cell,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let (width, height) = cell_size(cell, base_unit, scale);
+
+    let ring = container(Space::new(Length::Fill, Length::Fill))
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .class(cosmic::style::Container::custom(|theme| container::Style {
+            background: None,
+            border: cosmic::iced::Border {
+                color: focus_ring_color(theme),
+                width: FOCUS_RING_WIDTH,
+                radius: 6.0.into(),
+            },
+            icon_color: None,
+            text_color: None,
+            shadow: cosmic::iced::Shadow::default(),
+        }));
+
+    stack![element, ring].into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Key, PanelRef, Row, Sizing};
+    use std::collections::HashMap;
+
+    fn key(id: &str, width: f32) -> Cell {
+        Cell::Key(Key {
+            label: id.to_string(),
+            identifier: Some(id.to_string()),
+            width: Sizing::Relative(width),
+            ..Key::default()
+        })
+    }
+
+    fn target(id: &str) -> NavTarget {
+        NavTarget::Key(id.to_string())
+    }
+
+    fn panel() -> Panel {
+        Panel {
+            id: "main".to_string(),
+            rows: vec![
+                Row {
+                    cells: vec![key("q", 1.0), key("w", 1.0), key("e", 1.0), key("r", 1.0)],
+                },
+                Row {
+                    cells: vec![
+                        Cell::PanelRef(PanelRef {
+                            panel_id: "symbols".to_string(),
+                            width: Sizing::Relative(1.0),
+                            height: Sizing::Relative(1.0),
+                        }),
+                        key("space", 3.0),
+                    ],
+                },
+            ],
+            ..Panel::default()
+        }
+    }
+
+    /// Test: The focus moves to the nearest key on screen and stops at edges.
+    #[test]
+    fn test_move_focus() {
+        let panel = panel();
+        let step = |from: &str, direction| {
+            move_focus(&panel, Some(&target(from)), direction, 400.0, 200.0)
+        };
+
+        assert_eq!(
+            move_focus(&panel, None, NavDirection::Right, 400.0, 200.0),
+            Some(target("q"))
+        );
+        assert_eq!(step("q", NavDirection::Right), Some(target("w")));
+        assert_eq!(step("r", NavDirection::Right), Some(target("r")));
+        assert_eq!(step("e", NavDirection::Down), Some(target("space")));
+        assert_eq!(
+            step("q", NavDirection::Down),
+            Some(NavTarget::Panel("symbols".to_string()))
+        );
+        // Up from the wide space bar picks the key closest to its center
+        let up = step("space", NavDirection::Up);
+        assert!(matches!(up, Some(NavTarget::Key(ref id)) if id == "e" || id == "r"));
+        assert_eq!(step("q", NavDirection::Up), Some(target("q")));
+    }
+
+    /// Test: Panels cycle from the default panel, in ID order, wrapping.
+    #[test]
+    fn test_adjacent_panel() {
+        let mut panels = HashMap::new();
+        for id in ["main", "symbols", "numpad"] {
+            panels.insert(
+                id.to_string(),
+                Panel {
+                    id: id.to_string(),
+                    ..Panel::default()
+                },
+            );
+        }
+        let layout = Layout {
+            default_panel_id: "main".to_string(),
+            panels,
+            ..Layout::default()
+        };
+
+        assert_eq!(panel_order(&layout), vec!["main", "numpad", "symbols"]);
+        assert_eq!(
+            adjacent_panel(&layout, "main", true).as_deref(),
+            Some("numpad")
+        );
+        assert_eq!(
+            adjacent_panel(&layout, "symbols", true).as_deref(),
+            Some("main")
+        );
+        assert_eq!(
+            adjacent_panel(&layout, "main", false).as_deref(),
+            Some("symbols")
+        );
+    }
+}
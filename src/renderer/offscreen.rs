@@ -0,0 +1,308 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Software rendering of panels into images (`preview` feature).
+//!
+//! Draws a panel with tiny-skia, without a compositor or GPU: cells are
+//! placed by the offline [`geometry`](super::geometry) and filled with the
+//! colors of an [`ImageStyle`], usually the key colors of a COSMIC theme.
+//! Key labels are drawn only when a font is given, so images do not depend
+//! on the fonts installed; icon labels (`backspace`, `shift`, ...) are never
+//! drawn.
+//!
+//! Used by the `cosboard-preview` binary and the visual regression tests,
+//! which draw with a fixed style and font and compare images with
+//! [`compare_images`].
+
+use cosmic::iced::Color;
+use cosmic::Theme;
+use tiny_skia::{FillRule, IntSize, Paint, PathBuilder, Pixmap, PixmapPaint, Transform};
+
+use crate::layout::{Cell, Panel};
+use crate::renderer::geometry::panel_geometry;
+use crate::renderer::key::is_icon_name;
+use crate::renderer::theme::{key_background_color, key_text_color, keyboard_background_color};
+
+/// Text size of key labels (COSMIC body text).
+pub const LABEL_SIZE: f32 = 14.0;
+
+/// Colors and corner radius panels are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageStyle {
+    /// Keyboard background.
+    pub background: Color,
+    /// Key background; widget placeholders are drawn at half its alpha.
+    pub key: Color,
+    /// Key labels.
+    pub text: Color,
+    /// Key corner radius in pixels.
+    pub radius: f32,
+}
+
+impl ImageStyle {
+    /// Returns the key colors and radius of a COSMIC theme.
+    #[must_use]
+    pub fn from_theme(theme: &Theme) -> Self {
+        Self {
+            background: keyboard_background_color(theme),
+            key: key_background_color(theme),
+            text: key_text_color(theme),
+            radius: theme.cosmic().radius_s()[0],
+        }
+    }
+}
+
+/// Draws `panel` on a surface of the given size.
+///
+/// `scale` is the HDPI factor applied to pixel sizes, as passed to
+/// [`render_panel`](super::panel::render_panel). Returns `None` if the
+/// size rounds to zero pixels.
+#[must_use]
+pub fn render_panel_image(
+    panel: &Panel,
+    width: f32,
+    height: f32,
+    scale: f32,
+    style: &ImageStyle,
+    font: Option<&fontdue::Font>,
+) -> Option<Pixmap> {
+    let mut pixmap = Pixmap::new(width.round() as u32, height.round() as u32)?;
+    pixmap.fill(paint_color(style.background));
+
+    for bounds in &panel_geometry(panel, width, height, scale).cells {
+        let (label, alpha) = match bounds.cell {
+            Cell::Key(key) => (key.label.as_str(), 1.0),
+            Cell::PanelRef(panel_ref) => (panel_ref.panel_id.as_str(), 1.0),
+            // Widgets are drawn as their placeholder
+            Cell::Widget(widget) => (widget.widget_type.as_str(), 0.5),
+        };
+
+        let mut paint = Paint::default();
+        paint.set_color(paint_color(Color {
+            a: style.key.a * alpha,
+            ..style.key
+        }));
+        paint.anti_alias = true;
+        if let Some(path) = rounded_rect(
+            bounds.x,
+            bounds.y,
+            bounds.width,
+            bounds.height,
+            style.radius,
+        ) {
+            pixmap.fill_path(
+                &path,
+                &paint,
+                FillRule::Winding,
+                Transform::identity(),
+                None,
+            );
+        }
+
+        if let Some(font) = font {
+            if !is_icon_name(&label.to_lowercase()) {
+                let center = (
+                    bounds.x + bounds.width / 2.0,
+                    bounds.y + bounds.height / 2.0,
+                );
+                draw_label(&mut pixmap, font, label, center, style.text);
+            }
+        }
+    }
+
+    Some(pixmap)
+}
+
+/// Differences between two images of the same size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// Pixels with any channel differing by more than the tolerance.
+    pub differing_pixels: usize,
+    /// Largest difference of a single channel.
+    pub max_delta: u8,
+}
+
+impl ImageDiff {
+    /// Returns `true` if no pixel differs by more than the tolerance.
+    #[must_use]
+    pub fn is_match(&self) -> bool {
+        self.differing_pixels == 0
+    }
+}
+
+/// Compares two images channel by channel.
+///
+/// Channels differing by at most `tolerance` are equal, which absorbs
+/// anti-aliasing changes between rasterizer versions. Returns `None` if the
+/// images differ in size.
+#[must_use]
+pub fn compare_images(actual: &Pixmap, expected: &Pixmap, tolerance: u8) -> Option<ImageDiff> {
+    if actual.width() != expected.width() || actual.height() != expected.height() {
+        return None;
+    }
+
+    let mut diff = ImageDiff {
+        differing_pixels: 0,
+        max_delta: 0,
+    };
+    for (a, b) in actual.data().chunks(4).zip(expected.data().chunks(4)) {
+        let delta = a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or(0);
+        diff.max_delta = diff.max_delta.max(delta);
+        if delta > tolerance {
+            diff.differing_pixels += 1;
+        }
+    }
+    Some(diff)
+}
+
+/// Converts a theme color for drawing.
+fn paint_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba(color.r, color.g, color.b, color.a)
+        .unwrap_or(tiny_skia::Color::BLACK)
+}
+
+/// Builds a rectangle with rounded corners.
+fn rounded_rect(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Option<tiny_skia::Path> {
+    let radius = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+    let (right, bottom) = (x + width, y + height);
+
+    let mut path = PathBuilder::new();
+    path.move_to(x + radius, y);
+    path.line_to(right - radius, y);
+    path.quad_to(right, y, right, y + radius);
+    path.line_to(right, bottom - radius);
+    path.quad_to(right, bottom, right - radius, bottom);
+    path.line_to(x + radius, bottom);
+    path.quad_to(x, bottom, x, bottom - radius);
+    path.line_to(x, y + radius);
+    path.quad_to(x, y, x + radius, y);
+    path.close();
+    path.finish()
+}
+
+/// Draws a single line of text centered on `center`.
+fn draw_label(
+    pixmap: &mut Pixmap,
+    font: &fontdue::Font,
+    text: &str,
+    center: (f32, f32),
+    color: Color,
+) {
+    let glyphs: Vec<_> = text
+        .chars()
+        .map(|c| font.rasterize(c, LABEL_SIZE))
+        .collect();
+    let width: f32 = glyphs
+        .iter()
+        .map(|(metrics, _)| metrics.advance_width)
+        .sum();
+    let (ascent, descent) = font
+        .horizontal_line_metrics(LABEL_SIZE)
+        .map_or((LABEL_SIZE, 0.0), |line| (line.ascent, line.descent));
+
+    // Descent is negative: center the line between ascent and descent
+    let baseline = center.1 + (ascent + descent) / 2.0;
+    let mut pen = center.0 - width / 2.0;
+    for (metrics, coverage) in glyphs {
+        if let Some(glyph) = glyph_pixmap(metrics.width, metrics.height, &coverage, color) {
+            let x = (pen + metrics.xmin as f32).round() as i32;
+            let y = (baseline - metrics.height as f32 - metrics.ymin as f32).round() as i32;
+            pixmap.draw_pixmap(
+                x,
+                y,
+                glyph.as_ref(),
+                &PixmapPaint::default(),
+                Transform::identity(),
+                None,
+            );
+        }
+        pen += metrics.advance_width;
+    }
+}
+
+/// Colors a rasterized glyph's coverage (one byte per pixel).
+fn glyph_pixmap(width: usize, height: usize, coverage: &[u8], color: Color) -> Option<Pixmap> {
+    let size = IntSize::from_wh(width as u32, height as u32)?;
+    let data = coverage
+        .iter()
+        .flat_map(|&coverage| {
+            // Premultiplied RGBA
+            let alpha = f32::from(coverage) / 255.0 * color.a;
+            [color.r * alpha, color.g * alpha, color.b * alpha, alpha]
+                .map(|channel| (channel * 255.0).round() as u8)
+        })
+        .collect();
+    Pixmap::from_vec(data, size)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Key, Row};
+
+    /// Test: Keys are filled with the key color on the keyboard background.
+    #[test]
+    fn test_render_panel_image() {
+        let panel = Panel {
+            id: "main".to_string(),
+            rows: vec![Row {
+                cells: vec![Cell::Key(Key {
+                    label: "a".to_string(),
+                    ..Key::default()
+                })],
+            }],
+            ..Panel::default()
+        };
+        let theme = Theme::dark();
+        let style = ImageStyle::from_theme(&theme);
+        let image = render_panel_image(&panel, 200.0, 100.0, 1.0, &style, None).unwrap();
+        assert_eq!((image.width(), image.height()), (200, 100));
+
+        let corner = image.pixel(0, 0).unwrap();
+        let center = image.pixel(100, 50).unwrap();
+        assert_ne!(corner, center);
+        assert_eq!(
+            corner,
+            paint_color(keyboard_background_color(&theme))
+                .premultiply()
+                .to_color_u8()
+        );
+
+        assert!(render_panel_image(&panel, 0.0, 100.0, 1.0, &style, None).is_none());
+    }
+
+    /// Test: Images match within the tolerance, and not across sizes.
+    #[test]
+    fn test_compare_images() {
+        let mut expected = Pixmap::new(4, 4).unwrap();
+        expected.fill(tiny_skia::Color::from_rgba8(100, 100, 100, 255));
+        let mut actual = expected.clone();
+        assert_eq!(
+            compare_images(&actual, &expected, 0),
+            Some(ImageDiff {
+                differing_pixels: 0,
+                max_delta: 0,
+            })
+        );
+
+        actual.pixels_mut()[0] =
+            tiny_skia::PremultipliedColorU8::from_rgba(102, 100, 100, 255).unwrap();
+        let diff = compare_images(&actual, &expected, 2).unwrap();
+        assert!(diff.is_match());
+        assert_eq!(diff.max_delta, 2);
+        assert!(!compare_images(&actual, &expected, 1).unwrap().is_match());
+
+        assert_eq!(
+            compare_images(&Pixmap::new(2, 2).unwrap(), &expected, 0),
+            None
+        );
+    }
+}
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Command palette overlay rendering.
+//!
+//! The palette is drawn as a strip above the keyboard panel while its query
+//! field has focus, so the keys below stay usable for typing the query. The
+//! entries to show are computed by the caller with [`crate::palette::entries`].
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, button, container};
+use cosmic::Element;
+
+use crate::fl;
+use crate::palette::{PaletteEntry, PALETTE_FIELD, PALETTE_MAX_ENTRIES};
+use crate::renderer::message::RendererMessage;
+use crate::renderer::state::KeyboardRenderer;
+
+/// Height of the palette strip in pixels.
+pub const PALETTE_HEIGHT: f32 = 48.0;
+
+/// Width of the palette query field in pixels.
+const QUERY_FIELD_WIDTH: f32 = 200.0;
+
+/// Returns `true` if the command palette is open.
+#[must_use]
+pub fn is_palette_open(state: &KeyboardRenderer) -> bool {
+    state.focus.is_focused(PALETTE_FIELD)
+}
+
+/// Renders the command palette strip.
+///
+/// The query field closes the palette when tapped (emits `BlurTextField`).
+/// Each entry emits `RunAction`; the first entry is highlighted as
+/// the one Enter runs.
+///
+/// # Arguments
+///
+/// * `state` - The keyboard renderer state (for the query buffer)
+/// * `entries` - The filtered palette entries, best first
+///
+/// # Returns
+///
+/// An Element containing the query field and entry buttons.
+pub fn render_palette<'a>(
+    state: &KeyboardRenderer,
+    entries: &[PaletteEntry],
+) -> Element<'a, RendererMessage> {
+    let query = state.focus.buffer(PALETTE_FIELD);
+
+    let field = button::custom(
+        container(widget::text::body(format!("> {}|", query)))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_y(Alignment::Center),
+    )
+    .on_press(RendererMessage::BlurTextField)
+    .class(cosmic::style::Button::Suggested)
+    .width(Length::Fixed(QUERY_FIELD_WIDTH))
+    .height(Length::Fill);
+
+    let mut content = widget::row::row().spacing(4).push(field);
+
+    if entries.is_empty() {
+        content = content.push(
+            container(widget::text::body(fl!("palette-no-matches")))
+                .height(Length::Fill)
+                .align_y(Alignment::Center),
+        );
+    }
+
+    for (index, entry) in entries.iter().take(PALETTE_MAX_ENTRIES).enumerate() {
+        let entry_button = button::custom(
+            container(widget::text::body(entry.label.clone()))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center),
+        )
+        .on_press(RendererMessage::RunAction(entry.action.clone()))
+        .class(if index == 0 {
+            cosmic::style::Button::Standard
+        } else {
+            cosmic::style::Button::Text
+        })
+        .width(Length::Shrink)
+        .height(Length::Fill);
+
+        content = content.push(entry_button);
+    }
+
+    container(content)
+        .padding(4)
+        .width(Length::Fill)
+        .height(Length::Fixed(PALETTE_HEIGHT))
+        .class(cosmic::style::Container::Background)
+        .into()
+}
+
+/// Combines the keyboard with the palette strip above it.
+///
+/// # Arguments
+///
+/// * `keyboard` - The keyboard element (panel and toast)
+/// * `palette` - The palette element, if the palette is open
+///
+/// # Returns
+///
+/// The keyboard alone, or a column with the palette on top.
+pub fn render_keyboard_with_palette<'a>(
+    keyboard: Element<'a, RendererMessage>,
+    palette: Option<Element<'a, RendererMessage>>,
+) -> Element<'a, RendererMessage> {
+    match palette {
+        Some(palette_element) => widget::column::column()
+            .push(palette_element)
+            .push(container(keyboard).width(Length::Fill).height(Length::Fill))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into(),
+        None => keyboard,
+    }
+}
@@ -17,17 +17,22 @@ use cosmic::iced::{Length, Padding};
 use cosmic::widget::{self, container};
 use cosmic::Element;
 
+use crate::fl;
 use crate::layout::Panel;
+use crate::renderer::debug_overlay::with_panel_overlay;
+use crate::renderer::geometry::{
+    is_scrolling, panel_base_unit, row_heights, DEFAULT_MARGIN, DEFAULT_PADDING,
+};
 use crate::renderer::message::RendererMessage;
-use crate::renderer::row::{calculate_row_width, render_row};
-use crate::renderer::sizing::{calculate_base_unit, calculate_total_height_units};
+use crate::renderer::row::render_row;
 use crate::renderer::state::KeyboardRenderer;
+use crate::renderer::virtualize::{render_virtual_rows, row_window};
 
-/// Default padding in pixels if not specified in the layout.
-const DEFAULT_PADDING: f32 = 8.0;
-
-/// Default margin between cells in pixels if not specified in the layout.
-const DEFAULT_MARGIN: f32 = 4.0;
+/// Returns the scroll area ID of a scrolling panel.
+#[must_use]
+pub fn panel_scroll_id(panel_id: &str) -> String {
+    format!("panel:{}", panel_id)
+}
 
 /// Renders a panel as a vertical layout of rows.
 ///
@@ -37,6 +42,9 @@ const DEFAULT_MARGIN: f32 = 4.0;
 /// - Rows with margin spacing between cells
 /// - Base unit calculated from surface dimensions
 ///
+/// A panel with more rows than its `visible_rows` scrolls vertically, and
+/// only the rows in view are built (see [`crate::renderer::virtualize`]).
+///
 /// # Arguments
 ///
 /// * `panel` - The panel definition from the layout
@@ -59,68 +67,49 @@ pub fn render_panel<'a>(
     let padding = panel.padding.unwrap_or(DEFAULT_PADDING);
     let margin = panel.margin.unwrap_or(DEFAULT_MARGIN);
 
-    // Calculate the maximum row width (in relative units)
-    let max_row_width = calculate_max_row_width(panel);
-
-    // Calculate total height units (sum of max heights per row)
-    let total_height_units = calculate_total_height_units(&panel.rows);
-
-    // Calculate available dimensions after padding
-    let available_width = surface_width - (padding * 2.0);
-    let available_height = surface_height - (padding * 2.0);
-
-    // Account for margin spacing between rows
-    let margin_height = margin * (panel.rows.len().saturating_sub(1)) as f32;
-    let content_height = available_height - margin_height;
-
-    // Calculate base unit from both width and height constraints
-    let base_unit = calculate_base_unit(
-        available_width,
-        content_height,
-        max_row_width as usize,
-        total_height_units,
-    );
+    // Calculate base unit from both width and height constraints, after
+    // padding and the margins between rows
+    let base_unit = panel_base_unit(panel, surface_width, surface_height);
 
     // Build column with rows
-    let mut column = widget::column::column().spacing(margin);
-
-    for row in &panel.rows {
-        let row_element = render_row(row, state, base_unit, scale, margin);
-        column = column.push(row_element);
-    }
+    let column: Element<'a, RendererMessage> = if is_scrolling(panel) {
+        let scroll_id = panel_scroll_id(&panel.id);
+        let window = row_window(
+            &row_heights(panel, base_unit, scale),
+            margin,
+            state.scroll_offset(&scroll_id),
+            surface_height - padding * 2.0,
+        );
+        let rows = panel.rows[window.rows.clone()]
+            .iter()
+            .map(|row| render_row(row, state, base_unit, scale, margin));
+        render_virtual_rows(&scroll_id, &window, rows, margin, Length::Fill)
+    } else {
+        let mut column = widget::column::column().spacing(margin);
+        for row in &panel.rows {
+            let row_element = render_row(row, state, base_unit, scale, margin);
+            column = column.push(row_element);
+        }
+        column.into()
+    };
 
     // Center the column horizontally within the available space
     let centered_column = container(column).center_x(Length::Fill);
 
     // Wrap in container with padding and background
     // Use the standard Background container style which uses the theme's background
-    container(centered_column)
+    let element: Element<'a, RendererMessage> = container(centered_column)
         .width(Length::Fill)
         .height(Length::Fill)
         .padding(Padding::from(padding))
         .class(cosmic::style::Container::Background)
-        .into()
-}
+        .into();
 
-/// Calculates the maximum row width across all rows in a panel.
-///
-/// This is used to determine the base unit for proportional sizing.
-///
-/// # Arguments
-///
-/// * `panel` - The panel to analyze
-///
-/// # Returns
-///
-/// The maximum width in relative units, or 10 as a fallback minimum.
-fn calculate_max_row_width(panel: &Panel) -> f32 {
-    panel
-        .rows
-        .iter()
-        .map(calculate_row_width)
-        .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-        .unwrap_or(10.0)
-        .max(1.0) // Ensure at least 1 to avoid division by zero
+    if state.debug_overlay {
+        with_panel_overlay(element, &panel.id, base_unit, scale)
+    } else {
+        element
+    }
 }
 
 /// Renders the current panel from the keyboard renderer state.
@@ -257,7 +246,7 @@ pub fn render_animated_panels<'a>(
             }
             _ => {
                 // One or both panels not found - render error
-                container(widget::text::body("Animation error: panel not found"))
+                container(widget::text::body(fl!("animation-panel-not-found")))
                     .width(Length::Fill)
                     .height(Length::Fill)
                     .class(cosmic::style::Container::Background)
@@ -329,6 +318,7 @@ pub fn render_panel_with_offset<'a>(
 mod tests {
     use super::*;
     use crate::layout::{Cell, Key, KeyCode, Layout, Panel, Row, Sizing};
+    use crate::renderer::geometry::calculate_max_row_width;
     use std::collections::HashMap;
 
     /// Helper to create a test layout with a panel containing multiple rows
@@ -340,6 +330,9 @@ mod tests {
             padding: Some(8.0),
             margin: Some(4.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![
                 Row {
                     cells: vec![
@@ -354,6 +347,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                         Cell::Key(Key {
                             label: "W".to_string(),
@@ -366,6 +362,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                         Cell::Key(Key {
                             label: "E".to_string(),
@@ -378,6 +377,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                     ],
                 },
@@ -394,6 +396,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                         Cell::Key(Key {
                             label: "S".to_string(),
@@ -406,6 +411,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                         Cell::Key(Key {
                             label: "D".to_string(),
@@ -418,6 +426,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                     ],
                 },
@@ -429,6 +440,9 @@ mod tests {
             padding: Some(8.0),
             margin: Some(4.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![Row {
                 cells: vec![
                     Cell::Key(Key {
@@ -442,6 +456,9 @@ mod tests {
                         alternatives: HashMap::new(),
                         sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                     }),
                     Cell::Key(Key {
                         label: "2".to_string(),
@@ -454,6 +471,9 @@ mod tests {
                         alternatives: HashMap::new(),
                         sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                     }),
                     Cell::Key(Key {
                         label: "3".to_string(),
@@ -466,6 +486,9 @@ mod tests {
                         alternatives: HashMap::new(),
                         sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                     }),
                 ],
             }],
@@ -482,6 +505,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         }
@@ -524,6 +550,9 @@ mod tests {
             padding: None,
             margin: None,
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![
                 Row {
                     cells: vec![Cell::Key(Key {
@@ -537,6 +566,9 @@ mod tests {
                         alternatives: HashMap::new(),
                         sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                     })],
                 },
                 Row {
@@ -552,6 +584,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                         Cell::Key(Key {
                             label: "Space".to_string(),
@@ -564,6 +599,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                         Cell::Key(Key {
                             label: "C".to_string(),
@@ -576,6 +614,9 @@ mod tests {
                             alternatives: HashMap::new(),
                             sticky: false,
                             stickyrelease: true,
+                            after: None,
+                            feedback: None,
+                            post_keys: Vec::new(),
                         }),
                     ],
                 },
@@ -588,6 +629,18 @@ mod tests {
             "Max row width should be 1.0 + 4.0 + 1.0 = 6.0, got {}",
             max_width
         );
+
+        // Declared units widen the panel, but never below its widest row
+        let declared = Panel {
+            units: Some(10.0),
+            ..panel.clone()
+        };
+        assert_eq!(calculate_max_row_width(&declared), 10.0);
+        let declared = Panel {
+            units: Some(4.0),
+            ..panel
+        };
+        assert_eq!(calculate_max_row_width(&declared), 6.0);
     }
 
     /// Test: Empty panel renders without panic
@@ -601,6 +654,9 @@ mod tests {
                 padding: None,
                 margin: None,
                 nesting_depth: 0,
+                piano: false,
+                visible_rows: None,
+                units: None,
                 rows: vec![],
             },
         );
@@ -613,6 +669,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         };
@@ -637,6 +696,9 @@ mod tests {
                 padding: None,
                 margin: None,
                 nesting_depth: 0,
+                piano: false,
+                visible_rows: None,
+                units: None,
                 rows: vec![],
             },
         );
@@ -649,6 +711,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(), // This panel doesn't exist
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         };
@@ -81,6 +81,7 @@ fn format_panel_label(panel_id: &str) -> String {
         "symbols" | "sym" | "symbol" => "#+=".to_string(),
         "emoji" | "emojis" => "\u{1F600}".to_string(), // Grinning face emoji
         "main" | "qwerty" | "default" => "ABC".to_string(),
+        "unicode_symbols" => "∑".to_string(),
         other => {
             // Capitalize first letter
             let mut chars = other.chars();
@@ -14,14 +14,20 @@
 //! 2. Render the popup using `render_popup()`
 //! 3. The popup shows alternative actions for each available swipe direction
 //! 4. Dismiss the popup when the user releases or moves away
+//!
+//! While the popup is open, the pointer or finger slides from the key onto
+//! an alternative ([`popup_direction_at`]), which is highlighted, and
+//! releasing types it; releasing over the key itself types the key.
 
 use std::collections::HashMap;
 
-use cosmic::iced::{Alignment, Length};
+use cosmic::iced::widget::stack;
+use cosmic::iced::{Alignment, Color, Length, Padding};
 use cosmic::widget::{self, container};
 use cosmic::Element;
 
 use crate::layout::{Action, AlternativeKey, Key, SwipeDirection};
+use crate::renderer::fonts::label_font;
 use crate::renderer::message::RendererMessage;
 
 // ============================================================================
@@ -119,6 +125,58 @@ impl Rectangle {
     }
 }
 
+/// The popup of a long-pressed key, while it is shown.
+#[derive(Debug, Clone)]
+pub struct ActivePopup {
+    /// Identifier of the long-pressed key.
+    pub key: String,
+    /// Where the popup is centered on the panel.
+    pub position: PopupPosition,
+    /// Alternative under the pointer, or `None` over the key itself.
+    pub selected: Option<SwipeDirection>,
+}
+
+/// Returns the alternative of a popup at a point of the panel, or `None`
+/// if the point is over the center cell (the key itself).
+///
+/// Points beyond the center cell pick the cell in the direction they lean
+/// most towards, so the finger does not have to land exactly on a cell.
+/// Directions without an alternative pick nothing.
+#[must_use]
+pub fn popup_direction_at(
+    position: &PopupPosition,
+    point: (f32, f32),
+    scale: f32,
+) -> Option<SwipeDirection> {
+    let pitch = (POPUP_CELL_SIZE + POPUP_CELL_SPACING) * scale;
+    let dx = point.0 - position.anchor_x;
+    let dy = point.1 - position.anchor_y;
+    if dx.abs().max(dy.abs()) < pitch / 2.0 {
+        return None;
+    }
+    let direction = if dx.abs() > dy.abs() {
+        if dx < 0.0 {
+            SwipeDirection::Left
+        } else {
+            SwipeDirection::Right
+        }
+    } else if dy < 0.0 {
+        SwipeDirection::Up
+    } else {
+        SwipeDirection::Down
+    };
+    position
+        .available_directions
+        .contains(&direction)
+        .then_some(direction)
+}
+
+/// Returns the alternative a key offers in a direction.
+#[must_use]
+pub fn popup_alternative(key: &Key, direction: SwipeDirection) -> Option<&Action> {
+    key.alternatives.get(&AlternativeKey::Swipe(direction))
+}
+
 // ============================================================================
 // Popup Rendering
 // ============================================================================
@@ -142,6 +200,17 @@ pub fn render_popup<'a>(
     key: &Key,
     position: &PopupPosition,
     scale: f32,
+) -> Element<'a, RendererMessage> {
+    render_popup_with_selection(key, position, None, scale)
+}
+
+/// Renders a popup like [`render_popup`], with the alternative in the
+/// `selected` direction highlighted.
+pub fn render_popup_with_selection<'a>(
+    key: &Key,
+    position: &PopupPosition,
+    selected: Option<SwipeDirection>,
+    scale: f32,
 ) -> Element<'a, RendererMessage> {
     let cell_size = POPUP_CELL_SIZE * scale;
     let spacing = POPUP_CELL_SPACING * scale;
@@ -165,7 +234,7 @@ pub fn render_popup<'a>(
 
     // Top row (Up direction)
     let up_cell = if let Some(action) = swipe_alternatives.get(&SwipeDirection::Up) {
-        render_popup_cell(action, cell_size)
+        render_popup_cell(action, cell_size, selected == Some(SwipeDirection::Up))
     } else {
         render_empty_cell(cell_size)
     };
@@ -179,7 +248,7 @@ pub fn render_popup<'a>(
 
     // Middle row (Left, Center, Right)
     let left_cell = if let Some(action) = swipe_alternatives.get(&SwipeDirection::Left) {
-        render_popup_cell(action, cell_size)
+        render_popup_cell(action, cell_size, selected == Some(SwipeDirection::Left))
     } else {
         render_empty_cell(cell_size)
     };
@@ -187,7 +256,7 @@ pub fn render_popup<'a>(
     let center_cell = render_center_cell(&key.label, cell_size);
 
     let right_cell = if let Some(action) = swipe_alternatives.get(&SwipeDirection::Right) {
-        render_popup_cell(action, cell_size)
+        render_popup_cell(action, cell_size, selected == Some(SwipeDirection::Right))
     } else {
         render_empty_cell(cell_size)
     };
@@ -201,7 +270,7 @@ pub fn render_popup<'a>(
 
     // Bottom row (Down direction)
     let down_cell = if let Some(action) = swipe_alternatives.get(&SwipeDirection::Down) {
-        render_popup_cell(action, cell_size)
+        render_popup_cell(action, cell_size, selected == Some(SwipeDirection::Down))
     } else {
         render_empty_cell(cell_size)
     };
@@ -229,23 +298,79 @@ pub fn render_popup<'a>(
 }
 
 /// Renders a single popup cell with an action label.
-fn render_popup_cell<'a>(action: &Action, size: f32) -> Element<'a, RendererMessage> {
+fn render_popup_cell<'a>(
+    action: &Action,
+    size: f32,
+    selected: bool,
+) -> Element<'a, RendererMessage> {
     let label = action_to_label(action);
 
-    let cell_content = widget::text::body(label);
+    let font = label_font(&label);
+    let cell_content = widget::text::body(label).font(font);
+
+    let class = if selected {
+        // The alternative typed on release uses the accent color
+        cosmic::style::Container::custom(|theme| container::Style {
+            background: Some(cosmic::iced::Background::Color(Color::from(
+                theme.cosmic().accent_color(),
+            ))),
+            border: cosmic::iced::Border {
+                radius: 4.0.into(),
+                ..cosmic::iced::Border::default()
+            },
+            ..Default::default()
+        })
+    } else {
+        cosmic::style::Container::Primary
+    };
 
     container(cell_content)
         .width(Length::Fixed(size))
         .height(Length::Fixed(size))
         .align_x(Alignment::Center)
         .align_y(Alignment::Center)
-        .class(cosmic::style::Container::Primary)
+        .class(class)
         .into()
 }
 
+/// Draws the popup of a long-pressed key over the panel.
+///
+/// # Arguments
+///
+/// * `panel` - The rendered panel
+/// * `key` - The long-pressed key
+/// * `popup` - The popup state, positioned in panel coordinates
+/// * `scale` - HDPI scale factor for sizing
+///
+/// # Returns
+///
+/// The panel with the popup centered on its anchor. The popup captures no
+/// events: selection follows the pointer, see [`popup_direction_at`].
+pub fn render_popup_overlay<'a>(
+    panel: Element<'a, RendererMessage>,
+    key: &Key,
+    popup: &ActivePopup,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let position = &popup.position;
+    let offset = Padding {
+        top: (position.anchor_y - position.popup_height(scale) / 2.0).max(0.0),
+        left: (position.anchor_x - position.popup_width(scale) / 2.0).max(0.0),
+        ..Padding::ZERO
+    };
+    let popup = container(render_popup_with_selection(
+        key,
+        position,
+        popup.selected,
+        scale,
+    ))
+    .padding(offset);
+    stack![panel, popup].into()
+}
+
 /// Renders the center cell showing the original key label.
 fn render_center_cell<'a>(label: &str, size: f32) -> Element<'a, RendererMessage> {
-    let cell_content = widget::text::body(label.to_string());
+    let cell_content = widget::text::body(label.to_string()).font(label_font(label));
 
     container(cell_content)
         .width(Length::Fixed(size))
@@ -412,6 +537,9 @@ mod tests {
             alternatives,
             sticky: false,
             stickyrelease: true,
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         }
     }
 
@@ -477,6 +605,9 @@ mod tests {
             alternatives: HashMap::new(),
             sticky: false,
             stickyrelease: true,
+            after: None,
+            feedback: None,
+            post_keys: Vec::new(),
         };
         assert!(!has_swipe_alternatives(&empty_key.alternatives));
 
@@ -616,6 +747,32 @@ mod tests {
         let _element = render_popup(&key, &position, 1.0);
     }
 
+    /// Test: The alternative under the pointer is the one the pointer leans
+    /// towards past the center cell, if the key has one there.
+    #[test]
+    fn test_popup_direction_at() {
+        let key = create_key_with_alternatives();
+        let mut position = PopupPosition::new(100.0, 100.0)
+            .with_directions(vec![SwipeDirection::Up, SwipeDirection::Left]);
+
+        assert_eq!(popup_direction_at(&position, (110.0, 90.0), 1.0), None);
+        assert_eq!(
+            popup_direction_at(&position, (110.0, 40.0), 1.0),
+            Some(SwipeDirection::Up)
+        );
+        assert_eq!(
+            popup_direction_at(&position, (30.0, 120.0), 1.0),
+            Some(SwipeDirection::Left)
+        );
+        assert_eq!(popup_direction_at(&position, (100.0, 160.0), 1.0), None);
+        // Cells are twice as far apart at scale 2
+        assert_eq!(popup_direction_at(&position, (100.0, 60.0), 2.0), None);
+
+        position.available_directions.push(SwipeDirection::Down);
+        let down = popup_direction_at(&position, (100.0, 160.0), 1.0).unwrap();
+        assert_eq!(popup_alternative(&key, down), Some(&Action::Character('2')));
+    }
+
     /// Test: Rectangle center calculations
     #[test]
     fn test_rectangle_center() {
@@ -9,10 +9,22 @@ use cosmic::widget;
 use cosmic::Element;
 
 use crate::layout::{Cell, Row};
+use crate::symbols::SYMBOLS_BROWSER_WIDGET;
+use crate::renderer::debug_overlay::with_cell_overlay;
+use crate::renderer::emoji_recents::{render_emoji_recents, EMOJI_RECENTS_WIDGET};
+use crate::renderer::emoji_search::{render_emoji_search, EMOJI_SEARCH_WIDGET};
 use crate::renderer::key::render_key;
 use crate::renderer::message::RendererMessage;
+use crate::renderer::navigation::{with_focus_ring, NavTarget};
 use crate::renderer::panel_ref::render_panel_ref_button;
+#[cfg(feature = "scan")]
+use crate::renderer::scan_button::render_scan_button;
 use crate::renderer::state::KeyboardRenderer;
+use crate::renderer::symbols_browser::render_symbols_browser;
+use crate::renderer::date_picker::render_date_picker;
+use crate::date_picker::DATE_PICKER_WIDGET;
+use crate::renderer::calculator_display::render_calculator_display;
+use crate::calculator::CALCULATOR_DISPLAY_WIDGET;
 use crate::renderer::widget_placeholder::render_widget_placeholder;
 
 /// Renders a row of cells as a horizontal layout.
@@ -52,9 +64,18 @@ pub fn render_row<'a>(
 ///
 /// Dispatches to the appropriate rendering function based on the cell type:
 /// - `Cell::Key` -> `render_key()`
-/// - `Cell::Widget` -> `render_widget_placeholder()`
+/// - `Cell::Widget` -> `render_emoji_search()` for `emoji_search` widgets,
+///   `render_emoji_recents()` for `emoji_recents` widgets,
+///   `render_symbols_browser()` for `symbols_browser` widgets,
+///   `render_date_picker()` for `date_picker` widgets,
+///   `render_calculator_display()` for `calculator_display` widgets,
+///   `render_scan_button()` for `scan` widgets (with the `scan` feature),
+///   `render_widget_placeholder()` otherwise
 /// - `Cell::PanelRef` -> `render_panel_ref_button()`
 ///
+/// The cell holding the navigation focus is outlined. With the debug
+/// overlay enabled, the cell's bounding box and label are drawn over it.
+///
 /// # Arguments
 ///
 /// * `cell` - The cell to render
@@ -71,10 +92,41 @@ pub fn render_cell<'a>(
     base_unit: f32,
     scale: f32,
 ) -> Element<'a, RendererMessage> {
-    match cell {
+    let element = match cell {
         Cell::Key(key) => render_key(key, state, base_unit, scale),
+        Cell::Widget(widget) if widget.widget_type == EMOJI_SEARCH_WIDGET => {
+            render_emoji_search(widget, state, base_unit, scale)
+        }
+        Cell::Widget(widget) if widget.widget_type == EMOJI_RECENTS_WIDGET => {
+            render_emoji_recents(widget, state, base_unit, scale)
+        }
+        Cell::Widget(widget) if widget.widget_type == SYMBOLS_BROWSER_WIDGET => {
+            render_symbols_browser(widget, state, base_unit, scale)
+        }
+        Cell::Widget(widget) if widget.widget_type == DATE_PICKER_WIDGET => {
+            render_date_picker(widget, state, base_unit, scale)
+        }
+        Cell::Widget(widget) if widget.widget_type == CALCULATOR_DISPLAY_WIDGET => {
+            render_calculator_display(widget, state, base_unit, scale)
+        }
+        #[cfg(feature = "scan")]
+        Cell::Widget(widget) if widget.widget_type == crate::scan::SCAN_WIDGET => {
+            render_scan_button(widget, base_unit, scale)
+        }
         Cell::Widget(widget) => render_widget_placeholder(widget, base_unit, scale),
         Cell::PanelRef(panel_ref) => render_panel_ref_button(panel_ref, base_unit, scale),
+    };
+
+    let element = if state.nav_focus.is_some() && state.nav_focus == NavTarget::of(cell) {
+        with_focus_ring(element, cell, base_unit, scale)
+    } else {
+        element
+    };
+
+    if state.debug_overlay {
+        with_cell_overlay(element, cell, base_unit, scale)
+    } else {
+        element
     }
 }
 
@@ -141,6 +193,9 @@ mod tests {
             padding: Some(5.0),
             margin: Some(2.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![],
         };
 
@@ -154,6 +209,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         }
@@ -181,6 +239,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 }),
                 Cell::Key(Key {
                     label: "B".to_string(),
@@ -193,6 +254,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 }),
                 Cell::Key(Key {
                     label: "C".to_string(),
@@ -205,6 +269,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 }),
             ],
         };
@@ -235,6 +302,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 }),
                 Cell::Widget(Widget {
                     widget_type: "trackpad".to_string(),
@@ -269,6 +339,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 }),
                 Cell::Key(Key {
                     label: "Shift".to_string(),
@@ -281,6 +354,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: true,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 }),
                 Cell::Key(Key {
                     label: "Space".to_string(),
@@ -293,6 +369,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 }),
             ],
         };
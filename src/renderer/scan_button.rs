@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Scan button rendering for the keyboard layout renderer.
+//!
+//! This module renders the `scan` widget: a button that dispatches
+//! [`AppAction::ScanCode`], so the applet picks an image, decodes the QR code
+//! or barcode in it and types the text (see [`crate::scan`]).
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, button, container, icon};
+use cosmic::Element;
+
+use crate::actions::AppAction;
+use crate::fl;
+use crate::layout::Widget;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::sizing::resolve_sizing;
+
+/// Icon shown on the scan button.
+const SCAN_ICON: &str = "camera-photo-symbolic";
+
+/// Renders the scan button widget.
+///
+/// # Arguments
+///
+/// * `widget` - The widget definition from the layout
+/// * `base_unit` - The calculated base unit for relative sizing
+/// * `scale` - HDPI scale factor for pixel sizing
+///
+/// # Returns
+///
+/// An Element containing the scan button.
+pub fn render_scan_button<'a>(
+    widget: &Widget,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let width = resolve_sizing(&widget.width, base_unit, scale);
+    let height = resolve_sizing(&widget.height, base_unit, scale);
+
+    let content = container(
+        widget::row::row()
+            .spacing(8)
+            .align_y(Alignment::Center)
+            .push(icon::from_name(SCAN_ICON).size(16).symbolic(true))
+            .push(widget::text::body(fl!("scan-code"))),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .align_x(Alignment::Center)
+    .align_y(Alignment::Center);
+
+    button::custom(content)
+        .on_press(RendererMessage::RunAction(AppAction::ScanCode))
+        .class(cosmic::style::Button::Standard)
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .into()
+}
@@ -239,6 +239,20 @@ pub fn get_scale_factor() -> f32 {
     1.0
 }
 
+/// Converts the scale an output announces to a scale factor.
+///
+/// Outputs announce an integer scale (the fractional scale rounded up);
+/// anything below 1 means the output announced none, and the fallback of
+/// [`get_scale_factor`] is used.
+#[must_use]
+pub fn output_scale_factor(scale: i32) -> f32 {
+    if scale >= 1 {
+        scale as f32
+    } else {
+        get_scale_factor()
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -493,4 +507,13 @@ mod tests {
             result_invalid
         );
     }
+
+    /// Test: Output scales become scale factors, unset ones fall back.
+    #[test]
+    fn test_output_scale_factor() {
+        assert_eq!(output_scale_factor(1), 1.0);
+        assert_eq!(output_scale_factor(2), 2.0);
+        assert_eq!(output_scale_factor(0), get_scale_factor());
+        assert_eq!(output_scale_factor(-1), get_scale_factor());
+    }
 }
@@ -5,11 +5,21 @@
 //! This module provides the core state structures for tracking keyboard rendering,
 //! including pressed keys, sticky keys, panel animations, and toast notifications.
 
-use std::collections::{HashSet, VecDeque};
-use std::time::Instant;
-
-use crate::input::ModifierState;
-use crate::layout::{Layout, Modifier, Panel};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::emoji::EmojiRecents;
+use crate::fl;
+use crate::input::{InputFocus, ModifierMode, ModifierState, ResolvedKeycode};
+use crate::symbols::SymbolCategory;
+use crate::date_picker;
+use crate::calculator::Calculator;
+use crate::layout::{AlternativeKey, Cell, Layout, Modifier, Panel};
+use crate::renderer::navigation::{first_target, NavTarget};
+use crate::renderer::popup::ActivePopup;
+use crate::renderer::theme::KeyStyle;
 
 // ============================================================================
 // Animation Constants
@@ -18,12 +28,15 @@ use crate::layout::{Layout, Modifier, Panel};
 /// Duration of panel slide animations in milliseconds.
 pub const ANIMATION_DURATION_MS: u64 = 250;
 
-/// Animation frame interval for smooth 60fps animations in milliseconds.
-pub const ANIMATION_FRAME_INTERVAL_MS: u64 = 16;
-
 /// Duration of toast notifications in milliseconds.
 pub const TOAST_DURATION_MS: u64 = 3000;
 
+/// Duration of toasts with an action button in milliseconds.
+///
+/// They ask the user something, so they stay long enough to be read and
+/// answered.
+pub const TOAST_ACTION_DURATION_MS: u64 = 20_000;
+
 /// Timer tick interval for toast timeout checking in milliseconds.
 ///
 /// The toast timer emits ticks at this interval to check if the
@@ -42,6 +55,34 @@ pub const LONG_PRESS_THRESHOLD_MS: u64 = 300;
 /// threshold has been exceeded.
 pub const LONG_PRESS_TIMER_INTERVAL_MS: u64 = 50;
 
+/// How long a key may stay pressed before it is taken for stuck, in
+/// milliseconds.
+///
+/// A key whose release never arrives, because the pointer left the surface
+/// before it was lifted, would keep its character repeating. No one holds a
+/// key this long on purpose.
+pub const STUCK_KEY_TIMEOUT_MS: u64 = 10_000;
+
+/// Timer tick interval of the stuck key watchdog in milliseconds.
+pub const STUCK_KEY_CHECK_INTERVAL_MS: u64 = 1000;
+
+/// What happens to keys still pressed when the pointer leaves the keyboard.
+///
+/// Layer surfaces do not grab the pointer, so a button released outside the
+/// keyboard never reaches it; the press is settled when the pointer leaves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PointerLeavePolicy {
+    /// Release the keys as if the button was released over them.
+    #[default]
+    Commit,
+    /// Drop what the keys had not typed yet (a held back key, an open
+    /// alternatives popup), then release them.
+    Cancel,
+}
+
+/// Maximum number of panels remembered for going back.
+pub const MAX_PANEL_HISTORY: usize = 16;
+
 // ============================================================================
 // Toast Types
 // ============================================================================
@@ -64,6 +105,18 @@ pub struct Toast {
     pub message: String,
     /// Severity level affecting visual styling
     pub severity: ToastSeverity,
+    /// Button shown next to the message, if any
+    pub action: Option<ToastAction>,
+}
+
+/// A button on a toast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToastAction {
+    /// Button label
+    pub label: String,
+    /// ID sent with [`RendererMessage::ToastAction`](crate::renderer::RendererMessage::ToastAction)
+    /// when the button is pressed
+    pub id: u64,
 }
 
 impl Toast {
@@ -72,6 +125,27 @@ impl Toast {
         Self {
             message: message.into(),
             severity,
+            action: None,
+        }
+    }
+
+    /// Adds a button to the toast.
+    #[must_use]
+    pub fn with_action(mut self, label: impl Into<String>, id: u64) -> Self {
+        self.action = Some(ToastAction {
+            label: label.into(),
+            id,
+        });
+        self
+    }
+
+    /// Returns how long the toast is shown.
+    #[must_use]
+    pub fn duration_ms(&self) -> u64 {
+        if self.action.is_some() {
+            TOAST_ACTION_DURATION_MS
+        } else {
+            TOAST_DURATION_MS
         }
     }
 
@@ -123,7 +197,15 @@ impl PanelAnimation {
     ///
     /// Returns `true` if the animation is complete.
     pub fn update(&mut self) -> bool {
-        let elapsed_ms = self.start_time.elapsed().as_millis() as u64;
+        self.update_at(Instant::now())
+    }
+
+    /// Updates the animation progress for a frame drawn at `now`.
+    ///
+    /// Progress only depends on the time since the start, so frames may
+    /// arrive at any rate. Returns `true` if the animation is complete.
+    pub fn update_at(&mut self, now: Instant) -> bool {
+        let elapsed_ms = now.saturating_duration_since(self.start_time).as_millis() as u64;
         self.progress = (elapsed_ms as f32 / ANIMATION_DURATION_MS as f32).min(1.0);
         self.progress >= 1.0
     }
@@ -143,6 +225,44 @@ impl PanelAnimation {
     }
 }
 
+/// Keyboard height following a panel slide to a panel of another height.
+///
+/// The surface is resized along with the slide, so windows above a docked
+/// keyboard reflow smoothly instead of jumping at the end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeightTransition {
+    /// Height before the switch
+    pub from: f32,
+    /// Height of the panel switched to
+    pub to: f32,
+    /// Height the surface was last resized to
+    pub current: f32,
+}
+
+impl HeightTransition {
+    /// Creates a transition starting at `from`.
+    pub fn new(from: f32, to: f32) -> Self {
+        Self {
+            from,
+            to,
+            current: from,
+        }
+    }
+
+    /// Moves to the height at an eased slide progress, in whole pixels.
+    ///
+    /// Returns the new height, or `None` if it did not change since the last
+    /// step, so frames that would not move the surface cost nothing.
+    pub fn step(&mut self, eased_progress: f32) -> Option<f32> {
+        let height = (self.from + (self.to - self.from) * eased_progress.clamp(0.0, 1.0)).round();
+        if height == self.current {
+            return None;
+        }
+        self.current = height;
+        Some(height)
+    }
+}
+
 // ============================================================================
 // Keyboard Renderer State
 // ============================================================================
@@ -162,6 +282,9 @@ pub struct KeyboardRenderer {
     /// Set of key identifiers that are currently pressed
     pub pressed_keys: HashSet<String>,
 
+    /// When each pressed key was pressed, for the stuck key watchdog
+    press_times: HashMap<String, Instant>,
+
     /// Set of sticky key identifiers that are currently active (for visual state)
     pub sticky_keys_active: HashSet<String>,
 
@@ -173,6 +296,9 @@ pub struct KeyboardRenderer {
     /// modifier state by `Modifier` enum.
     modifier_state: ModifierState,
 
+    /// Modifier of each key shown active, to tell how it is active
+    modifier_keys: HashMap<String, Modifier>,
+
     /// Key identifier for the key being long-pressed (if any)
     pub long_press_key: Option<String>,
 
@@ -182,6 +308,9 @@ pub struct KeyboardRenderer {
     /// Whether a long press has been detected and popup is active
     pub long_press_active: bool,
 
+    /// Alternatives popup of the long-pressed key, while it is shown
+    pub popup: Option<ActivePopup>,
+
     /// Current panel animation state (if animating)
     pub animation_state: Option<PanelAnimation>,
 
@@ -190,6 +319,48 @@ pub struct KeyboardRenderer {
 
     /// Currently displayed toast with its display start time
     pub current_toast: Option<(Toast, Instant)>,
+
+    /// Which internal text field (if any) receives key input instead of the client
+    pub focus: InputFocus,
+
+    /// Recently used and pinned emoji, mirrored from the user configuration
+    pub emoji_recents: EmojiRecents,
+
+    /// Category tab shown in the symbols browser
+    pub symbol_category: SymbolCategory,
+
+    /// Date and time shown in the date picker
+    pub picked_date: chrono::NaiveDateTime,
+
+    /// Expression entered on the calculator panel
+    pub calculator: Calculator,
+
+    /// Whether cell bounding boxes, identifiers and sizes are drawn over the keyboard
+    pub debug_overlay: bool,
+
+    /// Heat of each key by identifier (0.0 to 1.0), when keys are tinted by
+    /// how often they were pressed; `None` when the heat map is off
+    pub heat_map: Option<HashMap<String, f32>>,
+
+    /// Key colors and corner radius, from the user's theme overrides
+    pub key_style: KeyStyle,
+
+    /// Whether prediction candidates can be dragged out of the keyboard
+    pub drag_suggestions: bool,
+
+    /// Whether every key reports the pointer or finger entering it, for the
+    /// haptic ticks of key boundaries (piano mode panels always do)
+    pub key_crossings: bool,
+
+    /// Key or panel button holding the gamepad/remote navigation focus, if
+    /// navigation is in use
+    pub nav_focus: Option<NavTarget>,
+
+    /// Panels shown before the current one, most recent last
+    panel_history: Vec<String>,
+
+    /// Scroll offsets in pixels of scrolling panels and grids, by scroll area ID
+    scroll_offsets: HashMap<String, f32>,
 }
 
 impl KeyboardRenderer {
@@ -202,14 +373,30 @@ impl KeyboardRenderer {
             layout,
             current_panel_id,
             pressed_keys: HashSet::new(),
+            press_times: HashMap::new(),
             sticky_keys_active: HashSet::new(),
             modifier_state: ModifierState::new(),
+            modifier_keys: HashMap::new(),
             long_press_key: None,
             long_press_start: None,
             long_press_active: false,
+            popup: None,
             animation_state: None,
             toast_queue: VecDeque::new(),
             current_toast: None,
+            focus: InputFocus::new(),
+            emoji_recents: EmojiRecents::default(),
+            symbol_category: SymbolCategory::default(),
+            picked_date: date_picker::now(),
+            calculator: Calculator::new(),
+            debug_overlay: false,
+            heat_map: None,
+            key_style: KeyStyle::default(),
+            drag_suggestions: false,
+            key_crossings: false,
+            nav_focus: None,
+            panel_history: Vec::new(),
+            scroll_offsets: HashMap::new(),
         }
     }
 
@@ -220,6 +407,21 @@ impl KeyboardRenderer {
         self.layout.panels.get(&self.current_panel_id)
     }
 
+    /// Returns `true` if the current panel is in piano mode (slide to type).
+    pub fn is_piano_panel(&self) -> bool {
+        self.current_panel().is_some_and(|panel| panel.piano)
+    }
+
+    /// Returns the index of the row of the current panel holding the key
+    /// with the given identifier.
+    pub fn key_row(&self, identifier: &str) -> Option<usize> {
+        self.current_panel()?.rows.iter().position(|row| {
+            row.cells.iter().any(|cell| {
+                matches!(cell, Cell::Key(key) if key.identifier.as_deref() == Some(identifier))
+            })
+        })
+    }
+
     /// Returns a reference to a panel by ID.
     ///
     /// Returns `None` if the panel ID does not exist in the layout.
@@ -249,6 +451,7 @@ impl KeyboardRenderer {
     pub fn press_key(&mut self, identifier: impl Into<String>) {
         let id = identifier.into();
         self.pressed_keys.insert(id.clone());
+        self.press_times.insert(id.clone(), Instant::now());
         self.start_long_press_timer(&id);
     }
 
@@ -260,6 +463,7 @@ impl KeyboardRenderer {
     /// 3. Resets the long press active state
     pub fn release_key(&mut self, identifier: &str) {
         self.pressed_keys.remove(identifier);
+        self.press_times.remove(identifier);
 
         // Cancel long press if this was the key being long-pressed
         if self.long_press_key.as_deref() == Some(identifier) {
@@ -328,6 +532,46 @@ impl KeyboardRenderer {
         self.long_press_key.is_some() && self.long_press_start.is_some() && !self.long_press_active
     }
 
+    /// Returns how long a pressed key has been held, or `None` if it is not
+    /// pressed.
+    pub fn held_for(&self, identifier: &str, now: Instant) -> Option<Duration> {
+        self.press_times
+            .get(identifier)
+            .map(|pressed_at| now.saturating_duration_since(*pressed_at))
+    }
+
+    /// Returns `true` if a key is pressed, so the stuck key watchdog runs.
+    pub fn has_pressed_keys(&self) -> bool {
+        !self.pressed_keys.is_empty()
+    }
+
+    /// Returns the keys currently pressed, sorted.
+    pub fn pressed_key_ids(&self) -> Vec<String> {
+        let mut pressed: Vec<String> = self.pressed_keys.iter().cloned().collect();
+        pressed.sort();
+        pressed
+    }
+
+    /// Returns the keys pressed for at least [`STUCK_KEY_TIMEOUT_MS`] at
+    /// `now`, sorted.
+    ///
+    /// The caller releases them as if their release had arrived.
+    pub fn stuck_keys(&mut self, now: Instant) -> Vec<String> {
+        // Keys released without release_key (the pressed set was cleared)
+        let pressed_keys = &self.pressed_keys;
+        self.press_times.retain(|id, _| pressed_keys.contains(id));
+
+        let timeout = Duration::from_millis(STUCK_KEY_TIMEOUT_MS);
+        let mut stuck: Vec<String> = self
+            .press_times
+            .iter()
+            .filter(|(_, pressed_at)| now.saturating_duration_since(**pressed_at) >= timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+        stuck.sort();
+        stuck
+    }
+
     // ========================================================================
     // Sticky Key Management
     // ========================================================================
@@ -377,6 +621,65 @@ impl KeyboardRenderer {
         self.modifier_state.activate(modifier, stickyrelease);
     }
 
+    /// Activates a modifier while its key is held down (hold behavior).
+    ///
+    /// Deactivate it with `deactivate_modifier()` when the key is released.
+    pub fn hold_modifier(&mut self, modifier: Modifier) {
+        self.modifier_state.hold(modifier);
+    }
+
+    /// Records a tap of a one-shot modifier key: activates it as one-shot,
+    /// locks it on a double tap, or releases it if it was locked.
+    ///
+    /// # Returns
+    ///
+    /// The mode the modifier is in after the tap, `None` if it is inactive.
+    pub fn tap_sticky_modifier(&mut self, modifier: Modifier) -> Option<ModifierMode> {
+        self.modifier_state.tap_sticky(modifier, Instant::now())
+    }
+
+    /// Returns how a modifier is active, or `None` if it is inactive.
+    pub fn modifier_mode(&self, modifier: Modifier) -> Option<ModifierMode> {
+        self.modifier_state.mode(modifier)
+    }
+
+    /// Releases a latched modifier, e.g. from its status strip chip.
+    ///
+    /// Deactivates it whether it is one-shot or locked, clears the visual
+    /// state of its keys, and turns caps-word off along with Shift.
+    pub fn release_modifier(&mut self, modifier: Modifier) {
+        self.modifier_state.deactivate(modifier);
+        if modifier == Modifier::Shift {
+            self.modifier_state.cancel_caps_word();
+        }
+        let sticky_keys_active = &mut self.sticky_keys_active;
+        self.modifier_keys.retain(|identifier, key_modifier| {
+            let released = *key_modifier == modifier;
+            if released {
+                sticky_keys_active.remove(identifier);
+            }
+            !released
+        });
+        self.sticky_keys_active
+            .remove(modifier_to_identifier(modifier));
+    }
+
+    /// Returns how the modifier of an active modifier key is active, or
+    /// `None` if the key is not shown active or is no modifier key.
+    pub fn modifier_key_mode(&self, identifier: &str) -> Option<ModifierMode> {
+        if !self.sticky_keys_active.contains(identifier) {
+            return None;
+        }
+        let modifier = self.modifier_keys.get(identifier)?;
+        self.modifier_state.mode(*modifier)
+    }
+
+    /// Returns `true` if a modifier is active only while its key is held
+    /// down, so its key pulses.
+    pub fn has_held_modifiers(&self) -> bool {
+        self.modifier_state.has_held_modifiers()
+    }
+
     /// Deactivates a modifier key.
     ///
     /// This removes the modifier from both the active set and the one-shot set.
@@ -419,6 +722,22 @@ impl KeyboardRenderer {
         self.modifier_state.get_active_modifiers()
     }
 
+    /// Returns the modifiers shown as active: the active modifiers, then
+    /// Caps Lock if it is latched.
+    pub fn shown_modifiers(&self) -> Vec<Modifier> {
+        let mut modifiers = self.get_active_modifiers();
+        if self.modifier_state.is_caps_lock() {
+            modifiers.push(Modifier::CapsLock);
+        }
+        modifiers
+    }
+
+    /// Returns the layer of the active modifiers, whose `alternatives` keys
+    /// show and type instead of their own label and code.
+    pub fn active_layer(&self) -> Option<AlternativeKey> {
+        AlternativeKey::layer(&self.get_active_modifiers())
+    }
+
     /// Clears all one-shot (sticky release) modifiers.
     ///
     /// This should be called after a regular key is pressed to implement
@@ -456,6 +775,22 @@ impl KeyboardRenderer {
         }
     }
 
+    /// Releases all keys and modifiers, including toggled ones.
+    ///
+    /// Used when input stops abruptly (the applet quits) so the keyboard
+    /// never shows or applies a latched modifier afterwards.
+    pub fn reset_input_state(&mut self) {
+        self.pressed_keys.clear();
+        self.press_times.clear();
+        self.sticky_keys_active.clear();
+        self.modifier_state.clear_all();
+        self.modifier_keys.clear();
+        self.long_press_key = None;
+        self.long_press_start = None;
+        self.long_press_active = false;
+        self.popup = None;
+    }
+
     /// Returns `true` if any modifiers are currently active.
     ///
     /// Useful for determining if a combo key sequence needs to be emitted.
@@ -481,9 +816,77 @@ impl KeyboardRenderer {
     pub fn sync_modifier_visual_state(&mut self, modifier: Modifier, key_identifier: &str) {
         if self.modifier_state.is_active(modifier) {
             self.sticky_keys_active.insert(key_identifier.to_string());
+            self.modifier_keys
+                .insert(key_identifier.to_string(), modifier);
         } else {
             self.sticky_keys_active.remove(key_identifier);
+            self.modifier_keys.remove(key_identifier);
+        }
+    }
+
+    /// Records a Shift tap, enabling caps-word on a double tap.
+    ///
+    /// Returns `true` if this tap enabled caps-word. Shift itself is then
+    /// inactive, so its visual state is cleared too.
+    pub fn register_shift_tap(&mut self, key_identifier: &str) -> bool {
+        let enabled = self.modifier_state.register_shift_tap(Instant::now());
+        if enabled {
+            self.sticky_keys_active.remove(key_identifier);
         }
+        enabled
+    }
+
+    /// Records a tap of a Caps Lock key, latching Caps Lock or releasing
+    /// it, and shows the key as locked while it is latched.
+    ///
+    /// Returns `true` if Caps Lock is now latched.
+    pub fn toggle_caps_lock(&mut self, key_identifier: &str) -> bool {
+        let latched = self.modifier_state.toggle_caps_lock();
+        self.sync_modifier_visual_state(Modifier::CapsLock, key_identifier);
+        latched
+    }
+
+    /// Returns `true` if Caps Lock is latched.
+    pub fn is_caps_lock_active(&self) -> bool {
+        self.modifier_state.is_caps_lock()
+    }
+
+    /// Returns `true` if caps-word is active.
+    pub fn is_caps_word_active(&self) -> bool {
+        self.modifier_state.is_caps_word()
+    }
+
+    /// Turns caps-word off.
+    pub fn cancel_caps_word(&mut self) {
+        self.modifier_state.cancel_caps_word();
+    }
+
+    /// Applies caps-word to a key about to be typed.
+    ///
+    /// When the key should be shifted, Shift is activated as a one-shot
+    /// modifier (unless it already is active), so it is emitted with the key
+    /// and cleared after it like a regular one-shot Shift. Keys that end the
+    /// word turn caps-word off.
+    pub fn apply_caps_word(&mut self, key: &ResolvedKeycode) {
+        if self.modifier_state.apply_caps_word(key) && !self.modifier_state.is_active(Modifier::Shift) {
+            self.modifier_state.activate(Modifier::Shift, true);
+        }
+    }
+
+    // ========================================================================
+    // Scrolling
+    // ========================================================================
+
+    /// Returns the scroll offset in pixels of a scroll area (0 until it is
+    /// scrolled).
+    pub fn scroll_offset(&self, scroll_id: &str) -> f32 {
+        self.scroll_offsets.get(scroll_id).copied().unwrap_or(0.0)
+    }
+
+    /// Records the scroll offset of a scroll area, as reported by its
+    /// scrollable.
+    pub fn set_scroll_offset(&mut self, scroll_id: &str, offset: f32) {
+        self.scroll_offsets.insert(scroll_id.to_string(), offset.max(0.0));
     }
 
     // ========================================================================
@@ -500,7 +903,7 @@ impl KeyboardRenderer {
     /// Returns an error message if the target panel does not exist in the layout.
     pub fn switch_panel(&mut self, panel_id: &str) -> Result<(), String> {
         if !self.layout.panels.contains_key(panel_id) {
-            return Err(format!("Panel '{}' not found", panel_id));
+            return Err(fl!("panel-not-found", panel = panel_id));
         }
 
         // Don't animate if we're already on this panel
@@ -508,12 +911,59 @@ impl KeyboardRenderer {
             return Ok(());
         }
 
+        // Remember the panel being left for going back
+        let shown = self.shown_panel_id().to_string();
+        if shown != panel_id {
+            self.panel_history.push(shown);
+            if self.panel_history.len() > MAX_PANEL_HISTORY {
+                self.panel_history.remove(0);
+            }
+        }
+
         // Start animation from current panel to target panel
         self.start_animation(panel_id.to_string());
+        self.refocus_navigation();
 
         Ok(())
     }
 
+    /// Switches back to the panel shown before the current one.
+    ///
+    /// Panels removed from the layout since are skipped. Returns `false` if
+    /// there is no panel to go back to.
+    pub fn switch_panel_back(&mut self) -> bool {
+        while let Some(panel_id) = self.panel_history.pop() {
+            if panel_id != self.shown_panel_id() && self.layout.panels.contains_key(&panel_id) {
+                if panel_id == self.current_panel_id {
+                    // Back before the slide away from it finished
+                    self.animation_state = None;
+                } else {
+                    self.start_animation(panel_id);
+                }
+                self.refocus_navigation();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the ID of the panel being shown: the panel being slid in
+    /// during an animation, the current panel otherwise.
+    #[must_use]
+    pub fn shown_panel_id(&self) -> &str {
+        self.animation_state
+            .as_ref()
+            .map_or(&self.current_panel_id, |animation| &animation.to_panel_id)
+    }
+
+    /// Moves the navigation focus, if any, to the first key of the panel
+    /// being shown.
+    fn refocus_navigation(&mut self) {
+        if self.nav_focus.is_some() {
+            self.nav_focus = self.get_panel(self.shown_panel_id()).and_then(first_target);
+        }
+    }
+
     /// Switches to a different panel by ID, queuing a toast on error.
     ///
     /// This is a convenience method that combines `switch_panel()` with
@@ -550,6 +1000,8 @@ impl KeyboardRenderer {
     pub fn start_animation(&mut self, to_panel_id: String) {
         let animation = PanelAnimation::new(&self.current_panel_id, to_panel_id);
         self.animation_state = Some(animation);
+        // Panels are shown from the top again
+        self.scroll_offsets.clear();
     }
 
     /// Returns `true` if a panel animation is currently in progress.
@@ -601,6 +1053,60 @@ impl KeyboardRenderer {
         self.animation_state.as_ref()
     }
 
+    /// Checks the invariants the view relies on and repairs broken state.
+    ///
+    /// After a layout hot-reload the current panel, or a panel being animated
+    /// between, may no longer exist. A broken animation is dropped, and a
+    /// missing current panel is replaced by the layout's default panel (or
+    /// its first panel) with a warning toast.
+    ///
+    /// Returns `true` if anything was repaired.
+    pub fn repair_invariants(&mut self) -> bool {
+        let mut repaired = false;
+
+        let broken_animation = self.animation_state.as_ref().is_some_and(|animation| {
+            self.get_panel(&animation.from_panel_id).is_none()
+                || self.get_panel(&animation.to_panel_id).is_none()
+        });
+        if broken_animation {
+            tracing::warn!("Dropping animation between missing panels");
+            self.animation_state = None;
+            repaired = true;
+        }
+
+        if self.current_panel().is_none() {
+            let missing = std::mem::take(&mut self.current_panel_id);
+            let fallback = if self.layout.panels.contains_key(&self.layout.default_panel_id) {
+                Some(self.layout.default_panel_id.clone())
+            } else {
+                self.layout.panels.keys().min().cloned()
+            };
+
+            match fallback {
+                Some(panel_id) => {
+                    tracing::warn!("Panel '{}' not found, showing '{}'", missing, panel_id);
+                    self.queue_toast(
+                        fl!(
+                            "panel-not-found-fallback",
+                            panel = missing.clone(),
+                            fallback = panel_id.clone()
+                        ),
+                        ToastSeverity::Warning,
+                    );
+                    self.current_panel_id = panel_id;
+                }
+                None => {
+                    tracing::error!("Layout has no panels");
+                    self.queue_toast(fl!("layout-has-no-panels"), ToastSeverity::Error);
+                    self.current_panel_id = missing;
+                }
+            }
+            repaired = true;
+        }
+
+        repaired
+    }
+
     // ========================================================================
     // Toast Management (Task 6.3, 6.6, 6.7)
     // ========================================================================
@@ -616,7 +1122,11 @@ impl KeyboardRenderer {
     /// * `message` - The message text to display
     /// * `severity` - The severity level (Info, Warning, Error)
     pub fn queue_toast(&mut self, message: impl Into<String>, severity: ToastSeverity) {
-        let toast = Toast::new(message, severity);
+        self.queue(Toast::new(message, severity));
+    }
+
+    /// Queues a toast, e.g. one with an action button.
+    pub fn queue(&mut self, toast: Toast) {
         self.toast_queue.push_back(toast);
 
         // If no toast is currently displayed, show this one
@@ -646,15 +1156,16 @@ impl KeyboardRenderer {
 
     /// Checks if the current toast has timed out.
     ///
-    /// Returns `true` if a toast is currently displayed and the 3-second
-    /// timeout (`TOAST_DURATION_MS`) has elapsed since it was displayed.
+    /// Returns `true` if a toast is currently displayed and its timeout
+    /// (`TOAST_DURATION_MS`, or `TOAST_ACTION_DURATION_MS` for toasts with a
+    /// button) has elapsed since it was displayed.
     ///
     /// # Returns
     ///
     /// `true` if the toast should be dismissed, `false` otherwise.
     pub fn check_toast_timeout(&self) -> bool {
-        if let Some((_, start_time)) = &self.current_toast {
-            start_time.elapsed().as_millis() as u64 >= TOAST_DURATION_MS
+        if let Some((toast, start_time)) = &self.current_toast {
+            start_time.elapsed().as_millis() as u64 >= toast.duration_ms()
         } else {
             false
         }
@@ -702,6 +1213,7 @@ fn modifier_to_identifier(modifier: Modifier) -> &'static str {
         Modifier::Ctrl => "ctrl",
         Modifier::Alt => "alt",
         Modifier::Super => "super",
+        Modifier::CapsLock => "caps_lock",
     }
 }
 
@@ -727,6 +1239,9 @@ mod tests {
             padding: Some(5.0),
             margin: Some(2.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![Row {
                 cells: vec![Cell::Key(Key {
                     label: "A".to_string(),
@@ -739,6 +1254,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 })],
             }],
         };
@@ -749,6 +1267,9 @@ mod tests {
             padding: Some(5.0),
             margin: Some(2.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![Row {
                 cells: vec![Cell::Key(Key {
                     label: "1".to_string(),
@@ -761,6 +1282,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 })],
             }],
         };
@@ -771,6 +1295,9 @@ mod tests {
             padding: Some(5.0),
             margin: Some(2.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![Row {
                 cells: vec![Cell::Key(Key {
                     label: "!".to_string(),
@@ -783,6 +1310,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 })],
             }],
         };
@@ -799,6 +1329,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         }
@@ -1168,6 +1701,45 @@ mod tests {
         assert!(renderer.long_press_key_identifier().is_none());
     }
 
+    /// Test: Keys held past the timeout are reported as stuck, released and
+    /// cleared keys are not.
+    #[test]
+    fn test_stuck_keys() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+        let now = Instant::now();
+        let timeout = Duration::from_millis(STUCK_KEY_TIMEOUT_MS);
+
+        assert!(!renderer.has_pressed_keys());
+        renderer.press_key("key_b");
+        renderer.press_key("key_a");
+        renderer.press_key("key_c");
+        assert!(renderer.has_pressed_keys());
+        assert!(renderer.stuck_keys(now).is_empty());
+
+        renderer.release_key("key_c");
+        assert_eq!(
+            renderer.stuck_keys(now + timeout + Duration::from_millis(1)),
+            ["key_a", "key_b"]
+        );
+
+        renderer.pressed_keys.clear();
+        assert!(renderer.stuck_keys(now + timeout * 2).is_empty());
+    }
+
+    /// Test: The keys to settle when the pointer leaves are listed in order.
+    #[test]
+    fn test_pressed_key_ids() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+        assert!(renderer.pressed_key_ids().is_empty());
+
+        renderer.press_key("key_c");
+        renderer.press_key("key_a");
+        assert_eq!(renderer.pressed_key_ids(), ["key_a", "key_c"]);
+        assert_eq!(PointerLeavePolicy::default(), PointerLeavePolicy::Commit);
+    }
+
     /// Test 5: Multiple key presses don't interfere
     ///
     /// Verifies that pressing multiple keys correctly tracks each key's state.
@@ -1266,6 +1838,24 @@ mod tests {
         assert_eq!(renderer.current_panel_id, "main");
     }
 
+    /// Test: A missing current panel falls back to the default panel with a toast
+    #[test]
+    fn test_repair_missing_panel() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+        assert!(!renderer.repair_invariants());
+
+        renderer.switch_panel("numpad").unwrap();
+        renderer.layout.panels.remove("numpad");
+        renderer.current_panel_id = "numpad".to_string();
+
+        assert!(renderer.repair_invariants());
+        assert_eq!(renderer.current_panel_id, "main");
+        assert!(!renderer.is_animating());
+        assert!(renderer.current_toast.is_some());
+        assert!(!renderer.repair_invariants());
+    }
+
     /// Test 3: Animation progress interpolation (0.0 -> 1.0)
     ///
     /// Verifies that animation progress correctly interpolates from 0.0 to 1.0
@@ -1302,6 +1892,48 @@ mod tests {
         assert!(eased <= 1.0);
     }
 
+    /// Test: Progress follows frame timestamps at any refresh rate.
+    #[test]
+    fn test_animation_progress_at_frame_times() {
+        for refresh_hz in [48u64, 60, 90, 120] {
+            let mut anim = PanelAnimation::new("main", "numpad");
+            let start = anim.start_time;
+            let frame = Duration::from_micros(1_000_000 / refresh_hz);
+
+            let mut frames = 1;
+            let mut last = 0.0;
+            while !anim.update_at(start + frame * frames) {
+                assert!(anim.progress > last);
+                last = anim.progress;
+                frames += 1;
+            }
+            assert_eq!(anim.progress, 1.0);
+            // The slide takes the same time whatever the refresh rate
+            let duration = Duration::from_millis(ANIMATION_DURATION_MS);
+            assert!(frame * (frames - 1) < duration, "{} Hz", refresh_hz);
+            assert!(frame * frames < duration + frame * 2, "{} Hz", refresh_hz);
+        }
+
+        // A frame timestamp before the start counts as no progress
+        let mut anim = PanelAnimation::new("main", "numpad");
+        let before = anim.start_time - Duration::from_millis(5);
+        assert!(!anim.update_at(before));
+        assert_eq!(anim.progress, 0.0);
+    }
+
+    /// Test: The height moves toward the target in whole pixels, and frames
+    /// that would not move it are skipped.
+    #[test]
+    fn test_height_transition() {
+        let mut transition = HeightTransition::new(200.0, 60.0);
+        assert_eq!(transition.step(0.0), None);
+        assert_eq!(transition.step(0.5), Some(130.0));
+        assert_eq!(transition.step(0.501), None);
+        assert_eq!(transition.step(1.0), Some(60.0));
+        assert_eq!(transition.step(1.5), None);
+        assert_eq!(transition.current, 60.0);
+    }
+
     /// Test 4: Animation completion callback
     ///
     /// Verifies that update_animation returns true when animation completes
@@ -1398,6 +2030,37 @@ mod tests {
         assert!(renderer.animation_state.is_none());
     }
 
+    /// Test: Going back returns to the previous panels and moves the focus
+    ///
+    /// Verifies that the navigation focus follows the panel being shown.
+    #[test]
+    fn test_switch_panel_back() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+        assert!(!renderer.switch_panel_back());
+
+        renderer.nav_focus = Some(NavTarget::Key("key_a".to_string()));
+        renderer.switch_panel("numpad").unwrap();
+        renderer.complete_animation();
+        renderer.switch_panel("symbols").unwrap();
+        assert_eq!(renderer.shown_panel_id(), "symbols");
+        assert_eq!(
+            renderer.nav_focus,
+            Some(NavTarget::Key("key_exclaim".to_string()))
+        );
+        renderer.complete_animation();
+
+        assert!(renderer.switch_panel_back());
+        assert_eq!(renderer.shown_panel_id(), "numpad");
+        assert_eq!(renderer.nav_focus, Some(NavTarget::Key("key_1".to_string())));
+        renderer.complete_animation();
+
+        assert!(renderer.switch_panel_back());
+        assert_eq!(renderer.shown_panel_id(), "main");
+        renderer.complete_animation();
+        assert!(!renderer.switch_panel_back());
+    }
+
     // ========================================================================
     // Task 6.6: Panel switch with toast on error
     // ========================================================================
@@ -1660,6 +2323,85 @@ mod tests {
         assert!(active_after.is_empty());
     }
 
+    /// Test: Modifier keys shown active report how their modifier is active,
+    /// and a double tap locks a one-shot modifier
+    #[test]
+    fn test_modifier_key_modes() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+
+        renderer.tap_sticky_modifier(Modifier::Ctrl);
+        renderer.sync_modifier_visual_state(Modifier::Ctrl, "ctrl");
+        renderer.hold_modifier(Modifier::Alt);
+        renderer.sync_modifier_visual_state(Modifier::Alt, "alt");
+        assert_eq!(renderer.modifier_key_mode("ctrl"), Some(ModifierMode::OneShot));
+        assert_eq!(renderer.modifier_key_mode("alt"), Some(ModifierMode::Held));
+        assert_eq!(renderer.modifier_key_mode("shift"), None);
+        assert!(renderer.has_held_modifiers());
+
+        assert_eq!(
+            renderer.tap_sticky_modifier(Modifier::Ctrl),
+            Some(ModifierMode::Locked)
+        );
+        renderer.clear_oneshot_modifiers();
+        assert_eq!(renderer.modifier_key_mode("ctrl"), Some(ModifierMode::Locked));
+
+        renderer.reset_input_state();
+        assert_eq!(renderer.modifier_key_mode("ctrl"), None);
+        assert!(!renderer.has_held_modifiers());
+    }
+
+    /// Test: Releasing a modifier clears it and its keys, whether it was
+    /// one-shot or locked, and caps-word along with Shift
+    #[test]
+    fn test_release_modifier() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+
+        renderer.activate_modifier(Modifier::Ctrl, false);
+        renderer.sync_modifier_visual_state(Modifier::Ctrl, "left_ctrl");
+        renderer.activate_modifier(Modifier::Alt, true);
+        renderer.sync_modifier_visual_state(Modifier::Alt, "alt");
+        assert_eq!(
+            renderer.modifier_mode(Modifier::Ctrl),
+            Some(ModifierMode::Locked)
+        );
+
+        renderer.release_modifier(Modifier::Ctrl);
+        assert_eq!(renderer.modifier_mode(Modifier::Ctrl), None);
+        assert!(!renderer.is_sticky_active("left_ctrl"));
+        assert!(renderer.is_sticky_active("alt"));
+
+        renderer.register_shift_tap("shift");
+        renderer.register_shift_tap("shift");
+        assert!(renderer.is_caps_word_active());
+        renderer.release_modifier(Modifier::Shift);
+        assert!(!renderer.is_caps_word_active());
+    }
+
+    /// Test: Caps Lock keys stay locked until tapped again, and Caps Lock is
+    /// shown but not pressed with keys
+    #[test]
+    fn test_toggle_caps_lock() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+
+        assert!(renderer.toggle_caps_lock("caps"));
+        renderer.activate_modifier(Modifier::Shift, true);
+        renderer.clear_oneshot_modifiers();
+        assert!(renderer.is_caps_lock_active());
+        assert_eq!(
+            renderer.modifier_key_mode("caps"),
+            Some(ModifierMode::Locked)
+        );
+        assert!(renderer.get_active_modifiers().is_empty());
+        assert_eq!(renderer.shown_modifiers(), [Modifier::CapsLock]);
+
+        assert!(!renderer.toggle_caps_lock("caps"));
+        assert!(!renderer.is_sticky_active("caps"));
+        assert!(renderer.shown_modifiers().is_empty());
+    }
+
     /// Test: Renderer initialization includes empty modifier state
     #[test]
     fn test_renderer_init_empty_modifier_state() {
@@ -1674,4 +2416,32 @@ mod tests {
         assert!(!renderer.is_modifier_active(Modifier::Alt));
         assert!(!renderer.is_modifier_active(Modifier::Super));
     }
+
+    /// Test: Scroll offsets are kept per scroll area and reset on panel switch
+    #[test]
+    fn test_scroll_offsets() {
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+        assert_eq!(renderer.scroll_offset("panel:main"), 0.0);
+
+        renderer.set_scroll_offset("panel:main", 480.0);
+        renderer.set_scroll_offset("symbols_browser", -3.0);
+        assert_eq!(renderer.scroll_offset("panel:main"), 480.0);
+        assert_eq!(renderer.scroll_offset("symbols_browser"), 0.0);
+
+        renderer.switch_panel("numpad").unwrap();
+        assert_eq!(renderer.scroll_offset("panel:main"), 0.0);
+    }
+
+    /// Test: Keys are found in the rows of the panel shown only.
+    #[test]
+    fn test_key_row() {
+        let mut renderer = KeyboardRenderer::new(create_test_layout());
+        assert_eq!(renderer.key_row("key_a"), Some(0));
+        assert_eq!(renderer.key_row("key_1"), None);
+
+        renderer.switch_panel("numpad").unwrap();
+        renderer.complete_animation();
+        assert_eq!(renderer.key_row("key_1"), Some(0));
+    }
 }
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Status strip for the keyboard layout renderer.
+//!
+//! With `status_strip` enabled, a slim strip above the panel shows the
+//! keyboard state as chips, so it can be read at a glance instead of by
+//! scanning the keys:
+//!
+//! - one chip per latched modifier, outlined while one-shot and filled while
+//!   locked, one for Caps Lock, framed like its key, and one for caps-word;
+//!   tapping a chip clears the modifier;
+//! - the layout name, with the panel shown when it is not the default one;
+//!   tapping it goes back to the default panel.
+//!
+//! Modifiers held down are not shown: the finger on the key says as much.
+
+use cosmic::iced::{Alignment, Length, Padding};
+use cosmic::widget::{self, button, container};
+use cosmic::Element;
+
+use crate::fl;
+use crate::input::ModifierMode;
+use crate::layout::Modifier;
+use crate::renderer::collapsed_bar::modifier_label;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::state::KeyboardRenderer;
+
+/// Height of the status strip in pixels.
+pub const STATUS_STRIP_HEIGHT: f32 = 28.0;
+
+/// Modifiers in the order their chips are shown.
+const MODIFIERS: [Modifier; 5] = [
+    Modifier::Shift,
+    Modifier::Ctrl,
+    Modifier::Alt,
+    Modifier::Super,
+    Modifier::CapsLock,
+];
+
+/// One chip of the status strip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusChip {
+    /// A latched modifier, one-shot or locked.
+    Modifier(Modifier, ModifierMode),
+    /// Caps-word is active.
+    CapsWord,
+    /// The layout, and the panel shown if it is not the default one.
+    Layout {
+        /// Name of the layout.
+        name: String,
+        /// Panel shown, unless it is the default panel.
+        panel: Option<String>,
+    },
+}
+
+impl StatusChip {
+    /// Returns the text of the chip.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self {
+            Self::Modifier(modifier, _) => modifier_label(*modifier).to_string(),
+            Self::CapsWord => fl!("status-caps-word"),
+            Self::Layout { name, panel: None } => name.clone(),
+            Self::Layout {
+                name,
+                panel: Some(panel),
+            } => format!("{} · {}", name, panel),
+        }
+    }
+
+    /// Returns the message a tap on the chip emits, if it does anything.
+    #[must_use]
+    pub fn message(&self, default_panel_id: &str) -> Option<RendererMessage> {
+        match self {
+            Self::Modifier(modifier, _) => Some(RendererMessage::ClearModifier(*modifier)),
+            Self::CapsWord => Some(RendererMessage::ClearModifier(Modifier::Shift)),
+            Self::Layout { panel: None, .. } => None,
+            Self::Layout { panel: Some(_), .. } => {
+                Some(RendererMessage::SwitchPanel(default_panel_id.to_string()))
+            }
+        }
+    }
+}
+
+/// Returns the chips of the status strip: latched modifiers, caps-word,
+/// then the layout.
+#[must_use]
+pub fn status_chips(state: &KeyboardRenderer) -> Vec<StatusChip> {
+    let mut chips: Vec<StatusChip> = MODIFIERS
+        .into_iter()
+        .filter_map(|modifier| match state.modifier_mode(modifier) {
+            Some(ModifierMode::Held) | None => None,
+            Some(mode) => Some(StatusChip::Modifier(modifier, mode)),
+        })
+        .collect();
+    if state.is_caps_word_active() {
+        chips.push(StatusChip::CapsWord);
+    }
+    let panel = &state.current_panel_id;
+    chips.push(StatusChip::Layout {
+        name: state.layout.name.clone(),
+        panel: (*panel != state.layout.default_panel_id).then(|| panel.clone()),
+    });
+    chips
+}
+
+/// Renders the status strip.
+///
+/// # Arguments
+///
+/// * `state` - The keyboard renderer state (for modifiers and panels)
+///
+/// # Returns
+///
+/// An Element [`STATUS_STRIP_HEIGHT`] high, with a chip per entry of
+/// [`status_chips`].
+pub fn render_status_strip<'a>(state: &KeyboardRenderer) -> Element<'a, RendererMessage> {
+    let default_panel_id = &state.layout.default_panel_id;
+    let mut chips = widget::row::row().spacing(6).align_y(Alignment::Center);
+    for chip in status_chips(state) {
+        // Modifier chips are drawn like their keys
+        let class = match chip {
+            StatusChip::Modifier(Modifier::CapsLock, _) => state.key_style.caps_lock_class(),
+            StatusChip::Modifier(_, mode) => state.key_style.modifier_class(mode, 1.0),
+            StatusChip::CapsWord => state.key_style.button_class(true),
+            StatusChip::Layout { .. } => cosmic::style::Button::Text,
+        };
+        let mut button = button::custom(widget::text::caption(chip.label()))
+            .padding(Padding::new(2.0).left(10.0).right(10.0))
+            .class(class);
+        if let Some(message) = chip.message(default_panel_id) {
+            button = button.on_press(message);
+        }
+        chips = chips.push(button);
+    }
+
+    container(chips)
+        .width(Length::Fill)
+        .height(Length::Fixed(STATUS_STRIP_HEIGHT))
+        .padding(Padding::new(2.0).left(8.0).right(8.0))
+        .align_y(Alignment::Center)
+        .into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::layout::{Layout, Panel};
+
+    fn create_test_layout() -> Layout {
+        let mut panels = HashMap::new();
+        for id in ["main", "symbols"] {
+            panels.insert(
+                id.to_string(),
+                Panel {
+                    id: id.to_string(),
+                    ..Panel::default()
+                },
+            );
+        }
+        Layout {
+            name: "QWERTY".to_string(),
+            default_panel_id: "main".to_string(),
+            panels,
+            ..Layout::default()
+        }
+    }
+
+    /// Test: Latched modifiers and caps-word get chips before the layout;
+    /// held modifiers do not.
+    #[test]
+    fn test_status_chips() {
+        let mut state = KeyboardRenderer::new(create_test_layout());
+        let layout_chip = StatusChip::Layout {
+            name: "QWERTY".to_string(),
+            panel: None,
+        };
+        assert_eq!(status_chips(&state), std::slice::from_ref(&layout_chip));
+
+        state.activate_modifier(Modifier::Alt, false);
+        state.activate_modifier(Modifier::Ctrl, true);
+        state.hold_modifier(Modifier::Super);
+        assert_eq!(
+            status_chips(&state),
+            [
+                StatusChip::Modifier(Modifier::Ctrl, ModifierMode::OneShot),
+                StatusChip::Modifier(Modifier::Alt, ModifierMode::Locked),
+                layout_chip,
+            ]
+        );
+
+        state.current_panel_id = "symbols".to_string();
+        let chips = status_chips(&state);
+        assert_eq!(chips[2].label(), "QWERTY · symbols");
+        // Caps Lock stays locked until its key is tapped again
+        state.toggle_caps_lock("caps");
+        assert!(status_chips(&state).contains(&StatusChip::Modifier(
+            Modifier::CapsLock,
+            ModifierMode::Locked
+        )));
+    }
+
+    /// Test: Modifier chips clear their modifier, the layout chip goes back
+    /// to the default panel from another one.
+    #[test]
+    fn test_status_chip_messages() {
+        let chip = StatusChip::Modifier(Modifier::Ctrl, ModifierMode::Locked);
+        assert_eq!(
+            chip.message("main"),
+            Some(RendererMessage::ClearModifier(Modifier::Ctrl))
+        );
+        assert_eq!(
+            StatusChip::CapsWord.message("main"),
+            Some(RendererMessage::ClearModifier(Modifier::Shift))
+        );
+
+        let mut layout_chip = StatusChip::Layout {
+            name: "QWERTY".to_string(),
+            panel: None,
+        };
+        assert_eq!(layout_chip.message("main"), None);
+        layout_chip = StatusChip::Layout {
+            name: "QWERTY".to_string(),
+            panel: Some("symbols".to_string()),
+        };
+        assert_eq!(
+            layout_chip.message("main"),
+            Some(RendererMessage::SwitchPanel("main".to_string()))
+        );
+    }
+}
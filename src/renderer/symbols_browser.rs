@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Symbols browser rendering for the keyboard layout renderer.
+//!
+//! This module renders the `symbols_browser` widget used by the built-in
+//! `unicode_symbols` panel: a row of category tabs above a scrollable grid
+//! of symbols from the bundled Unicode block table. Only the grid rows in
+//! view are built (see [`crate::renderer::virtualize`]).
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, button, container};
+use cosmic::Element;
+
+use crate::layout::Widget;
+use crate::renderer::message::RendererMessage;
+use crate::renderer::sizing::resolve_sizing;
+use crate::renderer::state::KeyboardRenderer;
+use crate::renderer::virtualize::{render_virtual_rows, uniform_row_window};
+use crate::symbols::{self, SymbolCategory, SYMBOLS_BROWSER_WIDGET};
+
+/// Spacing between tabs and grid cells in pixels.
+const GRID_SPACING: f32 = 4.0;
+
+/// Share of the widget height taken by the category tabs.
+const TABS_HEIGHT_RATIO: f32 = 0.25;
+
+/// Returns the number of grid columns that fit in `width`.
+///
+/// Cells are one base unit wide; at least one column is always returned.
+#[must_use]
+pub fn grid_columns(width: f32, cell_size: f32) -> usize {
+    if cell_size <= 0.0 {
+        return 1;
+    }
+    (((width + GRID_SPACING) / (cell_size + GRID_SPACING)).floor() as usize).max(1)
+}
+
+/// Renders the symbols browser widget.
+///
+/// Tapping a tab emits `SelectSymbolCategory`; tapping a symbol emits
+/// `InsertText` with it.
+///
+/// # Arguments
+///
+/// * `widget` - The widget definition from the layout
+/// * `state` - The keyboard renderer state (for the selected category)
+/// * `base_unit` - The calculated base unit for relative sizing
+/// * `scale` - HDPI scale factor for pixel sizing
+///
+/// # Returns
+///
+/// An Element containing the tabs and the symbol grid.
+pub fn render_symbols_browser<'a>(
+    widget: &Widget,
+    state: &KeyboardRenderer,
+    base_unit: f32,
+    scale: f32,
+) -> Element<'a, RendererMessage> {
+    let width = resolve_sizing(&widget.width, base_unit, scale);
+    let height = resolve_sizing(&widget.height, base_unit, scale);
+    let tabs_height = height * TABS_HEIGHT_RATIO;
+
+    // Category tabs
+    let mut tabs = widget::row::row().spacing(GRID_SPACING);
+    for category in SymbolCategory::ALL {
+        let tab = button::custom(
+            container(widget::text::body(category.label()))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Alignment::Center)
+                .align_y(Alignment::Center),
+        )
+        .on_press(RendererMessage::SelectSymbolCategory(category))
+        .class(if category == state.symbol_category {
+            cosmic::style::Button::Suggested
+        } else {
+            cosmic::style::Button::Standard
+        })
+        .width(Length::Fill)
+        .height(Length::Fixed(tabs_height));
+
+        tabs = tabs.push(tab);
+    }
+
+    // Symbol grid, one base unit per cell, built only around the rows in view
+    let columns = grid_columns(width, base_unit);
+    let symbols = symbols::symbols(state.symbol_category);
+    let window = uniform_row_window(
+        symbols.len().div_ceil(columns),
+        base_unit,
+        GRID_SPACING,
+        state.scroll_offset(SYMBOLS_BROWSER_WIDGET),
+        height - tabs_height - GRID_SPACING,
+    );
+    let chunks = symbols
+        .chunks(columns)
+        .skip(window.rows.start)
+        .take(window.rows.len());
+    let mut grid_rows = Vec::with_capacity(window.rows.len());
+    for chunk in chunks {
+        let mut grid_row = widget::row::row().spacing(GRID_SPACING);
+        for &symbol in chunk {
+            let cell = button::custom(
+                container(widget::text::title4(symbol.to_string()))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Alignment::Center)
+                    .align_y(Alignment::Center),
+            )
+            .on_press(RendererMessage::InsertText(symbol.to_string()))
+            .class(cosmic::style::Button::Standard)
+            .width(Length::Fixed(base_unit))
+            .height(Length::Fixed(base_unit));
+
+            grid_row = grid_row.push(cell);
+        }
+        grid_rows.push(grid_row.into());
+    }
+    let grid = render_virtual_rows(
+        SYMBOLS_BROWSER_WIDGET,
+        &window,
+        grid_rows,
+        GRID_SPACING,
+        Length::Fill,
+    );
+
+    let content = widget::column::column()
+        .spacing(GRID_SPACING)
+        .push(tabs)
+        .push(grid);
+
+    container(content)
+        .width(Length::Fixed(width))
+        .height(Length::Fixed(height))
+        .into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Grid columns account for spacing and never drop to zero.
+    #[test]
+    fn test_grid_columns() {
+        assert_eq!(grid_columns(100.0, 48.0), 2);
+        assert_eq!(grid_columns(96.0, 48.0), 1);
+        assert_eq!(grid_columns(10.0, 48.0), 1);
+        assert_eq!(grid_columns(100.0, 0.0), 1);
+    }
+}
@@ -16,9 +16,30 @@
 //! - `key_text_color`: Text color for key labels
 //! - `sticky_active_color`: Background for active sticky keys (Shift, Ctrl, etc.)
 //! - `toast_background_color`: Background for toast notifications
+//!
+//! [`heat_color`] does not depend on the theme: the heat map uses the same
+//! cold-to-hot scale in every theme so screenshots can be compared.
+//!
+//! # Overrides
+//!
+//! The `theme` configuration entry ([`ThemeOverrides`]) replaces the key
+//! background, pressed, text and sticky-active colors, the key corner radius
+//! and the gap between keys:
+//!
+//! ```ron
+//! (key_background: Some("#303446"), key_text: Some("#c6d0f5"), corner_radius: Some(8))
+//! ```
+//!
+//! Colors are `#RRGGBB` or `#RRGGBBAA`. Anything left unset, or a color that
+//! does not parse, keeps the COSMIC theme's value.
 
 use cosmic::iced::Color;
+use cosmic::widget::button;
 use cosmic::Theme;
+use serde::{Deserialize, Serialize};
+
+use crate::input::ModifierMode;
+use crate::layout::Layout;
 
 // ============================================================================
 // Public API
@@ -205,6 +226,305 @@ pub fn keyboard_background_color(theme: &Theme) -> Color {
     Color::from(cosmic.bg_color())
 }
 
+/// Returns the color of the navigation focus ring.
+///
+/// Uses the accent color, like a pressed key, so the focused key stands out
+/// in every theme.
+pub fn focus_ring_color(theme: &Theme) -> Color {
+    Color::from(theme.cosmic().accent_color())
+}
+
+/// Returns the tint of a key at a heat level (see
+/// [`crate::stats::heat_level`]).
+///
+/// Blends from a translucent blue for keys never pressed to an opaque red
+/// for the most pressed keys, over the key's normal background.
+pub fn heat_color(level: u8) -> Color {
+    let t = f32::from(level.min(crate::stats::HEAT_LEVELS))
+        / f32::from(crate::stats::HEAT_LEVELS);
+
+    // Blue (cold) through yellow to red (hot)
+    let (r, g, b) = if t < 0.5 {
+        let u = t * 2.0;
+        (u, 0.4 + 0.45 * u, 1.0 - u)
+    } else {
+        let u = (t - 0.5) * 2.0;
+        (1.0, 0.85 * (1.0 - u), 0.0)
+    };
+    Color::from_rgba(r, g, b, 0.25 + 0.5 * t)
+}
+
+// ============================================================================
+// Overrides
+// ============================================================================
+
+/// Key colors and shape chosen by the user, from the `theme` configuration
+/// entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeOverrides {
+    /// Background of keys, as `#RRGGBB` or `#RRGGBBAA`.
+    pub key_background: Option<String>,
+    /// Background of pressed keys.
+    pub key_pressed: Option<String>,
+    /// Color of key labels and icons.
+    pub key_text: Option<String>,
+    /// Background of active sticky keys (Shift, Ctrl, ...) and of Shift
+    /// while caps-word is on.
+    pub sticky_active: Option<String>,
+    /// Corner radius of keys, in pixels.
+    pub corner_radius: Option<u32>,
+    /// Gap between keys and between rows, in pixels; replaces the `margin`
+    /// of every panel.
+    pub key_gap: Option<u32>,
+}
+
+impl ThemeOverrides {
+    /// Returns the key style to draw with, leaving out colors that do not
+    /// parse.
+    #[must_use]
+    pub fn key_style(&self) -> KeyStyle {
+        let color = |name: &str, value: &Option<String>| {
+            let value = value.as_deref()?;
+            let color = parse_color(value);
+            if color.is_none() {
+                tracing::warn!("Ignoring theme.{}: '{}' is not a color", name, value);
+            }
+            color
+        };
+        KeyStyle {
+            background: color("key_background", &self.key_background),
+            pressed: color("key_pressed", &self.key_pressed),
+            text: color("key_text", &self.key_text),
+            sticky_active: color("sticky_active", &self.sticky_active),
+            corner_radius: self.corner_radius,
+        }
+    }
+
+    /// Sets the margin of every panel of `layout` to the key gap, if one is
+    /// set.
+    pub fn apply_key_gap(&self, layout: &mut Layout) {
+        if let Some(gap) = self.key_gap {
+            for panel in layout.panels.values_mut() {
+                panel.margin = Some(gap as f32);
+            }
+        }
+    }
+}
+
+/// Parses a `#RRGGBB` or `#RRGGBBAA` color into `0xRRGGBBAA`.
+#[must_use]
+pub fn parse_color(value: &str) -> Option<u32> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let rgba = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some((rgba << 8) | 0xFF),
+        8 => Some(rgba),
+        _ => None,
+    }
+}
+
+/// Width of the outline of a key whose modifier is one-shot, in pixels.
+pub const ONE_SHOT_BORDER_WIDTH: f32 = 2.0;
+
+/// Width of the frame of a Caps Lock key while Caps Lock is latched, in
+/// pixels.
+pub const CAPS_LOCK_BORDER_WIDTH: f32 = 3.0;
+
+/// Returns the color `amount` (0.0 to 1.0) of the way from `from` to `to`.
+#[must_use]
+pub fn mix_color(from: Color, to: Color, amount: f32) -> Color {
+    let amount = amount.clamp(0.0, 1.0);
+    let mix = |a: f32, b: f32| a + (b - a) * amount;
+    Color {
+        r: mix(from.r, to.r),
+        g: mix(from.g, to.g),
+        b: mix(from.b, to.b),
+        a: mix(from.a, to.a),
+    }
+}
+
+/// Converts a `0xRRGGBBAA` color.
+#[must_use]
+pub fn rgba_color(rgba: u32) -> Color {
+    let [r, g, b, a] = rgba.to_be_bytes();
+    Color::from_rgba8(r, g, b, f32::from(a) / 255.0)
+}
+
+/// Key colors and corner radius after the overrides were parsed.
+///
+/// Unset fields keep the COSMIC theme's value. Small and hashable, so it is
+/// part of every key's [`KeyVisual`](super::key::KeyVisual).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct KeyStyle {
+    /// Key background, as `0xRRGGBBAA`.
+    pub background: Option<u32>,
+    /// Pressed key background.
+    pub pressed: Option<u32>,
+    /// Label color.
+    pub text: Option<u32>,
+    /// Active sticky key background.
+    pub sticky_active: Option<u32>,
+    /// Corner radius in pixels.
+    pub corner_radius: Option<u32>,
+}
+
+impl KeyStyle {
+    /// Returns `true` if nothing is overridden, so keys use the standard
+    /// button styles.
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the background of a key, `highlighted` for an active sticky
+    /// key.
+    ///
+    /// Active sticky keys default to the accent color, like the suggested
+    /// button style they are otherwise drawn with.
+    #[must_use]
+    pub fn background_color(&self, theme: &Theme, highlighted: bool) -> Color {
+        if highlighted {
+            self.sticky_active
+                .map_or_else(|| key_pressed_color(theme), rgba_color)
+        } else {
+            self.background
+                .map_or_else(|| key_background_color(theme), rgba_color)
+        }
+    }
+
+    /// Returns the background of a pressed key.
+    #[must_use]
+    pub fn pressed_color(&self, theme: &Theme) -> Color {
+        self.pressed
+            .map_or_else(|| key_pressed_color(theme), rgba_color)
+    }
+
+    /// Returns the label color of a key, `highlighted` for an active sticky
+    /// key.
+    #[must_use]
+    pub fn text_color(&self, theme: &Theme, highlighted: bool) -> Color {
+        match self.text {
+            Some(rgba) => rgba_color(rgba),
+            None if highlighted => Color::from(theme.cosmic().on_accent_color()),
+            None => key_text_color(theme),
+        }
+    }
+
+    /// Returns the corner radius of keys, in pixels.
+    #[must_use]
+    pub fn corner_radius(&self, theme: &Theme) -> f32 {
+        self.corner_radius
+            .map_or(theme.cosmic().corner_radii.radius_s[0], |radius| {
+                radius as f32
+            })
+    }
+
+    /// Returns the button style of a key, `highlighted` for an active sticky
+    /// key.
+    #[must_use]
+    pub fn button_class(self, highlighted: bool) -> cosmic::style::Button {
+        if self.is_default() {
+            return if highlighted {
+                cosmic::style::Button::Suggested
+            } else {
+                cosmic::style::Button::Standard
+            };
+        }
+        let style = move |theme: &Theme, background: Color| button::Style {
+            background: Some(background.into()),
+            border_radius: self.corner_radius(theme).into(),
+            text_color: Some(self.text_color(theme, highlighted)),
+            icon_color: Some(self.text_color(theme, highlighted)),
+            ..button::Style::new()
+        };
+        let active = move |_focused: bool, theme: &Theme| {
+            style(theme, self.background_color(theme, highlighted))
+        };
+        cosmic::style::Button::Custom {
+            active: Box::new(active),
+            disabled: Box::new(move |theme: &Theme| active(false, theme)),
+            hovered: Box::new(active),
+            pressed: Box::new(move |_focused: bool, theme: &Theme| {
+                style(theme, self.pressed_color(theme))
+            }),
+        }
+    }
+
+    /// Returns the button style of an active modifier key.
+    ///
+    /// Locked modifiers are filled like other active sticky keys, one-shot
+    /// modifiers are outlined in the sticky-active color, and held modifiers
+    /// are filled `pulse` (0.0 to 1.0) of the way to it, so they pulse as the
+    /// key is redrawn.
+    #[must_use]
+    pub fn modifier_class(self, mode: ModifierMode, pulse: f32) -> cosmic::style::Button {
+        let held = match mode {
+            ModifierMode::Locked => return self.button_class(true),
+            ModifierMode::OneShot => false,
+            ModifierMode::Held => true,
+        };
+        let style = move |theme: &Theme, pressed: bool| {
+            let accent = self.background_color(theme, true);
+            let idle = self.background_color(theme, false);
+            let (background, border_width, highlighted) = if held {
+                (mix_color(idle, accent, pulse), 0.0, pulse >= 0.5)
+            } else if pressed {
+                (self.pressed_color(theme), ONE_SHOT_BORDER_WIDTH, false)
+            } else {
+                (idle, ONE_SHOT_BORDER_WIDTH, false)
+            };
+            button::Style {
+                background: Some(background.into()),
+                border_radius: self.corner_radius(theme).into(),
+                border_width,
+                border_color: accent,
+                text_color: Some(self.text_color(theme, highlighted)),
+                icon_color: Some(self.text_color(theme, highlighted)),
+                ..button::Style::new()
+            }
+        };
+        let active = move |_focused: bool, theme: &Theme| style(theme, false);
+        cosmic::style::Button::Custom {
+            active: Box::new(active),
+            disabled: Box::new(move |theme: &Theme| active(false, theme)),
+            hovered: Box::new(active),
+            pressed: Box::new(move |_focused: bool, theme: &Theme| style(theme, true)),
+        }
+    }
+
+    /// Returns the button style of a Caps Lock key while Caps Lock is
+    /// latched.
+    ///
+    /// The key is filled like a locked modifier and framed in the label
+    /// color, so a latched Caps Lock reads apart from a locked Shift.
+    #[must_use]
+    pub fn caps_lock_class(self) -> cosmic::style::Button {
+        let style = move |theme: &Theme, background: Color| button::Style {
+            background: Some(background.into()),
+            border_radius: self.corner_radius(theme).into(),
+            border_width: CAPS_LOCK_BORDER_WIDTH,
+            border_color: self.text_color(theme, true),
+            text_color: Some(self.text_color(theme, true)),
+            icon_color: Some(self.text_color(theme, true)),
+            ..button::Style::new()
+        };
+        let active =
+            move |_focused: bool, theme: &Theme| style(theme, self.background_color(theme, true));
+        cosmic::style::Button::Custom {
+            active: Box::new(active),
+            disabled: Box::new(move |theme: &Theme| active(false, theme)),
+            hovered: Box::new(active),
+            pressed: Box::new(move |_focused: bool, theme: &Theme| {
+                style(theme, self.pressed_color(theme))
+            }),
+        }
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -258,6 +578,69 @@ mod tests {
         validate_color(toast_background_color(&theme), "toast_background");
         validate_color(keyboard_background_color(&theme), "keyboard_background");
         validate_color(key_pressed_border_color(&theme), "key_pressed_border");
+        validate_color(focus_ring_color(&theme), "focus_ring");
+        for level in 0..=crate::stats::HEAT_LEVELS {
+            validate_color(heat_color(level), "heat");
+        }
+    }
+
+    /// Test: Mixing colors goes linearly from one to the other, clamped.
+    #[test]
+    fn test_mix_color() {
+        let black = Color::from_rgba(0.0, 0.0, 0.0, 0.5);
+        let white = Color::WHITE;
+        assert_eq!(mix_color(black, white, 0.0), black);
+        assert_eq!(mix_color(black, white, 1.0), white);
+        assert_eq!(mix_color(black, white, 2.0), white);
+        assert_eq!(
+            mix_color(black, white, 0.5),
+            Color::from_rgba(0.5, 0.5, 0.5, 0.75)
+        );
+    }
+
+    /// Test: Override colors parse from `#RRGGBB` and `#RRGGBBAA`, anything
+    /// else is ignored.
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#303446"), Some(0x303446FF));
+        assert_eq!(parse_color(" #FF000080 "), Some(0xFF000080));
+        assert_eq!(parse_color("303446"), None);
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("#+30344"), None);
+        assert_eq!(parse_color("#zzzzzz"), None);
+
+        let color = rgba_color(0xFF000080);
+        assert_eq!((color.r, color.g, color.b), (1.0, 0.0, 0.0));
+        assert!((color.a - 128.0 / 255.0).abs() < f32::EPSILON);
+    }
+
+    /// Test: Overridden key colors replace the theme's, the others and
+    /// invalid ones keep it.
+    #[test]
+    fn test_theme_overrides() {
+        let theme = Theme::dark();
+        assert!(ThemeOverrides::default().key_style().is_default());
+
+        let style = ThemeOverrides {
+            key_background: Some("#102030".to_string()),
+            key_pressed: Some("red".to_string()),
+            corner_radius: Some(8),
+            ..ThemeOverrides::default()
+        }
+        .key_style();
+        assert!(!style.is_default());
+        assert_eq!(style.pressed, None);
+        assert_eq!(
+            style.background_color(&theme, false),
+            Color::from_rgb8(0x10, 0x20, 0x30)
+        );
+        assert_eq!(style.pressed_color(&theme), key_pressed_color(&theme));
+        assert_eq!(style.text_color(&theme, false), key_text_color(&theme));
+        assert_eq!(
+            style.background_color(&theme, true),
+            key_pressed_color(&theme)
+        );
+        assert_eq!(style.corner_radius(&theme), 8.0);
     }
 
     /// Test: Key background and pressed colors are different
@@ -12,6 +12,7 @@
 //! - Centered message text
 //! - Severity-based color accent (Info, Warning, Error)
 //! - Auto-dismiss after 3 seconds
+//! - Optional action button (e.g. "Accept"), shown for 20 seconds
 //! - Queue-based system for multiple toasts
 //!
 //! # Usage
@@ -36,8 +37,8 @@
 //! );
 //! ```
 
-use cosmic::iced::{alignment, Length, Padding};
-use cosmic::widget::{self, container};
+use cosmic::iced::{alignment, Alignment, Length, Padding};
+use cosmic::widget::{self, button, container};
 use cosmic::Element;
 use cosmic::Theme;
 
@@ -89,8 +90,23 @@ pub fn render_toast<'a>(toast: &Toast, theme: &Theme) -> Element<'a, RendererMes
         .width(Length::Shrink)
         .height(Length::Shrink);
 
+    // Add the action button after the message, if any
+    let content: Element<'a, RendererMessage> = match toast.action {
+        Some(ref action) => widget::row::row()
+            .spacing(12)
+            .align_y(Alignment::Center)
+            .push(message_text)
+            .push(
+                button::custom(widget::text::body(action.label.clone()))
+                    .on_press(RendererMessage::ToastAction(action.id))
+                    .class(cosmic::style::Button::Suggested),
+            )
+            .into(),
+        None => message_text.into(),
+    };
+
     // Wrap in a container with themed background
-    let toast_container = container(message_text)
+    let toast_container = container(content)
         .width(Length::Shrink)
         .height(Length::Fixed(TOAST_HEIGHT))
         .padding(Padding::new(TOAST_PADDING_VERTICAL).left(TOAST_PADDING_HORIZONTAL).right(TOAST_PADDING_HORIZONTAL))
@@ -217,6 +233,9 @@ mod tests {
             padding: Some(5.0),
             margin: Some(2.0),
             nesting_depth: 0,
+            piano: false,
+            visible_rows: None,
+            units: None,
             rows: vec![Row {
                 cells: vec![Cell::Key(Key {
                     label: "A".to_string(),
@@ -229,6 +248,9 @@ mod tests {
                     alternatives: HashMap::new(),
                     sticky: false,
                     stickyrelease: true,
+                    after: None,
+                    feedback: None,
+                    post_keys: Vec::new(),
                 })],
             }],
         };
@@ -243,6 +265,9 @@ mod tests {
             locale: None,
             version: "1.0".to_string(),
             default_panel_id: "main".to_string(),
+            chords: Vec::new(),
+            feedback: None,
+            accent_cycling: None,
             inherits: None,
             panels,
         }
@@ -439,4 +464,19 @@ mod tests {
             let _element = render_toast(&toast, &theme);
         }
     }
+
+    /// Test: Toasts with a button render and stay longer than plain toasts
+    #[test]
+    fn test_toast_with_action() {
+        let theme = Theme::dark();
+        let toast = Toast::warning("Allow typing?").with_action("Accept", 7);
+        assert_eq!(toast.action.as_ref().map(|action| action.id), Some(7));
+        assert!(toast.duration_ms() > Toast::warning("Allow typing?").duration_ms());
+        let _element = render_toast(&toast, &theme);
+
+        let layout = create_test_layout();
+        let mut renderer = KeyboardRenderer::new(layout);
+        renderer.queue(toast);
+        assert!(!renderer.check_toast_timeout());
+    }
 }
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Minimum physical size of keys.
+//!
+//! Keys are sized in base units, so how large they end up on screen depends
+//! on the surface size and on the output's pixel density. A key narrower or
+//! shorter than a fingertip (about 9 mm) is easy to miss, so the applet
+//! checks the visible panel against the `touch_target` configuration entry
+//! whenever the layout, the panel, the surface size or the output changes
+//! ([`check_panel`]). Undersized keys are reported with a toast; with
+//! `auto_height`, the keyboard is made tall enough for keys to reach the
+//! minimum height instead.
+//!
+//! The density comes from the output's physical size, as announced by the
+//! compositor ([`pixels_per_mm`]). Outputs that do not report one
+//! (projectors, virtual outputs) are not checked.
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{Cell, Panel, Sizing};
+use crate::renderer::geometry::{
+    calculate_max_row_width, panel_geometry, panel_height_for_unit, DEFAULT_PADDING,
+};
+
+/// Default minimum key size, in millimetres.
+pub const DEFAULT_MIN_SIZE_MM: u32 = 9;
+
+/// How small keys may get, from the user configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TouchTargetPolicy {
+    /// Smallest key width and height, in millimetres; 0 disables the check.
+    #[serde(default = "default_min_size_mm")]
+    pub min_size_mm: u32,
+    /// Whether the keyboard grows taller when keys are shorter than the
+    /// minimum, instead of only warning.
+    #[serde(default)]
+    pub auto_height: bool,
+}
+
+fn default_min_size_mm() -> u32 {
+    DEFAULT_MIN_SIZE_MM
+}
+
+impl Default for TouchTargetPolicy {
+    fn default() -> Self {
+        Self {
+            min_size_mm: default_min_size_mm(),
+            auto_height: false,
+        }
+    }
+}
+
+impl TouchTargetPolicy {
+    /// Returns `true` if keys are checked at all.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.min_size_mm > 0
+    }
+}
+
+/// Keys of a panel below the minimum size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TouchTargetReport {
+    /// Label of the smallest key.
+    pub smallest_label: String,
+    /// Shorter side of the smallest key, in millimetres.
+    pub smallest_mm: f32,
+    /// Number of keys with a side below the minimum.
+    pub undersized: usize,
+    /// Surface height at which every key is tall enough, if making the
+    /// surface taller is enough (keys too narrow for the surface width stay
+    /// too narrow).
+    pub required_height: Option<f32>,
+}
+
+/// Returns the logical pixels per millimetre of an output, from its logical
+/// size in pixels and its physical size in millimetres.
+///
+/// Returns `None` for outputs with no physical size.
+#[must_use]
+pub fn pixels_per_mm(logical_size: (i32, i32), physical_size: (i32, i32)) -> Option<f32> {
+    // The logical size follows the output's rotation, the physical size
+    // does not: compare the long sides
+    let logical = logical_size.0.max(logical_size.1);
+    let physical = physical_size.0.max(physical_size.1);
+    (logical > 0 && physical > 0).then(|| logical as f32 / physical as f32)
+}
+
+/// Checks the keys of `panel` on a surface of the given size against a
+/// minimum size in millimetres.
+///
+/// Returns `None` if every key is large enough.
+#[must_use]
+pub fn check_panel(
+    panel: &Panel,
+    surface_width: f32,
+    surface_height: f32,
+    scale: f32,
+    pixels_per_mm: f32,
+    min_size_mm: u32,
+) -> Option<TouchTargetReport> {
+    let min_px = min_size_mm as f32 * pixels_per_mm;
+    let geometry = panel_geometry(panel, surface_width, surface_height, scale);
+
+    let mut smallest: Option<(&str, f32)> = None;
+    let mut undersized = 0;
+    // Base unit at which every relatively sized key is tall enough
+    let mut required_unit = geometry.base_unit;
+    for bounds in &geometry.cells {
+        let Cell::Key(key) = bounds.cell else {
+            continue;
+        };
+        let side = bounds.width.min(bounds.height);
+        if side >= min_px {
+            continue;
+        }
+        undersized += 1;
+        if smallest.is_none_or(|(_, smallest)| side < smallest) {
+            smallest = Some((key.label.as_str(), side));
+        }
+        match key.height {
+            Sizing::Relative(height) if height > 0.0 && bounds.height < min_px => {
+                required_unit = required_unit.max(min_px / height);
+            }
+            _ => {}
+        }
+    }
+    let (label, side) = smallest?;
+
+    // A taller surface only helps while the width does not limit the unit
+    let padding = panel.padding.unwrap_or(DEFAULT_PADDING);
+    let width_unit = (surface_width - padding * 2.0) / calculate_max_row_width(panel);
+    let required_height = (required_unit > geometry.base_unit && required_unit <= width_unit)
+        .then(|| panel_height_for_unit(panel, required_unit).ceil());
+
+    Some(TouchTargetReport {
+        smallest_label: label.to_string(),
+        smallest_mm: side / pixels_per_mm,
+        undersized,
+        required_height,
+    })
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Key, Row};
+
+    fn panel(rows: &[&[(&str, f32)]]) -> Panel {
+        Panel {
+            id: "main".to_string(),
+            padding: Some(0.0),
+            margin: Some(0.0),
+            rows: rows
+                .iter()
+                .map(|keys| Row {
+                    cells: keys
+                        .iter()
+                        .map(|(label, width)| {
+                            Cell::Key(Key {
+                                label: label.to_string(),
+                                width: Sizing::Relative(*width),
+                                ..Key::default()
+                            })
+                        })
+                        .collect(),
+                })
+                .collect(),
+            ..Panel::default()
+        }
+    }
+
+    /// Test: The density is taken from the long sides, whatever the
+    /// rotation, and outputs without a physical size are not checked.
+    #[test]
+    fn test_pixels_per_mm() {
+        assert_eq!(pixels_per_mm((1920, 1080), (480, 270)), Some(4.0));
+        assert_eq!(pixels_per_mm((1080, 1920), (480, 270)), Some(4.0));
+        assert_eq!(pixels_per_mm((1920, 1080), (0, 0)), None);
+        assert!(!TouchTargetPolicy {
+            min_size_mm: 0,
+            auto_height: true
+        }
+        .is_enabled());
+    }
+
+    /// Test: Keys below the minimum are counted, and a taller surface is
+    /// suggested only when the width leaves room for larger keys.
+    #[test]
+    fn test_check_panel() {
+        // Two rows of ten keys: 40 px wide and 30 px tall at 400×60
+        let keys = [("q", 1.0); 10];
+        let mut panel = panel(&[&keys, &keys]);
+        if let Cell::Key(ref mut key) = panel.rows[0].cells[0] {
+            key.label = "tab".to_string();
+            key.width = Sizing::Relative(0.5);
+        }
+
+        // 4 px/mm: 36 px minimum
+        let report = check_panel(&panel, 400.0, 60.0, 1.0, 4.0, 9).unwrap();
+        assert_eq!(report.undersized, 20);
+        assert_eq!(report.smallest_label, "tab");
+        assert_eq!(report.smallest_mm, 15.0 / 4.0);
+        assert_eq!(report.required_height, Some(72.0));
+
+        // Tall enough, but the tab key stays too narrow
+        let report = check_panel(&panel, 400.0, 72.0, 1.0, 4.0, 9).unwrap();
+        assert_eq!(report.undersized, 1);
+        assert_eq!(report.required_height, None);
+
+        // 44 px keys do not fit in the width
+        let report = check_panel(&panel, 400.0, 60.0, 1.0, 4.0, 11).unwrap();
+        assert_eq!(report.required_height, None);
+
+        assert_eq!(check_panel(&panel, 400.0, 60.0, 1.0, 1.0, 9), None);
+    }
+}
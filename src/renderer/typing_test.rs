@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Typing test overlay rendering.
+//!
+//! While a typing test runs, a strip above the keyboard panel shows the
+//! phrase to type, what has been typed so far, and the live speed and
+//! accuracy. Keys typed during the test are intercepted by the renderer's
+//! input focus and never reach the client application.
+
+use std::time::Instant;
+
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, button, container};
+use cosmic::Element;
+
+use crate::renderer::message::RendererMessage;
+use crate::renderer::state::KeyboardRenderer;
+use crate::typing_test::{TypingTest, TYPING_TEST_FIELD};
+
+/// Height of the typing test strip in pixels.
+pub const TYPING_TEST_HEIGHT: f32 = 72.0;
+
+/// Width of the stats area in pixels.
+const STATS_WIDTH: f32 = 160.0;
+
+/// Returns `true` if a typing test is running.
+#[must_use]
+pub fn is_typing_test_active(state: &KeyboardRenderer) -> bool {
+    state.focus.is_focused(TYPING_TEST_FIELD)
+}
+
+/// Renders the typing test strip.
+///
+/// The stats area ends the test when tapped (emits `BlurTextField`).
+///
+/// # Arguments
+///
+/// * `state` - The keyboard renderer state (for the typed buffer)
+/// * `test` - The running typing test
+/// * `now` - Current time, for live speed
+///
+/// # Returns
+///
+/// An Element containing the phrase, the typed text and the stats.
+pub fn render_typing_test<'a>(
+    state: &KeyboardRenderer,
+    test: &TypingTest,
+    now: Instant,
+) -> Element<'a, RendererMessage> {
+    let typed = state.focus.buffer(TYPING_TEST_FIELD);
+    let stats = test.stats(now);
+
+    let texts = widget::column::column()
+        .spacing(2)
+        .push(widget::text::title4(test.phrase()))
+        .push(widget::text::body(format!("{}|", typed)))
+        .width(Length::Fill);
+
+    let summary = if test.is_complete() {
+        format!(
+            "{:.0} WPM · {:.0}%\nEnter: next · Esc: done",
+            stats.wpm,
+            stats.accuracy * 100.0
+        )
+    } else {
+        format!("{:.0} WPM · {:.0}%", stats.wpm, stats.accuracy * 100.0)
+    };
+
+    let stats_button = button::custom(
+        container(widget::text::body(summary))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Alignment::Center)
+            .align_y(Alignment::Center),
+    )
+    .on_press(RendererMessage::BlurTextField)
+    .class(if test.is_complete() {
+        cosmic::style::Button::Suggested
+    } else {
+        cosmic::style::Button::Standard
+    })
+    .width(Length::Fixed(STATS_WIDTH))
+    .height(Length::Fill);
+
+    container(
+        widget::row::row()
+            .spacing(8)
+            .align_y(Alignment::Center)
+            .push(texts)
+            .push(stats_button),
+    )
+    .padding(4)
+    .width(Length::Fill)
+    .height(Length::Fixed(TYPING_TEST_HEIGHT))
+    .class(cosmic::style::Container::Background)
+    .into()
+}
@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Lazy row virtualization for scrolling panels and grids.
+//!
+//! A panel with thousands of cells (e.g. a full emoji set) would take longer
+//! to build into widgets than a frame lasts. Scrolling panels and grids
+//! therefore only build the rows in view, plus [`OVERSCAN_ROWS`] on each
+//! side so a fast fling does not show empty space before the next frame.
+//! The rows above and below are replaced by empty space of the same height,
+//! so the scrollbar and scroll position behave as if every row was there.
+//!
+//! The scroll offset of each scroll area is kept in the renderer state
+//! ([`KeyboardRenderer::scroll_offset`]); the scrollable reports every move
+//! with [`RendererMessage::Scrolled`].
+//!
+//! [`KeyboardRenderer::scroll_offset`]: crate::renderer::KeyboardRenderer::scroll_offset
+
+use std::ops::Range;
+
+use cosmic::iced::Length;
+use cosmic::widget::{self, Space};
+use cosmic::Element;
+
+use crate::renderer::message::RendererMessage;
+
+/// Rows built beyond each edge of the viewport.
+pub const OVERSCAN_ROWS: usize = 2;
+
+/// Rows of a scroll area to build, and the space standing in for the rest.
+///
+/// The spaces assume the rows are stacked in a column with `spacing`
+/// between children, as [`render_virtual_rows`] does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowWindow {
+    /// Indices of the rows to build.
+    pub rows: Range<usize>,
+    /// Height of the space above the first built row (0 if none is needed).
+    pub space_before: f32,
+    /// Height of the space below the last built row (0 if none is needed).
+    pub space_after: f32,
+}
+
+/// Returns the rows of a column that are in view.
+///
+/// `row_heights` are the heights of every row, `offset` is the scroll
+/// offset and `viewport_height` the visible height, all in pixels.
+#[must_use]
+pub fn row_window(
+    row_heights: &[f32],
+    spacing: f32,
+    offset: f32,
+    viewport_height: f32,
+) -> RowWindow {
+    let count = row_heights.len();
+    if count == 0 {
+        return RowWindow {
+            rows: 0..0,
+            space_before: 0.0,
+            space_after: 0.0,
+        };
+    }
+
+    // Top of every row, and of the end of the column
+    let mut tops = Vec::with_capacity(count + 1);
+    let mut y = 0.0;
+    for height in row_heights {
+        tops.push(y);
+        y += height + spacing;
+    }
+    tops.push(y);
+
+    let offset = offset.max(0.0);
+    let first = (0..count)
+        .find(|&row| tops[row] + row_heights[row] > offset)
+        .unwrap_or(count - 1);
+    let end = (first..count)
+        .find(|&row| tops[row] >= offset + viewport_height)
+        .unwrap_or(count);
+
+    window(
+        first.saturating_sub(OVERSCAN_ROWS)..(end + OVERSCAN_ROWS).min(count),
+        &tops,
+        spacing,
+    )
+}
+
+/// Returns the rows in view of a column of `count` rows of equal height.
+///
+/// Unlike [`row_window`], this does not depend on the number of rows, for
+/// grids of thousands of cells.
+#[must_use]
+pub fn uniform_row_window(
+    count: usize,
+    row_height: f32,
+    spacing: f32,
+    offset: f32,
+    viewport_height: f32,
+) -> RowWindow {
+    let pitch = row_height + spacing;
+    if count == 0 || pitch <= 0.0 {
+        return RowWindow {
+            rows: 0..count,
+            space_before: 0.0,
+            space_after: 0.0,
+        };
+    }
+
+    // First row whose bottom is below the top of the viewport
+    let offset = offset.max(0.0);
+    let first = if offset < row_height {
+        0
+    } else {
+        (((offset - row_height) / pitch).floor() as usize + 1).min(count - 1)
+    };
+    let end = (((offset + viewport_height) / pitch).ceil() as usize).clamp(first + 1, count);
+    let rows = first.saturating_sub(OVERSCAN_ROWS)..(end + OVERSCAN_ROWS).min(count);
+
+    let top = |row: usize| row as f32 * pitch;
+    RowWindow {
+        space_before: if rows.start > 0 {
+            top(rows.start) - spacing
+        } else {
+            0.0
+        },
+        space_after: if rows.end < count {
+            top(count) - spacing - top(rows.end)
+        } else {
+            0.0
+        },
+        rows,
+    }
+}
+
+/// Builds the window for `rows`, given the top of every row and of the end.
+fn window(rows: Range<usize>, tops: &[f32], spacing: f32) -> RowWindow {
+    let count = tops.len() - 1;
+    RowWindow {
+        // The column adds `spacing` between the space and the first row
+        space_before: if rows.start > 0 {
+            tops[rows.start] - spacing
+        } else {
+            0.0
+        },
+        space_after: if rows.end < count {
+            tops[count] - spacing - tops[rows.end]
+        } else {
+            0.0
+        },
+        rows,
+    }
+}
+
+/// Stacks the built rows of `window` in a vertical scrollable.
+///
+/// `rows` are the elements of the rows in `window.rows`, in order. Scrolling
+/// emits [`RendererMessage::Scrolled`] with `scroll_id` and the new offset.
+pub fn render_virtual_rows<'a>(
+    scroll_id: &str,
+    window: &RowWindow,
+    rows: impl IntoIterator<Item = Element<'a, RendererMessage>>,
+    spacing: f32,
+    height: Length,
+) -> Element<'a, RendererMessage> {
+    let mut column = widget::column::column().spacing(spacing);
+    if window.space_before > 0.0 {
+        column = column.push(Space::new(Length::Shrink, window.space_before));
+    }
+    for row in rows {
+        column = column.push(row);
+    }
+    if window.space_after > 0.0 {
+        column = column.push(Space::new(Length::Shrink, window.space_after));
+    }
+
+    let scroll_id = scroll_id.to_string();
+    widget::scrollable(column)
+        .on_scroll(move |viewport| {
+            let offset = viewport.absolute_offset().y.max(0.0).round() as u32;
+            RendererMessage::Scrolled(scroll_id.clone(), offset)
+        })
+        .height(height)
+        .into()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Total height of a column built from `window` (spaces, rows, spacing).
+    fn column_height(window: &RowWindow, row_heights: &[f32], spacing: f32) -> f32 {
+        let mut children: Vec<f32> = Vec::new();
+        if window.space_before > 0.0 {
+            children.push(window.space_before);
+        }
+        children.extend(&row_heights[window.rows.clone()]);
+        if window.space_after > 0.0 {
+            children.push(window.space_after);
+        }
+        children.iter().sum::<f32>() + spacing * children.len().saturating_sub(1) as f32
+    }
+
+    /// Test: Only the rows in view and the overscan are built.
+    #[test]
+    fn test_row_window() {
+        let heights = vec![40.0; 1000];
+        let full_height = 1000.0 * 44.0 - 4.0;
+
+        let top = row_window(&heights, 4.0, 0.0, 200.0);
+        assert_eq!(top.rows, 0..(5 + OVERSCAN_ROWS));
+        assert_eq!(top.space_before, 0.0);
+        assert_eq!(column_height(&top, &heights, 4.0), full_height);
+
+        // Row 100 starts at 4400
+        let middle = row_window(&heights, 4.0, 4400.0, 200.0);
+        assert_eq!(middle.rows, (100 - OVERSCAN_ROWS)..(105 + OVERSCAN_ROWS));
+        assert_eq!(column_height(&middle, &heights, 4.0), full_height);
+
+        let bottom = row_window(&heights, 4.0, full_height, 200.0);
+        assert_eq!(bottom.rows.end, 1000);
+        assert_eq!(bottom.space_after, 0.0);
+        assert_eq!(column_height(&bottom, &heights, 4.0), full_height);
+
+        assert_eq!(row_window(&[], 4.0, 0.0, 200.0).rows, 0..0);
+    }
+
+    /// Test: Equal rows give the same window without measuring each row.
+    #[test]
+    fn test_uniform_row_window() {
+        let heights = vec![40.0; 1000];
+        for offset in [0.0, 130.0, 4400.0, 43_000.0, 1e9] {
+            assert_eq!(
+                uniform_row_window(1000, 40.0, 4.0, offset, 200.0),
+                row_window(&heights, 4.0, offset, 200.0),
+                "offset {}",
+                offset
+            );
+        }
+        assert_eq!(uniform_row_window(0, 40.0, 4.0, 0.0, 200.0).rows, 0..0);
+    }
+}
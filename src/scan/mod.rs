@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! "Scan to type": decode a QR code or barcode and type its text.
+//!
+//! Typing a Wi-Fi password or a serial number on a touch screen is slow and
+//! error prone. The `scan` layout widget (see [`SCAN_WIDGET`]) dispatches
+//! [`AppAction::ScanCode`], which asks the desktop for an image through the
+//! file chooser portal ([`portal::pick_image`]) — a photo of the code, or a
+//! screenshot of one. [`AppAction::ScanImage`] decodes a given image file
+//! instead, for command keys and action aliases. Both type text, so the
+//! D-Bus `RunAction` method refuses them like other typing actions.
+//!
+//! The image is decoded by [`decode_file`] (QR codes and the common 1D
+//! barcodes) and the text typed through the usual emission path. Wi-Fi
+//! network codes (`WIFI:T:WPA;S:home;P:secret;;`) type only their password,
+//! which is what a password field wants ([`ScannedText::from_payload`]).
+//!
+//! Live capture through the camera portal is not supported: the portal only
+//! hands out a PipeWire video stream, and reading frames from it would add a
+//! PipeWire dependency for one widget.
+//!
+//! Available with the `scan` feature.
+//!
+//! [`AppAction::ScanCode`]: crate::actions::AppAction::ScanCode
+//! [`AppAction::ScanImage`]: crate::actions::AppAction::ScanImage
+
+use std::fmt;
+use std::path::Path;
+
+use crate::fl;
+
+pub mod portal;
+
+/// Widget type name for the scan button in layout files.
+pub const SCAN_WIDGET: &str = "scan";
+
+/// Maximum number of characters typed from one code.
+///
+/// QR codes hold at most a few thousand characters; anything longer is not
+/// meant to be typed.
+pub const MAX_SCANNED_CHARS: usize = 4_096;
+
+/// Error scanning a code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError {
+    /// The user closed the file chooser without picking an image.
+    Cancelled,
+    /// The file chooser portal is unavailable or failed.
+    Portal(String),
+    /// No code could be found in the image, or the image cannot be read.
+    Decode(String),
+    /// The code holds no text that can be typed.
+    Empty,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Cancelled => write!(f, "Scan cancelled"),
+            ScanError::Portal(e) => write!(f, "File chooser portal failed: {}", e),
+            ScanError::Decode(e) => write!(f, "No code found: {}", e),
+            ScanError::Empty => write!(f, "The code holds no text"),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+impl ScanError {
+    /// Returns the error message in the user's language, for toasts.
+    #[must_use]
+    pub fn localized(&self) -> String {
+        match self {
+            ScanError::Cancelled => fl!("scan-cancelled"),
+            ScanError::Portal(_) => fl!("scan-portal-failed"),
+            ScanError::Decode(_) => fl!("scan-no-code"),
+            ScanError::Empty => fl!("scan-empty"),
+        }
+    }
+}
+
+/// Text decoded from a code, ready to be typed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedText {
+    /// The text to type.
+    pub text: String,
+    /// Whether the text is the password of a Wi-Fi network code.
+    pub wifi_password: bool,
+}
+
+impl ScannedText {
+    /// Turns the raw payload of a code into the text to type.
+    ///
+    /// Wi-Fi network codes yield their password. Otherwise control
+    /// characters other than tabs and line breaks are dropped, trailing
+    /// line breaks are trimmed (scanners often append one) and the text is
+    /// cut at [`MAX_SCANNED_CHARS`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ScanError::Empty`] if nothing is left to type, including
+    /// for Wi-Fi codes of open networks.
+    pub fn from_payload(payload: &str) -> Result<Self, ScanError> {
+        if payload.starts_with("WIFI:") {
+            let password = wifi_password(payload).ok_or(ScanError::Empty)?;
+            return Ok(Self {
+                text: password,
+                wifi_password: true,
+            });
+        }
+
+        let text: String = payload
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .filter(|c| !c.is_control() || matches!(c, '\t' | '\n'))
+            .take(MAX_SCANNED_CHARS)
+            .collect();
+        if text.trim().is_empty() {
+            return Err(ScanError::Empty);
+        }
+        Ok(Self {
+            text,
+            wifi_password: false,
+        })
+    }
+}
+
+/// Returns the password of a Wi-Fi network code
+/// (`WIFI:T:WPA;S:<ssid>;P:<password>;;`), unescaping `\;`, `\,`, `\:`,
+/// `\"` and `\\`.
+///
+/// Returns `None` if `payload` is not a Wi-Fi code or has no password
+/// (open networks).
+fn wifi_password(payload: &str) -> Option<String> {
+    let fields = payload.strip_prefix("WIFI:")?;
+
+    let mut field = String::new();
+    let mut chars = fields.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => field.extend(chars.next()),
+            ';' => {
+                if let Some(password) = field.strip_prefix("P:") {
+                    return Some(password.to_string()).filter(|p| !p.is_empty());
+                }
+                field.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    field
+        .strip_prefix("P:")
+        .map(str::to_string)
+        .filter(|p| !p.is_empty())
+}
+
+/// Decodes the first QR code or barcode found in an image file.
+///
+/// This reads and scans the whole image; call it off the UI thread.
+///
+/// # Errors
+///
+/// Returns [`ScanError::Decode`] if the image cannot be read or holds no
+/// code, and [`ScanError::Empty`] if the code holds no text to type.
+pub fn decode_file(path: &Path) -> Result<ScannedText, ScanError> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| ScanError::Decode(format!("{} is not valid UTF-8", path.display())))?;
+    let result =
+        rxing::helpers::detect_in_file(path, None).map_err(|e| ScanError::Decode(e.to_string()))?;
+    ScannedText::from_payload(result.getText())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Plain codes are cleaned up, empty ones are rejected.
+    #[test]
+    fn test_plain_payload() {
+        let scanned = ScannedText::from_payload("SN-4711-AB\r\n").unwrap();
+        assert_eq!(scanned.text, "SN-4711-AB");
+        assert!(!scanned.wifi_password);
+
+        assert_eq!(
+            ScannedText::from_payload("a\u{7}b\tc\nd").unwrap().text,
+            "ab\tc\nd"
+        );
+        assert_eq!(ScannedText::from_payload(" \n"), Err(ScanError::Empty));
+
+        let long = "x".repeat(MAX_SCANNED_CHARS + 10);
+        assert_eq!(
+            ScannedText::from_payload(&long).unwrap().text.len(),
+            MAX_SCANNED_CHARS
+        );
+    }
+
+    /// Test: Wi-Fi codes type their unescaped password.
+    #[test]
+    fn test_wifi_payload() {
+        let scanned = ScannedText::from_payload("WIFI:T:WPA;S:home;P:secret;;").unwrap();
+        assert_eq!(scanned.text, "secret");
+        assert!(scanned.wifi_password);
+
+        assert_eq!(
+            wifi_password(r"WIFI:S:cafe\;bar;P:p\;a\\s\:s;T:WPA;;").as_deref(),
+            Some(r"p;a\s:s")
+        );
+        assert_eq!(wifi_password("WIFI:S:home;P:last").as_deref(), Some("last"));
+        // Open networks have no password to type
+        assert_eq!(wifi_password("WIFI:T:nopass;S:open;P:;;"), None);
+        assert_eq!(
+            ScannedText::from_payload("WIFI:T:nopass;S:open;;"),
+            Err(ScanError::Empty)
+        );
+    }
+}
@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Picking the image to scan through the XDG file chooser portal.
+//!
+//! The portal works the same inside and outside Flatpak, and its dialog can
+//! usually open the camera roll or take a screenshot. `OpenFile` returns a
+//! request object whose `Response` signal carries the chosen file; the
+//! request path is predicted from a handle token so the signal can be
+//! subscribed to before the call, as the portal documentation asks.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+use std::path::PathBuf;
+
+use futures::StreamExt;
+use zbus::zvariant::{OwnedValue, Value};
+
+use super::ScanError;
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const FILE_CHOOSER_INTERFACE: &str = "org.freedesktop.portal.FileChooser";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+/// MIME types offered by the file chooser filter.
+const IMAGE_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/webp",
+    "image/bmp",
+    "image/gif",
+];
+
+/// Asks the user for an image through the file chooser portal.
+///
+/// # Errors
+///
+/// Returns [`ScanError::Cancelled`] if the dialog is closed without a
+/// choice and [`ScanError::Portal`] if the portal cannot be reached or the
+/// chosen file is not local.
+pub async fn pick_image(title: &str) -> Result<PathBuf, ScanError> {
+    let portal_error = |e: zbus::Error| ScanError::Portal(e.to_string());
+
+    let connection = zbus::Connection::session().await.map_err(portal_error)?;
+    let sender = connection
+        .unique_name()
+        .map(|name| request_sender(name.as_str()))
+        .ok_or_else(|| ScanError::Portal("no unique bus name".to_string()))?;
+    let token = format!("cosboard_scan_{}", std::process::id());
+    let request_path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+
+    let request = zbus::Proxy::new(
+        &connection,
+        PORTAL_DESTINATION,
+        request_path.as_str(),
+        REQUEST_INTERFACE,
+    )
+    .await
+    .map_err(portal_error)?;
+    let mut responses = request
+        .receive_signal("Response")
+        .await
+        .map_err(portal_error)?;
+
+    let filter = (
+        "Images",
+        IMAGE_TYPES
+            .iter()
+            .map(|mime| (1u32, *mime))
+            .collect::<Vec<_>>(),
+    );
+    let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+    options.insert("modal", Value::from(false));
+    options.insert("filters", Value::from(vec![filter.clone()]));
+    options.insert("current_filter", Value::from(filter));
+
+    let chooser = zbus::Proxy::new(
+        &connection,
+        PORTAL_DESTINATION,
+        PORTAL_PATH,
+        FILE_CHOOSER_INTERFACE,
+    )
+    .await
+    .map_err(portal_error)?;
+    let _handle: zbus::zvariant::OwnedObjectPath = chooser
+        .call("OpenFile", &("", title, options))
+        .await
+        .map_err(portal_error)?;
+
+    let response = responses
+        .next()
+        .await
+        .ok_or_else(|| ScanError::Portal("no response from the portal".to_string()))?;
+    let (code, results): (u32, HashMap<String, OwnedValue>) =
+        response.body().deserialize().map_err(portal_error)?;
+    if code != 0 {
+        return Err(ScanError::Cancelled);
+    }
+
+    let uris = results
+        .get("uris")
+        .and_then(|uris| Vec::<String>::try_from(uris.try_clone().ok()?).ok())
+        .unwrap_or_default();
+    let uri = uris.first().ok_or(ScanError::Cancelled)?;
+    uri_to_path(uri).ok_or_else(|| ScanError::Portal(format!("{} is not a local file", uri)))
+}
+
+/// Returns the part of a request object path naming the caller: its unique
+/// bus name without the leading `:`, with `.` replaced by `_`.
+fn request_sender(unique_name: &str) -> String {
+    unique_name.trim_start_matches(':').replace('.', "_")
+}
+
+/// Converts a `file://` URI to a path, decoding percent escapes.
+///
+/// Returns `None` for other schemes and malformed escapes.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    // Only local files; `file://host/...` names another machine
+    let encoded = encoded.strip_prefix("localhost").unwrap_or(encoded);
+    if !encoded.starts_with('/') {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Local file URIs become paths; other URIs are rejected.
+    #[test]
+    fn test_uri_to_path() {
+        assert_eq!(
+            uri_to_path("file:///home/me/Pictures/wifi%20code.png"),
+            Some(PathBuf::from("/home/me/Pictures/wifi code.png"))
+        );
+        assert_eq!(
+            uri_to_path("file://localhost/tmp/a.png"),
+            Some(PathBuf::from("/tmp/a.png"))
+        );
+        assert_eq!(uri_to_path("file://server/share/a.png"), None);
+        assert_eq!(uri_to_path("https://example.com/a.png"), None);
+        assert_eq!(uri_to_path("file:///tmp/%2"), None);
+        assert_eq!(request_sender(":1.42"), "1_42");
+    }
+}
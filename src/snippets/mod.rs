@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Text shortcut expansion.
+//!
+//! Snippets replace a short trigger with longer text when a word boundary
+//! is typed after it: `brb` followed by a space becomes `be right back `,
+//! `@@` becomes an email address. Triggers may contain punctuation, so
+//! [`SnippetExpander`] tracks everything typed since the last whitespace
+//! rather than the word the predictor sees. Punctuation typed right after a
+//! trigger is kept: `brb.` becomes `be right back.`.
+//!
+//! Snippets live in the user configuration ([`SnippetSettings`]):
+//!
+//! ```ron
+//! (
+//!     enabled: true,
+//!     snippets: [
+//!         (trigger: "brb", expansion: "be right back"),
+//!         (trigger: "@@", expansion: "me@example.com"),
+//!     ],
+//!     disabled_apps: ["org.gnome.Terminal"],
+//! )
+//! ```
+//!
+//! Expansion can be switched off entirely, or for the applications in
+//! `disabled_apps`. Cosboard cannot see which window has focus by itself;
+//! the focused application is reported with the `set_focused_app` action.
+
+use serde::{Deserialize, Serialize};
+
+/// Punctuation that may follow a trigger and is kept after the expansion.
+pub const TRAILING_PUNCTUATION: &[char] = &['.', ',', '!', '?', ';', ':'];
+
+/// A text shortcut.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snippet {
+    /// Text typed to trigger the expansion (matched exactly).
+    pub trigger: String,
+    /// Text the trigger is replaced with.
+    pub expansion: String,
+}
+
+/// User snippets and where they apply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnippetSettings {
+    /// Whether snippets are expanded at all.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// The user's snippets.
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+    /// Application IDs in which snippets are not expanded.
+    #[serde(default)]
+    pub disabled_apps: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for SnippetSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            snippets: Vec::new(),
+            disabled_apps: Vec::new(),
+        }
+    }
+}
+
+impl SnippetSettings {
+    /// Returns the expansion of `trigger`, if it is a trigger.
+    #[must_use]
+    pub fn expansion(&self, trigger: &str) -> Option<&str> {
+        self.snippets
+            .iter()
+            .find(|snippet| snippet.trigger == trigger)
+            .map(|snippet| snippet.expansion.as_str())
+    }
+
+    /// Returns `true` if snippets are expanded in the focused application.
+    #[must_use]
+    pub fn active_in(&self, app: Option<&str>) -> bool {
+        self.enabled
+            && !app.is_some_and(|app| self.disabled_apps.iter().any(|disabled| disabled == app))
+    }
+
+    /// Enables or disables expansion in an application.
+    pub fn set_active_in(&mut self, app: &str, active: bool) {
+        self.disabled_apps.retain(|disabled| disabled != app);
+        if !active {
+            self.disabled_apps.push(app.to_string());
+        }
+    }
+
+    /// Removes the snippet with this trigger. Returns `true` if there was one.
+    pub fn remove(&mut self, trigger: &str) -> bool {
+        let before = self.snippets.len();
+        self.snippets.retain(|snippet| snippet.trigger != trigger);
+        self.snippets.len() != before
+    }
+}
+
+/// Keystrokes that replace a typed trigger with its expansion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    /// Number of characters to delete with BackSpace first.
+    pub backspaces: usize,
+    /// Text to type afterwards.
+    pub text: String,
+}
+
+/// Tracks the text typed since the last whitespace.
+#[derive(Debug, Clone, Default)]
+pub struct SnippetExpander {
+    /// Characters typed since the last whitespace
+    token: String,
+}
+
+impl SnippetExpander {
+    /// Creates an expander with nothing typed.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the text typed since the last whitespace.
+    #[must_use]
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Records a typed character.
+    ///
+    /// Returns the expansion to apply if `c` is whitespace ending a trigger
+    /// of `settings`. The whitespace has already been typed, so it is
+    /// deleted and typed again after the expansion.
+    pub fn type_char(&mut self, c: char, settings: &SnippetSettings) -> Option<Expansion> {
+        if !c.is_whitespace() {
+            self.token.push(c);
+            return None;
+        }
+
+        let token = std::mem::take(&mut self.token);
+        let trigger = token.trim_end_matches(TRAILING_PUNCTUATION);
+        let (expansion, trailing) = match settings.expansion(&token) {
+            Some(expansion) => (expansion, ""),
+            None if !trigger.is_empty() => (settings.expansion(trigger)?, &token[trigger.len()..]),
+            None => return None,
+        };
+        Some(Expansion {
+            backspaces: token.chars().count() + 1,
+            text: format!("{}{}{}", expansion, trailing, c),
+        })
+    }
+
+    /// Records a BackSpace.
+    pub fn backspace(&mut self) {
+        self.token.pop();
+    }
+
+    /// Forgets the typed text, e.g. after the cursor moved.
+    pub fn reset(&mut self) {
+        self.token.clear();
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> SnippetSettings {
+        SnippetSettings {
+            snippets: vec![
+                Snippet {
+                    trigger: "brb".to_string(),
+                    expansion: "be right back".to_string(),
+                },
+                Snippet {
+                    trigger: "@@".to_string(),
+                    expansion: "me@example.com".to_string(),
+                },
+            ],
+            ..SnippetSettings::default()
+        }
+    }
+
+    fn type_text(expander: &mut SnippetExpander, text: &str) -> Option<Expansion> {
+        let settings = settings();
+        text.chars()
+            .map(|c| expander.type_char(c, &settings))
+            .last()
+            .flatten()
+    }
+
+    /// Test: Triggers expand on whitespace, keeping trailing punctuation.
+    #[test]
+    fn test_expand() {
+        let mut expander = SnippetExpander::new();
+        assert_eq!(
+            type_text(&mut expander, "brb "),
+            Some(Expansion {
+                backspaces: 4,
+                text: "be right back ".to_string(),
+            })
+        );
+        assert_eq!(
+            type_text(&mut expander, "@@\t"),
+            Some(Expansion {
+                backspaces: 3,
+                text: "me@example.com\t".to_string(),
+            })
+        );
+        assert_eq!(
+            type_text(&mut expander, "brb! "),
+            Some(Expansion {
+                backspaces: 5,
+                text: "be right back! ".to_string(),
+            })
+        );
+    }
+
+    /// Test: Other words, partial triggers and corrected text do not expand.
+    #[test]
+    fn test_no_expansion() {
+        let mut expander = SnippetExpander::new();
+        assert_eq!(type_text(&mut expander, "brbx "), None);
+        assert_eq!(type_text(&mut expander, "xbrb "), None);
+        assert_eq!(type_text(&mut expander, ". "), None);
+
+        type_text(&mut expander, "brbx");
+        expander.backspace();
+        assert_eq!(expander.token(), "brb");
+        expander.reset();
+        assert_eq!(type_text(&mut expander, " "), None);
+    }
+
+    /// Test: Expansion can be disabled globally and per application.
+    #[test]
+    fn test_active_in() {
+        let mut settings = settings();
+        assert!(settings.active_in(None));
+        settings.set_active_in("org.gnome.Terminal", false);
+        assert!(!settings.active_in(Some("org.gnome.Terminal")));
+        assert!(settings.active_in(Some("org.mozilla.firefox")));
+        settings.set_active_in("org.gnome.Terminal", true);
+        assert!(settings.active_in(Some("org.gnome.Terminal")));
+
+        settings.enabled = false;
+        assert!(!settings.active_in(None));
+        assert!(settings.remove("brb"));
+        assert!(!settings.remove("brb"));
+    }
+}
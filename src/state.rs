@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use crate::app_settings;
+use crate::profiles::{ProfileStore, ProfileWindow};
 use cosmic::cosmic_config;
 use cosmic::cosmic_config::{cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
 
@@ -24,6 +25,14 @@ pub struct WindowState {
     pub margin_bottom: i32,
     /// Margin from right edge (floating mode position).
     pub margin_right: i32,
+    /// Whether the height was chosen by resizing the keyboard. Until then,
+    /// the height follows the rows of the visible panel.
+    pub height_set_by_user: bool,
+    /// Docking, size and position of the profiles not in use.
+    pub profiles: ProfileStore<ProfileWindow>,
+    /// Name of the output (e.g. `HDMI-1`) the keyboard is pinned to; unset
+    /// to follow the active output.
+    pub output: Option<String>,
 }
 
 impl Default for WindowState {
@@ -34,6 +43,34 @@ impl Default for WindowState {
             is_floating: false, // Default to docked mode for proper soft keyboard behavior
             margin_bottom: 0,
             margin_right: 0,
+            height_set_by_user: false,
+            profiles: ProfileStore::default(),
+            output: None,
         }
     }
 }
+
+impl WindowState {
+    /// Returns the docking, size and position to store with a profile.
+    #[must_use]
+    pub fn profile_window(&self) -> ProfileWindow {
+        ProfileWindow {
+            width: self.width,
+            height: self.height,
+            is_floating: self.is_floating,
+            margin_bottom: self.margin_bottom,
+            margin_right: self.margin_right,
+            height_set_by_user: self.height_set_by_user,
+        }
+    }
+
+    /// Restores the docking, size and position stored with a profile.
+    pub fn apply_profile_window(&mut self, window: &ProfileWindow) {
+        self.width = window.width;
+        self.height = window.height;
+        self.is_floating = window.is_floating;
+        self.margin_bottom = window.margin_bottom;
+        self.margin_right = window.margin_right;
+        self.height_set_by_user = window.height_set_by_user;
+    }
+}
@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Opt-in per-key press statistics.
+//!
+//! When [`KeyStatsPolicy::enabled`] is set, every key press is counted by
+//! key identifier in [`KeyStats`]. Counts are stored only on this machine,
+//! in `$XDG_DATA_HOME/cosboard/key_stats.json`, and are meant for tuning
+//! layouts: with [`KeyStatsPolicy::heat_map`] the renderer tints each key by
+//! how often it was pressed (see [`KeyStats::heat`]), so rarely used keys
+//! and crowded corners stand out.
+//!
+//! Like learned words, nothing is counted in private mode or in kiosk mode.
+//! Only which key was pressed is stored, never the order of the presses, so
+//! typed text cannot be reconstructed from the statistics. The settings menu
+//! can clear them.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::Storage;
+
+/// File under the user data directory holding the key statistics.
+pub const KEY_STATS_FILE: &str = "cosboard/key_stats.json";
+
+/// Maximum number of keys counted.
+///
+/// Layouts have a few hundred keys at most; the cap only guards against
+/// counting identifiers from many layouts forever.
+pub const MAX_TRACKED_KEYS: usize = 4_096;
+
+/// Number of distinct heat levels keys are tinted with.
+pub const HEAT_LEVELS: u8 = 8;
+
+/// Whether key presses are counted, and whether the counts are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyStatsPolicy {
+    /// Whether key presses are counted.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Whether keys are tinted by how often they were pressed.
+    #[serde(default)]
+    pub heat_map: bool,
+}
+
+/// Press counts by key identifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyStats {
+    #[serde(default)]
+    counts: HashMap<String, u64>,
+}
+
+impl KeyStats {
+    /// Creates empty statistics.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the path of the key statistics file.
+    #[must_use]
+    pub fn path(storage: &dyn Storage) -> Option<PathBuf> {
+        storage.user_data_dir().map(|dir| dir.join(KEY_STATS_FILE))
+    }
+
+    /// Reads the key statistics (empty if none were stored yet).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(storage: &dyn Storage) -> io::Result<Self> {
+        let Some(path) = Self::path(storage) else {
+            return Ok(Self::new());
+        };
+        match storage.read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the key statistics.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no user data directory or the file
+    /// cannot be written.
+    pub fn save(&self, storage: &dyn Storage) -> io::Result<()> {
+        let path = Self::path(storage)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no user data directory"))?;
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        storage.write(&path, &text)
+    }
+
+    /// Returns the number of keys counted.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if no key was counted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns how often a key was pressed.
+    #[must_use]
+    pub fn count(&self, identifier: &str) -> u64 {
+        self.counts.get(identifier).copied().unwrap_or(0)
+    }
+
+    /// Counts a key press and returns the key's new count.
+    ///
+    /// Presses of new keys are not counted once [`MAX_TRACKED_KEYS`] keys
+    /// are.
+    pub fn record(&mut self, identifier: &str) -> u64 {
+        if let Some(count) = self.counts.get_mut(identifier) {
+            *count = count.saturating_add(1);
+            return *count;
+        }
+        if self.counts.len() >= MAX_TRACKED_KEYS {
+            return 0;
+        }
+        self.counts.insert(identifier.to_string(), 1);
+        1
+    }
+
+    /// Forgets every count.
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+
+    /// Returns how hot a key is, from 0.0 (never pressed) to 1.0 (the most
+    /// pressed key).
+    ///
+    /// The scale is logarithmic: on a text layout the space bar and a few
+    /// letters are pressed far more often than anything else, and a linear
+    /// scale would leave every other key cold.
+    #[must_use]
+    pub fn heat(&self, identifier: &str) -> f32 {
+        let max = self.counts.values().copied().max().unwrap_or(0);
+        heat_of(self.count(identifier), max)
+    }
+
+    /// Returns the heat of every counted key, by identifier.
+    #[must_use]
+    pub fn heat_map(&self) -> HashMap<String, f32> {
+        let max = self.counts.values().copied().max().unwrap_or(0);
+        self.counts
+            .iter()
+            .map(|(identifier, count)| (identifier.clone(), heat_of(*count, max)))
+            .collect()
+    }
+}
+
+/// Returns the heat of `count` presses when the most pressed key has `max`.
+fn heat_of(count: u64, max: u64) -> f32 {
+    if count == 0 || max == 0 {
+        return 0.0;
+    }
+    ((count as f64).ln_1p() / (max as f64).ln_1p()).clamp(0.0, 1.0) as f32
+}
+
+/// Quantizes a heat value to one of [`HEAT_LEVELS`] levels, from 1 (coldest
+/// pressed key) to `HEAT_LEVELS`.
+///
+/// Keys that were never pressed (heat 0.0) have level 0.
+#[must_use]
+pub fn heat_level(heat: f32) -> u8 {
+    if heat <= 0.0 {
+        return 0;
+    }
+    ((heat.min(1.0) * f32::from(HEAT_LEVELS)).ceil() as u8).clamp(1, HEAT_LEVELS)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: The most pressed key is hottest and heat grows with presses.
+    #[test]
+    fn test_key_heat() {
+        let mut stats = KeyStats::new();
+        assert_eq!(stats.heat("space"), 0.0);
+
+        for _ in 0..100 {
+            stats.record("space");
+        }
+        for _ in 0..10 {
+            stats.record("key_e");
+        }
+        assert_eq!(stats.record("key_q"), 1);
+
+        assert_eq!(stats.heat("space"), 1.0);
+        assert!(stats.heat("key_e") > stats.heat("key_q"));
+        assert!(stats.heat("key_q") > 0.0);
+        assert_eq!(stats.heat("key_z"), 0.0);
+        assert_eq!(stats.heat_map().len(), 3);
+
+        assert_eq!(heat_level(0.0), 0);
+        assert_eq!(heat_level(0.01), 1);
+        assert_eq!(heat_level(1.0), HEAT_LEVELS);
+
+        stats.clear();
+        assert!(stats.is_empty());
+    }
+
+    /// Test: Statistics survive being written and read back.
+    #[test]
+    fn test_key_stats_round_trip() {
+        let mut stats = KeyStats::new();
+        stats.record("key_a");
+        stats.record("key_a");
+        stats.record("enter");
+
+        let text = serde_json::to_string(&stats).unwrap();
+        let loaded: KeyStats = serde_json::from_str(&text).unwrap();
+        assert_eq!(loaded, stats);
+        assert_eq!(loaded.count("key_a"), 2);
+
+        // Files from a newer version without counts still load
+        let empty: KeyStats = serde_json::from_str("{}").unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Filesystem access for user files (layouts), native or sandboxed.
+//!
+//! Code that reads layout files goes through the [`Storage`] trait instead of
+//! calling `std::fs` directly, so the same code works in native and Flatpak
+//! builds:
+//!
+//! - [`NativeStorage`] reads anywhere and uses the usual XDG data dirs.
+//! - [`FlatpakStorage`] is used inside a Flatpak sandbox. Its user data dir
+//!   is the per-app `$XDG_DATA_HOME` (`~/.var/app/<id>/data`), system layouts
+//!   come from the `/app` prefix, and files the user granted through the
+//!   Documents portal (`$XDG_RUNTIME_DIR/doc/...`) are readable by path.
+//!   Paths outside these roots fail with a clear error rather than an
+//!   unexplained "not found".
+//!
+//! Window state and user configuration are stored through `cosmic_config`,
+//! which already resolves to the sandbox's config dir, so they do not need
+//! this abstraction.
+//!
+//! Use [`detect`] to get the storage for the current environment.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// File inside every Flatpak sandbox.
+const FLATPAK_INFO: &str = "/.flatpak-info";
+
+/// Read-mostly access to user-visible files.
+pub trait Storage: fmt::Debug + Send + Sync {
+    /// Reads a whole file as UTF-8.
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Writes a whole file, creating parent directories as needed.
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()>;
+
+    /// Lists the entries of a directory.
+    fn list_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Returns `true` if `path` is a readable regular file.
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Returns when a file was last modified.
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+
+    /// Per-user data directory (the XDG data home).
+    fn user_data_dir(&self) -> Option<PathBuf>;
+
+    /// System data directories, highest precedence first.
+    fn system_data_dirs(&self) -> Vec<PathBuf>;
+
+    /// Short name of the storage backend, for logs.
+    fn name(&self) -> &'static str;
+}
+
+/// Returns `true` when running inside a Flatpak sandbox.
+#[must_use]
+pub fn is_sandboxed() -> bool {
+    std::env::var_os("FLATPAK_ID").is_some() || Path::new(FLATPAK_INFO).exists()
+}
+
+/// Returns the storage backend for the current environment.
+#[must_use]
+pub fn detect() -> Arc<dyn Storage> {
+    if is_sandboxed() {
+        Arc::new(FlatpakStorage::from_env())
+    } else {
+        Arc::new(NativeStorage)
+    }
+}
+
+// ============================================================================
+// Native
+// ============================================================================
+
+/// Direct filesystem access for native builds.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NativeStorage;
+
+impl Storage for NativeStorage {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        write_file(path, contents)
+    }
+
+    fn list_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        list_dir(dir)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        std::fs::metadata(path)?.modified()
+    }
+
+    fn user_data_dir(&self) -> Option<PathBuf> {
+        env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".local/share")))
+    }
+
+    fn system_data_dirs(&self) -> Vec<PathBuf> {
+        vec![
+            PathBuf::from("/usr/local/share"),
+            PathBuf::from("/usr/share"),
+        ]
+    }
+
+    fn name(&self) -> &'static str {
+        "native"
+    }
+}
+
+// ============================================================================
+// Flatpak
+// ============================================================================
+
+/// Filesystem access confined to the roots a Flatpak sandbox can see.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatpakStorage {
+    /// Per-app data home (`~/.var/app/<id>/data`).
+    pub data_home: Option<PathBuf>,
+    /// Documents portal mount (`$XDG_RUNTIME_DIR/doc`).
+    pub documents_dir: Option<PathBuf>,
+    /// Install prefix of the app inside the sandbox.
+    pub app_prefix: PathBuf,
+}
+
+impl FlatpakStorage {
+    /// Builds the sandbox roots from the environment Flatpak sets up.
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self {
+            data_home: env_dir("XDG_DATA_HOME"),
+            documents_dir: env_dir("XDG_RUNTIME_DIR").map(|dir| dir.join("doc")),
+            app_prefix: PathBuf::from("/app"),
+        }
+    }
+
+    /// Returns `true` if `path` lies under a root the sandbox can access.
+    #[must_use]
+    pub fn is_accessible(&self, path: &Path) -> bool {
+        self.data_home
+            .iter()
+            .chain(self.documents_dir.iter())
+            .chain(std::iter::once(&self.app_prefix))
+            .any(|root| path.starts_with(root))
+    }
+
+    /// Error for paths outside the sandbox roots.
+    fn denied(&self, path: &Path) -> io::Error {
+        let hint = self
+            .data_home
+            .as_ref()
+            .map(|dir| format!(" or copy it to {}", dir.join("cosboard/layouts").display()))
+            .unwrap_or_default();
+        io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} is outside the Flatpak sandbox; open it through the file chooser{}",
+                path.display(),
+                hint
+            ),
+        )
+    }
+
+    /// Fails with a helpful error if `path` is outside the sandbox roots.
+    fn check(&self, path: &Path) -> io::Result<()> {
+        if self.is_accessible(path) {
+            Ok(())
+        } else {
+            Err(self.denied(path))
+        }
+    }
+}
+
+impl Storage for FlatpakStorage {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.check(path)?;
+        std::fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        self.check(path)?;
+        write_file(path, contents)
+    }
+
+    fn list_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        self.check(dir)?;
+        list_dir(dir)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.is_accessible(path) && path.is_file()
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.check(path)?;
+        std::fs::metadata(path)?.modified()
+    }
+
+    fn user_data_dir(&self) -> Option<PathBuf> {
+        self.data_home.clone()
+    }
+
+    fn system_data_dirs(&self) -> Vec<PathBuf> {
+        vec![self.app_prefix.join("share")]
+    }
+
+    fn name(&self) -> &'static str {
+        "flatpak"
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Returns a non-empty directory from an environment variable.
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Writes a file, creating its parent directories.
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)
+}
+
+/// Lists a directory's entries.
+fn list_dir(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox() -> FlatpakStorage {
+        FlatpakStorage {
+            data_home: Some(PathBuf::from(
+                "/home/u/.var/app/io.github.cosboard.Cosboard/data",
+            )),
+            documents_dir: Some(PathBuf::from("/run/user/1000/doc")),
+            app_prefix: PathBuf::from("/app"),
+        }
+    }
+
+    /// Test: The sandbox only grants its data home, portal docs and /app.
+    #[test]
+    fn test_flatpak_roots() {
+        let storage = sandbox();
+        assert!(storage.is_accessible(Path::new(
+            "/home/u/.var/app/io.github.cosboard.Cosboard/data/cosboard/layouts/a.json"
+        )));
+        assert!(storage.is_accessible(Path::new("/run/user/1000/doc/ab12/layout.json")));
+        assert!(storage.is_accessible(Path::new("/app/share/cosboard/layouts/qwerty.json")));
+        assert!(!storage.is_accessible(Path::new("/home/u/layouts/qwerty.json")));
+        assert!(!storage.is_accessible(Path::new("/usr/share/cosboard/layouts/qwerty.json")));
+
+        assert_eq!(
+            storage.system_data_dirs(),
+            vec![PathBuf::from("/app/share")]
+        );
+    }
+
+    /// Test: Paths outside the sandbox fail with an explanatory error.
+    #[test]
+    fn test_flatpak_denied_error() {
+        let err = sandbox()
+            .read_to_string(Path::new("/home/u/layouts/qwerty.json"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        let message = err.to_string();
+        assert!(message.contains("outside the Flatpak sandbox"));
+        assert!(message.contains("cosboard/layouts"));
+    }
+
+    /// Test: Native storage round-trips files and lists directories.
+    #[test]
+    fn test_native_round_trip() {
+        let dir = std::env::temp_dir().join(format!("cosboard-storage-{}", std::process::id()));
+        let file = dir.join("nested/layout.json");
+        let storage = NativeStorage;
+
+        storage.write(&file, "{}").unwrap();
+        assert!(storage.is_file(&file));
+        assert_eq!(storage.read_to_string(&file).unwrap(), "{}");
+        assert!(storage.modified(&file).is_ok());
+        assert_eq!(
+            storage.list_dir(&dir.join("nested")).unwrap(),
+            vec![file.clone()]
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Built-in Unicode symbols browser.
+//!
+//! This module provides a bundled table of Unicode blocks grouped into
+//! categories (math, arrows, currency, ...) and the built-in
+//! `unicode_symbols` panel that browses them. Symbols are inserted through
+//! the Unicode codepoint path, so they work regardless of the active keymap.
+//!
+//! # Built-in Panel
+//!
+//! [`add_builtin_panel`] adds the `unicode_symbols` panel to a layout unless
+//! the layout defines a panel with that ID itself. Layouts reach it with a
+//! regular panel reference:
+//!
+//! ```json
+//! {"type": "panel_ref", "panel_id": "unicode_symbols", "width": 1.0, "height": 1.0}
+//! ```
+
+use crate::layout::{Cell, Key, KeyCode, Layout, Panel, PanelRef, Row, Sizing, Widget};
+
+/// ID of the built-in symbols browser panel.
+pub const SYMBOLS_PANEL_ID: &str = "unicode_symbols";
+
+/// Widget type name for the symbols browser in layout files.
+pub const SYMBOLS_BROWSER_WIDGET: &str = "symbols_browser";
+
+/// Symbol categories, in tab order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SymbolCategory {
+    /// Mathematical operators.
+    #[default]
+    Math,
+    /// Arrows.
+    Arrows,
+    /// Currency signs.
+    Currency,
+    /// Greek letters.
+    Greek,
+    /// Letterlike symbols (℃, №, ™, ...).
+    Letterlike,
+    /// Miscellaneous technical symbols (⌘, ⌫, ⏎, ...).
+    Technical,
+    /// General punctuation (dashes, quotes, bullets, ...).
+    Punctuation,
+}
+
+impl SymbolCategory {
+    /// All categories, in tab order.
+    pub const ALL: [SymbolCategory; 7] = [
+        SymbolCategory::Math,
+        SymbolCategory::Arrows,
+        SymbolCategory::Currency,
+        SymbolCategory::Greek,
+        SymbolCategory::Letterlike,
+        SymbolCategory::Technical,
+        SymbolCategory::Punctuation,
+    ];
+
+    /// Returns the short tab label for the category.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            SymbolCategory::Math => "∑",
+            SymbolCategory::Arrows => "→",
+            SymbolCategory::Currency => "€",
+            SymbolCategory::Greek => "Ω",
+            SymbolCategory::Letterlike => "℃",
+            SymbolCategory::Technical => "⌘",
+            SymbolCategory::Punctuation => "…",
+        }
+    }
+}
+
+/// A Unicode block (or part of one) shown in the symbols browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolBlock {
+    /// Unicode block name.
+    pub name: &'static str,
+    /// Category tab the block is listed under.
+    pub category: SymbolCategory,
+    /// Inclusive codepoint ranges. Only fully assigned ranges are listed.
+    pub ranges: &'static [(u32, u32)],
+}
+
+/// Bundled Unicode block table, in display order.
+pub static BLOCKS: &[SymbolBlock] = &[
+    SymbolBlock {
+        name: "Latin-1 Supplement",
+        category: SymbolCategory::Math,
+        ranges: &[(0x00B1, 0x00B1), (0x00D7, 0x00D7), (0x00F7, 0x00F7)],
+    },
+    SymbolBlock {
+        name: "Mathematical Operators",
+        category: SymbolCategory::Math,
+        ranges: &[(0x2200, 0x22FF)],
+    },
+    SymbolBlock {
+        name: "Arrows",
+        category: SymbolCategory::Arrows,
+        ranges: &[(0x2190, 0x21FF)],
+    },
+    SymbolBlock {
+        name: "Supplemental Arrows-A",
+        category: SymbolCategory::Arrows,
+        ranges: &[(0x27F0, 0x27FF)],
+    },
+    SymbolBlock {
+        name: "Latin-1 Supplement",
+        category: SymbolCategory::Currency,
+        ranges: &[(0x0024, 0x0024), (0x00A2, 0x00A5)],
+    },
+    SymbolBlock {
+        name: "Currency Symbols",
+        category: SymbolCategory::Currency,
+        ranges: &[(0x20A0, 0x20C0)],
+    },
+    SymbolBlock {
+        name: "Greek and Coptic",
+        category: SymbolCategory::Greek,
+        ranges: &[(0x0391, 0x03A1), (0x03A3, 0x03A9), (0x03B1, 0x03C9)],
+    },
+    SymbolBlock {
+        name: "Letterlike Symbols",
+        category: SymbolCategory::Letterlike,
+        ranges: &[(0x2100, 0x214F)],
+    },
+    SymbolBlock {
+        name: "Miscellaneous Technical",
+        category: SymbolCategory::Technical,
+        ranges: &[(0x2300, 0x23FF)],
+    },
+    SymbolBlock {
+        name: "General Punctuation",
+        category: SymbolCategory::Punctuation,
+        ranges: &[(0x2010, 0x2027), (0x2030, 0x205E)],
+    },
+];
+
+/// Returns the symbols listed under a category, in table order.
+#[must_use]
+pub fn symbols(category: SymbolCategory) -> Vec<char> {
+    BLOCKS
+        .iter()
+        .filter(|block| block.category == category)
+        .flat_map(|block| block.ranges.iter())
+        .flat_map(|&(start, end)| (start..=end).filter_map(char::from_u32))
+        .collect()
+}
+
+/// Returns the built-in symbols browser panel.
+///
+/// The panel holds the browser widget and a bottom row with a panel
+/// reference back to `return_panel_id`, Space and Enter.
+#[must_use]
+pub fn builtin_panel(return_panel_id: &str) -> Panel {
+    let browser = Cell::Widget(Widget {
+        widget_type: SYMBOLS_BROWSER_WIDGET.to_string(),
+        width: Sizing::Relative(10.0),
+        height: Sizing::Relative(3.0),
+    });
+
+    let bottom_row = vec![
+        Cell::PanelRef(PanelRef {
+            panel_id: return_panel_id.to_string(),
+            width: Sizing::Relative(2.0),
+            height: Sizing::Relative(1.0),
+        }),
+        Cell::Key(Key {
+            label: "Space".to_string(),
+            code: KeyCode::Unicode(' '),
+            identifier: Some("usym_space".to_string()),
+            width: Sizing::Relative(6.0),
+            ..Key::default()
+        }),
+        Cell::Key(Key {
+            label: "⏎".to_string(),
+            code: KeyCode::Keysym("Return".to_string()),
+            identifier: Some("usym_enter".to_string()),
+            width: Sizing::Relative(2.0),
+            ..Key::default()
+        }),
+    ];
+
+    Panel {
+        id: SYMBOLS_PANEL_ID.to_string(),
+        padding: Some(8.0),
+        margin: Some(4.0),
+        rows: vec![Row { cells: vec![browser] }, Row { cells: bottom_row }],
+        ..Panel::default()
+    }
+}
+
+/// Adds the built-in symbols browser panel to a layout.
+///
+/// Layouts that define their own `unicode_symbols` panel keep it.
+pub fn add_builtin_panel(layout: &mut Layout) {
+    if !layout.panels.contains_key(SYMBOLS_PANEL_ID) {
+        let panel = builtin_panel(&layout.default_panel_id);
+        layout.panels.insert(SYMBOLS_PANEL_ID.to_string(), panel);
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Every category has printable, unique symbols.
+    #[test]
+    fn test_symbol_categories() {
+        for category in SymbolCategory::ALL {
+            let list = symbols(category);
+            assert!(!list.is_empty(), "{:?} has no symbols", category);
+            assert!(list.iter().all(|c| !c.is_control() && !c.is_whitespace()));
+
+            let unique: std::collections::HashSet<_> = list.iter().collect();
+            assert_eq!(unique.len(), list.len(), "{:?} has duplicates", category);
+        }
+
+        assert!(symbols(SymbolCategory::Math).contains(&'∀'));
+        assert!(symbols(SymbolCategory::Math).contains(&'≈'));
+        assert!(symbols(SymbolCategory::Arrows).contains(&'→'));
+        assert!(symbols(SymbolCategory::Currency).contains(&'€'));
+        assert_eq!(symbols(SymbolCategory::Greek).len(), 49);
+    }
+
+    /// Test: The built-in panel is added once and returns to the default panel.
+    #[test]
+    fn test_add_builtin_panel() {
+        let mut layout = Layout {
+            default_panel_id: "main".to_string(),
+            ..Layout::default()
+        };
+
+        add_builtin_panel(&mut layout);
+        let panel = layout.panels.get(SYMBOLS_PANEL_ID).expect("panel added");
+        assert_eq!(panel.rows.len(), 2);
+        assert!(matches!(
+            &panel.rows[1].cells[0],
+            Cell::PanelRef(panel_ref) if panel_ref.panel_id == "main"
+        ));
+
+        // A layout-defined panel is kept
+        layout.panels.get_mut(SYMBOLS_PANEL_ID).unwrap().rows.clear();
+        add_builtin_panel(&mut layout);
+        assert!(layout.panels[SYMBOLS_PANEL_ID].rows.is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Built-in typing test for evaluating layouts.
+//!
+//! While a typing test runs, the keyboard's internal text field
+//! [`TYPING_TEST_FIELD`] has focus, so keystrokes are intercepted by
+//! [`InputFocus`](crate::input::InputFocus) and never reach the client
+//! application. After every edit the applet passes the field's buffer to
+//! [`TypingTest::update`], which counts keystrokes and mistakes against the
+//! target phrase. Once the phrase is typed exactly, the test stops its clock
+//! and [`TypingTest::stats`] reports the final speed and accuracy.
+//!
+//! Speed uses the usual convention of five characters per word.
+
+use std::time::{Duration, Instant};
+
+/// Identifier of the internal text field that receives typing test input.
+pub const TYPING_TEST_FIELD: &str = "typing_test";
+
+/// Characters counted as one word for words-per-minute.
+pub const CHARS_PER_WORD: f32 = 5.0;
+
+/// Phrases offered by the typing test, in order.
+pub const PHRASES: &[&str] = &[
+    "the quick brown fox jumps over the lazy dog",
+    "pack my box with five dozen liquor jugs",
+    "how vexingly quick daft zebras jump",
+    "sphinx of black quartz, judge my vow",
+    "a soft keyboard should feel as fast as a real one",
+    "typing on glass is easier with big keys and good feedback",
+];
+
+/// Speed and accuracy of a typing test run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TypingStats {
+    /// Words per minute (five characters per word).
+    pub wpm: f32,
+    /// Share of keystrokes that matched the phrase, from 0.0 to 1.0.
+    pub accuracy: f32,
+    /// Time since the first keystroke.
+    pub elapsed: Duration,
+}
+
+/// State of one phrase of the typing test.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypingTest {
+    /// Index of the phrase in [`PHRASES`].
+    phrase_index: usize,
+    /// Number of characters in the buffer at the last update.
+    typed_chars: usize,
+    /// Characters typed (not counting deletions).
+    keystrokes: u32,
+    /// Characters typed that did not match the phrase.
+    errors: u32,
+    /// Whether the buffer currently equals the phrase.
+    complete: bool,
+    /// Time of the first keystroke.
+    started: Option<Instant>,
+    /// Time the phrase was completed.
+    finished: Option<Instant>,
+}
+
+impl TypingTest {
+    /// Starts a test on the phrase at `phrase_index` (wrapping around).
+    #[must_use]
+    pub fn new(phrase_index: usize) -> Self {
+        Self {
+            phrase_index: phrase_index % PHRASES.len(),
+            typed_chars: 0,
+            keystrokes: 0,
+            errors: 0,
+            complete: false,
+            started: None,
+            finished: None,
+        }
+    }
+
+    /// Returns a fresh test on the following phrase.
+    #[must_use]
+    pub fn next(&self) -> Self {
+        Self::new(self.phrase_index + 1)
+    }
+
+    /// Returns the phrase to type.
+    #[must_use]
+    pub fn phrase(&self) -> &'static str {
+        PHRASES[self.phrase_index]
+    }
+
+    /// Returns `true` once the phrase has been typed exactly.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Updates the test from the field's current buffer.
+    ///
+    /// Characters added since the last update count as keystrokes, and
+    /// each one that does not match the phrase at its position counts as a
+    /// mistake. Deletions are free. The clock starts at the first keystroke
+    /// and stops when the buffer equals the phrase; updates after that are
+    /// ignored.
+    pub fn update(&mut self, buffer: &str, now: Instant) {
+        if self.complete {
+            return;
+        }
+
+        let typed: Vec<char> = buffer.chars().collect();
+        if typed.len() > self.typed_chars {
+            self.started.get_or_insert(now);
+            let expected: Vec<char> = self.phrase().chars().collect();
+            for (position, c) in typed.iter().enumerate().skip(self.typed_chars) {
+                self.keystrokes += 1;
+                if expected.get(position) != Some(c) {
+                    self.errors += 1;
+                }
+            }
+        }
+        self.typed_chars = typed.len();
+
+        if buffer == self.phrase() {
+            self.complete = true;
+            self.finished = Some(now);
+        }
+    }
+
+    /// Returns the speed and accuracy so far (final once complete).
+    #[must_use]
+    pub fn stats(&self, now: Instant) -> TypingStats {
+        let elapsed = match self.started {
+            Some(started) => self
+                .finished
+                .unwrap_or(now)
+                .saturating_duration_since(started),
+            None => Duration::ZERO,
+        };
+
+        let minutes = elapsed.as_secs_f32() / 60.0;
+        let wpm = if minutes > 0.0 {
+            (self.typed_chars as f32 / CHARS_PER_WORD) / minutes
+        } else {
+            0.0
+        };
+
+        let accuracy = if self.keystrokes == 0 {
+            1.0
+        } else {
+            (self.keystrokes - self.errors) as f32 / self.keystrokes as f32
+        };
+
+        TypingStats {
+            wpm,
+            accuracy,
+            elapsed,
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: Typing the phrase exactly completes the test with full accuracy.
+    #[test]
+    fn test_complete_phrase() {
+        let start = Instant::now();
+        let mut test = TypingTest::new(0);
+        let phrase = test.phrase();
+
+        let mut buffer = String::new();
+        for c in phrase.chars() {
+            buffer.push(c);
+            test.update(&buffer, start);
+        }
+        assert!(test.is_complete());
+
+        let stats = test.stats(start + Duration::from_secs(6));
+        assert_eq!(stats.elapsed, Duration::ZERO);
+        assert_eq!(stats.accuracy, 1.0);
+
+        // 43 characters in 6 seconds is 86 WPM
+        let mut test = TypingTest::new(0);
+        test.update("t", start);
+        test.update(phrase, start + Duration::from_secs(6));
+        let stats = test.stats(start + Duration::from_secs(60));
+        assert_eq!(stats.elapsed, Duration::from_secs(6));
+        assert!((stats.wpm - 86.0).abs() < 0.01);
+    }
+
+    /// Test: Mistakes lower accuracy even after they are corrected.
+    #[test]
+    fn test_corrected_mistakes_count() {
+        let now = Instant::now();
+        let mut test = TypingTest::new(0);
+
+        test.update("x", now);
+        test.update("", now);
+        test.update("t", now);
+        test.update("th", now);
+        assert!(!test.is_complete());
+
+        let stats = test.stats(now);
+        assert!((stats.accuracy - 2.0 / 3.0).abs() < 0.001);
+    }
+
+    /// Test: Phrases advance and wrap around.
+    #[test]
+    fn test_next_phrase_wraps() {
+        let last = TypingTest::new(PHRASES.len() - 1);
+        assert_eq!(last.next().phrase(), PHRASES[0]);
+        assert_eq!(TypingTest::new(PHRASES.len()).phrase(), PHRASES[0]);
+    }
+}
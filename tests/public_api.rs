@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! API stability tests for `cosboard::prelude`.
+//!
+//! Each program in `tests/public_api/` is built against the crate like an
+//! external tool would be. A change that breaks one of them is a breaking
+//! change of the stable API and needs a new major version.
+
+/// Test: Programs written against the prelude compile and run.
+#[test]
+fn test_prelude_programs() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/public_api/*.rs");
+}
@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Pins the signatures of the stable functions and constructors.
+
+use cosboard::prelude::*;
+
+fn main() {
+    let _: fn(&str) -> Result<ParseResult<Layout>, ParseError> = parse_layout_from_string;
+    let _: fn(&str) -> Result<ParseResult<Layout>, ParseError> = parse_layout_file;
+    let _: fn(Layout) -> KeyboardRenderer = KeyboardRenderer::new;
+    let _: fn() -> VirtualKeyboard = VirtualKeyboard::new;
+    let _: fn() -> LayoutCache = LayoutCache::new;
+    let _: fn() -> ProviderChain = ProviderChain::standard;
+    let _: fn() -> ActionRegistry = ActionRegistry::new;
+    let _: fn() -> ModifierState = ModifierState::new;
+}
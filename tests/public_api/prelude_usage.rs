@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! An external tool parsing a layout and driving the renderer's state.
+
+use cosboard::prelude::*;
+
+/// A key sink that only counts events, as a test harness would.
+struct CountingSink(usize);
+
+impl KeySink for CountingSink {
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    fn send_key(&mut self, _keycode: u32, _state: KeyState) -> Result<(), String> {
+        self.0 += 1;
+        Ok(())
+    }
+}
+
+fn main() {
+    let json = r#"{
+        "name": "Tool",
+        "version": "1.0",
+        "default_panel_id": "main",
+        "panels": {
+            "main": {
+                "id": "main",
+                "rows": [{ "cells": [{ "type": "key", "label": "a", "code": "a", "identifier": "a" }] }]
+            }
+        }
+    }"#;
+    let result = parse_layout_from_string(json).expect("layout should parse");
+    let panel: &Panel = &result.layout.panels["main"];
+    let Cell::Key(key) = &panel.rows[0].cells[0] else {
+        panic!("expected a key");
+    };
+    let _: &Key = key;
+    let _: &KeyCode = &key.code;
+
+    let mut renderer = KeyboardRenderer::new(result.layout);
+    renderer.activate_modifier(Modifier::Shift, true);
+    assert!(renderer.is_modifier_active(Modifier::Shift));
+
+    let mut sink = CountingSink(0);
+    sink.send_key(30, KeyState::Pressed).unwrap();
+    sink.send_key(30, KeyState::Released).unwrap();
+    assert_eq!(sink.0, 2);
+
+    let action: AppAction = ActionRegistry::new().parse("hide").expect("builtin action");
+    let _ = action;
+}
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Soak test of long random applet sessions (`soak` feature).
+//!
+//! The sessions (see `cosboard::soak`) check that no key, long press, drag
+//! or panel state is left behind, and that the memory held by the applet
+//! stops growing once it is warmed up. Memory is measured with a global
+//! allocator counting the bytes each thread holds, so tests running in
+//! parallel do not disturb it. The allocator is installed for this test
+//! binary only.
+//!
+//! The default run is short; the long soak is ignored by default:
+//!
+//! ```bash
+//! cargo test --features soak --test soak
+//! cargo test --features soak --test soak -- --ignored
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// ============================================================================
+// Allocation counter
+// ============================================================================
+
+/// Global allocator counting the bytes each thread holds.
+struct CountingAllocator;
+
+thread_local! {
+    /// Bytes allocated by this thread and not yet freed.
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Adds `delta` to this thread's live bytes.
+fn track(delta: isize) {
+    // The counter is gone while the thread shuts down
+    let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + delta));
+}
+
+// SAFETY: Every call is forwarded to the system allocator unchanged.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        track(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            track(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the bytes this thread holds.
+fn live_bytes() -> isize {
+    LIVE_BYTES.with(Cell::get)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+/// Test: A short random session leaves no stuck state and no leak.
+#[test]
+fn test_soak_short() {
+    if !cosboard::soak::run(0x5eed, 2_000, live_bytes) {
+        eprintln!("Skipping test: XKB initialization failed");
+    }
+}
+
+/// Test: Tens of thousands of random steps leave no stuck state and no leak.
+#[test]
+#[ignore = "long-running; run with --ignored"]
+fn test_soak_long() {
+    for seed in [1, 2, 3] {
+        if !cosboard::soak::run(seed, 50_000, live_bytes) {
+            eprintln!("Skipping test: XKB initialization failed");
+            return;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Visual regression tests for the keyboard renderer (`preview` feature).
+//!
+//! Every panel of the shipped layouts is drawn offscreen at several surface
+//! sizes and scales and compared with the reference images in
+//! `tests/snapshots/`, catching accidental changes to key sizes, placement
+//! and labels. Panels are drawn with a fixed style and the bundled DejaVu
+//! Sans font (`tests/fonts/`), so the images do not depend on the COSMIC
+//! theme or the fonts installed.
+//!
+//! ```bash
+//! cargo test --features preview --test visual_regression
+//! ```
+//!
+//! A missing reference fails the test. After an intended change, or for a
+//! new layout, record the references with `UPDATE_SNAPSHOTS=1`, review the
+//! new images and commit them. A mismatching image is written next to the
+//! test binary's temporary files for inspection.
+
+use cosboard::layout::parse_layout_file;
+use cosboard::renderer::{compare_images, render_panel_image, ImageStyle};
+use cosmic::iced::Color;
+use std::path::{Path, PathBuf};
+use tiny_skia::Pixmap;
+
+/// Surface sizes and scale factors each panel is drawn at.
+const SURFACES: &[(f32, f32, f32)] = &[(800.0, 300.0, 1.0), (1600.0, 600.0, 2.0)];
+
+/// Largest channel difference still counted as equal (anti-aliasing).
+const TOLERANCE: u8 = 2;
+
+/// Environment variable that records the reference images when set to 1.
+const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// Colors of the reference images, close to the COSMIC dark theme.
+const STYLE: ImageStyle = ImageStyle {
+    background: Color {
+        r: 0.106,
+        g: 0.106,
+        b: 0.106,
+        a: 1.0,
+    },
+    key: Color {
+        r: 0.2,
+        g: 0.2,
+        b: 0.2,
+        a: 1.0,
+    },
+    text: Color {
+        r: 0.9,
+        g: 0.9,
+        b: 0.9,
+        a: 1.0,
+    },
+    radius: 8.0,
+};
+
+/// Font the key labels are drawn with.
+fn label_font() -> fontdue::Font {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fonts/DejaVuSans.ttf");
+    let data = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn shipped_layouts() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/layouts");
+    let mut layouts: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .expect("shipped layouts directory")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    layouts.sort();
+    layouts
+}
+
+/// Test: Every panel of the shipped layouts looks like its reference image.
+#[test]
+fn test_shipped_layout_snapshots() {
+    let snapshots = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    let update = std::env::var(UPDATE_ENV).is_ok_and(|value| value == "1");
+    let font = label_font();
+    let mut failures = Vec::new();
+
+    for path in shipped_layouts() {
+        let layout = parse_layout_file(path.to_str().unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+            .layout;
+        let stem = path.file_stem().unwrap().to_string_lossy();
+
+        let mut panel_ids: Vec<&String> = layout.panels.keys().collect();
+        panel_ids.sort();
+        for panel_id in panel_ids {
+            for &(width, height, scale) in SURFACES {
+                let name = format!("{}-{}-{}x{}@{}.png", stem, panel_id, width, height, scale);
+                let actual = render_panel_image(
+                    &layout.panels[panel_id],
+                    width,
+                    height,
+                    scale,
+                    &STYLE,
+                    Some(&font),
+                )
+                .expect("non-empty surface");
+                let reference = snapshots.join(&name);
+
+                if update {
+                    std::fs::create_dir_all(&snapshots).unwrap();
+                    actual.save_png(&reference).unwrap();
+                    eprintln!("Recorded {}", reference.display());
+                    continue;
+                }
+                if !reference.exists() {
+                    failures.push(format!("{}: no reference image", name));
+                    continue;
+                }
+
+                let expected = Pixmap::load_png(&reference)
+                    .unwrap_or_else(|e| panic!("{}: {}", reference.display(), e));
+                match compare_images(&actual, &expected, TOLERANCE) {
+                    Some(diff) if diff.is_match() => {}
+                    diff => {
+                        let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join(&name);
+                        actual.save_png(&output).unwrap();
+                        failures.push(format!(
+                            "{}: {:?}, actual image in {}",
+                            name,
+                            diff,
+                            output.display()
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Snapshots differ (set {}=1 to record them):\n{}",
+        UPDATE_ENV,
+        failures.join("\n")
+    );
+}